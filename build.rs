@@ -0,0 +1,73 @@
+use clap::{Command, CommandFactory};
+use clap_mangen::Man;
+use roff::{roman, Roff};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[allow(dead_code)]
+#[path = "src/cli/mod.rs"]
+mod cli;
+
+// A man page for `command` with its usage examples in an EXAMPLES section before VERSION.
+fn man_page(command: Command, examples: &[(&str, &str)]) -> io::Result<String> {
+    let source = format!("opencode {}", env!("CARGO_PKG_VERSION"));
+    let mut page = Vec::new();
+    Man::new(command).source(source).render(&mut page)?;
+    let page = String::from_utf8(page).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if examples.is_empty() {
+        return Ok(page);
+    }
+    let mut roff = Roff::new();
+    roff.control("SH", ["EXAMPLES"]);
+    for (description, line) in examples {
+        // The template system lives in the crate proper; `{{vars.bin}}` is the only variable the
+        // examples use, and installed man pages always document `opencode`.
+        roff.control("PP", []).text([roman(*description)]);
+        roff.control("RS", []).control("nf", []).text([roman(line.replace("{{vars.bin}}", "opencode"))]);
+        roff.control("fi", []).control("RE", []);
+    }
+    // `render` repeats the two-line apostrophe preamble the page already starts with.
+    let rendered = roff.render();
+    let section: String = rendered.split_inclusive('\n').skip(2).collect();
+    Ok(match page.find(".SH VERSION") {
+        Some(at) => format!("{}{}{}", &page[..at], section, &page[at..]),
+        None => page + &section,
+    })
+}
+
+// Writes `opencode.1` and one page per visible subcommand, e.g. `opencode-shell-explain.1`, and
+// returns their file names. Examples are listed per top-level subcommand (`depth` 1).
+fn write_pages(command: Command, depth: usize, dir: &Path) -> io::Result<Vec<String>> {
+    let mut names = Vec::new();
+    for sub in command.get_subcommands().filter(|c| !c.is_hide_set()) {
+        names.extend(write_pages(sub.clone(), depth + 1, dir)?);
+    }
+    let examples = match depth {
+        1 => cli::examples::EXAMPLES
+            .iter()
+            .find(|(name, _)| *name == command.get_name())
+            .map(|(_, examples)| examples.to_vec())
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    let name = format!("{}.1", command.get_display_name().unwrap_or(command.get_name()));
+    fs::write(dir.join(&name), man_page(command, &examples)?)?;
+    names.push(name);
+    Ok(names)
+}
+
+fn main() -> io::Result<()> {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/cli");
+    let dir = PathBuf::from(env::var_os("OUT_DIR").expect("cargo sets OUT_DIR")).join("man");
+    fs::create_dir_all(&dir)?;
+    let mut root = cli::commands::Cli::command().name("opencode").bin_name("opencode");
+    root.build();
+    let mut names = write_pages(root, 0, &dir)?;
+    names.sort();
+    let entries: Vec<String> =
+        names.iter().map(|name| format!("    ({:?}, include_str!(concat!(env!(\"OUT_DIR\"), \"/man/{}\"))),", name, name)).collect();
+    fs::write(dir.parent().unwrap_or(&dir).join("man_pages.rs"), format!("&[\n{}\n]\n", entries.join("\n")))
+}
@@ -0,0 +1,384 @@
+use anyhow::{anyhow, Context, Result};
+use futures_util::StreamExt;
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::api::chat_api::ChatApi;
+use crate::api::client::ApiClient;
+use crate::api::models::{ChatCompletionRequest, Message, Role, ToolCall, ToolChoice};
+use crate::config::{Config, PathRuleConfig};
+use crate::context::environment::EnvironmentProvider;
+use crate::context::ContextManager;
+use crate::hooks::HookRunner;
+use crate::loop_detection::{corrective_message, Repetition, RepetitionDetector};
+use crate::tools::execution::{SecurityPolicy, ToolExecutionEngine};
+use crate::tools::registry::ToolRegistry;
+use crate::tools::compact::compact_tool_definitions;
+use crate::tools::CliTool;
+
+const DEFAULT_MAX_ITERATIONS: usize = 20;
+
+// What an agent run reports while it works, in the order it happens.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AgentEvent {
+    MessageDelta(String),
+    ToolStarted { id: String, name: String, arguments: Value },
+    ToolFinished { id: String, name: String, result: Result<Value, String> },
+    Done { message: Option<String> },
+}
+
+type EventCallback = Box<dyn Fn(&AgentEvent) + Send + Sync>;
+
+pub type AgentTask = JoinHandle<(Agent, Result<Option<String>>)>;
+
+// Assembles an `Agent` for programs embedding this crate. Anything not set falls back to
+// the loaded `Config`: the default model, every registered tool, ConfirmWrites, and an
+// OpenRouter `ApiClient`.
+pub struct AgentBuilder {
+    config: Config,
+    model: Option<String>,
+    api: Option<Arc<dyn ChatApi>>,
+    extra_tools: Vec<Box<dyn CliTool>>,
+    allowed_tools: Option<Vec<String>>,
+    security_policy: SecurityPolicy,
+    system_prompt: Option<String>,
+    include_environment: bool,
+    max_iterations: usize,
+    callbacks: Vec<EventCallback>,
+}
+
+impl AgentBuilder {
+    pub fn new(config: Config) -> Self {
+        AgentBuilder {
+            config,
+            model: None,
+            api: None,
+            extra_tools: Vec::new(),
+            allowed_tools: None,
+            security_policy: SecurityPolicy::ConfirmWrites,
+            system_prompt: None,
+            include_environment: true,
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+            callbacks: Vec::new(),
+        }
+    }
+
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn api(mut self, api: Arc<dyn ChatApi>) -> Self {
+        self.api = Some(api);
+        self
+    }
+
+    pub fn tool(mut self, tool: Box<dyn CliTool>) -> Self {
+        self.extra_tools.push(tool);
+        self
+    }
+
+    // Restricts the agent to the named tools; tools added with `tool` are always kept.
+    pub fn allow_tools<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_tools = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn security_policy(mut self, policy: SecurityPolicy) -> Self {
+        self.security_policy = policy;
+        self
+    }
+
+    pub fn path_rule(mut self, rule: PathRuleConfig) -> Self {
+        self.config.path_rules.push(rule);
+        self
+    }
+
+    pub fn system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(prompt.into());
+        self
+    }
+
+    pub fn include_environment(mut self, include: bool) -> Self {
+        self.include_environment = include;
+        self
+    }
+
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations.max(1);
+        self
+    }
+
+    // Called synchronously for every event, before it is sent on the run's channel.
+    pub fn on_event(mut self, callback: impl Fn(&AgentEvent) + Send + Sync + 'static) -> Self {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+
+    pub async fn build(self) -> Result<Agent> {
+        let api = match self.api {
+            Some(api) => api,
+            None => Arc::new(
+                ApiClient::new(self.config.clone()).context("Failed to create API client (check API key configuration)")?,
+            ),
+        };
+
+        let mut registry = ToolRegistry::new(&self.config);
+        if let Some(allowed) = &self.allowed_tools {
+            registry.retain_tools(allowed);
+        }
+        for tool in self.extra_tools {
+            registry.register(tool);
+        }
+
+        let mut context = ContextManager::new(self.config.clone())?;
+        context.attach_notes(registry.notes().clone());
+        context.attach_memory(registry.memory().clone());
+        if let Some(prompt) = self.system_prompt {
+            context.pin_system_message(prompt)?;
+        }
+        if self.include_environment {
+            context.set_environment_context(EnvironmentProvider::from_current_dir()?.snapshot().await.summary())?;
+        }
+
+        Ok(Agent {
+            model: self.model.unwrap_or_else(|| self.config.api.default_model.clone()),
+            config: self.config,
+            api,
+            registry,
+            context,
+            security_policy: self.security_policy,
+            max_iterations: self.max_iterations,
+            callbacks: self.callbacks,
+        })
+    }
+}
+
+pub struct Agent {
+    config: Config,
+    model: String,
+    api: Arc<dyn ChatApi>,
+    registry: ToolRegistry,
+    context: ContextManager,
+    security_policy: SecurityPolicy,
+    max_iterations: usize,
+    callbacks: Vec<EventCallback>,
+}
+
+impl Agent {
+    // Runs one user turn to completion: streams the reply, executes requested tools and
+    // feeds their results back until the model answers without tool calls. The
+    // conversation is kept, so later calls continue it.
+    pub async fn run(&mut self, prompt: &str, events: &mpsc::UnboundedSender<AgentEvent>) -> Result<Option<String>> {
+        let engine = ToolExecutionEngine::new(&self.registry, self.security_policy)
+            .with_network_limiter(self.registry.network_limiter().clone())
+            .with_hooks(HookRunner::new(&self.config.hooks))
+            .with_untrusted_content(&self.config.untrusted_content)
+            .with_tool_policy(&self.config.tool_policy);
+        let tool_definitions = self.registry.get_tool_definitions()?;
+        let emit = |event: AgentEvent| {
+            for callback in &self.callbacks {
+                callback(&event);
+            }
+            // The receiver may have been dropped; the run still completes.
+            let _ = events.send(event);
+        };
+
+        self.context.add_message(Message {
+            role: Role::User,
+            content: Some(prompt.to_string()),
+            tool_calls: None,
+            tool_call_id: None,
+        })?;
+
+        let mut repetition = RepetitionDetector::new(self.config.run.max_repeated_tool_calls);
+        let compact_definitions = compact_tool_definitions(&tool_definitions);
+        for iteration in 0..self.max_iterations {
+            let definitions = match iteration {
+                0 => &tool_definitions,
+                _ if self.config.context.compact_tool_definitions => &compact_definitions,
+                _ => &tool_definitions,
+            };
+            let request = ChatCompletionRequest {
+                model: self.model.clone(),
+                messages: self.context.construct_api_messages()?,
+                stream: Some(true),
+                temperature: None,
+                max_tokens: None,
+                tools: Some(definitions.clone()).filter(|t| !t.is_empty()),
+                tool_choice: (!tool_definitions.is_empty()).then_some(ToolChoice::Auto),
+                source_map: None,
+            };
+            let mut stream = self.api.chat_completion_stream(request).await?;
+            let mut content = String::new();
+            let mut tool_calls: Vec<ToolCall> = Vec::new();
+            while let Some(chunk) = stream.next().await {
+                let chunk = chunk?;
+                let Some(choice) = chunk.choices.first() else { continue };
+                if let Some(text) = choice.delta.content.as_deref().filter(|t| !t.is_empty()) {
+                    content.push_str(text);
+                    emit(AgentEvent::MessageDelta(text.to_string()));
+                }
+                if let Some(calls) = &choice.delta.tool_calls {
+                    tool_calls.extend(calls.iter().cloned());
+                }
+            }
+
+            let message = (!content.is_empty()).then_some(content);
+            self.context.add_message(Message {
+                role: Role::Assistant,
+                content: message.clone(),
+                tool_calls: (!tool_calls.is_empty()).then(|| tool_calls.clone()),
+                tool_call_id: None,
+            })?;
+            if tool_calls.is_empty() {
+                emit(AgentEvent::Done { message: message.clone() });
+                return Ok(message);
+            }
+
+            let repeated = repetition.observe(&tool_calls);
+            if let Repetition::Stuck { tool, count } = &repeated {
+                return Err(anyhow!("Agent stopped after calling {} with the same arguments {} times in a row", tool, count));
+            }
+
+            for call in tool_calls {
+                let arguments: Value = serde_json::from_str(&call.function.arguments).unwrap_or(Value::Null);
+                emit(AgentEvent::ToolStarted {
+                    id: call.id.clone(),
+                    name: call.function.name.clone(),
+                    arguments: arguments.clone(),
+                });
+                let result = engine
+                    .execute_tool_call(&call.function.name, arguments.clone())
+                    .await
+                    .map(|value| self.context.dedupe_file_read(&call.function.name, &arguments, &call.id, value))
+                    .map_err(|e| e.to_string());
+                let content = match &result {
+                    Ok(value) => serde_json::to_string(value)?,
+                    Err(e) => serde_json::json!({ "error": e }).to_string(),
+                };
+                emit(AgentEvent::ToolFinished { id: call.id.clone(), name: call.function.name.clone(), result });
+                self.context.add_message(Message {
+                    role: Role::Tool,
+                    content: Some(content),
+                    tool_calls: None,
+                    tool_call_id: Some(call.id),
+                })?;
+            }
+            if let Repetition::Repeated { tool, count } = repeated {
+                self.context.add_message(Message {
+                    role: Role::System,
+                    content: Some(corrective_message(&tool, count)),
+                    tool_calls: None,
+                    tool_call_id: None,
+                })?;
+            }
+        }
+        Err(anyhow!("Agent stopped after {} iterations without a final answer", self.max_iterations))
+    }
+
+    // Runs `prompt` on a background task and returns the event stream alongside it. The
+    // agent is handed back when the task finishes so the conversation can continue.
+    pub fn spawn(mut self, prompt: impl Into<String>) -> (AgentTask, mpsc::UnboundedReceiver<AgentEvent>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let prompt = prompt.into();
+        let handle = tokio::spawn(async move {
+            let result = self.run(&prompt, &sender).await;
+            (self, result)
+        });
+        (handle, receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::chat_api::MockChatApi;
+    use crate::api::models::{ChatCompletionChunk, ToolCallFunction};
+    use crate::tools::ToolError;
+    use async_trait::async_trait;
+
+    #[derive(Debug)]
+    struct EchoTool;
+
+    #[async_trait]
+    impl CliTool for EchoTool {
+        fn name(&self) -> String {
+            "echo".to_string()
+        }
+        fn description(&self) -> String {
+            "Echoes its arguments".to_string()
+        }
+        fn parameters_schema(&self) -> Result<Value> {
+            Ok(serde_json::json!({ "type": "object" }))
+        }
+        async fn execute(&self, args: Value) -> Result<Value, ToolError> {
+            Ok(args)
+        }
+    }
+
+    fn chunk(content: Option<&str>, tool_calls: Option<Vec<ToolCall>>) -> ChatCompletionChunk {
+        let mut chunk: ChatCompletionChunk = serde_json::from_str(r#"{"choices":[{"delta":{}}]}"#).unwrap();
+        chunk.choices[0].delta.content = content.map(String::from);
+        chunk.choices[0].delta.tool_calls = tool_calls;
+        chunk
+    }
+
+    #[tokio::test]
+    async fn test_agent_emits_typed_events_through_tool_round_trip() {
+        let call = ToolCall {
+            id: "call_1".to_string(),
+            tool_type: "function".to_string(),
+            function: ToolCallFunction { name: "echo".to_string(), arguments: "{\"text\":\"hi\"}".to_string() },
+        };
+        let api = Arc::new(
+            MockChatApi::new()
+                .with_stream(vec![chunk(None, Some(vec![call]))])
+                .with_stream(vec![chunk(Some("Echoed "), None), chunk(Some("hi."), None)]),
+        );
+        let seen = Arc::new(std::sync::Mutex::new(0));
+        let counter = seen.clone();
+        let agent = AgentBuilder::new(Config::default())
+            .api(api.clone())
+            .allow_tools(Vec::<String>::new())
+            .tool(Box::new(EchoTool))
+            .include_environment(false)
+            .on_event(move |_| *counter.lock().unwrap() += 1)
+            .build()
+            .await
+            .unwrap();
+
+        let (handle, mut events) = agent.spawn("Echo hi");
+        let (_agent, result) = handle.await.unwrap();
+        assert_eq!(result.unwrap().as_deref(), Some("Echoed hi."));
+
+        let mut received = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+        assert_eq!(
+            received,
+            vec![
+                AgentEvent::ToolStarted { id: "call_1".to_string(), name: "echo".to_string(), arguments: serde_json::json!({ "text": "hi" }) },
+                AgentEvent::ToolFinished { id: "call_1".to_string(), name: "echo".to_string(), result: Ok(serde_json::json!({ "text": "hi" })) },
+                AgentEvent::MessageDelta("Echoed ".to_string()),
+                AgentEvent::MessageDelta("hi.".to_string()),
+                AgentEvent::Done { message: Some("Echoed hi.".to_string()) },
+            ]
+        );
+        assert_eq!(*seen.lock().unwrap(), 5);
+
+        let requests = api.requests();
+        assert_eq!(requests.len(), 2);
+        let tools = requests[0].tools.as_ref().unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].function.name, "echo");
+        assert_eq!(requests[1].messages.last().unwrap().tool_call_id.as_deref(), Some("call_1"));
+    }
+}
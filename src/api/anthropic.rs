@@ -0,0 +1,307 @@
+// Anthropic's Messages API, for `api.provider = "anthropic"`. The rest of the program speaks
+// the OpenAI-style chat schema OpenRouter uses, so requests are translated on the way out and
+// responses and stream events on the way back: system messages move to `system`, tool calls
+// become `tool_use` blocks and tool results `tool_result` blocks in a user turn.
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+use crate::api::models::{
+    ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse, Choice, ChunkChoice, Delta, Message, Role, ToolCall,
+    ToolCallFunction, ToolChoice, UsageStats,
+};
+
+pub const ANTHROPIC_API_BASE_URL: &str = "https://api.anthropic.com/v1";
+pub const ANTHROPIC_VERSION: &str = "2023-06-01";
+// The Messages API requires `max_tokens`; used when the request leaves it unset.
+const DEFAULT_MAX_TOKENS: u32 = 8192;
+
+fn content_blocks(message: &Message) -> Vec<Value> {
+    let mut blocks = Vec::new();
+    let text = message.content.as_deref().unwrap_or_default();
+    if message.role == Role::Tool {
+        let id = message.tool_call_id.clone().unwrap_or_default();
+        blocks.push(json!({ "type": "tool_result", "tool_use_id": id, "content": text }));
+        return blocks;
+    }
+    // Empty text blocks are rejected, and assistant turns that only call tools have none.
+    if !text.is_empty() {
+        blocks.push(json!({ "type": "text", "text": text }));
+    }
+    for call in message.tool_calls.iter().flatten() {
+        let input: Value = serde_json::from_str(&call.function.arguments).unwrap_or_else(|_| json!({}));
+        blocks.push(json!({ "type": "tool_use", "id": call.id, "name": call.function.name, "input": input }));
+    }
+    blocks
+}
+
+// The Messages API body for `request`. Turns must alternate between user and assistant, so
+// consecutive messages of one role (several tool results, say) share a turn.
+pub fn to_messages_request(request: &ChatCompletionRequest) -> Result<Value> {
+    // OpenRouter slugs (`anthropic/claude-3.5-sonnet`) don't map reliably onto Anthropic's
+    // model IDs, so they are refused rather than guessed at.
+    if request.model.contains('/') {
+        bail!(
+            "'{}' is an OpenRouter model slug; with api.provider = \"anthropic\" set the models in [api] to \
+             Anthropic model IDs, e.g. claude-sonnet-4-5",
+            request.model
+        );
+    }
+    let system: Vec<&str> = request
+        .messages
+        .iter()
+        .filter(|m| m.role == Role::System)
+        .filter_map(|m| m.content.as_deref())
+        .collect();
+    let mut turns: Vec<(&str, Vec<Value>)> = Vec::new();
+    for message in request.messages.iter().filter(|m| m.role != Role::System) {
+        let role = if message.role == Role::Assistant { "assistant" } else { "user" };
+        let blocks = content_blocks(message);
+        match turns.last_mut() {
+            Some((last, content)) if *last == role => content.extend(blocks),
+            _ => turns.push((role, blocks)),
+        }
+    }
+
+    let mut body = Map::new();
+    body.insert("model".to_string(), json!(request.model));
+    body.insert("max_tokens".to_string(), json!(request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS)));
+    body.insert(
+        "messages".to_string(),
+        turns.into_iter().map(|(role, content)| json!({ "role": role, "content": content })).collect(),
+    );
+    if !system.is_empty() {
+        body.insert("system".to_string(), json!(system.join("\n\n")));
+    }
+    if let Some(temperature) = request.temperature {
+        body.insert("temperature".to_string(), json!(temperature));
+    }
+    if request.stream == Some(true) {
+        body.insert("stream".to_string(), json!(true));
+    }
+    if let Some(tools) = &request.tools {
+        let tools: Vec<Value> = tools
+            .iter()
+            .map(|t| json!({ "name": t.function.name, "description": t.function.description, "input_schema": t.function.parameters }))
+            .collect();
+        body.insert("tools".to_string(), json!(tools));
+    }
+    if let Some(choice) = &request.tool_choice {
+        let choice = match choice {
+            ToolChoice::None => json!({ "type": "none" }),
+            ToolChoice::Auto => json!({ "type": "auto" }),
+            ToolChoice::Tool { function, .. } => json!({ "type": "tool", "name": function.name }),
+        };
+        body.insert("tool_choice".to_string(), choice);
+    }
+    Ok(Value::Object(body))
+}
+
+fn finish_reason(stop_reason: &str) -> String {
+    match stop_reason {
+        "end_turn" | "stop_sequence" => "stop",
+        "max_tokens" => "length",
+        "tool_use" => "tool_calls",
+        other => other,
+    }
+    .to_string()
+}
+
+fn usage(input_tokens: u64, output_tokens: u64) -> UsageStats {
+    UsageStats {
+        prompt_tokens: input_tokens as u32,
+        completion_tokens: output_tokens as u32,
+        total_tokens: (input_tokens + output_tokens) as u32,
+        cost: None,
+    }
+}
+
+fn tool_call(id: &str, name: &str, arguments: String) -> ToolCall {
+    ToolCall {
+        id: id.to_string(),
+        tool_type: "function".to_string(),
+        function: ToolCallFunction { name: name.to_string(), arguments },
+    }
+}
+
+pub fn from_messages_response(response: Value) -> Result<ChatCompletionResponse> {
+    let blocks = response["content"].as_array().ok_or_else(|| anyhow!("Messages API response has no content: {}", response))?;
+    let mut text = String::new();
+    let mut tool_calls = Vec::new();
+    for block in blocks {
+        match block["type"].as_str() {
+            Some("text") => text.push_str(block["text"].as_str().unwrap_or_default()),
+            Some("tool_use") => tool_calls.push(tool_call(
+                block["id"].as_str().unwrap_or_default(),
+                block["name"].as_str().unwrap_or_default(),
+                block["input"].to_string(),
+            )),
+            _ => {}
+        }
+    }
+    let message = Message {
+        role: Role::Assistant,
+        content: (!text.is_empty()).then_some(text),
+        tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+        tool_call_id: None,
+    };
+    let usage = response.get("usage").map(|u| usage(u["input_tokens"].as_u64().unwrap_or(0), u["output_tokens"].as_u64().unwrap_or(0)));
+    Ok(ChatCompletionResponse {
+        choices: vec![Choice { message, finish_reason: response["stop_reason"].as_str().map(finish_reason) }],
+        usage,
+    })
+}
+
+fn chunk(delta: Delta, finish_reason: Option<String>, usage: Option<UsageStats>) -> ChatCompletionChunk {
+    ChatCompletionChunk {
+        id: String::new(),
+        object: "chat.completion.chunk".to_string(),
+        created: 0,
+        model: String::new(),
+        choices: vec![ChunkChoice { index: 0, delta, finish_reason }],
+        usage,
+    }
+}
+
+// Turns Messages API stream events into chat chunks. Text arrives as it streams; a tool call
+// is sent whole once its block ends, since its input JSON comes in pieces and the agent loops
+// expect complete calls in a delta.
+#[derive(Debug, Default)]
+pub struct StreamTranslator {
+    input_tokens: u64,
+    // Tool use blocks still receiving input, by block index: id, name and input so far.
+    tool_uses: BTreeMap<u64, (String, String, String)>,
+}
+
+impl StreamTranslator {
+    pub fn event(&mut self, data: &str) -> Result<Option<ChatCompletionChunk>> {
+        let event: Value = serde_json::from_str(data).map_err(|e| anyhow!("Failed to parse Messages API event: {}. Data: '{}'", e, data))?;
+        let index = event["index"].as_u64().unwrap_or(0);
+        let text_delta = |text: &Value| Delta { content: text.as_str().map(String::from), ..Delta::default() };
+        Ok(match event["type"].as_str().unwrap_or_default() {
+            "message_start" => {
+                self.input_tokens = event["message"]["usage"]["input_tokens"].as_u64().unwrap_or(0);
+                Some(chunk(Delta { role: Some(Role::Assistant), ..Delta::default() }, None, None))
+            }
+            "content_block_start" => {
+                let block = &event["content_block"];
+                match block["type"].as_str() {
+                    Some("tool_use") => {
+                        let (id, name) = (block["id"].as_str().unwrap_or_default(), block["name"].as_str().unwrap_or_default());
+                        self.tool_uses.insert(index, (id.to_string(), name.to_string(), String::new()));
+                        None
+                    }
+                    Some("text") if block["text"].as_str().is_some_and(|t| !t.is_empty()) => Some(chunk(text_delta(&block["text"]), None, None)),
+                    _ => None,
+                }
+            }
+            "content_block_delta" => {
+                let delta = &event["delta"];
+                match delta["type"].as_str() {
+                    Some("text_delta") => Some(chunk(text_delta(&delta["text"]), None, None)),
+                    Some("thinking_delta") => {
+                        Some(chunk(Delta { reasoning: delta["thinking"].as_str().map(String::from), ..Delta::default() }, None, None))
+                    }
+                    Some("input_json_delta") => {
+                        if let Some((_, _, input)) = self.tool_uses.get_mut(&index) {
+                            input.push_str(delta["partial_json"].as_str().unwrap_or_default());
+                        }
+                        None
+                    }
+                    _ => None,
+                }
+            }
+            "content_block_stop" => self.tool_uses.remove(&index).map(|(id, name, input)| {
+                // A tool without parameters streams no input at all.
+                let arguments = if input.trim().is_empty() { "{}".to_string() } else { input };
+                chunk(Delta { tool_calls: Some(vec![tool_call(&id, &name, arguments)]), ..Delta::default() }, None, None)
+            }),
+            "message_delta" => {
+                let output_tokens = event["usage"]["output_tokens"].as_u64().unwrap_or(0);
+                let reason = event["delta"]["stop_reason"].as_str().map(finish_reason);
+                Some(chunk(Delta::default(), reason, Some(usage(self.input_tokens, output_tokens))))
+            }
+            "error" => return Err(anyhow!("Messages API stream error: {}", event["error"])),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::models::{FunctionDefinition, ToolChoiceFunction, ToolDefinition};
+
+    #[test]
+    fn test_translates_tool_round_trip() {
+        let call = tool_call("toolu_1", "FileReadTool", r#"{"path":"src/main.rs"}"#.to_string());
+        let message = |role, content: Option<&str>| Message { role, content: content.map(String::from), tool_calls: None, tool_call_id: None };
+        let request = ChatCompletionRequest {
+            model: "claude-sonnet-4-5".to_string(),
+            messages: vec![
+                message(Role::System, Some("Be brief.")),
+                message(Role::User, Some("What does main do?")),
+                Message { tool_calls: Some(vec![call]), ..message(Role::Assistant, Some("")) },
+                Message { tool_call_id: Some("toolu_1".to_string()), ..message(Role::Tool, Some("fn main() {}")) },
+                message(Role::User, Some("Answer now.")),
+            ],
+            temperature: None,
+            max_tokens: None,
+            stream: None,
+            tools: Some(vec![ToolDefinition {
+                tool_type: "function".to_string(),
+                function: FunctionDefinition { name: "FileReadTool".to_string(), description: "Read a file".to_string(), parameters: json!({ "type": "object" }) },
+            }]),
+            tool_choice: Some(ToolChoice::Tool { tool_type: "function".to_string(), function: ToolChoiceFunction { name: "FileReadTool".to_string() } }),
+            source_map: None,
+        };
+        let body = to_messages_request(&request).unwrap();
+        assert_eq!(body["model"], "claude-sonnet-4-5");
+        assert_eq!(body["system"], "Be brief.");
+        assert_eq!(body["max_tokens"], DEFAULT_MAX_TOKENS);
+        assert_eq!(body["tools"][0], json!({ "name": "FileReadTool", "description": "Read a file", "input_schema": { "type": "object" } }));
+        assert_eq!(body["tool_choice"], json!({ "type": "tool", "name": "FileReadTool" }));
+        let slug = ChatCompletionRequest { model: "anthropic/claude-3.5-sonnet".to_string(), ..request.clone() };
+        assert!(to_messages_request(&slug).is_err());
+        assert_eq!(
+            body["messages"],
+            json!([
+                { "role": "user", "content": [{ "type": "text", "text": "What does main do?" }] },
+                { "role": "assistant", "content": [{ "type": "tool_use", "id": "toolu_1", "name": "FileReadTool", "input": { "path": "src/main.rs" } }] },
+                { "role": "user", "content": [
+                    { "type": "tool_result", "tool_use_id": "toolu_1", "content": "fn main() {}" },
+                    { "type": "text", "text": "Answer now." }
+                ] }
+            ])
+        );
+
+        let response = from_messages_response(json!({
+            "content": [{ "type": "text", "text": "Reading." }, { "type": "tool_use", "id": "toolu_2", "name": "FileReadTool", "input": { "path": "a.rs" } }],
+            "stop_reason": "tool_use",
+            "usage": { "input_tokens": 10, "output_tokens": 5 }
+        }))
+        .unwrap();
+        let choice = &response.choices[0];
+        assert_eq!(choice.message.content.as_deref(), Some("Reading."));
+        assert_eq!(choice.message.tool_calls.as_ref().unwrap()[0].function.arguments, r#"{"path":"a.rs"}"#);
+        assert_eq!(choice.finish_reason.as_deref(), Some("tool_calls"));
+        assert_eq!(response.usage.unwrap().total_tokens, 15);
+
+        let mut translator = StreamTranslator::default();
+        let events = [
+            r#"{"type":"message_start","message":{"usage":{"input_tokens":7}}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hi"}}"#,
+            r#"{"type":"content_block_start","index":1,"content_block":{"type":"tool_use","id":"toolu_3","name":"FileReadTool","input":{}}}"#,
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"{\"path\":"}}"#,
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"\"b.rs\"}"}}"#,
+            r#"{"type":"content_block_stop","index":1}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"tool_use"},"usage":{"output_tokens":4}}"#,
+        ];
+        let chunks: Vec<ChatCompletionChunk> = events.iter().filter_map(|e| translator.event(e).unwrap()).collect();
+        assert_eq!(chunks[1].choices[0].delta.content.as_deref(), Some("Hi"));
+        assert_eq!(chunks[2].choices[0].delta.tool_calls.as_ref().unwrap()[0].function.arguments, r#"{"path":"b.rs"}"#);
+        assert_eq!(chunks[3].choices[0].finish_reason.as_deref(), Some("tool_calls"));
+        assert_eq!(chunks[3].usage.as_ref().unwrap().total_tokens, 11);
+        assert!(translator.event(r#"{"type":"error","error":{"type":"overloaded_error"}}"#).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::api::models::{ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse};
+use crate::config::CassetteMode;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum RecordedResponse {
+    Complete { response: Value },
+    // `error` is set when the stream failed part way; replays end with it.
+    Stream {
+        chunks: Vec<Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Interaction {
+    pub request: Value,
+    #[serde(flatten)]
+    pub response: RecordedResponse,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Debug)]
+struct CassetteState {
+    interactions: Vec<Interaction>,
+    used: Vec<bool>,
+}
+
+// Recorded API traffic. While recording, every exchange is appended and the file is
+// rewritten; while replaying, each request is answered by the first unused recording of
+// an equivalent request (see `match_key`), so nothing goes over the network.
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    replay: bool,
+    state: Mutex<CassetteState>,
+}
+
+impl Cassette {
+    // Every ApiClient in the process shares one cassette per path and mode, so commands that
+    // create several clients record into (and replay from) the same sequence.
+    pub fn open(mode: &CassetteMode) -> Result<Arc<Cassette>> {
+        static OPEN: OnceLock<Mutex<HashMap<CassetteMode, Arc<Cassette>>>> = OnceLock::new();
+        let (path, replay) = match mode {
+            CassetteMode::Record(path) => (path, false),
+            CassetteMode::Replay(path) => (path, true),
+        };
+        let mut open = OPEN.get_or_init(Default::default).lock().map_err(|_| anyhow!("Cassette registry poisoned"))?;
+        if let Some(cassette) = open.get(mode) {
+            return Ok(cassette.clone());
+        }
+        let cassette = Arc::new(if replay { Self::load(path)? } else { Self::empty(path) });
+        open.insert(mode.clone(), cassette.clone());
+        Ok(cassette)
+    }
+
+    fn empty(path: &Path) -> Self {
+        Cassette {
+            path: path.to_path_buf(),
+            replay: false,
+            state: Mutex::new(CassetteState { interactions: Vec::new(), used: Vec::new() }),
+        }
+    }
+
+    fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read cassette {:?}", path))?;
+        let file: CassetteFile =
+            serde_json::from_str(&content).with_context(|| format!("Failed to parse cassette {:?}", path))?;
+        let used = vec![false; file.interactions.len()];
+        Ok(Cassette {
+            path: path.to_path_buf(),
+            replay: true,
+            state: Mutex::new(CassetteState { interactions: file.interactions, used }),
+        })
+    }
+
+    pub fn is_replay(&self) -> bool {
+        self.replay
+    }
+
+    pub fn replay_response(&self, request: &ChatCompletionRequest) -> Result<ChatCompletionResponse> {
+        match self.take(request)? {
+            RecordedResponse::Complete { response } => {
+                serde_json::from_value(response).context("Recorded response is not a chat completion")
+            }
+            RecordedResponse::Stream { .. } => Err(anyhow!("Cassette recorded a streaming response for this request")),
+        }
+    }
+
+    pub fn replay_stream(&self, request: &ChatCompletionRequest) -> Result<Vec<Result<ChatCompletionChunk>>> {
+        match self.take(request)? {
+            RecordedResponse::Stream { chunks, error } => {
+                let mut replayed = chunks
+                    .into_iter()
+                    .map(|chunk| serde_json::from_value(chunk).context("Recorded chunk is not a chat completion chunk").map(Ok))
+                    .collect::<Result<Vec<_>>>()?;
+                replayed.extend(error.map(|e| Err(anyhow!(e))));
+                Ok(replayed)
+            }
+            RecordedResponse::Complete { .. } => Err(anyhow!("Cassette recorded a non-streaming response for this request")),
+        }
+    }
+
+    pub fn record_response(&self, request: &ChatCompletionRequest, response: &ChatCompletionResponse) -> Result<()> {
+        self.record(request, RecordedResponse::Complete { response: serde_json::to_value(response)? })
+    }
+
+    pub fn record_stream(&self, request: &ChatCompletionRequest, chunks: &[ChatCompletionChunk], error: Option<String>) -> Result<()> {
+        let chunks = chunks.iter().map(serde_json::to_value).collect::<Result<Vec<_>, _>>()?;
+        self.record(request, RecordedResponse::Stream { chunks, error })
+    }
+
+    fn take(&self, request: &ChatCompletionRequest) -> Result<RecordedResponse> {
+        let request = match_key(serde_json::to_value(request)?);
+        let mut state = self.state.lock().map_err(|_| anyhow!("Cassette state poisoned"))?;
+        let CassetteState { interactions, used } = &mut *state;
+        let index = interactions
+            .iter()
+            .zip(used.iter())
+            .position(|(interaction, used)| !used && match_key(interaction.request.clone()) == request)
+            .ok_or_else(|| anyhow!("No unused recording in {:?} matches the request for model {}", self.path, request["model"]))?;
+        used[index] = true;
+        Ok(interactions[index].response.clone())
+    }
+
+    fn record(&self, request: &ChatCompletionRequest, response: RecordedResponse) -> Result<()> {
+        let mut state = self.state.lock().map_err(|_| anyhow!("Cassette state poisoned"))?;
+        state.interactions.push(Interaction { request: serde_json::to_value(request)?, response });
+        let file = CassetteFile { interactions: state.interactions.clone() };
+        fs::write(&self.path, serde_json::to_string_pretty(&file)?)
+            .with_context(|| format!("Failed to write cassette {:?}", self.path))
+    }
+}
+
+// What a replayed request has to match. System messages carry the date, git status, memory and
+// other context that changes between runs, so only their position is compared.
+fn match_key(mut request: Value) -> Value {
+    if let Some(messages) = request.get_mut("messages").and_then(Value::as_array_mut) {
+        for message in messages.iter_mut().filter(|m| m["role"] == "system") {
+            if let Some(message) = message.as_object_mut() {
+                message.remove("content");
+            }
+        }
+    }
+    request
+}
+
+// Collects a recorded stream's chunks and saves them when the stream is dropped, so a stream
+// that fails or is abandoned part way is still on the cassette.
+#[derive(Debug)]
+pub struct StreamRecording {
+    cassette: Arc<Cassette>,
+    request: ChatCompletionRequest,
+    chunks: Vec<ChatCompletionChunk>,
+    error: Option<String>,
+}
+
+impl StreamRecording {
+    pub fn new(cassette: Arc<Cassette>, request: ChatCompletionRequest) -> Self {
+        StreamRecording { cassette, request, chunks: Vec::new(), error: None }
+    }
+
+    pub fn observe(&mut self, item: &Result<ChatCompletionChunk>) {
+        match item {
+            Ok(chunk) => self.chunks.push(chunk.clone()),
+            Err(e) => self.error = Some(format!("{:#}", e)),
+        }
+    }
+}
+
+impl Drop for StreamRecording {
+    fn drop(&mut self) {
+        if let Err(e) = self.cassette.record_stream(&self.request, &self.chunks, self.error.take()) {
+            tracing::error!("Failed to record streamed response: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::models::{Choice, Message, Role};
+
+    fn request(prompt: &str) -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "test-model".to_string(),
+            messages: vec![Message { role: Role::User, content: Some(prompt.to_string()), tool_calls: None, tool_call_id: None }],
+            stream: None,
+            temperature: None,
+            max_tokens: None,
+            tools: None,
+            tool_choice: None,
+            source_map: None,
+        }
+    }
+
+    fn response(answer: &str) -> ChatCompletionResponse {
+        ChatCompletionResponse {
+            choices: vec![Choice {
+                message: Message { role: Role::Assistant, content: Some(answer.to_string()), tool_calls: None, tool_call_id: None },
+                finish_reason: None,
+            }],
+            usage: None,
+        }
+    }
+
+    #[test]
+    fn test_recorded_interactions_replay_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cassette.json");
+        let recorder = Cassette::open(&CassetteMode::Record(path.clone())).unwrap();
+        recorder.record_response(&request("hi"), &response("first")).unwrap();
+        recorder.record_response(&request("hi"), &response("second")).unwrap();
+        recorder.record_stream(&request("stream"), &[], None).unwrap();
+        drop(StreamRecording::new(recorder.clone(), request("dropped")));
+
+        let player = Cassette::load(&path).unwrap();
+        assert!(player.is_replay());
+        let answer = |r: ChatCompletionResponse| r.choices[0].message.content.clone().unwrap();
+        assert_eq!(answer(player.replay_response(&request("hi")).unwrap()), "first");
+        assert_eq!(answer(player.replay_response(&request("hi")).unwrap()), "second");
+        assert!(player.replay_response(&request("hi")).is_err());
+        assert!(player.replay_stream(&request("stream")).unwrap().is_empty());
+        assert!(player.replay_stream(&request("dropped")).unwrap().is_empty());
+        assert!(player.replay_response(&request("unknown")).is_err());
+    }
+
+    #[test]
+    fn test_system_content_is_ignored_when_matching() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cassette.json");
+        let with_system = |date: &str| {
+            let mut request = request("hi");
+            request.messages.insert(0, Message { role: Role::System, content: Some(date.to_string()), tool_calls: None, tool_call_id: None });
+            request
+        };
+        let recorder = Cassette::open(&CassetteMode::Record(path.clone())).unwrap();
+        recorder.record_response(&with_system("Today is 2026-01-01"), &response("ok")).unwrap();
+
+        let player = Cassette::load(&path).unwrap();
+        assert!(player.replay_response(&request("hi")).is_err());
+        assert!(player.replay_response(&with_system("Today is 2026-10-16")).is_ok());
+    }
+}
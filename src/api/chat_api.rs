@@ -0,0 +1,152 @@
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use futures_util::stream::Stream;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::Mutex;
+use tokio::sync::OnceCell;
+
+use crate::api::client::ApiClient;
+use crate::api::models::{ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse};
+use crate::config::Config;
+
+pub type ChatStream = Pin<Box<dyn Stream<Item = Result<ChatCompletionChunk>> + Send>>;
+
+// The chat endpoints command handlers depend on. `ApiClient` talks to OpenRouter;
+// `MockChatApi` serves canned answers so flows can be unit tested without a network.
+#[async_trait]
+pub trait ChatApi: Send + Sync {
+    async fn chat_completion(&self, request: ChatCompletionRequest) -> Result<ChatCompletionResponse>;
+
+    async fn chat_completion_stream(&self, request: ChatCompletionRequest) -> Result<ChatStream>;
+
+    // Whether requests can be sent at all, for callers that would otherwise fail once per
+    // request for the same reason, such as a missing API key.
+    async fn ready(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChatApi for ApiClient {
+    async fn chat_completion(&self, request: ChatCompletionRequest) -> Result<ChatCompletionResponse> {
+        ApiClient::chat_completion(self, request).await
+    }
+
+    async fn chat_completion_stream(&self, request: ChatCompletionRequest) -> Result<ChatStream> {
+        ApiClient::chat_completion_stream(self, request).await
+    }
+}
+
+// Builds the `ApiClient` on the first request, so a command that never reaches the model
+// (`todos --no-triage`, `/memory` in interactive mode) works without an API key.
+pub struct LazyApiClient {
+    config: Config,
+    client: OnceCell<ApiClient>,
+}
+
+impl LazyApiClient {
+    pub fn new(config: Config) -> Self {
+        LazyApiClient { config, client: OnceCell::new() }
+    }
+
+    async fn client(&self) -> Result<&ApiClient> {
+        self.client
+            .get_or_try_init(|| async {
+                ApiClient::new(self.config.clone()).context("Failed to create API client (check API key configuration)")
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl ChatApi for LazyApiClient {
+    async fn chat_completion(&self, request: ChatCompletionRequest) -> Result<ChatCompletionResponse> {
+        self.client().await?.chat_completion(request).await
+    }
+
+    async fn chat_completion_stream(&self, request: ChatCompletionRequest) -> Result<ChatStream> {
+        self.client().await?.chat_completion_stream(request).await
+    }
+
+    async fn ready(&self) -> Result<()> {
+        self.client().await.map(|_| ())
+    }
+}
+
+// Test double that answers from queues filled up front and keeps every request it saw.
+#[derive(Debug, Default)]
+pub struct MockChatApi {
+    responses: Mutex<VecDeque<ChatCompletionResponse>>,
+    streams: Mutex<VecDeque<Vec<ChatCompletionChunk>>>,
+    requests: Mutex<Vec<ChatCompletionRequest>>,
+}
+
+impl MockChatApi {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_response(self, response: ChatCompletionResponse) -> Self {
+        self.responses.lock().expect("mock poisoned").push_back(response);
+        self
+    }
+
+    pub fn with_stream(self, chunks: Vec<ChatCompletionChunk>) -> Self {
+        self.streams.lock().expect("mock poisoned").push_back(chunks);
+        self
+    }
+
+    pub fn requests(&self) -> Vec<ChatCompletionRequest> {
+        self.requests.lock().expect("mock poisoned").clone()
+    }
+}
+
+#[async_trait]
+impl ChatApi for MockChatApi {
+    async fn chat_completion(&self, request: ChatCompletionRequest) -> Result<ChatCompletionResponse> {
+        self.requests.lock().expect("mock poisoned").push(request);
+        self.responses
+            .lock()
+            .expect("mock poisoned")
+            .pop_front()
+            .ok_or_else(|| anyhow!("MockChatApi has no queued response"))
+    }
+
+    async fn chat_completion_stream(&self, request: ChatCompletionRequest) -> Result<ChatStream> {
+        self.requests.lock().expect("mock poisoned").push(request);
+        let chunks = self
+            .streams
+            .lock()
+            .expect("mock poisoned")
+            .pop_front()
+            .ok_or_else(|| anyhow!("MockChatApi has no queued stream"))?;
+        Ok(Box::pin(futures_util::stream::iter(chunks.into_iter().map(Ok))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CassetteMode;
+
+    #[tokio::test]
+    async fn test_lazy_client_fails_only_when_used() {
+        let mut config = Config::default();
+        config.api.cassette = Some(CassetteMode::Replay("/nonexistent/cassette.jsonl".into()));
+        let client = LazyApiClient::new(config);
+        assert!(client.ready().await.is_err());
+        let request = ChatCompletionRequest {
+            model: "test".to_string(),
+            messages: Vec::new(),
+            stream: None,
+            temperature: None,
+            max_tokens: None,
+            tools: None,
+            tool_choice: None,
+            source_map: None,
+        };
+        let error = client.chat_completion(request).await.unwrap_err();
+        assert!(format!("{:#}", error).starts_with("Failed to create API client"), "{:#}", error);
+    }
+}
@@ -1,2 +1,7 @@
+pub mod anthropic;
+pub mod cassette;
+pub mod chat_api;
 pub mod client;
-pub mod models;
\ No newline at end of file
+pub mod models;
+pub mod scheduler;
+pub mod tool_emulation;
\ No newline at end of file
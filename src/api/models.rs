@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ChatCompletionRequest {
     pub model: String,
     pub messages: Vec<Message>,
@@ -71,15 +71,19 @@ pub struct ToolChoiceFunction {
 
 
 
-#[derive(Deserialize, Debug, Clone)] 
+#[derive(Serialize, Deserialize, Debug, Clone)] 
 pub struct ChatCompletionResponse {
     pub choices: Vec<Choice>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<UsageStats>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Choice {
     pub message: Message, 
-    
+    // "length" when the reply stopped at the output token limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)] 
@@ -96,29 +100,55 @@ pub struct ToolCallFunction {
     pub arguments: String, 
 }
 
-#[derive(Deserialize, Debug, Clone, Default)] 
+#[derive(Serialize, Deserialize, Debug, Clone, Default)] 
 pub struct UsageStats {
+    #[serde(default)]
+    pub prompt_tokens: u32,
+    #[serde(default)]
+    pub completion_tokens: u32,
+    #[serde(default)]
+    pub total_tokens: u32,
+    // Reported by OpenRouter in credits (USD).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
 }
 
 
 
 
-#[derive(Deserialize, Debug, Clone)] 
+#[derive(Serialize, Deserialize, Debug, Clone)] 
 pub struct ChatCompletionChunk {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub object: String,
+    #[serde(default)]
+    pub created: u64,
+    #[serde(default)]
+    pub model: String,
     pub choices: Vec<ChunkChoice>,
+    #[serde(default)]
+    pub usage: Option<UsageStats>,
 }
 
-#[derive(Deserialize, Debug, Clone)] 
+#[derive(Serialize, Deserialize, Debug, Clone)] 
 pub struct ChunkChoice {
+    #[serde(default)]
+    pub index: u32,
     pub delta: Delta, 
-    
+    #[serde(default)]
+    pub finish_reason: Option<String>,
 }
 
-#[derive(Deserialize, Debug, Clone)] 
+#[derive(Serialize, Deserialize, Debug, Clone, Default)] 
 pub struct Delta {
+    #[serde(default)]
+    pub role: Option<Role>,
     #[serde(default)]
     pub content: Option<String>,
     #[serde(default)]
+    pub reasoning: Option<String>,
+    #[serde(default)]
     pub tool_calls: Option<Vec<ToolCall>>,
 }
 
@@ -0,0 +1,95 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
+
+use crate::config::ApiConfig;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+static SHARED: OnceLock<Arc<RequestScheduler>> = OnceLock::new();
+
+// Paces model API requests: at most `requests_per_minute` start in any minute (0 for no
+// limit) and at most `max_concurrent_requests` are in flight. A request holds its slot until
+// its response, or its whole stream, has been read.
+#[derive(Debug)]
+pub struct RequestScheduler {
+    // Start times of the requests in the current window, oldest first.
+    started: Mutex<VecDeque<Instant>>,
+    requests_per_window: usize,
+    window: Duration,
+    slots: Arc<Semaphore>,
+}
+
+impl RequestScheduler {
+    pub fn new(requests_per_minute: u32, max_concurrent_requests: usize) -> Self {
+        Self::with_window(requests_per_minute as usize, max_concurrent_requests, WINDOW)
+    }
+
+    fn with_window(requests_per_window: usize, max_concurrent_requests: usize, window: Duration) -> Self {
+        RequestScheduler {
+            started: Mutex::new(VecDeque::new()),
+            requests_per_window,
+            window,
+            slots: Arc::new(Semaphore::new(max_concurrent_requests.max(1))),
+        }
+    }
+
+    // The scheduler every ApiClient in the process shares, built from the first client's
+    // `[api]` settings, so parallel loops and `bench` draw from one account budget.
+    pub fn shared(config: &ApiConfig) -> Arc<RequestScheduler> {
+        SHARED.get_or_init(|| Arc::new(Self::new(config.requests_per_minute, config.max_concurrent_requests))).clone()
+    }
+
+    // Waits for a free slot, then for room in the per-minute window. The slot is released
+    // when the returned permit is dropped.
+    pub async fn acquire(&self) -> OwnedSemaphorePermit {
+        let permit = self.slots.clone().acquire_owned().await.expect("request semaphore is never closed");
+        if self.requests_per_window == 0 {
+            return permit;
+        }
+        loop {
+            let wait = {
+                let mut started = self.started.lock().await;
+                let now = Instant::now();
+                while started.front().is_some_and(|t| now.duration_since(*t) >= self.window) {
+                    started.pop_front();
+                }
+                if started.len() < self.requests_per_window {
+                    started.push_back(now);
+                    None
+                } else {
+                    started.front().map(|oldest| self.window - now.duration_since(*oldest))
+                }
+            };
+            match wait {
+                Some(delay) => {
+                    tracing::debug!("API request limit reached, waiting {:?}", delay);
+                    tokio::time::sleep(delay).await;
+                }
+                None => return permit,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_limits_rate_and_concurrency() {
+        let scheduler = Arc::new(RequestScheduler::with_window(2, 1, Duration::from_millis(100)));
+        let start = Instant::now();
+        let first = scheduler.acquire().await;
+        let blocked = tokio::time::timeout(Duration::from_millis(30), scheduler.acquire()).await;
+        assert!(blocked.is_err(), "a second request should wait for the only slot");
+        drop(first);
+        drop(scheduler.acquire().await);
+        assert!(start.elapsed() < Duration::from_millis(90));
+
+        // Two requests started in this window; the third waits for the first to age out.
+        drop(scheduler.acquire().await);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,183 @@
+// Tool calls for models without native function calling. The tool definitions go into the
+// system prompt, the model answers with fenced `tool_call` blocks, and those are parsed back
+// into ordinary tool calls, so the agent loops work unchanged.
+use serde_json::Value;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::api::client::add_system_instruction;
+use crate::api::models::{
+    ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse, ChunkChoice, Delta, Role, ToolCall, ToolCallFunction,
+    ToolDefinition,
+};
+
+const TOOL_CALL_FENCE: &str = "tool_call";
+// Emulated calls get ids of their own; results are matched to them in the next request.
+static NEXT_CALL_ID: AtomicUsize = AtomicUsize::new(1);
+
+// Whether `error` is a provider saying the model cannot take tools, e.g. OpenRouter's
+// "No endpoints found that support tool use".
+pub fn lacks_tool_support(error: &anyhow::Error) -> bool {
+    format!("{:#}", error).to_lowercase().contains("support tool use")
+}
+
+pub fn tool_prompt(tools: &[ToolDefinition]) -> String {
+    let mut prompt = format!(
+        "You can call tools. To call one, reply with a fenced block tagged {0} holding a JSON object \
+with the tool's name and arguments:\n```{0}\n{{\"name\": \"<tool name>\", \"arguments\": {{...}}}}\n```\n\
+Use one block per call; several calls may share a reply. Each result comes back in the next \
+message. Available tools:",
+        TOOL_CALL_FENCE
+    );
+    for tool in tools {
+        prompt.push_str(&format!(
+            "\n- {}: {}\n  arguments schema: {}",
+            tool.function.name, tool.function.description, tool.function.parameters
+        ));
+    }
+    prompt
+}
+
+fn fenced_call(call: &ToolCall) -> String {
+    let arguments = serde_json::from_str::<Value>(&call.function.arguments).unwrap_or(Value::String(call.function.arguments.clone()));
+    let block = serde_json::json!({ "name": call.function.name, "arguments": arguments });
+    format!("```{}\n{}\n```", TOOL_CALL_FENCE, block)
+}
+
+// `request` without native tools: their descriptions join the system prompt, and earlier tool
+// calls and results in the history become the text the model would have written and read.
+pub fn emulate_request(request: &ChatCompletionRequest) -> ChatCompletionRequest {
+    let mut request = request.clone();
+    let tools = request.tools.take().unwrap_or_default();
+    request.tool_choice = None;
+    for message in &mut request.messages {
+        if let Some(calls) = message.tool_calls.take() {
+            let blocks: Vec<String> = calls.iter().map(fenced_call).collect();
+            let text = message.content.take().filter(|c| !c.trim().is_empty());
+            message.content = Some(text.into_iter().chain(blocks).collect::<Vec<_>>().join("\n\n"));
+        }
+        if message.role == Role::Tool {
+            let id = message.tool_call_id.take().unwrap_or_default();
+            message.role = Role::User;
+            message.content = Some(format!("Result of tool call {}:\n{}", id, message.content.take().unwrap_or_default()));
+        }
+    }
+    add_system_instruction(&mut request.messages, &tool_prompt(&tools));
+    request
+}
+
+// The `tool_call` blocks in `content` naming one of `tools`, and the text around them.
+pub fn parse_tool_calls(content: &str, tools: &[String]) -> (String, Vec<ToolCall>) {
+    let mut text = String::new();
+    let mut calls = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find("```") {
+        let after_fence = &rest[start + 3..];
+        let Some(end) = after_fence.find("```") else { break };
+        let (tag, body) = after_fence[..end].split_once('\n').unwrap_or((&after_fence[..end], ""));
+        // Only the dedicated tag counts, so JSON quoted in an answer is never run as a call.
+        let call = Some(body).filter(|_| tag.trim() == TOOL_CALL_FENCE).and_then(|body| serde_json::from_str::<Value>(body.trim()).ok());
+        let name = call.as_ref().and_then(|c| c.get("name")).and_then(Value::as_str).filter(|n| tools.iter().any(|t| t == n));
+        match (name, &call) {
+            (Some(name), Some(call)) => {
+                text.push_str(&rest[..start]);
+                let arguments = call.get("arguments").cloned().unwrap_or_else(|| serde_json::json!({}));
+                calls.push(ToolCall {
+                    id: format!("call_emulated_{}", NEXT_CALL_ID.fetch_add(1, Ordering::Relaxed)),
+                    tool_type: "function".to_string(),
+                    function: ToolCallFunction { name: name.to_string(), arguments: arguments.to_string() },
+                });
+            }
+            _ => text.push_str(&rest[..start + 3 + end + 3]),
+        }
+        rest = &after_fence[end + 3..];
+    }
+    text.push_str(rest);
+    (text.trim().to_string(), calls)
+}
+
+// `response` with the tool calls written into its text turned into native ones.
+pub fn emulate_response(mut response: ChatCompletionResponse, tools: &[String]) -> ChatCompletionResponse {
+    for choice in &mut response.choices {
+        let (text, calls) = parse_tool_calls(choice.message.content.as_deref().unwrap_or_default(), tools);
+        if !calls.is_empty() {
+            choice.message.content = Some(text).filter(|t| !t.is_empty());
+            choice.message.tool_calls = Some(calls);
+            choice.finish_reason = Some("tool_calls".to_string());
+        }
+    }
+    response
+}
+
+// A complete response as the single chunk of a stream, for streamed requests that are
+// answered without streaming.
+pub fn response_chunk(response: ChatCompletionResponse) -> ChatCompletionChunk {
+    ChatCompletionChunk {
+        id: String::new(),
+        object: "chat.completion.chunk".to_string(),
+        created: 0,
+        model: String::new(),
+        choices: response
+            .choices
+            .into_iter()
+            .enumerate()
+            .map(|(index, choice)| ChunkChoice {
+                index: index as u32,
+                delta: Delta { role: Some(Role::Assistant), content: choice.message.content, reasoning: None, tool_calls: choice.message.tool_calls },
+                finish_reason: choice.finish_reason,
+            })
+            .collect(),
+        usage: response.usage,
+    }
+}
+
+pub fn tool_names(request: &ChatCompletionRequest) -> Vec<String> {
+    request.tools.iter().flatten().map(|t| t.function.name.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::models::{FunctionDefinition, Message};
+
+    fn user_message(content: &str) -> Message {
+        Message { role: Role::User, content: Some(content.to_string()), tool_calls: None, tool_call_id: None }
+    }
+
+    #[test]
+    fn test_emulated_round_trip() {
+        let tools = vec!["FileReadTool".to_string()];
+        let reply = "Let me look.\n```tool_call\n{\"name\": \"FileReadTool\", \"arguments\": {\"path\": \"src/main.rs\"}}\n```\n```rust\nfn main() {}\n```";
+        let (text, calls) = parse_tool_calls(reply, &tools);
+        assert_eq!(text, "Let me look.\n\n```rust\nfn main() {}\n```");
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].function.arguments, "{\"path\":\"src/main.rs\"}");
+        assert!(parse_tool_calls("```json\n{\"name\": \"ShellTool\", \"arguments\": {}}\n```", &tools).1.is_empty());
+        let quoted = "For example:\n```json\n{\"name\": \"FileReadTool\", \"arguments\": {}}\n```\n```\n{\"name\": \"FileReadTool\"}\n```";
+        let (quoted_text, quoted_calls) = parse_tool_calls(quoted, &tools);
+        assert_eq!((quoted_text.as_str(), quoted_calls.len()), (quoted, 0));
+
+        let request = ChatCompletionRequest {
+            model: "cheap/model".to_string(),
+            messages: vec![
+                user_message("Read main.rs"),
+                Message { role: Role::Assistant, content: None, tool_calls: Some(calls), tool_call_id: None },
+                Message { role: Role::Tool, content: Some("fn main() {}".to_string()), tool_calls: None, tool_call_id: Some("call_1".to_string()) },
+            ],
+            temperature: None,
+            max_tokens: None,
+            stream: None,
+            tools: Some(vec![ToolDefinition {
+                tool_type: "function".to_string(),
+                function: FunctionDefinition { name: "FileReadTool".to_string(), description: "Reads a file".to_string(), parameters: serde_json::json!({}) },
+            }]),
+            tool_choice: None,
+            source_map: None,
+        };
+        let emulated = emulate_request(&request);
+        assert!(emulated.tools.is_none());
+        assert!(emulated.messages[0].content.as_deref().unwrap().contains("- FileReadTool: Reads a file"));
+        assert!(emulated.messages[2].content.as_deref().unwrap().starts_with("```tool_call\n{\"arguments\":{\"path\":\"src/main.rs\"},\"name\":\"FileReadTool\"}"));
+        assert_eq!(emulated.messages[3].role, Role::User);
+        assert_eq!(emulated.messages[3].content.as_deref(), Some("Result of tool call call_1:\nfn main() {}"));
+    }
+}
@@ -1,18 +1,31 @@
 use anyhow::{Context, Result}; // Keep Context and Result
-use clap::Parser;
+use clap::FromArgMatches;
 // Removed std::fs
+use std::ffi::OsString;
 use std::fs;
 use std::path::{Path, PathBuf};
 use serde_json::json;
 // Removed tokio::sync::mpsc import
-use tracing_subscriber::{fmt, EnvFilter};
 
-use crate::api::client::ApiClient;
-use crate::cli::commands::{Cli, Commands}; // Removed ShellCommands
-use crate::config::Config;
+use crate::api::chat_api::LazyApiClient;
+use crate::cargo_workspace::is_build_output;
+use crate::cli::commands::{Cli, Commands, OutputFormat}; // Removed ShellCommands
+use crate::config::{CassetteMode, Config};
+use crate::context::environment::EnvironmentProvider;
+use crate::context::session::NamedSessionStore;
+use crate::context::summaries::SummaryCache;
 use crate::context::ContextManager;
+use crate::events::EventBus;
+use crate::hooks::HookRunner;
+use crate::logging;
+use crate::stream_json::StreamJsonWriter;
+use crate::streaming::tee_to;
+use crate::shutdown::{restore_terminal, save_interrupted_snapshots, ShutdownCoordinator};
+use crate::tools::devcontainer;
 use crate::tools::execution::{SecurityPolicy, ToolExecutionEngine};
+use crate::tools::plugin::PluginStore;
 use crate::tools::registry::ToolRegistry;
+use crate::tui::{print_error, print_info, print_warning, Verbosity};
 // Removed TUI imports
 
 // Import command handlers (assuming they exist in submodules)
@@ -25,8 +38,28 @@ use crate::commands::{
     debug::handle_debug,
     test_cmd::handle_test,
     doc::handle_doc,
-    run::handle_run,
+    run::{handle_run, IsolatedRun},
     shell::handle_shell,
+    deps::handle_deps,
+    audit::handle_audit_deps,
+    plugin::handle_plugin,
+    pipeline::handle_pipeline,
+    review::handle_review,
+    diagram::handle_diagram,
+    bench::handle_bench,
+    scaffold::handle_new,
+    session::handle_session,
+    lsp_bridge::handle_lsp_bridge,
+    apply_patch::handle_apply_patch,
+    logs::handle_logs,
+    refactor::handle_refactor,
+    todos::handle_todos,
+    self_update::handle_self_update,
+    help::{command_with_examples, handle_help},
+    alias::{handle_alias, parse_with_aliases},
+    ping::handle_ping,
+    onboard::handle_onboard,
+    index::handle_index,
 };
 use crate::interactive::run_interactive_mode;
 
@@ -49,7 +82,7 @@ pub fn generate_source_map(dir: &Path) -> Result<String> {
             let file_name = file_name_os.to_str().ok_or_else(|| anyhow::anyhow!("Filename is not valid UTF-8"))?;
 
             // Skip common unnecessary directories/files
-            if file_name == ".git" || file_name == "target" || file_name.starts_with('.') {
+            if file_name.starts_with('.') || is_build_output(&path) {
                 continue;
             }
 
@@ -70,62 +103,213 @@ pub fn generate_source_map(dir: &Path) -> Result<String> {
 }
 
 pub async fn run() -> Result<()> {
-    fmt()
-        .with_env_filter(EnvFilter::builder().parse("info").unwrap())
-        .init();
-
-    tracing::info!("Application started");
+    run_from(std::env::args_os()).await
+}
 
+// `run` with explicit arguments, the first being the program name; used by `cargo opencode`.
+pub async fn run_from(args: impl IntoIterator<Item = OsString>) -> Result<()> {
+    let args: Vec<OsString> = args.into_iter().collect();
+    // Examples in `--help` name the program as it was invoked, e.g. `cargo opencode`.
+    let bin = args.first().and_then(|a| Path::new(a).file_name()).map(|a| a.to_string_lossy().to_string()).unwrap_or_else(|| "opencode".to_string());
+    let matches = parse_with_aliases(command_with_examples(&bin), args);
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let output_format = match &cli.command {
+        Some(Commands::Ask { output, .. }) => *output,
+        Some(Commands::Run(args)) => args.output,
+        _ => OutputFormat::Text,
+    };
     // Reverted: Removed terminal initialization and TUI app setup
 
     // Reverted: Command handling logic runs directly, not in a separate task
-    let cli = Cli::parse();
-    let config = Config::load().context("Failed to load configuration")?;
-    let context_manager = ContextManager::new(config.clone())?;
-    let tool_registry = ToolRegistry::new(&config);
-    let tool_engine = ToolExecutionEngine::new(&tool_registry, SecurityPolicy::ConfirmWrites);
-
-    let command_result = if let Some(command) = cli.command {
-        match command {
-            Commands::Configure(args) => {
-                handle_configure(config, args).await
-            }
-            Commands::Ask { prompt } => {
-                handle_ask(config, context_manager, &tool_registry, &tool_engine, prompt).await
-            }
-            Commands::Generate(args) => {
-                handle_generate(config, args).await
-            }
-            Commands::Explain(args) => {
-                handle_explain(config, args).await
-            }
-            Commands::Edit(args) => {
-                handle_edit(config, &tool_registry, &tool_engine, args).await
-            }
-            Commands::Debug(args) => {
-                handle_debug(config, args).await
-            }
-            Commands::Test(args) => {
-                handle_test(config, args).await
-            }
-            Commands::Doc(args) => {
-                handle_doc(config, args).await
-            }
-            Commands::Run(args) => {
-                handle_run(config, context_manager, &tool_registry, &tool_engine, args).await
+    let mut config = Config::load().context("Failed to load configuration")?;
+    let _log_guard = logging::init(&config.logging)?;
+    tracing::info!("Application started");
+    if let Some(lang) = cli.lang.clone() {
+        config.output.language = Some(lang);
+    }
+    config.api.cassette = match (cli.record.clone(), cli.replay.clone()) {
+        (Some(path), _) => Some(CassetteMode::Record(path)),
+        (None, Some(path)) => Some(CassetteMode::Replay(path)),
+        (None, None) => None,
+    };
+    let config = config;
+    crate::tui::wrap::set_max_width(config.output.max_width);
+    crate::tui::pager::set_tui_enabled(config.output.tui.unwrap_or(true));
+    crate::tui::theme::set_ui_config(&config.ui);
+    crate::tui::set_verbosity(match (cli.quiet, cli.verbose) {
+        (true, _) => Verbosity::Quiet,
+        (_, true) => Verbosity::Verbose,
+        _ => Verbosity::Normal,
+    });
+    if let Some(path) = &cli.tee {
+        tee_to(path)?;
+    }
+    let isolated = match &cli.command {
+        Some(Commands::Run(args)) if args.isolated => Some(IsolatedRun::enter().await?),
+        _ => None,
+    };
+    let mut context_manager = ContextManager::new(config.clone())?;
+    context_manager.set_environment_context(EnvironmentProvider::from_current_dir()?.snapshot().await.summary())?;
+    let mut tool_registry = ToolRegistry::new(&config);
+    match PluginStore::from_config_dir() {
+        Ok(store) => tool_registry.load_plugins(&store),
+        Err(e) => tracing::warn!("Plugins unavailable: {}", e),
+    }
+    let runs_agent = matches!(cli.command, None | Some(Commands::Ask { .. }) | Some(Commands::Run(_)));
+    if runs_agent && output_format == OutputFormat::Text {
+        if let Ok(dir) = std::env::current_dir() {
+            devcontainer::offer(&config.devcontainer, tool_registry.devcontainer(), &dir).await;
+        }
+    }
+    let tool_registry = tool_registry;
+    context_manager.attach_notes(tool_registry.notes().clone());
+    context_manager.attach_memory(tool_registry.memory().clone());
+    // Only the commands that send the shared context get the onboarding overview.
+    let uses_project_context = runs_agent || matches!(cli.command, Some(Commands::Refactor(_)));
+    if let (true, Ok(dir)) = (uses_project_context, std::env::current_dir()) {
+        context_manager.attach_summaries(&SummaryCache::for_project(&dir));
+    }
+    if let Some(name) = &cli.session {
+        let store = NamedSessionStore::from_config_dir()?;
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        let session = store.open(name, &std::env::current_dir()?, now)?;
+        if !session.messages.is_empty() {
+            print_info(&format!("Continuing session '{}' ({} messages).", name, session.messages.len()));
+        }
+        context_manager.attach_named_session(store, session)?;
+    }
+    let tool_engine = ToolExecutionEngine::new(&tool_registry, SecurityPolicy::ConfirmWrites)
+        .with_network_limiter(tool_registry.network_limiter().clone())
+        .with_hooks(HookRunner::new(&config.hooks))
+        .with_untrusted_content(&config.untrusted_content)
+        .with_tool_policy(&config.tool_policy);
+
+    let shutdown = ShutdownCoordinator::global();
+    shutdown.listen_for_signals()?;
+    let stream_json = (output_format == OutputFormat::StreamJson)
+        .then(|| StreamJsonWriter::start(EventBus::global(), std::io::stdout()));
+    // The one model client of this run, shared by every command so requests reuse its
+    // connections. Built on the first model request, so offline commands and paths never need a key.
+    let api_client = LazyApiClient::new(config.clone());
+    let command = async {
+        if let Some(command) = cli.command {
+            match command {
+                Commands::Configure(args) => {
+                    handle_configure(config, args).await
+                }
+                Commands::Ask { prompt, .. } => {
+                    handle_ask(&api_client, config, context_manager, &tool_registry, &tool_engine, prompt).await
+                }
+                Commands::Generate(args) => {
+                    handle_generate(&api_client, config, args).await
+                }
+                Commands::Explain(args) => {
+                    handle_explain(&api_client, config, args).await
+                }
+                Commands::Edit(args) => {
+                    handle_edit(&api_client, config, &tool_registry, &tool_engine, args).await
+                }
+                Commands::Debug(args) => {
+                    handle_debug(&api_client, config, args).await
+                }
+                Commands::Test(args) => {
+                    handle_test(&api_client, config, args).await
+                }
+                Commands::Doc(args) => {
+                    handle_doc(&api_client, config, args).await
+                }
+                Commands::Run(args) => {
+                    handle_run(&api_client, config, context_manager, &tool_registry, &tool_engine, args, isolated).await
+                }
+                Commands::Shell(shell_args) => {
+                    handle_shell(&api_client, config, shell_args).await
+                }
+                Commands::Deps(deps_args) => {
+                    handle_deps(&api_client, config, &tool_registry, deps_args).await
+                }
+                Commands::AuditDeps(args) => {
+                    handle_audit_deps(&api_client, config, &tool_registry, args).await
+                }
+                Commands::Plugin(args) => {
+                    handle_plugin(args).await
+                }
+                Commands::Pipeline(args) => {
+                    handle_pipeline(&api_client, config, &tool_registry, args).await
+                }
+                Commands::Review(args) => {
+                    handle_review(&api_client, config, args).await
+                }
+                Commands::Diagram(args) => {
+                    handle_diagram(&api_client, config, args).await
+                }
+                Commands::Bench(args) => {
+                    handle_bench(&api_client, args).await
+                }
+                Commands::New(args) => {
+                    handle_new(&api_client, config, &tool_engine, args).await
+                }
+                Commands::Session(args) => {
+                    handle_session(args).await
+                }
+                Commands::ApplyPatch(args) => {
+                    handle_apply_patch(args).await
+                }
+                Commands::Logs(args) => {
+                    handle_logs(args).await
+                }
+                Commands::Refactor(args) => {
+                    handle_refactor(&api_client, config, &context_manager, &tool_registry, &tool_engine, args).await
+                }
+                Commands::Todos(args) => {
+                    handle_todos(&api_client, config, args).await
+                }
+                Commands::SelfUpdate(args) => {
+                    handle_self_update(config, args).await
+                }
+                Commands::Help(args) => {
+                    handle_help(args, &bin).await
+                }
+                Commands::Alias(args) => {
+                    handle_alias(config, args).await
+                }
+                Commands::Ping(args) => {
+                    handle_ping(&api_client, config, args).await
+                }
+                Commands::Onboard(args) => {
+                    handle_onboard(&api_client, config, args).await
+                }
+                Commands::Index(args) => {
+                    handle_index(config, args).await
+                }
+                Commands::LspBridge => {
+                    handle_lsp_bridge(&api_client, config, &tool_registry, &tool_engine).await
+                }
             }
-            Commands::Shell(shell_args) => {
-                handle_shell(config, shell_args).await
+        } else {
+            tracing::info!("No subcommand provided, entering interactive mode.");
+            run_interactive_mode(config, &api_client, context_manager, &tool_registry, &tool_engine).await
+        }
+    };
+
+    let command_result = match shutdown.run_until_signal(command).await {
+        Ok(result) => result,
+        Err(signal) => {
+            restore_terminal();
+            shutdown.run_cleanups();
+            print_warning(&format!("Received {}, stopping.", signal));
+            match save_interrupted_snapshots(tool_registry.snapshots()) {
+                Ok(Some(path)) => print_info(&format!("Original contents of modified files saved to {}", path.display())),
+                Ok(None) => {}
+                Err(e) => print_error(&format!("Failed to save modified file snapshots: {}", e)),
             }
+            Ok(())
         }
-    } else {
-        tracing::info!("No subcommand provided, entering interactive mode.");
-        let api_client = ApiClient::new(config.clone())
-            .context("Failed to create API client for interactive mode (check API key configuration)")?;
-        run_interactive_mode(config, api_client, context_manager, &tool_registry, &tool_engine).await
     };
 
-    // Reverted: Removed TUI run loop and terminal restoration logic
+    if let Some(writer) = stream_json {
+        let error = command_result.as_ref().err().map(|e| format!("{:#}", e));
+        writer.finish(EventBus::global(), error).await;
+    }
 
     tracing::info!("Application finished");
 
@@ -0,0 +1,43 @@
+// `cargo opencode <command> ...`: runs opencode from the root of the enclosing cargo workspace,
+// telling it where the workspace's build output lives. Cargo invokes a subcommand binary as
+// `cargo-opencode opencode <args>`.
+use std::ffi::OsString;
+
+use opencode::app;
+use opencode::cargo_workspace::{rebase_path_args, CargoWorkspace, TARGET_DIR_ENV};
+use opencode::commands::self_update::UpdateAvailable;
+use opencode::shutdown::ShutdownCoordinator;
+use opencode::tui::print_error;
+
+// Not `#[tokio::main]`: the environment is set up before the runtime starts any threads.
+fn main() {
+    let mut args: Vec<OsString> = std::env::args_os().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "opencode") {
+        args.remove(0);
+    }
+
+    let result = enter_workspace(args).and_then(|args| {
+        let runtime = tokio::runtime::Runtime::new()?;
+        runtime.block_on(app::run_from(std::iter::once(OsString::from("cargo opencode")).chain(args)))
+    });
+    if let Some(signal) = ShutdownCoordinator::global().received() {
+        std::process::exit(signal.exit_code());
+    }
+    if let Err(e) = result {
+        if e.downcast_ref::<UpdateAvailable>().is_some() {
+            std::process::exit(UpdateAvailable::EXIT_CODE);
+        }
+        print_error(&format!("Application failed: {:?}", e));
+        std::process::exit(1);
+    }
+}
+
+// Moves to the workspace root and returns `args` with relative paths rebased onto it.
+fn enter_workspace(args: Vec<OsString>) -> anyhow::Result<Vec<OsString>> {
+    let cwd = std::env::current_dir()?;
+    let workspace = CargoWorkspace::locate(&cwd)?;
+    let args = rebase_path_args(args, &cwd, &workspace.root);
+    std::env::set_current_dir(&workspace.root)?;
+    std::env::set_var(TARGET_DIR_ENV, &workspace.target_dir);
+    Ok(args)
+}
@@ -0,0 +1,83 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Set by `cargo opencode` so the environment context and file walkers know where build
+// output goes, including a target dir moved by CARGO_TARGET_DIR or `build.target-dir`.
+pub const TARGET_DIR_ENV: &str = "OPENCODE_CARGO_TARGET_DIR";
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CargoWorkspace {
+    #[serde(rename = "workspace_root")]
+    pub root: PathBuf,
+    #[serde(rename = "target_directory")]
+    pub target_dir: PathBuf,
+}
+
+impl CargoWorkspace {
+    // The workspace containing `dir`, as `cargo metadata` reports it. Cargo sets `CARGO` for
+    // subcommands, so the toolchain that launched us answers.
+    pub fn locate(dir: &Path) -> Result<Self> {
+        let cargo = std::env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo"));
+        let output = Command::new(cargo)
+            .args(["metadata", "--format-version", "1", "--no-deps"])
+            .current_dir(dir)
+            .output()
+            .context("Failed to run cargo metadata")?;
+        if !output.status.success() {
+            bail!("cargo metadata failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        serde_json::from_slice(&output.stdout).context("Failed to parse cargo metadata output")
+    }
+}
+
+// The target dir announced by `cargo opencode`, if this process was started by it.
+pub fn target_dir() -> Option<PathBuf> {
+    std::env::var_os(TARGET_DIR_ENV).map(PathBuf::from)
+}
+
+// Whether a directory walk should skip `path` as build output: any `target` directory, or
+// the workspace's configured target dir.
+pub fn is_build_output(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name == "target") || target_dir().is_some_and(|dir| path == dir)
+}
+
+// Arguments naming existing paths relative to `cwd`, rewritten relative to `root`, so they
+// still resolve once the process has moved to the workspace root.
+pub fn rebase_path_args(args: Vec<OsString>, cwd: &Path, root: &Path) -> Vec<OsString> {
+    args.into_iter()
+        .map(|arg| {
+            let path = Path::new(&arg);
+            if path.is_absolute() || arg.to_string_lossy().starts_with('-') || !cwd.join(path).exists() {
+                return arg;
+            }
+            match cwd.join(path).strip_prefix(root) {
+                Ok(relative) if !relative.as_os_str().is_empty() => relative.as_os_str().to_os_string(),
+                Ok(_) => OsString::from("."),
+                Err(_) => arg,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_rebase_path_args_from_member_crate() {
+        let root = tempdir().unwrap();
+        let member = root.path().join("crates/core");
+        std::fs::create_dir_all(member.join("src")).unwrap();
+        std::fs::write(member.join("src/lib.rs"), "").unwrap();
+
+        let args = ["explain", "src/lib.rs", "--function", "parse", "."].map(OsString::from).to_vec();
+        let rebased = rebase_path_args(args, &member, root.path());
+        assert_eq!(rebased, ["explain", "crates/core/src/lib.rs", "--function", "parse", "crates/core"].map(OsString::from));
+        assert!(is_build_output(Path::new("/work/target")));
+        assert!(!is_build_output(Path::new("/work/src")));
+    }
+}
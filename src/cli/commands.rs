@@ -1,10 +1,41 @@
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, disable_help_subcommand = true)]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>, 
+
+    
+    #[arg(long, global = true, value_name = "LANGUAGE")]
+    pub lang: Option<String>,
+
+    
+    #[arg(long, global = true, value_name = "CASSETTE", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    
+    #[arg(long, global = true, value_name = "CASSETTE")]
+    pub replay: Option<PathBuf>,
+
+    
+    #[arg(long, global = true, value_name = "FILE")]
+    pub tee: Option<PathBuf>,
+
+    
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
+
+    
+    #[arg(long, global = true, value_name = "NAME")]
+    pub session: Option<String>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -12,7 +43,12 @@ pub enum Commands {
     
     Configure(ConfigureArgs),
     
-    Ask { prompt: String },
+    Ask {
+        prompt: String,
+
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+    },
     
     Generate(GenerateArgs),
     
@@ -28,6 +64,51 @@ pub enum Commands {
     Run(RunArgs),
     
     Shell(ShellArgs),
+
+    Deps(DepsArgs),
+
+    AuditDeps(AuditDepsArgs),
+
+    Plugin(PluginArgs),
+
+    Pipeline(PipelineArgs),
+
+    Review(ReviewArgs),
+
+    Diagram(DiagramArgs),
+
+    Bench(BenchArgs),
+
+    New(NewArgs),
+
+    Session(SessionArgs),
+
+    LspBridge,
+
+    ApplyPatch(ApplyPatchArgs),
+
+    Logs(LogsArgs),
+
+    Refactor(RefactorArgs),
+
+    Todos(TodosArgs),
+
+    SelfUpdate(SelfUpdateArgs),
+
+    
+    Help(HelpArgs),
+
+    
+    Alias(AliasArgs),
+
+    
+    Ping(PingArgs),
+
+    
+    Onboard(OnboardArgs),
+
+    
+    Index(IndexArgs),
    }
    
    #[derive(Args, Debug)]
@@ -57,11 +138,18 @@ pub struct GenerateArgs {
 }
 
 #[derive(Args, Debug)]
-#[group(required = false, multiple = false)] 
 pub struct ExplainArgs {
     
-    #[arg(long, required = true)]
-    pub file: String,
+    #[arg(long, required_unless_present = "dir", conflicts_with = "dir")]
+    pub file: Option<String>,
+
+    
+    #[arg(long, value_name = "DIRECTORY")]
+    pub dir: Option<String>,
+
+    
+    #[arg(long, value_enum, default_value_t = DiagramFormat::Ascii, requires = "dir")]
+    pub diagram: DiagramFormat,
 
     
     #[arg(long, group = "context_specifier")]
@@ -70,6 +158,10 @@ pub struct ExplainArgs {
     
     #[arg(long, group = "context_specifier")]
     pub symbol: Option<String>,
+
+    
+    #[arg(long, requires = "file")]
+    pub with_history: bool,
 }
 
 
@@ -114,6 +206,28 @@ pub struct DocArgs {
 pub struct RunArgs {
     
     pub task_description: String,
+
+    
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+
+    
+    #[arg(long)]
+    pub isolated: bool,
+
+    
+    #[arg(long)]
+    pub verify: bool,
+
+    
+    #[arg(long, conflicts_with_all = ["isolated", "verify"])]
+    pub plan_only: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    StreamJson,
 }
 
 #[derive(Args, Debug)]
@@ -140,4 +254,415 @@ pub struct ShellExplainArgs {
 pub struct ShellSuggestArgs {
     
     pub description: String,
-}
\ No newline at end of file
+}
+#[derive(Args, Debug)]
+pub struct DepsArgs {
+    #[command(subcommand)]
+    pub command: DepsCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DepsCommands {
+    
+    Upgrade(DepsUpgradeArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct DepsUpgradeArgs {
+    
+    #[arg(long, default_value = "Cargo.toml")]
+    pub manifest: String,
+
+    
+    #[arg(long)]
+    pub major: bool,
+
+    
+    #[arg(long)]
+    pub yes: bool,
+
+    
+    #[arg(long)]
+    pub test: bool,
+
+    
+    #[arg(long)]
+    pub no_changelog: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct AuditDepsArgs {
+    
+    #[arg(long, default_value = ".")]
+    pub directory: String,
+
+    
+    #[arg(long)]
+    pub yes: bool,
+
+    
+    #[arg(long)]
+    pub no_explain: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct PluginArgs {
+    #[command(subcommand)]
+    pub command: PluginCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PluginCommands {
+    
+    Install(PluginInstallArgs),
+    
+    List,
+    
+    Remove(PluginRemoveArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct PluginInstallArgs {
+    
+    pub command: String,
+
+    
+    #[arg(long)]
+    pub name: Option<String>,
+
+    
+    #[arg(last = true)]
+    pub args: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct PluginRemoveArgs {
+    
+    pub name: String,
+}
+
+#[derive(Args, Debug)]
+pub struct PipelineArgs {
+    #[command(subcommand)]
+    pub command: PipelineCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PipelineCommands {
+    
+    Run(PipelineRunArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct PipelineRunArgs {
+    
+    pub file: String,
+
+    
+    #[arg(long = "var", value_name = "KEY=VALUE")]
+    pub vars: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct ReviewArgs {
+    
+    pub files: Vec<String>,
+
+    
+    #[arg(long, default_value = "HEAD")]
+    pub base: String,
+
+    
+    #[arg(long)]
+    pub ci: bool,
+
+    
+    #[arg(long, value_enum, default_value_t = CiFormat::Github)]
+    pub format: CiFormat,
+
+    
+    #[arg(long, value_name = "FILE_PATH")]
+    pub output: Option<String>,
+
+    
+    #[arg(long, value_enum, default_value_t = Severity::Error)]
+    pub fail_on: Severity,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CiFormat {
+    Github,
+    Gitlab,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Notice,
+    Warning,
+    Error,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiagramFormat {
+    Ascii,
+    Mermaid,
+}
+
+#[derive(Args, Debug)]
+pub struct DiagramArgs {
+    
+    #[arg(long, default_value = "src")]
+    pub scope: String,
+
+    
+    #[arg(long, value_enum, default_value_t = GraphFormat::Mermaid)]
+    pub format: GraphFormat,
+
+    
+    #[arg(long, value_name = "FILE_PATH")]
+    pub output: Option<String>,
+
+    
+    #[arg(long)]
+    pub render: bool,
+
+    
+    #[arg(long)]
+    pub no_model: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphFormat {
+    Mermaid,
+    Dot,
+}
+
+#[derive(Args, Debug)]
+pub struct BenchArgs {
+    
+    #[arg(long, value_name = "FILE_PATH")]
+    pub prompts: String,
+
+    
+    #[arg(long, value_name = "MODEL_IDS")]
+    pub models: String,
+
+    
+    #[arg(long, value_name = "MODEL_ID")]
+    pub judge: Option<String>,
+
+    
+    #[arg(long, value_enum, default_value_t = BenchFormat::Table)]
+    pub format: BenchFormat,
+
+    
+    #[arg(long, value_name = "FILE_PATH")]
+    pub output: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BenchFormat {
+    Table,
+    Json,
+}
+
+#[derive(Args, Debug)]
+pub struct NewArgs {
+    
+    pub template: String,
+
+    
+    pub name: String,
+
+    
+    #[arg(long, value_name = "DIRECTORY")]
+    pub dir: Option<String>,
+
+    
+    #[arg(long)]
+    pub yes: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct SessionArgs {
+    #[command(subcommand)]
+    pub command: SessionCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SessionCommands {
+    
+    List,
+    
+    Delete {
+        name: String,
+    },
+    
+    Rename {
+        name: String,
+
+        new_name: String,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct AliasArgs {
+    #[command(subcommand)]
+    pub command: AliasCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AliasCommands {
+    
+    List,
+}
+
+#[derive(Args, Debug)]
+pub struct IndexArgs {
+    #[command(subcommand)]
+    pub command: IndexCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum IndexCommands {
+    
+    Update {
+        
+        path: Option<String>,
+    },
+    
+    Status {
+        
+        path: Option<String>,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct LogsArgs {
+    #[command(subcommand)]
+    pub command: LogsCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LogsCommands {
+    
+    Tail {
+        
+        #[arg(short = 'n', long, default_value_t = 50)]
+        lines: usize,
+
+        
+        #[arg(short, long)]
+        follow: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct RefactorArgs {
+    
+    pub instruction: String,
+
+    
+    #[arg(long, value_name = "DIRECTORY")]
+    pub path: Option<String>,
+
+    
+    #[arg(long = "pattern", value_name = "TEXT")]
+    pub patterns: Vec<String>,
+
+    
+    #[arg(long, value_name = "TOKENS", default_value_t = 24_000)]
+    pub batch_tokens: usize,
+
+    
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct TodosArgs {
+    
+    #[arg(long, value_name = "DIRECTORY")]
+    pub path: Option<String>,
+
+    
+    #[arg(long, value_name = "FILE")]
+    pub report: Option<String>,
+
+    
+    #[arg(long)]
+    pub create_issues: bool,
+
+    
+    #[arg(long, conflicts_with = "create_issues")]
+    pub no_triage: bool,
+
+    
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct SelfUpdateArgs {
+    
+    #[arg(long)]
+    pub check: bool,
+
+    
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct PingArgs {
+    
+    #[arg(long, value_name = "MODEL_ID")]
+    pub model: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct OnboardArgs {
+    
+    pub path: Option<String>,
+
+    
+    #[arg(long, value_name = "FILE")]
+    pub output: Option<String>,
+
+    
+    #[arg(long, value_name = "N", default_value_t = 500)]
+    pub max_files: usize,
+
+    
+    #[arg(long)]
+    pub refresh: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct HelpArgs {
+    
+    pub command: Vec<String>,
+
+    
+    #[arg(long)]
+    pub examples: bool,
+
+    
+    #[arg(long, value_name = "DIRECTORY", conflicts_with_all = ["command", "examples"])]
+    pub man: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct ApplyPatchArgs {
+    
+    pub file: String,
+
+    
+    #[arg(long)]
+    pub check: bool,
+
+    
+    #[arg(long)]
+    pub three_way: bool,
+}
@@ -0,0 +1,82 @@
+// Usage examples per subcommand: a description and a command line, rendered with the pipeline
+// template syntax so `{{vars.bin}}` reads `opencode` or `cargo opencode` as invoked. Kept free of
+// crate imports so build.rs can render them into the man pages.
+pub const EXAMPLES: &[(&str, &[(&str, &str)])] = &[
+    ("configure", &[
+        ("Store the OpenRouter API key in the system keyring", "{{vars.bin}} configure --set-api-key"),
+        ("Change the model used for edits", "{{vars.bin}} configure --set-edit-model anthropic/claude-3.5-sonnet"),
+    ]),
+    ("ask", &[
+        ("Ask a question about the current project", "{{vars.bin}} ask \"Where is the config file loaded?\""),
+        ("Stream events as JSON lines for another program", "{{vars.bin}} ask \"List the public API\" --output stream-json"),
+        ("Continue a named conversation from an earlier run", "{{vars.bin}} ask \"And where is it validated?\" --session config"),
+    ]),
+    ("generate", &[
+        ("Generate code from a description", "{{vars.bin}} generate \"a function that parses ISO 8601 durations\""),
+        ("Generate code in the style of an existing file", "{{vars.bin}} generate \"a retry helper with backoff\" --file src/api/client.rs"),
+    ]),
+    ("explain", &[
+        ("Explain one function", "{{vars.bin}} explain --file src/app.rs --symbol run"),
+        ("Explain a line range with the commits that shaped it", "{{vars.bin}} explain --file src/app.rs --lines 40-80 --with-history"),
+        ("Explain a directory with a Mermaid diagram", "{{vars.bin}} explain --dir src/context --diagram mermaid"),
+    ]),
+    ("edit", &[("Change a file from an instruction", "{{vars.bin}} edit --file src/config/mod.rs \"add a timeout field to ApiConfig\"")]),
+    ("debug", &[("Diagnose an error message", "{{vars.bin}} debug --error \"thread 'main' panicked at src/main.rs:10\" --file src/main.rs")]),
+    ("test", &[("Write tests for a file", "{{vars.bin}} test --file src/parsing/outline.rs")]),
+    ("doc", &[("Add documentation comments to a file", "{{vars.bin}} doc --file src/tools/symlinks.rs")]),
+    ("run", &[
+        ("Let the agent complete a task", "{{vars.bin}} run \"rename Config::load to Config::read and fix callers\""),
+        ("Work in a throwaway worktree and self-check the result", "{{vars.bin}} run \"upgrade to tokio 1.40\" --isolated --verify"),
+        ("List the tool calls a task would make without changing anything", "{{vars.bin}} run \"add a --json flag to the list command\" --plan-only"),
+    ]),
+    ("shell", &[
+        ("Explain a shell command", "{{vars.bin}} shell explain \"find . -name '*.rs' -mtime -1\""),
+        ("Suggest a command for a task", "{{vars.bin}} shell suggest \"list the ten largest files in this repository\""),
+    ]),
+    ("deps", &[("Upgrade dependencies, including major versions, and run the tests", "{{vars.bin}} deps upgrade --major --test")]),
+    ("audit-deps", &[("Audit dependencies for known vulnerabilities", "{{vars.bin}} audit-deps --directory . --no-explain")]),
+    ("plugin", &[
+        ("Install an external tool as a plugin", "{{vars.bin}} plugin install --name jq jq -- --help"),
+        ("List installed plugins", "{{vars.bin}} plugin list"),
+    ]),
+    ("pipeline", &[("Run a pipeline file with a variable", "{{vars.bin}} pipeline run release.toml --var version=1.2.0")]),
+    ("review", &[
+        ("Review the uncommitted changes", "{{vars.bin}} review"),
+        ("Review a branch in CI and fail on warnings", "{{vars.bin}} review --base origin/main --ci --fail-on warning"),
+    ]),
+    ("diagram", &[("Draw the module graph of src without the model", "{{vars.bin}} diagram --scope src --format dot --no-model --output modules.dot")]),
+    ("bench", &[("Compare two models on a prompt file", "{{vars.bin}} bench --prompts bench.toml --models openai/gpt-4o,anthropic/claude-3.5-sonnet")]),
+    ("new", &[("Scaffold a new project from a template", "{{vars.bin}} new rust-cli mytool --dir ~/src")]),
+    ("session", &[
+        ("List named sessions and summaries of earlier interactive sessions", "{{vars.bin}} session list"),
+        ("Rename a session started with --session", "{{vars.bin}} session rename parser-fix parser"),
+    ]),
+    ("apply-patch", &[("Check that a saved session patch still applies", "{{vars.bin}} apply-patch session.patch --check")]),
+    ("logs", &[("Follow the log file", "{{vars.bin}} logs tail -n 100 --follow")]),
+    ("refactor", &[("Apply one change across matching files", "{{vars.bin}} refactor \"replace unwrap() with ? in handlers\" --path src/commands --pattern unwrap")]),
+    ("todos", &[
+        ("Triage TODO comments into a report", "{{vars.bin}} todos --report todos.md"),
+        ("List TODO comments without calling the model", "{{vars.bin}} todos --no-triage"),
+    ]),
+    ("self-update", &[
+        ("Install the latest release", "{{vars.bin}} self-update"),
+        ("Fail a CI job when a newer release exists", "{{vars.bin}} self-update --check"),
+    ]),
+    ("onboard", &[
+        ("Summarize every file and module and write ONBOARDING.md", "{{vars.bin}} onboard"),
+        ("Summarize everything again, ignoring cached summaries", "{{vars.bin}} onboard --refresh --output docs/overview.md"),
+    ]),
+    ("index", &[
+        ("Record which files changed since the last update, reading only those", "{{vars.bin}} index update"),
+        ("Show how fresh the index is and its coverage per language", "{{vars.bin}} index status"),
+    ]),
+    ("ping", &[
+        ("Check every configured model for reachability, latency and streaming", "{{vars.bin}} ping"),
+        ("Probe one model", "{{vars.bin}} ping --model openai/gpt-4o-mini"),
+    ]),
+    ("alias", &[("List the aliases defined in .OpenCode.toml", "{{vars.bin}} alias list")]),
+    ("help", &[
+        ("Show the examples for a command", "{{vars.bin}} help explain --examples"),
+        ("Write man pages", "{{vars.bin}} help --man target/man"),
+    ]),
+];
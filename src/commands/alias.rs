@@ -0,0 +1,108 @@
+use anyhow::{bail, Result};
+use clap::error::{ContextKind, ContextValue, ErrorKind};
+use clap::{ArgMatches, Command, CommandFactory};
+use std::collections::HashMap;
+use std::ffi::OsString;
+
+use crate::cli::commands::{AliasArgs, AliasCommands, Cli};
+use crate::config::Config;
+use crate::tui::{print_info, print_result};
+
+// The words `alias` stands for, split as a shell would so quoted task text stays one argument.
+pub fn alias_words(name: &str, invocation: &str) -> Result<Vec<String>> {
+    match shlex::split(invocation) {
+        Some(words) if !words.is_empty() => Ok(words),
+        Some(_) => bail!("Alias '{}' is empty", name),
+        None => bail!("Alias '{}' has unbalanced quotes: {}", name, invocation),
+    }
+}
+
+// `args` with the first occurrence of `name` after the program name replaced by the words of
+// its alias; global options before and arguments after it are kept.
+pub fn expand_alias(args: &[OsString], name: &str, invocation: &str) -> Result<Vec<OsString>> {
+    let words = alias_words(name, invocation)?;
+    let Some(position) = args.iter().skip(1).position(|arg| arg == name).map(|i| i + 1) else {
+        return Ok(args.to_vec());
+    };
+    let mut expanded = args[..position].to_vec();
+    expanded.extend(words.into_iter().map(OsString::from));
+    expanded.extend_from_slice(&args[position + 1..]);
+    Ok(expanded)
+}
+
+fn invalid_subcommand(error: &clap::Error) -> Option<String> {
+    if error.kind() != ErrorKind::InvalidSubcommand {
+        return None;
+    }
+    match error.get(ContextKind::InvalidSubcommand) {
+        Some(ContextValue::String(name)) => Some(name.clone()),
+        _ => None,
+    }
+}
+
+// Parses `args` with `command`, expanding `[aliases]` from the configuration when the
+// command line names something that is not a built-in command. Aliases may refer to other
+// aliases; a cycle is reported instead of looping.
+pub fn parse_with_aliases(command: Command, mut args: Vec<OsString>) -> ArgMatches {
+    let mut aliases: Option<HashMap<String, String>> = None;
+    let mut expanded = Vec::new();
+    loop {
+        let error = match command.clone().try_get_matches_from(&args) {
+            Ok(matches) => return matches,
+            Err(error) => error,
+        };
+        let Some(name) = invalid_subcommand(&error) else { error.exit() };
+        // Only a command line that needs an alias pays for loading the configuration.
+        let aliases = aliases.get_or_insert_with(|| Config::load().map(|config| config.aliases).unwrap_or_default());
+        let Some(invocation) = aliases.get(&name) else { error.exit() };
+        if expanded.contains(&name) {
+            command.clone().error(ErrorKind::InvalidSubcommand, format!("alias '{}' refers to itself (via {})", name, expanded.join(" -> "))).exit();
+        }
+        match expand_alias(&args, &name, invocation) {
+            Ok(next) => args = next,
+            Err(e) => command.clone().error(ErrorKind::InvalidValue, e).exit(),
+        }
+        expanded.push(name);
+    }
+}
+
+pub async fn handle_alias(config: Config, args: AliasArgs) -> Result<()> {
+    match args.command {
+        AliasCommands::List => {
+            if config.aliases.is_empty() {
+                print_info("No aliases defined; add them under [aliases] in .OpenCode.toml, e.g. fix = \"run 'make the tests pass'\".");
+                return Ok(());
+            }
+            let builtins = Cli::command();
+            let mut names: Vec<&String> = config.aliases.keys().collect();
+            names.sort();
+            let width = names.iter().map(|name| name.len()).max().unwrap_or(0);
+            let lines: Vec<String> = names
+                .into_iter()
+                .map(|name| {
+                    let note = if builtins.find_subcommand(name).is_some() { "  (shadowed by the built-in command)" } else { "" };
+                    format!("{:width$}  {}{}", name, config.aliases[name], note, width = width)
+                })
+                .collect();
+            print_result(&lines.join("\n"));
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_alias_splits_quoted_invocation() {
+        let args: Vec<OsString> = ["opencode", "--lang", "fr", "fix", "--verify"].map(OsString::from).to_vec();
+        let expanded = expand_alias(&args, "fix", "run 'make the tests pass'").unwrap();
+        assert_eq!(expanded, ["opencode", "--lang", "fr", "run", "make the tests pass", "--verify"].map(OsString::from));
+        assert!(alias_words("broken", "run 'unterminated").is_err());
+        assert!(alias_words("empty", "  ").is_err());
+
+        let error = Cli::command().try_get_matches_from(["opencode", "fix"]).unwrap_err();
+        assert_eq!(invalid_subcommand(&error).as_deref(), Some("fix"));
+    }
+}
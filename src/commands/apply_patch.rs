@@ -0,0 +1,130 @@
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+use std::process::Output;
+use tokio::process::Command;
+
+use crate::cli::commands::ApplyPatchArgs;
+use crate::tui::{print_info, print_result, print_warning};
+
+// A file `git apply` could not patch cleanly, and why.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatchConflict {
+    pub path: String,
+    pub detail: String,
+}
+
+pub async fn git_apply(args: &[&str], patch: &str) -> Result<Output> {
+    Command::new("git")
+        .arg("apply")
+        .args(args)
+        .arg(patch)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("Failed to run git apply")
+}
+
+// Reads conflicts out of `git apply` stderr: `error: patch failed: <path>:<line>`,
+// `error: <path>: <reason>` and, after `--3way`, `U <path>` for files left with markers.
+pub fn parse_conflicts(stderr: &str) -> Vec<PatchConflict> {
+    let mut conflicts: Vec<PatchConflict> = Vec::new();
+    for line in stderr.lines() {
+        let (path, detail) = if let Some(rest) = line.strip_prefix("error: patch failed: ") {
+            match rest.rsplit_once(':') {
+                Some((path, at)) => (path.to_string(), format!("hunk at line {} does not match", at)),
+                None => (rest.to_string(), "hunk does not match".to_string()),
+            }
+        } else if let Some(rest) = line.strip_prefix("error: ") {
+            match rest.split_once(": ") {
+                Some((path, reason)) => (path.to_string(), reason.to_string()),
+                None => continue,
+            }
+        } else if let Some(path) = line.strip_prefix("U ") {
+            (path.to_string(), "merged with conflict markers".to_string())
+        } else {
+            continue;
+        };
+        match conflicts.iter_mut().find(|c| c.path == path) {
+            Some(existing) if existing.detail != detail && detail != "patch does not apply" => {
+                existing.detail = format!("{}; {}", existing.detail, detail)
+            }
+            Some(_) => {}
+            None => conflicts.push(PatchConflict { path, detail }),
+        }
+    }
+    conflicts
+}
+
+fn report_conflicts(conflicts: &[PatchConflict]) {
+    for conflict in conflicts {
+        print_warning(&format!("{}: {}", conflict.path, conflict.detail));
+    }
+}
+
+pub async fn handle_apply_patch(args: ApplyPatchArgs) -> Result<()> {
+    if !Path::new(&args.file).is_file() {
+        bail!("Patch file '{}' not found", args.file);
+    }
+    let stat = git_apply(&["--stat"], &args.file).await?;
+    if !stat.status.success() {
+        bail!("'{}' is not a patch git can read: {}", args.file, String::from_utf8_lossy(&stat.stderr).trim());
+    }
+    print_result(String::from_utf8_lossy(&stat.stdout).trim_end());
+
+    let check = git_apply(&["--check"], &args.file).await?;
+    if check.status.success() {
+        if args.check {
+            print_info("The patch applies cleanly.");
+            return Ok(());
+        }
+        let applied = git_apply(&[], &args.file).await?;
+        if !applied.status.success() {
+            bail!("git apply failed: {}", String::from_utf8_lossy(&applied.stderr).trim());
+        }
+        print_info("Applied the patch.");
+        return Ok(());
+    }
+
+    let conflicts = parse_conflicts(&String::from_utf8_lossy(&check.stderr));
+    print_warning(&format!("The patch does not apply cleanly to {} file(s):", conflicts.len()));
+    report_conflicts(&conflicts);
+    if args.check || !args.three_way {
+        bail!("Patch not applied; resolve the conflicts or retry with --three-way");
+    }
+
+    let merged = git_apply(&["--3way"], &args.file).await?;
+    let conflicts = parse_conflicts(&String::from_utf8_lossy(&merged.stderr));
+    if merged.status.success() {
+        print_info("Applied the patch with a three-way merge.");
+        return Ok(());
+    }
+    if conflicts.iter().all(|c| c.detail == "merged with conflict markers") && !conflicts.is_empty() {
+        print_warning("Applied the patch, leaving conflict markers in:");
+        report_conflicts(&conflicts);
+        return Ok(());
+    }
+    report_conflicts(&conflicts);
+    bail!("Three-way apply failed: {}", String::from_utf8_lossy(&merged.stderr).trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_conflicts_groups_by_file() {
+        let stderr = "error: patch failed: src/main.rs:12\n\
+                      error: src/main.rs: patch does not apply\n\
+                      error: notes.txt: already exists in working directory\n\
+                      Applied patch to 'lib.rs' with conflicts.\n\
+                      U lib.rs\n";
+        assert_eq!(
+            parse_conflicts(stderr),
+            vec![
+                PatchConflict { path: "src/main.rs".to_string(), detail: "hunk at line 12 does not match".to_string() },
+                PatchConflict { path: "notes.txt".to_string(), detail: "already exists in working directory".to_string() },
+                PatchConflict { path: "lib.rs".to_string(), detail: "merged with conflict markers".to_string() },
+            ]
+        );
+    }
+}
@@ -1,25 +1,46 @@
 use anyhow::{Context, Result}; // Removed anyhow
-use serde_json;
+use serde_json::{self, Value};
 
-use crate::api::client::ApiClient;
+use crate::api::chat_api::ChatApi;
 use crate::api::models::{ChatCompletionRequest, Message, Role, ToolChoice};
 use crate::config::Config;
+use crate::context::citations::{tool_sources, with_sources, CITATION_INSTRUCTION};
+use crate::context::retrieval::{render, retrieve};
 use crate::context::ContextManager;
+use crate::stream_json::emit_response;
 use crate::tools::execution::ToolExecutionEngine;
 use crate::tools::registry::ToolRegistry;
 use crate::tools::ToolError;
 use crate::tui::{print_error, print_result, print_warning, start_spinner}; // Removed print_info
 
 pub async fn handle_ask(
+    api_client: &dyn ChatApi,
     config: Config,
     mut context_manager: ContextManager,
     tool_registry: &ToolRegistry,
     tool_engine: &ToolExecutionEngine<'_>,
     prompt: String,
 ) -> Result<()> {
-    let api_client = ApiClient::new(config.clone())
-        .context("Failed to create API client (check API key configuration)")?;
     tracing::debug!("Processing 'ask' command with prompt: '{}'", prompt);
+    let citations = config.context.citations;
+    if citations {
+        context_manager.pin_system_message(CITATION_INSTRUCTION.to_string())?;
+    }
+    if config.context.retrieval.enabled {
+        match retrieve(&config, &std::env::current_dir()?, &prompt).await {
+            Ok(files) => {
+                if let Some(block) = render(&files, config.context.retrieval.max_chars) {
+                    let block = match tool_engine.screen("CodeSearchTool", Value::String(block)) {
+                        Value::String(block) => block,
+                        screened => serde_json::to_string_pretty(&screened)?,
+                    };
+                    context_manager.add_snippet("retrieval".to_string(), block)?;
+                    files.iter().for_each(|file| context_manager.track_source(&file.path));
+                }
+            }
+            Err(e) => tracing::warn!("Skipping retrieval: {:#}", e),
+        }
+    }
     let user_message = Message {
         role: Role::User,
         content: Some(prompt),
@@ -52,6 +73,7 @@ pub async fn handle_ask(
     match result {
         Ok(response) => {
             tracing::debug!("Received response from API: {:?}", response);
+            emit_response(&config.api.default_model, &response);
             if let Some(choice) = response.choices.first() {
                 context_manager.add_message(choice.message.clone())?;
                 tracing::debug!("Added assistant message (potentially with tool calls) to context.");
@@ -64,7 +86,7 @@ pub async fn handle_ask(
                         let tool_name = &tool_call.function.name;
                         let arguments_str = &tool_call.function.arguments;
 
-                        let arguments_value = match serde_json::from_str(arguments_str) {
+                        let arguments_value: serde_json::Value = match serde_json::from_str(arguments_str) {
                             Ok(val) => val,
                             Err(e) => {
                                 let error_result = Err(ToolError::InvalidArguments {
@@ -76,9 +98,12 @@ pub async fn handle_ask(
                             }
                         };
 
-                        let tool_result = tool_engine.execute_tool_call(tool_name, arguments_value).await;
+                        let tool_result = tool_engine.execute_tool_call(tool_name, arguments_value.clone()).await
+                            .map(|value| context_manager.dedupe_file_read(tool_name, &arguments_value, &tool_call_id, value));
+                        if let Ok(value) = &tool_result {
+                            tool_sources(tool_name, &arguments_value, value).iter().for_each(|source| context_manager.track_source(source));
+                        }
 
-                        print_result(&format!("Tool Call ID: {}, Result: {:?}", tool_call_id, tool_result));
                         tool_results_with_ids.push((tool_call_id, tool_result));
                     }
                 }
@@ -102,7 +127,9 @@ pub async fn handle_ask(
                 }
 
                 if let Some(content) = &choice.message.content {
-                     if !content.is_empty() {
+                     if !content.is_empty() && citations {
+                        print_result(&with_sources(content, context_manager.sources()));
+                     } else if !content.is_empty() {
                         print_result(content);
                      }
                 } else if choice.message.tool_calls.is_none() {
@@ -120,4 +147,36 @@ pub async fn handle_ask(
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::chat_api::MockChatApi;
+    use crate::api::models::{ChatCompletionResponse, Choice};
+    use crate::tools::execution::SecurityPolicy;
+
+    #[tokio::test]
+    async fn test_handle_ask_runs_against_mock_chat_api() {
+        let config = Config::default();
+        let api = MockChatApi::new().with_response(ChatCompletionResponse {
+            choices: vec![Choice {
+                message: Message { role: Role::Assistant, content: Some("42".to_string()), tool_calls: None, tool_call_id: None },
+                finish_reason: None,
+            }],
+            usage: None,
+        });
+        let context_manager = ContextManager::new(config.clone()).unwrap();
+        let registry = ToolRegistry::new(&config);
+        let engine = ToolExecutionEngine::new(&registry, SecurityPolicy::ConfirmWrites);
+
+        handle_ask(&api, config, context_manager, &registry, &engine, "What is the answer?".to_string())
+            .await
+            .unwrap();
+
+        let requests = api.requests();
+        assert_eq!(requests.len(), 1);
+        let last = requests[0].messages.last().unwrap();
+        assert_eq!(last.content.as_deref(), Some("What is the answer?"));
+    }
+}
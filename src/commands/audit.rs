@@ -0,0 +1,222 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::api::chat_api::ChatApi;
+use crate::api::models::ChatCompletionRequest;
+use crate::cli::commands::AuditDepsArgs;
+use crate::commands::prompts::command_messages;
+use crate::config::Config;
+use crate::streaming::handle_streamed_response;
+use crate::tools::package_lookup::{is_outdated, Ecosystem};
+use crate::tools::registry::ToolRegistry;
+use crate::tools::security_audit::{run_audit, Advisory};
+use crate::tui::{print_diff, print_error, print_info, print_result, prompt_confirmation, start_spinner};
+
+use super::deps::{apply_upgrades, parse_dependencies, DependencySpec};
+use super::summary::report_session_changes;
+
+// Picks the lowest fixed version at or above the installed one from requirements such as
+// `>=1.18.4, <1.19.0`.
+fn minimal_patched_version(advisory: &Advisory) -> Option<String> {
+    let installed = advisory.installed_version.as_deref().unwrap_or("0");
+    advisory
+        .patched_versions
+        .iter()
+        .filter_map(|req| {
+            let lower = req.split(',').next()?.trim();
+            lower.strip_prefix(">=").or_else(|| lower.strip_prefix('^')).map(|v| v.trim().to_string())
+        })
+        .filter(|version| !is_outdated(version, installed))
+        .min_by(|a, b| {
+            if is_outdated(a, b) {
+                std::cmp::Ordering::Less
+            } else if is_outdated(b, a) {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+}
+
+// Direct dependencies whose requirement allows a vulnerable version get bumped to the first
+// fixed release; everything else is left to `cargo update` and reported in the explanation.
+fn propose_manifest_edits(manifest: &str, advisories: &[Advisory]) -> Vec<(DependencySpec, String)> {
+    let dependencies = parse_dependencies(manifest);
+    let mut edits: Vec<(DependencySpec, String)> = Vec::new();
+    for advisory in advisories {
+        let Some(fixed) = minimal_patched_version(advisory) else { continue };
+        for spec in dependencies.iter().filter(|d| d.name == advisory.package) {
+            if !is_outdated(&spec.requirement, &fixed) {
+                continue;
+            }
+            match edits.iter_mut().find(|(existing, _)| existing == spec) {
+                Some((_, version)) if is_outdated(version, &fixed) => *version = fixed.clone(),
+                Some(_) => {}
+                None => edits.push((spec.clone(), fixed.clone())),
+            }
+        }
+    }
+    edits
+}
+
+fn format_advisories(advisories: &[Advisory]) -> String {
+    advisories
+        .iter()
+        .map(|a| {
+            format!(
+                "{} {}{}: {}{}",
+                a.id,
+                a.package,
+                a.installed_version.as_deref().map(|v| format!(" {}", v)).unwrap_or_default(),
+                a.title,
+                if a.fix_available { "" } else { " (no fix available)" }
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+async fn explain_advisories(
+    api_client: &dyn ChatApi,
+    config: &Config,
+    ecosystem: Ecosystem,
+    advisories: &[Advisory],
+    edits: &[(DependencySpec, String)],
+) -> Result<()> {
+    let proposed = if edits.is_empty() {
+        "No direct manifest edits were proposed.".to_string()
+    } else {
+        edits
+            .iter()
+            .map(|(spec, version)| format!("- bump {} from {} to {}", spec.name, spec.requirement, version))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let prompt = format!(
+        "A {} security audit reported these advisories (JSON):\n\n```json\n{}\n```\n\n\
+         Proposed manifest edits:\n{}\n\n\
+         For each advisory, briefly explain the vulnerability and whether it is likely to matter for a typical \
+         application, then give the concrete remediation: the manifest edit above, a lockfile update such as \
+         `cargo update -p <name>` / `npm audit fix` for transitive dependencies, or a workaround when no fix exists.",
+        match ecosystem {
+            Ecosystem::Crates => "cargo",
+            Ecosystem::Npm => "npm",
+            Ecosystem::Pypi => "pip",
+        },
+        serde_json::to_string_pretty(advisories)?,
+        proposed
+    );
+    let request = ChatCompletionRequest {
+        model: config.api.default_model.clone(),
+        messages: command_messages(config, "audit", None, prompt)?,
+        stream: Some(true),
+        temperature: None,
+        max_tokens: None,
+        tools: None,
+        tool_choice: None,
+        source_map: None,
+    };
+    match api_client.chat_completion_stream(request).await {
+        Ok(stream) => {
+            handle_streamed_response(stream).await?;
+        }
+        Err(e) => print_error(&format!("Error getting advisory explanation stream: {}", e)),
+    }
+    Ok(())
+}
+
+pub async fn handle_audit_deps(api_client: &dyn ChatApi, config: Config, tool_registry: &ToolRegistry, args: AuditDepsArgs) -> Result<()> {
+    let dir = PathBuf::from(&args.directory);
+    let spinner = start_spinner("Running security audit...");
+    let report = run_audit(&dir, None).await;
+    spinner.finish_and_clear();
+    let report = report?;
+
+    if report.advisories.is_empty() {
+        print_info("No known vulnerabilities found.");
+        return Ok(());
+    }
+    print_info(&format!("Found {} advisories:", report.advisories.len()));
+    print_result(&format_advisories(&report.advisories));
+
+    let manifest_path = dir.join("Cargo.toml");
+    let manifest = match report.ecosystem {
+        Ecosystem::Crates => Some(
+            fs::read_to_string(&manifest_path)
+                .with_context(|| format!("Failed to read manifest {}", manifest_path.display()))?,
+        ),
+        _ => None,
+    };
+    let edits = manifest
+        .as_deref()
+        .map(|m| propose_manifest_edits(m, &report.advisories))
+        .unwrap_or_default();
+
+    if !args.no_explain {
+        explain_advisories(api_client, &config, report.ecosystem, &report.advisories, &edits).await?;
+    }
+
+    let Some(manifest) = manifest.filter(|_| !edits.is_empty()) else {
+        return Ok(());
+    };
+    apply_manifest_edits(tool_registry, &manifest_path, &manifest, &edits, args.yes).await
+}
+
+async fn apply_manifest_edits(
+    tool_registry: &ToolRegistry,
+    manifest_path: &Path,
+    manifest: &str,
+    edits: &[(DependencySpec, String)],
+    assume_yes: bool,
+) -> Result<()> {
+    let updated = apply_upgrades(manifest, edits);
+    print_diff(manifest, &updated)?;
+    if !assume_yes && !prompt_confirmation(&format!("Apply the remediation edits to {}?", manifest_path.display()))? {
+        print_info("No changes made.");
+        return Ok(());
+    }
+    tool_registry.snapshots().record_before_change(manifest_path);
+    fs::write(manifest_path, &updated)
+        .with_context(|| format!("Failed to write manifest {}", manifest_path.display()))?;
+    print_info("Run `cargo update` to refresh the lockfile, then re-run the audit to confirm.");
+    report_session_changes(tool_registry.snapshots()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advisory(package: &str, installed: &str, patched: &[&str]) -> Advisory {
+        Advisory {
+            id: "RUSTSEC-0000-0000".to_string(),
+            package: package.to_string(),
+            installed_version: Some(installed.to_string()),
+            title: "test".to_string(),
+            severity: None,
+            url: None,
+            patched_versions: patched.iter().map(|p| p.to_string()).collect(),
+            fix_available: !patched.is_empty(),
+        }
+    }
+
+    #[test]
+    fn test_minimal_patched_version() {
+        let a = advisory("tokio", "1.20.0", &[">=1.18.4, <1.19.0", ">=1.23.1", ">=1.21.2, <1.22.0"]);
+        assert_eq!(minimal_patched_version(&a).as_deref(), Some("1.21.2"));
+        assert_eq!(minimal_patched_version(&advisory("x", "1.0.0", &[])), None);
+    }
+
+    #[test]
+    fn test_propose_manifest_edits_only_bumps_direct_vulnerable_deps() {
+        let manifest = "[dependencies]\ntokio = { version = \"1.20\", features = [\"full\"] }\nserde = \"1.0\"\n";
+        let advisories = vec![
+            advisory("tokio", "1.20.0", &[">=1.23.1"]),
+            advisory("smallvec", "1.6.0", &[">=1.6.1"]),
+        ];
+        let edits = propose_manifest_edits(manifest, &advisories);
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].0.name, "tokio");
+        assert_eq!(edits[0].1, "1.23.1");
+    }
+}
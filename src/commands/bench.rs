@@ -0,0 +1,367 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::time::Instant;
+
+use crate::api::chat_api::ChatApi;
+use crate::api::models::{ChatCompletionRequest, Message, Role, UsageStats};
+use crate::cli::commands::{BenchArgs, BenchFormat};
+use crate::tui::{print_error, print_info, print_result, start_spinner};
+
+const JUDGE_INSTRUCTIONS: &str = "You grade answers produced by another model. Score the answer from 0 to 10 \
+against the rubric. Reply with only a JSON object: {\"score\": <number>, \"reason\": \"<one sentence>\"}.";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BenchFile {
+    // Used when `--judge` is not given.
+    #[serde(default)]
+    pub judge_model: Option<String>,
+    // Applies to prompts without their own rubric.
+    #[serde(default)]
+    pub rubric: Option<String>,
+    // USD per million tokens, used when the API does not report a cost.
+    #[serde(default)]
+    pub pricing: HashMap<String, ModelPricing>,
+    pub prompts: Vec<BenchPrompt>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ModelPricing {
+    pub prompt: f64,
+    pub completion: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BenchPrompt {
+    pub id: String,
+    pub prompt: String,
+    #[serde(default)]
+    pub system: Option<String>,
+    #[serde(default)]
+    pub rubric: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BenchResult {
+    pub prompt_id: String,
+    pub model: String,
+    pub latency_ms: u128,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub cost: Option<f64>,
+    pub score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ModelSummary {
+    pub model: String,
+    pub runs: usize,
+    pub failures: usize,
+    pub avg_latency_ms: u128,
+    pub total_tokens: u64,
+    pub total_cost: Option<f64>,
+    pub avg_score: Option<f64>,
+}
+
+pub fn parse_bench_file(content: &str) -> Result<BenchFile> {
+    let file: BenchFile = toml::from_str(content).context("Failed to parse prompts file")?;
+    if file.prompts.is_empty() {
+        bail!("Prompts file defines no [[prompts]]");
+    }
+    Ok(file)
+}
+
+pub fn parse_models(models: &str) -> Vec<String> {
+    models
+        .split(',')
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+pub fn estimate_cost(usage: &UsageStats, pricing: Option<&ModelPricing>) -> Option<f64> {
+    usage.cost.or_else(|| {
+        pricing.map(|p| {
+            (f64::from(usage.prompt_tokens) * p.prompt + f64::from(usage.completion_tokens) * p.completion) / 1_000_000.0
+        })
+    })
+}
+
+// Accepts the bare JSON object or one wrapped in prose/code fences.
+pub fn parse_judge_score(reply: &str) -> Option<f64> {
+    let start = reply.find('{')?;
+    let end = reply.rfind('}')?;
+    let value: serde_json::Value = serde_json::from_str(reply.get(start..=end)?).ok()?;
+    value.get("score")?.as_f64().map(|s| s.clamp(0.0, 10.0))
+}
+
+pub fn summarize(models: &[String], results: &[BenchResult]) -> Vec<ModelSummary> {
+    models
+        .iter()
+        .map(|model| {
+            let runs: Vec<&BenchResult> = results.iter().filter(|r| &r.model == model).collect();
+            let ok: Vec<&&BenchResult> = runs.iter().filter(|r| r.error.is_none()).collect();
+            let costs: Vec<f64> = ok.iter().filter_map(|r| r.cost).collect();
+            let scores: Vec<f64> = ok.iter().filter_map(|r| r.score).collect();
+            ModelSummary {
+                model: model.clone(),
+                runs: runs.len(),
+                failures: runs.len() - ok.len(),
+                avg_latency_ms: if ok.is_empty() { 0 } else { ok.iter().map(|r| r.latency_ms).sum::<u128>() / ok.len() as u128 },
+                total_tokens: ok.iter().map(|r| u64::from(r.prompt_tokens) + u64::from(r.completion_tokens)).sum(),
+                total_cost: (!costs.is_empty()).then(|| costs.iter().sum()),
+                avg_score: (!scores.is_empty()).then(|| scores.iter().sum::<f64>() / scores.len() as f64),
+            }
+        })
+        .collect()
+}
+
+pub fn render_table(results: &[BenchResult], summaries: &[ModelSummary]) -> String {
+    let cost = |c: Option<f64>| c.map(|c| format!("${:.4}", c)).unwrap_or_else(|| "-".to_string());
+    let score = |s: Option<f64>| s.map(|s| format!("{:.1}", s)).unwrap_or_else(|| "-".to_string());
+
+    let mut rows = vec![vec![
+        "prompt".to_string(),
+        "model".to_string(),
+        "latency".to_string(),
+        "tokens in/out".to_string(),
+        "cost".to_string(),
+        "score".to_string(),
+    ]];
+    for r in results {
+        rows.push(vec![
+            r.prompt_id.clone(),
+            r.model.clone(),
+            match &r.error {
+                Some(_) => "error".to_string(),
+                None => format!("{} ms", r.latency_ms),
+            },
+            format!("{}/{}", r.prompt_tokens, r.completion_tokens),
+            cost(r.cost),
+            score(r.score),
+        ]);
+    }
+    let mut out = align(&rows);
+
+    let mut summary_rows = vec![vec![
+        "model".to_string(),
+        "runs".to_string(),
+        "failed".to_string(),
+        "avg latency".to_string(),
+        "tokens".to_string(),
+        "total cost".to_string(),
+        "avg score".to_string(),
+    ]];
+    for s in summaries {
+        summary_rows.push(vec![
+            s.model.clone(),
+            s.runs.to_string(),
+            s.failures.to_string(),
+            format!("{} ms", s.avg_latency_ms),
+            s.total_tokens.to_string(),
+            cost(s.total_cost),
+            score(s.avg_score),
+        ]);
+    }
+    out.push('\n');
+    out.push_str(&align(&summary_rows));
+    out
+}
+
+pub fn align(rows: &[Vec<String>]) -> String {
+    let columns = rows.first().map_or(0, Vec::len);
+    let widths: Vec<usize> = (0..columns)
+        .map(|i| rows.iter().map(|row| row[i].chars().count()).max().unwrap_or(0))
+        .collect();
+    let mut out = String::new();
+    for row in rows {
+        let line: Vec<String> = row.iter().zip(&widths).map(|(cell, w)| format!("{:<width$}", cell, width = *w)).collect();
+        out.push_str(line.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn request(model: &str, system: Option<&str>, prompt: &str) -> ChatCompletionRequest {
+    let mut messages = Vec::new();
+    if let Some(system) = system {
+        messages.push(Message { role: Role::System, content: Some(system.to_string()), tool_calls: None, tool_call_id: None });
+    }
+    messages.push(Message { role: Role::User, content: Some(prompt.to_string()), tool_calls: None, tool_call_id: None });
+    ChatCompletionRequest {
+        model: model.to_string(),
+        messages,
+        stream: None,
+        temperature: Some(0.0),
+        max_tokens: None,
+        tools: None,
+        tool_choice: None,
+        source_map: None,
+    }
+}
+
+async fn judge(api_client: &dyn ChatApi, judge_model: &str, rubric: &str, prompt: &str, answer: &str) -> Result<f64> {
+    let input = format!("Rubric:\n{}\n\nPrompt:\n{}\n\nAnswer:\n{}", rubric, prompt, answer);
+    let response = api_client.chat_completion(request(judge_model, Some(JUDGE_INSTRUCTIONS), &input)).await?;
+    let reply = response.choices.into_iter().next().and_then(|c| c.message.content).unwrap_or_default();
+    parse_judge_score(&reply).ok_or_else(|| anyhow!("Judge reply had no score: {}", reply))
+}
+
+pub async fn handle_bench(api_client: &dyn ChatApi, args: BenchArgs) -> Result<()> {
+    let content = fs::read_to_string(&args.prompts)
+        .with_context(|| format!("Failed to read prompts file {}", args.prompts))?;
+    let bench = parse_bench_file(&content)?;
+    let models = parse_models(&args.models);
+    if models.is_empty() {
+        bail!("--models must list at least one model");
+    }
+    let judge_model = args.judge.clone().or_else(|| bench.judge_model.clone());
+
+    let mut results = Vec::new();
+    for prompt in &bench.prompts {
+        for model in &models {
+            let spinner = start_spinner(&format!("Running '{}' on {}...", prompt.id, model));
+            let started = Instant::now();
+            let response = api_client.chat_completion(request(model, prompt.system.as_deref(), &prompt.prompt)).await;
+            let latency_ms = started.elapsed().as_millis();
+            spinner.finish_and_clear();
+
+            let mut result = BenchResult {
+                prompt_id: prompt.id.clone(),
+                model: model.clone(),
+                latency_ms,
+                prompt_tokens: 0,
+                completion_tokens: 0,
+                cost: None,
+                score: None,
+                error: None,
+            };
+            match response {
+                Ok(response) => {
+                    let usage = response.usage.clone().unwrap_or_default();
+                    result.prompt_tokens = usage.prompt_tokens;
+                    result.completion_tokens = usage.completion_tokens;
+                    result.cost = estimate_cost(&usage, bench.pricing.get(model));
+                    let answer = response.choices.into_iter().next().and_then(|c| c.message.content).unwrap_or_default();
+                    let rubric = prompt.rubric.as_deref().or(bench.rubric.as_deref());
+                    if let (Some(judge_model), Some(rubric)) = (&judge_model, rubric) {
+                        match judge(api_client, judge_model, rubric, &prompt.prompt, &answer).await {
+                            Ok(score) => result.score = Some(score),
+                            Err(e) => print_error(&format!("Grading '{}' on {} failed: {}", prompt.id, model, e)),
+                        }
+                    }
+                }
+                Err(e) => {
+                    print_error(&format!("'{}' on {} failed: {}", prompt.id, model, e));
+                    result.error = Some(e.to_string());
+                }
+            }
+            results.push(result);
+        }
+    }
+
+    let summaries = summarize(&models, &results);
+    let report = match args.format {
+        BenchFormat::Table => render_table(&results, &summaries),
+        BenchFormat::Json => serde_json::to_string_pretty(&serde_json::json!({
+            "results": results,
+            "summary": summaries,
+        }))?,
+    };
+    match &args.output {
+        Some(path) => {
+            fs::write(path, &report).with_context(|| format!("Failed to write report to {}", path))?;
+            print_info(&format!("Benchmark report written to {}", path));
+        }
+        None => print_result(&report),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(model: &str, latency_ms: u128, cost: Option<f64>, score: Option<f64>, error: Option<&str>) -> BenchResult {
+        BenchResult {
+            prompt_id: "p1".to_string(),
+            model: model.to_string(),
+            latency_ms,
+            prompt_tokens: 10,
+            completion_tokens: 5,
+            cost,
+            score,
+            error: error.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_parse_bench_file() {
+        let bench = parse_bench_file(
+            r#"
+judge_model = "judge/model"
+rubric = "Correct and concise."
+
+[pricing."a/model"]
+prompt = 1.0
+completion = 2.0
+
+[[prompts]]
+id = "fizzbuzz"
+prompt = "Write fizzbuzz in Rust."
+"#,
+        )
+        .unwrap();
+        assert_eq!(bench.judge_model.as_deref(), Some("judge/model"));
+        assert_eq!(bench.prompts[0].id, "fizzbuzz");
+        assert_eq!(bench.pricing["a/model"], ModelPricing { prompt: 1.0, completion: 2.0 });
+        assert!(parse_bench_file("prompts = []").is_err());
+    }
+
+    #[test]
+    fn test_estimate_cost_prefers_reported_cost() {
+        let usage = UsageStats { prompt_tokens: 1_000_000, completion_tokens: 500_000, total_tokens: 1_500_000, cost: None };
+        let pricing = ModelPricing { prompt: 1.0, completion: 2.0 };
+        assert_eq!(estimate_cost(&usage, Some(&pricing)), Some(2.0));
+        assert_eq!(estimate_cost(&usage, None), None);
+        let reported = UsageStats { cost: Some(0.5), ..usage };
+        assert_eq!(estimate_cost(&reported, Some(&pricing)), Some(0.5));
+    }
+
+    #[test]
+    fn test_parse_judge_score() {
+        assert_eq!(parse_judge_score("{\"score\": 7, \"reason\": \"ok\"}"), Some(7.0));
+        assert_eq!(parse_judge_score("```json\n{\"score\": 12.5}\n```"), Some(10.0));
+        assert_eq!(parse_judge_score("no score"), None);
+    }
+
+    #[test]
+    fn test_summarize_excludes_failed_runs() {
+        let models = parse_models("a, b,");
+        assert_eq!(models, vec!["a", "b"]);
+        let results = vec![
+            result("a", 100, Some(0.01), Some(8.0), None),
+            result("a", 300, Some(0.02), Some(6.0), None),
+            result("b", 50, None, None, Some("timeout")),
+        ];
+        let summaries = summarize(&models, &results);
+        assert_eq!(summaries[0].avg_latency_ms, 200);
+        assert_eq!(summaries[0].total_tokens, 30);
+        assert_eq!(summaries[0].avg_score, Some(7.0));
+        assert!((summaries[0].total_cost.unwrap() - 0.03).abs() < 1e-9);
+        assert_eq!(summaries[1].failures, 1);
+        assert_eq!(summaries[1].total_cost, None);
+
+        let table = render_table(&results, &summaries);
+        let lines: Vec<Vec<&str>> = table.lines().map(|l| l.split_whitespace().collect()).collect();
+        assert!(lines.contains(&vec!["p1", "b", "error", "10/5", "-", "-"]));
+        assert!(lines.contains(&vec!["a", "2", "0", "200", "ms", "30", "$0.0300", "7.0"]));
+    }
+}
@@ -1,7 +1,7 @@
 use anyhow::{Context, Result}; // Removed anyhow
 use keyring::Entry;
 
-use crate::config::{Config, DEFAULT_KEYRING_ENTRY_NAME, KEYRING_SERVICE_NAME};
+use crate::config::{Config, KEYRING_SERVICE_NAME};
 use crate::cli::commands::ConfigureArgs;
 use crate::tui::{print_info};
 
@@ -12,8 +12,8 @@ pub async fn handle_configure(config: Config, args: ConfigureArgs) -> Result<()>
     if let Some(ref key_entry_opt) = args.set_api_key {
         let entry_name = key_entry_opt
             .as_deref()
-            .unwrap_or(DEFAULT_KEYRING_ENTRY_NAME);
-        set_api_key(entry_name)?;
+            .unwrap_or(config.keyring_entry_for(config.api.provider));
+        set_api_key(config.api.provider.display_name(), entry_name)?;
     }
 
     if let Some(model_id) = args.set_default_model {
@@ -43,10 +43,11 @@ pub async fn handle_configure(config: Config, args: ConfigureArgs) -> Result<()>
     Ok(())
 }
 
-fn set_api_key(entry_name: &str) -> Result<()> {
-    print_info(
-        "Please enter your OpenRouter API key (it will not be displayed):"
-    );
+fn set_api_key(provider: &str, entry_name: &str) -> Result<()> {
+    print_info(&format!(
+        "Please enter your {} API key (it will not be displayed):",
+        provider
+    ));
     let api_key = rpassword::prompt_password("API Key: ")
         .context("Failed to read API key from prompt")?;
 
@@ -0,0 +1,362 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::api::chat_api::ChatApi;
+use crate::api::models::ChatCompletionRequest;
+use crate::cli::commands::{DepsArgs, DepsCommands, DepsUpgradeArgs};
+use crate::commands::prompts::command_messages;
+use crate::config::Config;
+use crate::tools::package_lookup::{is_major_bump, is_outdated, Ecosystem, PackageRegistry};
+use crate::tools::rate_limit::NetworkLimiter;
+use crate::tools::registry::ToolRegistry;
+use crate::tools::url_fetch::UrlFetcher;
+use crate::tui::{print_diff, print_error, print_info, print_result, print_warning, prompt_confirmation, start_spinner};
+
+use super::summary::report_session_changes;
+
+const DEPENDENCY_SECTIONS: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+const CHANGELOG_FILES: &[&str] = &["CHANGELOG.md", "CHANGES.md", "RELEASES.md"];
+const MAX_CHANGELOG_CHARS: usize = 12_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DependencySpec {
+    pub name: String,
+    pub requirement: String,
+    pub section: String,
+    line: usize,
+}
+
+#[derive(Debug, Clone)]
+struct ProposedUpgrade {
+    spec: DependencySpec,
+    latest: String,
+    major: bool,
+    repository: Option<String>,
+}
+
+// Finds the quoted version requirement on a dependency line, either `name = "1.0"` or
+// `name = { version = "1.0", ... }`. Returns the byte range of the requirement.
+fn version_span(line: &str) -> Option<(usize, usize)> {
+    let (_, value) = line.split_once('=')?;
+    let value_start = line.len() - value.len();
+    let trimmed = value.trim_start();
+    let offset = value_start + (value.len() - trimmed.len());
+    let search_from = if trimmed.starts_with('"') {
+        offset
+    } else if trimmed.starts_with('{') {
+        let key = trimmed.find("version")?;
+        let after_key = &trimmed[key + "version".len()..];
+        if !after_key.trim_start().starts_with('=') {
+            return None;
+        }
+        offset + key
+    } else {
+        return None;
+    };
+    let start = search_from + line[search_from..].find('"')? + 1;
+    let end = start + line[start..].find('"')?;
+    Some((start, end))
+}
+
+// Lists registry dependencies declared inline in the manifest's dependency tables.
+// Path, git and workspace-inherited dependencies are skipped.
+pub fn parse_dependencies(manifest: &str) -> Vec<DependencySpec> {
+    let mut specs = Vec::new();
+    let mut section: Option<String> = None;
+    for (index, raw_line) in manifest.lines().enumerate() {
+        let line = raw_line.trim();
+        if line.starts_with('[') {
+            let name = line.trim_matches(|c| c == '[' || c == ']').trim();
+            let table = name.rsplit('.').next().unwrap_or(name);
+            section = DEPENDENCY_SECTIONS.contains(&table).then(|| table.to_string());
+            continue;
+        }
+        let Some(section) = &section else { continue };
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((name, value)) = line.split_once('=') else { continue };
+        let value = value.trim();
+        if value.starts_with('{')
+            && ["path", "git", "workspace"]
+                .iter()
+                .any(|k| value.split([',', '{']).any(|field| field.trim().starts_with(k)))
+        {
+            continue;
+        }
+        if let Some((start, end)) = version_span(raw_line) {
+            specs.push(DependencySpec {
+                name: name.trim().trim_matches('"').to_string(),
+                requirement: raw_line[start..end].to_string(),
+                section: section.clone(),
+                line: index,
+            });
+        }
+    }
+    specs
+}
+
+// Keeps any requirement operator (`^`, `~`, `=`) and replaces the version itself.
+fn bumped_requirement(requirement: &str, latest: &str) -> String {
+    let operator: String = requirement.chars().take_while(|c| !c.is_ascii_digit()).collect();
+    format!("{}{}", operator, latest)
+}
+
+// Rewrites the requirement of each given dependency in place, preserving formatting.
+pub fn apply_upgrades(manifest: &str, upgrades: &[(DependencySpec, String)]) -> String {
+    let mut lines: Vec<String> = manifest.lines().map(String::from).collect();
+    for (spec, latest) in upgrades {
+        let Some(line) = lines.get_mut(spec.line) else { continue };
+        if let Some((start, end)) = version_span(line) {
+            line.replace_range(start..end, &bumped_requirement(&spec.requirement, latest));
+        }
+    }
+    let mut updated = lines.join("\n");
+    if manifest.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated
+}
+
+fn github_repo(repository: &str) -> Option<(String, String)> {
+    let rest = repository.trim_end_matches('/').trim_end_matches(".git");
+    let rest = rest.split("github.com/").nth(1)?;
+    let mut parts = rest.split('/');
+    Some((parts.next()?.to_string(), parts.next()?.to_string()))
+}
+
+async fn fetch_changelog(fetcher: &UrlFetcher, limiter: &NetworkLimiter, repository: &str) -> Option<String> {
+    let (owner, repo) = github_repo(repository)?;
+    for file in CHANGELOG_FILES {
+        let url = format!("https://raw.githubusercontent.com/{}/{}/HEAD/{}", owner, repo, file);
+        let _permit = limiter.acquire("raw.githubusercontent.com").await;
+        match fetcher.fetch(&url).await {
+            Ok(page) if page.status == 200 => return Some(page.content),
+            Ok(_) => continue,
+            Err(e) => {
+                tracing::debug!("Failed to fetch changelog {}: {}", url, e);
+                continue;
+            }
+        }
+    }
+    None
+}
+
+async fn summarize_changelog(
+    api_client: &dyn ChatApi,
+    config: &Config,
+    upgrade: &ProposedUpgrade,
+    changelog: &str,
+) -> Result<String> {
+    let excerpt: String = changelog.chars().take(MAX_CHANGELOG_CHARS).collect();
+    let prompt = format!(
+        "The Rust crate `{}` is being upgraded from `{}` to `{}`. Using the changelog below, list the breaking \
+         changes and migration steps that affect users between those versions as short bullet points. \
+         If the changelog does not cover that range, say so.\n\n{}",
+        upgrade.spec.name, upgrade.spec.requirement, upgrade.latest, excerpt
+    );
+    let request = ChatCompletionRequest {
+        model: config.api.default_model.clone(),
+        messages: command_messages(config, "deps", None, prompt)?,
+        stream: None,
+        temperature: None,
+        max_tokens: None,
+        tools: None,
+        tool_choice: None,
+        source_map: None,
+    };
+    let response = api_client.chat_completion(request).await?;
+    response
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|choice| choice.message.content)
+        .context("Changelog summary response was empty")
+}
+
+async fn run_test_suite(manifest_path: &Path) -> Result<bool> {
+    print_info("Running `cargo test` to validate the upgrade...");
+    let status = tokio::process::Command::new("cargo")
+        .arg("test")
+        .arg("--manifest-path")
+        .arg(manifest_path)
+        .kill_on_drop(true)
+        .status()
+        .await
+        .context("Failed to run cargo test")?;
+    Ok(status.success())
+}
+
+async fn handle_upgrade(api_client: &dyn ChatApi, config: Config, tool_registry: &ToolRegistry, args: DepsUpgradeArgs) -> Result<()> {
+    let manifest_path = Path::new(&args.manifest);
+    let manifest = fs::read_to_string(manifest_path)
+        .with_context(|| format!("Failed to read manifest {}", manifest_path.display()))?;
+    let dependencies = parse_dependencies(&manifest);
+    if dependencies.is_empty() {
+        print_info("No registry dependencies found in the manifest.");
+        return Ok(());
+    }
+
+    let registry = PackageRegistry::new(&config.network);
+    let limiter = NetworkLimiter::new(&config.network);
+    let spinner = start_spinner(&format!("Checking {} dependencies on crates.io...", dependencies.len()));
+    let mut upgrades = Vec::new();
+    for spec in dependencies {
+        let _permit = limiter.acquire("crates.io").await;
+        match registry.lookup(Ecosystem::Crates, &spec.name).await {
+            Ok(info) if is_outdated(&spec.requirement, &info.latest_version) => {
+                let major = is_major_bump(&spec.requirement, &info.latest_version);
+                if major && !args.major {
+                    tracing::debug!("Skipping major upgrade of {} to {}", spec.name, info.latest_version);
+                    continue;
+                }
+                upgrades.push(ProposedUpgrade {
+                    spec,
+                    latest: info.latest_version,
+                    major,
+                    repository: info.repository,
+                });
+            }
+            Ok(_) => {}
+            Err(e) => print_warning(&format!("Could not look up '{}': {}", spec.name, e)),
+        }
+    }
+    spinner.finish_and_clear();
+
+    if upgrades.is_empty() {
+        print_info("All dependencies are up to date.");
+        return Ok(());
+    }
+
+    let report = upgrades
+        .iter()
+        .map(|u| {
+            format!(
+                "{:<24} {:>12} -> {:<12} [{}]{}",
+                u.spec.name,
+                u.spec.requirement,
+                u.latest,
+                u.spec.section,
+                if u.major { "  (breaking)" } else { "" }
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    print_info("Outdated dependencies:");
+    print_result(&report);
+
+    if !args.no_changelog && upgrades.iter().any(|u| u.major) {
+        let fetcher = UrlFetcher::new(&config.network);
+        match api_client.ready().await {
+            Ok(()) => {
+                for upgrade in upgrades.iter().filter(|u| u.major) {
+                    let Some(repository) = &upgrade.repository else { continue };
+                    let Some(changelog) = fetch_changelog(&fetcher, &limiter, repository).await else {
+                        print_warning(&format!("No changelog found for {}.", upgrade.spec.name));
+                        continue;
+                    };
+                    match summarize_changelog(api_client, &config, upgrade, &changelog).await {
+                        Ok(summary) => {
+                            print_info(&format!("Breaking changes in {} {}:", upgrade.spec.name, upgrade.latest));
+                            print_result(&summary);
+                        }
+                        Err(e) => print_warning(&format!("Could not summarize changelog for {}: {}", upgrade.spec.name, e)),
+                    }
+                }
+            }
+            Err(e) => print_warning(&format!("Skipping changelog summaries: {:#}", e)),
+        }
+    }
+
+    let edits: Vec<(DependencySpec, String)> = upgrades.iter().map(|u| (u.spec.clone(), u.latest.clone())).collect();
+    let updated = apply_upgrades(&manifest, &edits);
+    print_diff(&manifest, &updated)?;
+    if !args.yes && !prompt_confirmation(&format!("Apply these edits to {}?", manifest_path.display()))? {
+        print_info("No changes made.");
+        return Ok(());
+    }
+
+    tool_registry.snapshots().record_before_change(manifest_path);
+    fs::write(manifest_path, &updated)
+        .with_context(|| format!("Failed to write manifest {}", manifest_path.display()))?;
+    print_info(&format!("Updated {} dependencies.", edits.len()));
+
+    if args.test && !run_test_suite(manifest_path).await? {
+        print_error("The test suite failed after the upgrade.");
+        if prompt_confirmation("Revert the manifest changes?")? {
+            fs::write(manifest_path, &manifest)
+                .with_context(|| format!("Failed to restore manifest {}", manifest_path.display()))?;
+            print_info("Manifest restored.");
+            return Ok(());
+        }
+    }
+
+    report_session_changes(tool_registry.snapshots()).await
+}
+
+pub async fn handle_deps(api_client: &dyn ChatApi, config: Config, tool_registry: &ToolRegistry, args: DepsArgs) -> Result<()> {
+    match args.command {
+        DepsCommands::Upgrade(upgrade_args) => handle_upgrade(api_client, config, tool_registry, upgrade_args).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"[package]
+name = "demo"
+version = "0.1.0"
+
+[dependencies]
+anyhow = "1.0.80"
+serde = { version = "^1.0.100", features = ["derive"] }
+local = { path = "../local" }
+# comment = "1.0"
+
+[dev-dependencies]
+tempfile = "3.10"
+
+[target.'cfg(unix)'.build-dependencies]
+cc = "~1.0"
+"#;
+
+    #[test]
+    fn test_parse_dependencies() {
+        let deps = parse_dependencies(MANIFEST);
+        let names: Vec<(&str, &str, &str)> = deps
+            .iter()
+            .map(|d| (d.name.as_str(), d.requirement.as_str(), d.section.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("anyhow", "1.0.80", "dependencies"),
+                ("serde", "^1.0.100", "dependencies"),
+                ("tempfile", "3.10", "dev-dependencies"),
+                ("cc", "~1.0", "build-dependencies"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_upgrades_preserves_formatting() {
+        let deps = parse_dependencies(MANIFEST);
+        let edits = vec![(deps[1].clone(), "1.0.219".to_string()), (deps[3].clone(), "1.2.0".to_string())];
+        let updated = apply_upgrades(MANIFEST, &edits);
+        assert!(updated.contains(r#"serde = { version = "^1.0.219", features = ["derive"] }"#));
+        assert!(updated.contains(r#"cc = "~1.2.0""#));
+        assert!(updated.contains(r#"anyhow = "1.0.80""#));
+        assert!(updated.ends_with('\n'));
+    }
+
+    #[test]
+    fn test_github_repo() {
+        assert_eq!(
+            github_repo("https://github.com/serde-rs/serde.git"),
+            Some(("serde-rs".to_string(), "serde".to_string()))
+        );
+        assert_eq!(github_repo("https://gitlab.com/a/b"), None);
+    }
+}
@@ -0,0 +1,184 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use crate::api::chat_api::ChatApi;
+use crate::api::models::ChatCompletionRequest;
+use crate::cli::commands::{DiagramArgs, GraphFormat};
+use crate::commands::prompts::command_messages;
+use crate::config::Config;
+use crate::parsing::dependencies::{module_graph, render_graph_dot, render_graph_mermaid, ModuleGraph};
+use crate::parsing::outline::{outline_directory, render_ascii_tree};
+use crate::tui::{print_info, print_warning, start_spinner};
+
+fn format_name(format: GraphFormat) -> &'static str {
+    match format {
+        GraphFormat::Mermaid => "mermaid",
+        GraphFormat::Dot => "dot",
+    }
+}
+
+fn default_output(format: GraphFormat) -> PathBuf {
+    PathBuf::from(match format {
+        GraphFormat::Mermaid => "architecture.mmd",
+        GraphFormat::Dot => "architecture.dot",
+    })
+}
+
+// Takes the diagram source out of a model response, accepting fenced or bare output.
+// Returns None unless it starts like a diagram of the requested format.
+pub fn extract_diagram(response: &str, format: GraphFormat) -> Option<String> {
+    let body = match response.find("```") {
+        Some(start) => {
+            let after = &response[start + 3..];
+            let after = after.split_once('\n').map(|(_, rest)| rest).unwrap_or(after);
+            after.split("```").next().unwrap_or(after)
+        }
+        None => response,
+    }
+    .trim();
+    let valid = match format {
+        GraphFormat::Mermaid => ["graph", "flowchart", "classDiagram"].iter().any(|p| body.starts_with(p)),
+        GraphFormat::Dot => body.starts_with("digraph") || body.starts_with("strict digraph") || body.starts_with("graph"),
+    };
+    valid.then(|| body.to_string())
+}
+
+async fn model_diagram(
+    api_client: &dyn ChatApi,
+    config: &Config,
+    scope: &str,
+    graph: &ModuleGraph,
+    outline: &str,
+    format: GraphFormat,
+) -> Result<Option<String>> {
+    let edges = graph
+        .edges
+        .iter()
+        .map(|(from, to)| format!("{} -> {}", from, to))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let prompt = format!(
+        "Produce an architecture diagram in {} syntax for the Rust code under `{}`.\n\n\
+         Module import relationships (A -> B means A uses B):\n```\n{}\n```\n\n\
+         Files and their definitions:\n```\n{}\n```\n\n\
+         Group related modules into subgraphs/clusters, label the main types on each module, and keep only \
+         edges that are in the list above. Respond with only the diagram source in a single code block.",
+        format_name(format),
+        scope,
+        edges,
+        outline
+    );
+    let request = ChatCompletionRequest {
+        model: config.api.big_model.clone(),
+        messages: command_messages(config, "diagram", None, prompt)?,
+        stream: None,
+        temperature: Some(0.0),
+        max_tokens: None,
+        tools: None,
+        tool_choice: None,
+        source_map: None,
+    };
+    let response = api_client.chat_completion(request).await?;
+    let content = response.choices.into_iter().next().and_then(|c| c.message.content).unwrap_or_default();
+    Ok(extract_diagram(&content, format))
+}
+
+async fn render_svg(source: &Path, format: GraphFormat) -> Result<()> {
+    let svg = source.with_extension("svg");
+    let mut command = match format {
+        GraphFormat::Mermaid => {
+            let mut c = Command::new("mmdc");
+            c.arg("-i").arg(source).arg("-o").arg(&svg);
+            c
+        }
+        GraphFormat::Dot => {
+            let mut c = Command::new("dot");
+            c.arg("-Tsvg").arg(source).arg("-o").arg(&svg);
+            c
+        }
+    };
+    let program = command.as_std().get_program().to_string_lossy().to_string();
+    match command.kill_on_drop(true).output().await {
+        Ok(output) if output.status.success() => {
+            print_info(&format!("Rendered {}", svg.display()));
+            Ok(())
+        }
+        Ok(output) => anyhow::bail!("{} failed: {}", program, String::from_utf8_lossy(&output.stderr).trim()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            print_warning(&format!("`{}` is not installed; skipping SVG rendering.", program));
+            Ok(())
+        }
+        Err(e) => Err(e).with_context(|| format!("Failed to run {}", program)),
+    }
+}
+
+pub async fn handle_diagram(api_client: &dyn ChatApi, config: Config, args: DiagramArgs) -> Result<()> {
+    let scope = Path::new(&args.scope);
+    let graph = module_graph(scope).with_context(|| format!("Failed to analyze modules under '{}'", args.scope))?;
+    if graph.modules.is_empty() {
+        anyhow::bail!("No Rust source files found under '{}'", args.scope);
+    }
+    let fallback = match args.format {
+        GraphFormat::Mermaid => render_graph_mermaid(&graph),
+        GraphFormat::Dot => render_graph_dot(&graph),
+    };
+
+    let diagram = if args.no_model {
+        fallback
+    } else {
+        let outline = render_ascii_tree(&args.scope, &outline_directory(scope)?);
+        let spinner = start_spinner("Generating diagram...");
+        let generated = model_diagram(api_client, &config, &args.scope, &graph, &outline, args.format).await;
+        spinner.finish_and_clear();
+        match generated {
+            Ok(Some(diagram)) => diagram,
+            Ok(None) => {
+                print_warning("The model did not return a usable diagram; writing the derived module graph instead.");
+                fallback
+            }
+            Err(e) => {
+                print_warning(&format!("Diagram generation failed ({}); writing the derived module graph instead.", e));
+                fallback
+            }
+        }
+    };
+
+    let output = args.output.map(PathBuf::from).unwrap_or_else(|| default_output(args.format));
+    fs::write(&output, format!("{}\n", diagram)).with_context(|| format!("Failed to write {}", output.display()))?;
+    print_info(&format!(
+        "Wrote {} diagram ({} modules, {} edges) to {}",
+        format_name(args.format),
+        graph.modules.len(),
+        graph.edges.len(),
+        output.display()
+    ));
+    if args.render {
+        render_svg(&output, args.format).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::chat_api::MockChatApi;
+    use crate::api::models::{ChatCompletionResponse, Choice, Message, Role};
+
+    #[tokio::test]
+    async fn test_model_diagram_uses_the_given_client() {
+        let reply = "```mermaid\ngraph TD\n    tools --> config\n```";
+        let api = MockChatApi::new().with_response(ChatCompletionResponse {
+            choices: vec![Choice {
+                message: Message { role: Role::Assistant, content: Some(reply.to_string()), tool_calls: None, tool_call_id: None },
+                finish_reason: None,
+            }],
+            usage: None,
+        });
+        let graph = ModuleGraph { modules: vec!["config".into(), "tools".into()], edges: vec![("tools".into(), "config".into())] };
+        let diagram = model_diagram(&api, &Config::default(), "src", &graph, "", GraphFormat::Mermaid).await.unwrap();
+        assert_eq!(diagram.as_deref(), Some("graph TD\n    tools --> config"));
+        assert_eq!(api.requests().len(), 1);
+    }
+}
@@ -1,23 +1,31 @@
 use anyhow::{Context, Result}; // Removed anyhow
 use std::fs;
+use std::path::Path;
 use serde_json;
 
-use crate::api::client::ApiClient;
-use crate::api::models::{ChatCompletionRequest, Message, Role, ToolChoice};
+use crate::api::chat_api::ChatApi;
+use crate::api::models::{ChatCompletionRequest, ToolChoice};
 use crate::cli::commands::EditArgs;
+use crate::commands::prompts::command_messages;
+use crate::commands::summary::report_session_changes;
 use crate::config::Config;
+use crate::context::style::style_summary_for;
+use crate::parsing::type_context::type_context_for;
 use crate::tools::execution::ToolExecutionEngine;
 use crate::tools::registry::ToolRegistry;
-use crate::tui::{print_error, print_info, print_result, print_warning, start_spinner};
+use crate::tui::{print_error, print_info, print_warning, start_spinner};
+
+const EDIT_PREAMBLE: &str = "Apply the following edit instruction to the provided file content. \
+You MUST call the appropriate file modification tool (e.g., 'file_write', 'apply_diff') \
+to apply the changes. Output ONLY the tool call.";
 
 pub async fn handle_edit(
+    api_client: &dyn ChatApi,
     config: Config,
     tool_registry: &ToolRegistry,
     tool_engine: &ToolExecutionEngine<'_>,
     args: EditArgs,
 ) -> Result<()> {
-    let api_client = ApiClient::new(config.clone())
-        .context("Failed to create API client (check API key configuration)")?;
     tracing::debug!(
         "Processing 'edit' command for file: '{}' with instruction: '{}'",
         args.file,
@@ -36,29 +44,29 @@ pub async fn handle_edit(
         }
     };
 
-    let prompt = format!(
-        "Apply the following edit instruction to the provided file content. \
-        You MUST call the appropriate file modification tool (e.g., 'file_write', 'apply_diff') \
-        to apply the changes. Output ONLY the tool call.\n\n\
-        Instruction: {}\n\n\
+    let mut prompt = format!(
+        "Instruction: {}\n\n\
         File Path: {}\n\n\
         File Content:\n```\n{}\n```",
         args.instruction, args.file, file_content
     );
+    if let Some(types) = type_context_for(Path::new(&args.file), &file_content, config.context.type_context_tokens) {
+        prompt.push_str("\n\n");
+        prompt.push_str(&types);
+    }
+    if let Some(style_summary) = style_summary_for(Some(Path::new(&args.file))) {
+        prompt.push_str("\n\n");
+        prompt.push_str(&style_summary);
+    }
 
-    let user_message = Message {
-        role: Role::User,
-        content: Some(prompt),
-        tool_calls: None,
-        tool_call_id: None,
-    };
+    let messages = command_messages(&config, "edit", Some(EDIT_PREAMBLE), prompt)?;
 
     let tool_definitions = tool_registry.get_tool_definitions()
         .context("Failed to get tool definitions from registry")?;
 
     let request = ChatCompletionRequest {
         model: config.api.edit_model.clone(),
-        messages: vec![user_message],
+        messages,
         stream: None,
         temperature: None,
         max_tokens: None,
@@ -82,8 +90,9 @@ pub async fn handle_edit(
                         let arguments_str = &tool_call.function.arguments;
                         match serde_json::from_str(arguments_str) {
                             Ok(arguments_value) => {
-                                let tool_result = tool_engine.execute_tool_call(tool_name, arguments_value).await;
-                                print_result(&format!("Tool '{}' execution result: {:?}", tool_name, tool_result));
+                                if let Err(e) = tool_engine.execute_tool_call(tool_name, arguments_value).await {
+                                    tracing::error!("Tool '{}' failed: {}", tool_name, e);
+                                }
                             }
                             Err(e) => {
                                 print_error(&format!("Failed to parse tool arguments: {}", e));
@@ -110,5 +119,6 @@ pub async fn handle_edit(
             print_error(&format!("Error requesting edit from AI: {}", e));
         }
     }
+    report_session_changes(tool_registry.snapshots()).await?;
     Ok(())
 }
\ No newline at end of file
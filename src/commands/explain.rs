@@ -1,48 +1,113 @@
-use anyhow::{Context, Result}; // Removed anyhow
+use anyhow::{bail, Context, Result}; // Removed anyhow
 use std::fs;
+use std::path::Path;
 
-use crate::api::client::ApiClient;
-use crate::api::models::{ChatCompletionRequest, Message, Role};
-use crate::cli::commands::ExplainArgs;
+use crate::api::chat_api::ChatApi;
+use crate::api::models::ChatCompletionRequest;
+use crate::cli::commands::{DiagramFormat, ExplainArgs};
+use crate::commands::prompts::command_messages;
 use crate::config::Config;
-use crate::parsing::find_symbol_context;
+use crate::parsing::{find_symbol_context, find_symbol_lines};
+use crate::parsing::outline::{outline_directory, render_ascii_tree, render_mermaid};
+use crate::parsing::type_context::type_context_for;
 use crate::streaming::handle_streamed_response;
-use crate::tui::{print_error};
+use crate::tui::{print_error, print_info, print_result, print_warning};
+
+const MAX_DIRECTORY_OUTLINE_CHARS: usize = 40_000;
+const MAX_HISTORY_COMMITS: usize = 5;
+const MAX_BLAME_RANGES: usize = 40;
+const MAX_COMMIT_MESSAGE_CHARS: usize = 800;
+// Each commit starts with a record separator: `<hash> <author> <date>`, then the message.
+const HISTORY_LOG_FORMAT: &str = "--format=%x1e%h %an %ad%n%B";
+
+async fn explain_directory(api_client: &dyn ChatApi, config: &Config, dir: &str, diagram: DiagramFormat) -> Result<()> {
+    let outlines = outline_directory(std::path::Path::new(dir))
+        .with_context(|| format!("Failed to outline directory '{}'", dir))?;
+    if outlines.is_empty() {
+        print_error(&format!("No supported source files found in '{}'", dir));
+        return Ok(());
+    }
+    let tree = render_ascii_tree(dir, &outlines);
+    let mut outline_text = tree.clone();
+    if outline_text.len() > MAX_DIRECTORY_OUTLINE_CHARS {
+        outline_text = outline_text.chars().take(MAX_DIRECTORY_OUTLINE_CHARS).collect();
+        outline_text.push_str("\n[... truncated]");
+    }
+
+    let prompt = format!(
+        "Below is the structure of the module/directory `{}`: every source file with the definitions it contains.\n\n\
+         ```\n{}\n```\n\n\
+         Give an architectural explanation of this module: its overall purpose, the responsibility of each file, \
+         the key types and how they relate, and the main flow of control or data between them. \
+         Point out the entry points someone new should read first.",
+        dir, outline_text
+    );
+    let request = ChatCompletionRequest {
+        model: config.api.big_model.clone(),
+        messages: command_messages(config, "explain", None, prompt)?,
+        stream: None,
+        temperature: None,
+        max_tokens: None,
+        tools: None,
+        tool_choice: None,
+        source_map: None,
+    };
+
+    match api_client.chat_completion_stream(request).await {
+        Ok(stream) => {
+            handle_streamed_response(stream).await?;
+        }
+        Err(e) => {
+            print_error(&format!("Error getting module explanation stream: {}", e));
+        }
+    }
+
+    print_info("Module structure:");
+    match diagram {
+        DiagramFormat::Ascii => print_result(&tree),
+        DiagramFormat::Mermaid => print_result(&format!("```mermaid\n{}\n```", render_mermaid(dir, &outlines))),
+    }
+    Ok(())
+}
 
 pub async fn handle_explain(
+    api_client: &dyn ChatApi,
     config: Config,
     args: ExplainArgs,
 ) -> Result<()> {
-    let api_client = ApiClient::new(config.clone())
-        .context("Failed to create API client (check API key configuration)")?;
+    if let Some(dir) = &args.dir {
+        tracing::debug!("Processing 'explain' command for directory: '{}'", dir);
+        return explain_directory(api_client, &config, dir, args.diagram).await;
+    }
+    let file = args.file.clone().context("Either --file or --dir is required")?;
     tracing::debug!(
         "Processing 'explain' command for file: '{}', lines: {:?}, symbol: {:?}",
-        args.file,
+        file,
         args.lines,
         args.symbol
     );
 
     let code_context = if let Some(symbol_name) = &args.symbol {
-        match find_symbol_context(&args.file, symbol_name) {
+        match find_symbol_context(&file, symbol_name) {
             Ok(context) => {
-                tracing::debug!("Successfully found context for symbol '{}' in file '{}'", symbol_name, args.file);
+                tracing::debug!("Successfully found context for symbol '{}' in file '{}'", symbol_name, file);
                 context
             }
             Err(e) => {
                 print_error(&format!("Error finding symbol '{}': {}", symbol_name, e));
-                tracing::error!("Error finding symbol '{}' in {}: {}", symbol_name, args.file, e);
+                tracing::error!("Error finding symbol '{}' in {}: {}", symbol_name, file, e);
                 return Err(anyhow::anyhow!("Failed to find symbol context: {}", e));
             }
         }
     } else {
-        let full_content = match fs::read_to_string(&args.file) {
+        let full_content = match fs::read_to_string(&file) {
             Ok(content) => {
-                tracing::debug!("Successfully read file: {}", args.file);
+                tracing::debug!("Successfully read file: {}", file);
                 content
             }
             Err(e) => {
-                print_error(&format!("Could not read file '{}': {}", args.file, e));
-                tracing::error!("Failed to read file '{}': {}", args.file, e);
+                print_error(&format!("Could not read file '{}': {}", file, e));
+                tracing::error!("Failed to read file '{}': {}", file, e);
                 return Err(anyhow::anyhow!("Failed to read file: {}", e));
             }
         };
@@ -54,7 +119,7 @@ pub async fn handle_explain(
                         Ok(extracted) => extracted,
                         Err(e) => {
                             print_error(&format!("Error extracting lines: {}", e));
-                            tracing::error!("Failed extracting lines '{}' from {}: {}", lines_str, args.file, e);
+                            tracing::error!("Failed extracting lines '{}' from {}: {}", lines_str, file, e);
                             return Err(anyhow::anyhow!("Failed to extract lines: {}", e));
                         }
                     }
@@ -70,21 +135,30 @@ pub async fn handle_explain(
         }
     };
 
-    let prompt = format!(
+    let mut prompt = format!(
         "Explain the following code. Identify the programming language if possible:\n\n```\n{}\n```",
         code_context
     );
+    if let Some(types) = type_context_for(Path::new(&file), &code_context, config.context.type_context_tokens) {
+        prompt.push_str("\n\n");
+        prompt.push_str(&types);
+    }
+    if args.with_history {
+        match history_context(&file, history_range(&file, &args)).await {
+            Ok(history) => {
+                prompt.push_str("\n\n");
+                prompt.push_str(&history);
+                prompt.push_str("\n\nUse this history to explain why the code is the way it is where it helps.");
+            }
+            Err(e) => print_warning(&format!("Could not read git history for '{}': {:#}", file, e)),
+        }
+    }
 
-    let user_message = Message {
-        role: Role::User,
-        content: Some(prompt),
-        tool_calls: None,
-        tool_call_id: None,
-    };
+    let messages = command_messages(&config, "explain", None, prompt)?;
 
     let request = ChatCompletionRequest {
         model: config.api.big_model.clone(),
-        messages: vec![user_message],
+        messages,
         stream: None,
         temperature: None,
         max_tokens: None,
@@ -107,7 +181,97 @@ pub async fn handle_explain(
     Ok(())
 }
 
-fn parse_lines(lines_str: &str) -> Result<(usize, Option<usize>), String> {
+// The line range `--lines` or `--symbol` selects, for history lookups; None for the whole file.
+fn history_range(file: &str, args: &ExplainArgs) -> Option<(usize, usize)> {
+    if let Some(lines) = &args.lines {
+        return parse_lines(lines).ok().map(|(start, end)| (start, end.unwrap_or(start)));
+    }
+    let symbol = args.symbol.as_ref()?;
+    let source = fs::read_to_string(file).ok()?;
+    find_symbol_lines(Path::new(file), &source, symbol).ok().flatten()
+}
+
+async fn git_output(args: &[String]) -> Result<String> {
+    let output = tokio::process::Command::new("git").args(args).kill_on_drop(true).output().await.context("Failed to run git")?;
+    if !output.status.success() {
+        bail!("git {} failed: {}", args[0], String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// Consecutive lines last changed by the same commit, from `git blame --date=short` output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlameRange {
+    pub start: usize,
+    pub end: usize,
+    pub commit: String,
+    pub author: String,
+    pub date: String,
+}
+
+pub fn blame_ranges(output: &str) -> Vec<BlameRange> {
+    let mut ranges: Vec<BlameRange> = Vec::new();
+    for line in output.lines() {
+        let (Some(open), Some(close)) = (line.find('('), line.find(')')) else {
+            continue;
+        };
+        let commit = line[..open].trim().trim_start_matches('^').to_string();
+        let fields: Vec<&str> = line[open + 1..close].split_whitespace().collect();
+        let [author @ .., date, number] = fields.as_slice() else {
+            continue;
+        };
+        let Ok(number) = number.parse::<usize>() else {
+            continue;
+        };
+        match ranges.last_mut() {
+            Some(last) if last.commit == commit && last.end + 1 == number => last.end = number,
+            _ => ranges.push(BlameRange { start: number, end: number, commit, author: author.join(" "), date: date.to_string() }),
+        }
+    }
+    ranges
+}
+
+// Blame and the messages of recent commits touching `range` of `file`, for `--with-history`.
+async fn history_context(file: &str, range: Option<(usize, usize)>) -> Result<String> {
+    let mut blame_args: Vec<String> = vec!["blame".into(), "--date=short".into()];
+    let mut log_args: Vec<String> = vec!["log".into(), "--date=short".into(), HISTORY_LOG_FORMAT.into()];
+    log_args.push(format!("--max-count={}", MAX_HISTORY_COMMITS));
+    let scope = match range {
+        Some((start, end)) => {
+            blame_args.push(format!("-L{},{}", start, end));
+            log_args.extend(["-s".into(), format!("-L{},{}:{}", start, end, file)]);
+            format!("lines {}-{} of {}", start, end, file)
+        }
+        None => {
+            log_args.extend(["--follow".into(), "--".into(), file.into()]);
+            file.to_string()
+        }
+    };
+    blame_args.extend(["--".into(), file.into()]);
+
+    let ranges = blame_ranges(&git_output(&blame_args).await?);
+    let mut context = format!("Git history for {}.\n\nLast changed (git blame):\n", scope);
+    for range in ranges.iter().take(MAX_BLAME_RANGES) {
+        context.push_str(&format!(
+            "- lines {}-{}: {} by {} on {}\n",
+            range.start, range.end, range.commit, range.author, range.date
+        ));
+    }
+    if ranges.len() > MAX_BLAME_RANGES {
+        context.push_str(&format!("- ... {} more ranges\n", ranges.len() - MAX_BLAME_RANGES));
+    }
+    context.push_str("\nRecent commits touching this code, newest first:\n");
+    for commit in git_output(&log_args).await?.split('\u{1e}').map(str::trim).filter(|c| !c.is_empty()) {
+        let mut message: String = commit.chars().take(MAX_COMMIT_MESSAGE_CHARS).collect();
+        if message.len() < commit.len() {
+            message.push_str(" [...]");
+        }
+        context.push_str(&format!("\n{}\n", message));
+    }
+    Ok(context)
+}
+
+pub fn parse_lines(lines_str: &str) -> Result<(usize, Option<usize>), String> {
     if lines_str.contains('-') {
         let parts: Vec<&str> = lines_str.splitn(2, '-').collect();
         if parts.len() == 2 {
@@ -133,7 +297,7 @@ fn parse_lines(lines_str: &str) -> Result<(usize, Option<usize>), String> {
     }
 }
 
-fn extract_lines(content: &str, start_line: usize, end_line: Option<usize>) -> Result<String, String> {
+pub fn extract_lines(content: &str, start_line: usize, end_line: Option<usize>) -> Result<String, String> {
     let lines: Vec<&str> = content.lines().collect();
     let total_lines = lines.len();
 
@@ -163,4 +327,22 @@ fn extract_lines(content: &str, start_line: usize, end_line: Option<usize>) -> R
     }
 
     Ok(lines[start_index..end_index].join("\n"))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blame_ranges_groups_consecutive_lines() {
+        let output = "1f7e3041 (Ana Lima 2024-05-01 10) fn run() {\n1f7e3041 (Ana Lima 2024-05-01 11)     step();\n^2446ae6 (Bo 2023-01-09 12) }\n1f7e3041 (Ana Lima 2024-05-01 13) \n";
+        assert_eq!(
+            blame_ranges(output),
+            vec![
+                BlameRange { start: 10, end: 11, commit: "1f7e3041".into(), author: "Ana Lima".into(), date: "2024-05-01".into() },
+                BlameRange { start: 12, end: 12, commit: "2446ae6".into(), author: "Bo".into(), date: "2023-01-09".into() },
+                BlameRange { start: 13, end: 13, commit: "1f7e3041".into(), author: "Ana Lima".into(), date: "2024-05-01".into() },
+            ]
+        );
+    }
+}
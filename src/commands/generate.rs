@@ -1,25 +1,160 @@
 use anyhow::{Context, Result};
 use std::fs;
+use std::path::Path;
 
-use crate::api::client::ApiClient;
+use crate::api::chat_api::ChatApi;
 use crate::api::models::{ChatCompletionRequest, Message, Role};
 use crate::cli::commands::GenerateArgs;
+use crate::commands::prompts::command_messages;
 use crate::config::Config;
-use crate::streaming::handle_streamed_response;
-use crate::tui::{print_error, print_warning};
+use crate::context::style::style_summary_for;
+use crate::tools::write_checks::{SyntaxCheck, WriteCheck};
+use crate::streaming::{collect_streamed_response, tee, tee_end};
+use crate::tui::{print_error, print_result, print_warning, start_spinner};
+
+// Answers without usable code are asked for again, up to this many attempts in total.
+const MAX_GENERATE_ATTEMPTS: usize = 3;
+
+// One name per language, so a `py` file and a ```python block match.
+pub fn canonical_language(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    match name.as_str() {
+        "rs" => "rust",
+        "py" => "python",
+        "js" | "jsx" | "mjs" => "javascript",
+        "ts" | "tsx" => "typescript",
+        "sh" | "bash" | "zsh" => "shell",
+        "yml" => "yaml",
+        "rb" => "ruby",
+        "md" => "markdown",
+        "c++" | "cc" | "hpp" => "cpp",
+        "cs" => "csharp",
+        other => other,
+    }
+    .to_string()
+}
+
+// The file extension SyntaxCheck parses `language` by, where it has a parser.
+fn checked_extension(language: &str) -> Option<&'static str> {
+    match language {
+        "rust" => Some("rs"),
+        "json" => Some("json"),
+        "toml" => Some("toml"),
+        _ => None,
+    }
+}
+
+// The fenced code blocks in `reply` as (language tag, code).
+fn code_blocks(reply: &str) -> Vec<(String, String)> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+    for line in reply.lines() {
+        let trimmed = line.trim_start();
+        match (&mut current, trimmed.strip_prefix("```")) {
+            (None, Some(tag)) => current = Some((tag.trim().to_string(), Vec::new())),
+            (Some(_), Some(rest)) if rest.trim().is_empty() => {
+                let (tag, lines) = current.take().unwrap_or_default();
+                blocks.push((tag, lines.join("\n")));
+            }
+            (Some((_, lines)), _) => lines.push(line),
+            (None, None) => {}
+        }
+    }
+    blocks
+}
+
+// Why `reply` is not usable generated code, if it is not: it needs a fenced code block, in
+// `language` when one is expected, and the block has to parse where a parser is available.
+pub fn validate_generated(reply: &str, language: Option<&str>) -> Result<(), String> {
+    let blocks = code_blocks(reply);
+    if blocks.is_empty() {
+        return Err("it contains no fenced code block".to_string());
+    }
+    let expected = language.map(canonical_language);
+    let candidates: Vec<&(String, String)> = blocks
+        .iter()
+        .filter(|(tag, _)| match &expected {
+            Some(expected) => tag.is_empty() || canonical_language(tag) == *expected,
+            None => true,
+        })
+        .collect();
+    let Some(first) = candidates.first() else {
+        return Err(format!("it has no {} code block", expected.unwrap_or_default()));
+    };
+    let block_language = expected.unwrap_or_else(|| canonical_language(&first.0));
+    let Some(extension) = checked_extension(&block_language) else { return Ok(()) };
+    for (_, code) in candidates {
+        SyntaxCheck.check(Path::new(&format!("generated.{}", extension)), None, code)?;
+    }
+    Ok(())
+}
+
+// The answer kept by `generate_reply`.
+pub struct Generated {
+    pub reply: String,
+    // The language asked for: the context file's, when there is one.
+    pub language: Option<String>,
+    // Why the last attempt was still not usable code, when none was.
+    pub problem: Option<String>,
+}
+
+impl Generated {
+    // The code of the first block in the language asked for.
+    pub fn code(&self) -> Option<String> {
+        let expected = self.language.as_deref().map(canonical_language);
+        code_blocks(&self.reply)
+            .into_iter()
+            .find(|(tag, _)| match &expected {
+                Some(expected) => tag.is_empty() || canonical_language(tag) == *expected,
+                None => true,
+            })
+            .map(|(_, code)| code)
+    }
+}
 
 pub async fn handle_generate(
+    api_client: &dyn ChatApi,
     config: Config,
     args: GenerateArgs,
 ) -> Result<()> {
-    let api_client = ApiClient::new(config.clone())
-        .context("Failed to create API client (check API key configuration)")?;
+    let generated = match generate_reply(api_client, &config, args).await {
+        Ok(generated) => generated,
+        Err(e) => {
+            print_error(&format!("{:#}", e));
+            return Ok(());
+        }
+    };
+    if let Some(problem) = &generated.problem {
+        print_warning(&format!("The answer still could not be validated ({}).", problem));
+    }
+    tee(&generated.reply);
+    tee_end(&generated.reply);
+    print_result(&generated.reply);
+    Ok(())
+}
+
+// Asks for the code `args` describes. Each attempt is collected without being shown and
+// checked with `validate_generated`; unusable answers are sent back, and the last attempt is
+// kept when none passes.
+pub async fn generate_reply(
+    api_client: &dyn ChatApi,
+    config: &Config,
+    args: GenerateArgs,
+) -> Result<Generated> {
     tracing::debug!(
         "Processing 'generate' command with description: '{}', file: {:?}",
         args.description,
         args.file
     );
 
+    let style_summary = style_summary_for(args.file.as_deref().map(Path::new));
+    // The language asked for: the context file's, when there is one.
+    let language = args
+        .file
+        .as_deref()
+        .and_then(|file| Path::new(file).extension())
+        .map(|extension| canonical_language(&extension.to_string_lossy()));
+
     let file_content = match args.file {
         Some(path) => match fs::read_to_string(&path) {
             Ok(content) => {
@@ -38,7 +173,7 @@ pub async fn handle_generate(
         None => None,
     };
 
-    let prompt = if let Some(content) = file_content {
+    let mut prompt = if let Some(content) = file_content {
         format!(
             "Generate code based on the following description:\n{}\n\nUse this file content as context:\n```\n{}\n```",
             args.description, content
@@ -49,35 +184,81 @@ pub async fn handle_generate(
             args.description
         )
     };
+    if let Some(style_summary) = style_summary {
+        prompt.push_str("\n\n");
+        prompt.push_str(&style_summary);
+    }
 
-    let user_message = Message {
-        role: Role::User,
-        content: Some(prompt),
-        tool_calls: None,
-        tool_call_id: None,
-    };
+    let mut messages = command_messages(config, "generate", None, prompt)?;
 
-    let request = ChatCompletionRequest {
-        model: config.api.big_model.clone(),
-        messages: vec![user_message],
-        stream: Some(true),
-        temperature: None,
-        max_tokens: None,
-        tools: None,
-        tool_choice: None,
-        source_map: None,
-    };
+    for attempt in 1..=MAX_GENERATE_ATTEMPTS {
+        let request = ChatCompletionRequest {
+            model: config.api.big_model.clone(),
+            messages: messages.clone(),
+            stream: Some(true),
+            temperature: None,
+            max_tokens: None,
+            tools: None,
+            tool_choice: None,
+            source_map: None,
+        };
+        tracing::debug!("Sending generation request to API (streaming, attempt {}): {:?}", attempt, request);
 
-    tracing::debug!("Sending generation request to API (streaming): {:?}", request);
+        let spinner = start_spinner("Generating code...");
+        let reply = match api_client.chat_completion_stream(request).await {
+            Ok(stream) => collect_streamed_response(stream).await,
+            Err(e) => Err(e),
+        };
+        spinner.finish_and_clear();
+        let reply = reply.context("Error generating code stream")?;
 
-    match api_client.chat_completion_stream(request).await {
-        Ok(stream) => {
-            tracing::debug!("Received generation stream from API.");
-            handle_streamed_response(stream).await?;
-        }
-        Err(e) => {
-            print_error(&format!("Error generating code stream: {}", e));
+        match validate_generated(&reply, language.as_deref()) {
+            Ok(()) => return Ok(Generated { reply, language, problem: None }),
+            Err(problem) if attempt == MAX_GENERATE_ATTEMPTS => return Ok(Generated { reply, language, problem: Some(problem) }),
+            Err(problem) => {
+                print_warning(&format!("Asking again: the answer was not usable code ({}).", problem));
+                let fence = language.clone().unwrap_or_default();
+                messages.push(Message { role: Role::Assistant, content: Some(reply), tool_calls: None, tool_call_id: None });
+                messages.push(Message {
+                    role: Role::User,
+                    content: Some(format!(
+                        "That answer could not be used: {}. Reply with the complete code in a fenced ```{} block.",
+                        problem, fence
+                    )),
+                    tool_calls: None,
+                    tool_call_id: None,
+                });
+            }
         }
     }
-    Ok(())
-}
\ No newline at end of file
+    unreachable!("the last attempt always returns")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::chat_api::MockChatApi;
+    use crate::api::models::ChatCompletionChunk;
+
+    fn reply(content: &str) -> Vec<ChatCompletionChunk> {
+        vec![serde_json::from_value(serde_json::json!({ "choices": [{ "delta": { "content": content } }] })).unwrap()]
+    }
+
+    #[test]
+    fn test_validate_generated_requires_parseable_code() {
+        assert_eq!(validate_generated("Sure, here is how you could do it.", None), Err("it contains no fenced code block".to_string()));
+        assert!(validate_generated("```rust\nfn add(a: i32, b: i32) -> i32 { a + b }\n```", Some("rs")).is_ok());
+        assert!(validate_generated("```rust\nfn add(a: i32 -> i32 {\n```", None).unwrap_err().contains("does not parse"));
+        assert_eq!(validate_generated("```python\nprint(1)\n```", Some("rs")), Err("it has no rust code block".to_string()));
+        assert!(validate_generated("```py\nprint(1)\n```", Some("python")).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_unusable_answers_are_asked_for_again() {
+        let api = MockChatApi::new().with_stream(reply("I would add the numbers.")).with_stream(reply("```rust\nfn add() {}\n```"));
+        let args = GenerateArgs { description: "an add function".to_string(), file: None };
+        let generated = generate_reply(&api, &Config::default(), args).await.unwrap();
+        assert_eq!((generated.reply.as_str(), generated.problem), ("```rust\nfn add() {}\n```", None));
+        assert_eq!(api.requests().len(), 2);
+    }
+}
@@ -0,0 +1,112 @@
+use anyhow::{bail, Context, Result};
+use clap::{Command, CommandFactory};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cli::commands::{Cli, HelpArgs};
+use crate::cli::examples::EXAMPLES;
+use crate::commands::pipeline::render_template;
+use crate::tui::{print_info, print_result};
+
+// The examples of `command` with `bin` filled in, as (description, command line) pairs.
+pub fn examples(command: &str, bin: &str) -> Result<Vec<(String, String)>> {
+    let vars = HashMap::from([("bin".to_string(), bin.to_string())]);
+    let Some((_, examples)) = EXAMPLES.iter().find(|(name, _)| *name == command) else {
+        return Ok(Vec::new());
+    };
+    examples
+        .iter()
+        .map(|(description, line)| Ok((description.to_string(), render_template(line, &vars, &HashMap::new())?)))
+        .collect()
+}
+
+fn format_examples(examples: &[(String, String)]) -> String {
+    let lines: Vec<String> = examples.iter().map(|(description, line)| format!("  # {}\n  {}", description, line)).collect();
+    format!("Examples:\n{}", lines.join("\n\n"))
+}
+
+// The CLI definition with each subcommand's examples appended to its `--help`.
+pub fn command_with_examples(bin: &str) -> Command {
+    let mut command = Cli::command();
+    for (name, _) in EXAMPLES {
+        let Ok(examples) = examples(name, bin) else { continue };
+        if command.find_subcommand(name).is_some() {
+            command = command.mut_subcommand(*name, |sub| sub.after_long_help(format_examples(&examples)));
+        }
+    }
+    command
+}
+
+// The man pages build.rs renders from the CLI definition with clap_mangen, as (file name, roff).
+const MAN_PAGES: &[(&str, &str)] = include!(concat!(env!("OUT_DIR"), "/man_pages.rs"));
+
+// Writes `opencode.1` and one page per subcommand, e.g. `opencode-shell-explain.1`, to `dir`.
+pub fn write_man_pages(dir: &Path) -> Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    MAN_PAGES
+        .iter()
+        .map(|(name, page)| {
+            let file = dir.join(name);
+            fs::write(&file, page).with_context(|| format!("Failed to write {}", file.display()))?;
+            Ok(file)
+        })
+        .collect()
+}
+
+pub async fn handle_help(args: HelpArgs, bin: &str) -> Result<()> {
+    if let Some(dir) = &args.man {
+        let pages = write_man_pages(dir)?;
+        print_info(&format!("Wrote {} man pages to {}.", pages.len(), dir.display()));
+        return Ok(());
+    }
+    let mut command = command_with_examples(bin).bin_name(bin);
+    command.build();
+    let mut target = &mut command;
+    for name in &args.command {
+        target = match target.find_subcommand_mut(name) {
+            Some(sub) => sub,
+            None => bail!("Unknown command '{}'; run `{} help` to list commands", args.command.join(" "), bin),
+        };
+    }
+    if !args.examples {
+        print_result(&target.render_long_help().to_string());
+        return Ok(());
+    }
+    let Some(name) = args.command.first() else {
+        bail!("Name a command to show its examples, e.g. `{} help explain --examples`", bin);
+    };
+    let examples = examples(name, bin)?;
+    if examples.is_empty() {
+        print_info(&format!("No examples for '{}' yet.", name));
+    } else {
+        print_result(&format_examples(&examples));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_command_has_examples_and_a_man_page() {
+        let cli = Cli::command();
+        for sub in cli.get_subcommands().filter(|c| c.get_name() != "lsp-bridge") {
+            assert!(!examples(sub.get_name(), "opencode").unwrap().is_empty(), "no examples for {}", sub.get_name());
+        }
+        assert_eq!(
+            examples("todos", "cargo opencode").unwrap()[1],
+            ("List TODO comments without calling the model".to_string(), "cargo opencode todos --no-triage".to_string())
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let pages = write_man_pages(dir.path()).unwrap();
+        assert!(pages.iter().any(|p| p.ends_with("opencode-shell-explain.1")));
+        let page = fs::read_to_string(dir.path().join("opencode-logs-tail.1")).unwrap();
+        assert!(page.contains(".TH opencode-logs-tail 1"), "{}", page);
+        assert!(page.contains(".TP\n\\fB\\-n\\fR, \\fB\\-\\-lines\\fR \\fI<LINES>\\fR [default: 50]"), "{}", page);
+        let explain = fs::read_to_string(dir.path().join("opencode-explain.1")).unwrap();
+        assert!(explain.contains(".SH EXAMPLES\n.PP\nExplain one function\n.RS\n.nf\nopencode explain \\-\\-file src/app.rs \\-\\-symbol run\n"));
+    }
+}
@@ -0,0 +1,135 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cli::commands::{IndexArgs, IndexCommands};
+use crate::commands::bench::align;
+use crate::commands::onboard::onboarding_files;
+use crate::config::Config;
+use crate::context::embeddings::{embedding_provider, EmbeddingProvider};
+use crate::context::environment::format_utc;
+use crate::context::file_index::{FileIndex, IndexStore};
+use crate::tui::{print_info, print_result, print_warning, start_spinner};
+
+// Changed files listed by name after an update; longer lists are only counted.
+const MAX_LISTED_CHANGES: usize = 20;
+// Files are embedded from their path and beginning.
+const MAX_EMBEDDED_CHARS: usize = 4000;
+
+fn project_root(path: Option<&str>) -> Result<PathBuf> {
+    fs::canonicalize(path.unwrap_or(".")).with_context(|| format!("Cannot index {}", path.unwrap_or(".")))
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+// Embeds the indexed files that have no embedding yet, `batch_size` per request. Returns how
+// many were embedded; a failed batch stops the run but keeps what was embedded before it.
+async fn embed_files(index: &mut FileIndex, root: &Path, provider: &dyn EmbeddingProvider, batch_size: usize) -> Result<usize> {
+    index.set_embedding_model(&provider.id());
+    let pending = index.unembedded();
+    let mut embedded = 0;
+    for batch in pending.chunks(batch_size.max(1)) {
+        let spinner = start_spinner(&format!("Embedding files with {} ({}/{})...", provider.id(), embedded, pending.len()));
+        let texts: Vec<String> = batch
+            .iter()
+            .map(|key| {
+                let content = fs::read_to_string(root.join(key)).unwrap_or_default();
+                format!("{}\n{}", key, content.chars().take(MAX_EMBEDDED_CHARS).collect::<String>())
+            })
+            .collect();
+        let vectors = provider.embed(&texts).await;
+        spinner.finish_and_clear();
+        for (key, vector) in batch.iter().zip(vectors?) {
+            if let Some(entry) = index.files.get_mut(key) {
+                entry.embedding = Some(vector);
+            }
+        }
+        embedded += batch.len();
+    }
+    Ok(embedded)
+}
+
+pub async fn handle_index(config: Config, args: IndexArgs) -> Result<()> {
+    match args.command {
+        IndexCommands::Update { path } => {
+            let root = project_root(path.as_deref())?;
+            let store = IndexStore::for_project(&root);
+            let mut index = store.load()?;
+            let spinner = start_spinner("Updating the file index...");
+            let files = onboarding_files(&root);
+            let changes = index.update(&files, unix_now());
+            spinner.finish_and_clear();
+            let embedded = match embedding_provider(&config) {
+                Ok(provider) => embed_files(&mut index, &root, provider.as_ref(), config.index.batch_size).await,
+                Err(e) => Err(e),
+            };
+            store.save(&index)?;
+            print_info(&format!(
+                "Indexed {} files: {} added, {} changed, {} removed, {} unchanged ({} read).",
+                index.files.len(),
+                changes.added.len(),
+                changes.changed.len(),
+                changes.removed.len(),
+                changes.unchanged,
+                changes.hashed
+            ));
+            match embedded {
+                Ok(0) => {}
+                Ok(embedded) => print_info(&format!("Embedded {} files with {}.", embedded, index.embedding_model)),
+                Err(e) => print_warning(&format!(
+                    "Embedding stopped: {:#}. {} of {} files are embedded; rerun `opencode index update` to continue.",
+                    e,
+                    index.embedded_count(),
+                    index.files.len()
+                )),
+            }
+            let listed: Vec<String> = [("+", &changes.added), ("~", &changes.changed), ("-", &changes.removed)]
+                .into_iter()
+                .flat_map(|(mark, keys)| keys.iter().map(move |key| format!("{} {}", mark, key)))
+                .collect();
+            if !listed.is_empty() && listed.len() <= MAX_LISTED_CHANGES {
+                print_result(&listed.join("\n"));
+            }
+            Ok(())
+        }
+        IndexCommands::Status { path } => {
+            let root = project_root(path.as_deref())?;
+            let index = IndexStore::for_project(&root).load()?;
+            if index.updated_at == 0 {
+                print_info("No index yet; build it with `opencode index update`.");
+                return Ok(());
+            }
+            let files = onboarding_files(&root);
+            let freshness = index.freshness(&files);
+            let state = if freshness.is_fresh() {
+                "up to date".to_string()
+            } else {
+                format!(
+                    "stale: {} new, {} modified, {} deleted since the last update",
+                    freshness.new.len(),
+                    freshness.modified.len(),
+                    freshness.deleted.len()
+                )
+            };
+            print_info(&format!(
+                "Index of {}: {} files, {} KiB, updated {}; {}.",
+                root.display(),
+                index.files.len(),
+                index.total_bytes() / 1024,
+                format_utc(index.updated_at),
+                state
+            ));
+            if !index.embedding_model.is_empty() {
+                print_info(&format!("Embeddings: {} of {} files with {}.", index.embedded_count(), index.files.len(), index.embedding_model));
+            }
+            let mut rows = vec![["language", "indexed", "files", "coverage"].map(String::from).to_vec()];
+            for (language, (indexed, present)) in index.coverage(&files) {
+                rows.push(vec![language, indexed.to_string(), present.to_string(), format!("{}%", indexed * 100 / present.max(1))]);
+            }
+            print_result(align(&rows).trim_end());
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::time::Duration;
+
+use crate::cli::commands::{LogsArgs, LogsCommands};
+use crate::logging::{latest_log_file, log_dir};
+use crate::tui::{print_info, print_result};
+
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// The last `count` lines of `text`.
+pub fn last_lines(text: &str, count: usize) -> &str {
+    let text = text.trim_end_matches('\n');
+    if count == 0 {
+        return "";
+    }
+    match text.rmatch_indices('\n').nth(count - 1) {
+        Some((newline, _)) => &text[newline + 1..],
+        None => text,
+    }
+}
+
+pub async fn handle_logs(args: LogsArgs) -> Result<()> {
+    match args.command {
+        LogsCommands::Tail { lines, follow } => {
+            let dir = log_dir().context("Could not determine the config directory")?;
+            let Some(mut path) = latest_log_file(&dir) else {
+                print_info(&format!("No log files in {} yet.", dir.display()));
+                return Ok(());
+            };
+            let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+            let tail = last_lines(&content, lines);
+            if !tail.is_empty() {
+                print_result(tail);
+            }
+            if !follow {
+                return Ok(());
+            }
+            // Polls for appended lines, moving on to the next file when the log rotates.
+            let mut offset = content.len() as u64;
+            loop {
+                tokio::time::sleep(FOLLOW_POLL_INTERVAL).await;
+                if let Some(latest) = latest_log_file(&dir).filter(|latest| *latest != path) {
+                    path = latest;
+                    offset = 0;
+                }
+                let mut file = fs::File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+                if file.metadata()?.len() <= offset {
+                    continue;
+                }
+                file.seek(SeekFrom::Start(offset))?;
+                let mut appended = String::new();
+                offset += file.read_to_string(&mut appended)? as u64;
+                print_result(appended.trim_end_matches('\n'));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_lines() {
+        let text = "one\ntwo\nthree\n";
+        assert_eq!(last_lines(text, 2), "two\nthree");
+        assert_eq!(last_lines(text, 10), "one\ntwo\nthree");
+        assert_eq!(last_lines(text, 0), "");
+    }
+}
@@ -0,0 +1,246 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::io::Write;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, BufReader};
+
+use crate::api::chat_api::ChatApi;
+use crate::cli::commands::{DiagramFormat, EditArgs, ExplainArgs, GenerateArgs};
+use crate::commands::{edit::handle_edit, explain::handle_explain, generate::generate_reply};
+use crate::config::Config;
+use crate::events::{EventBus, UiEvent};
+use crate::stream_json::stream_json_event;
+use crate::tools::execution::ToolExecutionEngine;
+use crate::tools::registry::ToolRegistry;
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const REQUEST_FAILED: i64 = -32000;
+const METHODS: &[&str] = &["complete", "edit", "explainAtRange"];
+
+#[derive(Debug, Deserialize)]
+struct CompleteParams {
+    file: String,
+    line: usize,
+    #[serde(default)]
+    character: usize,
+    #[serde(default)]
+    instruction: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EditParams {
+    file: String,
+    instruction: String,
+    #[serde(default)]
+    start_line: Option<usize>,
+    #[serde(default)]
+    end_line: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RangeParams {
+    file: String,
+    start_line: usize,
+    end_line: usize,
+}
+
+// Reads one `Content-Length`-framed message (the LSP base protocol). `None` at end of input.
+pub async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = Some(value.trim().parse::<usize>().context("Invalid Content-Length header")?);
+            }
+        }
+    }
+    let length = content_length.ok_or_else(|| anyhow!("Message is missing a Content-Length header"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(serde_json::from_slice(&body).context("Message body is not valid JSON")?))
+}
+
+pub fn write_message(out: &mut impl Write, message: &Value) -> Result<()> {
+    let body = message.to_string();
+    write!(out, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    out.flush()?;
+    Ok(())
+}
+
+fn params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, (i64, String)> {
+    serde_json::from_value(params).map_err(|e| (INVALID_PARAMS, format!("Invalid params: {}", e)))
+}
+
+// Runs a command handler while collecting what it reports on the event bus: streamed text and
+// results make up `content`, everything else (warnings, tool calls and results) is in `events`.
+async fn capture(handler: impl Future<Output = Result<()>>) -> Result<Value, (i64, String)> {
+    capture_with(handler).await.map(|((), response)| response)
+}
+
+// `capture` for handlers that also return a value.
+async fn capture_with<T>(handler: impl Future<Output = Result<T>>) -> Result<(T, Value), (i64, String)> {
+    let mut receiver = EventBus::global().subscribe();
+    let mut collected = Vec::new();
+    let mut handler = std::pin::pin!(handler);
+    let result = loop {
+        tokio::select! {
+            result = &mut handler => break result,
+            Ok(event) = receiver.recv() => collected.push(event),
+        }
+    };
+    while let Ok(event) = receiver.try_recv() {
+        collected.push(event);
+    }
+    let value = result.map_err(|e| (REQUEST_FAILED, format!("{:#}", e)))?;
+
+    let mut content = String::new();
+    let mut events = Vec::new();
+    for event in collected {
+        match event {
+            UiEvent::ContentDelta { content: delta } => content.push_str(&delta),
+            UiEvent::Result { content: result } => {
+                if !content.is_empty() && !content.ends_with('\n') {
+                    content.push('\n');
+                }
+                content.push_str(&result);
+            }
+            other => events.push(stream_json_event(&other)),
+        }
+    }
+    Ok((value, json!({ "content": content, "events": events })))
+}
+
+// The code of the first answer that passed validation; the attempts before it are not part
+// of the completion.
+async fn complete(api_client: &dyn ChatApi, config: &Config, args: GenerateArgs) -> Result<String> {
+    let generated = generate_reply(api_client, config, args).await?;
+    if let Some(problem) = generated.problem {
+        bail!("No usable completion: {}", problem);
+    }
+    generated.code().context("No usable completion: the answer has no code block")
+}
+
+async fn dispatch(
+    api_client: &dyn ChatApi,
+    config: &Config,
+    tool_registry: &ToolRegistry,
+    tool_engine: &ToolExecutionEngine<'_>,
+    method: &str,
+    raw_params: Value,
+) -> Result<Value, (i64, String)> {
+    match method {
+        "initialize" => Ok(json!({ "name": "opencode", "version": env!("CARGO_PKG_VERSION"), "methods": METHODS })),
+        "shutdown" => Ok(Value::Null),
+        "complete" => {
+            let p: CompleteParams = params(raw_params)?;
+            let mut description = format!(
+                "Write the code to insert in {} at line {}, column {}. Respond with only the code to insert, in a single fenced code block with no explanation.",
+                p.file, p.line, p.character
+            );
+            if let Some(instruction) = p.instruction {
+                description.push_str(&format!(" It should: {}", instruction));
+            }
+            let args = GenerateArgs { description, file: Some(p.file) };
+            let (code, mut response) = capture_with(complete(api_client, config, args)).await?;
+            response["content"] = Value::from(code);
+            Ok(response)
+        }
+        "edit" => {
+            let p: EditParams = params(raw_params)?;
+            let instruction = match (p.start_line, p.end_line) {
+                (Some(start), Some(end)) => format!("{} (only change lines {}-{})", p.instruction, start, end),
+                _ => p.instruction,
+            };
+            let args = EditArgs { instruction, file: p.file };
+            capture(handle_edit(api_client, config.clone(), tool_registry, tool_engine, args)).await
+        }
+        "explainAtRange" => {
+            let p: RangeParams = params(raw_params)?;
+            let args = ExplainArgs {
+                file: Some(p.file),
+                dir: None,
+                diagram: DiagramFormat::Ascii,
+                lines: Some(format!("{}-{}", p.start_line, p.end_line)),
+                symbol: None,
+                with_history: false,
+            };
+            capture(handle_explain(api_client, config.clone(), args)).await
+        }
+        _ => Err((METHOD_NOT_FOUND, format!("Unknown method '{}'", method))),
+    }
+}
+
+// `opencode lsp-bridge`: JSON-RPC 2.0 over stdio for editor extensions. Requests are handled one
+// at a time by the same handlers as `generate`, `edit` and `explain`; their terminal output is
+// switched off and returned in the response instead.
+pub async fn handle_lsp_bridge(
+    api_client: &dyn ChatApi,
+    config: Config,
+    tool_registry: &ToolRegistry,
+    tool_engine: &ToolExecutionEngine<'_>,
+) -> Result<()> {
+    EventBus::global().set_terminal_output(false);
+    let mut stdin = BufReader::new(tokio::io::stdin());
+    let mut stdout = std::io::stdout();
+    tracing::info!("lsp-bridge listening on stdio");
+
+    while let Some(message) = read_message(&mut stdin).await? {
+        let method = message.get("method").and_then(Value::as_str).unwrap_or_default().to_string();
+        if method == "exit" {
+            break;
+        }
+        let Some(id) = message.get("id").cloned() else {
+            tracing::debug!("Ignoring notification '{}'", method);
+            continue;
+        };
+        let raw_params = message.get("params").cloned().unwrap_or(Value::Null);
+        let response = match dispatch(api_client, &config, tool_registry, tool_engine, &method, raw_params).await {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, error)) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": error } }),
+        };
+        write_message(&mut stdout, &response)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::chat_api::MockChatApi;
+    use crate::tools::execution::SecurityPolicy;
+
+    #[tokio::test]
+    async fn test_message_framing_round_trips() {
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize" })).unwrap();
+        write_message(&mut buffer, &json!({ "jsonrpc": "2.0", "method": "exit" })).unwrap();
+
+        let mut reader = BufReader::new(buffer.as_slice());
+        assert_eq!(read_message(&mut reader).await.unwrap().unwrap()["method"], json!("initialize"));
+        assert_eq!(read_message(&mut reader).await.unwrap().unwrap()["method"], json!("exit"));
+        assert!(read_message(&mut reader).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_complete_returns_only_the_accepted_code() {
+        let reply = |content: &str| vec![serde_json::from_value(json!({ "choices": [{ "delta": { "content": content } }] })).unwrap()];
+        let api = MockChatApi::new().with_stream(reply("fn add() {}")).with_stream(reply("```rust\nfn add() {}\n```"));
+        let config = Config::default();
+        let registry = ToolRegistry::new(&config);
+        let engine = ToolExecutionEngine::new(&registry, SecurityPolicy::ConfirmWrites);
+        let params = json!({ "file": "/nonexistent/lib.rs", "line": 1, "character": 0 });
+
+        let response = dispatch(&api, &config, &registry, &engine, "complete", params).await.unwrap();
+        assert_eq!(response["content"], json!("fn add() {}"));
+    }
+}
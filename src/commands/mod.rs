@@ -8,5 +8,31 @@ pub mod test_cmd;
 pub mod doc;
 pub mod run;
 pub mod shell;
+pub mod summary;
+pub mod deps;
+pub mod audit;
+pub mod plugin;
+pub mod pipeline;
+pub mod review;
+pub mod diagram;
+pub mod bench;
+pub mod scaffold;
+pub mod session;
+pub mod lsp_bridge;
+pub mod worktree;
+pub mod apply_patch;
+pub mod verify;
+pub mod run_plan;
+pub mod run_status;
+pub mod logs;
+pub mod prompts;
+pub mod refactor;
+pub mod todos;
+pub mod self_update;
+pub mod help;
+pub mod alias;
+pub mod ping;
+pub mod onboard;
+pub mod index;
 
 // TODO: Potentially add a dispatch function or trait here later
\ No newline at end of file
@@ -0,0 +1,215 @@
+use anyhow::{Context, Result};
+use ignore::WalkBuilder;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::api::chat_api::ChatApi;
+use crate::api::models::ChatCompletionRequest;
+use crate::cargo_workspace::is_build_output;
+use crate::cli::commands::OnboardArgs;
+use crate::commands::prompts::command_messages;
+use crate::config::Config;
+use crate::context::summaries::{content_hash, module_label, FileSummary, SummaryCache, SummaryIndex};
+use crate::tui::{print_info, print_warning, start_spinner};
+
+const FILE_PREAMBLE: &str = "You summarize one source file for a developer new to the codebase. \
+In at most two sentences say what the file is for and name its key types or functions. Reply with \
+the summary only.";
+const MODULE_PREAMBLE: &str = "You summarize one directory of a codebase from summaries of its \
+files. In at most two sentences say what the directory is responsible for. Reply with the summary only.";
+// Larger files are summarized from their beginning.
+const MAX_SUMMARIZED_CHARS: usize = 12_000;
+const MAX_FILE_BYTES: u64 = 1_000_000;
+const DEFAULT_OUTPUT: &str = "ONBOARDING.md";
+
+// Text files under `root` that git or `.ignore` files do not exclude, relative to it with `/`
+// separators, in path order. Hidden entries and build output are skipped too.
+pub fn onboarding_files(root: &Path) -> Vec<(String, PathBuf)> {
+    let walker = WalkBuilder::new(root)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(|entry| !is_build_output(entry.path()) && entry.file_name() != "node_modules")
+        .build();
+    let mut files = Vec::new();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                tracing::warn!("Skipping unreadable entry: {}", e);
+                continue;
+            }
+        };
+        let small = entry.metadata().map(|m| m.len() <= MAX_FILE_BYTES).unwrap_or(false);
+        if !entry.file_type().is_some_and(|t| t.is_file()) || !small {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+        let key = relative.to_string_lossy().replace('\\', "/");
+        files.push((key, entry.path().to_path_buf()));
+    }
+    files
+}
+
+// The module a file belongs to: its directory, "." for files at the root.
+pub fn module_of(path: &str) -> &str {
+    path.rsplit_once('/').map_or(".", |(dir, _)| dir)
+}
+
+async fn summarize(api_client: &dyn ChatApi, config: &Config, preamble: &str, prompt: String) -> Result<String> {
+    let request = ChatCompletionRequest {
+        model: config.api.default_model.clone(),
+        messages: command_messages(config, "onboard", Some(preamble), prompt)?,
+        stream: None,
+        temperature: Some(0.0),
+        max_tokens: None,
+        tools: None,
+        tool_choice: None,
+        source_map: None,
+    };
+    let response = api_client.chat_completion(request).await?;
+    let content = response.choices.into_iter().next().and_then(|c| c.message.content).unwrap_or_default();
+    // One line each keeps the overview document and the context block compact.
+    Ok(content.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+// GitHub's heading anchor: lowercase, punctuation other than `-` and `_` dropped, spaces as `-`.
+fn anchor(heading: &str) -> String {
+    heading
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ' '))
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+// The navigable overview: a table of contents linking each module's section, which lists
+// its files with their summaries.
+pub fn overview_document(index: &SummaryIndex) -> String {
+    let mut by_module: BTreeMap<&str, Vec<(&String, &FileSummary)>> = BTreeMap::new();
+    for (path, summary) in &index.files {
+        by_module.entry(module_of(path)).or_default().push((path, summary));
+    }
+    let mut doc = format!("# Project overview\n\n{} files in {} modules.\n\n## Modules\n\n", index.files.len(), by_module.len());
+    for module in by_module.keys() {
+        let label = module_label(module);
+        match index.modules.get(*module) {
+            Some(summary) => doc.push_str(&format!("- [{}](#{}): {}\n", label, anchor(label), summary)),
+            None => doc.push_str(&format!("- [{}](#{})\n", label, anchor(label))),
+        }
+    }
+    for (module, files) in &by_module {
+        doc.push_str(&format!("\n## {}\n\n", module_label(module)));
+        if let Some(summary) = index.modules.get(*module) {
+            doc.push_str(&format!("{}\n\n", summary));
+        }
+        for (path, file) in files {
+            doc.push_str(&format!("- [`{}`]({}): {}\n", path, path, file.summary));
+        }
+    }
+    doc
+}
+
+pub async fn handle_onboard(api_client: &dyn ChatApi, config: Config, args: OnboardArgs) -> Result<()> {
+    let root = fs::canonicalize(args.path.as_deref().unwrap_or("."))
+        .with_context(|| format!("Cannot onboard {}", args.path.as_deref().unwrap_or(".")))?;
+    let cache = SummaryCache::for_project(&root);
+    let mut index = if args.refresh { SummaryIndex::default() } else { cache.load()? };
+    let output = args.output.map(PathBuf::from).unwrap_or_else(|| root.join(DEFAULT_OUTPUT));
+    // The overview of an earlier run is not part of the project to summarize.
+    let previous_overview = fs::canonicalize(&output).ok();
+    let files: Vec<_> = onboarding_files(&root).into_iter().filter(|(_, path)| Some(path) != previous_overview.as_ref()).collect();
+    if files.len() > args.max_files {
+        print_warning(&format!("Found {} files; summarizing the first {} (raise --max-files for more).", files.len(), args.max_files));
+    }
+
+    let mut summaries = BTreeMap::new();
+    let mut changed_modules = Vec::new();
+    let (mut reused, mut failed) = (0, 0);
+    let total = files.len().min(args.max_files);
+    for (i, (key, path)) in files.into_iter().take(args.max_files).enumerate() {
+        // Binary and non-UTF-8 files have nothing to summarize.
+        let Ok(content) = fs::read_to_string(&path) else { continue };
+        if let Some(summary) = index.fresh_summary(&key, &content) {
+            summaries.insert(key, FileSummary { hash: content_hash(&content), summary: summary.to_string() });
+            reused += 1;
+            continue;
+        }
+        let excerpt: String = content.chars().take(MAX_SUMMARIZED_CHARS).collect();
+        let spinner = start_spinner(&format!("Summarizing {} ({}/{})...", key, i + 1, total));
+        let summary = summarize(api_client, &config, FILE_PREAMBLE, format!("File: {}\n\n{}", key, excerpt)).await;
+        spinner.finish_and_clear();
+        match summary {
+            Ok(summary) => {
+                changed_modules.push(module_of(&key).to_string());
+                summaries.insert(key, FileSummary { hash: content_hash(&content), summary });
+            }
+            Err(e) => {
+                print_warning(&format!("Could not summarize {}: {:#}", key, e));
+                failed += 1;
+            }
+        }
+    }
+
+    // Modules are re-summarized when a file in them changed, appeared or disappeared.
+    let old_modules: Vec<String> = index.files.keys().filter(|k| !summaries.contains_key(*k)).map(|k| module_of(k).to_string()).collect();
+    changed_modules.extend(old_modules);
+    index.files = summaries;
+    let mut modules: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (path, file) in &index.files {
+        modules.entry(module_of(path).to_string()).or_default().push(format!("- {}: {}", path, file.summary));
+    }
+    index.modules.retain(|module, _| modules.contains_key(module));
+    for (module, listing) in &modules {
+        if index.modules.contains_key(module) && !changed_modules.contains(module) {
+            continue;
+        }
+        let spinner = start_spinner(&format!("Summarizing module {}...", module_label(module)));
+        let summary = summarize(api_client, &config, MODULE_PREAMBLE, format!("Directory: {}\n\n{}", module, listing.join("\n"))).await;
+        spinner.finish_and_clear();
+        match summary {
+            Ok(summary) => {
+                index.modules.insert(module.clone(), summary);
+            }
+            Err(e) => print_warning(&format!("Could not summarize module {}: {:#}", module_label(module), e)),
+        }
+    }
+    cache.save(&index)?;
+
+    fs::write(&output, overview_document(&index)).with_context(|| format!("Failed to write {}", output.display()))?;
+    print_info(&format!(
+        "Summarized {} files ({} from cache, {} failed) in {} modules; wrote {}.",
+        index.files.len(),
+        reused,
+        failed,
+        index.modules.len(),
+        output.display()
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_files_respect_ignore_rules_and_overview_links_modules() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/api")).unwrap();
+        fs::create_dir_all(dir.path().join("generated")).unwrap();
+        fs::write(dir.path().join(".ignore"), "generated/\n").unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+        fs::write(dir.path().join("src/api/client.rs"), "pub struct Client;").unwrap();
+        fs::write(dir.path().join("generated/schema.rs"), "// generated").unwrap();
+        let keys: Vec<String> = onboarding_files(dir.path()).into_iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, vec!["Cargo.toml", "src/api/client.rs"]);
+
+        let mut index = SummaryIndex::default();
+        for key in &keys {
+            index.files.insert(key.clone(), FileSummary { hash: String::new(), summary: format!("About {}", key) });
+        }
+        index.modules.insert("src/api".to_string(), "HTTP client".to_string());
+        let doc = overview_document(&index);
+        assert!(doc.contains("- [(project root)](#project-root)\n- [src/api](#srcapi): HTTP client\n"));
+        assert!(doc.contains("## src/api\n\nHTTP client\n\n- [`src/api/client.rs`](src/api/client.rs): About src/api/client.rs\n"));
+    }
+}
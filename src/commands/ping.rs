@@ -0,0 +1,194 @@
+use anyhow::{bail, Context, Result};
+use futures_util::StreamExt;
+use reqwest::Client;
+use std::time::{Duration, Instant};
+
+use crate::api::chat_api::ChatApi;
+use crate::api::client::base_url;
+use crate::api::models::{ChatCompletionRequest, Message, Role};
+use crate::cli::commands::PingArgs;
+use crate::commands::bench::align;
+use crate::config::Config;
+use crate::tui::{print_info, print_result, print_warning, start_spinner};
+
+const PROBE_PROMPT: &str = "Reply with the word OK.";
+// Enough for a one-word reply; keeps each probe cheap.
+const PROBE_MAX_TOKENS: u32 = 5;
+
+// What probing one model found. Latencies are in milliseconds.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelProbe {
+    pub model: String,
+    pub latency_ms: Option<u128>,
+    pub first_token_ms: Option<u128>,
+    pub streaming: Option<bool>,
+    pub error: Option<String>,
+}
+
+// The models to probe: `--model`, or every distinct model the configuration uses.
+pub fn probe_models(config: &Config, model: Option<&str>) -> Vec<String> {
+    if let Some(model) = model {
+        return vec![model.to_string()];
+    }
+    let mut models = Vec::new();
+    for model in [&config.api.default_model, &config.api.edit_model, &config.api.big_model] {
+        if !models.contains(model) {
+            models.push(model.clone());
+        }
+    }
+    models
+}
+
+fn probe_request(model: &str) -> ChatCompletionRequest {
+    ChatCompletionRequest {
+        model: model.to_string(),
+        messages: vec![Message { role: Role::User, content: Some(PROBE_PROMPT.to_string()), tool_calls: None, tool_call_id: None }],
+        stream: None,
+        temperature: Some(0.0),
+        max_tokens: Some(PROBE_MAX_TOKENS),
+        tools: None,
+        tool_choice: None,
+        source_map: None,
+    }
+}
+
+// Time to a response from the provider's endpoint, whatever its status: the network and TLS
+// cost every request pays before a model is involved.
+async fn probe_network(config: &Config) -> Result<u128> {
+    let client = Client::builder()
+        .timeout(Duration::from_secs(config.network.fetch_timeout_seconds))
+        .build()
+        .context("Failed to build HTTP client")?;
+    let url = base_url(config.api.provider);
+    let started = Instant::now();
+    client.head(url).send().await.with_context(|| format!("{} is unreachable", url))?;
+    Ok(started.elapsed().as_millis())
+}
+
+// A full completion for the round-trip latency, then a streamed one for the time to the first
+// token and whether streaming works at all.
+async fn probe_model(api_client: &dyn ChatApi, model: &str) -> ModelProbe {
+    let mut probe = ModelProbe { model: model.to_string(), ..Default::default() };
+    let started = Instant::now();
+    if let Err(e) = api_client.chat_completion(probe_request(model)).await {
+        probe.error = Some(format!("{:#}", e));
+        return probe;
+    }
+    probe.latency_ms = Some(started.elapsed().as_millis());
+
+    let started = Instant::now();
+    let mut stream = match api_client.chat_completion_stream(probe_request(model)).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            probe.streaming = Some(false);
+            probe.error = Some(format!("streaming: {:#}", e));
+            return probe;
+        }
+    };
+    while let Some(chunk) = stream.next().await {
+        match chunk {
+            Ok(chunk) => {
+                let has_text = chunk.choices.iter().any(|c| c.delta.content.as_deref().is_some_and(|t| !t.is_empty()));
+                if has_text && probe.first_token_ms.is_none() {
+                    probe.first_token_ms = Some(started.elapsed().as_millis());
+                }
+            }
+            Err(e) => {
+                probe.error = Some(format!("streaming: {:#}", e));
+                break;
+            }
+        }
+    }
+    probe.streaming = Some(probe.error.is_none() && probe.first_token_ms.is_some());
+    probe
+}
+
+fn millis(value: Option<u128>) -> String {
+    value.map_or_else(|| "-".to_string(), |ms| format!("{} ms", ms))
+}
+
+pub fn render_probes(probes: &[ModelProbe]) -> String {
+    let mut rows = vec![["model", "reachable", "latency", "first token", "streaming"].map(String::from).to_vec()];
+    for probe in probes {
+        rows.push(vec![
+            probe.model.clone(),
+            if probe.latency_ms.is_some() { "yes" } else { "no" }.to_string(),
+            millis(probe.latency_ms),
+            millis(probe.first_token_ms),
+            match probe.streaming {
+                Some(true) => "yes",
+                Some(false) => "no",
+                None => "-",
+            }
+            .to_string(),
+        ]);
+    }
+    align(&rows)
+}
+
+pub async fn handle_ping(api_client: &dyn ChatApi, config: Config, args: PingArgs) -> Result<()> {
+    let url = base_url(config.api.provider);
+    let spinner = start_spinner(&format!("Reaching {}...", url));
+    let network = probe_network(&config).await;
+    spinner.finish_and_clear();
+    match &network {
+        Ok(ms) => print_info(&format!("{} answered in {} ms (network and TLS, no model involved).", url, ms)),
+        Err(e) => print_warning(&format!("{:#}", e)),
+    }
+
+    let mut probes = Vec::new();
+    for model in probe_models(&config, args.model.as_deref()) {
+        let spinner = start_spinner(&format!("Probing {}...", model));
+        probes.push(probe_model(api_client, &model).await);
+        spinner.finish_and_clear();
+    }
+    print_result(render_probes(&probes).trim_end());
+    for probe in &probes {
+        if let Some(error) = &probe.error {
+            print_warning(&format!("{}: {}", probe.model, error));
+        }
+    }
+
+    let failed = probes.iter().filter(|p| p.error.is_some()).count();
+    if failed > 0 {
+        bail!("{} of {} models failed their probe", failed, probes.len());
+    }
+    network.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::chat_api::MockChatApi;
+    use crate::api::models::{ChatCompletionChunk, ChatCompletionResponse, ChunkChoice, Delta};
+
+    #[tokio::test]
+    async fn test_probe_model_reports_latency_and_streaming() {
+        let response: ChatCompletionResponse = serde_json::from_value(serde_json::json!({
+            "id": "1", "object": "chat.completion", "created": 0, "model": "m",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "OK"}, "finish_reason": "stop"}]
+        }))
+        .unwrap();
+        let chunk = ChatCompletionChunk {
+            id: String::new(),
+            object: String::new(),
+            created: 0,
+            model: "m".to_string(),
+            choices: vec![ChunkChoice {
+                index: 0,
+                delta: Delta { role: None, content: Some("OK".to_string()), reasoning: None, tool_calls: None },
+                finish_reason: None,
+            }],
+            usage: None,
+        };
+        let api = MockChatApi::new().with_response(response).with_stream(vec![chunk]);
+        let probe = probe_model(&api, "m").await;
+        assert_eq!(probe.error, None);
+        assert!(probe.latency_ms.is_some() && probe.first_token_ms.is_some());
+        assert_eq!(probe.streaming, Some(true));
+
+        let failed = probe_model(&MockChatApi::new(), "missing").await;
+        assert!(failed.error.is_some());
+        assert!(render_probes(&[probe, failed]).lines().nth(2).unwrap().starts_with("missing  no         -"));
+    }
+}
@@ -0,0 +1,375 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tokio::process::Command;
+
+use crate::api::chat_api::ChatApi;
+use crate::api::models::{ChatCompletionRequest, Message, Role};
+use crate::cli::commands::{PipelineArgs, PipelineCommands, PipelineRunArgs};
+use crate::config::Config;
+use crate::tools::registry::ToolRegistry;
+use crate::tui::{print_error, print_info, print_result, start_spinner, verbosity, Verbosity};
+
+use super::summary::report_session_changes;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineFile {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub vars: HashMap<String, String>,
+    pub steps: Vec<PipelineStep>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PipelineStep {
+    pub id: String,
+    #[serde(flatten)]
+    pub action: StepAction,
+    // Let later steps run even if this one fails (e.g. run tests, then review the failures).
+    #[serde(default)]
+    pub continue_on_error: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StepAction {
+    Prompt {
+        prompt: String,
+        #[serde(default)]
+        include_files: Vec<String>,
+        #[serde(default)]
+        model: Option<String>,
+    },
+    // Template references expand to quoted shell variables, so write them unquoted.
+    Shell {
+        command: String,
+    },
+    Write {
+        path: String,
+        content: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepResult {
+    pub output: String,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+}
+
+// Expands `{{vars.NAME}}`, `{{steps.ID.output}}`, `{{steps.ID.success}}` and
+// `{{steps.ID.exit_code}}`. Unknown references are an error so typos fail fast.
+pub fn render_template(
+    template: &str,
+    vars: &HashMap<String, String>,
+    results: &HashMap<String, StepResult>,
+) -> Result<String> {
+    expand_template(template, vars, results, |value| value)
+}
+
+// Renders a shell step's command. Values are never spliced into the script, since step
+// outputs may come from the model: each reference becomes a quoted variable, e.g.
+// `"$PIPELINE_VALUE_0"`, set in the returned environment.
+pub fn render_shell_command(
+    template: &str,
+    vars: &HashMap<String, String>,
+    results: &HashMap<String, StepResult>,
+) -> Result<(String, Vec<(String, String)>)> {
+    let mut env = Vec::new();
+    let command = expand_template(template, vars, results, |value| {
+        let name = format!("PIPELINE_VALUE_{}", env.len());
+        let reference = if cfg!(target_os = "windows") { format!("\"%{}%\"", name) } else { format!("\"${}\"", name) };
+        env.push((name, value));
+        reference
+    })?;
+    Ok((command, env))
+}
+
+fn expand_template(
+    template: &str,
+    vars: &HashMap<String, String>,
+    results: &HashMap<String, StepResult>,
+    mut substitute: impl FnMut(String) -> String,
+) -> Result<String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or_else(|| anyhow!("Unclosed '{{{{' in template: {}", template))?;
+        let reference = after[..end].trim();
+        let parts: Vec<&str> = reference.split('.').collect();
+        let value = match parts.as_slice() {
+            ["vars", name] => vars.get(*name).cloned(),
+            ["steps", id, field] => results.get(*id).and_then(|r| match *field {
+                "output" => Some(r.output.clone()),
+                "success" => Some(r.success.to_string()),
+                "exit_code" => Some(r.exit_code.map(|c| c.to_string()).unwrap_or_default()),
+                _ => None,
+            }),
+            _ => None,
+        }
+        .ok_or_else(|| anyhow!("Unknown template reference '{{{{{}}}}}'", reference))?;
+        rendered.push_str(&substitute(value));
+        rest = &after[end + 2..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+pub fn parse_var(raw: &str) -> Result<(String, String)> {
+    let (key, value) = raw
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Expected KEY=VALUE for --var, got '{}'", raw))?;
+    Ok((key.trim().to_string(), value.to_string()))
+}
+
+fn validate(pipeline: &PipelineFile) -> Result<()> {
+    if pipeline.steps.is_empty() {
+        bail!("Pipeline has no steps");
+    }
+    let mut seen = Vec::new();
+    for step in &pipeline.steps {
+        if seen.contains(&step.id.as_str()) {
+            bail!("Duplicate step id '{}'", step.id);
+        }
+        seen.push(step.id.as_str());
+    }
+    Ok(())
+}
+
+async fn run_prompt_step(
+    api_client: &dyn ChatApi,
+    config: &Config,
+    prompt: String,
+    include_files: &[String],
+    model: Option<String>,
+) -> Result<StepResult> {
+    let mut content = prompt;
+    for file in include_files {
+        let text = fs::read_to_string(file).with_context(|| format!("Failed to read included file '{}'", file))?;
+        content.push_str(&format!("\n\nContent of {}:\n```\n{}\n```", file, text));
+    }
+    let request = ChatCompletionRequest {
+        model: model.unwrap_or_else(|| config.api.default_model.clone()),
+        messages: vec![Message {
+            role: Role::User,
+            content: Some(content),
+            tool_calls: None,
+            tool_call_id: None,
+        }],
+        stream: None,
+        temperature: None,
+        max_tokens: None,
+        tools: None,
+        tool_choice: None,
+        source_map: None,
+    };
+    let response = api_client.chat_completion(request).await?;
+    let output = response
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|c| c.message.content)
+        .unwrap_or_default();
+    Ok(StepResult { output, success: true, exit_code: None })
+}
+
+async fn run_shell_step(command: &str, env: Vec<(String, String)>) -> Result<StepResult> {
+    let (shell, shell_arg) = if cfg!(target_os = "windows") { ("cmd", "/C") } else { ("sh", "-c") };
+    let output = Command::new(shell)
+        .arg(shell_arg)
+        .arg(command)
+        .envs(env)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .with_context(|| format!("Failed to run '{}'", command))?;
+    let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.trim().is_empty() {
+        text.push_str(&stderr);
+    }
+    Ok(StepResult {
+        output: text,
+        success: output.status.success(),
+        exit_code: output.status.code(),
+    })
+}
+
+// Runs one step, templates included, so a reference that fails to render is a step failure
+// like any other and `continue_on_error` applies to it.
+async fn run_step(
+    api_client: &dyn ChatApi,
+    config: &Config,
+    tool_registry: &ToolRegistry,
+    action: &StepAction,
+    vars: &HashMap<String, String>,
+    results: &HashMap<String, StepResult>,
+) -> Result<StepResult> {
+    match action {
+        StepAction::Prompt { prompt, include_files, model } => {
+            let prompt = render_template(prompt, vars, results)?;
+            let include_files = include_files
+                .iter()
+                .map(|f| render_template(f, vars, results))
+                .collect::<Result<Vec<_>>>()?;
+            run_prompt_step(api_client, config, prompt, &include_files, model.clone()).await
+        }
+        StepAction::Shell { command } => {
+            let (command, env) = render_shell_command(command, vars, results)?;
+            run_shell_step(&command, env).await
+        }
+        StepAction::Write { path, content } => {
+            let path = render_template(path, vars, results)?;
+            let content = render_template(content, vars, results)?;
+            if let Some(parent) = Path::new(&path).parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent)?;
+            }
+            tool_registry.snapshots().record_before_change(Path::new(&path));
+            fs::write(&path, &content)
+                .with_context(|| format!("Failed to write {}", path))
+                .map(|_| StepResult { output: path.clone(), success: true, exit_code: None })
+        }
+    }
+}
+
+async fn run_pipeline(api_client: &dyn ChatApi, config: Config, tool_registry: &ToolRegistry, args: PipelineRunArgs) -> Result<()> {
+    let path = Path::new(&args.file);
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read pipeline file {}", path.display()))?;
+    let pipeline: PipelineFile =
+        toml::from_str(&content).with_context(|| format!("Failed to parse pipeline file {}", path.display()))?;
+    validate(&pipeline)?;
+
+    let mut vars = pipeline.vars.clone();
+    for raw in &args.vars {
+        let (key, value) = parse_var(raw)?;
+        vars.insert(key, value);
+    }
+
+    print_info(&format!(
+        "Running pipeline '{}' ({} steps)",
+        pipeline.name.as_deref().unwrap_or(&args.file),
+        pipeline.steps.len()
+    ));
+    let mut results: HashMap<String, StepResult> = HashMap::new();
+    // `-v` prints every step's output, not just the last.
+    let verbose = verbosity() == Verbosity::Verbose;
+    let mut failed_steps = Vec::new();
+    for (index, step) in pipeline.steps.iter().enumerate() {
+        let label = format!("[{}/{}] {}", index + 1, pipeline.steps.len(), step.id);
+        let spinner = start_spinner(&label);
+        let result = run_step(api_client, &config, tool_registry, &step.action, &vars, &results).await;
+        spinner.finish_and_clear();
+
+        let result = result.unwrap_or_else(|e| StepResult {
+            output: format!("{:#}", e),
+            success: false,
+            exit_code: None,
+        });
+        if result.success {
+            print_info(&format!("{} ✓", label));
+        } else {
+            print_error(&format!("{} failed", label));
+        }
+        if verbose || !result.success {
+            print_result(&result.output);
+        }
+        let success = result.success;
+        results.insert(step.id.clone(), result);
+        if !success {
+            failed_steps.push(step.id.clone());
+            if !step.continue_on_error {
+                break;
+            }
+        }
+    }
+
+    if let Some(last) = pipeline.steps.last().and_then(|s| results.get(&s.id)) {
+        if !verbose && last.success {
+            print_result(&last.output);
+        }
+    }
+    report_session_changes(tool_registry.snapshots()).await?;
+    if !failed_steps.is_empty() {
+        bail!("Pipeline failed at step(s): {}", failed_steps.join(", "));
+    }
+    Ok(())
+}
+
+pub async fn handle_pipeline(api_client: &dyn ChatApi, config: Config, tool_registry: &ToolRegistry, args: PipelineArgs) -> Result<()> {
+    match args.command {
+        PipelineCommands::Run(run_args) => run_pipeline(api_client, config, tool_registry, run_args).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pipeline_file() {
+        let pipeline: PipelineFile = toml::from_str(
+            r#"
+name = "tests"
+[vars]
+file = "src/lib.rs"
+
+[[steps]]
+id = "explain"
+kind = "prompt"
+prompt = "Explain {{vars.file}}"
+include_files = ["{{vars.file}}"]
+
+[[steps]]
+id = "test"
+kind = "shell"
+command = "cargo test"
+continue_on_error = true
+"#,
+        )
+        .unwrap();
+        assert_eq!(pipeline.steps.len(), 2);
+        assert!(matches!(pipeline.steps[0].action, StepAction::Prompt { .. }));
+        assert!(pipeline.steps[1].continue_on_error);
+        assert!(validate(&pipeline).is_ok());
+    }
+
+    #[test]
+    fn test_render_template() {
+        let vars = HashMap::from([("file".to_string(), "src/lib.rs".to_string())]);
+        let results = HashMap::from([(
+            "test".to_string(),
+            StepResult { output: "1 failed".to_string(), success: false, exit_code: Some(101) },
+        )]);
+        assert_eq!(
+            render_template(
+                "Review {{ vars.file }}: {{steps.test.output}} ({{steps.test.exit_code}}, {{steps.test.success}})",
+                &vars,
+                &results
+            )
+            .unwrap(),
+            "Review src/lib.rs: 1 failed (101, false)"
+        );
+        assert!(render_template("{{vars.missing}}", &vars, &results).is_err());
+        assert!(render_template("{{steps.test.output", &vars, &results).is_err());
+        assert_eq!(parse_var("model=gpt=4").unwrap(), ("model".to_string(), "gpt=4".to_string()));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shell_steps_receive_outputs_as_data() {
+        let results = HashMap::from([(
+            "ask".to_string(),
+            StepResult { output: "x; touch pwned $(id)".to_string(), success: true, exit_code: None },
+        )]);
+        let (command, env) = render_shell_command("printf '%s' {{steps.ask.output}}", &HashMap::new(), &results).unwrap();
+        assert_eq!(command, "printf '%s' \"$PIPELINE_VALUE_0\"");
+        let result = run_shell_step(&command, env).await.unwrap();
+        assert_eq!(result.output, "x; touch pwned $(id)");
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use crate::cli::commands::{PluginArgs, PluginCommands, PluginInstallArgs};
+use crate::tools::plugin::{describe_plugin, validate_name, PluginManifest, PluginStore};
+use crate::tui::{print_info, print_result, start_spinner};
+
+// Local paths are stored absolute so the plugin still resolves from other directories;
+// bare names are left for PATH lookup.
+fn resolve_command(command: &str) -> Result<String> {
+    let path = Path::new(command);
+    if path.components().count() > 1 || path.exists() {
+        let absolute = path
+            .canonicalize()
+            .with_context(|| format!("Plugin executable not found: {}", command))?;
+        return Ok(absolute.display().to_string());
+    }
+    Ok(command.to_string())
+}
+
+fn default_plugin_name(command: &str) -> String {
+    Path::new(command)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| command.to_string())
+}
+
+async fn install(store: &PluginStore, args: PluginInstallArgs) -> Result<()> {
+    let command = resolve_command(&args.command)?;
+    let name = args.name.unwrap_or_else(|| default_plugin_name(&command));
+    validate_name(&name)?;
+
+    let spinner = start_spinner(&format!("Querying plugin '{}' for its tools...", name));
+    let tools = describe_plugin(&command, &args.args).await;
+    spinner.finish_and_clear();
+    let tools = tools.with_context(|| format!("Plugin '{}' did not answer list_tools", name))?;
+
+    let manifest = PluginManifest {
+        name: name.clone(),
+        command,
+        args: args.args,
+        tools,
+    };
+    let path = store.save(&manifest)?;
+    print_info(&format!(
+        "Installed plugin '{}' with {} tool(s): {}",
+        name,
+        manifest.tools.len(),
+        manifest.tools.iter().map(|t| t.name.as_str()).collect::<Vec<_>>().join(", ")
+    ));
+    tracing::debug!("Plugin manifest written to {:?}", path);
+    Ok(())
+}
+
+fn list(store: &PluginStore) {
+    let plugins = store.list();
+    if plugins.is_empty() {
+        print_info("No plugins installed.");
+        return;
+    }
+    let listing = plugins
+        .iter()
+        .map(|plugin| {
+            let tools = plugin
+                .tools
+                .iter()
+                .map(|t| format!("    {} - {}", t.name, t.description))
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!("{} ({} {})\n{}", plugin.name, plugin.command, plugin.args.join(" "), tools)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    print_result(&listing);
+}
+
+pub async fn handle_plugin(args: PluginArgs) -> Result<()> {
+    let store = PluginStore::from_config_dir()?;
+    match args.command {
+        PluginCommands::Install(install_args) => install(&store, install_args).await,
+        PluginCommands::List => {
+            list(&store);
+            Ok(())
+        }
+        PluginCommands::Remove(remove_args) => {
+            store.remove(&remove_args.name)?;
+            print_info(&format!("Removed plugin '{}'.", remove_args.name));
+            Ok(())
+        }
+    }
+}
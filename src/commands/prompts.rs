@@ -0,0 +1,65 @@
+use anyhow::{Context, Result};
+use std::iter;
+
+use crate::api::models::{Message, Role};
+use crate::config::Config;
+
+// Expands, inside a `[prompts.<command>]` prompt, to the command's built-in preamble.
+const DEFAULT_PLACEHOLDER: &str = "{{default}}";
+
+// The system prompt for `command`: `[prompts.<command>]` when configured, otherwise the
+// command's built-in preamble. A configured prompt can include `{{default}}` to keep the
+// built-in instructions and add a team's own conventions around them.
+pub fn command_system_prompt(config: &Config, command: &str, builtin: Option<&str>) -> Result<Option<String>> {
+    let configured = match config.prompts.get(command) {
+        Some(prompt) => prompt
+            .resolve_system_prompt()
+            .with_context(|| format!("Invalid [prompts.{}] in config", command))?,
+        None => None,
+    };
+    Ok(match configured {
+        Some(prompt) => Some(prompt.replace(DEFAULT_PLACEHOLDER, builtin.unwrap_or_default()).trim().to_string())
+            .filter(|p| !p.is_empty()),
+        None => builtin.map(String::from),
+    })
+}
+
+// The messages of a one-shot command request: its system prompt, if any, then `user`.
+pub fn command_messages(config: &Config, command: &str, builtin: Option<&str>, user: String) -> Result<Vec<Message>> {
+    let system = command_system_prompt(config, command, builtin)?;
+    Ok(system
+        .map(|content| Message { role: Role::System, content: Some(content), tool_calls: None, tool_call_id: None })
+        .into_iter()
+        .chain(iter::once(Message { role: Role::User, content: Some(user), tool_calls: None, tool_call_id: None }))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PromptConfig;
+
+    #[test]
+    fn test_configured_prompt_replaces_builtin() {
+        let mut config = Config::default();
+        assert_eq!(command_system_prompt(&config, "edit", Some("Built in.")).unwrap().as_deref(), Some("Built in."));
+        assert_eq!(command_system_prompt(&config, "explain", None).unwrap(), None);
+
+        config.prompts.insert(
+            "edit".to_string(),
+            PromptConfig { system: Some("{{default}}\nFollow the team style guide.".to_string()), system_file: None },
+        );
+        config.prompts.insert(
+            "explain".to_string(),
+            PromptConfig { system: Some("Explain for new hires.".to_string()), system_file: None },
+        );
+        assert_eq!(
+            command_system_prompt(&config, "edit", Some("Built in.")).unwrap().as_deref(),
+            Some("Built in.\nFollow the team style guide.")
+        );
+        let messages = command_messages(&config, "explain", None, "What does this do?".to_string()).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, Role::System);
+        assert_eq!(messages[0].content.as_deref(), Some("Explain for new hires."));
+    }
+}
@@ -0,0 +1,398 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::api::chat_api::ChatApi;
+use crate::api::models::{ChatCompletionRequest, Message, Role};
+use crate::cargo_workspace::is_build_output;
+use crate::cli::commands::RefactorArgs;
+use crate::commands::prompts::command_messages;
+use crate::commands::summary::report_session_changes;
+use crate::config::Config;
+use crate::context::ContextManager;
+use crate::tools::registry::ToolRegistry;
+use crate::tools::execution::ToolExecutionEngine;
+use crate::tui::{print_info, print_result, print_warning, prompt_confirmation, start_spinner};
+
+const PLAN_PREAMBLE: &str = "You plan a codebase-wide refactor. Given the instruction, reply with \
+ONLY a JSON object naming where to look and what to search for: {\"path\": \"directory to search\", \
+\"patterns\": [\"literal text that appears in every file needing a change\"], \"extensions\": \
+[\"file extensions to include, without the dot; empty for all\"]}. Patterns are plain substrings, \
+not regular expressions.";
+
+const REFACTOR_PREAMBLE: &str = "You apply one refactoring instruction to the files you are given. \
+Reply with ONLY a JSON object: {\"edits\": [{\"path\": \"path exactly as given\", \"diff\": \"unified \
+diff\"}]}. Each diff starts with `--- a/<path>` and `+++ b/<path>` headers and has enough context \
+lines to apply with `git apply`. Leave out files that need no change, and change nothing the \
+instruction does not ask for.";
+
+// A batch is retried once with the failed files and why they failed.
+const MAX_ATTEMPTS: usize = 2;
+
+// Where the files to change are and which substrings mark them.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RefactorPlan {
+    #[serde(default = "default_plan_path")]
+    pub path: String,
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    #[serde(default)]
+    pub extensions: Vec<String>,
+}
+
+fn default_plan_path() -> String {
+    ".".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct FileEdit {
+    path: String,
+    diff: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchEdits {
+    #[serde(default)]
+    edits: Vec<FileEdit>,
+}
+
+#[derive(Debug, Default)]
+struct RefactorSummary {
+    changed: Vec<String>,
+    unchanged: Vec<String>,
+    failed: Vec<(String, String)>,
+}
+
+// The JSON object in a model reply, which may be fenced or surrounded by prose.
+fn json_object(content: &str) -> Option<&str> {
+    let start = content.find('{')?;
+    let end = content.rfind('}')?;
+    (start < end).then(|| &content[start..=end])
+}
+
+// Text files under the plan's path that contain any of its patterns, skipping hidden
+// entries and `target`.
+pub fn find_files(plan: &RefactorPlan) -> Result<Vec<PathBuf>> {
+    let root = Path::new(&plan.path);
+    if !root.exists() {
+        bail!("Refactor path '{}' does not exist", plan.path);
+    }
+    let walker = WalkDir::new(root).sort_by_file_name().into_iter().filter_entry(|entry| {
+        let name = entry.file_name().to_string_lossy();
+        entry.depth() == 0 || !(name.starts_with('.') || is_build_output(entry.path()))
+    });
+    let mut files = Vec::new();
+    for entry in walker {
+        let entry = entry.with_context(|| format!("Failed to walk {}", root.display()))?;
+        let path = entry.path();
+        let included = plan.extensions.is_empty()
+            || path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| plan.extensions.iter().any(|wanted| wanted.trim_start_matches('.') == e));
+        if !entry.file_type().is_file() || !included {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+        if plan.patterns.is_empty() || plan.patterns.iter().any(|p| content.contains(p.as_str())) {
+            files.push(path.strip_prefix(".").unwrap_or(path).to_path_buf());
+        }
+    }
+    Ok(files)
+}
+
+// Groups files, in order, so each batch's content stays within `max_tokens`. A file larger
+// than the budget gets a batch of its own.
+pub fn batch_files(files: Vec<(PathBuf, usize)>, max_tokens: usize) -> Vec<Vec<PathBuf>> {
+    let mut batches: Vec<Vec<PathBuf>> = Vec::new();
+    let mut used = 0;
+    for (path, tokens) in files {
+        match batches.last_mut() {
+            Some(batch) if used + tokens <= max_tokens => batch.push(path),
+            _ => {
+                batches.push(vec![path]);
+                used = 0;
+            }
+        }
+        used += tokens;
+    }
+    batches
+}
+
+async fn complete(api_client: &dyn ChatApi, config: &Config, messages: Vec<Message>) -> Result<String> {
+    let request = ChatCompletionRequest {
+        model: config.api.big_model.clone(),
+        messages,
+        stream: None,
+        temperature: Some(0.0),
+        max_tokens: None,
+        tools: None,
+        tool_choice: None,
+        source_map: None,
+    };
+    let response = api_client.chat_completion(request).await?;
+    Ok(response.choices.into_iter().next().and_then(|c| c.message.content).unwrap_or_default())
+}
+
+async fn plan_refactor(api_client: &dyn ChatApi, config: &Config, args: &RefactorArgs) -> Result<RefactorPlan> {
+    if !args.patterns.is_empty() {
+        return Ok(RefactorPlan {
+            path: args.path.clone().unwrap_or_else(default_plan_path),
+            patterns: args.patterns.clone(),
+            extensions: Vec::new(),
+        });
+    }
+    let spinner = start_spinner("Planning the refactor...");
+    let messages = vec![
+        Message { role: Role::System, content: Some(PLAN_PREAMBLE.to_string()), tool_calls: None, tool_call_id: None },
+        Message { role: Role::User, content: Some(format!("Instruction: {}", args.instruction)), tool_calls: None, tool_call_id: None },
+    ];
+    let reply = complete(api_client, config, messages).await;
+    spinner.finish_and_clear();
+    let reply = reply?;
+    let mut plan: RefactorPlan = json_object(&reply)
+        .and_then(|json| serde_json::from_str(json).ok())
+        .with_context(|| format!("The model did not return a search plan: {}", reply.trim()))?;
+    if let Some(path) = &args.path {
+        plan.path = path.clone();
+    }
+    Ok(plan)
+}
+
+// The model's own headers may name another file, so everything before the first hunk is
+// dropped and `path`'s headers written in their place.
+fn with_headers(path: &str, diff: &str) -> String {
+    let hunks = match diff.find("@@") {
+        Some(start) => &diff[diff[..start].rfind('\n').map_or(0, |i| i + 1)..],
+        None => diff.trim_start(),
+    };
+    let mut patch = format!("--- a/{}\n+++ b/{}\n{}", path, path, hunks);
+    if !patch.ends_with('\n') {
+        patch.push('\n');
+    }
+    patch
+}
+
+// Applies one file's diff to a scratch copy with `git apply` and returns the patched text,
+// leaving the real file untouched.
+async fn patched_content(edit: &FileEdit, original: &str) -> Result<String, String> {
+    let path = Path::new(&edit.path);
+    let name = match path.file_name() {
+        Some(name) if path.is_absolute() => PathBuf::from(name),
+        _ => path.to_path_buf(),
+    };
+    let scratch = tempfile::tempdir().map_err(|e| e.to_string())?;
+    let copy = scratch.path().join(&name);
+    if let Some(parent) = copy.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::write(&copy, original).map_err(|e| e.to_string())?;
+    let mut patch = tempfile::NamedTempFile::new().map_err(|e| e.to_string())?;
+    patch
+        .write_all(with_headers(&name.to_string_lossy(), &edit.diff).as_bytes())
+        .map_err(|e| e.to_string())?;
+    let applied = tokio::process::Command::new("git")
+        .args(["apply", "--recount"])
+        .arg(patch.path())
+        .current_dir(scratch.path())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run git apply: {}", e))?;
+    if !applied.status.success() {
+        return Err(format!("diff does not apply: {}", String::from_utf8_lossy(&applied.stderr).trim()));
+    }
+    fs::read_to_string(&copy).map_err(|e| format!("cannot read patched file: {}", e))
+}
+
+// Writes the patched file through FileWriteTool, so path rules, tool policy, hooks, write
+// checks and snapshots apply exactly as they do to the model's own writes.
+async fn apply_edit(edit: &FileEdit, tool_engine: &ToolExecutionEngine<'_>) -> Result<bool, String> {
+    let original = fs::read_to_string(&edit.path).map_err(|e| format!("cannot read file: {}", e))?;
+    let updated = patched_content(edit, &original).await?;
+    if updated == original {
+        return Ok(false);
+    }
+    let result = tool_engine
+        .execute_tool_call("FileWriteTool", serde_json::json!({ "path": edit.path, "content": updated }))
+        .await
+        .map_err(|e| e.to_string())?;
+    if result.get("status").and_then(|s| s.as_str()) == Some("rejected") {
+        let failures = result.get("check_failures").map(|f| f.to_string()).unwrap_or_default();
+        return Err(format!("rejected by write checks ({})", failures));
+    }
+    Ok(true)
+}
+
+fn batch_prompt(instruction: &str, files: &[PathBuf]) -> String {
+    let mut prompt = format!("Instruction: {}\n", instruction);
+    for path in files {
+        let content = fs::read_to_string(path).unwrap_or_default();
+        prompt.push_str(&format!("\nFile: {}\n```\n{}\n```\n", path.display(), content));
+    }
+    prompt
+}
+
+async fn refactor_batch(
+    api_client: &dyn ChatApi,
+    config: &Config,
+    instruction: &str,
+    batch: &[PathBuf],
+    tool_engine: &ToolExecutionEngine<'_>,
+    summary: &mut RefactorSummary,
+) -> Result<()> {
+    let mut pending: Vec<PathBuf> = batch.to_vec();
+    let mut messages = command_messages(config, "refactor", Some(REFACTOR_PREAMBLE), batch_prompt(instruction, batch))?;
+    for attempt in 1..=MAX_ATTEMPTS {
+        let reply = complete(api_client, config, messages.clone()).await?;
+        let edits = json_object(&reply)
+            .and_then(|json| serde_json::from_str::<BatchEdits>(json).ok())
+            .map(|b| b.edits);
+        let mut failures: Vec<(String, String)> = Vec::new();
+        match edits {
+            None => failures.extend(pending.iter().map(|p| (p.display().to_string(), "reply was not an edits object".to_string()))),
+            Some(edits) => {
+                for path in &pending {
+                    let name = path.display().to_string();
+                    let Some(edit) = edits.iter().find(|e| Path::new(&e.path) == path.as_path()) else {
+                        summary.unchanged.push(name);
+                        continue;
+                    };
+                    match apply_edit(edit, tool_engine).await {
+                        Ok(true) => summary.changed.push(name),
+                        Ok(false) => summary.unchanged.push(name),
+                        Err(reason) => failures.push((name, reason)),
+                    }
+                }
+            }
+        }
+        if failures.is_empty() {
+            return Ok(());
+        }
+        if attempt == MAX_ATTEMPTS {
+            summary.failed.extend(failures);
+            return Ok(());
+        }
+        tracing::info!("Retrying {} file(s) whose edits failed", failures.len());
+        pending.retain(|p| failures.iter().any(|(name, _)| *name == p.display().to_string()));
+        let feedback: Vec<String> = failures.iter().map(|(name, reason)| format!("- {}: {}", name, reason)).collect();
+        messages.push(Message { role: Role::Assistant, content: Some(reply), tool_calls: None, tool_call_id: None });
+        messages.push(Message {
+            role: Role::User,
+            content: Some(format!(
+                "These edits were not applied:\n{}\n\nThe files are unchanged. Send corrected edits for these files only, against their current content:\n{}",
+                feedback.join("\n"),
+                batch_prompt(instruction, &pending)
+            )),
+            tool_calls: None,
+            tool_call_id: None,
+        });
+    }
+    Ok(())
+}
+
+fn format_summary(summary: &RefactorSummary) -> String {
+    let mut lines = vec![format!(
+        "Refactor finished: {} changed, {} unchanged, {} failed.",
+        summary.changed.len(),
+        summary.unchanged.len(),
+        summary.failed.len()
+    )];
+    lines.extend(summary.failed.iter().map(|(path, reason)| format!("  {}: {}", path, reason)));
+    lines.join("\n")
+}
+
+pub async fn handle_refactor(
+    api_client: &dyn ChatApi,
+    config: Config,
+    context_manager: &ContextManager,
+    tool_registry: &ToolRegistry,
+    tool_engine: &ToolExecutionEngine<'_>,
+    args: RefactorArgs,
+) -> Result<()> {
+    let plan = plan_refactor(api_client, &config, &args).await?;
+    tracing::info!("Refactor plan: {:?}", plan);
+    let files = find_files(&plan)?;
+    if files.is_empty() {
+        print_info(&format!("No files under '{}' match {:?}; nothing to refactor.", plan.path, plan.patterns));
+        return Ok(());
+    }
+    let sized = files
+        .into_iter()
+        .map(|path| {
+            let tokens = context_manager.count_tokens(&fs::read_to_string(&path).unwrap_or_default());
+            (path, tokens)
+        })
+        .collect::<Vec<_>>();
+    let file_count = sized.len();
+    let batches = batch_files(sized, args.batch_tokens);
+    print_info(&format!(
+        "{} file(s) under '{}' match {:?}; processing them in {} batch(es).",
+        file_count,
+        plan.path,
+        plan.patterns,
+        batches.len()
+    ));
+    if !args.yes && std::io::stdin().is_terminal() && !prompt_confirmation("Start the refactor?")? {
+        print_info("Refactor cancelled.");
+        return Ok(());
+    }
+
+    let mut summary = RefactorSummary::default();
+    for (index, batch) in batches.iter().enumerate() {
+        let spinner = start_spinner(&format!("Batch {}/{}: {} file(s)...", index + 1, batches.len(), batch.len()));
+        let result = refactor_batch(api_client, &config, &args.instruction, batch, tool_engine, &mut summary).await;
+        spinner.finish_and_clear();
+        if let Err(e) = result {
+            print_warning(&format!("Batch {}/{} failed: {:#}", index + 1, batches.len(), e));
+            summary.failed.extend(batch.iter().map(|p| (p.display().to_string(), format!("{:#}", e))));
+        }
+    }
+
+    let report = format_summary(&summary);
+    if summary.failed.is_empty() {
+        print_result(&report);
+    } else {
+        print_warning(&report);
+    }
+    report_session_changes(tool_registry.snapshots()).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_files_respects_token_budget() {
+        let files = vec![
+            (PathBuf::from("a.rs"), 400),
+            (PathBuf::from("b.rs"), 500),
+            (PathBuf::from("c.rs"), 300),
+            (PathBuf::from("huge.rs"), 5_000),
+            (PathBuf::from("d.rs"), 100),
+        ];
+        let batches = batch_files(files, 1_000);
+        assert_eq!(
+            batches,
+            vec![
+                vec![PathBuf::from("a.rs"), PathBuf::from("b.rs")],
+                vec![PathBuf::from("c.rs")],
+                vec![PathBuf::from("huge.rs")],
+                vec![PathBuf::from("d.rs")],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_with_headers_replaces_model_headers() {
+        let diff = "diff --git a/other.rs b/other.rs\nindex 1..2\n--- a/other.rs\n+++ b/other.rs\n@@ -1 +1 @@\n-a\n+b";
+        assert_eq!(with_headers("src/lib.rs", diff), "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-a\n+b\n");
+        assert_eq!(with_headers("x.rs", "@@ -1 +1 @@\n-a\n+b\n"), "--- a/x.rs\n+++ b/x.rs\n@@ -1 +1 @@\n-a\n+b\n");
+    }
+}
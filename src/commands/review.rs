@@ -0,0 +1,265 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+
+use crate::api::chat_api::ChatApi;
+use crate::api::models::ChatCompletionRequest;
+use crate::cli::commands::{CiFormat, ReviewArgs, Severity};
+use crate::commands::prompts::command_messages;
+use crate::config::Config;
+use crate::tui::{print_info, print_result, print_warning, start_spinner};
+
+const MAX_REVIEW_INPUT_CHARS: usize = 60_000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    pub file: String,
+    pub line: u32,
+    #[serde(default)]
+    pub end_line: Option<u32>,
+    pub severity: Severity,
+    #[serde(default)]
+    pub title: Option<String>,
+    pub message: String,
+}
+
+const REVIEW_INSTRUCTIONS: &str = "You are reviewing a code change. Report only concrete problems: bugs, \
+security issues, unhandled errors, and clear maintainability issues. Respond with ONLY a JSON array; each \
+element is {\"file\": path relative to the repository root, \"line\": line number in the new version, \
+\"end_line\": optional, \"severity\": \"error\" | \"warning\" | \"notice\", \"title\": short summary, \
+\"message\": explanation and suggested fix}. Respond with [] if there is nothing to report.";
+
+// Pulls the JSON array of findings out of a model response, tolerating code fences or prose.
+pub fn parse_findings(response: &str) -> Result<Vec<Finding>> {
+    let start = response.find('[').ok_or_else(|| anyhow!("Review response contained no JSON array"))?;
+    let end = response.rfind(']').ok_or_else(|| anyhow!("Review response contained no JSON array"))?;
+    if end < start {
+        bail!("Review response contained no JSON array");
+    }
+    serde_json::from_str(&response[start..=end]).context("Failed to parse review findings")
+}
+
+fn escape_annotation_data(value: &str) -> String {
+    value.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+fn escape_annotation_property(value: &str) -> String {
+    escape_annotation_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+// Formats findings as GitHub Actions workflow commands, e.g. `::error file=a.rs,line=3::msg`.
+pub fn github_annotations(findings: &[Finding]) -> String {
+    findings
+        .iter()
+        .map(|f| {
+            let level = match f.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+                Severity::Notice => "notice",
+            };
+            let mut properties = vec![
+                format!("file={}", escape_annotation_property(&f.file)),
+                format!("line={}", f.line),
+            ];
+            if let Some(end_line) = f.end_line {
+                properties.push(format!("endLine={}", end_line));
+            }
+            if let Some(title) = &f.title {
+                properties.push(format!("title={}", escape_annotation_property(title)));
+            }
+            format!("::{} {}::{}", level, properties.join(","), escape_annotation_data(&f.message))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn fingerprint(finding: &Finding) -> String {
+    // GitLab matches findings across pipelines by fingerprint, so it must not change between builds.
+    let mut hasher = Sha256::new();
+    hasher.update(finding.file.as_bytes());
+    hasher.update([0]);
+    hasher.update(finding.line.to_string().as_bytes());
+    hasher.update([0]);
+    hasher.update(finding.message.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+// Formats findings as a GitLab Code Quality report.
+pub fn gitlab_code_quality(findings: &[Finding]) -> Result<String> {
+    let issues: Vec<serde_json::Value> = findings
+        .iter()
+        .map(|f| {
+            serde_json::json!({
+                "description": match &f.title {
+                    Some(title) => format!("{}: {}", title, f.message),
+                    None => f.message.clone(),
+                },
+                "check_name": "opencode-review",
+                "fingerprint": fingerprint(f),
+                "severity": match f.severity {
+                    Severity::Error => "major",
+                    Severity::Warning => "minor",
+                    Severity::Notice => "info",
+                },
+                "location": {
+                    "path": f.file,
+                    "lines": { "begin": f.line, "end": f.end_line.unwrap_or(f.line) }
+                }
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&issues).context("Failed to serialize code quality report")
+}
+
+fn format_findings(findings: &[Finding]) -> String {
+    findings
+        .iter()
+        .map(|f| {
+            format!(
+                "{}:{} [{:?}] {}{}",
+                f.file,
+                f.line,
+                f.severity,
+                f.title.as_deref().map(|t| format!("{}: ", t)).unwrap_or_default(),
+                f.message
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+async fn collect_review_input(args: &ReviewArgs) -> Result<String> {
+    if !args.files.is_empty() {
+        let mut input = String::new();
+        for file in &args.files {
+            let content = fs::read_to_string(file).with_context(|| format!("Failed to read {}", file))?;
+            let numbered: Vec<String> = content.lines().enumerate().map(|(i, l)| format!("{:>5} {}", i + 1, l)).collect();
+            input.push_str(&format!("File: {}\n```\n{}\n```\n\n", file, numbered.join("\n")));
+        }
+        return Ok(input);
+    }
+    let output = tokio::process::Command::new("git")
+        .args(["diff", "--unified=5", &args.base])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("Failed to run git diff")?;
+    if !output.status.success() {
+        bail!("git diff {} failed: {}", args.base, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(format!("Diff against {}:\n```diff\n{}\n```", args.base, String::from_utf8_lossy(&output.stdout)))
+}
+
+pub async fn handle_review(api_client: &dyn ChatApi, config: Config, args: ReviewArgs) -> Result<()> {
+    let mut input = collect_review_input(&args).await?;
+    if input.trim().is_empty() || input.contains("```diff\n\n```") {
+        if !args.ci {
+            print_info("Nothing to review.");
+        }
+        return Ok(());
+    }
+    if input.len() > MAX_REVIEW_INPUT_CHARS {
+        let mut cut = MAX_REVIEW_INPUT_CHARS;
+        while !input.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        input.truncate(cut);
+        input.push_str("\n[... truncated]");
+    }
+
+    let request = ChatCompletionRequest {
+        model: config.api.big_model.clone(),
+        messages: command_messages(&config, "review", Some(REVIEW_INSTRUCTIONS), input)?,
+        stream: None,
+        temperature: Some(0.0),
+        max_tokens: None,
+        tools: None,
+        tool_choice: None,
+        source_map: None,
+    };
+
+    let spinner = (!args.ci).then(|| start_spinner("Reviewing changes..."));
+    let response = api_client.chat_completion(request).await;
+    if let Some(spinner) = spinner {
+        spinner.finish_and_clear();
+    }
+    let content = response?
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|c| c.message.content)
+        .unwrap_or_default();
+    let findings = parse_findings(&content)?;
+
+    if !args.ci {
+        if findings.is_empty() {
+            print_info("No issues found.");
+        } else {
+            print_result(&format_findings(&findings));
+        }
+        return Ok(());
+    }
+
+    let report = match args.format {
+        CiFormat::Github => github_annotations(&findings),
+        CiFormat::Gitlab => gitlab_code_quality(&findings)?,
+    };
+    match &args.output {
+        Some(path) => fs::write(path, &report).with_context(|| format!("Failed to write report to {}", path))?,
+        None if !report.is_empty() => println!("{}", report),
+        None => {}
+    }
+
+    let failing = findings.iter().filter(|f| f.severity >= args.fail_on).count();
+    if failing > 0 {
+        print_warning(&format!("{} finding(s) at or above the failure threshold.", failing));
+        bail!("Review reported {} blocking finding(s)", failing);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(severity: Severity, message: &str) -> Finding {
+        Finding {
+            file: "src/lib.rs".to_string(),
+            line: 12,
+            end_line: None,
+            severity,
+            title: Some("Unwrap, on None".to_string()),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_findings_from_fenced_response() {
+        let response = "Here you go:\n```json\n[{\"file\": \"a.rs\", \"line\": 3, \"severity\": \"warning\", \"message\": \"x\"}]\n```";
+        let findings = parse_findings(response).unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert!(parse_findings("[]").unwrap().is_empty());
+        assert!(parse_findings("no findings").is_err());
+    }
+
+    #[test]
+    fn test_github_annotations_escape_values() {
+        let output = github_annotations(&[finding(Severity::Error, "100% broken\nsee docs")]);
+        assert_eq!(
+            output,
+            "::error file=src/lib.rs,line=12,title=Unwrap%2C on None::100%25 broken%0Asee docs"
+        );
+    }
+
+    #[test]
+    fn test_gitlab_code_quality() {
+        let report = gitlab_code_quality(&[finding(Severity::Warning, "msg")]).unwrap();
+        let issues: serde_json::Value = serde_json::from_str(&report).unwrap();
+        assert_eq!(issues[0]["severity"], "minor");
+        assert_eq!(issues[0]["location"]["path"], "src/lib.rs");
+        assert_eq!(issues[0]["location"]["lines"]["begin"], 12);
+        assert_eq!(issues[0]["fingerprint"].as_str().unwrap(), format!("{:x}", Sha256::digest(b"src/lib.rs\x0012\x00msg")));
+    }
+}
@@ -1,52 +1,283 @@
 use anyhow::{anyhow, Context, Result};
 use serde_json;
 
-use crate::api::client::ApiClient;
+use crate::api::chat_api::ChatApi;
 use crate::api::models::{ChatCompletionRequest, Message, Role, ToolChoice};
 use crate::cli::commands::RunArgs;
 use crate::config::Config;
 use crate::context::ContextManager;
+use crate::loop_detection::{corrective_message, Repetition, RepetitionDetector};
+use crate::stream_json::emit_response;
 use crate::tools; // For tool_result_format
 use crate::tools::execution::ToolExecutionEngine;
+use crate::tools::compact::compact_tool_definitions;
+use crate::tools::gating::{AgentPhase, ToolGate, EXPLORE_INSTRUCTION, SIDE_EFFECT_FREE_TOOLS};
 use crate::tools::registry::ToolRegistry;
-use crate::tui::{print_error, print_info, print_result, print_warning, start_spinner};
+use crate::commands::worktree::IsolatedWorktree;
+use crate::tui::pager::page_diff;
+use crate::tui::{print_error, print_info, print_result, print_warning, prompt_confirmation, prompt_text, start_spinner};
 use crate::app::generate_source_map;
+use crate::commands::prompts::command_system_prompt;
+use crate::commands::summary::report_session_changes;
+use crate::commands::run_plan::{parse_plan, plan_feedback, plan_protocol, PlanRecord, RunPlan, MAX_PLAN_ATTEMPTS};
+use crate::commands::run_status::{parse_run_status, RunStatus, STATUS_PROTOCOL};
+use crate::commands::verify::{format_verification, verify_run};
+use crate::tools::snapshot::session_diff;
+use crate::shutdown::ShutdownCoordinator;
+use std::collections::HashSet;
 use std::env;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::sync::OnceLock;
 
-const MAX_ITERATIONS: usize = 5;
+const DEFAULT_RUN_SYSTEM_PROMPT: &str = "You are an AI assistant tasked with completing the objective given by the user. \
+    Break down the task into steps and use the available tools to execute those steps. \
+    Respond with the next single tool call required, or report the task status.";
+const STATUS_REMINDER: &str = "Continue with the next tool call, or report the task status as the JSON object described in the instructions.";
+const PLAN_ONLY_INSTRUCTION: &str = "This is a dry run. Tools that only read are executed; any other call is \
+    recorded but not carried out, and its result says so. Treat those calls as having succeeded and continue \
+    with the calls the task needs, then report the task status.";
+const PLAN_ONLY_STUB: &str = "Not executed: this is a dry run. Assume the call succeeded and continue.";
+// The longest argument summary shown per planned call.
+const PLANNED_ARGUMENTS_CHARS: usize = 160;
+
+// A `run --isolated` in progress. It is entered before the context, tool registry and engine
+// are built, so the environment block, path rules and tool policy all see the worktree rather
+// than the user's checkout.
+pub struct IsolatedRun {
+    worktree: IsolatedWorktree,
+    original_dir: PathBuf,
+    cleanup: u64,
+}
+
+impl IsolatedRun {
+    pub async fn enter() -> Result<Self> {
+        let original_dir = env::current_dir().context("Failed to get current directory")?;
+        let worktree = IsolatedWorktree::create(&original_dir).await?;
+        // Ctrl-C drops the run without reaching `worktree.remove()`.
+        let cleanup = ShutdownCoordinator::global().register_cleanup(worktree.remover());
+        print_info(&format!("Working in an isolated worktree at {}", worktree.path().display()));
+        env::set_current_dir(worktree.work_dir()).context("Failed to enter the isolated worktree")?;
+        Ok(IsolatedRun { worktree, original_dir, cleanup })
+    }
+}
 
 pub async fn handle_run(
+    api_client: &dyn ChatApi,
     config: Config,
-    mut context_manager: ContextManager,
+    context_manager: ContextManager,
     tool_registry: &ToolRegistry,
     tool_engine: &ToolExecutionEngine<'_>,
     args: RunArgs,
+    isolated: Option<IsolatedRun>,
 ) -> Result<()> {
-    let api_client = ApiClient::new(config.clone())
-        .context("Failed to create API client (check API key configuration)")?;
     tracing::info!("Processing 'run' command with task: '{}'", args.task_description);
+    tracing::info!("Run session id: {}", run_session_id());
     print_info(&format!("Starting agentic task: {}", args.task_description));
 
-    context_manager.clear_history();
-    context_manager.clear_snippets();
-    let initial_prompt = format!(
-        "You are an AI assistant tasked with completing the following objective: '{}'. \
-        Break down the task into steps and use the available tools to execute those steps. \
-        Respond with the next single tool call required, or indicate if the task is complete.",
-        args.task_description
-    );
-    let system_message = Message {
+    if args.plan_only {
+        print_info("Dry run: only read-only tools are executed; other tool calls are listed instead.");
+        return run_agent_loop(api_client, &config, context_manager, tool_registry, tool_engine, &args).await;
+    }
+    if let Some(isolated) = isolated {
+        return run_isolated(api_client, &config, context_manager, tool_registry, tool_engine, &args, isolated).await;
+    }
+    run_agent_loop(api_client, &config, context_manager, tool_registry, tool_engine, &args).await?;
+    if args.verify || config.run.verify {
+        let diff = session_diff(tool_registry.snapshots(), &env::current_dir().context("Failed to get current directory")?);
+        report_verification(api_client, &config, &args.task_description, &diff).await;
+    }
+    report_session_changes(tool_registry.snapshots()).await?;
+    Ok(())
+}
+
+// The self-evaluation phase: the model checks the run's diff against the task. A failed check
+// is reported but does not fail the run, whose changes are already made.
+async fn report_verification(api_client: &dyn ChatApi, config: &Config, task: &str, diff: &str) {
+    match verify_run(api_client, config, task, diff).await {
+        Ok(verification) if verification.satisfied => print_info(&format_verification(&verification)),
+        Ok(verification) => print_warning(&format_verification(&verification)),
+        Err(e) => print_warning(&format!("Self-check failed: {:#}", e)),
+    }
+}
+
+// Runs the agent in a throwaway worktree, then shows the combined diff and applies it to the
+// user's checkout only if they accept. Without a terminal, or if it no longer applies cleanly,
+// the diff is saved as a patch file instead.
+async fn run_isolated(
+    api_client: &dyn ChatApi,
+    config: &Config,
+    context_manager: ContextManager,
+    tool_registry: &ToolRegistry,
+    tool_engine: &ToolExecutionEngine<'_>,
+    args: &RunArgs,
+    isolated: IsolatedRun,
+) -> Result<()> {
+    let IsolatedRun { worktree, original_dir, cleanup } = isolated;
+    let result = run_agent_loop(api_client, config, context_manager, tool_registry, tool_engine, args).await;
+    env::set_current_dir(&original_dir).context("Failed to leave the isolated worktree")?;
+    if result.is_ok() && (args.verify || config.run.verify) {
+        report_verification(api_client, config, &args.task_description, &worktree.diff().await?).await;
+    }
+
+    let reviewed = review_isolated_changes(&worktree).await;
+    ShutdownCoordinator::global().unregister_cleanup(cleanup);
+    worktree.remove().await?;
+    result.and(reviewed)
+}
+
+async fn review_isolated_changes(worktree: &IsolatedWorktree) -> Result<()> {
+    let diff = worktree.diff().await?;
+    if diff.trim().is_empty() {
+        print_info("The isolated run made no changes.");
+        return Ok(());
+    }
+    print_info("Changes made in the isolated worktree:");
+    page_diff(&diff)?;
+
+    if std::io::stdin().is_terminal() {
+        if !prompt_confirmation("Apply these changes to your working tree?")? {
+            print_info("Discarded the isolated changes.");
+            return Ok(());
+        }
+        match worktree.apply(&diff).await {
+            Ok(()) => {
+                print_info("Applied the changes to your working tree.");
+                return Ok(());
+            }
+            Err(e) => print_warning(&format!("The changes no longer apply cleanly: {:#}", e)),
+        }
+    }
+    let name = worktree.path().file_name().unwrap_or_default().to_string_lossy();
+    let patch = env::temp_dir().join(format!("{}.patch", name));
+    fs::write(&patch, &diff).with_context(|| format!("Failed to save {}", patch.display()))?;
+    print_info(&format!("Saved the changes to {0}; apply them with `git apply {0}`.", patch.display()));
+    Ok(())
+}
+
+// Ends the exploration phase once the user accepts the recorded plan; without a terminal the
+// plan is accepted as is. Rejection feedback goes back to the model to revise the plan.
+fn review_plan(context_manager: &mut ContextManager, plan: &str) -> Result<bool> {
+    print_info(plan);
+    if std::io::stdin().is_terminal() && !prompt_confirmation("Approve this plan and allow changes?")? {
+        let feedback = prompt_text("What should change in the plan?", "")?;
+        context_manager.add_message(user_message(format!("The plan was not approved. {}", feedback).trim_end().to_string()))?;
+        return Ok(false);
+    }
+    context_manager.add_message(Message {
         role: Role::System,
-        content: Some(initial_prompt),
+        content: Some("The plan is approved. All tools are now available; carry it out.".to_string()),
         tool_calls: None,
         tool_call_id: None,
+    })?;
+    print_info("Plan approved; all tools are now available.");
+    Ok(true)
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+// One run per process: its start time in seconds, like interactive session ids, plus the pid
+// so runs started in the same second stay apart.
+fn run_session_id() -> &'static str {
+    static ID: OnceLock<String> = OnceLock::new();
+    ID.get_or_init(|| format!("{}-{}", unix_now(), std::process::id()))
+}
+
+// Best effort: a plan that can't be saved doesn't stop the run.
+fn save_plan(task: &str, plan: RunPlan) {
+    let record = PlanRecord {
+        session_id: run_session_id().to_string(),
+        task: task.to_string(),
+        project_dir: env::current_dir().unwrap_or_default(),
+        approved_at: unix_now(),
+        plan,
     };
-    context_manager.add_message(system_message)?;
+    match record.save() {
+        Ok(path) => print_info(&format!("Saved the approved plan to {}", path.display())),
+        Err(e) => tracing::warn!("Failed to save the approved plan: {:#}", e),
+    }
+}
 
-    let mut task_complete = false;
+// Tools a dry run still executes, so the model plans from real file contents. Everything
+// else, notes, memories and git included, is stubbed like any other change.
+fn runs_in_plan_only(tool: &str) -> bool {
+    SIDE_EFFECT_FREE_TOOLS.contains(&tool)
+}
 
-    for i in 0..MAX_ITERATIONS {
-        print_info(&format!("Iteration {}/{}", i + 1, MAX_ITERATIONS));
+fn summarize_arguments(arguments: &serde_json::Value) -> String {
+    let text = arguments.to_string();
+    if text.chars().count() <= PLANNED_ARGUMENTS_CHARS {
+        return text;
+    }
+    format!("{}...", text.chars().take(PLANNED_ARGUMENTS_CHARS).collect::<String>())
+}
+
+// The tool calls a dry run stubbed, in the order the model made them.
+fn format_planned_calls(calls: &[(String, serde_json::Value)]) -> String {
+    if calls.is_empty() {
+        return "The agent would not change anything: it made no tool calls beyond reading.".to_string();
+    }
+    let lines: Vec<String> = calls
+        .iter()
+        .enumerate()
+        .map(|(i, (tool, arguments))| format!("  {}. {} {}", i + 1, tool, summarize_arguments(arguments)))
+        .collect();
+    format!("Tool calls the agent would make:\n{}", lines.join("\n"))
+}
+
+fn user_message(content: String) -> Message {
+    Message { role: Role::User, content: Some(content), tool_calls: None, tool_call_id: None }
+}
+
+async fn run_agent_loop(
+    api_client: &dyn ChatApi,
+    config: &Config,
+    mut context_manager: ContextManager,
+    tool_registry: &ToolRegistry,
+    tool_engine: &ToolExecutionEngine<'_>,
+    args: &RunArgs,
+) -> Result<()> {
+    context_manager.clear_history();
+    context_manager.clear_snippets();
+    let builtin = (!context_manager.has_pinned_messages()).then_some(DEFAULT_RUN_SYSTEM_PROMPT);
+    if let Some(system_prompt) = command_system_prompt(config, "run", builtin)? {
+        context_manager.pin_system_message(system_prompt)?;
+    }
+    context_manager.pin_system_message(STATUS_PROTOCOL.to_string())?;
+    if args.plan_only {
+        context_manager.pin_system_message(PLAN_ONLY_INSTRUCTION.to_string())?;
+    }
+    let gate = ToolGate::new(&config.tool_gating);
+    let mut phase = gate.initial_phase();
+    // Tools a plan may name: those available once it is approved.
+    let plan_tools: Vec<String> = gate
+        .filter(AgentPhase::Execute, tool_registry.get_tool_definitions().context("Failed to get tool definitions from registry")?)
+        .into_iter()
+        .map(|d| d.function.name)
+        .collect();
+    let mut plan_attempts = 0;
+    if phase == AgentPhase::Explore {
+        context_manager.pin_system_message(EXPLORE_INSTRUCTION.to_string())?;
+        context_manager.pin_system_message(plan_protocol(&plan_tools))?;
+    }
+    context_manager.add_message(user_message(format!("Objective: {}", args.task_description)))?;
+
+    let max_iterations = config.run.max_iterations;
+    let mut outcome = None;
+    let mut repetition = RepetitionDetector::new(config.run.max_repeated_tool_calls);
+
+    let mut sent_in_full = HashSet::new();
+    let mut planned_calls: Vec<(String, serde_json::Value)> = Vec::new();
+    let (mut prompt_tokens, mut completion_tokens, mut tokens_saved) = (0u64, 0u64, 0usize);
+
+    for i in 0..max_iterations {
+        print_info(&format!("Iteration {}/{}", i + 1, max_iterations));
+        if let Some(notice) = context_manager.take_eviction_notice() {
+            print_info(&notice);
+        }
         tracing::debug!("Agentic loop iteration {} starting.", i + 1);
 
         let messages_for_api = context_manager.construct_api_messages()?;
@@ -55,8 +286,8 @@ pub async fn handle_run(
             break;
         }
 
-        let tool_definitions = tool_registry.get_tool_definitions()
-            .context("Failed to get tool definitions from registry")?;
+        let tool_definitions = gate.filter(phase, tool_registry.get_tool_definitions()
+            .context("Failed to get tool definitions from registry")?);
 
         let current_dir = env::current_dir().context("Failed to get current directory for source map generation")?;
         let source_map = match generate_source_map(&current_dir) {
@@ -68,6 +299,19 @@ pub async fn handle_run(
             }
         };
 
+        let tool_definitions = if config.context.compact_tool_definitions {
+            // Only tools whose full definition the model has already seen are shortened.
+            let (seen, new): (Vec<_>, Vec<_>) =
+                tool_definitions.into_iter().partition(|d| sent_in_full.contains(&d.function.name));
+            let compact = compact_tool_definitions(&seen);
+            let full_tokens = context_manager.count_tokens(&serde_json::to_string(&seen)?);
+            tokens_saved += full_tokens.saturating_sub(context_manager.count_tokens(&serde_json::to_string(&compact)?));
+            sent_in_full.extend(new.iter().map(|d| d.function.name.clone()));
+            compact.into_iter().chain(new).collect()
+        } else {
+            tool_definitions
+        };
+
         let request = ChatCompletionRequest {
             model: config.api.default_model.clone(),
             messages: messages_for_api,
@@ -76,21 +320,51 @@ pub async fn handle_run(
             max_tokens: None,
             tools: Some(tool_definitions),
             tool_choice: Some(ToolChoice::Auto),
-            source_map: source_map,
+            source_map,
         };
 
         tracing::debug!("Sending agent request to API: {:?}", request);
-        let spinner = start_spinner("Waiting for AI step...");
+        let spinner = match tool_registry.todos().in_progress() {
+            Some(current) => start_spinner(&format!("Working on: {}", current)),
+            None => start_spinner("Waiting for AI step..."),
+        };
         let result = api_client.chat_completion(request).await;
         spinner.finish_and_clear();
 
         match result {
             Ok(response) => {
                 tracing::debug!("Received agent response from API: {:?}", response);
+                emit_response(&config.api.default_model, &response);
+                if let Some(usage) = &response.usage {
+                    prompt_tokens += u64::from(usage.prompt_tokens);
+                    completion_tokens += u64::from(usage.completion_tokens);
+                }
                 if let Some(choice) = response.choices.first() {
                     context_manager.add_message(choice.message.clone())?;
                     tracing::debug!("Added assistant message to context.");
 
+                    // Checked before the calls run, so a call the loop is stuck on is not run again.
+                    let repeat = repetition.observe(choice.message.tool_calls.as_deref().unwrap_or_default());
+                    if let Repetition::Stuck { tool, count } = &repeat {
+                        print_error(&format!("Agentic task stuck: the same {} call was made {} times in a row.", tool, count));
+                        tracing::warn!("Stopping agentic loop after {} identical {} calls.", count, tool);
+                        // Every call still gets a result, so the saved history stays well-formed.
+                        for tool_call in choice.message.tool_calls.iter().flatten() {
+                            let skipped = tools::tool_result_format::format_tool_result(
+                                &tool_call.function.name,
+                                &serde_json::Value::Null,
+                                Some("Not run: the same call was already made repeatedly."),
+                            );
+                            context_manager.add_message(Message {
+                                role: Role::Tool,
+                                content: Some(serde_json::to_string(&skipped)?),
+                                tool_calls: None,
+                                tool_call_id: Some(tool_call.id.clone()),
+                            })?;
+                        }
+                        break;
+                    }
+
                     let mut tool_results_with_ids: Vec<(String, serde_json::Value)> = Vec::new();
                     let mut tool_execution_occurred = false;
                     let mut tool_execution_failed = false;
@@ -101,10 +375,9 @@ pub async fn handle_run(
                             let tool_call_id = tool_call.id.clone();
                             let tool_name = &tool_call.function.name;
                             let arguments_str = &tool_call.function.arguments;
-                            print_info(&format!("Attempting tool call: {} with ID: {}", tool_name, tool_call_id));
                             tracing::info!("Attempting tool call: {} (ID: {})", tool_name, tool_call_id);
 
-                            let arguments_value = match serde_json::from_str(arguments_str) {
+                            let arguments_value: serde_json::Value = match serde_json::from_str(arguments_str) {
                                 Ok(val) => val,
                                 Err(e) => {
                                     let error_msg = format!("Failed to parse JSON arguments for tool '{}': {}", tool_name, e);
@@ -122,12 +395,36 @@ pub async fn handle_run(
                                 }
                             };
 
-                            let tool_result = tool_engine.execute_tool_call(tool_name, arguments_value).await;
+                            if !gate.allows(phase, tool_name) {
+                                let error_msg = format!("{} is not available until the plan is approved.", tool_name);
+                                let error_value = tools::tool_result_format::format_tool_result(
+                                    tool_name,
+                                    &serde_json::Value::Null,
+                                    Some(&error_msg),
+                                );
+                                tool_results_with_ids.push((tool_call_id, error_value));
+                                continue;
+                            }
+                            if args.plan_only && !runs_in_plan_only(tool_name) {
+                                print_info(&format!("Would call {} {}", tool_name, summarize_arguments(&arguments_value)));
+                                let stub = tools::tool_result_format::format_tool_result(
+                                    tool_name,
+                                    &serde_json::json!({ "dry_run": true, "message": PLAN_ONLY_STUB }),
+                                    None,
+                                );
+                                planned_calls.push((tool_name.clone(), arguments_value));
+                                tool_results_with_ids.push((tool_call_id, stub));
+                                continue;
+                            }
+                            let tool_result = tool_engine.execute_tool_call(tool_name, arguments_value.clone()).await;
 
                             // The match block below handles both Ok and Err for storing the result.
                             // This first match block for logging/checking is removed to potentially fix E0282.
                              match tool_result { // This match now starts at the original line 134
-                                Ok(value) => tool_results_with_ids.push((tool_call_id, value)),
+                                Ok(value) => {
+                                    let value = context_manager.dedupe_file_read(tool_name, &arguments_value, &tool_call_id, value);
+                                    tool_results_with_ids.push((tool_call_id, value))
+                                }
                                 Err(e) => {
                                      let error_value = tools::tool_result_format::format_tool_result(
                                         tool_name,
@@ -155,30 +452,80 @@ pub async fn handle_run(
                         context_manager.add_message(tool_message)?;
                     }
 
+                    if let Repetition::Repeated { tool, count } = repeat {
+                        print_warning(&format!("The agent repeated the same {} call {} times.", tool, count));
+                        context_manager.add_message(Message {
+                            role: Role::System,
+                            content: Some(corrective_message(&tool, count)),
+                            tool_calls: None,
+                            tool_call_id: None,
+                        })?;
+                    }
+
                     if tool_execution_failed {
                         print_error("Agentic task failed due to tool execution error.");
                         tracing::error!("Agentic task failed due to tool execution error.");
                         break;
                     } else if !tool_execution_occurred {
-                        if let Some(content) = &choice.message.content {
-                            if !content.is_empty() {
-                                print_result(&format!("AI Response: {}", content));
-                                if content.to_lowercase().contains("task complete") || content.to_lowercase().contains("task finished") {
-                                    print_info("Task marked as complete by AI.");
-                                    task_complete = true;
-                                    break;
+                        let content = choice.message.content.as_deref().unwrap_or_default();
+                        if !content.is_empty() {
+                            print_result(&format!("AI Response: {}", content));
+                        }
+                        let status = parse_run_status(content);
+                        let gives_up = matches!(status, Some(RunStatus::Blocked { .. } | RunStatus::NeedsInput { .. }));
+                        if phase == AgentPhase::Explore && !gives_up {
+                            match parse_plan(content, &plan_tools) {
+                                Ok(plan) if args.plan_only => {
+                                    // A dry run shows the plan and goes on to the calls it implies
+                                    // without asking for approval or saving it.
+                                    print_info(&plan.render());
+                                    context_manager.add_message(Message {
+                                        role: Role::System,
+                                        content: Some("The plan is accepted for this dry run; make the tool calls it needs.".to_string()),
+                                        tool_calls: None,
+                                        tool_call_id: None,
+                                    })?;
+                                    phase = AgentPhase::Execute;
+                                    tool_registry.todos().set(plan.todo_items());
+                                }
+                                Ok(plan) => {
+                                    plan_attempts = 0;
+                                    if review_plan(&mut context_manager, &plan.render())? {
+                                        phase = AgentPhase::Execute;
+                                        tool_registry.todos().set(plan.todo_items());
+                                        save_plan(&args.task_description, plan);
+                                    }
+                                }
+                                Err(problems) => {
+                                    plan_attempts += 1;
+                                    print_warning(&format!("Rejected a malformed plan: {}", problems.join("; ")));
+                                    if plan_attempts >= MAX_PLAN_ATTEMPTS {
+                                        print_error(&format!("Agentic task stopped: no valid plan after {} attempts.", plan_attempts));
+                                        break;
+                                    }
+                                    context_manager.add_message(user_message(plan_feedback(&problems)))?;
                                 }
-                            } else {
-                                 print_warning("AI responded with empty content and no tool calls.");
-                                 tracing::warn!("AI responded with empty content and no tool calls in agentic loop.");
-                                 print_error("Agentic task stalled: AI provided no action or completion signal.");
-                                 break;
                             }
-                        } else {
-                             print_warning("AI responded with no content and no tool calls.");
-                             tracing::warn!("AI responded with None content and no tool calls in agentic loop.");
-                             print_error("Agentic task stalled: AI provided no action or completion signal.");
-                             break;
+                            continue;
+                        }
+                        match status {
+                            Some(RunStatus::NeedsInput { reason }) if std::io::stdin().is_terminal() => {
+                                let answer = prompt_text(&format!("The agent asks: {}", reason), "")?;
+                                context_manager.add_message(user_message(answer))?;
+                            }
+                            Some(status) => {
+                                outcome = Some(status);
+                                break;
+                            }
+                            None if content.is_empty() => {
+                                print_error("Agentic task stalled: AI provided no action or completion signal.");
+                                tracing::warn!("AI responded with no content and no tool calls in agentic loop.");
+                                break;
+                            }
+                            None => {
+                                tracing::debug!("No status object in reply; asking the model to continue.");
+                                context_manager.add_message(user_message(STATUS_REMINDER.to_string()))?;
+                            }
                         }
                     }
                 } else {
@@ -195,12 +542,61 @@ pub async fn handle_run(
         }
     }
 
-    if task_complete {
-         print_info("Agentic task finished successfully.");
-         tracing::info!("Agentic task finished successfully.");
-    } else {
-         print_warning(&format!("Agentic task stopped after {} iterations.", MAX_ITERATIONS));
-         tracing::warn!("Agentic task stopped after max iterations.");
+    let mut usage_report = format!(
+        "Token usage: {} prompt + {} completion = {} total",
+        prompt_tokens,
+        completion_tokens,
+        prompt_tokens + completion_tokens
+    );
+    if tokens_saved > 0 {
+        usage_report.push_str(&format!("; compact tool definitions saved ~{} prompt tokens", tokens_saved));
+    }
+    print_info(&usage_report);
+    if args.plan_only {
+        print_result(&format_planned_calls(&planned_calls));
+    }
+
+    match outcome {
+        Some(RunStatus::Complete { reason }) => {
+            print_info("Agentic task finished successfully.");
+            if !reason.is_empty() {
+                print_info(&reason);
+            }
+            tracing::info!("Agentic task finished successfully.");
+        }
+        Some(RunStatus::Blocked { reason }) => {
+            print_warning(&format!("Agentic task blocked: {}", reason));
+            tracing::warn!("Agentic task blocked: {}", reason);
+        }
+        Some(RunStatus::NeedsInput { reason }) => {
+            print_warning(&format!("Agentic task needs input: {}", reason));
+            tracing::warn!("Agentic task stopped waiting for input without a terminal.");
+        }
+        None => {
+            print_warning(&format!("Agentic task stopped after {} iterations.", max_iterations));
+            tracing::warn!("Agentic task stopped without reporting a status.");
+        }
     }
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_only_stubs_changes_and_lists_calls() {
+        assert!(runs_in_plan_only("FileReadTool"));
+        assert!(!runs_in_plan_only("FileWriteTool"));
+        assert!(!runs_in_plan_only("MemoryTool"));
+        assert!(!runs_in_plan_only("GitTool"));
+        assert!(!runs_in_plan_only("AskUserTool"));
+
+        let calls = vec![
+            ("FileWriteTool".to_string(), serde_json::json!({"path": "src/lib.rs"})),
+            ("ShellTool".to_string(), serde_json::json!({"command": "x".repeat(200)})),
+        ];
+        let listing = format_planned_calls(&calls);
+        assert!(listing.starts_with("Tool calls the agent would make:\n  1. FileWriteTool {\"path\":\"src/lib.rs\"}\n  2. ShellTool {\"command\":\"xxx"));
+        assert!(listing.ends_with("..."));
+    }
+}
@@ -0,0 +1,203 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::global_config_dir;
+use crate::context::session::SESSION_DIR;
+use crate::tools::todo::{TodoItem, TodoStatus};
+
+const PLAN_DIR: &str = "plans";
+// Malformed plans sent back to the model before the run gives up.
+pub const MAX_PLAN_ATTEMPTS: usize = 3;
+
+// Pinned while a gated run explores. `tools` are every tool the run may use once the plan is
+// approved, which the explore phase does not expose.
+pub fn plan_protocol(tools: &[String]) -> String {
+    format!(
+        "End the exploration phase with a reply containing only your plan as a JSON object: \
+{{\"steps\": [{{\"description\": \"what to do\", \"tools\": [\"tools the step will call\"]}}], \
+\"success_criteria\": [\"checkable conditions that show the objective is met\"]}}. The plan is \
+validated and sent back to you if it does not match. Tools available once the plan is approved: {}.",
+        tools.join(", ")
+    )
+}
+
+fn plan_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "required": ["steps", "success_criteria"],
+        "additionalProperties": false,
+        "properties": {
+            "steps": {
+                "type": "array",
+                "minItems": 1,
+                "maxItems": 30,
+                "items": {
+                    "type": "object",
+                    "required": ["description", "tools"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "description": { "type": "string", "minLength": 1 },
+                        "tools": { "type": "array", "items": { "type": "string" } }
+                    }
+                }
+            },
+            "success_criteria": {
+                "type": "array",
+                "minItems": 1,
+                "items": { "type": "string", "minLength": 1 }
+            }
+        }
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanStep {
+    pub description: String,
+    pub tools: Vec<String>,
+}
+
+// The plan a gated run commits to before it may change anything.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunPlan {
+    pub steps: Vec<PlanStep>,
+    pub success_criteria: Vec<String>,
+}
+
+impl RunPlan {
+    pub fn render(&self) -> String {
+        let mut text = String::from("Plan:");
+        for (i, step) in self.steps.iter().enumerate() {
+            text.push_str(&format!("\n  {}. {}", i + 1, step.description));
+            if !step.tools.is_empty() {
+                text.push_str(&format!(" ({})", step.tools.join(", ")));
+            }
+        }
+        text.push_str("\nSuccess criteria:");
+        for criterion in &self.success_criteria {
+            text.push_str(&format!("\n  - {}", criterion));
+        }
+        text
+    }
+
+    // The steps as the run's to-do list, so progress is tracked as with a TodoTool plan.
+    pub fn todo_items(&self) -> Vec<TodoItem> {
+        self.steps
+            .iter()
+            .map(|step| TodoItem { content: step.description.clone(), status: TodoStatus::Pending })
+            .collect()
+    }
+}
+
+// The plan in a model reply, or what is wrong with it. The plan is the last JSON object with
+// a "steps" key; each `{` is tried from the end, as for status objects.
+pub fn parse_plan(content: &str, tools: &[String]) -> Result<RunPlan, Vec<String>> {
+    let candidate = content.rfind('}').and_then(|end| {
+        content[..end].rmatch_indices('{').find_map(|(start, _)| {
+            serde_json::from_str::<Value>(&content[start..=end]).ok().filter(|value| value.get("steps").is_some())
+        })
+    });
+    let Some(value) = candidate else {
+        return Err(vec!["no JSON object with a \"steps\" key was found".to_string()]);
+    };
+    let validator = jsonschema::validator_for(&plan_schema()).map_err(|e| vec![format!("invalid plan schema: {}", e)])?;
+    let errors: Vec<String> = validator
+        .iter_errors(&value)
+        .map(|e| match e.instance_path.to_string() {
+            path if path.is_empty() => e.to_string(),
+            path => format!("{} (at {})", e, path),
+        })
+        .collect();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let plan: RunPlan = serde_json::from_value(value).map_err(|e| vec![e.to_string()])?;
+    let unknown: Vec<String> = plan
+        .steps
+        .iter()
+        .flat_map(|step| &step.tools)
+        .filter(|tool| !tools.contains(tool))
+        .map(|tool| format!("unknown tool '{}'", tool))
+        .collect();
+    if unknown.is_empty() { Ok(plan) } else { Err(unknown) }
+}
+
+pub fn plan_feedback(problems: &[String]) -> String {
+    format!(
+        "The plan could not be accepted: {}. Reply with only the corrected plan as the JSON object described in the instructions.",
+        problems.join("; ")
+    )
+}
+
+// An approved plan kept for auditing what a run set out to do. `session_id` names the run that
+// approved it; records saved before it existed read as empty.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanRecord {
+    #[serde(default)]
+    pub session_id: String,
+    pub task: String,
+    pub project_dir: PathBuf,
+    pub approved_at: u64,
+    pub plan: RunPlan,
+}
+
+impl PlanRecord {
+    // Written to `sessions/plans/<session_id>-<n>.json` in the config directory.
+    pub fn save(&self) -> Result<PathBuf> {
+        let dir = global_config_dir().context("Could not determine the config directory")?.join(SESSION_DIR).join(PLAN_DIR);
+        self.save_in(&dir)
+    }
+
+    // `n` is the first number not taken yet; the file is created exclusively, so a second
+    // approval never overwrites the first.
+    fn save_in(&self, dir: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+        let json = serde_json::to_string_pretty(self)?;
+        for n in 1.. {
+            let path = dir.join(format!("{}-{}.json", self.session_id, n));
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(json.as_bytes()).with_context(|| format!("Failed to write plan {:?}", path))?;
+                    return Ok(path);
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e).with_context(|| format!("Failed to write plan {:?}", path)),
+            }
+        }
+        unreachable!("plan file numbers are unbounded")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plan_validates_schema_and_tools() {
+        let tools = vec!["FileReadTool".to_string(), "FileWriteTool".to_string()];
+        let reply = "Here is the plan:\n```json\n{\"steps\": [{\"description\": \"Fix the parser\", \"tools\": [\"FileWriteTool\"]}], \"success_criteria\": [\"cargo test passes\"]}\n```";
+        let plan = parse_plan(reply, &tools).unwrap();
+        assert_eq!(plan.steps[0].description, "Fix the parser");
+        assert_eq!(plan.render(), "Plan:\n  1. Fix the parser (FileWriteTool)\nSuccess criteria:\n  - cargo test passes");
+
+        let missing_criteria = parse_plan("{\"steps\": [{\"description\": \"x\", \"tools\": []}]}", &tools).unwrap_err();
+        assert!(missing_criteria[0].contains("success_criteria"), "{:?}", missing_criteria);
+        let unknown = parse_plan("{\"steps\": [{\"description\": \"x\", \"tools\": [\"ShellTool\"]}], \"success_criteria\": [\"ok\"]}", &tools);
+        assert_eq!(unknown, Err(vec!["unknown tool 'ShellTool'".to_string()]));
+        assert!(parse_plan("I will fix it.", &tools).is_err());
+    }
+
+    #[test]
+    fn test_plan_records_from_one_session_do_not_overwrite_each_other() {
+        let dir = tempfile::tempdir().unwrap();
+        let plan = parse_plan("{\"steps\": [{\"description\": \"x\", \"tools\": []}], \"success_criteria\": [\"ok\"]}", &[]).unwrap();
+        let record = PlanRecord { session_id: "1700000000-42".into(), task: "t".into(), project_dir: PathBuf::new(), approved_at: 1, plan };
+        let first = record.save_in(dir.path()).unwrap();
+        let second = record.save_in(dir.path()).unwrap();
+        assert_eq!(first.file_name().unwrap(), "1700000000-42-1.json");
+        assert_eq!(second.file_name().unwrap(), "1700000000-42-2.json");
+    }
+}
@@ -0,0 +1,52 @@
+use serde::Deserialize;
+
+// Pinned for every `opencode run`, after the (possibly user-supplied) system prompt, so the
+// loop can tell a finished task from a stuck one without guessing from prose.
+pub const STATUS_PROTOCOL: &str = "When you stop calling tools, end your reply with a JSON object \
+reporting the task status, on its own line: {\"status\": \"complete\" | \"blocked\" | \"needs_input\", \
+\"reason\": \"...\"}. Use \"complete\" only when the objective is fully done, \"blocked\" when it cannot \
+be done (say why), and \"needs_input\" when you need an answer from the user (the reason is the question).";
+
+// The status object a model reports under STATUS_PROTOCOL.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RunStatus {
+    Complete {
+        #[serde(default)]
+        reason: String,
+    },
+    Blocked {
+        #[serde(default)]
+        reason: String,
+    },
+    NeedsInput {
+        #[serde(default)]
+        reason: String,
+    },
+}
+
+// The last status object in `content`. Replies are usually prose followed by the object,
+// possibly fenced, so each `{` is tried from the end until one parses.
+pub fn parse_run_status(content: &str) -> Option<RunStatus> {
+    let end = content.rfind('}')?;
+    content[..end]
+        .rmatch_indices('{')
+        .find_map(|(start, _)| serde_json::from_str(&content[start..=end]).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_run_status_after_prose() {
+        let reply = "Updated the parser and the tests pass.\n```json\n{\"status\": \"complete\", \"reason\": \"done\"}\n```";
+        assert_eq!(parse_run_status(reply), Some(RunStatus::Complete { reason: "done".to_string() }));
+        assert_eq!(
+            parse_run_status("{\"status\": \"needs_input\", \"reason\": \"Which branch?\"}"),
+            Some(RunStatus::NeedsInput { reason: "Which branch?".to_string() })
+        );
+        assert_eq!(parse_run_status("Task complete!"), None);
+        assert_eq!(parse_run_status("{\"status\": \"finished\"}"), None);
+    }
+}
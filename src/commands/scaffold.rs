@@ -0,0 +1,237 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::api::chat_api::ChatApi;
+use crate::api::models::{ChatCompletionRequest, Message, Role};
+use crate::cli::commands::NewArgs;
+use crate::config::{global_config_dir, Config};
+use crate::tools::execution::ToolExecutionEngine;
+use crate::tui::{print_info, print_result, prompt_confirmation, start_spinner};
+
+const TEMPLATE_DIR: &str = "templates";
+
+// A project template: a short description for listings and the instructions the model follows
+// when planning the scaffold. User templates are `<config dir>/OpenCode/templates/<name>.toml`
+// and replace built-ins of the same name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScaffoldTemplate {
+    #[serde(default)]
+    pub name: String,
+    pub description: String,
+    pub instructions: String,
+}
+
+const BUILTIN_TEMPLATES: &[(&str, &str, &str)] = &[
+    (
+        "rust-cli",
+        "Rust command-line application using clap",
+        "A Rust 2021 binary crate. Use clap (derive) for argument parsing and anyhow for errors. Include \
+         Cargo.toml, src/main.rs with a small example subcommand, a README.md with usage, and a .gitignore \
+         ignoring /target.",
+    ),
+    (
+        "rust-lib",
+        "Rust library crate with docs and tests",
+        "A Rust 2021 library crate. Include Cargo.toml, src/lib.rs with a documented example function and \
+         a #[cfg(test)] module, tests/integration.rs exercising the public API, a README.md and a .gitignore \
+         ignoring /target.",
+    ),
+    (
+        "axum-service",
+        "HTTP service using axum and tokio",
+        "A Rust 2021 binary crate for an HTTP service using axum, tokio (full features), serde and tracing. \
+         Include Cargo.toml, src/main.rs that binds to the address in the PORT environment variable (default \
+         3000), src/routes.rs with a GET /health route and one JSON example route, a README.md and a \
+         .gitignore ignoring /target.",
+    ),
+];
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ScaffoldFile {
+    pub path: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ScaffoldPlan {
+    pub files: Vec<ScaffoldFile>,
+    #[serde(default)]
+    pub next_steps: Vec<String>,
+}
+
+fn builtin_templates() -> Vec<ScaffoldTemplate> {
+    BUILTIN_TEMPLATES
+        .iter()
+        .map(|(name, description, instructions)| ScaffoldTemplate {
+            name: name.to_string(),
+            description: description.to_string(),
+            instructions: instructions.to_string(),
+        })
+        .collect()
+}
+
+// Built-in templates overlaid with the user's, sorted by name.
+pub fn load_templates(user_dir: Option<&Path>) -> Vec<ScaffoldTemplate> {
+    let mut templates = builtin_templates();
+    let entries = user_dir.and_then(|dir| fs::read_dir(dir).ok());
+    for path in entries.into_iter().flatten().filter_map(|e| e.ok()).map(|e| e.path()) {
+        if path.extension().is_none_or(|ext| ext != "toml") {
+            continue;
+        }
+        let parsed = fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| toml::from_str::<ScaffoldTemplate>(&content).map_err(anyhow::Error::from));
+        match parsed {
+            Ok(mut template) => {
+                template.name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+                templates.retain(|t| t.name != template.name);
+                templates.push(template);
+            }
+            Err(e) => tracing::error!("Skipping invalid template {:?}: {}", path, e),
+        }
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    templates
+}
+
+// Pulls the scaffold plan object out of a model response, tolerating code fences or prose.
+pub fn parse_plan(response: &str) -> Result<ScaffoldPlan> {
+    let start = response.find('{').ok_or_else(|| anyhow!("Scaffold response contained no JSON object"))?;
+    let end = response.rfind('}').ok_or_else(|| anyhow!("Scaffold response contained no JSON object"))?;
+    if end < start {
+        bail!("Scaffold response contained no JSON object");
+    }
+    let plan: ScaffoldPlan = serde_json::from_str(&response[start..=end]).context("Failed to parse scaffold plan")?;
+    if plan.files.is_empty() {
+        bail!("Scaffold plan contained no files");
+    }
+    for file in &plan.files {
+        let path = Path::new(&file.path);
+        if path.as_os_str().is_empty() || !path.components().all(|c| matches!(c, Component::Normal(_))) {
+            bail!("Scaffold plan contains a path outside the project: '{}'", file.path);
+        }
+    }
+    Ok(plan)
+}
+
+fn scaffold_prompt(template: &ScaffoldTemplate, name: &str) -> String {
+    format!(
+        "Plan the initial files for a new project named `{}` from the `{}` template.\n\n\
+         Template: {}\n\n\
+         Respond with ONLY a JSON object: {{\"files\": [{{\"path\": path relative to the project root, \
+         \"content\": complete file content}}], \"next_steps\": [shell commands the user should run next]}}. \
+         Use `{}` as the package name, keep the project minimal but buildable, and pin dependencies to \
+         current major versions.",
+        name, template.name, template.instructions, name
+    )
+}
+
+async fn write_plan(tool_engine: &ToolExecutionEngine<'_>, root: &Path, plan: &ScaffoldPlan) -> Result<()> {
+    for file in &plan.files {
+        let path = root.join(&file.path);
+        if let Some(parent) = path.parent() {
+            tool_engine
+                .execute_tool_call("CreateDirectoryTool", serde_json::json!({ "path": parent.to_string_lossy() }))
+                .await
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        tool_engine
+            .execute_tool_call(
+                "FileWriteTool",
+                serde_json::json!({ "path": path.to_string_lossy(), "content": file.content }),
+            )
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+pub async fn handle_new(
+    api_client: &dyn ChatApi,
+    config: Config,
+    tool_engine: &ToolExecutionEngine<'_>,
+    args: NewArgs,
+) -> Result<()> {
+    let user_dir = global_config_dir().map(|dir| dir.join(TEMPLATE_DIR));
+    let templates = load_templates(user_dir.as_deref());
+    let template = templates.iter().find(|t| t.name == args.template).ok_or_else(|| {
+        let available = templates
+            .iter()
+            .map(|t| format!("  {} - {}", t.name, t.description))
+            .collect::<Vec<_>>()
+            .join("\n");
+        anyhow!("Unknown template '{}'. Available templates:\n{}", args.template, available)
+    })?;
+
+    let root = args.dir.map(PathBuf::from).unwrap_or_else(|| PathBuf::from(&args.name));
+    if root.exists() && fs::read_dir(&root).map(|mut d| d.next().is_some()).unwrap_or(true) {
+        bail!("'{}' already exists and is not empty", root.display());
+    }
+
+    let request = ChatCompletionRequest {
+        model: config.api.default_model.clone(),
+        messages: vec![Message {
+            role: Role::User,
+            content: Some(scaffold_prompt(template, &args.name)),
+            tool_calls: None,
+            tool_call_id: None,
+        }],
+        stream: None,
+        temperature: Some(0.2),
+        max_tokens: None,
+        tools: None,
+        tool_choice: None,
+        source_map: None,
+    };
+    let spinner = start_spinner(&format!("Planning {} project '{}'...", template.name, args.name));
+    let response = api_client.chat_completion(request).await;
+    spinner.finish_and_clear();
+    let content = response?.choices.into_iter().next().and_then(|c| c.message.content).unwrap_or_default();
+    let plan = parse_plan(&content)?;
+
+    let listing = plan.files.iter().map(|f| format!("  {}", root.join(&f.path).display())).collect::<Vec<_>>();
+    print_result(&format!("Files to create:\n{}", listing.join("\n")));
+    if !args.yes && !prompt_confirmation(&format!("Create {} files in {}?", plan.files.len(), root.display()))? {
+        print_info("Scaffold cancelled.");
+        return Ok(());
+    }
+
+    write_plan(tool_engine, &root, &plan).await?;
+    print_info(&format!("Created {} from the {} template.", root.display(), template.name));
+    if !plan.next_steps.is_empty() {
+        print_result(&format!("Next steps:\n  cd {}\n  {}", root.display(), plan.next_steps.join("\n  ")));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plan_rejects_escaping_paths() {
+        let plan = parse_plan("```json\n{\"files\": [{\"path\": \"src/main.rs\", \"content\": \"fn main() {}\"}], \"next_steps\": [\"cargo run\"]}\n```").unwrap();
+        assert_eq!(plan.files[0].path, "src/main.rs");
+        assert_eq!(plan.next_steps, vec!["cargo run"]);
+
+        assert!(parse_plan("{\"files\": [{\"path\": \"../evil\", \"content\": \"\"}]}").is_err());
+        assert!(parse_plan("{\"files\": [{\"path\": \"/etc/passwd\", \"content\": \"\"}]}").is_err());
+        assert!(parse_plan("{\"files\": []}").is_err());
+    }
+
+    #[test]
+    fn test_user_templates_override_builtins() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("rust-cli.toml"), "description = \"Ours\"\ninstructions = \"Use argh.\"\n").unwrap();
+        fs::write(dir.path().join("svelte.toml"), "description = \"Svelte app\"\ninstructions = \"SvelteKit.\"\n").unwrap();
+        fs::write(dir.path().join("broken.toml"), "description = 1").unwrap();
+
+        let templates = load_templates(Some(dir.path()));
+        let names: Vec<&str> = templates.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["axum-service", "rust-cli", "rust-lib", "svelte"]);
+        assert_eq!(templates[1].instructions, "Use argh.");
+    }
+}
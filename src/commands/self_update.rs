@@ -0,0 +1,259 @@
+use anyhow::{bail, Context, Result};
+use reqwest::Client;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::cli::commands::SelfUpdateArgs;
+use crate::config::Config;
+use crate::tui::{print_info, print_warning, prompt_confirmation, start_spinner};
+
+const LATEST_RELEASE_API: &str = "https://api.github.com/repos/dallenpyrah/OpenCode/releases/latest";
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+// Binaries are far larger than fetched pages, so downloads get their own timeout.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+// Hex Ed25519 key the release checksums are signed with, set when release binaries are built.
+// Builds without it can verify only the checksum.
+const RELEASE_PUBLIC_KEY: Option<&str> = option_env!("OPENCODE_RELEASE_PUBLIC_KEY");
+
+// Returned by `self-update --check` when a newer release exists, so the process exits with
+// `EXIT_CODE` rather than the generic failure status.
+#[derive(Error, Debug)]
+#[error("opencode {tag} is available")]
+pub struct UpdateAvailable {
+    pub tag: String,
+}
+
+impl UpdateAvailable {
+    pub const EXIT_CODE: i32 = 100;
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+// Release binaries are named `opencode-<os>-<arch>`, e.g. `opencode-macos-aarch64`.
+pub fn platform_asset_name() -> String {
+    format!("opencode-{}-{}{}", std::env::consts::OS, std::env::consts::ARCH, std::env::consts::EXE_SUFFIX)
+}
+
+// Numeric version components of a tag like `v1.4.0`; pre-release suffixes are ignored.
+fn version_parts(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+pub fn is_newer(tag: &str, current: &str) -> bool {
+    version_parts(tag) > version_parts(current)
+}
+
+// The expected SHA-256 of `asset` from a `<asset>.sha256` file or a `SHA256SUMS` listing,
+// both in `sha256sum` output format.
+pub fn expected_checksum(listing: &str, asset: &str) -> Option<String> {
+    listing.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let hash = fields.next()?;
+        let name = fields.next().map(|name| name.trim_start_matches('*'));
+        (name.is_none() || name == Some(asset)).then(|| hash.to_lowercase())
+    })
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    let hex = hex.trim();
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+// Checks the detached Ed25519 `signature` over the checksum `listing` against the hex
+// `public_key`. The signature may be raw bytes or hex text.
+pub fn verify_signature(public_key: &str, listing: &[u8], signature: &[u8]) -> Result<()> {
+    let key = decode_hex(public_key).context("The release public key is not valid hex")?;
+    let signature = match std::str::from_utf8(signature).ok().and_then(decode_hex) {
+        Some(decoded) => decoded,
+        None => signature.to_vec(),
+    };
+    UnparsedPublicKey::new(&ED25519, key)
+        .verify(listing, &signature)
+        .map_err(|_| anyhow::anyhow!("The checksum signature does not match the release key"))
+}
+
+// The package manager that owns `exe`, with the command that updates it. Replacing a
+// managed binary would leave the manager's records out of date.
+pub fn package_manager(exe: &Path) -> Option<(&'static str, &'static str)> {
+    let path = exe.to_string_lossy().replace('\\', "/").to_lowercase();
+    if path.contains("/cellar/") || path.contains("/homebrew/") || path.contains("/linuxbrew/") {
+        Some(("Homebrew", "brew upgrade opencode"))
+    } else if path.contains("/scoop/") {
+        Some(("Scoop", "scoop update opencode"))
+    } else {
+        None
+    }
+}
+
+async fn download(client: &Client, url: &str) -> Result<Vec<u8>> {
+    let response = client.get(url).timeout(DOWNLOAD_TIMEOUT).send().await.with_context(|| format!("Failed to download {}", url))?;
+    let response = response.error_for_status().with_context(|| format!("Failed to download {}", url))?;
+    Ok(response.bytes().await.with_context(|| format!("Failed to read {}", url))?.to_vec())
+}
+
+// Writes the new binary next to `exe` and renames it over the old one, so an interrupted
+// update leaves the old executable in place. Windows cannot replace a running executable, so
+// it is moved aside to `<name>.old` first.
+fn replace_executable(exe: &Path, binary: &[u8]) -> Result<()> {
+    let dir = exe.parent().context("The executable has no parent directory")?;
+    let mut staged = tempfile::Builder::new()
+        .prefix(".opencode-update")
+        .tempfile_in(dir)
+        .with_context(|| format!("Failed to create a file in {}; is it writable?", dir.display()))?;
+    staged.write_all(binary)?;
+    staged.as_file().sync_all()?;
+    let permissions = fs::metadata(exe).with_context(|| format!("Failed to read {}", exe.display()))?.permissions();
+    fs::set_permissions(staged.path(), permissions)?;
+    if cfg!(windows) {
+        let old = exe.with_extension("old");
+        let _ = fs::remove_file(&old);
+        fs::rename(exe, &old).with_context(|| format!("Failed to move {} aside", exe.display()))?;
+    }
+    staged.persist(exe).with_context(|| format!("Failed to replace {}", exe.display()))?;
+    Ok(())
+}
+
+pub async fn handle_self_update(config: Config, args: SelfUpdateArgs) -> Result<()> {
+    let client = Client::builder()
+        .user_agent(format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")))
+        .timeout(Duration::from_secs(config.network.fetch_timeout_seconds))
+        .build()
+        .context("Failed to build HTTP client")?;
+    let current = env!("CARGO_PKG_VERSION");
+
+    let spinner = start_spinner("Checking for a newer release...");
+    let response = client.get(LATEST_RELEASE_API).send().await;
+    spinner.finish_and_clear();
+    let release: Release = response
+        .and_then(|r| r.error_for_status())
+        .context("Failed to query GitHub releases")?
+        .json()
+        .await
+        .context("Failed to parse the GitHub release")?;
+
+    if !is_newer(&release.tag_name, current) {
+        print_info(&format!("opencode {} is up to date (latest release: {}).", current, release.tag_name));
+        return Ok(());
+    }
+    print_info(&format!("opencode {} is available (installed: {}).", release.tag_name, current));
+    if args.check {
+        return Err(UpdateAvailable { tag: release.tag_name }.into());
+    }
+
+    let exe = std::env::current_exe().and_then(fs::canonicalize).context("Failed to locate the running executable")?;
+    if let Some((manager, command)) = package_manager(&exe) {
+        print_info(&format!("{} is managed by {}; update it with `{}`.", exe.display(), manager, command));
+        return Ok(());
+    }
+    let asset_name = platform_asset_name();
+    let find = |name: &str| release.assets.iter().find(|a| a.name == name);
+    let Some(asset) = find(&asset_name) else {
+        bail!("Release {} has no binary for this platform ({})", release.tag_name, asset_name);
+    };
+    let Some(checksums) = find(&format!("{}.sha256", asset_name)).or_else(|| find(CHECKSUMS_ASSET)) else {
+        bail!("Release {} publishes no checksum for {}; not installing it", release.tag_name, asset_name);
+    };
+    let signature = match RELEASE_PUBLIC_KEY {
+        Some(_) => match find(&format!("{}.sig", checksums.name)) {
+            Some(signature) => Some(signature),
+            None => bail!("Release {} publishes no signature for {}; not installing it", release.tag_name, checksums.name),
+        },
+        None => {
+            print_warning("This build has no release signing key; only the checksum will be verified.");
+            None
+        }
+    };
+
+    let prompt = format!("Replace {} with {}?", exe.display(), release.tag_name);
+    if !args.yes && (!std::io::stdin().is_terminal() || !prompt_confirmation(&prompt)?) {
+        print_info("Not updated.");
+        return Ok(());
+    }
+
+    let spinner = start_spinner(&format!("Downloading {}...", asset.name));
+    let downloaded = download(&client, &asset.browser_download_url).await;
+    let listing = download(&client, &checksums.browser_download_url).await;
+    let signed = match signature {
+        Some(signature) => Some(download(&client, &signature.browser_download_url).await),
+        None => None,
+    };
+    spinner.finish_and_clear();
+    let (binary, listing) = (downloaded?, listing?);
+    if let (Some(public_key), Some(signed)) = (RELEASE_PUBLIC_KEY, signed) {
+        verify_signature(public_key, &listing, &signed?).with_context(|| format!("Not installing {}", release.tag_name))?;
+    }
+    let listing = String::from_utf8_lossy(&listing).to_string();
+    let expected = expected_checksum(&listing, &asset_name).with_context(|| format!("No checksum for {} in {}", asset_name, checksums.name))?;
+    let actual = format!("{:x}", Sha256::digest(&binary));
+    if actual != expected {
+        bail!("Checksum mismatch for {}: expected {}, got {}; not installing it", asset_name, expected, actual);
+    }
+
+    replace_executable(&exe, &binary)?;
+    print_info(&format!("Updated {} to {}.", exe.display(), release.tag_name));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_checksums_and_package_managers() {
+        assert!(is_newer("v0.2.0", "0.1.9"));
+        assert!(is_newer("v0.10.0", "0.9.3"));
+        assert!(!is_newer("v0.1.0", "0.1.0"));
+        assert!(!is_newer("v0.1.0-beta.1", "0.1.0"));
+
+        let listing = "aa11  opencode-linux-x86_64\nBB22 *opencode-macos-aarch64\n";
+        assert_eq!(expected_checksum(listing, "opencode-macos-aarch64").as_deref(), Some("bb22"));
+        assert_eq!(expected_checksum(listing, "opencode-windows-x86_64.exe"), None);
+        assert_eq!(expected_checksum("cc33\n", "opencode-linux-x86_64").as_deref(), Some("cc33"));
+
+        assert_eq!(package_manager(Path::new("/opt/homebrew/Cellar/opencode/0.1.0/bin/opencode")).map(|m| m.0), Some("Homebrew"));
+        assert_eq!(package_manager(Path::new("C:\\Users\\ana\\scoop\\apps\\opencode\\current\\opencode.exe")).map(|m| m.0), Some("Scoop"));
+        assert_eq!(package_manager(Path::new("/usr/local/bin/opencode")), None);
+    }
+
+    #[test]
+    fn test_checksum_listing_signature() {
+        use ring::signature::{Ed25519KeyPair, KeyPair};
+        let rng = ring::rand::SystemRandom::new();
+        let key_pair = Ed25519KeyPair::from_pkcs8(Ed25519KeyPair::generate_pkcs8(&rng).unwrap().as_ref()).unwrap();
+        let public_key: String = key_pair.public_key().as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+        let listing = b"aa11  opencode-linux-x86_64\n";
+        let signature = key_pair.sign(listing);
+
+        assert!(verify_signature(&public_key, listing, signature.as_ref()).is_ok());
+        let hex: String = signature.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+        assert!(verify_signature(&public_key, listing, hex.as_bytes()).is_ok());
+        assert!(verify_signature(&public_key, b"bb22  opencode-linux-x86_64\n", signature.as_ref()).is_err());
+    }
+}
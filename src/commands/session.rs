@@ -0,0 +1,63 @@
+use anyhow::Result;
+
+use crate::cli::commands::{SessionArgs, SessionCommands};
+use crate::context::environment::format_utc;
+use crate::context::session::{NamedSession, NamedSessionStore, SessionSummary, SessionSummaryStore};
+use crate::tui::{print_info, print_result};
+
+fn format_listing(summaries: &[SessionSummary]) -> String {
+    summaries
+        .iter()
+        .map(|s| {
+            format!(
+                "{}  {}  {} messages  {}\n    {}",
+                s.id,
+                format_utc(s.started_at),
+                s.message_count,
+                s.project_dir.display(),
+                s.task
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn format_named(sessions: &[NamedSession]) -> String {
+    sessions
+        .iter()
+        .map(|s| format!("{}  {}  {} messages  {}", s.name, format_utc(s.updated_at), s.messages.len(), s.project_dir.display()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub async fn handle_session(args: SessionArgs) -> Result<()> {
+    let named = NamedSessionStore::from_config_dir()?;
+    match args.command {
+        SessionCommands::List => {
+            let sessions = named.list();
+            if !sessions.is_empty() {
+                print_result(&format_named(&sessions));
+                print_info("Continue one with --session <name>.");
+            }
+            let summaries = SessionSummaryStore::from_config_dir()?.list();
+            if !summaries.is_empty() {
+                print_result(&format_listing(&summaries));
+                print_info("Continue from one with /resume-summary <id> in interactive mode.");
+            }
+            if sessions.is_empty() && summaries.is_empty() {
+                print_info("No saved sessions yet; start a named one with --session <name>, or end an interactive session to save its summary.");
+            }
+            Ok(())
+        }
+        SessionCommands::Delete { name } => {
+            named.delete(&name)?;
+            print_info(&format!("Deleted session '{}'.", name));
+            Ok(())
+        }
+        SessionCommands::Rename { name, new_name } => {
+            named.rename(&name, &new_name)?;
+            print_info(&format!("Renamed session '{}' to '{}'.", name, new_name));
+            Ok(())
+        }
+    }
+}
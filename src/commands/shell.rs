@@ -1,18 +1,18 @@
-use anyhow::{Context, Result};
+use anyhow::Result;
 
-use crate::api::client::ApiClient;
-use crate::api::models::{ChatCompletionRequest, Message, Role};
+use crate::api::chat_api::ChatApi;
+use crate::api::models::ChatCompletionRequest;
 use crate::cli::commands::{ShellArgs, ShellCommands};
+use crate::commands::prompts::command_messages;
 use crate::config::Config;
 use crate::streaming::handle_streamed_response;
 use crate::tui::{print_error};
 
 pub async fn handle_shell(
+    api_client: &dyn ChatApi,
     config: Config,
     args: ShellArgs,
 ) -> Result<()> {
-    let api_client = ApiClient::new(config.clone())
-        .context("Failed to create API client (check API key configuration)")?;
     match args.command {
         ShellCommands::Explain(explain_args) => {
             tracing::debug!(
@@ -25,16 +25,11 @@ pub async fn handle_shell(
                 explain_args.command_string
             );
 
-            let user_message = Message {
-                role: Role::User,
-                content: Some(prompt),
-                tool_calls: None,
-                tool_call_id: None,
-            };
+            let messages = command_messages(&config, "shell", None, prompt)?;
 
             let request = ChatCompletionRequest {
                 model: config.api.default_model.clone(),
-                messages: vec![user_message],
+                messages,
                 stream: Some(true),
                 temperature: None,
                 max_tokens: None,
@@ -66,16 +61,11 @@ pub async fn handle_shell(
                 suggest_args.description
             );
 
-            let user_message = Message {
-                role: Role::User,
-                content: Some(prompt),
-                tool_calls: None,
-                tool_call_id: None,
-            };
+            let messages = command_messages(&config, "shell", None, prompt)?;
 
             let request = ChatCompletionRequest {
                 model: config.api.default_model.clone(),
-                messages: vec![user_message],
+                messages,
                 stream: Some(true),
                 temperature: None,
                 max_tokens: None,
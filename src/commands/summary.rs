@@ -0,0 +1,72 @@
+use anyhow::{Context, Result};
+use std::io::IsTerminal;
+use tokio::process::Command;
+
+use crate::tools::snapshot::{display_path, format_diffstat, SnapshotStore};
+use crate::tui::{print_info, print_result, print_warning, prompt_confirmation, prompt_text};
+
+const DIFFSTAT_BAR_WIDTH: usize = 40;
+
+async fn in_git_work_tree() -> bool {
+    Command::new("git")
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+async fn run_git(args: &[&str]) -> Result<()> {
+    let output = Command::new("git")
+        .args(args)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+// Prints a diffstat of everything the tools changed during this invocation and, when
+// running interactively inside a git repository, offers to stage and commit it.
+pub async fn report_session_changes(snapshots: &SnapshotStore) -> Result<()> {
+    let stats = snapshots.diffstat();
+    if stats.is_empty() {
+        tracing::debug!("No file changes recorded during this invocation.");
+        return Ok(());
+    }
+
+    print_info("Changes made during this run:");
+    print_result(&format_diffstat(&stats, DIFFSTAT_BAR_WIDTH));
+
+    if !std::io::stdin().is_terminal() || !in_git_work_tree().await {
+        return Ok(());
+    }
+    if !prompt_confirmation("Stage these changes?")? {
+        return Ok(());
+    }
+    let paths: Vec<String> = stats.iter().map(|s| display_path(&s.path)).collect();
+    let mut add_args = vec!["add", "-A", "--"];
+    add_args.extend(paths.iter().map(String::as_str));
+    run_git(&add_args).await?;
+    print_info(&format!("Staged {} file(s).", paths.len()));
+
+    if !prompt_confirmation("Commit the staged changes?")? {
+        return Ok(());
+    }
+    let message = prompt_text("Commit message", "Apply changes from opencode")?;
+    if message.trim().is_empty() {
+        print_warning("Empty commit message, leaving changes staged.");
+        return Ok(());
+    }
+    run_git(&["commit", "-m", message.trim()]).await?;
+    print_info("Changes committed.");
+    Ok(())
+}
@@ -1,19 +1,19 @@
-use anyhow::{Context, Result}; // Removed anyhow
+use anyhow::Result; // Removed anyhow
 use std::fs;
 
-use crate::api::client::ApiClient;
-use crate::api::models::{ChatCompletionRequest, Message, Role};
+use crate::api::chat_api::ChatApi;
+use crate::api::models::ChatCompletionRequest;
 use crate::cli::commands::TestArgs;
+use crate::commands::prompts::command_messages;
 use crate::config::Config;
 use crate::streaming::handle_streamed_response;
 use crate::tui::{print_error};
 
 pub async fn handle_test(
+    api_client: &dyn ChatApi,
     config: Config,
     args: TestArgs,
 ) -> Result<()> {
-    let api_client = ApiClient::new(config.clone())
-        .context("Failed to create API client (check API key configuration)")?;
     tracing::debug!(
         "Processing 'test' command for file: '{}'",
         args.file
@@ -36,16 +36,11 @@ pub async fn handle_test(
         file_content
     );
 
-    let user_message = Message {
-        role: Role::User,
-        content: Some(prompt),
-        tool_calls: None,
-        tool_call_id: None,
-    };
+    let messages = command_messages(&config, "test", None, prompt)?;
 
     let request = ChatCompletionRequest {
         model: config.api.big_model.clone(),
-        messages: vec![user_message],
+        messages,
         stream: None,
         temperature: None,
         max_tokens: None,
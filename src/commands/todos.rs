@@ -0,0 +1,246 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::api::chat_api::ChatApi;
+use crate::api::models::ChatCompletionRequest;
+use crate::cargo_workspace::is_build_output;
+use crate::cli::commands::TodosArgs;
+use crate::commands::prompts::command_messages;
+use crate::config::Config;
+use crate::tools::write_checks::TODO_MARKERS;
+use crate::tui::{print_info, print_result, print_warning, prompt_confirmation, start_spinner};
+
+const TRIAGE_PREAMBLE: &str = "You triage to-do comments left in a codebase. For each numbered \
+comment, reply with ONLY a JSON array of objects: [{\"id\": number, \"category\": \"bug\" | \"feature\" | \
+\"refactor\" | \"performance\" | \"docs\" | \"test\" | \"cleanup\", \"effort\": \"small\" | \"medium\" | \
+\"large\", \"summary\": \"one-line issue title\"}]. Small is under an hour, medium under a day.";
+
+// Comments sent to the model in one request; the rest are listed untriaged.
+const MAX_TRIAGED_TODOS: usize = 200;
+// Files larger than this are generated or vendored more often than not.
+const MAX_SCANNED_FILE_BYTES: u64 = 1_000_000;
+const COMMENT_STARTS: &[&str] = &["//", "#", "/*", "*", "--", ";", "<!--"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TodoComment {
+    pub path: PathBuf,
+    pub line: usize,
+    pub marker: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Triage {
+    pub id: usize,
+    #[serde(default)]
+    pub category: String,
+    #[serde(default)]
+    pub effort: String,
+    #[serde(default)]
+    pub summary: String,
+}
+
+// The marker and its text when `line` is a to-do comment: a marker from TODO_MARKERS after a
+// comment start, followed by `:`, `(`, a space or the end of the line.
+pub fn todo_in_line(line: &str) -> Option<(&'static str, String)> {
+    for marker in TODO_MARKERS {
+        let Some(at) = line.find(marker) else {
+            continue;
+        };
+        let before = &line[..at];
+        let after = &line[at + marker.len()..];
+        let word_start = !before.ends_with(|c: char| c.is_alphanumeric() || c == '_');
+        let word_end = after.is_empty() || after.starts_with(|c: char| c == ':' || c == '(' || c.is_whitespace());
+        let in_comment = COMMENT_STARTS.iter().any(|start| before.trim_start().starts_with(start));
+        if word_start && word_end && in_comment {
+            let text = after.trim_start_matches(|c: char| c == ':' || c == '(' || c.is_whitespace());
+            let text = match text.find("):") {
+                Some(end) if !text[..end].contains(' ') => &text[end + 2..],
+                _ => text,
+            };
+            let text = text.trim().trim_end_matches("*/").trim_end_matches("-->").trim();
+            return Some((marker, text.to_string()));
+        }
+    }
+    None
+}
+
+// To-do comments in the text files under `root`, skipping hidden entries and `target`.
+pub fn scan_todos(root: &Path) -> Result<Vec<TodoComment>> {
+    let walker = WalkDir::new(root).sort_by_file_name().into_iter().filter_entry(|entry| {
+        let name = entry.file_name().to_string_lossy();
+        entry.depth() == 0 || !(name.starts_with('.') || is_build_output(entry.path()) || name == "node_modules")
+    });
+    let mut todos = Vec::new();
+    for entry in walker {
+        let entry = entry.with_context(|| format!("Failed to walk {}", root.display()))?;
+        let small = entry.metadata().map(|m| m.len() <= MAX_SCANNED_FILE_BYTES).unwrap_or(false);
+        if !entry.file_type().is_file() || !small {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        let path = entry.path().strip_prefix(root).unwrap_or(entry.path()).to_path_buf();
+        for (index, line) in content.lines().enumerate() {
+            if let Some((marker, text)) = todo_in_line(line) {
+                todos.push(TodoComment { path: path.clone(), line: index + 1, marker: marker.to_string(), text });
+            }
+        }
+    }
+    Ok(todos)
+}
+
+async fn triage(api_client: &dyn ChatApi, config: &Config, todos: &[TodoComment]) -> Result<Vec<Triage>> {
+    let listing: Vec<String> = todos
+        .iter()
+        .take(MAX_TRIAGED_TODOS)
+        .enumerate()
+        .map(|(id, todo)| format!("{}. {}:{} {}: {}", id, todo.path.display(), todo.line, todo.marker, todo.text))
+        .collect();
+    let request = ChatCompletionRequest {
+        model: config.api.default_model.clone(),
+        messages: command_messages(config, "todos", Some(TRIAGE_PREAMBLE), listing.join("\n"))?,
+        stream: None,
+        temperature: Some(0.0),
+        max_tokens: None,
+        tools: None,
+        tool_choice: None,
+        source_map: None,
+    };
+    let spinner = start_spinner(&format!("Triaging {} comment(s)...", listing.len()));
+    let response = api_client.chat_completion(request).await;
+    spinner.finish_and_clear();
+    let content = response?.choices.into_iter().next().and_then(|c| c.message.content).unwrap_or_default();
+    let (Some(start), Some(end)) = (content.find('['), content.rfind(']')) else {
+        bail!("The model did not return a triage list: {}", content.trim());
+    };
+    serde_json::from_str(&content[start..=end]).context("Failed to parse the model's triage list")
+}
+
+fn triage_for(triages: &[Triage], id: usize) -> Option<&Triage> {
+    triages.iter().find(|t| t.id == id)
+}
+
+// A markdown report grouped by category, then by file.
+pub fn markdown_report(todos: &[TodoComment], triages: &[Triage]) -> String {
+    let mut groups: BTreeMap<String, Vec<(usize, &TodoComment)>> = BTreeMap::new();
+    for (id, todo) in todos.iter().enumerate() {
+        let category = triage_for(triages, id).map(|t| t.category.clone()).filter(|c| !c.is_empty());
+        groups.entry(category.unwrap_or_else(|| "untriaged".to_string())).or_default().push((id, todo));
+    }
+    let mut report = format!("# TODO triage\n\n{} comment(s) found.\n", todos.len());
+    for (category, mut entries) in groups {
+        entries.sort_by(|a, b| a.1.path.cmp(&b.1.path).then(a.1.line.cmp(&b.1.line)));
+        report.push_str(&format!("\n## {} ({})\n\n", category, entries.len()));
+        for (id, todo) in entries {
+            let location = format!("`{}:{}`", todo.path.display(), todo.line);
+            match triage_for(triages, id) {
+                Some(t) => report.push_str(&format!("- [{}] {} — {} ({}: {})\n", t.effort, t.summary, location, todo.marker, todo.text)),
+                None => report.push_str(&format!("- {} {}: {}\n", location, todo.marker, todo.text)),
+            }
+        }
+    }
+    report
+}
+
+// Opens one GitHub issue per triaged comment with the `gh` CLI, which must be installed and
+// authenticated for the current repository.
+async fn create_issues(todos: &[TodoComment], triages: &[Triage]) -> Result<usize> {
+    let mut created = 0;
+    for (id, todo) in todos.iter().enumerate() {
+        let Some(triage) = triage_for(triages, id) else {
+            continue;
+        };
+        let title = if triage.summary.is_empty() { todo.text.clone() } else { triage.summary.clone() };
+        let body = format!(
+            "From a `{}` comment at `{}:{}`:\n\n> {}\n\nCategory: {}\nEstimated effort: {}",
+            todo.marker,
+            todo.path.display(),
+            todo.line,
+            todo.text,
+            triage.category,
+            triage.effort
+        );
+        let output = tokio::process::Command::new("gh")
+            .args(["issue", "create", "--title", &title, "--body", &body])
+            .kill_on_drop(true)
+            .output()
+            .await
+            .context("Failed to run gh; install the GitHub CLI to create issues")?;
+        if !output.status.success() {
+            bail!("gh issue create failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        print_info(&format!("Created {}", String::from_utf8_lossy(&output.stdout).trim()));
+        created += 1;
+    }
+    Ok(created)
+}
+
+pub async fn handle_todos(api_client: &dyn ChatApi, config: Config, args: TodosArgs) -> Result<()> {
+    let root = PathBuf::from(args.path.as_deref().unwrap_or("."));
+    let todos = scan_todos(&root)?;
+    if todos.is_empty() {
+        print_info("No TODO, FIXME, XXX or HACK comments found.");
+        return Ok(());
+    }
+    if todos.len() > MAX_TRIAGED_TODOS {
+        print_warning(&format!("Triaging the first {} of {} comments.", MAX_TRIAGED_TODOS, todos.len()));
+    }
+    let triages = if args.no_triage { Vec::new() } else { triage(api_client, &config, &todos).await? };
+    let report = markdown_report(&todos, &triages);
+
+    match &args.report {
+        Some(path) => {
+            fs::write(path, &report).with_context(|| format!("Failed to write report to {}", path))?;
+            print_info(&format!("Wrote the triage report to {}", path));
+        }
+        None => print_result(&report),
+    }
+
+    if args.create_issues {
+        if triages.is_empty() {
+            bail!("Issues are created from triaged comments; drop --no-triage");
+        }
+        let prompt = format!("Create {} GitHub issue(s) with gh?", triages.len());
+        if !args.yes && (!std::io::stdin().is_terminal() || !prompt_confirmation(&prompt)?) {
+            print_info("No issues created.");
+            return Ok(());
+        }
+        let created = create_issues(&todos, &triages).await?;
+        print_info(&format!("Created {} issue(s).", created));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_todo_in_line_needs_comment_and_whole_word() {
+        assert_eq!(todo_in_line("    // TODO: retry on timeout"), Some(("TODO", "retry on timeout".to_string())));
+        assert_eq!(todo_in_line("# FIXME(ana): handle empty input"), Some(("FIXME", "handle empty input".to_string())));
+        assert_eq!(todo_in_line("/* HACK work around the parser */"), Some(("HACK", "work around the parser".to_string())));
+        assert_eq!(todo_in_line("let todo = \"TODO: not a comment\";"), None);
+        assert_eq!(todo_in_line("// TODOS are tracked elsewhere"), None);
+        assert_eq!(todo_in_line("// Finds TODO-style markers"), None);
+    }
+
+    #[test]
+    fn test_markdown_report_groups_by_category() {
+        let todos = vec![
+            TodoComment { path: PathBuf::from("src/a.rs"), line: 3, marker: "TODO".to_string(), text: "retry".to_string() },
+            TodoComment { path: PathBuf::from("src/b.rs"), line: 9, marker: "FIXME".to_string(), text: "leak".to_string() },
+        ];
+        let triages = vec![Triage { id: 1, category: "bug".to_string(), effort: "small".to_string(), summary: "Fix leak".to_string() }];
+        let report = markdown_report(&todos, &triages);
+        assert!(report.contains("## bug (1)\n\n- [small] Fix leak — `src/b.rs:9` (FIXME: leak)"));
+        assert!(report.contains("## untriaged (1)\n\n- `src/a.rs:3` TODO: retry"));
+    }
+}
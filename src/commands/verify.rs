@@ -0,0 +1,114 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+
+use crate::api::chat_api::ChatApi;
+use crate::api::models::{ChatCompletionRequest, Message, Role};
+use crate::config::Config;
+use crate::stream_json::emit_response;
+use crate::tui::start_spinner;
+
+const MAX_VERIFY_DIFF_CHARS: usize = 60_000;
+
+// `{task}` and `{diff}` are filled in; `[run] verify_prompt` replaces the whole template.
+pub const DEFAULT_VERIFY_PROMPT: &str = "You are checking whether a coding agent finished its task. \
+Compare the diff it produced against the task statement and list every requirement of the task \
+that the diff does not satisfy, including ones that are only partly done. Do not report style \
+preferences or improvements beyond what the task asks for. Respond with ONLY a JSON object: \
+{\"satisfied\": true | false, \"unmet_requirements\": [short descriptions], \"notes\": optional \
+one-sentence summary}.\n\nTask:\n{task}\n\nDiff:\n{diff}";
+
+// The outcome of the self-evaluation pass after `opencode run`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Verification {
+    pub satisfied: bool,
+    #[serde(default)]
+    pub unmet_requirements: Vec<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+pub fn verification_prompt(template: &str, task: &str, diff: &str) -> String {
+    let diff = if diff.trim().is_empty() {
+        "(no changes)".to_string()
+    } else if diff.len() > MAX_VERIFY_DIFF_CHARS {
+        let cut = (0..=MAX_VERIFY_DIFF_CHARS).rev().find(|&i| diff.is_char_boundary(i)).unwrap_or(0);
+        format!("{}\n... (diff truncated)", &diff[..cut])
+    } else {
+        diff.to_string()
+    };
+    template.replace("{task}", task).replace("{diff}", &diff)
+}
+
+// Pulls the JSON object out of the response, tolerating code fences or prose around it.
+pub fn parse_verification(response: &str) -> Result<Verification> {
+    let start = response.find('{').ok_or_else(|| anyhow!("Verification response contained no JSON object"))?;
+    let end = response.rfind('}').ok_or_else(|| anyhow!("Verification response contained no JSON object"))?;
+    if end < start {
+        bail!("Verification response contained no JSON object");
+    }
+    let mut verification: Verification =
+        serde_json::from_str(&response[start..=end]).context("Failed to parse verification result")?;
+    // A model that lists gaps has not satisfied the task, whatever it claims.
+    verification.satisfied &= verification.unmet_requirements.is_empty();
+    Ok(verification)
+}
+
+pub fn format_verification(verification: &Verification) -> String {
+    let mut lines = vec![if verification.satisfied {
+        "Self-check: all requirements of the task appear to be met.".to_string()
+    } else {
+        format!("Self-check: {} unmet requirement(s):", verification.unmet_requirements.len())
+    }];
+    lines.extend(verification.unmet_requirements.iter().map(|r| format!("  - {}", r)));
+    if let Some(notes) = verification.notes.as_deref().filter(|n| !n.trim().is_empty()) {
+        lines.push(format!("  {}", notes.trim()));
+    }
+    lines.join("\n")
+}
+
+// Asks the model, without tools, whether `diff` accomplishes `task`.
+pub async fn verify_run(api_client: &dyn ChatApi, config: &Config, task: &str, diff: &str) -> Result<Verification> {
+    let template = config.run.verify_prompt.as_deref().unwrap_or(DEFAULT_VERIFY_PROMPT);
+    let request = ChatCompletionRequest {
+        model: config.api.default_model.clone(),
+        messages: vec![Message {
+            role: Role::User,
+            content: Some(verification_prompt(template, task, diff)),
+            tool_calls: None,
+            tool_call_id: None,
+        }],
+        stream: None,
+        temperature: Some(0.0),
+        max_tokens: None,
+        tools: None,
+        tool_choice: None,
+        source_map: None,
+    };
+    let spinner = start_spinner("Checking the result against the task...");
+    let response = api_client.chat_completion(request).await;
+    spinner.finish_and_clear();
+    let response = response.context("Verification request failed")?;
+    emit_response(&config.api.default_model, &response);
+    let content = response.choices.first().and_then(|c| c.message.content.clone()).unwrap_or_default();
+    parse_verification(&content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_verification_from_fenced_response() {
+        let response = "Here is my assessment:\n```json\n{\"satisfied\": true, \"unmet_requirements\": \
+                        [\"No test for the empty input case\"], \"notes\": \"Mostly done.\"}\n```";
+        let verification = parse_verification(response).unwrap();
+        assert!(!verification.satisfied);
+        assert_eq!(verification.unmet_requirements, vec!["No test for the empty input case"]);
+        assert_eq!(
+            format_verification(&verification),
+            "Self-check: 1 unmet requirement(s):\n  - No test for the empty input case\n  Mostly done."
+        );
+        assert!(parse_verification("looks good").is_err());
+        assert!(verification_prompt("{task}|{diff}", "add x", "").ends_with("add x|(no changes)"));
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+async fn git(dir: &Path, args: &[&str], stdin: Option<&str>) -> Result<String> {
+    let mut child = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(input.as_bytes()).await?;
+    }
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        bail!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// A detached worktree of HEAD for `run --isolated`: the agent works there and the user's
+// checkout is only touched if they accept the resulting diff. Uncommitted changes in the
+// checkout are not carried over.
+#[derive(Debug)]
+pub struct IsolatedWorktree {
+    repo_root: PathBuf,
+    path: PathBuf,
+    // Where `dir` sits inside the repository, so the run starts in the matching subdirectory.
+    relative_dir: PathBuf,
+}
+
+impl IsolatedWorktree {
+    pub async fn create(dir: &Path) -> Result<Self> {
+        let repo_root = PathBuf::from(
+            git(dir, &["rev-parse", "--show-toplevel"], None)
+                .await
+                .context("--isolated needs to run inside a git repository")?
+                .trim(),
+        );
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        let relative_dir = dir.strip_prefix(&repo_root).map(Path::to_path_buf).unwrap_or_default();
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or_default();
+        let path = std::env::temp_dir().join(format!("opencode-worktree-{}-{}", std::process::id(), stamp));
+        git(&repo_root, &["worktree", "add", "--detach", &path.to_string_lossy(), "HEAD"], None).await?;
+        Ok(IsolatedWorktree { repo_root, path, relative_dir })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn work_dir(&self) -> PathBuf {
+        self.path.join(&self.relative_dir)
+    }
+
+    // Everything the run changed relative to HEAD, new files included.
+    pub async fn diff(&self) -> Result<String> {
+        git(&self.path, &["add", "-A"], None).await?;
+        git(&self.path, &["diff", "--cached", "--binary", "HEAD"], None).await
+    }
+
+    pub async fn apply(&self, diff: &str) -> Result<()> {
+        git(&self.repo_root, &["apply", "--whitespace=nowarn", "-"], Some(diff)).await.map(|_| ())
+    }
+
+    pub async fn remove(self) -> Result<()> {
+        git(&self.repo_root, &["worktree", "remove", "--force", &self.path.to_string_lossy()], None).await.map(|_| ())
+    }
+
+    // Removes the worktree when called, for cleanup after an interruption. Shutdown cleanups
+    // are synchronous and run once the command itself has been dropped, so this one waits
+    // for git to finish.
+    pub fn remover(&self) -> impl FnOnce() + Send + 'static {
+        let (repo_root, path) = (self.repo_root.clone(), self.path.clone());
+        move || {
+            let removed = std::process::Command::new("git")
+                .args(["worktree", "remove", "--force", &path.to_string_lossy()])
+                .current_dir(&repo_root)
+                .output();
+            match removed {
+                Ok(output) if output.status.success() => {}
+                Ok(output) => tracing::warn!(
+                    "Failed to remove the isolated worktree {}: {}",
+                    path.display(),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+                Err(e) => tracing::warn!("Failed to remove the isolated worktree {}: {}", path.display(), e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[tokio::test]
+    async fn test_changes_stay_in_worktree_until_applied() {
+        let repo = tempfile::tempdir().unwrap();
+        let root = repo.path();
+        git(root, &["init", "-q"], None).await.unwrap();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "fn a() {}\n").unwrap();
+        git(root, &["add", "."], None).await.unwrap();
+        git(root, &["-c", "user.name=t", "-c", "user.email=t@t", "commit", "-qm", "init"], None).await.unwrap();
+
+        let worktree = IsolatedWorktree::create(&root.join("src")).await.unwrap();
+        assert!(worktree.work_dir().ends_with("src"));
+        fs::write(worktree.work_dir().join("lib.rs"), "fn b() {}\n").unwrap();
+        fs::write(worktree.work_dir().join("new.rs"), "fn c() {}\n").unwrap();
+        assert_eq!(fs::read_to_string(root.join("src/lib.rs")).unwrap(), "fn a() {}\n");
+
+        let diff = worktree.diff().await.unwrap();
+        assert!(diff.contains("+fn b() {}") && diff.contains("new.rs"));
+        worktree.apply(&diff).await.unwrap();
+        let path = worktree.path().to_path_buf();
+        worktree.remove().await.unwrap();
+
+        assert_eq!(fs::read_to_string(root.join("src/lib.rs")).unwrap(), "fn b() {}\n");
+        assert!(root.join("src/new.rs").exists());
+        assert!(!path.exists());
+    }
+}
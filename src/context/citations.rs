@@ -0,0 +1,77 @@
+use serde_json::Value;
+
+pub const CITATION_INSTRUCTION: &str = "When your answer relies on a project file or web page you were \
+given or read, cite it inline by its path or URL in square brackets, e.g. [src/main.rs] or \
+[https://example.com/guide]. Cite only sources that were actually provided to you.";
+
+fn normalize(source: &str) -> &str {
+    source.trim_start_matches("./")
+}
+
+// Links found anywhere in a tool result, e.g. the `link` of each web search result.
+fn result_links(value: &Value, links: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match value.as_str() {
+                    Some(link) if matches!(key.as_str(), "url" | "link") && link.starts_with("http") => links.push(link.to_string()),
+                    _ => result_links(value, links),
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| result_links(item, links)),
+        _ => {}
+    }
+}
+
+// The files and pages a successful tool call put in front of the model.
+pub fn tool_sources(tool_name: &str, arguments: &Value, result: &Value) -> Vec<String> {
+    match tool_name {
+        "FileReadTool" => arguments.get("path").and_then(Value::as_str).map(|p| normalize(p).to_string()).into_iter().collect(),
+        "UrlFetchTool" | "BrowserTool" => arguments.get("url").and_then(Value::as_str).map(String::from).into_iter().collect(),
+        _ => {
+            let mut links = Vec::new();
+            result_links(result, &mut links);
+            links
+        }
+    }
+}
+
+// The "Sources" footer for `answer`: the sources it cites, or when it cites none, every source
+// that was in context. Only sources that really were in context are listed, so a citation the
+// model made up never gets one.
+pub fn sources_footer(answer: &str, sources: &[String]) -> Option<String> {
+    let cited: Vec<&String> = sources.iter().filter(|source| answer.contains(normalize(source))).collect();
+    let listed = if cited.is_empty() { sources.iter().collect() } else { cited };
+    if listed.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = listed.iter().map(|source| format!("- {}", normalize(source))).collect();
+    Some(format!("Sources:\n{}", lines.join("\n")))
+}
+
+pub fn with_sources(answer: &str, sources: &[String]) -> String {
+    match sources_footer(answer, sources) {
+        Some(footer) => format!("{}\n\n{}", answer.trim_end(), footer),
+        None => answer.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_footer_lists_cited_sources_from_context() {
+        let search = json!({ "results": [{ "title": "Guide", "link": "https://docs.rs/tokio", "snippet": "" }] });
+        assert_eq!(tool_sources("web_search", &json!({ "query": "tokio" }), &search), vec!["https://docs.rs/tokio"]);
+        assert_eq!(tool_sources("FileReadTool", &json!({ "path": "./src/lib.rs" }), &json!({})), vec!["src/lib.rs"]);
+
+        let sources = vec!["src/lib.rs".to_string(), "./src/app.rs".to_string(), "https://docs.rs/tokio".to_string()];
+        let answer = "Startup is in [src/app.rs], see [https://docs.rs/tokio] and [src/made_up.rs].";
+        assert_eq!(with_sources(answer, &sources), format!("{}\n\nSources:\n- src/app.rs\n- https://docs.rs/tokio", answer));
+        assert_eq!(sources_footer("No citations.", &sources).unwrap(), "Sources:\n- src/lib.rs\n- src/app.rs\n- https://docs.rs/tokio");
+        assert_eq!(with_sources("Plain.", &[]), "Plain.");
+    }
+}
@@ -0,0 +1,210 @@
+use crate::api::client::OPENROUTER_API_BASE_URL;
+use crate::config::{ApiProvider, Config, EmbeddingProviderKind};
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const OLLAMA_BASE_URL: &str = "http://localhost:11434/v1";
+const OPENAI_MODEL: &str = "text-embedding-3-small";
+const OPENROUTER_MODEL: &str = "openai/text-embedding-3-small";
+const OLLAMA_MODEL: &str = "nomic-embed-text";
+const REQUEST_TIMEOUT_SECONDS: u64 = 120;
+
+// Turns text into vectors whose cosine similarity tracks how related the texts are.
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    // Names the provider and model; vectors from different ids are not comparable, so the
+    // index re-embeds everything when it changes.
+    fn id(&self) -> String;
+
+    // One vector per text, in order.
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+// Offline fallback: identifiers and words hashed into a fixed number of signed buckets. It
+// only captures shared vocabulary, but needs no model, network or key.
+#[derive(Debug, Clone)]
+pub struct HashedEmbeddings {
+    dimensions: usize,
+}
+
+impl HashedEmbeddings {
+    pub fn new(dimensions: usize) -> Self {
+        HashedEmbeddings { dimensions: dimensions.max(1) }
+    }
+
+    pub fn embed_one(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0f32; self.dimensions];
+        for word in text.split(|c: char| !c.is_alphanumeric()).filter(|w| w.len() > 1) {
+            // FNV-1a, so vectors stay the same across builds and platforms.
+            let hash = word.to_lowercase().bytes().fold(0xcbf29ce484222325u64, |h, b| (h ^ b as u64).wrapping_mul(0x100000001b3));
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            vector[(hash % self.dimensions as u64) as usize] += sign;
+        }
+        let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|v| *v /= norm);
+        }
+        vector
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for HashedEmbeddings {
+    fn id(&self) -> String {
+        format!("hashed:{}", self.dimensions)
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| self.embed_one(text)).collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+// Any server speaking OpenAI's `/embeddings` API: OpenAI, OpenRouter, or Ollama and other
+// local servers.
+#[derive(Debug, Clone)]
+pub struct HttpEmbeddings {
+    client: Client,
+    name: &'static str,
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+impl HttpEmbeddings {
+    pub fn new(name: &'static str, base_url: String, model: String, api_key: Option<String>) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECONDS))
+            .build()
+            .context("Failed to build reqwest client")?;
+        Ok(HttpEmbeddings { client, name, base_url: base_url.trim_end_matches('/').to_string(), model, api_key })
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for HttpEmbeddings {
+    fn id(&self) -> String {
+        format!("{}:{}", self.name, self.model)
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let url = format!("{}/embeddings", self.base_url);
+        let mut request = self.client.post(&url).json(&json!({ "model": self.model, "input": texts }));
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+        let response = request.send().await.with_context(|| format!("Failed to send request to {}", url))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_else(|_| "Could not read error body".to_string());
+            bail!("Embedding request failed with status {}: {}", status, body);
+        }
+        let mut body: EmbeddingResponse = response.json().await.context("Failed to parse embedding response")?;
+        if body.data.len() != texts.len() {
+            bail!("Asked {} for {} embeddings, got {}", self.id(), texts.len(), body.data.len());
+        }
+        body.data.sort_by_key(|d| d.index);
+        Ok(body.data.into_iter().map(|d| d.embedding).collect())
+    }
+}
+
+fn env_key(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|key| !key.is_empty())
+}
+
+// The server a keyed provider sends to. A repository's own config could point `base_url` at a
+// server it controls and collect the user's key, so a key only goes to a custom URL set in
+// the global config.
+fn keyed_base_url(config: &Config, default: &str) -> Result<String> {
+    match &config.index.base_url {
+        Some(url) if url != default && config.index.from_project_config => bail!(
+            "Refusing to send an API key to [index] base_url '{}' from the project's .OpenCode.toml; \
+             set base_url in the global config instead",
+            url
+        ),
+        Some(url) => Ok(url.clone()),
+        None => Ok(default.to_string()),
+    }
+}
+
+// The provider `[index]` selects.
+pub fn embedding_provider(config: &Config) -> Result<Box<dyn EmbeddingProvider>> {
+    let index = &config.index;
+    let model = |default: &str| index.embedding_model.clone().unwrap_or_else(|| default.to_string());
+    let base_url = |default: &str| index.base_url.clone().unwrap_or_else(|| default.to_string());
+    Ok(match index.embedding_provider {
+        EmbeddingProviderKind::Hashed => Box::new(HashedEmbeddings::new(index.dimensions)),
+        EmbeddingProviderKind::Ollama => Box::new(HttpEmbeddings::new("ollama", base_url(OLLAMA_BASE_URL), model(OLLAMA_MODEL), None)?),
+        EmbeddingProviderKind::Openai => {
+            let variable = index.api_key_env.as_deref().unwrap_or("OPENAI_API_KEY");
+            let key = env_key(variable).ok_or_else(|| anyhow!("OpenAI embeddings need an API key in {}", variable))?;
+            Box::new(HttpEmbeddings::new("openai", keyed_base_url(config, OPENAI_BASE_URL)?, model(OPENAI_MODEL), Some(key))?)
+        }
+        EmbeddingProviderKind::Openrouter => {
+            let key = match &index.api_key_env {
+                Some(variable) => env_key(variable),
+                None => config.api_key_for(ApiProvider::Openrouter)?,
+            };
+            let key = key.ok_or_else(|| anyhow!("OpenRouter embeddings need an API key; run `opencode configure`"))?;
+            let url = keyed_base_url(config, OPENROUTER_API_BASE_URL)?;
+            Box::new(HttpEmbeddings::new("openrouter", url, model(OPENROUTER_MODEL), Some(key))?)
+        }
+    })
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norms = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|y| y * y).sum::<f32>().sqrt();
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_hashed_embeddings_rank_shared_vocabulary() {
+        let provider = embedding_provider(&Config::default()).unwrap();
+        assert_eq!(provider.id(), "hashed:256");
+        let texts = ["fn parse_config(path) -> Config", "parse the config file at path", "render a progress spinner"].map(String::from);
+        let vectors = provider.embed(&texts).await.unwrap();
+        assert_eq!(vectors[0].len(), 256);
+        assert_eq!(vectors[0], HashedEmbeddings::new(256).embed_one(&texts[0]));
+        assert!((cosine_similarity(&vectors[0], &vectors[0]) - 1.0).abs() < 1e-5);
+        assert!(cosine_similarity(&vectors[0], &vectors[1]) > cosine_similarity(&vectors[0], &vectors[2]));
+        assert_eq!(cosine_similarity(&vectors[0], &[1.0]), 0.0);
+    }
+
+    #[test]
+    fn test_project_config_cannot_redirect_keys() {
+        let mut config = Config::default();
+        config.index.base_url = Some("https://collector.example".to_string());
+        assert_eq!(keyed_base_url(&config, OPENAI_BASE_URL).unwrap(), "https://collector.example");
+        config.index.from_project_config = true;
+        assert!(keyed_base_url(&config, OPENAI_BASE_URL).is_err());
+        config.index.base_url = Some(OPENAI_BASE_URL.to_string());
+        assert_eq!(keyed_base_url(&config, OPENAI_BASE_URL).unwrap(), OPENAI_BASE_URL);
+    }
+}
@@ -0,0 +1,145 @@
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::process::Command;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvironmentSnapshot {
+    pub os: String,
+    pub cwd: PathBuf,
+    pub git_branch: Option<String>,
+    pub git_dirty: Option<bool>,
+    pub datetime: String,
+    pub shell: Option<String>,
+    pub cargo_target_dir: Option<PathBuf>,
+}
+
+impl EnvironmentSnapshot {
+    pub fn summary(&self) -> String {
+        let mut lines = vec![
+            "Environment:".to_string(),
+            format!("- OS: {}", self.os),
+            format!("- Working directory: {}", self.cwd.display()),
+            format!("- Date/time: {}", self.datetime),
+        ];
+        match (&self.git_branch, self.git_dirty) {
+            (Some(branch), Some(dirty)) => lines.push(format!(
+                "- Git branch: {} ({})",
+                branch,
+                if dirty { "uncommitted changes" } else { "clean" }
+            )),
+            (Some(branch), None) => lines.push(format!("- Git branch: {}", branch)),
+            _ => lines.push("- Git: not a repository".to_string()),
+        }
+        if let Some(shell) = &self.shell {
+            lines.push(format!("- Shell: {}", shell));
+        }
+        if let Some(target_dir) = &self.cargo_target_dir {
+            lines.push(format!("- Cargo target directory: {} (build output; don't search or edit it)", target_dir.display()));
+        }
+        lines.join("\n")
+    }
+}
+
+// Describes where the session runs: OS, cwd, git state, current time and shell.
+#[derive(Debug)]
+pub struct EnvironmentProvider {
+    root: PathBuf,
+}
+
+impl EnvironmentProvider {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        EnvironmentProvider { root: root.into() }
+    }
+
+    pub fn from_current_dir() -> Result<Self> {
+        let root = std::env::current_dir().context("Failed to get current directory")?;
+        Ok(Self::new(root))
+    }
+
+    pub async fn snapshot(&self) -> EnvironmentSnapshot {
+        let git_branch = self.git(&["rev-parse", "--abbrev-ref", "HEAD"]).await;
+        let git_dirty = match git_branch {
+            Some(_) => self.git(&["status", "--porcelain"]).await.map(|status| !status.is_empty()),
+            None => None,
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        EnvironmentSnapshot {
+            os: format!("{} ({})", std::env::consts::OS, std::env::consts::ARCH),
+            cwd: self.root.clone(),
+            git_branch,
+            git_dirty,
+            datetime: format_utc(now),
+            shell: std::env::var("SHELL").or_else(|_| std::env::var("COMSPEC")).ok(),
+            cargo_target_dir: crate::cargo_workspace::target_dir(),
+        }
+    }
+
+    async fn git(&self, args: &[&str]) -> Option<String> {
+        let output = Command::new("git").args(args).current_dir(&self.root).kill_on_drop(true).output().await.ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+// Formats seconds since the Unix epoch as `YYYY-MM-DD HH:MM UTC (Weekday)`.
+pub fn format_utc(epoch_seconds: u64) -> String {
+    const WEEKDAYS: [&str; 7] = ["Thursday", "Friday", "Saturday", "Sunday", "Monday", "Tuesday", "Wednesday"];
+    let days = (epoch_seconds / 86_400) as i64;
+    let seconds_of_day = epoch_seconds % 86_400;
+
+    // Civil-from-days conversion (proleptic Gregorian calendar).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC ({})",
+        year,
+        month,
+        day,
+        seconds_of_day / 3_600,
+        (seconds_of_day % 3_600) / 60,
+        WEEKDAYS[days.rem_euclid(7) as usize]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_utc() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00 UTC (Thursday)");
+        assert_eq!(format_utc(951_782_400), "2000-02-29 00:00 UTC (Tuesday)");
+        assert_eq!(format_utc(1_792_064_700), "2026-10-15 11:45 UTC (Thursday)");
+    }
+
+    #[test]
+    fn test_summary_outside_git_repository() {
+        let snapshot = EnvironmentSnapshot {
+            os: "linux (x86_64)".to_string(),
+            cwd: PathBuf::from("/work"),
+            git_branch: None,
+            git_dirty: None,
+            datetime: "2026-10-15 11:45 UTC (Thursday)".to_string(),
+            shell: Some("/bin/zsh".to_string()),
+            cargo_target_dir: None,
+        };
+        let summary = snapshot.summary();
+        assert!(summary.contains("- Working directory: /work"));
+        assert!(summary.contains("- Git: not a repository"));
+        assert!(summary.ends_with("- Shell: /bin/zsh"));
+    }
+}
@@ -0,0 +1,248 @@
+use crate::api::models::{Message, Role};
+use crate::config::{ContextConfig, EvictionStrategyKind};
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+// Picks which history message to drop when the context window is over budget (the manager drops
+// its tool-call group with it). `history` is oldest first, each message paired with its token count. Returning `None` leaves history alone
+// and lets the manager fall back to evicting snippets.
+pub trait EvictionStrategy: Debug + Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn select_victim(&self, history: &[(Message, usize)]) -> Option<usize>;
+}
+
+pub fn from_config(config: &ContextConfig) -> Box<dyn EvictionStrategy> {
+    match config.eviction {
+        EvictionStrategyKind::OldestFirst => Box::new(OldestFirst),
+        EvictionStrategyKind::LargestFirst => Box::new(LargestFirst),
+        EvictionStrategyKind::Relevance => Box::new(RelevanceScored::new(Box::new(LexicalScorer))),
+        EvictionStrategyKind::PreserveRecent => Box::new(PreserveRecent { turns: config.preserve_turns }),
+    }
+}
+
+// Strategies other than oldest-first keep the message just added unless it is all there is,
+// so a large tool result is not dropped before the model has seen it.
+fn evictable(history: &[(Message, usize)]) -> usize {
+    history.len().saturating_sub(1).max(history.len().min(1))
+}
+
+// The victim plus the messages that must go with it: an assistant message's tool calls and
+// their results are evicted together, since a result without its call (or the reverse) is
+// rejected by the API.
+pub fn with_tool_group(history: &[(Message, usize)], victim: usize) -> Vec<usize> {
+    let call_index = match &history[victim].0 {
+        message if message.role == Role::Tool => history[..victim].iter().rposition(|(candidate, _)| {
+            candidate.tool_calls.iter().flatten().any(|call| Some(&call.id) == message.tool_call_id.as_ref())
+        }),
+        message if message.tool_calls.as_ref().is_some_and(|calls| !calls.is_empty()) => Some(victim),
+        _ => None,
+    };
+    let Some(call_index) = call_index else {
+        return vec![victim];
+    };
+    let ids: Vec<&String> = history[call_index].0.tool_calls.iter().flatten().map(|call| &call.id).collect();
+    let mut group = vec![call_index];
+    group.extend(
+        history
+            .iter()
+            .enumerate()
+            .skip(call_index + 1)
+            .filter(|(_, (message, _))| message.role == Role::Tool && message.tool_call_id.as_ref().is_some_and(|id| ids.contains(&id)))
+            .map(|(index, _)| index),
+    );
+    if !group.contains(&victim) {
+        group.push(victim);
+        group.sort_unstable();
+    }
+    group
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OldestFirst;
+
+impl EvictionStrategy for OldestFirst {
+    fn name(&self) -> &'static str {
+        "oldest_first"
+    }
+
+    fn select_victim(&self, history: &[(Message, usize)]) -> Option<usize> {
+        (!history.is_empty()).then_some(0)
+    }
+}
+
+// Drops the biggest message first (the oldest among equals), which usually means a stale tool
+// result rather than the conversation around it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LargestFirst;
+
+impl EvictionStrategy for LargestFirst {
+    fn name(&self) -> &'static str {
+        "largest_first"
+    }
+
+    fn select_victim(&self, history: &[(Message, usize)]) -> Option<usize> {
+        largest(&history[..evictable(history)])
+    }
+}
+
+fn largest(messages: &[(Message, usize)]) -> Option<usize> {
+    let mut victim: Option<(usize, usize)> = None;
+    for (index, (_, tokens)) in messages.iter().enumerate() {
+        if victim.is_none_or(|(_, largest)| *tokens > largest) {
+            victim = Some((index, *tokens));
+        }
+    }
+    victim.map(|(index, _)| index)
+}
+
+// Keeps the last `turns` user turns (each user message and everything after it) intact and
+// evicts the largest of the older messages first. When only protected turns are left it
+// degrades to oldest-first.
+#[derive(Debug, Clone, Copy)]
+pub struct PreserveRecent {
+    pub turns: usize,
+}
+
+impl EvictionStrategy for PreserveRecent {
+    fn name(&self) -> &'static str {
+        "preserve_recent"
+    }
+
+    fn select_victim(&self, history: &[(Message, usize)]) -> Option<usize> {
+        if self.turns == 0 {
+            return LargestFirst.select_victim(history);
+        }
+        let protected_from = history
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, (message, _))| message.role == Role::User)
+            .nth(self.turns - 1)
+            .map(|(index, _)| index)
+            .unwrap_or(0);
+        match protected_from {
+            0 => OldestFirst.select_victim(history),
+            end => largest(&history[..end]),
+        }
+    }
+}
+
+// Scores how related `text` is to `query`, higher meaning more related. The relevance strategy
+// takes any scorer; the only one shipped is lexical, and `[context] eviction = "relevance"`
+// uses it, so no embeddings are involved unless a caller sets its own strategy.
+pub trait RelevanceScorer: Debug + Send + Sync {
+    fn score(&self, query: &str, text: &str) -> f32;
+}
+
+// Cosine similarity over the sets of lowercase words of three or more characters.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LexicalScorer;
+
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| word.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+impl RelevanceScorer for LexicalScorer {
+    fn score(&self, query: &str, text: &str) -> f32 {
+        let (query, text) = (words(query), words(text));
+        if query.is_empty() || text.is_empty() {
+            return 0.0;
+        }
+        let shared = query.intersection(&text).count() as f32;
+        shared / ((query.len() * text.len()) as f32).sqrt()
+    }
+}
+
+// Evicts the message least related to the latest user message (the oldest among equals).
+// Without a user message to compare against it behaves like oldest-first.
+#[derive(Debug)]
+pub struct RelevanceScored {
+    scorer: Box<dyn RelevanceScorer>,
+}
+
+impl RelevanceScored {
+    pub fn new(scorer: Box<dyn RelevanceScorer>) -> Self {
+        RelevanceScored { scorer }
+    }
+}
+
+impl EvictionStrategy for RelevanceScored {
+    fn name(&self) -> &'static str {
+        "relevance"
+    }
+
+    fn select_victim(&self, history: &[(Message, usize)]) -> Option<usize> {
+        let query = history
+            .iter()
+            .rev()
+            .find(|(message, _)| message.role == Role::User)
+            .and_then(|(message, _)| message.content.as_deref());
+        let Some(query) = query else {
+            return (!history.is_empty()).then_some(0);
+        };
+        let mut victim: Option<(usize, f32)> = None;
+        for (index, (message, _)) in history.iter().enumerate().take(evictable(history)) {
+            let score = self.scorer.score(query, message.content.as_deref().unwrap_or_default());
+            if victim.is_none_or(|(_, lowest)| score < lowest) {
+                victim = Some((index, score));
+            }
+        }
+        victim.map(|(index, _)| index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::models::{ToolCall, ToolCallFunction};
+
+    fn message(role: Role, content: &str) -> (Message, usize) {
+        let message = Message { role, content: Some(content.to_string()), tool_calls: None, tool_call_id: None };
+        (message, content.len())
+    }
+
+    #[test]
+    fn test_strategies_pick_expected_victims() {
+        let history = vec![
+            message(Role::User, "how is the config parsed"),
+            message(Role::Tool, &"x".repeat(500)),
+            message(Role::User, "rename the parser module"),
+            message(Role::Assistant, "the parser module lives in src/parsing"),
+            message(Role::Tool, &"y".repeat(800)),
+        ];
+        assert_eq!(OldestFirst.select_victim(&history), Some(0));
+        assert_eq!(LargestFirst.select_victim(&history), Some(1));
+        assert_eq!(PreserveRecent { turns: 1 }.select_victim(&history), Some(1));
+        assert_eq!(RelevanceScored::new(Box::new(LexicalScorer)).select_victim(&history), Some(1));
+
+        // Everything is inside the protected turns, so it falls back to the oldest message.
+        assert_eq!(PreserveRecent { turns: 2 }.select_victim(&history), Some(0));
+        assert_eq!(LargestFirst.select_victim(&history[..1]), Some(0));
+        assert_eq!(LargestFirst.select_victim(&[]), None);
+    }
+
+    #[test]
+    fn test_tool_calls_and_results_are_grouped() {
+        let call = |id: &str| ToolCall {
+            id: id.to_string(),
+            tool_type: "function".to_string(),
+            function: ToolCallFunction { name: "FileReadTool".to_string(), arguments: "{}".to_string() },
+        };
+        let result = |id: &str| {
+            let (mut message, tokens) = message(Role::Tool, "contents");
+            message.tool_call_id = Some(id.to_string());
+            (message, tokens)
+        };
+        let (mut calling, tokens) = message(Role::Assistant, "");
+        calling.tool_calls = Some(vec![call("a"), call("b")]);
+        let history = vec![message(Role::User, "read both"), (calling, tokens), result("a"), result("b"), message(Role::User, "thanks")];
+
+        assert_eq!(with_tool_group(&history, 1), vec![1, 2, 3]);
+        assert_eq!(with_tool_group(&history, 3), vec![1, 2, 3]);
+        assert_eq!(with_tool_group(&history, 4), vec![4]);
+    }
+}
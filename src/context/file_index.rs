@@ -0,0 +1,265 @@
+use crate::config::global_config_dir;
+use crate::context::session::project_key;
+use crate::context::summaries::content_hash;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+const INDEX_DIR: &str = "index";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub hash: String,
+    // Nanoseconds since the Unix epoch; with `size`, lets unchanged files skip re-reading.
+    // Whole seconds would miss a same-size edit made in the second of the last update.
+    pub modified: u64,
+    pub size: u64,
+    pub language: String,
+    // From the provider named by `FileIndex::embedding_model`; None until embedded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
+}
+
+// What the project's files looked like when the index was last updated, keyed like the
+// onboarding summaries. Anything derived from file contents can rebuild only the entries an
+// update reports as added or changed instead of rescanning everything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FileIndex {
+    #[serde(default)]
+    pub updated_at: u64,
+    #[serde(default)]
+    pub files: BTreeMap<String, IndexEntry>,
+    #[serde(default)]
+    pub embedding_model: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IndexChanges {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+    pub unchanged: usize,
+    // Files whose content had to be read and hashed.
+    pub hashed: usize,
+}
+
+// Files that differ from the index by modification time or size, without reading them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Freshness {
+    pub new: Vec<String>,
+    pub modified: Vec<String>,
+    pub deleted: Vec<String>,
+}
+
+impl Freshness {
+    pub fn is_fresh(&self) -> bool {
+        self.new.is_empty() && self.modified.is_empty() && self.deleted.is_empty()
+    }
+}
+
+fn stat(path: &Path) -> Option<(u64, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+    let modified = u64::try_from(modified).ok()?;
+    Some((modified, metadata.len()))
+}
+
+pub fn language_of(path: &str) -> String {
+    match path.rsplit_once('.') {
+        Some((_, extension)) if !extension.contains('/') => crate::commands::generate::canonical_language(extension),
+        _ => "other".to_string(),
+    }
+}
+
+impl FileIndex {
+    // Brings the index up to date with `files` (relative key, path). Files whose modification
+    // time and size match their entry are not read; the rest are hashed, and only a changed
+    // hash counts as a change.
+    pub fn update(&mut self, files: &[(String, PathBuf)], now: u64) -> IndexChanges {
+        let mut changes = IndexChanges::default();
+        let mut seen = BTreeMap::new();
+        for (key, path) in files {
+            let Some((modified, size)) = stat(path) else { continue };
+            if let Some(entry) = self.files.get(key).filter(|e| e.modified == modified && e.size == size) {
+                seen.insert(key.clone(), entry.clone());
+                changes.unchanged += 1;
+                continue;
+            }
+            // Binary and non-UTF-8 files are not indexed.
+            let Ok(content) = fs::read_to_string(path) else { continue };
+            changes.hashed += 1;
+            let hash = content_hash(&content);
+            // A file touched without changing keeps its embedding.
+            let embedding = match self.files.get(key) {
+                None => {
+                    changes.added.push(key.clone());
+                    None
+                }
+                Some(entry) if entry.hash != hash => {
+                    changes.changed.push(key.clone());
+                    None
+                }
+                Some(entry) => {
+                    changes.unchanged += 1;
+                    entry.embedding.clone()
+                }
+            };
+            seen.insert(key.clone(), IndexEntry { hash, modified, size, language: language_of(key), embedding });
+        }
+        changes.removed = self.files.keys().filter(|key| !seen.contains_key(*key)).cloned().collect();
+        self.files = seen;
+        self.updated_at = now;
+        changes
+    }
+
+    pub fn freshness(&self, files: &[(String, PathBuf)]) -> Freshness {
+        let mut freshness = Freshness::default();
+        for (key, path) in files {
+            match (self.files.get(key), stat(path)) {
+                (None, _) => freshness.new.push(key.clone()),
+                (Some(entry), Some((modified, size))) if entry.modified == modified && entry.size == size => {}
+                (Some(_), _) => freshness.modified.push(key.clone()),
+            }
+        }
+        let present: std::collections::HashSet<&String> = files.iter().map(|(key, _)| key).collect();
+        freshness.deleted = self.files.keys().filter(|key| !present.contains(key)).cloned().collect();
+        freshness
+    }
+
+    // Per language: (files indexed, files present). Present files that are not text count as
+    // present but can never be indexed.
+    pub fn coverage(&self, files: &[(String, PathBuf)]) -> BTreeMap<String, (usize, usize)> {
+        let mut coverage: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+        for (key, _) in files {
+            let counts = coverage.entry(language_of(key)).or_default();
+            counts.1 += 1;
+            if self.files.contains_key(key) {
+                counts.0 += 1;
+            }
+        }
+        coverage
+    }
+
+    // Switches to embeddings from `model`, dropping any made by another.
+    pub fn set_embedding_model(&mut self, model: &str) {
+        if self.embedding_model != model {
+            self.files.values_mut().for_each(|entry| entry.embedding = None);
+            self.embedding_model = model.to_string();
+        }
+    }
+
+    pub fn unembedded(&self) -> Vec<String> {
+        self.files.iter().filter(|(_, entry)| entry.embedding.is_none()).map(|(key, _)| key.clone()).collect()
+    }
+
+    pub fn embedded_count(&self) -> usize {
+        self.files.values().filter(|entry| entry.embedding.is_some()).count()
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.files.values().map(|e| e.size).sum()
+    }
+}
+
+// The index for one project, kept in the config directory next to its onboarding summaries.
+#[derive(Debug, Clone, Default)]
+pub struct IndexStore {
+    path: Option<PathBuf>,
+}
+
+impl IndexStore {
+    pub fn new(path: PathBuf) -> Self {
+        IndexStore { path: Some(path) }
+    }
+
+    pub fn for_project(project_dir: &Path) -> Self {
+        match global_config_dir() {
+            Some(dir) => Self::new(dir.join(INDEX_DIR).join(format!("{}.json", project_key(project_dir)))),
+            None => Self::default(),
+        }
+    }
+
+    pub fn path(&self) -> Result<&Path> {
+        self.path.as_deref().ok_or_else(|| anyhow!("The file index is unavailable: no config directory"))
+    }
+
+    pub fn load(&self) -> Result<FileIndex> {
+        let path = self.path()?;
+        match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).with_context(|| format!("Corrupt file index {:?}", path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(FileIndex::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read file index {:?}", path)),
+        }
+    }
+
+    pub fn save(&self, index: &FileIndex) -> Result<()> {
+        let path = self.path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create {:?}", parent))?;
+        }
+        fs::write(path, serde_json::to_string(index)?).with_context(|| format!("Failed to write file index {:?}", path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_is_incremental() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = |name: &str| (name.to_string(), dir.path().join(name));
+        fs::write(dir.path().join("lib.rs"), "fn a() {}").unwrap();
+        fs::write(dir.path().join("build.py"), "print(1)").unwrap();
+        let mut files = vec![file("build.py"), file("lib.rs")];
+
+        let mut index = FileIndex::default();
+        let first = index.update(&files, 1);
+        assert_eq!((first.added.len(), first.hashed), (2, 2));
+        let second = index.update(&files, 2);
+        assert_eq!((second.unchanged, second.hashed), (2, 0));
+
+        fs::write(dir.path().join("lib.rs"), "fn a() { b(); }").unwrap();
+        fs::write(dir.path().join("notes.md"), "# Notes").unwrap();
+        files.push(file("notes.md"));
+        files.retain(|(key, _)| key != "build.py");
+        let freshness = index.freshness(&files);
+        assert_eq!((freshness.new, freshness.modified, freshness.deleted), (vec!["notes.md".to_string()], vec!["lib.rs".to_string()], vec!["build.py".to_string()]));
+
+        let third = index.update(&files, 3);
+        assert_eq!((third.added, third.changed, third.removed), (vec!["notes.md".to_string()], vec!["lib.rs".to_string()], vec!["build.py".to_string()]));
+        assert!(index.freshness(&files).is_fresh());
+        assert_eq!(index.coverage(&files).get("rust"), Some(&(1, 1)));
+        assert_eq!(index.files["notes.md"].language, "markdown");
+
+        index.set_embedding_model("hashed:8");
+        index.files.values_mut().for_each(|entry| entry.embedding = Some(vec![1.0]));
+        fs::write(dir.path().join("notes.md"), "# Notes!").unwrap();
+        index.update(&files, 4);
+        assert_eq!((index.unembedded(), index.embedded_count()), (vec!["notes.md".to_string()], 1));
+        index.set_embedding_model("ollama:nomic-embed-text");
+        assert_eq!(index.embedded_count(), 0);
+    }
+
+    #[test]
+    fn test_same_size_edit_within_a_second_is_seen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lib.rs");
+        let files = vec![("lib.rs".to_string(), path.clone())];
+        let second = UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        let write = |content: &str, nanos: u64| {
+            fs::write(&path, content).unwrap();
+            fs::File::options().write(true).open(&path).unwrap().set_modified(second + std::time::Duration::from_nanos(nanos)).unwrap();
+        };
+
+        write("fn a() {}", 100);
+        let mut index = FileIndex::default();
+        index.update(&files, 1_700_000_000);
+        write("fn b() {}", 200);
+        assert_eq!(index.freshness(&files).modified, vec!["lib.rs".to_string()]);
+        assert_eq!(index.update(&files, 1_700_000_000).changed, vec!["lib.rs".to_string()]);
+    }
+}
@@ -0,0 +1,135 @@
+use crate::config::global_config_dir;
+use crate::context::session::project_key;
+use crate::file_lock::{with_lock, write_atomically};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MEMORY_DIR: &str = "memory";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryFact {
+    pub id: usize,
+    pub fact: String,
+}
+
+// Durable facts about one project ("tests run with `cargo nextest`"), kept across sessions in
+// the config directory and sent with every request. Each change re-reads the file under its
+// lock, so concurrent sessions in the same checkout don't drop each other's facts, and
+// replaces it atomically, so a read never sees half a write. Cloning shares the same file.
+#[derive(Debug, Clone, Default)]
+pub struct ProjectMemory {
+    path: Option<PathBuf>,
+}
+
+impl ProjectMemory {
+    pub fn new(path: PathBuf) -> Self {
+        ProjectMemory { path: Some(path) }
+    }
+
+    // Memory for the project in `project_dir`; unavailable (every call fails) when there is
+    // no config directory.
+    pub fn for_project(project_dir: &Path) -> Self {
+        match global_config_dir() {
+            Some(dir) => Self::new(dir.join(MEMORY_DIR).join(format!("{}.json", project_key(project_dir)))),
+            None => Self::default(),
+        }
+    }
+
+    fn path(&self) -> Result<&Path> {
+        self.path.as_deref().ok_or_else(|| anyhow!("Project memory is unavailable: no config directory"))
+    }
+
+    pub fn facts(&self) -> Result<Vec<MemoryFact>> {
+        let path = self.path()?;
+        match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).with_context(|| format!("Corrupt project memory {:?}", path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read project memory {:?}", path)),
+        }
+    }
+
+    fn update<T>(&self, change: impl FnOnce(&mut Vec<MemoryFact>) -> Result<T>) -> Result<T> {
+        let path = self.path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create {:?}", parent))?;
+        }
+        with_lock(path, || {
+            let mut facts = self.facts()?;
+            let result = change(&mut facts)?;
+            let content = serde_json::to_string_pretty(&facts)?;
+            write_atomically(path, content).with_context(|| format!("Failed to write project memory {:?}", path))?;
+            Ok(result)
+        })
+    }
+
+    // Returns the new fact's id. Saving a fact that is already remembered returns its id.
+    pub fn remember(&self, fact: &str) -> Result<usize> {
+        let fact = fact.trim();
+        if fact.is_empty() {
+            bail!("A fact to remember cannot be empty");
+        }
+        self.update(|facts| {
+            if let Some(existing) = facts.iter().find(|f| f.fact == fact) {
+                return Ok(existing.id);
+            }
+            let id = facts.iter().map(|f| f.id).max().unwrap_or(0) + 1;
+            facts.push(MemoryFact { id, fact: fact.to_string() });
+            Ok(id)
+        })
+    }
+
+    pub fn forget(&self, id: usize) -> Result<MemoryFact> {
+        self.update(|facts| {
+            let index = facts.iter().position(|f| f.id == id).ok_or_else(|| anyhow!("No remembered fact with id {}", id))?;
+            Ok(facts.remove(index))
+        })
+    }
+
+    pub fn clear(&self) -> Result<usize> {
+        self.update(|facts| Ok(std::mem::take(facts).len()))
+    }
+
+    // The block sent with each request; None when nothing is remembered or memory can't be read.
+    pub fn render(&self) -> Option<String> {
+        let facts = self
+            .facts()
+            .map_err(|e| tracing::warn!("Skipping project memory: {:#}", e))
+            .ok()
+            .filter(|facts| !facts.is_empty())?;
+        let lines: Vec<String> = facts.iter().map(|f| format!("- [{}] {}", f.id, f.fact)).collect();
+        Some(format!("Facts remembered about this project from earlier sessions:\n{}", lines.join("\n")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remember_forget_and_render() {
+        let dir = tempfile::tempdir().unwrap();
+        let memory = ProjectMemory::new(dir.path().join("memory").join("project.json"));
+        assert_eq!(memory.render(), None);
+
+        assert_eq!(memory.remember("Tests run with `cargo nextest`").unwrap(), 1);
+        assert_eq!(memory.remember("The api module is being migrated to axum").unwrap(), 2);
+        assert_eq!(memory.remember("Tests run with `cargo nextest`").unwrap(), 1);
+        assert!(memory.remember("   ").is_err());
+
+        assert_eq!(memory.forget(1).unwrap().fact, "Tests run with `cargo nextest`");
+        assert!(memory.forget(1).is_err());
+        assert_eq!(memory.remember("Use tracing, not println").unwrap(), 3);
+        assert_eq!(
+            memory.clone().render().unwrap(),
+            "Facts remembered about this project from earlier sessions:\n\
+             - [2] The api module is being migrated to axum\n\
+             - [3] Use tracing, not println"
+        );
+
+        assert_eq!(memory.clear().unwrap(), 2);
+        assert_eq!(memory.render(), None);
+        assert!(ProjectMemory::default().remember("x").is_err());
+    }
+}
@@ -1,26 +1,153 @@
+pub mod citations;
+pub mod embeddings;
+pub mod environment;
+pub mod eviction;
+pub mod memory;
+pub mod summaries;
+pub mod file_index;
+pub mod provider;
+pub mod retrieval;
+pub mod session;
+pub mod style;
+
 use crate::api::models::{Message, Role};
-use crate::config::Config;
+use crate::config::{Config, SnippetOrder};
+use crate::file_lock::FileLock;
+use crate::tools::notes::NotesStore;
+use eviction::EvictionStrategy;
+use memory::ProjectMemory;
+use summaries::SummaryCache;
+use session::{NamedSession, NamedSessionStore, SessionJournal};
 use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use tiktoken_rs::{get_bpe_from_model, CoreBPE};
 use tracing::{debug, info, warn};
 
 
 const DEFAULT_TOKENIZER_MODEL: &str = "gpt-4"; 
 const MAX_CONTEXT_TOKENS: usize = 4000; 
+const FILE_READ_TOOL: &str = "FileReadTool";
+// Journal entries beyond this are compacted once most of them have been evicted.
+const JOURNAL_COMPACT_THRESHOLD: usize = 200;
 
 #[derive(Debug, Clone)]
 pub struct ContextSnippet {
     pub source: String, 
     pub content: String,
     token_count: usize, 
+    // Number of history messages added before this snippet.
+    position: usize,
+    // The file a snippet holds in full and its modification time when read; such snippets
+    // are re-read once the file changes.
+    file: Option<(PathBuf, Option<SystemTime>)>,
+    refreshed: bool,
+}
+
+// A snippet as the `/context` view lists it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnippetStat {
+    pub source: String,
+    pub tokens: usize,
+    pub refreshed: bool,
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+
+// A line from an earlier assistant message or tool result that matched a `/find` query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryMatch {
+    pub turn: usize,
+    pub role: Role,
+    pub line: String,
+}
+
+// A history message or snippet dropped to keep the context under its token limit. `turn` is
+// the message's position in the session; snippets have none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvictedItem {
+    pub turn: Option<usize>,
+    pub source: String,
+    pub preview: String,
+    pub tokens: usize,
+}
+
+// Characters of a dropped message kept for `/evicted`.
+const EVICTED_PREVIEW_CHARS: usize = 80;
+
+fn evicted_preview(content: &str) -> String {
+    let flat = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    match flat.char_indices().nth(EVICTED_PREVIEW_CHARS) {
+        Some((cut, _)) => format!("{}...", &flat[..cut]),
+        None => flat,
+    }
+}
+
+fn with_thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
 }
 
+// One line summarizing `items`, e.g. "Dropped 3 old messages (~1,200 tokens) to fit the context."
+pub fn eviction_notice(items: &[EvictedItem]) -> Option<String> {
+    if items.is_empty() {
+        return None;
+    }
+    let messages = items.iter().filter(|item| item.turn.is_some()).count();
+    let snippets = items.len() - messages;
+    let mut parts = Vec::new();
+    if messages > 0 {
+        parts.push(format!("{} old message{}", messages, if messages == 1 { "" } else { "s" }));
+    }
+    if snippets > 0 {
+        parts.push(format!("{} snippet{}", snippets, if snippets == 1 { "" } else { "s" }));
+    }
+    let tokens: usize = items.iter().map(|item| item.tokens).sum();
+    Some(format!(
+        "Dropped {} (~{} tokens) to fit the context.",
+        parts.join(" and "),
+        with_thousands(tokens)
+    ))
+}
 
 pub struct ContextManager {
     #[allow(dead_code)]
     config: Config,
+    pinned_messages: Vec<(Message, usize)>,
+    environment: Option<(Message, usize)>,
     history: Vec<(Message, usize)>, 
+    // Order in which each history message was added, parallel to `history`.
+    history_positions: Vec<usize>,
+    messages_added: usize,
     context_snippets: Vec<ContextSnippet>,
+    // Path -> (content hash, id of the tool call whose result holds that content).
+    file_reads: HashMap<String, (u64, String)>,
+    // Files and URLs the model has been shown, in first-seen order, for citing.
+    sources: Vec<String>,
+    journal: Option<SessionJournal>,
+    // The `--session` conversation, saved after every message.
+    named_session: Option<(NamedSessionStore, NamedSession)>,
+    notes: Option<NotesStore>,
+    memory: Option<ProjectMemory>,
+    overview: Option<String>,
+    eviction: Box<dyn EvictionStrategy>,
+    // Everything evicted this session, and how much of it has been reported to the user.
+    evicted: Vec<EvictedItem>,
+    evictions_reported: usize,
     tokenizer: CoreBPE,
     total_token_count: usize,
     max_tokens: usize, 
@@ -32,56 +159,317 @@ impl ContextManager {
         let tokenizer = get_bpe_from_model(DEFAULT_TOKENIZER_MODEL)
             .map_err(|e| anyhow!("Failed to load tokenizer: {}", e))?;
         let max_tokens = MAX_CONTEXT_TOKENS; 
-        Ok(ContextManager {
+        let system_prompt = config
+            .prompt
+            .resolve_system_prompt()
+            .context("Failed to load configured system prompt")?;
+        let mut manager = ContextManager {
+            eviction: eviction::from_config(&config.context),
+            evicted: Vec::new(),
+            evictions_reported: 0,
             config,
+            pinned_messages: Vec::new(),
+            environment: None,
             history: Vec::new(),
+            history_positions: Vec::new(),
+            messages_added: 0,
             context_snippets: Vec::new(),
+            file_reads: HashMap::new(),
+            sources: Vec::new(),
+            journal: None,
+            named_session: None,
+            notes: None,
+            memory: None,
+            overview: None,
             tokenizer,
             total_token_count: 0,
             max_tokens,
-        })
+        };
+        if let Some(prompt) = system_prompt {
+            manager.pin_system_message(prompt)?;
+        }
+        Ok(manager)
     }
 
     
     
     
-    fn count_tokens(&self, text: &str) -> usize {
+    pub fn count_tokens(&self, text: &str) -> usize {
         self.tokenizer.encode_with_special_tokens(text).len()
     }
 
     
     pub fn add_message(&mut self, message: Message) -> Result<()> {
-        
+        self.append_to_transcript(&message);
         let tokens = match &message.content {
             Some(content_str) => self.count_tokens(content_str), 
             None => 0, 
         };
         debug!(role = ?message.role, tokens = tokens, "Adding message to history");
         self.history.push((message, tokens));
+        self.history_positions.push(self.messages_added);
+        self.messages_added += 1;
         self.total_token_count += tokens;
         self.ensure_token_limit()
             .context("Failed to ensure token limit after adding message")?;
+        self.journal_last_message();
+        self.save_named_session();
+        Ok(())
+    }
+
+    // Continues `session`: its messages are restored and every later message saves it, with
+    // the live history, back to `store` and is appended to its full transcript. Save failures
+    // are logged like journal failures.
+    pub fn attach_named_session(&mut self, store: NamedSessionStore, session: NamedSession) -> Result<()> {
+        // Sessions saved before transcripts existed start theirs from what they kept.
+        if !session.messages.is_empty() && store.transcript(&session.name)?.is_empty() {
+            store.append_transcript(&session.name, &session.messages)?;
+        }
+        self.restore_history(session.messages.clone())?;
+        self.named_session = Some((store, session));
+        Ok(())
+    }
+
+    fn append_to_transcript(&self, message: &Message) {
+        let Some((store, session)) = self.named_session.as_ref() else { return };
+        if let Err(e) = store.append_transcript(&session.name, std::slice::from_ref(message)) {
+            warn!("Appending to the transcript of session '{}' failed: {:#}", session.name, e);
+        }
+    }
+
+    fn save_named_session(&mut self) {
+        let messages = self.history_messages();
+        let Some((store, session)) = self.named_session.as_mut() else { return };
+        session.messages = messages;
+        session.updated_at = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        if let Err(e) = store.save(session) {
+            warn!("Saving session '{}' failed: {:#}", session.name, e);
+        }
+    }
+
+    // Journal failures are logged rather than returned; losing crash recovery shouldn't
+    // end the session.
+    fn journal_last_message(&mut self) {
+        let Some(journal) = self.journal.as_mut() else { return };
+        let Some((message, _)) = self.history.last() else { return };
+        if let Err(e) = journal.append(message) {
+            warn!("Session journal write failed: {:#}", e);
+        }
+        if journal.entries() > JOURNAL_COMPACT_THRESHOLD && journal.entries() > 2 * self.history.len() {
+            self.compact_journal();
+        }
+    }
+
+    fn compact_journal(&mut self) {
+        let Some(journal) = self.journal.as_mut() else { return };
+        let messages: Vec<Message> = self.history.iter().map(|(message, _)| message.clone()).collect();
+        if let Err(e) = journal.compact(&messages) {
+            warn!("Session journal compaction failed: {:#}", e);
+        }
+    }
+
+    // Journals the current history and every message added from now on to `path`.
+    pub fn start_journal(&mut self, path: &Path, lock: FileLock) -> Result<()> {
+        let messages: Vec<Message> = self.history.iter().map(|(message, _)| message.clone()).collect();
+        self.journal = Some(SessionJournal::start(path, &messages, lock)?);
+        Ok(())
+    }
+
+    // Ends journaling after a clean exit, removing the journal so nothing is offered for recovery.
+    pub fn finish_journal(&mut self) -> Result<()> {
+        match self.journal.take() {
+            Some(journal) => journal.finish(),
+            None => Ok(()),
+        }
+    }
+
+    // Replays messages recovered from an interrupted session's journal.
+    pub fn restore_history(&mut self, messages: Vec<Message>) -> Result<()> {
+        for message in messages {
+            self.add_message(message)?;
+        }
+        Ok(())
+    }
+
+    // Pinned system messages always lead the constructed request and are never evicted
+    // or cleared; they are sent in the order they were pinned.
+    pub fn pin_system_message(&mut self, content: String) -> Result<()> {
+        let tokens = self.count_tokens(&content);
+        debug!(tokens = tokens, "Pinning system message");
+        let message = Message {
+            role: Role::System,
+            content: Some(content),
+            tool_calls: None,
+            tool_call_id: None,
+        };
+        self.pinned_messages.push((message, tokens));
+        self.total_token_count += tokens;
+        self.ensure_token_limit()
+            .context("Failed to ensure token limit after pinning system message")?;
+        Ok(())
+    }
+
+    // The environment block follows the pinned messages and, unlike them, is replaced
+    // rather than appended when refreshed.
+    pub fn set_environment_context(&mut self, content: String) -> Result<()> {
+        let tokens = self.count_tokens(&content);
+        debug!(tokens = tokens, "Setting environment context");
+        if let Some((_, previous_tokens)) = self.environment.take() {
+            self.total_token_count -= previous_tokens;
+        }
+        let message = Message {
+            role: Role::System,
+            content: Some(content),
+            tool_calls: None,
+            tool_call_id: None,
+        };
+        self.environment = Some((message, tokens));
+        self.total_token_count += tokens;
+        self.ensure_token_limit()
+            .context("Failed to ensure token limit after setting environment context")?;
         Ok(())
     }
 
+    // The notes themselves stay out of the window; each request carries a short summary
+    // of them after the environment block, budget permitting.
+    pub fn attach_notes(&mut self, notes: NotesStore) {
+        self.notes = Some(notes);
+    }
+
+    // The messages still in the window, oldest first.
+    pub fn history_messages(&self) -> Vec<Message> {
+        self.history.iter().map(|(message, _)| message.clone()).collect()
+    }
+
+    // Remembered project facts are re-read for every request, so facts saved mid-session
+    // reach the model on the next turn. They go between the environment block and the notes.
+    pub fn attach_memory(&mut self, memory: ProjectMemory) {
+        self.memory = Some(memory);
+    }
+
+    // The module overview `opencode onboard` cached, sent after the remembered facts so the
+    // model knows where things live without reading files first. Read once, here.
+    pub fn attach_summaries(&mut self, summaries: &SummaryCache) {
+        self.overview = summaries.render();
+    }
+
+    // Replaces the strategy chosen by `[context] eviction`, e.g. with one using a custom scorer.
+    pub fn set_eviction_strategy(&mut self, strategy: Box<dyn EvictionStrategy>) {
+        debug!(strategy = strategy.name(), "Setting eviction strategy");
+        self.eviction = strategy;
+    }
+
+    pub fn has_pinned_messages(&self) -> bool {
+        !self.pinned_messages.is_empty()
+    }
+
+    fn pinned_token_count(&self) -> usize {
+        self.pinned_messages.iter().chain(&self.environment).map(|(_, tokens)| tokens).sum()
+    }
+
     
     pub fn clear_history(&mut self) {
         info!("Clearing conversation history");
-        self.total_token_count = self
-            .context_snippets
-            .iter()
-            .map(|s| s.token_count)
-            .sum();
+        self.total_token_count = self.pinned_token_count()
+            + self
+                .context_snippets
+                .iter()
+                .map(|s| s.token_count)
+                .sum::<usize>();
         self.history.clear();
+        self.history_positions.clear();
+        self.compact_journal();
     }
 
     
     pub fn clear_snippets(&mut self) {
         info!("Clearing context snippets");
-        self.total_token_count = self.history.iter().map(|(_, tokens)| tokens).sum();
+        self.total_token_count = self.pinned_token_count()
+            + self.history.iter().map(|(_, tokens)| tokens).sum::<usize>();
         self.context_snippets.clear();
     }
 
+    // Adds file or command output to the context as a system message, remembering where in
+    // the conversation it arrived for the interleaved snippet order.
+    pub fn add_snippet(&mut self, source: String, content: String) -> Result<()> {
+        let token_count = self.count_tokens(&Self::format_snippet_content(&source, &content));
+        debug!(tokens = token_count, source = %source, "Adding context snippet");
+        self.context_snippets.push(ContextSnippet { source, content, token_count, position: self.messages_added, file: None, refreshed: false });
+        self.total_token_count += token_count;
+        self.ensure_token_limit()
+            .context("Failed to ensure token limit after adding snippet")?;
+        Ok(())
+    }
+
+    // Adds the whole of `path` as a snippet that `refresh_snippets` keeps current.
+    pub fn add_file_snippet(&mut self, path: &Path) -> Result<()> {
+        let modified = modified_time(path);
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        self.add_snippet(path.display().to_string(), content)?;
+        if let Some(snippet) = self.context_snippets.iter_mut().rev().find(|s| s.source == path.display().to_string()) {
+            snippet.file = Some((path.to_path_buf(), modified));
+        }
+        self.track_source(&path.display().to_string());
+        Ok(())
+    }
+
+    // Records a file or URL whose content reached the model, so answers can cite it.
+    pub fn track_source(&mut self, source: &str) {
+        if !self.sources.iter().any(|s| s == source) {
+            self.sources.push(source.to_string());
+        }
+    }
+
+    pub fn sources(&self) -> &[String] {
+        &self.sources
+    }
+
+    // Re-reads file snippets whose file changed since it was read and returns their sources.
+    // A file that can no longer be read keeps its last content.
+    pub fn refresh_snippets(&mut self) -> Result<Vec<String>> {
+        let mut refreshed = Vec::new();
+        for i in 0..self.context_snippets.len() {
+            let Some((path, read_at)) = self.context_snippets[i].file.clone() else { continue };
+            let modified = modified_time(&path);
+            if modified == read_at {
+                continue;
+            }
+            let content = match std::fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(e) => {
+                    warn!(path = %path.display(), "Cannot refresh snippet: {}", e);
+                    continue;
+                }
+            };
+            let token_count = self.count_tokens(&Self::format_snippet_content(&self.context_snippets[i].source, &content));
+            let snippet = &mut self.context_snippets[i];
+            self.total_token_count = self.total_token_count - snippet.token_count + token_count;
+            debug!(source = %snippet.source, old = snippet.token_count, new = token_count, "Refreshed snippet");
+            snippet.content = content;
+            snippet.token_count = token_count;
+            snippet.file = Some((path, modified));
+            snippet.refreshed = true;
+            refreshed.push(snippet.source.clone());
+        }
+        if !refreshed.is_empty() {
+            self.ensure_token_limit().context("Failed to ensure token limit after refreshing snippets")?;
+        }
+        Ok(refreshed)
+    }
+
+    pub fn snippet_stats(&self) -> Vec<SnippetStat> {
+        self.context_snippets
+            .iter()
+            .map(|s| SnippetStat { source: s.source.clone(), tokens: s.token_count, refreshed: s.refreshed })
+            .collect()
+    }
+
+    // (pinned, history, total) token counts and the budget they share.
+    pub fn token_stats(&self) -> (usize, usize, usize, usize) {
+        let history = self.history.iter().map(|(_, tokens)| tokens).sum();
+        (self.pinned_token_count(), history, self.total_token_count, self.context_budget())
+    }
+
     
     fn format_snippet_content(source: &str, content: &str) -> String {
         
@@ -90,22 +478,57 @@ impl ContextManager {
 
     
     
+    // The tokens a request may use: the window less what is held back for the reply.
+    fn context_budget(&self) -> usize {
+        self.max_tokens.saturating_sub(self.config.context.budget.response_tokens)
+    }
+
+    // The most of the budget snippets may take.
+    fn snippet_cap(&self) -> usize {
+        self.context_budget() * usize::from(self.config.context.budget.max_snippet_percent.min(100)) / 100
+    }
+
     fn ensure_token_limit(&mut self) -> Result<()> {
-        while self.total_token_count > self.max_tokens {
-            
-            
-            if !self.history.is_empty() {
-                let (removed_message, removed_tokens) = self.history.remove(0);
-                self.total_token_count -= removed_tokens;
-                debug!(tokens = removed_tokens, role = ?removed_message.role, "Evicted oldest message");
+        while self.total_token_count > self.context_budget() {
+            // Snippets over their cap are never sent, so they go before any history does.
+            let snippet_tokens: usize = self.context_snippets.iter().map(|s| s.token_count).sum();
+            let victim = if snippet_tokens > self.snippet_cap() { None } else { self.eviction.select_victim(&self.history) };
+            if let Some(victim) = victim {
+                let group = eviction::with_tool_group(&self.history, victim);
+                let first_evicted = self.evicted.len();
+                for index in group.into_iter().rev() {
+                    let (removed_message, removed_tokens) = self.history.remove(index);
+                    let position = self.history_positions.remove(index);
+                    self.total_token_count -= removed_tokens;
+                    debug!(tokens = removed_tokens, role = ?removed_message.role, index, strategy = self.eviction.name(), "Evicted message");
+                    let calls: Vec<&str> = removed_message.tool_calls.iter().flatten().map(|c| c.function.name.as_str()).collect();
+                    let preview = match (&removed_message.content, calls.is_empty()) {
+                        (Some(content), _) if !content.trim().is_empty() => evicted_preview(content),
+                        (_, false) => format!("[called {}]", calls.join(", ")),
+                        _ => String::new(),
+                    };
+                    self.evicted.push(EvictedItem {
+                        turn: Some(position),
+                        source: format!("{:?}", removed_message.role).to_lowercase(),
+                        preview,
+                        tokens: removed_tokens,
+                    });
+                }
+                self.evicted[first_evicted..].reverse();
             } else if !self.context_snippets.is_empty() {
                 let removed_snippet = self.context_snippets.remove(0);
                 self.total_token_count -= removed_snippet.token_count;
                 debug!(tokens = removed_snippet.token_count, source = %removed_snippet.source, "Evicted oldest snippet");
+                self.evicted.push(EvictedItem {
+                    turn: None,
+                    preview: evicted_preview(&removed_snippet.content),
+                    source: format!("snippet {}", removed_snippet.source),
+                    tokens: removed_snippet.token_count,
+                });
             } else {
                 
                 warn!("Token limit exceeded but nothing to evict. Total tokens: {}", self.total_token_count);
-                return Err(anyhow!("Cannot reduce tokens below limit, history and snippets are empty, but total_token_count ({}) > max_tokens ({})", self.total_token_count, self.max_tokens));
+                return Err(anyhow!("Cannot reduce tokens below limit, history and snippets are empty, but total_token_count ({}) > budget ({}) (pinned: {})", self.total_token_count, self.context_budget(), self.pinned_token_count()));
             }
         }
         Ok(())
@@ -119,37 +542,74 @@ impl ContextManager {
         self.ensure_token_limit()
             .context("Failed to ensure token limit before constructing API messages")?;
 
-        let mut api_messages = Vec::new();
-        let mut current_tokens = 0;
+        let budget = self.context_budget();
+        let mut current_tokens = self.pinned_token_count();
 
-        
-        
-        
+        let memory_block = self.memory.as_ref().and_then(ProjectMemory::render).and_then(|memory| {
+            let tokens = self.count_tokens(&memory);
+            if current_tokens + tokens <= budget {
+                current_tokens += tokens;
+                Some(Message { role: Role::System, content: Some(memory), tool_calls: None, tool_call_id: None })
+            } else {
+                warn!("Skipping project memory during construction due to token limit");
+                None
+            }
+        });
+
+        let overview = self.overview.clone().and_then(|overview| {
+            let tokens = self.count_tokens(&overview);
+            if current_tokens + tokens <= budget {
+                current_tokens += tokens;
+                Some(Message { role: Role::System, content: Some(overview), tool_calls: None, tool_call_id: None })
+            } else {
+                warn!("Skipping project overview during construction due to token limit");
+                None
+            }
+        });
+
+        let notes_summary = self.notes.as_ref().and_then(NotesStore::summary).and_then(|summary| {
+            let tokens = self.count_tokens(&summary);
+            if current_tokens + tokens <= budget {
+                current_tokens += tokens;
+                Some(Message { role: Role::System, content: Some(summary), tool_calls: None, tool_call_id: None })
+            } else {
+                warn!("Skipping notes summary during construction due to token limit");
+                None
+            }
+        });
+
+        // Budget goes to the newest snippets, then to history newest-first. Snippets stop at
+        // their cap and leave the history its minimum share, as far as the history needs it.
+        let shares = self.config.context.budget;
+        let history_tokens: usize = self.history.iter().map(|(_, tokens)| tokens).sum();
+        let history_reserve = history_tokens.min(budget * usize::from(shares.min_history_percent.min(100)) / 100);
+        let snippet_limit = self.snippet_cap().min(budget.saturating_sub(current_tokens).saturating_sub(history_reserve));
+        let mut snippet_tokens_used = 0;
+        let mut snippets = Vec::new();
         for snippet in self.context_snippets.iter().rev() {
              let formatted_content = Self::format_snippet_content(&snippet.source, &snippet.content);
              
              let snippet_tokens = self.count_tokens(&formatted_content); 
-             if current_tokens + snippet_tokens <= self.max_tokens {
-                 api_messages.push(Message {
+             if snippet_tokens_used + snippet_tokens <= snippet_limit {
+                 let message = Message {
                      role: Role::System, 
                      content: Some(formatted_content), 
                      tool_calls: None, 
                      tool_call_id: None, 
-                 });
+                 };
+                 snippets.push((snippet.position, message));
                  current_tokens += snippet_tokens;
+                 snippet_tokens_used += snippet_tokens;
              } else {
                  warn!(source = %snippet.source, "Skipping snippet during construction due to token limit");
              }
         }
-        
-        api_messages.reverse();
+        snippets.reverse();
 
-
-        
-        
-        for (message, message_tokens) in self.history.iter().rev() {
-            if current_tokens + message_tokens <= self.max_tokens {
-                api_messages.push(message.clone());
+        let mut history = Vec::new();
+        for ((message, message_tokens), position) in self.history.iter().zip(&self.history_positions).rev() {
+            if current_tokens + message_tokens <= budget {
+                history.push((*position, message.clone()));
                 current_tokens += message_tokens;
             } else {
                  warn!(role = ?message.role, "Skipping history message during construction due to token limit");
@@ -157,19 +617,97 @@ impl ContextManager {
                  break;
             }
         }
+        history.reverse();
 
-        
-        
-        let history_start_index = self.context_snippets.len(); 
-        if api_messages.len() > history_start_index {
-             api_messages[history_start_index..].reverse();
-        }
+        let mut api_messages = match self.config.context.snippet_order {
+            SnippetOrder::BeforeHistory => snippets.into_iter().chain(history).map(|(_, message)| message).collect(),
+            SnippetOrder::Interleaved => interleave_snippets(snippets, history),
+        };
 
+        let mut pinned: Vec<Message> = self.pinned_messages.iter().chain(&self.environment).map(|(m, _)| m.clone()).collect();
+        pinned.extend(memory_block);
+        pinned.extend(overview);
+        pinned.extend(notes_summary);
+        pinned.append(&mut api_messages);
+        let api_messages = pinned;
 
         debug!(messages_count = api_messages.len(), final_tokens = current_tokens, "Constructed API messages");
         Ok(api_messages)
     }
 
+    // Keeps one canonical copy of each file read in history. A re-read of unchanged
+    // content is swapped for a marker pointing at the earlier tool call, as long as that
+    // result has not been evicted; new or changed content is passed through and recorded.
+    pub fn dedupe_file_read(&mut self, tool_name: &str, arguments: &Value, tool_call_id: &str, result: Value) -> Value {
+        if tool_name != FILE_READ_TOOL {
+            return result;
+        }
+        let (Some(path), Some(content)) = (
+            arguments.get("path").and_then(Value::as_str),
+            result.get("content").and_then(Value::as_str),
+        ) else {
+            return result;
+        };
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        if let Some((known_hash, known_call_id)) = self.file_reads.get(path) {
+            let still_in_context = self
+                .history
+                .iter()
+                .any(|(m, _)| m.tool_call_id.as_deref() == Some(known_call_id.as_str()));
+            if *known_hash == hash && still_in_context {
+                debug!(path = %path, tool_call_id = %known_call_id, "File already in context, skipping duplicate content");
+                return serde_json::json!({
+                    "path": path,
+                    "status": "already in context (unchanged)",
+                    "tool_call_id": known_call_id,
+                });
+            }
+        }
+        self.file_reads.insert(path.to_string(), (hash, tool_call_id.to_string()));
+        result
+    }
+
+    // Everything evicted this session, oldest first.
+    pub fn evicted(&self) -> &[EvictedItem] {
+        &self.evicted
+    }
+
+    // A one-line notice about evictions since the last call, if there were any.
+    pub fn take_eviction_notice(&mut self) -> Option<String> {
+        let notice = eviction_notice(&self.evicted[self.evictions_reported..]);
+        self.evictions_reported = self.evicted.len();
+        notice
+    }
+
+    // Case-insensitive search over assistant messages and tool results still held in
+    // history; `turn` is the message's position in the session history.
+    pub fn search_history(&self, query: &str) -> Vec<HistoryMatch> {
+        let needle = query.to_lowercase();
+        if needle.is_empty() {
+            return Vec::new();
+        }
+        let mut matches = Vec::new();
+        for (turn, (message, _)) in self.history.iter().enumerate() {
+            if !matches!(message.role, Role::Assistant | Role::Tool) {
+                continue;
+            }
+            let Some(content) = &message.content else { continue };
+            for line in content.lines() {
+                if line.to_lowercase().contains(&needle) {
+                    matches.push(HistoryMatch {
+                        turn,
+                        role: message.role.clone(),
+                        line: line.trim().to_string(),
+                    });
+                }
+            }
+        }
+        matches
+    }
+
     
     #[allow(dead_code)]
     pub fn config(&self) -> &Config {
@@ -177,12 +715,29 @@ impl ContextManager {
     }
 }
 
+// Places each snippet just before the first history message added after it. A snippet never
+// goes ahead of a tool result, which must directly follow the assistant message that asked for it.
+fn interleave_snippets(snippets: Vec<(usize, Message)>, history: Vec<(usize, Message)>) -> Vec<Message> {
+    let mut snippets = snippets.into_iter().peekable();
+    let mut messages = Vec::new();
+    for (position, message) in history {
+        if message.role != Role::Tool {
+            while let Some((_, snippet)) = snippets.next_if(|(added_at, _)| *added_at <= position) {
+                messages.push(snippet);
+            }
+        }
+        messages.push(message);
+    }
+    messages.extend(snippets.map(|(_, snippet)| snippet));
+    messages
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::api::models::Role;
     use crate::config::Config;
+use crate::file_lock::FileLock;
 
     fn create_test_manager() -> ContextManager {
         let config = Config::default(); 
@@ -256,11 +811,323 @@ mod tests {
         assert!(!manager.history.iter().any(|(m, _)| m.content == Some("Message 0".to_string()))); 
     }
 
-    // Removed tests relying on add_snippet:
-    // - test_basic_eviction_snippets
-    // - test_eviction_mixed
-    // - test_construct_api_messages_format
-    // - test_construct_api_messages_respects_limit
-    // - test_clear_history (modified version might be possible if needed)
-    // - test_clear_snippets (modified version might be possible if needed)
-}
\ No newline at end of file
+    #[test]
+    fn test_named_session_transcript_keeps_evicted_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = crate::context::session::NamedSessionStore::new(dir.path().to_path_buf());
+        let session = store.open("long", dir.path(), 0).unwrap();
+        let mut manager = create_test_manager_with_limit(20);
+        manager.attach_named_session(store.clone(), session).unwrap();
+        for i in 0..10 {
+            manager.add_message(Message { role: Role::User, content: Some(format!("Message {}", i)), tool_calls: None, tool_call_id: None }).unwrap();
+        }
+        let saved = store.load("long").unwrap().unwrap();
+        assert!(saved.messages.len() < 10);
+        assert_eq!(store.transcript("long").unwrap().len(), 10);
+    }
+
+    #[test]
+    fn test_evictions_are_reported_once_and_listed() {
+        let mut manager = create_test_manager_with_limit(20);
+        for i in 0..10 {
+            manager.add_message(Message { role: Role::User, content: Some(format!("Message {}", i)), tool_calls: None, tool_call_id: None }).unwrap();
+        }
+        let evicted = manager.evicted().to_vec();
+        assert!(!evicted.is_empty());
+        assert_eq!(evicted[0], EvictedItem { turn: Some(0), source: "user".to_string(), preview: "Message 0".to_string(), tokens: 3 });
+
+        let notice = manager.take_eviction_notice().unwrap();
+        let tokens: usize = evicted.iter().map(|item| item.tokens).sum();
+        assert_eq!(notice, format!("Dropped {} old messages (~{} tokens) to fit the context.", evicted.len(), tokens));
+        assert_eq!(manager.take_eviction_notice(), None);
+        assert_eq!(with_thousands(1200), "1,200");
+    }
+
+    #[test]
+    fn test_configured_system_prompt_is_pinned_first() {
+        let mut config = Config::default();
+        config.prompt.system = Some("Always answer in haiku.".to_string());
+        let mut manager = ContextManager::new(config).unwrap();
+        manager.add_message(Message {
+            role: Role::User,
+            content: Some("Hello".to_string()),
+            tool_calls: None,
+            tool_call_id: None,
+        }).unwrap();
+
+        let messages = manager.construct_api_messages().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].role, Role::System);
+        assert_eq!(messages[0].content.as_deref(), Some("Always answer in haiku."));
+        assert_eq!(messages[1].content.as_deref(), Some("Hello"));
+    }
+
+    #[test]
+    fn test_pinned_messages_survive_clear_and_eviction() {
+        let mut manager = create_test_manager_with_limit(20);
+        manager.pin_system_message("Be brief.".to_string()).unwrap();
+        for i in 0..10 {
+            manager.add_message(Message {
+                role: Role::User,
+                content: Some(format!("Message {}", i)),
+                tool_calls: None,
+                tool_call_id: None,
+            }).unwrap();
+        }
+        manager.clear_history();
+
+        assert!(manager.has_pinned_messages());
+        assert_eq!(manager.total_token_count, manager.count_tokens("Be brief."));
+        let messages = manager.construct_api_messages().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content.as_deref(), Some("Be brief."));
+    }
+
+    #[test]
+    fn test_search_history_skips_user_messages() {
+        let mut manager = create_test_manager();
+        for (role, content) in [
+            (Role::User, "where is parse_config?"),
+            (Role::Tool, "{\"matches\": [\"src/config.rs\"]}\nParse_Config lives here"),
+            (Role::Assistant, "parse_config is defined in src/config.rs"),
+        ] {
+            manager.add_message(Message {
+                role,
+                content: Some(content.to_string()),
+                tool_calls: None,
+                tool_call_id: None,
+            }).unwrap();
+        }
+
+        let matches = manager.search_history("PARSE_CONFIG");
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].turn, 1);
+        assert_eq!(matches[0].role, Role::Tool);
+        assert_eq!(matches[0].line, "Parse_Config lives here");
+        assert_eq!(matches[1].turn, 2);
+        assert!(manager.search_history("").is_empty());
+    }
+
+    fn add_tool_result(manager: &mut ContextManager, id: &str, result: &Value) {
+        manager.add_message(Message {
+            role: Role::Tool,
+            content: Some(result.to_string()),
+            tool_calls: None,
+            tool_call_id: Some(id.to_string()),
+        }).unwrap();
+    }
+
+    #[test]
+    fn test_dedupe_file_read_replaces_unchanged_rereads() {
+        let mut manager = create_test_manager();
+        let args = serde_json::json!({ "path": "src/lib.rs" });
+        let original = serde_json::json!({ "content": "pub mod app;" });
+
+        let first = manager.dedupe_file_read("FileReadTool", &args, "call_1", original.clone());
+        assert_eq!(first, original);
+        add_tool_result(&mut manager, "call_1", &first);
+
+        let second = manager.dedupe_file_read("FileReadTool", &args, "call_2", original.clone());
+        assert_eq!(second["status"], "already in context (unchanged)");
+        assert_eq!(second["tool_call_id"], "call_1");
+
+        let changed = serde_json::json!({ "content": "pub mod app;\npub mod cli;" });
+        assert_eq!(manager.dedupe_file_read("FileReadTool", &args, "call_3", changed.clone()), changed);
+
+        let other_tool = manager.dedupe_file_read("FileSearchTool", &args, "call_4", original.clone());
+        assert_eq!(other_tool, original);
+    }
+
+    #[test]
+    fn test_dedupe_file_read_resends_after_history_cleared() {
+        let mut manager = create_test_manager();
+        let args = serde_json::json!({ "path": "src/lib.rs" });
+        let original = serde_json::json!({ "content": "pub mod app;" });
+        let first = manager.dedupe_file_read("FileReadTool", &args, "call_1", original.clone());
+        add_tool_result(&mut manager, "call_1", &first);
+        manager.clear_history();
+
+        assert_eq!(manager.dedupe_file_read("FileReadTool", &args, "call_2", original.clone()), original);
+    }
+
+    #[test]
+    fn test_environment_context_is_replaced_on_refresh() {
+        let mut manager = create_test_manager();
+        manager.pin_system_message("Be brief.".to_string()).unwrap();
+        manager.set_environment_context("Environment:\n- OS: linux".to_string()).unwrap();
+        manager.set_environment_context("Environment:\n- OS: macos".to_string()).unwrap();
+
+        let messages = manager.construct_api_messages().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].content.as_deref(), Some("Be brief."));
+        assert_eq!(messages[1].content.as_deref(), Some("Environment:\n- OS: macos"));
+        assert_eq!(
+            manager.total_token_count,
+            manager.count_tokens("Be brief.") + manager.count_tokens("Environment:\n- OS: macos")
+        );
+    }
+
+    #[test]
+    fn test_history_is_journaled_and_restored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let user = |content: &str| Message { role: Role::User, content: Some(content.to_string()), tool_calls: None, tool_call_id: None };
+
+        let mut manager = create_test_manager();
+        manager.start_journal(&path, FileLock::try_acquire(&path).unwrap()).unwrap();
+        manager.add_message(user("first")).unwrap();
+        manager.clear_history();
+        manager.add_message(user("second")).unwrap();
+
+        // The interrupted session left its journal behind; a new one picks it up.
+        let recovered = SessionJournal::recover(&path).unwrap();
+        let mut resumed = create_test_manager();
+        resumed.restore_history(recovered).unwrap();
+        let messages = resumed.construct_api_messages().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content.as_deref(), Some("second"));
+
+        manager.finish_journal().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_notes_summary_follows_environment() {
+        let notes = NotesStore::new();
+        let mut manager = create_test_manager();
+        manager.attach_notes(notes.clone());
+        manager.set_environment_context("Environment:\n- OS: linux".to_string()).unwrap();
+        assert_eq!(manager.construct_api_messages().unwrap().len(), 1);
+
+        notes.write("plan", "step one\nstep two");
+        let messages = manager.construct_api_messages().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[1].content.as_deref().unwrap().contains("- plan (2 lines): step one"));
+    }
+
+    #[test]
+    fn test_project_memory_precedes_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        let memory = ProjectMemory::new(dir.path().join("memory.json"));
+        let notes = NotesStore::new();
+        let mut manager = create_test_manager();
+        manager.attach_memory(memory.clone());
+        manager.attach_notes(notes.clone());
+        assert!(manager.construct_api_messages().unwrap().is_empty());
+
+        memory.remember("Tests run with `cargo nextest`").unwrap();
+        notes.write("plan", "step one");
+        let messages = manager.construct_api_messages().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].content.as_deref().unwrap().ends_with("- [1] Tests run with `cargo nextest`"));
+        assert!(messages[1].content.as_deref().unwrap().contains("- plan"));
+    }
+
+    #[test]
+    fn test_project_overview_is_read_once_when_attached() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = SummaryCache::new(dir.path().join("project.json"));
+        let mut index = summaries::SummaryIndex::default();
+        index.modules.insert("src".to_string(), "The library".to_string());
+        cache.save(&index).unwrap();
+        let mut manager = create_test_manager();
+        manager.attach_summaries(&cache);
+
+        std::fs::remove_file(dir.path().join("project.json")).unwrap();
+        let messages = manager.construct_api_messages().unwrap();
+        assert!(messages[0].content.as_deref().unwrap().ends_with("- src: The library"));
+    }
+
+    #[test]
+    fn test_snippet_order_modes() {
+        use crate::api::models::{ToolCall, ToolCallFunction};
+        use crate::config::SnippetOrder;
+
+        let message = |role: Role, content: &str| Message {
+            role,
+            content: Some(content.to_string()),
+            tool_calls: None,
+            tool_call_id: None,
+        };
+        let order_with = |snippet_order: SnippetOrder| {
+            let mut config = Config::default();
+            config.context.snippet_order = snippet_order;
+            let mut manager = ContextManager::new(config).unwrap();
+            manager.add_message(message(Role::User, "read main.rs")).unwrap();
+            manager.add_snippet("early.rs".to_string(), "fn early() {}".to_string()).unwrap();
+            let mut call = message(Role::Assistant, "");
+            call.tool_calls = Some(vec![ToolCall {
+                id: "call_1".to_string(),
+                tool_type: "function".to_string(),
+                function: ToolCallFunction { name: FILE_READ_TOOL.to_string(), arguments: "{}".to_string() },
+            }]);
+            manager.add_message(call).unwrap();
+            manager.add_snippet("during_call.rs".to_string(), "fn during() {}".to_string()).unwrap();
+            manager.add_message(message(Role::Tool, "fn main() {}")).unwrap();
+            manager.add_message(message(Role::User, "thanks")).unwrap();
+            manager.add_snippet("late.rs".to_string(), "fn late() {}".to_string()).unwrap();
+            manager
+                .construct_api_messages()
+                .unwrap()
+                .into_iter()
+                .map(|m| {
+                    let content = m.content.unwrap_or_default();
+                    let source = content.strip_prefix("Content from ").and_then(|rest| rest.split(':').next());
+                    source.map(str::to_string).unwrap_or(content)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            order_with(SnippetOrder::BeforeHistory),
+            vec!["early.rs", "during_call.rs", "late.rs", "read main.rs", "", "fn main() {}", "thanks"]
+        );
+        // The snippet added mid-call waits until after the tool result.
+        assert_eq!(
+            order_with(SnippetOrder::Interleaved),
+            vec!["read main.rs", "early.rs", "", "fn main() {}", "during_call.rs", "thanks", "late.rs"]
+        );
+    }
+
+    #[test]
+    fn test_budget_caps_snippets_and_keeps_history() {
+        let mut manager = create_test_manager_with_limit(100);
+        manager.config.context.budget.response_tokens = 20;
+        for i in 0..4 {
+            manager.add_message(Message { role: Role::User, content: Some(format!("Message {}", i)), tool_calls: None, tool_call_id: None }).unwrap();
+        }
+        // Over half of the 80-token budget: it evicts itself rather than the conversation.
+        manager.add_snippet("huge.rs".to_string(), "fn a() {} ".repeat(20)).unwrap();
+        manager.add_snippet("small.rs".to_string(), "fn b() {}".to_string()).unwrap();
+
+        let messages = manager.construct_api_messages().unwrap();
+        let contents: Vec<String> = messages.into_iter().filter_map(|m| m.content).collect();
+        assert!(contents[0].starts_with("Content from small.rs"), "{:?}", contents);
+        assert_eq!(&contents[1..], ["Message 0", "Message 1", "Message 2", "Message 3"]);
+        assert_eq!(manager.evicted()[0].source, "snippet huge.rs");
+        assert_eq!(manager.context_budget(), 80);
+    }
+
+    #[test]
+    fn test_file_snippets_refresh_when_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lib.rs");
+        std::fs::write(&path, "fn one() {}").unwrap();
+        let mut manager = create_test_manager();
+        manager.add_file_snippet(&path).unwrap();
+        manager.add_snippet("cargo test".to_string(), "ok".to_string()).unwrap();
+        assert!(manager.refresh_snippets().unwrap().is_empty());
+
+        std::fs::write(&path, "fn one() {}\nfn two() { one(); one(); }").unwrap();
+        let before = (manager.context_snippets[0].token_count, manager.total_token_count);
+        // Make the change visible on filesystems with coarse timestamps.
+        manager.context_snippets[0].file.as_mut().unwrap().1 = None;
+        assert_eq!(manager.refresh_snippets().unwrap(), vec![path.display().to_string()]);
+
+        let stats = manager.snippet_stats();
+        assert!(stats[0].refreshed && !stats[1].refreshed);
+        assert!(stats[0].tokens > before.0);
+        assert_eq!(manager.total_token_count, before.1 + stats[0].tokens - before.0);
+        assert!(manager.construct_api_messages().unwrap().iter().any(|m| m.content.as_deref().unwrap_or_default().contains("fn two()")));
+    }
+}
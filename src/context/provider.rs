@@ -0,0 +1,10 @@
+use anyhow::Result;
+use std::path::Path;
+
+// A source of extra prompt context that can be computed from the project on disk.
+// `target` is the file the request is about, when there is one.
+pub trait ContextProvider: Send + Sync + std::fmt::Debug {
+    fn name(&self) -> String;
+
+    fn provide(&self, target: Option<&Path>) -> Result<Option<String>>;
+}
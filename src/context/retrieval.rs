@@ -0,0 +1,207 @@
+use crate::config::{Config, RetrievalConfig};
+use crate::context::embeddings::{cosine_similarity, embedding_provider};
+use crate::context::file_index::{FileIndex, IndexStore};
+use crate::tools::path_policy::PathPolicy;
+use crate::tools::{CliTool, CodeSearchTool};
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const MAX_TERMS: usize = 8;
+const STOP_WORDS: &[&str] = &[
+    "about", "and", "are", "can", "does", "file", "for", "from", "how", "into", "its", "should", "that", "the",
+    "there", "this", "use", "what", "when", "where", "which", "why", "with",
+];
+// Output past this many lines is ignored; a common word can match almost every file.
+const MAX_SEARCH_LINES: usize = 2000;
+const LINES_SHOWN: usize = 3;
+const MAX_LINE_CHARS: usize = 160;
+
+// A file found by either search, before ranking. Scores are in 0..=1: `exact` is the share of
+// query terms the file contains, `semantic` the cosine similarity of its embedding.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Candidate {
+    pub exact: f32,
+    pub semantic: f32,
+    // Lines that matched a term, as ripgrep printed them.
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetrievedFile {
+    pub path: String,
+    pub score: f32,
+    pub lines: Vec<String>,
+}
+
+// The words of `query` worth searching for: lowercase identifiers of three or more
+// characters that are not common English, first occurrence order.
+pub fn query_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for word in query.split(|c: char| !c.is_alphanumeric() && c != '_') {
+        let word = word.to_lowercase();
+        if word.len() >= 3 && !STOP_WORDS.contains(&word.as_str()) && !terms.contains(&word) {
+            terms.push(word);
+        }
+    }
+    terms.truncate(MAX_TERMS);
+    terms
+}
+
+// Folds ripgrep's `path:line` output for a search under `root` into `candidates`.
+pub fn add_exact_matches(output: &str, root: &str, terms: &[String], candidates: &mut BTreeMap<String, Candidate>) {
+    let mut matched: BTreeMap<String, Vec<&String>> = BTreeMap::new();
+    for line in output.lines().take(MAX_SEARCH_LINES) {
+        let line = line.strip_prefix(root).map(|l| l.trim_start_matches('/')).unwrap_or(line);
+        let Some((path, text)) = line.split_once(':') else { continue };
+        let lower = text.to_lowercase();
+        let found = matched.entry(path.to_string()).or_default();
+        found.extend(terms.iter().filter(|term| lower.contains(term.as_str()) && !found.contains(term)).collect::<Vec<_>>());
+        let candidate = candidates.entry(path.to_string()).or_default();
+        if candidate.lines.len() < LINES_SHOWN {
+            candidate.lines.push(text.trim().chars().take(MAX_LINE_CHARS).collect());
+        }
+    }
+    for (path, found) in matched {
+        if let Some(candidate) = candidates.get_mut(&path) {
+            candidate.exact = found.len() as f32 / terms.len().max(1) as f32;
+        }
+    }
+}
+
+// Adds the `limit` indexed files whose embeddings are nearest `query`.
+pub fn add_semantic_neighbors(index: &FileIndex, query: &[f32], limit: usize, candidates: &mut BTreeMap<String, Candidate>) {
+    let mut scored: Vec<(&String, f32)> = index
+        .files
+        .iter()
+        .filter_map(|(path, entry)| Some((path, cosine_similarity(entry.embedding.as_deref()?, query).max(0.0))))
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    for (path, similarity) in scored.into_iter().take(limit) {
+        candidates.entry(path.clone()).or_default().semantic = similarity;
+    }
+}
+
+// Best first by blended score. Without embeddings the exact score stands alone, so
+// `min_score` means the same either way.
+pub fn rank(candidates: BTreeMap<String, Candidate>, config: &RetrievalConfig, semantic: bool) -> Vec<RetrievedFile> {
+    let semantic_weight = if semantic { config.semantic_weight } else { 0.0 };
+    let total_weight = config.exact_weight + semantic_weight;
+    if total_weight <= 0.0 {
+        return Vec::new();
+    }
+    let mut ranked: Vec<RetrievedFile> = candidates
+        .into_iter()
+        .map(|(path, c)| RetrievedFile {
+            path,
+            score: (config.exact_weight * c.exact + semantic_weight * c.semantic) / total_weight,
+            lines: c.lines,
+        })
+        .filter(|file| file.score >= config.min_score)
+        .collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+    ranked.truncate(config.max_results);
+    ranked
+}
+
+pub fn render(files: &[RetrievedFile], max_chars: usize) -> Option<String> {
+    if files.is_empty() {
+        return None;
+    }
+    let mut text = "Project files related to this request, by relevance (read them for detail):".to_string();
+    for file in files {
+        let mut entry = format!("\n- {} (score {:.2})", file.path, file.score);
+        for line in &file.lines {
+            entry.push_str(&format!("\n    {}", line));
+        }
+        if text.len() + entry.len() > max_chars {
+            text.push_str("\n- ...");
+            break;
+        }
+        text.push_str(&entry);
+    }
+    Some(text)
+}
+
+// Exact matches from `CodeSearchTool` and semantic neighbors from the project's embedding
+// index, merged and ranked. Either half being unavailable (no ripgrep, no index, index built
+// with another provider, embedding request failed) leaves the other. Files `[[path_rules]]`
+// keep CodeSearchTool or FileReadTool away from are left out.
+pub async fn retrieve(config: &Config, root: &Path, query: &str) -> Result<Vec<RetrievedFile>> {
+    let settings = &config.context.retrieval;
+    let mut candidates = BTreeMap::new();
+    let terms = query_terms(query);
+    let root_text = root.to_string_lossy().to_string();
+    if !terms.is_empty() && settings.exact_weight > 0.0 {
+        let args = serde_json::json!({ "pattern": format!("(?i){}", terms.join("|")), "path": root_text });
+        match CodeSearchTool.execute(args).await {
+            Ok(result) => add_exact_matches(result["stdout"].as_str().unwrap_or_default(), &root_text, &terms, &mut candidates),
+            Err(e) => tracing::debug!("Skipping exact matches: {}", e),
+        }
+    }
+
+    let index = IndexStore::for_project(root).load().unwrap_or_default();
+    let semantic = settings.semantic_weight > 0.0
+        && index.embedded_count() > 0
+        && match add_semantic(config, &index, query, &mut candidates).await {
+            Ok(added) => added,
+            Err(e) => {
+                tracing::warn!("Semantic retrieval failed, ranking exact matches only: {:#}", e);
+                false
+            }
+        };
+
+    let policy = PathPolicy::with_root(&config.path_rules, root.to_path_buf());
+    candidates.retain(|path, _| ["CodeSearchTool", "FileReadTool"].iter().all(|tool| policy.violation(tool, path).is_none()));
+    Ok(rank(candidates, settings, semantic))
+}
+
+// Adds the query's semantic neighbors; false when the index was built with another provider.
+async fn add_semantic(config: &Config, index: &FileIndex, query: &str, candidates: &mut BTreeMap<String, Candidate>) -> Result<bool> {
+    let provider = embedding_provider(config)?;
+    if provider.id() != index.embedding_model {
+        tracing::debug!("Skipping semantic neighbors: index built with {}, configured {}", index.embedding_model, provider.id());
+        return Ok(false);
+    }
+    let vectors = provider.embed(&[query.to_string()]).await?;
+    let vector = vectors.first().ok_or_else(|| anyhow!("{} returned no embedding", provider.id()))?;
+    add_semantic_neighbors(index, vector, config.context.retrieval.max_results * 3, candidates);
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::file_index::IndexEntry;
+
+    #[test]
+    fn test_blends_exact_and_semantic_results() {
+        let terms = query_terms("Where does the parser handle config_path?");
+        assert_eq!(terms, vec!["parser", "handle", "config_path"]);
+
+        let mut candidates = BTreeMap::new();
+        let output = "/repo/src/parser.rs:pub fn parse(config_path: &Path)\n/repo/src/parser.rs:// handle errors in the parser\n/repo/README.md:The parser\n";
+        add_exact_matches(output, "/repo", &terms, &mut candidates);
+        assert_eq!(candidates["src/parser.rs"].exact, 1.0);
+        assert_eq!(candidates["src/parser.rs"].lines, vec!["pub fn parse(config_path: &Path)", "// handle errors in the parser"]);
+
+        let mut index = FileIndex::default();
+        for (path, embedding) in [("src/parser.rs", vec![1.0, 0.0]), ("src/config.rs", vec![0.8, 0.6]), ("src/ui.rs", vec![0.0, 1.0])] {
+            let entry = IndexEntry { hash: String::new(), modified: 0, size: 0, language: "rust".to_string(), embedding: Some(embedding) };
+            index.files.insert(path.to_string(), entry);
+        }
+        add_semantic_neighbors(&index, &[1.0, 0.0], 2, &mut candidates);
+        assert!(!candidates.contains_key("src/ui.rs"));
+
+        let config = RetrievalConfig { max_results: 3, ..RetrievalConfig::default() };
+        let ranked = rank(candidates.clone(), &config, true);
+        let order: Vec<(&str, i32)> = ranked.iter().map(|f| (f.path.as_str(), (f.score * 100.0).round() as i32)).collect();
+        assert_eq!(order, vec![("src/parser.rs", 100), ("src/config.rs", 40), ("README.md", 17)]);
+        let exact_only = rank(candidates, &config, false);
+        assert_eq!(exact_only.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(), vec!["src/parser.rs", "README.md"]);
+
+        let text = render(&ranked, 4000).unwrap();
+        assert!(text.contains("\n- src/parser.rs (score 1.00)\n    pub fn parse(config_path: &Path)\n"));
+        assert!(render(&ranked, 100).unwrap().ends_with("\n- ..."));
+    }
+}
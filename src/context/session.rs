@@ -0,0 +1,464 @@
+use crate::api::models::Message;
+use crate::config::global_config_dir;
+use crate::file_lock::FileLock;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+pub const SESSION_DIR: &str = "sessions";
+const SUMMARY_DIR: &str = "summaries";
+const NAMED_DIR: &str = "named";
+
+// Stable per-checkout file name stem for state kept in the config directory. A fixed hash,
+// so the stem survives toolchain upgrades.
+pub fn project_key(project_dir: &Path) -> String {
+    let project_dir = fs::canonicalize(project_dir).unwrap_or_else(|_| project_dir.to_path_buf());
+    let digest = Sha256::digest(project_dir.as_os_str().as_encoded_bytes());
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Append-only record of an interactive session's history, one JSON message per line.
+// Every append is synced, so a crash loses at most the message being written; a clean
+// exit removes the file, so finding one on startup means the last session was cut short.
+// The journal's lock is held for the whole session so two processes never share it.
+#[derive(Debug)]
+pub struct SessionJournal {
+    path: PathBuf,
+    file: File,
+    entries: usize,
+    _lock: FileLock,
+}
+
+impl SessionJournal {
+    // One journal per project directory, so sessions in different checkouts don't collide.
+    // Creates the session directory so the journal can be locked before it exists.
+    pub fn path_for(project_dir: &Path) -> Result<PathBuf> {
+        let dir = global_config_dir().context("Could not determine the config directory")?.join(SESSION_DIR);
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {:?}", dir))?;
+        Ok(dir.join(format!("{}.jsonl", project_key(project_dir))))
+    }
+
+    // Messages left by an interrupted session. A torn final line from a crash mid-write
+    // is dropped rather than treated as corruption.
+    pub fn recover(path: &Path) -> Result<Vec<Message>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to open session journal {:?}", path)),
+        };
+        let lines = BufReader::new(file)
+            .lines()
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Failed to read session journal {:?}", path))?;
+        let mut messages = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(message) => messages.push(message),
+                Err(e) if index + 1 == lines.len() => warn!("Dropping incomplete journal entry in {:?}: {}", path, e),
+                Err(e) => return Err(e).with_context(|| format!("Corrupt entry {} in session journal {:?}", index + 1, path)),
+            }
+        }
+        Ok(messages)
+    }
+
+    // Starts journaling with `messages` as the initial content, replacing any old journal.
+    // `lock` must be the lock on `path`, taken before the old journal was recovered.
+    pub fn start(path: &Path, messages: &[Message], lock: FileLock) -> Result<Self> {
+        let file = Self::rewrite(path, messages)?;
+        Ok(SessionJournal { path: path.to_path_buf(), file, entries: messages.len(), _lock: lock })
+    }
+
+    pub fn append(&mut self, message: &Message) -> Result<()> {
+        let mut line = serde_json::to_string(message)?;
+        line.push('\n');
+        self.file
+            .write_all(line.as_bytes())
+            .and_then(|_| self.file.sync_data())
+            .with_context(|| format!("Failed to append to session journal {:?}", self.path))?;
+        self.entries += 1;
+        Ok(())
+    }
+
+    // Replaces the journal with just the live history, dropping evicted and cleared messages.
+    pub fn compact(&mut self, messages: &[Message]) -> Result<()> {
+        self.file = Self::rewrite(&self.path, messages)?;
+        self.entries = messages.len();
+        Ok(())
+    }
+
+    pub fn entries(&self) -> usize {
+        self.entries
+    }
+
+    // Called on a clean exit; there is nothing left to recover.
+    pub fn finish(self) -> Result<()> {
+        fs::remove_file(&self.path).with_context(|| format!("Failed to remove session journal {:?}", self.path))
+    }
+
+    // Written to a temporary file and renamed over the journal, so a crash during
+    // compaction leaves either the old or the new journal intact.
+    fn rewrite(path: &Path, messages: &[Message]) -> Result<File> {
+        let tmp_path = path.with_extension("jsonl.tmp");
+        let mut tmp = File::create(&tmp_path).with_context(|| format!("Failed to create {:?}", tmp_path))?;
+        for message in messages {
+            serde_json::to_writer(&mut tmp, message)?;
+            tmp.write_all(b"\n")?;
+        }
+        tmp.sync_all().with_context(|| format!("Failed to sync {:?}", tmp_path))?;
+        fs::rename(&tmp_path, path).with_context(|| format!("Failed to replace session journal {:?}", path))?;
+        OpenOptions::new()
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open session journal {:?}", path))
+    }
+}
+
+// What a finished interactive session was about, written when it closes. The id is the
+// start time in seconds since the Unix epoch, which also orders summaries.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub id: String,
+    pub project_dir: PathBuf,
+    pub started_at: u64,
+    pub ended_at: u64,
+    pub message_count: usize,
+    pub task: String,
+    #[serde(default)]
+    pub decisions: Vec<String>,
+    #[serde(default)]
+    pub files_touched: Vec<String>,
+    // The `/setenv` overrides in effect when the session closed, so a resumed session runs
+    // its tools the same way.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+impl SessionSummary {
+    // The summary as a system message for a new session to build on.
+    pub fn render(&self) -> String {
+        let mut text = format!(
+            "Summary of an earlier session ({}, {}):\nTask: {}",
+            self.id,
+            crate::context::environment::format_utc(self.started_at),
+            self.task
+        );
+        if !self.decisions.is_empty() {
+            text.push_str(&format!("\nDecisions:\n- {}", self.decisions.join("\n- ")));
+        }
+        if !self.files_touched.is_empty() {
+            text.push_str(&format!("\nFiles touched: {}", self.files_touched.join(", ")));
+        }
+        if !self.env.is_empty() {
+            let vars: Vec<String> = self.env.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+            text.push_str(&format!("\nEnvironment overrides for tools: {}", vars.join(" ")));
+        }
+        text
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionSummaryStore {
+    dir: PathBuf,
+}
+
+impl SessionSummaryStore {
+    pub fn new(dir: PathBuf) -> Self {
+        SessionSummaryStore { dir }
+    }
+
+    pub fn from_config_dir() -> Result<Self> {
+        let dir = global_config_dir().context("Could not determine the config directory")?;
+        Ok(Self::new(dir.join(SESSION_DIR).join(SUMMARY_DIR)))
+    }
+
+    pub fn save(&self, summary: &SessionSummary) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir).with_context(|| format!("Failed to create {:?}", self.dir))?;
+        let path = self.dir.join(format!("{}.json", summary.id));
+        let content = serde_json::to_string_pretty(summary)?;
+        fs::write(&path, content).with_context(|| format!("Failed to write session summary {:?}", path))?;
+        Ok(path)
+    }
+
+    // Newest first. Unreadable files are skipped with a warning.
+    pub fn list(&self) -> Vec<SessionSummary> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut summaries: Vec<SessionSummary> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                let parsed = fs::read_to_string(&path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|content| serde_json::from_str(&content).map_err(anyhow::Error::from));
+                parsed.map_err(|e| warn!("Skipping unreadable session summary {:?}: {}", path, e)).ok()
+            })
+            .collect();
+        summaries.sort_by_key(|s| std::cmp::Reverse(s.started_at));
+        summaries
+    }
+
+    // Accepts any unambiguous prefix of an id.
+    pub fn find(&self, id: &str) -> Result<SessionSummary> {
+        let mut matches: Vec<SessionSummary> = self.list().into_iter().filter(|s| s.id.starts_with(id)).collect();
+        match matches.len() {
+            0 => bail!("No session summary with id '{}'", id),
+            1 => Ok(matches.remove(0)),
+            n => bail!("'{}' matches {} sessions; use more of the id", id, n),
+        }
+    }
+}
+
+// A conversation kept under a name given with `--session`; `ask` and interactive runs with the
+// same name continue it, whichever directory they start in. `messages` is the live history,
+// trimmed like the context window; every message ever added is kept in the session's
+// transcript file instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamedSession {
+    pub name: String,
+    pub project_dir: PathBuf,
+    pub created_at: u64,
+    pub updated_at: u64,
+    #[serde(default)]
+    pub messages: Vec<Message>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NamedSessionStore {
+    dir: PathBuf,
+}
+
+impl NamedSessionStore {
+    pub fn new(dir: PathBuf) -> Self {
+        NamedSessionStore { dir }
+    }
+
+    pub fn from_config_dir() -> Result<Self> {
+        let dir = global_config_dir().context("Could not determine the config directory")?;
+        Ok(Self::new(dir.join(SESSION_DIR).join(NAMED_DIR)))
+    }
+
+    // Names become file names, so they are limited to letters, digits, `-`, `_` and `.`.
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        let valid = name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if name.is_empty() || name.starts_with('.') || !valid {
+            bail!("Invalid session name '{}': use letters, digits, '-', '_' and '.'", name);
+        }
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+
+    // Append-only JSONL next to the session file.
+    fn transcript_path(&self, name: &str) -> Result<PathBuf> {
+        Ok(self.path(name)?.with_extension("transcript.jsonl"))
+    }
+
+    pub fn append_transcript(&self, name: &str, messages: &[Message]) -> Result<()> {
+        let path = self.transcript_path(name)?;
+        fs::create_dir_all(&self.dir).with_context(|| format!("Failed to create {:?}", self.dir))?;
+        let mut lines = String::new();
+        for message in messages {
+            lines.push_str(&serde_json::to_string(message)?);
+            lines.push('\n');
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(lines.as_bytes()))
+            .with_context(|| format!("Failed to append to transcript {:?}", path))
+    }
+
+    // Every message the session has seen, oldest first.
+    pub fn transcript(&self, name: &str) -> Result<Vec<Message>> {
+        SessionJournal::recover(&self.transcript_path(name)?)
+    }
+
+    pub fn load(&self, name: &str) -> Result<Option<NamedSession>> {
+        let path = self.path(name)?;
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).map(Some).with_context(|| format!("Corrupt session {:?}", path)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read session {:?}", path)),
+        }
+    }
+
+    // The session called `name`, or a new empty one started in `project_dir`.
+    pub fn open(&self, name: &str, project_dir: &Path, now: u64) -> Result<NamedSession> {
+        Ok(self.load(name)?.unwrap_or_else(|| NamedSession {
+            name: name.to_string(),
+            project_dir: project_dir.to_path_buf(),
+            created_at: now,
+            updated_at: now,
+            messages: Vec::new(),
+        }))
+    }
+
+    // Written to a temporary file and renamed into place, so an interrupted save keeps the
+    // previous version.
+    pub fn save(&self, session: &NamedSession) -> Result<()> {
+        let path = self.path(&session.name)?;
+        fs::create_dir_all(&self.dir).with_context(|| format!("Failed to create {:?}", self.dir))?;
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string(session)?).with_context(|| format!("Failed to write {:?}", tmp_path))?;
+        fs::rename(&tmp_path, &path).with_context(|| format!("Failed to save session {:?}", path))
+    }
+
+    // Most recently used first. Unreadable files are skipped with a warning.
+    pub fn list(&self) -> Vec<NamedSession> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut sessions: Vec<NamedSession> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                let parsed = fs::read_to_string(&path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|content| serde_json::from_str(&content).map_err(anyhow::Error::from));
+                parsed.map_err(|e| warn!("Skipping unreadable session {:?}: {}", path, e)).ok()
+            })
+            .collect();
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+        sessions
+    }
+
+    pub fn delete(&self, name: &str) -> Result<()> {
+        let path = self.path(name)?;
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() == ErrorKind::NotFound => bail!("No session named '{}'", name),
+            result => result.with_context(|| format!("Failed to delete session {:?}", path))?,
+        }
+        let transcript = self.transcript_path(name)?;
+        match fs::remove_file(&transcript) {
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            result => result.with_context(|| format!("Failed to delete transcript {:?}", transcript)),
+        }
+    }
+
+    pub fn rename(&self, name: &str, new_name: &str) -> Result<()> {
+        let mut session = self.load(name)?.ok_or_else(|| anyhow!("No session named '{}'", name))?;
+        if self.load(new_name)?.is_some() {
+            bail!("A session named '{}' already exists", new_name);
+        }
+        session.name = new_name.to_string();
+        self.save(&session)?;
+        let transcript = self.transcript_path(name)?;
+        if transcript.exists() {
+            fs::rename(&transcript, self.transcript_path(new_name)?).with_context(|| format!("Failed to move transcript {:?}", transcript))?;
+        }
+        self.delete(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::models::Role;
+
+    fn message(role: Role, content: &str) -> Message {
+        Message { role, content: Some(content.to_string()), tool_calls: None, tool_call_id: None }
+    }
+
+    #[test]
+    fn test_journal_survives_torn_write_and_compacts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        assert!(SessionJournal::recover(&path).unwrap().is_empty());
+
+        let lock = FileLock::try_acquire(&path).unwrap();
+        let mut journal = SessionJournal::start(&path, &[message(Role::User, "hello")], lock).unwrap();
+        journal.append(&message(Role::Assistant, "hi there")).unwrap();
+        assert_eq!(journal.entries(), 2);
+
+        // Simulate power loss halfway through the next entry.
+        OpenOptions::new().append(true).open(&path).unwrap().write_all(b"{\"role\":\"us").unwrap();
+        let recovered = SessionJournal::recover(&path).unwrap();
+        assert_eq!(recovered.len(), 2);
+        assert_eq!(recovered[1].content.as_deref(), Some("hi there"));
+
+        journal.compact(&recovered[1..]).unwrap();
+        journal.append(&message(Role::User, "again")).unwrap();
+        let recovered = SessionJournal::recover(&path).unwrap();
+        assert_eq!(recovered.iter().map(|m| m.content.clone().unwrap()).collect::<Vec<_>>(), vec!["hi there", "again"]);
+
+        journal.finish().unwrap();
+        assert!(!path.exists());
+    }
+
+    fn summary(id: &str, task: &str) -> SessionSummary {
+        SessionSummary {
+            id: id.to_string(),
+            project_dir: PathBuf::from("/work/app"),
+            started_at: id.parse().unwrap(),
+            ended_at: id.parse::<u64>().unwrap() + 60,
+            message_count: 4,
+            task: task.to_string(),
+            decisions: vec!["Keep the parser hand-written".to_string()],
+            files_touched: vec!["src/parser.rs".to_string()],
+            env: BTreeMap::from([("RUST_LOG".to_string(), "debug".to_string())]),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_project_key_is_a_fixed_hash_of_the_path() {
+        assert_eq!(project_key(Path::new("/nonexistent/opencode-project")), "8940bc372bb9159e");
+    }
+
+    #[test]
+    fn test_summary_store_lists_newest_first_and_finds_by_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SessionSummaryStore::new(dir.path().to_path_buf());
+        store.save(&summary("1700000000", "Fix the parser")).unwrap();
+        store.save(&summary("1700000500", "Add tests")).unwrap();
+        fs::write(dir.path().join("junk.json"), "not json").unwrap();
+
+        let tasks: Vec<String> = store.list().into_iter().map(|s| s.task).collect();
+        assert_eq!(tasks, vec!["Add tests", "Fix the parser"]);
+        assert_eq!(store.find("17000005").unwrap().task, "Add tests");
+        assert!(store.find("1700").is_err());
+        assert!(store.find("99").is_err());
+
+        let rendered = store.find("1700000000").unwrap().render();
+        assert!(rendered.starts_with("Summary of an earlier session (1700000000, 2023-11-14"));
+        assert!(rendered.contains("Task: Fix the parser\nDecisions:\n- Keep the parser hand-written\nFiles touched: src/parser.rs\nEnvironment overrides for tools: RUST_LOG=debug"));
+    }
+
+    #[test]
+    fn test_named_sessions_save_rename_and_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = NamedSessionStore::new(dir.path().to_path_buf());
+        assert!(store.open("../escape", Path::new("/work/app"), 1).is_err());
+
+        let mut session = store.open("parser-work", Path::new("/work/app"), 100).unwrap();
+        assert!(session.messages.is_empty());
+        session.messages.push(message(Role::User, "fix the parser"));
+        store.save(&session).unwrap();
+        let mut other = store.open("docs", Path::new("/work/app"), 200).unwrap();
+        other.updated_at = 200;
+        store.save(&other).unwrap();
+        assert_eq!(store.list().iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), vec!["docs", "parser-work"]);
+        let reopened = store.open("parser-work", Path::new("/elsewhere"), 300).unwrap();
+        assert_eq!((reopened.created_at, reopened.project_dir, reopened.messages.len()), (100, PathBuf::from("/work/app"), 1));
+
+        store.append_transcript("parser-work", &[message(Role::User, "fix the parser")]).unwrap();
+        assert!(store.rename("parser-work", "docs").is_err());
+        store.rename("parser-work", "parser").unwrap();
+        assert_eq!(store.load("parser").unwrap().unwrap().messages.len(), 1);
+        assert_eq!(store.transcript("parser").unwrap().len(), 1);
+        assert!(store.load("parser-work").unwrap().is_none());
+        store.delete("parser").unwrap();
+        assert!(store.delete("parser").is_err());
+        assert!(store.transcript("parser").unwrap().is_empty());
+    }
+}
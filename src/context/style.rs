@@ -0,0 +1,303 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::context::provider::ContextProvider;
+
+const RUSTFMT_FILES: &[&str] = &["rustfmt.toml", ".rustfmt.toml"];
+const ESLINT_JSON_FILES: &[&str] = &[".eslintrc.json", ".eslintrc"];
+const ESLINT_OTHER_FILES: &[&str] = &[
+    "eslint.config.js",
+    "eslint.config.mjs",
+    "eslint.config.cjs",
+    ".eslintrc.js",
+    ".eslintrc.cjs",
+    ".eslintrc.yml",
+    ".eslintrc.yaml",
+];
+const EDITORCONFIG_KEYS: &[&str] = &[
+    "indent_style",
+    "indent_size",
+    "tab_width",
+    "end_of_line",
+    "charset",
+    "max_line_length",
+    "insert_final_newline",
+    "trim_trailing_whitespace",
+];
+const ESLINT_STYLE_RULES: &[&str] = &[
+    "indent",
+    "quotes",
+    "semi",
+    "camelcase",
+    "comma-dangle",
+    "max-len",
+    "brace-style",
+    "eol-last",
+    "object-curly-spacing",
+    "arrow-parens",
+];
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StyleProfile {
+    pub sources: Vec<String>,
+    pub rules: Vec<String>,
+}
+
+impl StyleProfile {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn summary(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut summary = format!(
+            "Project style conventions (from {}). Generated code MUST follow them:\n",
+            self.sources.join(", ")
+        );
+        for rule in &self.rules {
+            summary.push_str(&format!("- {}\n", rule));
+        }
+        Some(summary.trim_end().to_string())
+    }
+}
+
+#[derive(Debug)]
+pub struct StyleProfileProvider {
+    root: PathBuf,
+}
+
+impl StyleProfileProvider {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        StyleProfileProvider { root: root.into() }
+    }
+
+    pub fn from_current_dir() -> Result<Self> {
+        let root = std::env::current_dir().context("Failed to get current directory")?;
+        Ok(Self::new(root))
+    }
+
+    pub fn detect(&self, target: Option<&Path>) -> Result<StyleProfile> {
+        let mut profile = StyleProfile::default();
+        let extension = target
+            .and_then(|p| p.extension())
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+
+        if extension.as_deref().is_none_or(|ext| ext == "rs") {
+            if let Some(path) = self.find_file(RUSTFMT_FILES) {
+                let rules = read_rustfmt_rules(&path)?;
+                push_rules(&mut profile, &path, rules);
+            }
+        }
+
+        if let Some(path) = self.find_file(&[".editorconfig"]) {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {:?}", path))?;
+            let rules = parse_editorconfig(&content, extension.as_deref());
+            push_rules(&mut profile, &path, rules);
+        }
+
+        let is_js = extension
+            .as_deref()
+            .is_none_or(|ext| matches!(ext, "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" | "vue"));
+        if is_js {
+            if let Some(path) = self.find_file(ESLINT_JSON_FILES) {
+                let rules = read_eslint_rules(&path)?;
+                push_rules(&mut profile, &path, rules);
+            } else if let Some(path) = self.find_file(ESLINT_OTHER_FILES) {
+                let rules = vec![format!(
+                    "ESLint is configured in {}; follow its rules",
+                    path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default()
+                )];
+                push_rules(&mut profile, &path, rules);
+            }
+        }
+
+        Ok(profile)
+    }
+
+    // Walks up from the provider root and returns the first existing candidate.
+    fn find_file(&self, candidates: &[&str]) -> Option<PathBuf> {
+        self.root.ancestors().find_map(|dir| {
+            candidates
+                .iter()
+                .map(|name| dir.join(name))
+                .find(|path| path.is_file())
+        })
+    }
+}
+
+impl ContextProvider for StyleProfileProvider {
+    fn name(&self) -> String {
+        "style_profile".to_string()
+    }
+
+    fn provide(&self, target: Option<&Path>) -> Result<Option<String>> {
+        Ok(self.detect(target)?.summary())
+    }
+}
+
+// Best-effort summary for prompts; detection problems are logged, never fatal.
+pub fn style_summary_for(target: Option<&Path>) -> Option<String> {
+    let provider = match StyleProfileProvider::from_current_dir() {
+        Ok(provider) => provider,
+        Err(e) => {
+            tracing::warn!("Could not set up style profile detection: {}", e);
+            return None;
+        }
+    };
+    match provider.provide(target) {
+        Ok(summary) => summary,
+        Err(e) => {
+            tracing::warn!("Failed to detect project style conventions: {}", e);
+            None
+        }
+    }
+}
+
+fn push_rules(profile: &mut StyleProfile, path: &Path, rules: Vec<String>) {
+    if rules.is_empty() {
+        return;
+    }
+    let source = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string());
+    profile.sources.push(source);
+    profile.rules.extend(rules);
+}
+
+fn read_rustfmt_rules(path: &Path) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let table: toml::Table = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse {:?}", path))?;
+    Ok(table
+        .iter()
+        .map(|(key, value)| format!("rustfmt {} = {}", key, value))
+        .collect())
+}
+
+fn read_eslint_rules(path: &Path) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let config: Value = match serde_json::from_str(&content) {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::debug!("ESLint config {:?} is not plain JSON: {}", path, e);
+            return Ok(vec!["ESLint is configured for this project; follow its rules".to_string()]);
+        }
+    };
+    let mut rules = Vec::new();
+    if let Some(extends) = config.get("extends") {
+        rules.push(format!("ESLint extends {}", extends));
+    }
+    if let Some(rule_map) = config.get("rules").and_then(|r| r.as_object()) {
+        for name in ESLINT_STYLE_RULES {
+            if let Some(setting) = rule_map.get(*name) {
+                rules.push(format!("ESLint {}: {}", name, setting));
+            }
+        }
+    }
+    Ok(rules)
+}
+
+// Returns the settings that apply to files with `extension`, with later sections
+// overriding earlier ones as EditorConfig specifies.
+fn parse_editorconfig(content: &str, extension: Option<&str>) -> Vec<String> {
+    let mut settings: Vec<(String, String)> = Vec::new();
+    let mut section_applies = false;
+
+    for raw_line in content.lines() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(glob) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section_applies = editorconfig_glob_matches(glob, extension);
+            continue;
+        }
+        if !section_applies {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            let key = key.trim().to_lowercase();
+            if !EDITORCONFIG_KEYS.contains(&key.as_str()) {
+                continue;
+            }
+            let value = value.trim().to_string();
+            match settings.iter_mut().find(|(k, _)| *k == key) {
+                Some(existing) => existing.1 = value,
+                None => settings.push((key, value)),
+            }
+        }
+    }
+
+    settings
+        .into_iter()
+        .map(|(key, value)| format!("editorconfig {} = {}", key, value))
+        .collect()
+}
+
+fn editorconfig_glob_matches(glob: &str, extension: Option<&str>) -> bool {
+    if glob == "*" || glob == "**" {
+        return true;
+    }
+    let Some(ext) = extension else {
+        return false;
+    };
+    let glob = glob.trim_start_matches("**/");
+    if let Some(rest) = glob.strip_prefix("*.") {
+        if let Some(alternatives) = rest.strip_prefix('{').and_then(|r| r.strip_suffix('}')) {
+            return alternatives.split(',').any(|alt| alt.trim() == ext);
+        }
+        return rest == ext;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_editorconfig_sections_apply_by_extension() {
+        let content = "root = true\n\n[*]\nindent_style = space\nindent_size = 4\n\n[*.{js,ts}]\nindent_size = 2\n\n[Makefile]\nindent_style = tab\n";
+        let rs = parse_editorconfig(content, Some("rs"));
+        assert_eq!(rs, vec!["editorconfig indent_style = space", "editorconfig indent_size = 4"]);
+
+        let ts = parse_editorconfig(content, Some("ts"));
+        assert_eq!(ts, vec!["editorconfig indent_style = space", "editorconfig indent_size = 2"]);
+    }
+
+    #[test]
+    fn test_detect_rustfmt_and_eslint() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("rustfmt.toml"), "max_width = 80\nhard_tabs = false\n").unwrap();
+        fs::write(
+            dir.path().join(".eslintrc.json"),
+            r#"{"extends": "eslint:recommended", "rules": {"quotes": ["error", "single"], "no-console": "warn"}}"#,
+        )
+        .unwrap();
+        let provider = StyleProfileProvider::new(dir.path());
+
+        let rust_profile = provider.detect(Some(Path::new("src/main.rs"))).unwrap();
+        assert_eq!(rust_profile.sources, vec!["rustfmt.toml"]);
+        assert!(rust_profile.rules.contains(&"rustfmt max_width = 80".to_string()));
+
+        let js_profile = provider.detect(Some(Path::new("web/app.js"))).unwrap();
+        assert_eq!(js_profile.sources, vec![".eslintrc.json"]);
+        assert!(js_profile.rules.contains(&r#"ESLint quotes: ["error","single"]"#.to_string()));
+        assert!(!js_profile.rules.iter().any(|r| r.contains("no-console")));
+    }
+
+    #[test]
+    fn test_provider_returns_none_without_config_files() {
+        let dir = tempdir().unwrap();
+        let provider = StyleProfileProvider::new(dir.path());
+        assert_eq!(provider.provide(Some(Path::new("lib.rs"))).unwrap(), None);
+    }
+}
@@ -0,0 +1,135 @@
+use crate::config::global_config_dir;
+use crate::context::session::project_key;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SUMMARY_DIR: &str = "onboard";
+// The overview sent with requests holds module summaries only, cut off here.
+const MAX_RENDERED_CHARS: usize = 3000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileSummary {
+    // SHA-256 of the content the summary describes; a different hash means it is stale.
+    pub hash: String,
+    pub summary: String,
+}
+
+// Summaries keyed by path relative to the project root, with `/` separators. The root
+// directory's module key is ".".
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SummaryIndex {
+    #[serde(default)]
+    pub files: BTreeMap<String, FileSummary>,
+    #[serde(default)]
+    pub modules: BTreeMap<String, String>,
+}
+
+pub fn module_label(module: &str) -> &str {
+    if module == "." {
+        "(project root)"
+    } else {
+        module
+    }
+}
+
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+impl SummaryIndex {
+    // The cached summary of `path` if it still describes `content`.
+    pub fn fresh_summary(&self, path: &str, content: &str) -> Option<&str> {
+        self.files.get(path).filter(|s| s.hash == content_hash(content)).map(|s| s.summary.as_str())
+    }
+}
+
+// Per-file and per-module summaries written by `opencode onboard`, kept per project in the
+// config directory. Other commands send the module summaries as a cheap overview instead of
+// reading files to find their way around. Cloning shares the same file.
+#[derive(Debug, Clone, Default)]
+pub struct SummaryCache {
+    path: Option<PathBuf>,
+}
+
+impl SummaryCache {
+    pub fn new(path: PathBuf) -> Self {
+        SummaryCache { path: Some(path) }
+    }
+
+    // The cache for the project in `project_dir`; unavailable when there is no config directory.
+    pub fn for_project(project_dir: &Path) -> Self {
+        match global_config_dir() {
+            Some(dir) => Self::new(dir.join(SUMMARY_DIR).join(format!("{}.json", project_key(project_dir)))),
+            None => Self::default(),
+        }
+    }
+
+    fn path(&self) -> Result<&Path> {
+        self.path.as_deref().ok_or_else(|| anyhow!("The summary cache is unavailable: no config directory"))
+    }
+
+    pub fn load(&self) -> Result<SummaryIndex> {
+        let path = self.path()?;
+        match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).with_context(|| format!("Corrupt summary cache {:?}", path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(SummaryIndex::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read summary cache {:?}", path)),
+        }
+    }
+
+    pub fn save(&self, index: &SummaryIndex) -> Result<()> {
+        let path = self.path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create {:?}", parent))?;
+        }
+        fs::write(path, serde_json::to_string_pretty(index)?).with_context(|| format!("Failed to write summary cache {:?}", path))
+    }
+
+    // The overview sent with each request; None before the project has been onboarded.
+    pub fn render(&self) -> Option<String> {
+        let index = self.load().map_err(|e| tracing::warn!("Skipping project overview: {:#}", e)).ok()?;
+        if index.modules.is_empty() {
+            return None;
+        }
+        let mut text = "Overview of this project's modules (from `opencode onboard`; read files for detail):".to_string();
+        for (module, summary) in &index.modules {
+            let line = format!("\n- {}: {}", module_label(module), summary);
+            if text.len() + line.len() > MAX_RENDERED_CHARS {
+                text.push_str("\n- ...");
+                break;
+            }
+            text.push_str(&line);
+        }
+        Some(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fresh_summaries_and_overview() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = SummaryCache::new(dir.path().join("onboard").join("project.json"));
+        assert_eq!(cache.render(), None);
+
+        let mut index = SummaryIndex::default();
+        index.files.insert("src/lib.rs".to_string(), FileSummary { hash: content_hash("pub mod a;"), summary: "Crate root".to_string() });
+        index.modules.insert(".".to_string(), "Build files".to_string());
+        index.modules.insert("src".to_string(), "The library".to_string());
+        cache.save(&index).unwrap();
+
+        let loaded = cache.load().unwrap();
+        assert_eq!(loaded.fresh_summary("src/lib.rs", "pub mod a;"), Some("Crate root"));
+        assert_eq!(loaded.fresh_summary("src/lib.rs", "pub mod b;"), None);
+        assert_eq!(
+            cache.render().unwrap(),
+            "Overview of this project's modules (from `opencode onboard`; read files for detail):\n- (project root): Build files\n- src: The library"
+        );
+    }
+}
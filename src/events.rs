@@ -0,0 +1,111 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+
+use crate::tools::todo::TodoItem;
+
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+// Everything user-facing that command handlers and tools report. The terminal is one
+// consumer; JSON output, a server or tests can subscribe to the same stream.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UiEvent {
+    Info { message: String },
+    Warning { message: String },
+    Error { message: String },
+    Result { content: String },
+    ToolStarted { tool: String, arguments: Value },
+    ToolFinished { tool: String, success: bool, output: Value },
+    TodosUpdated { todos: Vec<TodoItem> },
+    // A model response arrived; its text follows as `ContentDelta` and its token counts as `Usage`.
+    MessageStart { model: String },
+    ContentDelta { content: String },
+    Usage { prompt_tokens: u32, completion_tokens: u32, total_tokens: u32 },
+    Done { success: bool, error: Option<String> },
+}
+
+#[derive(Debug)]
+pub struct EventBus {
+    sender: broadcast::Sender<UiEvent>,
+    terminal_output: AtomicBool,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        EventBus { sender, terminal_output: AtomicBool::new(true) }
+    }
+
+    // The process-wide bus that `tui::print_*` and the tool execution engine emit to.
+    pub fn global() -> &'static EventBus {
+        static GLOBAL: OnceLock<EventBus> = OnceLock::new();
+        GLOBAL.get_or_init(EventBus::new)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<UiEvent> {
+        self.sender.subscribe()
+    }
+
+    // Turn off the built-in terminal renderer when another subscriber owns stdout.
+    pub fn set_terminal_output(&self, enabled: bool) {
+        self.terminal_output.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn terminal_output(&self) -> bool {
+        self.terminal_output.load(Ordering::Relaxed)
+    }
+
+    // The terminal renderer runs inline rather than as a channel subscriber so its output
+    // stays ordered with text streamed straight to stdout.
+    pub fn emit(&self, event: UiEvent) {
+        if self.terminal_output() {
+            crate::tui::render_event(&event);
+        }
+        // No subscribers is the normal case for plain terminal use.
+        let _ = self.sender.send(event);
+    }
+}
+
+pub fn emit(event: UiEvent) {
+    EventBus::global().emit(event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscribers_receive_events_in_order() {
+        let bus = EventBus::new();
+        bus.set_terminal_output(false);
+        let mut first = bus.subscribe();
+        let mut second = bus.subscribe();
+
+        bus.emit(UiEvent::Info { message: "starting".to_string() });
+        bus.emit(UiEvent::ToolFinished { tool: "FileReadTool".to_string(), success: true, output: Value::Null });
+
+        for receiver in [&mut first, &mut second] {
+            assert_eq!(receiver.try_recv().unwrap(), UiEvent::Info { message: "starting".to_string() });
+            assert!(matches!(receiver.try_recv().unwrap(), UiEvent::ToolFinished { success: true, .. }));
+            assert!(receiver.try_recv().is_err());
+        }
+    }
+
+    #[test]
+    fn test_events_serialize_with_type_tag() {
+        let event = UiEvent::ToolStarted { tool: "GitTool".to_string(), arguments: serde_json::json!({ "command": "status" }) };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({ "type": "tool_started", "tool": "GitTool", "arguments": { "command": "status" } })
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use anyhow::{anyhow, Context, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const LOCK_WAIT: Duration = Duration::from_secs(5);
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+// Advisory lock on `<path>.lock` (flock on unix), held until dropped, that keeps concurrent
+// opencode processes from interleaving writes to the same config or session file. The OS
+// drops the lock when its owner exits, so a lock file left behind by a crash is simply taken
+// over; the pid it records is only used to name the holder.
+#[derive(Debug)]
+pub struct FileLock {
+    path: PathBuf,
+    _file: File,
+}
+
+impl FileLock {
+    // Waits briefly for another process to finish its write.
+    pub fn acquire(target: &Path) -> Result<FileLock> {
+        let started = Instant::now();
+        loop {
+            match Self::try_acquire(target) {
+                Err(LockError::Held(pid)) if started.elapsed() < LOCK_WAIT => {
+                    tracing::debug!("{:?} is locked by process {}, retrying", target, pid);
+                    std::thread::sleep(RETRY_INTERVAL);
+                }
+                result => return result.map_err(|e| e.into_anyhow(target)),
+            }
+        }
+    }
+
+    // Fails straight away if another process holds the lock.
+    pub fn try_acquire(target: &Path) -> Result<FileLock, LockError> {
+        let path = lock_path(target);
+        let file = open_lock_file(&path)?;
+        match try_lock_file(&file) {
+            Ok(true) => {
+                if let Some(pid) = read_owner(&path).filter(|pid| *pid != std::process::id() && !process_is_alive(*pid)) {
+                    tracing::info!("Taking over lock {:?} left by process {}", path, pid);
+                }
+                Self::claim(path, file)
+            }
+            // The file is never removed while locked: the holder may not have written its pid
+            // yet, so a dead pid here does not mean the lock is free.
+            Ok(false) => Err(LockError::Held(read_owner(&path).unwrap_or_default())),
+            Err(e) => Err(LockError::Io(e.into())),
+        }
+    }
+
+    fn claim(path: PathBuf, mut file: File) -> Result<FileLock, LockError> {
+        file.set_len(0)
+            .and_then(|_| write!(file, "{}", std::process::id()))
+            .and_then(|_| file.flush())
+            .map_err(|e| LockError::Io(e.into()))?;
+        Ok(FileLock { path, _file: file })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[derive(Debug)]
+pub enum LockError {
+    // Pid of the live process holding the lock.
+    Held(u32),
+    Io(anyhow::Error),
+}
+
+impl LockError {
+    pub fn into_anyhow(self, target: &Path) -> anyhow::Error {
+        match self {
+            LockError::Held(pid) => anyhow!("{:?} is locked by another opencode process (pid {})", target, pid),
+            LockError::Io(e) => e.context(format!("Failed to lock {:?}", target)),
+        }
+    }
+}
+
+fn lock_path(target: &Path) -> PathBuf {
+    let mut name = target.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(".lock");
+    target.with_file_name(name)
+}
+
+fn open_lock_file(path: &Path) -> Result<File, LockError> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .with_context(|| format!("Failed to open lock file {:?}", path))
+        .map_err(LockError::Io)
+}
+
+// Ok(false) when another open file holds the lock.
+#[cfg(unix)]
+fn try_lock_file(file: &File) -> io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+    let error = io::Error::last_os_error();
+    match error.kind() {
+        io::ErrorKind::WouldBlock => Ok(false),
+        _ => Err(error),
+    }
+}
+
+#[cfg(not(unix))]
+fn try_lock_file(file: &File) -> io::Result<bool> {
+    match file.try_lock() {
+        Ok(()) => Ok(true),
+        Err(fs::TryLockError::WouldBlock) => Ok(false),
+        Err(fs::TryLockError::Error(e)) => Err(e),
+    }
+}
+
+fn read_owner(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(target_os = "linux")]
+fn process_is_alive(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+// Without a cheap liveness check, assume the owner is still running.
+#[cfg(not(target_os = "linux"))]
+fn process_is_alive(_pid: u32) -> bool {
+    true
+}
+
+// Takes the lock only for the duration of `write`.
+pub fn with_lock<T>(target: &Path, write: impl FnOnce() -> Result<T>) -> Result<T> {
+    let _lock = FileLock::acquire(target)?;
+    write()
+}
+
+// Replaces `path` with `contents` through a temporary file in the same directory, so readers
+// see either the old file or the new one and never a partial write.
+pub fn write_atomically(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let mut file = tempfile::NamedTempFile::new_in(dir).with_context(|| format!("Failed to create a temporary file in {:?}", dir))?;
+    file.write_all(contents.as_ref())
+        .and_then(|_| file.as_file().sync_all())
+        .with_context(|| format!("Failed to write {:?}", path))?;
+    file.persist(path).map_err(|e| anyhow!(e.error)).with_context(|| format!("Failed to replace {:?}", path))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_lock_is_refused_until_first_is_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("config.toml");
+        let first = FileLock::try_acquire(&target).unwrap();
+        assert_eq!(first.path(), dir.path().join("config.toml.lock"));
+        assert!(matches!(FileLock::try_acquire(&target), Err(LockError::Held(pid)) if pid == std::process::id()));
+        drop(first);
+        assert!(FileLock::try_acquire(&target).is_ok());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_lock_file_left_by_dead_process_is_taken_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("session.jsonl");
+        // A pid that cannot exist stands in for an owner that crashed.
+        fs::write(lock_path(&target), u32::MAX.to_string()).unwrap();
+        let lock = FileLock::try_acquire(&target).unwrap();
+        assert_eq!(read_owner(lock.path()), Some(std::process::id()));
+
+        // A held lock is never stolen, whatever pid the file records.
+        fs::write(lock_path(&target), u32::MAX.to_string()).unwrap();
+        assert!(matches!(FileLock::try_acquire(&target), Err(LockError::Held(_))));
+
+        write_atomically(&target, "replaced").unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "replaced");
+    }
+}
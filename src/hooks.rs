@@ -0,0 +1,183 @@
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::config::HooksConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    PreTool,
+    PostTool,
+    PreRequest,
+    PostResponse,
+}
+
+impl HookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::PreTool => "pre_tool",
+            HookEvent::PostTool => "post_tool",
+            HookEvent::PreRequest => "pre_request",
+            HookEvent::PostResponse => "post_response",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HookOutcome {
+    // Proceed with the (possibly rewritten) payload.
+    Continue(Value),
+    // The hook vetoed the action; carries the reason it gave.
+    Veto(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HookRunner {
+    config: HooksConfig,
+}
+
+impl HookRunner {
+    pub fn new(config: &HooksConfig) -> Self {
+        HookRunner { config: config.clone() }
+    }
+
+    fn command_for(&self, event: HookEvent) -> Option<&str> {
+        match event {
+            HookEvent::PreTool => self.config.pre_tool.as_deref(),
+            HookEvent::PostTool => self.config.post_tool.as_deref(),
+            HookEvent::PreRequest => self.config.pre_request.as_deref(),
+            HookEvent::PostResponse => self.config.post_response.as_deref(),
+        }
+    }
+
+    pub fn is_configured(&self, event: HookEvent) -> bool {
+        self.command_for(event).is_some_and(|c| !c.trim().is_empty())
+    }
+
+    // Runs the hook for `event`, if any. The script reads `{"event", "payload"}` on stdin.
+    // Exiting non-zero vetoes the action with stderr as the reason. Printing
+    // `{"payload": ...}` replaces the payload, and `{"veto": "reason"}` vetoes it.
+    // Empty output leaves the payload unchanged.
+    pub async fn run(&self, event: HookEvent, payload: Value) -> Result<HookOutcome> {
+        let Some(script) = self.command_for(event).filter(|c| !c.trim().is_empty()) else {
+            return Ok(HookOutcome::Continue(payload));
+        };
+        tracing::debug!("Running {} hook: {}", event.as_str(), script);
+
+        let (shell, shell_arg) = if cfg!(target_os = "windows") {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        let mut child = Command::new(shell)
+            .arg(shell_arg)
+            .arg(script)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start {} hook '{}'", event.as_str(), script))?;
+
+        let input = serde_json::to_vec(&json!({ "event": event.as_str(), "payload": payload }))?;
+        let stdin = child.stdin.take();
+        let write_input = async move {
+            if let Some(mut stdin) = stdin {
+                // A hook that ignores stdin may exit before reading it; that's not an error.
+                if let Err(e) = stdin.write_all(&input).await {
+                    tracing::debug!("{} hook did not read its input: {}", event.as_str(), e);
+                }
+            }
+        };
+        // Writing shares the timeout with the run, and runs alongside the output being read so
+        // a hook that prints before it reads cannot block on a full pipe.
+        let run = async { tokio::join!(write_input, child.wait_with_output()).1 };
+        let output = tokio::time::timeout(Duration::from_secs(self.config.timeout_seconds), run)
+            .await
+            .map_err(|_| anyhow::anyhow!("{} hook timed out after {}s", event.as_str(), self.config.timeout_seconds))?
+            .with_context(|| format!("Failed to wait for {} hook", event.as_str()))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            let reason = if stderr.is_empty() {
+                format!("{} hook exited with {}", event.as_str(), output.status)
+            } else {
+                stderr
+            };
+            tracing::info!("{} hook vetoed the action: {}", event.as_str(), reason);
+            return Ok(HookOutcome::Veto(reason));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if stdout.trim().is_empty() {
+            return Ok(HookOutcome::Continue(payload));
+        }
+        let response: Value = serde_json::from_str(stdout.trim())
+            .with_context(|| format!("{} hook printed invalid JSON", event.as_str()))?;
+        if let Some(reason) = response.get("veto").and_then(|v| v.as_str()) {
+            return Ok(HookOutcome::Veto(reason.to_string()));
+        }
+        Ok(HookOutcome::Continue(response.get("payload").cloned().unwrap_or(payload)))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn runner(pre_tool: &str) -> HookRunner {
+        HookRunner::new(&HooksConfig {
+            pre_tool: Some(pre_tool.to_string()),
+            ..HooksConfig::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_unconfigured_hook_passes_payload_through() {
+        let outcome = HookRunner::default().run(HookEvent::PreTool, json!({"a": 1})).await.unwrap();
+        assert_eq!(outcome, HookOutcome::Continue(json!({"a": 1})));
+    }
+
+    #[tokio::test]
+    async fn test_hook_can_veto_and_mutate() {
+        let veto = runner("echo 'writes are frozen' >&2; exit 1");
+        assert_eq!(
+            veto.run(HookEvent::PreTool, json!({})).await.unwrap(),
+            HookOutcome::Veto("writes are frozen".to_string())
+        );
+
+        let json_veto = runner(r#"echo '{"veto": "no network"}'"#);
+        assert_eq!(
+            json_veto.run(HookEvent::PreTool, json!({})).await.unwrap(),
+            HookOutcome::Veto("no network".to_string())
+        );
+
+        let mutate = runner(r#"grep -q '"event":"pre_tool"' && echo '{"payload": {"tool": "rewritten"}}'"#);
+        assert_eq!(
+            mutate.run(HookEvent::PreTool, json!({"tool": "original"})).await.unwrap(),
+            HookOutcome::Continue(json!({"tool": "rewritten"}))
+        );
+
+        let silent = runner("cat > /dev/null");
+        assert_eq!(
+            silent.run(HookEvent::PreTool, json!({"tool": "original"})).await.unwrap(),
+            HookOutcome::Continue(json!({"tool": "original"}))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_timeout_covers_writing_the_payload() {
+        let stalled = HookRunner::new(&HooksConfig {
+            pre_tool: Some("sleep 5".to_string()),
+            timeout_seconds: 1,
+            ..HooksConfig::default()
+        });
+        let large = json!({ "content": "x".repeat(1 << 20) });
+        let started = std::time::Instant::now();
+        assert!(stalled.run(HookEvent::PreTool, large).await.unwrap_err().to_string().contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+}
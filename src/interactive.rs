@@ -4,24 +4,37 @@ use rustyline::DefaultEditor;
 use std::fs;
 use std::env;
 use dirs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use crate::api::client::ApiClient;
+use crate::api::chat_api::ChatApi;
 use crate::api::models::{ChatCompletionRequest, Message, Role, ToolChoice};
 use crate::config::{Config, GLOBAL_CONFIG_DIR};
+use crate::context::environment::EnvironmentProvider;
+use crate::context::session::{SessionJournal, SessionSummary, SessionSummaryStore};
+use crate::file_lock::{FileLock, LockError};
 use crate::context::ContextManager;
-use crate::tui::{print_error, print_info, print_warning};
+use crate::tui::wrap::{output_width, wrap_for_output, StreamWrapper};
+use crate::tui::{print_error, print_info, print_warning, prompt_confirmation, prompt_select, start_spinner};
 use crate::tools::execution::ToolExecutionEngine;
 use crate::tools::registry::ToolRegistry;
+use crate::tools::session_env::SessionEnv;
+use crate::tools::snapshot::format_patch;
 use crate::app::generate_source_map;
 use crate::tools::ToolError;
 
 use futures_util::StreamExt;
 use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SUMMARY_TRANSCRIPT_CHARS: usize = 24_000;
+const SUMMARY_INSTRUCTIONS: &str = "Summarize the coding session transcript below for someone resuming the work \
+later. Respond with ONLY a JSON object: {\"task\": one sentence describing what the user was trying to do, \
+\"decisions\": [short statements of decisions made or conclusions reached], \"files_touched\": [paths of files \
+read or changed]}.";
 
 pub async fn run_interactive_mode<'a>(
     config: Config,
-    api_client: ApiClient,
+    api_client: &dyn ChatApi,
     mut context_manager: ContextManager,
     tool_registry: &'a ToolRegistry,
     tool_execution_engine: &'a ToolExecutionEngine<'a>,
@@ -88,7 +101,18 @@ pub async fn run_interactive_mode<'a>(
         }
     };
 
+    let started_at = unix_now();
+    start_session_journal(&mut context_manager, &current_dir)?;
+    // Models the next prompt is sent to side by side, set by `/compare`.
+    let mut pending_compare: Option<(String, String)> = None;
+
     loop {
+        if let Some(notice) = context_manager.take_eviction_notice() {
+            print_info(&format!("{} /evicted lists what was dropped.", notice));
+        }
+        if let Some(status) = tool_registry.todos().status_line() {
+            print_info(&status);
+        }
         let readline = rl.readline(">> ");
         match readline {
             Ok(line) => {
@@ -100,6 +124,17 @@ pub async fn run_interactive_mode<'a>(
                 if let Err(e) = rl.add_history_entry(trimmed_line) {
                      tracing::warn!("Failed to add line to history: {}", e);
                 }
+                // Snippets from /context add are re-read before each prompt so edits made
+                // between turns reach the model.
+                if !trimmed_line.starts_with('/') {
+                    match context_manager.refresh_snippets() {
+                        Ok(refreshed) if !refreshed.is_empty() => {
+                            print_info(&format!("Refreshed changed context snippets: {}", refreshed.join(", ")))
+                        }
+                        Ok(_) => {}
+                        Err(e) => print_warning(&format!("Failed to refresh context snippets: {:#}", e)),
+                    }
+                }
 
                 match trimmed_line {
                     "/exit" => {
@@ -111,12 +146,115 @@ pub async fn run_interactive_mode<'a>(
                         print_info("  /exit    - Quit the interactive session.");
                         print_info("  /help    - Show this help message.");
                         print_info("  /clear   - Clear the conversation history.");
+                        print_info("  /env     - Refresh and show the environment context sent to the model.");
+                        print_info("  /find    - Search earlier assistant replies and tool output, e.g. /find parse_config.");
+                        print_info("  /resume-summary <id> - Add the summary of an earlier session (see `opencode session list`).");
+                        print_info("  /memory  - List remembered project facts; /memory add <fact>, /memory forget <id>, /memory clear.");
+                        print_info("  /setenv  - List tool environment overrides; /setenv KEY=value sets one for every later tool process, /setenv KEY removes it.");
+                        print_info("  /export-patch <file> - Save every file change made this session as a git patch.");
+                        print_info("  /context - Show what the context holds; /context add <file>, /context refresh re-reads changed files.");
+                        print_info("  /evicted - List messages dropped from the context this session to stay under the token limit.");
+                        print_info("  /compare <modelA> <modelB> - Send the next prompt to both models and keep the answer you pick.");
                     }
                     "/clear" => {
                         context_manager.clear_history();
                         print_info("Conversation history cleared.");
                         tracing::debug!("Cleared conversation history via /clear command.");
                     }
+                    "/env" => {
+                        let current_dir = match env::current_dir() {
+                            Ok(dir) => dir,
+                            Err(e) => {
+                                print_warning(&format!("Cannot read the current directory: {}", e));
+                                continue;
+                            }
+                        };
+                        let summary = EnvironmentProvider::new(current_dir).snapshot().await.summary();
+                        context_manager.set_environment_context(summary.clone())?;
+                        print_info(&summary);
+                        tracing::debug!("Refreshed environment context via /env command.");
+                    }
+                    "/evicted" => {
+                        let evicted = context_manager.evicted();
+                        if evicted.is_empty() {
+                            print_info("Nothing has been dropped from the context this session.");
+                        }
+                        for item in evicted {
+                            let turn = item.turn.map(|turn| format!("turn {} ", turn)).unwrap_or_default();
+                            print_info(&format!("  [{}{}, ~{} tokens] {}", turn, item.source, item.tokens, item.preview));
+                        }
+                    }
+                    context if context == "/context" || context.starts_with("/context ") => {
+                        handle_context_command(&mut context_manager, context["/context".len()..].trim());
+                    }
+                    find if find == "/find" || find.starts_with("/find ") => {
+                        let query = find["/find".len()..].trim();
+                        if query.is_empty() {
+                            print_warning("Usage: /find <text>");
+                            continue;
+                        }
+                        let matches = context_manager.search_history(query);
+                        if matches.is_empty() {
+                            print_info(&format!("No earlier output matches '{}'.", query));
+                        }
+                        for m in matches {
+                            print_info(&format!("  [turn {} {:?}] {}", m.turn, m.role, m.line));
+                        }
+                    }
+                    resume if resume == "/resume-summary" || resume.starts_with("/resume-summary ") => {
+                        let id = resume["/resume-summary".len()..].trim();
+                        if id.is_empty() {
+                            print_warning("Usage: /resume-summary <id>");
+                            continue;
+                        }
+                        match SessionSummaryStore::from_config_dir().and_then(|store| store.find(id)) {
+                            Ok(summary) => {
+                                context_manager.pin_system_message(summary.render())?;
+                                print_info(&format!("Added the summary of session {}: {}", summary.id, summary.task));
+                                for (name, value) in &summary.env {
+                                    if let Ok(value) = tool_registry.session_env().set(name, value) {
+                                        print_info(&format!("Restored {}={} for tools.", name, value));
+                                    }
+                                }
+                            }
+                            Err(e) => print_warning(&format!("{:#}", e)),
+                        }
+                    }
+                    setenv if setenv == "/setenv" || setenv.starts_with("/setenv ") => {
+                        handle_setenv_command(tool_registry.session_env(), setenv["/setenv".len()..].trim());
+                    }
+                    memory if memory == "/memory" || memory.starts_with("/memory ") => {
+                        handle_memory_command(tool_registry, memory["/memory".len()..].trim());
+                    }
+                    export if export == "/export-patch" || export.starts_with("/export-patch ") => {
+                        let file = export["/export-patch".len()..].trim();
+                        if file.is_empty() {
+                            print_warning("Usage: /export-patch <file.patch>");
+                            continue;
+                        }
+                        match format_patch(tool_registry.snapshots(), &env::current_dir()?, "Changes from an OpenCode session") {
+                            Some(patch) => match fs::write(file, patch) {
+                                Ok(()) => print_info(&format!("Wrote {}; apply it with `opencode apply-patch {}` or `git am`.", file, file)),
+                                Err(e) => print_warning(&format!("Failed to write {}: {}", file, e)),
+                            },
+                            None => print_info("No files under the current directory have changed this session."),
+                        }
+                    }
+                    compare if compare == "/compare" || compare.starts_with("/compare ") => {
+                        match parse_compare_args(compare["/compare".len()..].trim()) {
+                            Some((a, b)) => {
+                                print_info(&format!("The next prompt goes to {} (A) and {} (B).", a, b));
+                                pending_compare = Some((a, b));
+                            }
+                            None => print_warning("Usage: /compare <modelA> <modelB>"),
+                        }
+                    }
+                    prompt if pending_compare.is_some() => {
+                        let (model_a, model_b) = pending_compare.take().unwrap_or_default();
+                        if let Err(e) = compare_answers(api_client, &mut context_manager, prompt, &model_a, &model_b).await {
+                            print_error(&format!("Comparison failed: {:#}", e));
+                        }
+                    }
                     _ => {
                         let user_message = Message {
                             role: Role::User,
@@ -162,6 +300,7 @@ pub async fn run_interactive_mode<'a>(
                                 let mut current_tool_calls: Option<Vec<crate::api::models::ToolCall>> = None; // To handle incremental tool calls
 
                                 print_info("Assistant: "); // Indicate AI is responding
+                                let mut wrapper = StreamWrapper::new(output_width());
 
                                 while let Some(chunk_result) = stream.next().await {
                                     match chunk_result {
@@ -169,7 +308,7 @@ pub async fn run_interactive_mode<'a>(
                                             if let Some(choice) = chunk.choices.first() {
                                                 if let Some(content_text) = &choice.delta.content {
                                                     if !content_text.is_empty() {
-                                                        print!("{}", content_text); // Print content as it arrives
+                                                        print!("{}", wrapper.push(content_text)); // Print content as it arrives
                                                         std::io::stdout().flush().ok();
                                                         accumulated_content.push_str(content_text);
                                                     }
@@ -197,7 +336,7 @@ pub async fn run_interactive_mode<'a>(
                                         }
                                     }
                                 }
-                                println!(); // Newline after streaming is complete
+                                println!("{}", wrapper.finish()); // Newline after streaming is complete
 
                                 // Consolidate accumulated tool calls if any were received
                                 if let Some(calls) = current_tool_calls {
@@ -225,7 +364,6 @@ pub async fn run_interactive_mode<'a>(
                                     // but for sequential logic, we handle the first one.
                                     let tool_call = current_tool_calls.remove(0); // Take the first tool call
 
-                                    print_info(&format!("\nExecuting tool: {} (ID: {})", tool_call.function.name, tool_call.id));
                                     let tool_name = &tool_call.function.name;
                                     let tool_args_str = &tool_call.function.arguments;
 
@@ -240,10 +378,9 @@ pub async fn run_interactive_mode<'a>(
                                     };
 
                                     // Execute the single tool call
-                                    let tool_result_content = match tool_execution_engine.execute_tool_call(tool_name, arguments_value).await {
+                                    let tool_result_content = match tool_execution_engine.execute_tool_call(tool_name, arguments_value.clone()).await {
                                         Ok(result) => {
                                             tracing::info!("Tool '{}' executed successfully. Result: {:?}", tool_name, result);
-                                            print_info(&format!("  - Success: {}", serde_json::to_string(&result).unwrap_or_else(|_| "Result not serializable".to_string())));
                                             result
                                         },
                                         Err(ToolError::FileNotFound { path }) => {
@@ -278,6 +415,8 @@ pub async fn run_interactive_mode<'a>(
                                         }
                                     };
 
+                                    let tool_result_content = context_manager.dedupe_file_read(tool_name, &arguments_value, &tool_call.id, tool_result_content);
+
                                     // Serialize tool result content first
                                     let tool_result_content_str = serde_json::to_string(&tool_result_content)
                                         .unwrap_or_else(|_| "{\"error\": \"Failed to serialize tool result\"}".to_string());
@@ -323,6 +462,7 @@ pub async fn run_interactive_mode<'a>(
                                             let mut next_current_tool_calls: Option<Vec<crate::api::models::ToolCall>> = None;
 
                                             print_info("Assistant: ");
+                                            let mut wrapper = StreamWrapper::new(output_width());
 
                                             while let Some(next_chunk_result) = next_stream.next().await {
                                                 match next_chunk_result {
@@ -330,7 +470,7 @@ pub async fn run_interactive_mode<'a>(
                                                         if let Some(choice) = chunk.choices.first() {
                                                             if let Some(content_text) = &choice.delta.content {
                                                                 if !content_text.is_empty() {
-                                                                    print!("{}", content_text);
+                                                                    print!("{}", wrapper.push(content_text));
                                                                     std::io::stdout().flush().ok();
                                                                     next_accumulated_content.push_str(content_text);
                                                                 }
@@ -351,7 +491,7 @@ pub async fn run_interactive_mode<'a>(
                                                     }
                                                 }
                                             }
-                                            println!(); // Newline after streaming
+                                            println!("{}", wrapper.finish()); // Newline after streaming
 
                                             if let Some(calls) = next_current_tool_calls {
                                                 next_accumulated_tool_calls = calls;
@@ -447,6 +587,352 @@ pub async fn run_interactive_mode<'a>(
         }
     }
 
+    save_session_summary(api_client, &config, &context_manager, tool_registry, &current_dir, started_at).await;
+
+    if let Err(e) = context_manager.finish_journal() {
+        tracing::warn!("Failed to remove session journal: {:#}", e);
+    }
+
     tracing::info!("Exited interactive mode.");
     Ok(())
-}
\ No newline at end of file
+}
+
+// Offers to resume a session that ended without a clean exit, then journals this one.
+fn start_session_journal(context_manager: &mut ContextManager, current_dir: &Path) -> Result<()> {
+    let journal_path = match SessionJournal::path_for(current_dir) {
+        Ok(path) => path,
+        Err(e) => {
+            print_warning(&format!("Session recovery unavailable: {}", e));
+            return Ok(());
+        }
+    };
+    // Another session in this directory owns the journal; leave it alone.
+    let lock = match FileLock::try_acquire(&journal_path) {
+        Ok(lock) => lock,
+        Err(LockError::Held(pid)) => {
+            print_warning(&format!("Another opencode session (pid {}) is active here; this one won't be journaled.", pid));
+            return Ok(());
+        }
+        Err(e) => {
+            print_warning(&format!("Session journaling disabled: {:#}", e.into_anyhow(&journal_path)));
+            return Ok(());
+        }
+    };
+    match SessionJournal::recover(&journal_path) {
+        Ok(messages) if !messages.is_empty() => {
+            let prompt = format!("The previous session was interrupted. Resume it ({} messages)?", messages.len());
+            if prompt_confirmation(&prompt)? {
+                context_manager.restore_history(messages)?;
+                print_info("Resumed the interrupted session.");
+            }
+        }
+        Ok(_) => {}
+        Err(e) => print_warning(&format!("Could not recover the interrupted session: {:#}", e)),
+    }
+    if let Err(e) = context_manager.start_journal(&journal_path, lock) {
+        print_warning(&format!("Session journaling disabled: {:#}", e));
+    }
+    Ok(())
+}
+
+// `/memory [add <fact> | forget <id> | clear]`. Changes reach the model on the next request.
+fn handle_memory_command(tool_registry: &ToolRegistry, args: &str) {
+    let memory = tool_registry.memory();
+    let (action, rest) = args.split_once(' ').map(|(a, r)| (a, r.trim())).unwrap_or((args, ""));
+    let result = match action {
+        "" => memory.facts().map(|facts| {
+            if facts.is_empty() {
+                print_info("Nothing remembered for this project yet.");
+            }
+            for fact in facts {
+                print_info(&format!("  [{}] {}", fact.id, fact.fact));
+            }
+        }),
+        "add" => memory.remember(rest).map(|id| print_info(&format!("Remembered as [{}].", id))),
+        "forget" => match rest.parse::<usize>() {
+            Ok(id) => memory.forget(id).map(|fact| print_info(&format!("Forgot: {}", fact.fact))),
+            Err(_) => Err(anyhow::anyhow!("Usage: /memory forget <id>")),
+        },
+        "clear" => memory.clear().map(|count| print_info(&format!("Forgot {} fact(s).", count))),
+        _ => Err(anyhow::anyhow!("Usage: /memory [add <fact> | forget <id> | clear]")),
+    };
+    if let Err(e) = result {
+        print_warning(&format!("{:#}", e));
+    }
+}
+
+// `/setenv [KEY=value | KEY]`. Overrides apply to tool processes started afterwards only.
+fn handle_setenv_command(session_env: &SessionEnv, args: &str) {
+    match args.split_once('=') {
+        _ if args.is_empty() => {
+            if session_env.is_empty() {
+                print_info("No environment overrides for tools. Set one with /setenv KEY=value.");
+            }
+            for (name, value) in session_env.vars() {
+                print_info(&format!("  {}={}", name, value));
+            }
+        }
+        Some((name, value)) => match session_env.set(name.trim(), value.trim()) {
+            Ok(value) => print_info(&format!("Tools now run with {}={}.", name.trim(), value)),
+            Err(e) => print_warning(&e),
+        },
+        None => match session_env.unset(args) {
+            Some(_) => print_info(&format!("Tools no longer get an override for {}.", args)),
+            None => print_warning(&format!("{} has no override. Usage: /setenv KEY=value or /setenv KEY to remove one.", args)),
+        },
+    }
+}
+
+// `/context [add <file> | refresh]`. Refreshed snippets stay marked in the listing.
+fn handle_context_command(context_manager: &mut ContextManager, args: &str) {
+    let (action, rest) = args.split_once(' ').map_or((args, ""), |(a, r)| (a, r.trim()));
+    match action {
+        "" => {
+            let (pinned, history, total, budget) = context_manager.token_stats();
+            print_info(&format!("Context: {} of {} tokens (pinned {}, history {}).", total, budget, pinned, history));
+            let snippets = context_manager.snippet_stats();
+            if snippets.is_empty() {
+                print_info("No snippets. Add one with /context add <file>.");
+            }
+            for snippet in snippets {
+                let mark = if snippet.refreshed { " (refreshed)" } else { "" };
+                print_info(&format!("  [~{} tokens] {}{}", snippet.tokens, snippet.source, mark));
+            }
+        }
+        "add" if !rest.is_empty() => match context_manager.add_file_snippet(Path::new(rest)) {
+            Ok(()) => print_info(&format!("Added {} to the context.", rest)),
+            Err(e) => print_error(&format!("{:#}", e)),
+        },
+        "refresh" => match context_manager.refresh_snippets() {
+            Ok(refreshed) if refreshed.is_empty() => print_info("No snippet source changed."),
+            Ok(refreshed) => print_info(&format!("Refreshed {}.", refreshed.join(", "))),
+            Err(e) => print_error(&format!("{:#}", e)),
+        },
+        _ => print_warning("Usage: /context [add <file> | refresh]"),
+    }
+}
+
+// `/compare <modelA> <modelB>`: exactly two model names.
+fn parse_compare_args(args: &str) -> Option<(String, String)> {
+    let mut models = args.split_whitespace();
+    match (models.next(), models.next(), models.next()) {
+        (Some(a), Some(b), None) => Some((a.to_string(), b.to_string())),
+        _ => None,
+    }
+}
+
+// Splits `text` into lines of at most `width` characters.
+fn wrap_column(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let chars: Vec<char> = line.chars().collect();
+        if chars.is_empty() {
+            lines.push(String::new());
+        }
+        lines.extend(chars.chunks(width.max(1)).map(|chunk| chunk.iter().collect::<String>()));
+    }
+    lines
+}
+
+// Two answers in columns that fit `width` terminal columns, each headed by its label.
+fn side_by_side(left: (&str, &str), right: (&str, &str), width: usize) -> String {
+    let column = width.saturating_sub(3) / 2;
+    let left_lines = wrap_column(&format!("{}\n{}\n{}", left.0, "-".repeat(column), left.1), column);
+    let right_lines = wrap_column(&format!("{}\n{}\n{}", right.0, "-".repeat(column), right.1), column);
+    (0..left_lines.len().max(right_lines.len()))
+        .map(|i| {
+            let l = left_lines.get(i).map(String::as_str).unwrap_or("");
+            let r = right_lines.get(i).map(String::as_str).unwrap_or("");
+            format!("{:<column$} │ {}", l, r, column = column).trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Sends `prompt` to both models at once without tools, shows the answers, and adds the prompt
+// and the chosen answer to the history. Choosing neither leaves the history untouched.
+async fn compare_answers(
+    api_client: &dyn ChatApi,
+    context_manager: &mut ContextManager,
+    prompt: &str,
+    model_a: &str,
+    model_b: &str,
+) -> Result<()> {
+    let user_message = Message { role: Role::User, content: Some(prompt.to_string()), tool_calls: None, tool_call_id: None };
+    let mut messages = context_manager.construct_api_messages()?;
+    messages.push(user_message.clone());
+    let request = |model: &str| ChatCompletionRequest {
+        model: model.to_string(),
+        messages: messages.clone(),
+        stream: None,
+        temperature: None,
+        max_tokens: None,
+        tools: None,
+        tool_choice: None,
+        source_map: None,
+    };
+
+    let spinner = start_spinner(&format!("Asking {} and {}...", model_a, model_b));
+    let (a, b) = tokio::join!(api_client.chat_completion(request(model_a)), api_client.chat_completion(request(model_b)));
+    spinner.finish_and_clear();
+    let answer = |response: Result<crate::api::models::ChatCompletionResponse>| {
+        response.map(|r| r.choices.into_iter().next().and_then(|c| c.message.content).unwrap_or_default())
+    };
+    let answers = [(model_a, answer(a)), (model_b, answer(b))];
+    let shown: Vec<(String, String)> = answers
+        .iter()
+        .zip(["A", "B"])
+        .map(|((model, answer), label)| {
+            let text = match answer {
+                Ok(text) => text.trim().to_string(),
+                Err(e) => format!("(failed: {:#})", e),
+            };
+            (format!("{}: {}", label, model), text)
+        })
+        .collect();
+
+    let width = crossterm::terminal::size().map(|(columns, _)| columns as usize).unwrap_or(80);
+    if width >= 100 {
+        println!("{}", side_by_side((&shown[0].0, &shown[0].1), (&shown[1].0, &shown[1].1), width));
+    } else {
+        for (label, text) in &shown {
+            print_info(&format!("--- {} ---", label));
+            println!("{}", wrap_for_output(text));
+        }
+    }
+
+    let mut choices: Vec<(String, Option<&str>)> = answers
+        .iter()
+        .zip(["A", "B"])
+        .filter_map(|((model, answer), label)| {
+            answer.as_ref().ok().map(|text| (format!("Keep {} ({})", label, model), Some(text.as_str())))
+        })
+        .collect();
+    choices.push(("Keep neither".to_string(), None));
+    let items: Vec<String> = choices.iter().map(|(item, _)| item.clone()).collect();
+    let Some(chosen) = choices[prompt_select("Which answer goes into the conversation?", &items)?].1 else {
+        print_info("Discarded both answers; the prompt was not added to the history.");
+        return Ok(());
+    };
+    context_manager.add_message(user_message)?;
+    context_manager.add_message(Message {
+        role: Role::Assistant,
+        content: Some(chosen.to_string()),
+        tool_calls: None,
+        tool_call_id: None,
+    })?;
+    Ok(())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+// Flattens the session into text for the summarizer, keeping the most recent part if it is long.
+fn summary_transcript(messages: &[Message]) -> String {
+    let transcript = messages
+        .iter()
+        .filter_map(|message| {
+            let mut line = message.content.clone().unwrap_or_default();
+            for call in message.tool_calls.iter().flatten() {
+                line.push_str(&format!("\n[called {} {}]", call.function.name, call.function.arguments));
+            }
+            (!line.trim().is_empty()).then(|| format!("{:?}: {}", message.role, line))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let skip = transcript.chars().count().saturating_sub(SUMMARY_TRANSCRIPT_CHARS);
+    transcript.chars().skip(skip).collect()
+}
+
+async fn summarize_session(api_client: &dyn ChatApi, config: &Config, messages: &[Message]) -> Result<SessionSummary> {
+    let request = ChatCompletionRequest {
+        model: config.api.edit_model.clone(),
+        messages: vec![Message {
+            role: Role::User,
+            content: Some(format!("{}\n\n{}", SUMMARY_INSTRUCTIONS, summary_transcript(messages))),
+            tool_calls: None,
+            tool_call_id: None,
+        }],
+        stream: None,
+        temperature: Some(0.0),
+        max_tokens: None,
+        tools: None,
+        tool_choice: None,
+        source_map: None,
+    };
+    let response = api_client.chat_completion(request).await?;
+    let content = response.choices.into_iter().next().and_then(|c| c.message.content).unwrap_or_default();
+    let start = content.find('{').context("Summary response contained no JSON object")?;
+    let end = content.rfind('}').filter(|end| *end > start).context("Summary response contained no JSON object")?;
+    let parsed: serde_json::Value = serde_json::from_str(&content[start..=end]).context("Failed to parse session summary")?;
+    let strings = |key: &str| -> Vec<String> {
+        parsed[key].as_array().into_iter().flatten().filter_map(|v| v.as_str().map(str::to_string)).collect()
+    };
+    Ok(SessionSummary {
+        id: String::new(),
+        project_dir: PathBuf::new(),
+        started_at: 0,
+        ended_at: 0,
+        message_count: messages.len(),
+        task: parsed["task"].as_str().unwrap_or_default().to_string(),
+        decisions: strings("decisions"),
+        files_touched: strings("files_touched"),
+        env: Default::default(),
+    })
+}
+
+// Best effort: a session that can't be summarized still exits cleanly.
+async fn save_session_summary(
+    api_client: &dyn ChatApi,
+    config: &Config,
+    context_manager: &ContextManager,
+    tool_registry: &ToolRegistry,
+    current_dir: &Path,
+    started_at: u64,
+) {
+    let messages = context_manager.history_messages();
+    if !messages.iter().any(|m| m.role == Role::User) {
+        return;
+    }
+    let spinner = start_spinner("Summarizing session...");
+    let summarized = summarize_session(api_client, config, &messages).await;
+    spinner.finish_and_clear();
+    let mut summary = match summarized {
+        Ok(summary) => summary,
+        Err(e) => {
+            tracing::warn!("Failed to summarize session: {:#}", e);
+            return;
+        }
+    };
+    summary.id = started_at.to_string();
+    summary.project_dir = current_dir.to_path_buf();
+    summary.started_at = started_at;
+    summary.ended_at = unix_now();
+    summary.env = tool_registry.session_env().vars();
+    for snapshot in tool_registry.snapshots().snapshots() {
+        let path = crate::tools::snapshot::display_path(&snapshot.path);
+        if !summary.files_touched.contains(&path) {
+            summary.files_touched.push(path);
+        }
+    }
+    match SessionSummaryStore::from_config_dir().and_then(|store| store.save(&summary)) {
+        Ok(_) => print_info(&format!("Saved session summary {}: {}", summary.id, summary.task)),
+        Err(e) => tracing::warn!("Failed to save session summary: {:#}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_args_and_side_by_side_columns() {
+        assert_eq!(parse_compare_args("gpt-4o  claude-3"), Some(("gpt-4o".to_string(), "claude-3".to_string())));
+        assert_eq!(parse_compare_args("gpt-4o"), None);
+        assert_eq!(parse_compare_args("a b c"), None);
+
+        let rendered = side_by_side(("A: x", "short"), ("B: y", "a longer answer"), 23);
+        assert_eq!(rendered, "A: x       │ B: y\n---------- │ ----------\nshort      │ a longer a\n           │ nswer");
+    }
+}
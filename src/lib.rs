@@ -0,0 +1,21 @@
+pub mod agent;
+pub mod app;
+pub mod cargo_workspace;
+pub mod commands;
+pub mod interactive;
+pub mod events;
+pub mod file_lock;
+pub mod streaming;
+pub mod stream_json;
+pub mod hooks;
+pub mod shutdown;
+pub mod loop_detection;
+pub mod logging;
+
+pub mod api;
+pub mod cli;
+pub mod config;
+pub mod context;
+pub mod parsing;
+pub mod tools;
+pub mod tui;
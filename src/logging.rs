@@ -0,0 +1,106 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
+use tracing_appender::rolling::{Builder, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer};
+
+use crate::config::{global_config_dir, LoggingConfig};
+use crate::tui::print_warning;
+
+const LOG_DIR: &str = "logs";
+const LOG_FILE_PREFIX: &str = "opencode";
+const LOG_FILE_SUFFIX: &str = "log";
+
+pub fn log_dir() -> Option<PathBuf> {
+    global_config_dir().map(|dir| dir.join(LOG_DIR))
+}
+
+// `level` first, then one `module=level` directive per `[logging.modules]` entry, sorted so
+// the filter does not depend on map order. `RUST_LOG` replaces all of it when set.
+pub fn filter_directives(config: &LoggingConfig) -> String {
+    let mut modules: Vec<_> = config.modules.iter().collect();
+    modules.sort();
+    std::iter::once(config.level.clone())
+        .chain(modules.into_iter().map(|(module, level)| format!("{}={}", module, level)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn env_filter(config: &LoggingConfig) -> Result<EnvFilter> {
+    if let Ok(filter) = EnvFilter::try_from_default_env() {
+        return Ok(filter);
+    }
+    EnvFilter::builder()
+        .parse(filter_directives(config))
+        .context("Invalid [logging] level or module filter")
+}
+
+fn open_log_file(config: &LoggingConfig) -> Result<Option<(NonBlocking, WorkerGuard)>> {
+    let Some(dir) = log_dir() else { return Ok(None) };
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create log directory {}", dir.display()))?;
+    let appender = Builder::new()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(config.max_files.max(1))
+        .build(&dir)
+        .context("Failed to open log file")?;
+    Ok(Some(tracing_appender::non_blocking(appender)))
+}
+
+// Logs go to a daily rotated file under the config directory, keeping `max_files` of them, so
+// they never interleave with the TUI. With `terminal = true` they are also written to stderr,
+// as they are when the log file cannot be opened. The returned guard flushes the file on drop
+// and must be kept alive until exit.
+pub fn init(config: &LoggingConfig) -> Result<Option<WorkerGuard>> {
+    let (file, terminal) = match open_log_file(config) {
+        Ok(file) => (file, config.terminal),
+        Err(e) => {
+            print_warning(&format!("{:#}; logging to stderr instead.", e));
+            (None, true)
+        }
+    };
+    let (writer, guard) = file.unzip();
+    let file_layer = match writer {
+        Some(writer) => Some(fmt::layer().with_ansi(false).with_writer(writer).with_filter(env_filter(config)?)),
+        None => None,
+    };
+    let terminal_layer = if terminal {
+        Some(fmt::layer().with_writer(std::io::stderr).with_filter(env_filter(config)?))
+    } else {
+        None
+    };
+    tracing_subscriber::registry()
+        .with(file_layer)
+        .with(terminal_layer)
+        .try_init()
+        .context("Failed to initialise logging")?;
+    Ok(guard)
+}
+
+// The most recently written log file in `dir`.
+pub fn latest_log_file(dir: &Path) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(LOG_FILE_PREFIX))
+        .max_by_key(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+        .map(|entry| entry.path())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_directives_per_module() {
+        let mut config = LoggingConfig::default();
+        config.modules.insert("opencode::tools".to_string(), "trace".to_string());
+        config.modules.insert("opencode::api".to_string(), "debug".to_string());
+        assert_eq!(filter_directives(&config), "info,opencode::api=debug,opencode::tools=trace");
+        assert!(EnvFilter::builder().parse(filter_directives(&config)).is_ok());
+    }
+}
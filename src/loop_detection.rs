@@ -0,0 +1,95 @@
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::api::models::ToolCall;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Repetition {
+    // The calls differ from the previous iteration's.
+    Fresh,
+    // The same calls as the previous `count - 1` iterations.
+    Repeated { tool: String, count: usize },
+    // Repeated `max_repeats` times; the loop should stop.
+    Stuck { tool: String, count: usize },
+}
+
+// Spots an agent loop calling the same tools with the same arguments iteration after
+// iteration. Each iteration's calls are hashed together; ids are ignored and arguments are
+// compared as parsed JSON, so formatting differences do not hide a repeat.
+#[derive(Debug, Clone)]
+pub struct RepetitionDetector {
+    max_repeats: usize,
+    last: Option<u64>,
+    count: usize,
+}
+
+impl RepetitionDetector {
+    pub fn new(max_repeats: usize) -> Self {
+        RepetitionDetector { max_repeats, last: None, count: 0 }
+    }
+
+    pub fn observe(&mut self, calls: &[ToolCall]) -> Repetition {
+        let Some(first) = calls.first() else {
+            self.last = None;
+            return Repetition::Fresh;
+        };
+        let mut hasher = DefaultHasher::new();
+        for call in calls {
+            call.function.name.hash(&mut hasher);
+            match serde_json::from_str::<Value>(&call.function.arguments) {
+                Ok(arguments) => arguments.to_string().hash(&mut hasher),
+                Err(_) => call.function.arguments.hash(&mut hasher),
+            }
+        }
+        let hash = hasher.finish();
+        self.count = if self.last == Some(hash) { self.count + 1 } else { 1 };
+        self.last = Some(hash);
+
+        let tool = first.function.name.clone();
+        match self.count {
+            1 => Repetition::Fresh,
+            count if self.max_repeats > 0 && count >= self.max_repeats => Repetition::Stuck { tool, count },
+            count => Repetition::Repeated { tool, count },
+        }
+    }
+}
+
+// Added to the conversation as a system message once a repeat is seen.
+pub fn corrective_message(tool: &str, count: usize) -> String {
+    format!(
+        "You have called {} with the same arguments {} times in a row and got the same result. \
+         Repeating it will not change the outcome. Use what the earlier results told you, try a \
+         different approach, or stop and report that you are blocked.",
+        tool, count
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::models::ToolCallFunction;
+
+    fn call(id: &str, name: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            tool_type: "function".to_string(),
+            function: ToolCallFunction { name: name.to_string(), arguments: arguments.to_string() },
+        }
+    }
+
+    #[test]
+    fn test_detects_identical_consecutive_calls() {
+        let mut detector = RepetitionDetector::new(3);
+        assert_eq!(detector.observe(&[call("1", "FileReadTool", "{\"path\":\"a.rs\"}")]), Repetition::Fresh);
+        assert_eq!(
+            detector.observe(&[call("2", "FileReadTool", "{ \"path\": \"a.rs\" }")]),
+            Repetition::Repeated { tool: "FileReadTool".to_string(), count: 2 }
+        );
+        assert_eq!(
+            detector.observe(&[call("3", "FileReadTool", "{\"path\":\"a.rs\"}")]),
+            Repetition::Stuck { tool: "FileReadTool".to_string(), count: 3 }
+        );
+        assert_eq!(detector.observe(&[call("4", "FileReadTool", "{\"path\":\"b.rs\"}")]), Repetition::Fresh);
+    }
+}
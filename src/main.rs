@@ -1,22 +1,19 @@
-use crate::tui::print_error;
-
-mod app;
-mod commands;
-mod interactive;
-mod streaming;
-
-mod api;
-mod cli;
-mod config;
-mod context;
-mod parsing;
-mod tools;
-mod tui;
+use opencode::app;
+use opencode::commands::self_update::UpdateAvailable;
+use opencode::shutdown::ShutdownCoordinator;
+use opencode::tui::print_error;
 
 #[tokio::main]
 async fn main() {
-    if let Err(e) = app::run().await {
+    let result = app::run().await;
+    if let Some(signal) = ShutdownCoordinator::global().received() {
+        std::process::exit(signal.exit_code());
+    }
+    if let Err(e) = result {
+        if e.downcast_ref::<UpdateAvailable>().is_some() {
+            std::process::exit(UpdateAvailable::EXIT_CODE);
+        }
         print_error(&format!("Application failed: {:?}", e));
         std::process::exit(1);
     }
-}
\ No newline at end of file
+}
@@ -18,7 +18,7 @@ pub fn find_symbol_context(file_path: &str, symbol_name: &str) -> Result<String>
     let mut parser = Parser::new();
     parser
         .set_language(&language)
-        .with_context(|| format!("Failed to set language for parser"))?;
+        .context("Failed to set language for parser")?;
 
     let tree = parser
         .parse(&source_code, None)
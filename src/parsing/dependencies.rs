@@ -0,0 +1,191 @@
+use anyhow::{anyhow, Context, Result};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use tree_sitter::{Parser, Query, QueryCursor};
+
+use super::outline::outline_directory;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModuleGraph {
+    // Module paths relative to the scope root, e.g. `tools::registry`; the root is `crate`.
+    pub modules: Vec<String>,
+    // `(from, to)` pairs where `from` imports something from `to`.
+    pub edges: Vec<(String, String)>,
+}
+
+// Maps a source file (relative to the crate's `src` directory) to its module path.
+pub fn module_path_for(relative: &Path) -> String {
+    let mut segments: Vec<String> = relative
+        .with_extension("")
+        .iter()
+        .map(|s| s.to_string_lossy().to_string())
+        .collect();
+    if matches!(segments.last().map(String::as_str), Some("mod" | "lib" | "main")) {
+        segments.pop();
+    }
+    if segments.is_empty() {
+        "crate".to_string()
+    } else {
+        segments.join("::")
+    }
+}
+
+// Expands a use tree such as `crate::{a::B, c::{D, E}}` into individual paths.
+pub fn expand_use_tree(tree: &str) -> Vec<String> {
+    // Mark aliases before dropping whitespace so `B as C` still resolves to `B`.
+    let tree: String = tree.replace(" as ", "@").chars().filter(|c| !c.is_whitespace()).collect();
+    let Some(open) = tree.find('{') else {
+        let path = tree.split('@').next().unwrap_or(&tree);
+        return vec![path.trim_end_matches("::").to_string()];
+    };
+    let prefix = &tree[..open];
+    let inner = tree[open + 1..].strip_suffix('}').unwrap_or(&tree[open + 1..]);
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(&inner[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&inner[start..]);
+    items
+        .into_iter()
+        .filter(|item| !item.is_empty())
+        .flat_map(|item| {
+            if item == "self" {
+                vec![prefix.trim_end_matches("::").to_string()]
+            } else {
+                expand_use_tree(&format!("{}{}", prefix, item))
+            }
+        })
+        .collect()
+}
+
+fn extract_use_paths(path: &Path, source: &str) -> Result<Vec<String>> {
+    let language = tree_sitter_rust::language();
+    let mut parser = Parser::new();
+    parser.set_language(&language).context("Failed to set language for parser")?;
+    let tree = parser
+        .parse(source, None)
+        .ok_or_else(|| anyhow!("Failed to parse file: {:?}", path))?;
+    let query = Query::new(&language, "(use_declaration argument: (_) @path)").context("Failed to create query")?;
+    let mut cursor = QueryCursor::new();
+    let mut paths = Vec::new();
+    for m in cursor.matches(&query, tree.root_node(), source.as_bytes()) {
+        for capture in m.captures {
+            paths.extend(expand_use_tree(capture.node.utf8_text(source.as_bytes())?));
+        }
+    }
+    Ok(paths)
+}
+
+// Resolves an import to the most specific known module, or None for external crates.
+fn resolve_import(current: &str, import: &str, modules: &BTreeSet<String>) -> Option<String> {
+    let segments: Vec<&str> = import.split("::").collect();
+    let own: Vec<String> = if current == "crate" {
+        Vec::new()
+    } else {
+        current.split("::").map(String::from).collect()
+    };
+    let absolute: Vec<String> = match segments.first().copied() {
+        Some("crate") => segments[1..].iter().map(|s| s.to_string()).collect(),
+        Some("self") => own.iter().cloned().chain(segments[1..].iter().map(|s| s.to_string())).collect(),
+        Some("super") => {
+            let supers = segments.iter().take_while(|s| **s == "super").count();
+            let mut parent = own.clone();
+            parent.truncate(own.len().saturating_sub(supers));
+            parent.extend(segments[supers..].iter().map(|s| s.to_string()));
+            parent
+        }
+        Some(first) if modules.contains(first) => segments.iter().map(|s| s.to_string()).collect(),
+        _ => return None,
+    };
+    (1..=absolute.len())
+        .rev()
+        .map(|len| absolute[..len].join("::"))
+        .find(|candidate| modules.contains(candidate) && candidate != current)
+}
+
+// Builds the module import graph for the Rust sources under `dir` (usually a crate's `src`).
+pub fn module_graph(dir: &Path) -> Result<ModuleGraph> {
+    let outlines = outline_directory(dir)?;
+    let modules: BTreeSet<String> = outlines.iter().map(|o| module_path_for(&o.path)).collect();
+    let mut edges = BTreeSet::new();
+    for outline in &outlines {
+        let current = module_path_for(&outline.path);
+        let full_path = dir.join(&outline.path);
+        let source = fs::read_to_string(&full_path).with_context(|| format!("Failed to read {}", full_path.display()))?;
+        for import in extract_use_paths(&full_path, &source)? {
+            if let Some(target) = resolve_import(&current, &import, &modules) {
+                edges.insert((current.clone(), target));
+            }
+        }
+    }
+    Ok(ModuleGraph {
+        modules: modules.into_iter().collect(),
+        edges: edges.into_iter().collect(),
+    })
+}
+
+fn node_id(module: &str) -> String {
+    module.replace("::", "_")
+}
+
+pub fn render_graph_mermaid(graph: &ModuleGraph) -> String {
+    let mut lines = vec!["graph LR".to_string()];
+    lines.extend(graph.modules.iter().map(|m| format!("    {}[\"{}\"]", node_id(m), m)));
+    lines.extend(graph.edges.iter().map(|(from, to)| format!("    {} --> {}", node_id(from), node_id(to))));
+    lines.join("\n")
+}
+
+pub fn render_graph_dot(graph: &ModuleGraph) -> String {
+    let mut lines = vec!["digraph modules {".to_string(), "    rankdir=LR;".to_string(), "    node [shape=box];".to_string()];
+    lines.extend(graph.modules.iter().map(|m| format!("    \"{}\";", m)));
+    lines.extend(graph.edges.iter().map(|(from, to)| format!("    \"{}\" -> \"{}\";", from, to)));
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_graph_resolves_crate_super_and_grouped_imports() {
+        assert_eq!(
+            expand_use_tree("crate::{a::B, c::{D, E as F}, self}"),
+            vec!["crate::a::B", "crate::c::D", "crate::c::E", "crate"]
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("tools")).unwrap();
+        std::fs::write(dir.path().join("lib.rs"), "pub mod config;\npub mod tools;\n").unwrap();
+        std::fs::write(dir.path().join("config.rs"), "use std::path::PathBuf;\npub struct Config;\n").unwrap();
+        std::fs::write(dir.path().join("tools/mod.rs"), "pub mod registry;\nuse crate::config::Config;\n").unwrap();
+        std::fs::write(
+            dir.path().join("tools/registry.rs"),
+            "use super::super::config::Config;\nuse serde::Serialize;\nuse crate::tools::{self};\n",
+        )
+        .unwrap();
+
+        let graph = module_graph(dir.path()).unwrap();
+        assert_eq!(graph.modules, vec!["config", "crate", "tools", "tools::registry"]);
+        assert_eq!(
+            graph.edges,
+            vec![
+                ("tools".to_string(), "config".to_string()),
+                ("tools::registry".to_string(), "config".to_string()),
+                ("tools::registry".to_string(), "tools".to_string()),
+            ]
+        );
+        assert!(render_graph_dot(&graph).contains("\"tools::registry\" -> \"config\";"));
+    }
+}
@@ -1,3 +1,7 @@
+pub mod dependencies;
+pub mod outline;
+pub mod type_context;
+
 use anyhow::{anyhow, Context, Result};
 use std::fs;
 use std::path::Path;
@@ -18,7 +22,7 @@ pub fn find_symbol_context(file_path: &str, symbol_name: &str) -> Result<String>
     let mut parser = Parser::new();
     parser
         .set_language(&language)
-        .with_context(|| format!("Failed to set language for parser"))?;
+        .context("Failed to set language for parser")?;
 
     let tree = parser
         .parse(&source_code, None)
@@ -58,4 +62,73 @@ pub fn find_symbol_context(file_path: &str, symbol_name: &str) -> Result<String>
         symbol_name,
         file_path
     ))
-}
\ No newline at end of file
+}
+
+// 1-based, inclusive line range of the first function, struct, enum, trait or impl named
+// `symbol_name`, widened upwards to take in its doc comments and attributes.
+pub fn find_symbol_lines(path: &Path, source_code: &str, symbol_name: &str) -> Result<Option<(usize, usize)>> {
+    let language = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("rs") => tree_sitter_rust::language(),
+        _ => return Err(anyhow!("Symbol lookup is not supported for {}", path.display())),
+    };
+
+    let mut parser = Parser::new();
+    parser
+        .set_language(&language)
+        .context("Failed to set language for parser")?;
+    let tree = parser
+        .parse(source_code, None)
+        .ok_or_else(|| anyhow!("Failed to parse file: {}", path.display()))?;
+
+    let query = Query::new(
+        &language,
+        r#"
+        (function_item name: (identifier) @name) @definition
+        (struct_item name: (type_identifier) @name) @definition
+        (enum_item name: (type_identifier) @name) @definition
+        (trait_item name: (type_identifier) @name) @definition
+        (impl_item type: (type_identifier) @name) @definition
+        "#,
+    )
+    .context("Failed to create symbol query")?;
+    let name_index = query.capture_index_for_name("name");
+    let definition_index = query.capture_index_for_name("definition");
+
+    let mut query_cursor = QueryCursor::new();
+    for match_result in query_cursor.matches(&query, tree.root_node(), source_code.as_bytes()) {
+        let capture = |index| match_result.captures.iter().find(|c| Some(c.index) == index).map(|c| c.node);
+        let (Some(name), Some(definition)) = (capture(name_index), capture(definition_index)) else {
+            continue;
+        };
+        if name.utf8_text(source_code.as_bytes()).ok() != Some(symbol_name) {
+            continue;
+        }
+        let lines: Vec<&str> = source_code.lines().collect();
+        let mut start = definition.start_position().row;
+        while start > 0 {
+            let previous = lines[start - 1].trim_start();
+            if previous.starts_with("///") || previous.starts_with("#[") || previous.starts_with("//") {
+                start -= 1;
+            } else {
+                break;
+            }
+        }
+        return Ok(Some((start + 1, definition.end_position().row + 1)));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_symbol_lines_includes_docs_and_attributes() {
+        let source = "use std::fmt;\n\n/// A point.\n#[derive(Debug)]\nstruct Point {\n    x: i32,\n}\n\nimpl Point {\n    fn norm(&self) -> i32 {\n        self.x\n    }\n}\n";
+        let path = Path::new("point.rs");
+        assert_eq!(find_symbol_lines(path, source, "Point").unwrap(), Some((3, 7)));
+        assert_eq!(find_symbol_lines(path, source, "norm").unwrap(), Some((10, 12)));
+        assert_eq!(find_symbol_lines(path, source, "missing").unwrap(), None);
+        assert!(find_symbol_lines(Path::new("point.py"), source, "Point").is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::cargo_workspace::is_build_output;
+use crate::tools::code_intelligence::{parse_definitions, CodeDefinition};
+
+const SUPPORTED_EXTENSIONS: &[&str] = &["rs"];
+const MAX_DEFINITIONS_PER_FILE: usize = 12;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileOutline {
+    // Path relative to the outlined directory.
+    pub path: PathBuf,
+    pub definitions: Vec<CodeDefinition>,
+}
+
+// Lists the definitions of every supported source file under `dir`, skipping hidden
+// entries and `target`.
+pub fn outline_directory(dir: &Path) -> Result<Vec<FileOutline>> {
+    let mut outlines = Vec::new();
+    let walker = WalkDir::new(dir).sort_by_file_name().into_iter().filter_entry(|entry| {
+        let name = entry.file_name().to_string_lossy();
+        entry.depth() == 0 || !(name.starts_with('.') || is_build_output(entry.path()))
+    });
+    for entry in walker {
+        let entry = entry.with_context(|| format!("Failed to walk {}", dir.display()))?;
+        let path = entry.path();
+        let supported = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| SUPPORTED_EXTENSIONS.contains(&e));
+        if !entry.file_type().is_file() || !supported {
+            continue;
+        }
+        let source = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let definitions = match parse_definitions(path, &source) {
+            Ok(definitions) => definitions,
+            Err(e) => {
+                tracing::warn!("Skipping definitions for {}: {}", path.display(), e);
+                Vec::new()
+            }
+        };
+        outlines.push(FileOutline {
+            path: path.strip_prefix(dir).unwrap_or(path).to_path_buf(),
+            definitions,
+        });
+    }
+    Ok(outlines)
+}
+
+fn summarize_definitions(definitions: &[CodeDefinition]) -> String {
+    let mut names: Vec<String> = definitions
+        .iter()
+        .take(MAX_DEFINITIONS_PER_FILE)
+        .map(|d| match d.r#type.as_str() {
+            "impl" => d.name.clone(),
+            "function" => format!("fn {}", d.name),
+            other => format!("{} {}", other, d.name),
+        })
+        .collect();
+    if definitions.len() > MAX_DEFINITIONS_PER_FILE {
+        names.push(format!("+{} more", definitions.len() - MAX_DEFINITIONS_PER_FILE));
+    }
+    names.join(", ")
+}
+
+#[derive(Default)]
+struct TreeNode {
+    children: BTreeMap<String, TreeNode>,
+    summary: Option<String>,
+}
+
+fn build_tree(outlines: &[FileOutline]) -> TreeNode {
+    let mut root = TreeNode::default();
+    for outline in outlines {
+        let mut node = &mut root;
+        for component in outline.path.iter() {
+            node = node.children.entry(component.to_string_lossy().to_string()).or_default();
+        }
+        node.summary = Some(summarize_definitions(&outline.definitions));
+    }
+    root
+}
+
+fn render_ascii_node(node: &TreeNode, prefix: &str, out: &mut Vec<String>) {
+    let count = node.children.len();
+    for (index, (name, child)) in node.children.iter().enumerate() {
+        let last = index + 1 == count;
+        let branch = if last { "└── " } else { "├── " };
+        let label = match &child.summary {
+            Some(summary) if !summary.is_empty() => format!("{}  ({})", name, summary),
+            Some(_) => name.clone(),
+            None => format!("{}/", name),
+        };
+        out.push(format!("{}{}{}", prefix, branch, label));
+        let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        render_ascii_node(child, &child_prefix, out);
+    }
+}
+
+// Renders the outline as an indented tree, one file per line with its definitions.
+pub fn render_ascii_tree(root_label: &str, outlines: &[FileOutline]) -> String {
+    let mut lines = vec![format!("{}/", root_label.trim_end_matches('/'))];
+    render_ascii_node(&build_tree(outlines), "", &mut lines);
+    lines.join("\n")
+}
+
+fn mermaid_label(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+fn render_mermaid_node(node: &TreeNode, id: &str, next_id: &mut usize, out: &mut Vec<String>) {
+    for (name, child) in &node.children {
+        *next_id += 1;
+        let child_id = format!("n{}", next_id);
+        let label = match &child.summary {
+            Some(summary) if !summary.is_empty() => format!("{}<br/>{}", name, summary.replace(", ", "<br/>")),
+            Some(_) => name.clone(),
+            None => format!("{}/", name),
+        };
+        out.push(format!("    {}[\"{}\"]", child_id, mermaid_label(&label)));
+        out.push(format!("    {} --> {}", id, child_id));
+        render_mermaid_node(child, &child_id, next_id, out);
+    }
+}
+
+// Renders the outline as a Mermaid flowchart of directories and files.
+pub fn render_mermaid(root_label: &str, outlines: &[FileOutline]) -> String {
+    let mut lines = vec![
+        "graph TD".to_string(),
+        format!("    n0[\"{}/\"]", mermaid_label(root_label.trim_end_matches('/'))),
+    ];
+    let mut next_id = 0;
+    render_mermaid_node(&build_tree(outlines), "n0", &mut next_id, &mut lines);
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outline_directory_renders_tree_and_mermaid() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("nested")).unwrap();
+        std::fs::write(dir.path().join("lib.rs"), "pub struct Engine;\nimpl Engine { fn run(&self) {} }\n").unwrap();
+        std::fs::write(dir.path().join("nested/util.rs"), "pub fn helper() {}\n").unwrap();
+        std::fs::write(dir.path().join("README.md"), "# not code").unwrap();
+
+        let outlines = outline_directory(dir.path()).unwrap();
+        assert_eq!(outlines.len(), 2);
+
+        let tree = render_ascii_tree("src", &outlines);
+        assert_eq!(
+            tree,
+            "src/\n├── lib.rs  (struct Engine, impl Engine, fn run)\n└── nested/\n    └── util.rs  (fn helper)"
+        );
+
+        let mermaid = render_mermaid("src", &outlines);
+        assert!(mermaid.starts_with("graph TD\n    n0[\"src/\"]"));
+        assert!(mermaid.contains("n0 --> n1"));
+        assert!(mermaid.contains("util.rs<br/>fn helper"));
+    }
+}
@@ -0,0 +1,180 @@
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tiktoken_rs::cl100k_base;
+use tree_sitter::{Parser, Query, QueryCursor};
+use walkdir::WalkDir;
+
+use crate::cargo_workspace::is_build_output;
+
+const TYPE_DEFINITION_QUERY: &str = r#"
+    (struct_item name: (type_identifier) @name) @definition
+    (enum_item name: (type_identifier) @name) @definition
+    (union_item name: (type_identifier) @name) @definition
+    (trait_item name: (type_identifier) @name) @definition
+    (type_item name: (type_identifier) @name) @definition
+"#;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeDefinition {
+    pub name: String,
+    pub path: PathBuf,
+    pub code: String,
+}
+
+fn rust_parser() -> Result<Parser> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&tree_sitter_rust::language())
+        .context("Failed to set language for parser")?;
+    Ok(parser)
+}
+
+// The type names a Rust snippet refers to, in order of first use.
+pub fn referenced_types(code: &str) -> Result<Vec<String>> {
+    let tree = rust_parser()?.parse(code, None).ok_or_else(|| anyhow!("Failed to parse snippet"))?;
+    let mut names: Vec<String> = Vec::new();
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        if node.kind() == "type_identifier" {
+            let name = node.utf8_text(code.as_bytes())?.to_string();
+            if name != "Self" && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        let mut cursor = node.walk();
+        let children: Vec<_> = node.children(&mut cursor).collect();
+        stack.extend(children.into_iter().rev());
+    }
+    Ok(names)
+}
+
+// The first struct, enum, union, trait or type alias definition of each of `names` in the
+// Rust files under `root`, with its doc comments and attributes. Hidden entries and `target`
+// are skipped.
+pub fn find_type_definitions(root: &Path, names: &[String]) -> Result<Vec<TypeDefinition>> {
+    let language = tree_sitter_rust::language();
+    let query = Query::new(&language, TYPE_DEFINITION_QUERY).context("Failed to create type definition query")?;
+    let name_index = query.capture_index_for_name("name");
+    let definition_index = query.capture_index_for_name("definition");
+    let mut parser = rust_parser()?;
+    let mut found: HashMap<String, TypeDefinition> = HashMap::new();
+
+    let walker = WalkDir::new(root).sort_by_file_name().into_iter().filter_entry(|entry| {
+        let name = entry.file_name().to_string_lossy();
+        entry.depth() == 0 || !(name.starts_with('.') || is_build_output(entry.path()))
+    });
+    for entry in walker.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if found.len() == names.len() {
+            break;
+        }
+        if !entry.file_type().is_file() || path.extension().and_then(|e| e.to_str()) != Some("rs") {
+            continue;
+        }
+        let Ok(source) = fs::read_to_string(path) else {
+            continue;
+        };
+        if !names.iter().any(|name| !found.contains_key(name) && source.contains(name.as_str())) {
+            continue;
+        }
+        let Some(tree) = parser.parse(&source, None) else {
+            continue;
+        };
+        let lines: Vec<&str> = source.lines().collect();
+        let mut query_cursor = QueryCursor::new();
+        for match_result in query_cursor.matches(&query, tree.root_node(), source.as_bytes()) {
+            let capture = |index| match_result.captures.iter().find(|c| Some(c.index) == index).map(|c| c.node);
+            let (Some(name), Some(definition)) = (capture(name_index), capture(definition_index)) else {
+                continue;
+            };
+            let name = name.utf8_text(source.as_bytes())?.to_string();
+            if !names.contains(&name) || found.contains_key(&name) {
+                continue;
+            }
+            let mut start = definition.start_position().row;
+            while start > 0 && ["///", "#[", "//"].iter().any(|p| lines[start - 1].trim_start().starts_with(p)) {
+                start -= 1;
+            }
+            let code = lines[start..=definition.end_position().row.min(lines.len() - 1)].join("\n");
+            let path = path.strip_prefix(root).unwrap_or(path).to_path_buf();
+            found.insert(name.clone(), TypeDefinition { name, path, code });
+        }
+    }
+    Ok(names.iter().filter_map(|name| found.remove(name)).collect())
+}
+
+// Definitions of the types `code` refers to that are not already part of it, in order of
+// first use, for as many as fit in `budget` tokens.
+pub fn type_context(root: &Path, code: &str, budget: usize) -> Result<Vec<TypeDefinition>> {
+    if budget == 0 {
+        return Ok(Vec::new());
+    }
+    let tokenizer = cl100k_base().map_err(|e| anyhow!("Failed to load tokenizer: {}", e))?;
+    let mut remaining = budget;
+    let mut included = Vec::new();
+    for definition in find_type_definitions(root, &referenced_types(code)?)? {
+        if code.contains(&definition.code) {
+            continue;
+        }
+        let tokens = tokenizer.encode_with_special_tokens(&definition.code).len();
+        if tokens > remaining {
+            continue;
+        }
+        remaining -= tokens;
+        included.push(definition);
+    }
+    Ok(included)
+}
+
+// A prompt section with the definitions of the types a Rust file or snippet uses. Best
+// effort: lookup problems are logged and other languages get nothing.
+pub fn type_context_for(file: &Path, code: &str, budget: usize) -> Option<String> {
+    if file.extension().and_then(|e| e.to_str()) != Some("rs") {
+        return None;
+    }
+    let root = std::env::current_dir().ok()?;
+    let definitions = match type_context(&root, code, budget) {
+        Ok(definitions) => definitions,
+        Err(e) => {
+            tracing::warn!("Failed to collect referenced type definitions: {}", e);
+            return None;
+        }
+    };
+    if definitions.is_empty() {
+        return None;
+    }
+    tracing::debug!("Including {} referenced type definition(s)", definitions.len());
+    let blocks: Vec<String> = definitions
+        .iter()
+        .map(|d| format!("// {}\n```rust\n{}\n```", d.path.display(), d.code))
+        .collect();
+    Some(format!("Definitions of types used by this code:\n\n{}", blocks.join("\n\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_type_context_includes_referenced_definitions() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(
+            dir.path().join("src/model.rs"),
+            "/// A user account.\n#[derive(Debug)]\npub struct User {\n    pub name: String,\n}\n\npub enum Role { Admin, Guest }\n\npub struct Unused;\n",
+        )
+        .unwrap();
+        let code = "fn greet(user: &User, role: Role) -> String {\n    format!(\"hi {}\", user.name)\n}";
+
+        assert_eq!(referenced_types(code).unwrap(), vec!["User", "Role", "String"]);
+        let definitions = type_context(dir.path(), code, 1_000).unwrap();
+        let names: Vec<&str> = definitions.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["User", "Role"]);
+        assert!(definitions[0].code.starts_with("/// A user account.\n#[derive(Debug)]"));
+        assert_eq!(definitions[0].path, Path::new("src/model.rs"));
+        assert!(type_context(dir.path(), code, 5).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,235 @@
+use anyhow::{Context, Result};
+use std::fmt;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use tokio::sync::watch;
+
+use crate::config::global_config_dir;
+use crate::file_lock::{with_lock, write_atomically};
+use crate::tools::snapshot::SnapshotStore;
+
+const RECOVERY_FILE: &str = "interrupted-run.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownSignal {
+    Interrupt,
+    Terminate,
+}
+
+impl ShutdownSignal {
+    // Conventional 128 + signal number, as a shell reports it.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ShutdownSignal::Interrupt => 130,
+            ShutdownSignal::Terminate => 143,
+        }
+    }
+}
+
+impl fmt::Display for ShutdownSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShutdownSignal::Interrupt => write!(f, "SIGINT"),
+            ShutdownSignal::Terminate => write!(f, "SIGTERM"),
+        }
+    }
+}
+
+type Cleanup = Box<dyn FnOnce() + Send>;
+
+// Turns SIGINT/SIGTERM into a cancellation the running command can be raced against, so
+// the app gets to clean up instead of dying mid-write.
+pub struct ShutdownCoordinator {
+    sender: watch::Sender<Option<ShutdownSignal>>,
+    // Work that must be undone if the command is cut short, e.g. a temporary worktree.
+    cleanups: Mutex<Vec<(u64, Cleanup)>>,
+    next_cleanup: AtomicU64,
+}
+
+impl fmt::Debug for ShutdownCoordinator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShutdownCoordinator").field("received", &self.received()).finish_non_exhaustive()
+    }
+}
+
+impl Default for ShutdownCoordinator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShutdownCoordinator {
+    pub fn new() -> Self {
+        ShutdownCoordinator { sender: watch::Sender::new(None), cleanups: Mutex::new(Vec::new()), next_cleanup: AtomicU64::new(0) }
+    }
+
+    pub fn global() -> &'static ShutdownCoordinator {
+        static GLOBAL: OnceLock<ShutdownCoordinator> = OnceLock::new();
+        GLOBAL.get_or_init(ShutdownCoordinator::new)
+    }
+
+    // Only the first signal is kept; it decides the exit code.
+    pub fn trigger(&self, signal: ShutdownSignal) {
+        self.sender.send_if_modified(|current| {
+            if current.is_some() {
+                return false;
+            }
+            *current = Some(signal);
+            true
+        });
+    }
+
+    // Runs `cleanup` if the command is interrupted; `unregister_cleanup` with the returned id
+    // once the command has cleaned up by itself.
+    pub fn register_cleanup(&self, cleanup: impl FnOnce() + Send + 'static) -> u64 {
+        let id = self.next_cleanup.fetch_add(1, Ordering::Relaxed);
+        self.cleanups.lock().unwrap().push((id, Box::new(cleanup)));
+        id
+    }
+
+    pub fn unregister_cleanup(&self, id: u64) {
+        self.cleanups.lock().unwrap().retain(|(registered, _)| *registered != id);
+    }
+
+    // Newest first, like unwinding.
+    pub fn run_cleanups(&self) {
+        let cleanups = std::mem::take(&mut *self.cleanups.lock().unwrap());
+        for (_, cleanup) in cleanups.into_iter().rev() {
+            cleanup();
+        }
+    }
+
+    pub fn received(&self) -> Option<ShutdownSignal> {
+        *self.sender.borrow()
+    }
+
+    pub async fn wait(&self) -> ShutdownSignal {
+        let mut receiver = self.sender.subscribe();
+        loop {
+            if let Some(signal) = *receiver.borrow_and_update() {
+                return signal;
+            }
+            if receiver.changed().await.is_err() {
+                return std::future::pending().await;
+            }
+        }
+    }
+
+    // A second signal exits immediately, for when the command is stuck in blocking code
+    // and never gets to observe the first one.
+    pub fn listen_for_signals(&'static self) -> Result<()> {
+        #[cfg(unix)]
+        let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .context("Failed to install SIGTERM handler")?;
+        tokio::spawn(async move {
+            loop {
+                #[cfg(unix)]
+                let signal = tokio::select! {
+                    _ = tokio::signal::ctrl_c() => ShutdownSignal::Interrupt,
+                    _ = terminate.recv() => ShutdownSignal::Terminate,
+                };
+                #[cfg(not(unix))]
+                let signal = match tokio::signal::ctrl_c().await {
+                    Ok(()) => ShutdownSignal::Interrupt,
+                    Err(_) => return,
+                };
+                if let Some(first) = self.received() {
+                    tracing::warn!("Received {} during shutdown, exiting immediately.", signal);
+                    restore_terminal();
+                    std::process::exit(first.exit_code());
+                }
+                tracing::info!("Received {}, shutting down.", signal);
+                self.trigger(signal);
+            }
+        });
+        Ok(())
+    }
+
+    // Dropping `task` on a signal cancels any in-flight API stream, and tool processes
+    // spawned with `kill_on_drop` are killed along with it.
+    pub async fn run_until_signal<F: Future>(&self, task: F) -> Result<F::Output, ShutdownSignal> {
+        tokio::select! {
+            output = task => Ok(output),
+            signal = self.wait() => Err(signal),
+        }
+    }
+}
+
+// Undoes anything a spinner or raw-mode prompt left behind when it was cut short.
+pub fn restore_terminal() {
+    let _ = crossterm::terminal::disable_raw_mode();
+    let _ = crossterm::execute!(std::io::stdout(), crossterm::cursor::Show);
+}
+
+// Writes the pre-change content of every file the interrupted run touched, so partial
+// edits can be reverted by hand. Returns None when nothing was modified.
+pub fn save_interrupted_snapshots(snapshots: &SnapshotStore) -> Result<Option<PathBuf>> {
+    let dir = global_config_dir().context("Could not determine the config directory")?;
+    save_snapshots_to(snapshots, &dir)
+}
+
+fn save_snapshots_to(snapshots: &SnapshotStore, dir: &Path) -> Result<Option<PathBuf>> {
+    if snapshots.diffstat().is_empty() {
+        return Ok(None);
+    }
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    let path = dir.join(RECOVERY_FILE);
+    let content = serde_json::to_string_pretty(&snapshots.snapshots())?;
+    with_lock(&path, || write_atomically(&path, content))?;
+    Ok(Some(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_signal_cancels_running_task() {
+        let coordinator = ShutdownCoordinator::new();
+        let task = async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            "finished"
+        };
+        let (result, _) = tokio::join!(coordinator.run_until_signal(task), async {
+            coordinator.trigger(ShutdownSignal::Terminate);
+            coordinator.trigger(ShutdownSignal::Interrupt);
+        });
+        assert_eq!(result, Err(ShutdownSignal::Terminate));
+        assert_eq!(coordinator.received().map(ShutdownSignal::exit_code), Some(143));
+    }
+
+    #[tokio::test]
+    async fn test_task_finishing_first_wins() {
+        let coordinator = ShutdownCoordinator::new();
+        assert_eq!(coordinator.run_until_signal(async { 7 }).await, Ok(7));
+        assert_eq!(coordinator.received(), None);
+
+        let ran = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let (first, second) = (ran.clone(), ran.clone());
+        coordinator.register_cleanup(move || first.lock().unwrap().push("first"));
+        let id = coordinator.register_cleanup(move || second.lock().unwrap().push("second"));
+        coordinator.unregister_cleanup(id);
+        coordinator.run_cleanups();
+        coordinator.run_cleanups();
+        assert_eq!(*ran.lock().unwrap(), vec!["first"]);
+    }
+
+    #[test]
+    fn test_interrupted_snapshots_are_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let edited = dir.path().join("edited.txt");
+        std::fs::write(&edited, "before\n").unwrap();
+        let store = SnapshotStore::new();
+        let out_dir = dir.path().join("state");
+        assert_eq!(save_snapshots_to(&store, &out_dir).unwrap(), None);
+
+        store.record_before_change(&edited);
+        std::fs::write(&edited, "after\n").unwrap();
+        let path = save_snapshots_to(&store, &out_dir).unwrap().unwrap();
+        let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(saved[0]["original"], "before\n");
+    }
+}
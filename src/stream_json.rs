@@ -0,0 +1,122 @@
+use serde_json::Value;
+use std::io::Write;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+
+use crate::api::models::ChatCompletionResponse;
+use crate::events::{self, EventBus, UiEvent};
+use crate::streaming::{tee, tee_end};
+
+// `--output stream-json`: every event on the bus becomes one JSON object per line on stdout,
+// so editor plugins can render progress themselves. The terminal renderer is switched off
+// for the rest of the process while it runs.
+pub struct StreamJsonWriter {
+    task: JoinHandle<()>,
+}
+
+impl StreamJsonWriter {
+    pub fn start(bus: &EventBus, mut out: impl Write + Send + 'static) -> Self {
+        bus.set_terminal_output(false);
+        let mut receiver = bus.subscribe();
+        let task = tokio::spawn(async move {
+            loop {
+                let event = match receiver.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("stream-json output dropped {} events", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                let done = matches!(event, UiEvent::Done { .. });
+                let line = stream_json_event(&event).to_string();
+                if writeln!(out, "{}", line).and_then(|_| out.flush()).is_err() {
+                    break;
+                }
+                if done {
+                    break;
+                }
+            }
+        });
+        StreamJsonWriter { task }
+    }
+
+    // Emits the closing `done` event and waits until everything before it has been written.
+    pub async fn finish(self, bus: &EventBus, error: Option<String>) {
+        bus.emit(UiEvent::Done { success: error.is_none(), error });
+        if let Err(e) = self.task.await {
+            tracing::warn!("stream-json writer failed: {}", e);
+        }
+    }
+}
+
+// Tool events use the names editor integrations expect; everything else keeps its bus name.
+pub fn stream_json_event(event: &UiEvent) -> Value {
+    let mut value = serde_json::to_value(event).unwrap_or(Value::Null);
+    let renamed = match event {
+        UiEvent::ToolStarted { .. } => "tool_call",
+        UiEvent::ToolFinished { .. } => "tool_result",
+        _ => return value,
+    };
+    value["type"] = Value::from(renamed);
+    value
+}
+
+// Reports a (non-streamed) model response as message_start, content_delta and usage events.
+pub fn emit_response(model: &str, response: &ChatCompletionResponse) {
+    events::emit(UiEvent::MessageStart { model: model.to_string() });
+    let content = response.choices.first().and_then(|c| c.message.content.clone()).unwrap_or_default();
+    if !content.is_empty() {
+        tee(&content);
+        tee_end(&content);
+        events::emit(UiEvent::ContentDelta { content });
+    }
+    if let Some(usage) = &response.usage {
+        events::emit(UiEvent::Usage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_writes_one_event_per_line_until_done() {
+        let bus = EventBus::new();
+        let buffer = SharedBuffer::default();
+        let writer = StreamJsonWriter::start(&bus, buffer.clone());
+
+        bus.emit(UiEvent::MessageStart { model: "m".to_string() });
+        bus.emit(UiEvent::ToolStarted { tool: "GitTool".to_string(), arguments: json!({}) });
+        bus.emit(UiEvent::ToolFinished { tool: "GitTool".to_string(), success: true, output: json!("ok") });
+        writer.finish(&bus, None).await;
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let types: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["type"].clone())
+            .collect();
+        assert_eq!(types, vec![json!("message_start"), json!("tool_call"), json!("tool_result"), json!("done")]);
+        assert!(output.ends_with("{\"error\":null,\"success\":true,\"type\":\"done\"}\n"));
+    }
+}
@@ -1,17 +1,51 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use futures_util::stream::Stream;
 use futures_util::StreamExt;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
 use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 use iocraft::prelude::*;
 
 use crate::api::models::ChatCompletionChunk;
+use crate::events::{self, EventBus, UiEvent};
 use crate::tui::StreamingOutput;
 
+// `--tee <file>`: assistant text is appended to this file chunk by chunk as it arrives, so a
+// long generation survives terminal scrollback and interruptions.
+static TEE: Mutex<Option<File>> = Mutex::new(None);
+
+// Creates (or truncates) `path` and starts copying responses into it.
+pub fn tee_to(path: &Path) -> Result<()> {
+    let file = File::create(path).with_context(|| format!("Failed to create tee file {}", path.display()))?;
+    *TEE.lock().unwrap() = Some(file);
+    Ok(())
+}
+
+// Writes and flushes `text` to the tee file, if any. A failing file is dropped with a warning
+// rather than interrupting the response.
+pub fn tee(text: &str) {
+    let mut guard = TEE.lock().unwrap();
+    let Some(file) = guard.as_mut() else { return };
+    if let Err(e) = file.write_all(text.as_bytes()).and_then(|_| file.flush()) {
+        tracing::warn!("Stopped writing to the tee file: {}", e);
+        *guard = None;
+    }
+}
+
+// Separates one response from the next in the tee file.
+pub fn tee_end(content: &str) {
+    tee(if content.ends_with('\n') { "\n" } else { "\n\n" });
+}
+
 pub async fn handle_streamed_response(
     mut stream: Pin<Box<dyn Stream<Item = Result<ChatCompletionChunk>> + Send>>,
-) -> Result<()> {
+) -> Result<String> {
+    if !EventBus::global().terminal_output() {
+        return emit_streamed_response(stream).await;
+    }
     let (tx, rx) = mpsc::unbounded_channel::<Result<String, String>>();
 
     let stream_processor = tokio::spawn(async move {
@@ -26,6 +60,7 @@ pub async fn handle_streamed_response(
                         }
                     }
                     if !chunk_text.is_empty() {
+                        tee(&chunk_text);
                         accumulated_content.push_str(&chunk_text);
                         if tx.send(Ok(chunk_text)).is_err() {
                             tracing::warn!("Stream receiver dropped, stopping stream processing.");
@@ -40,6 +75,7 @@ pub async fn handle_streamed_response(
                 }
             }
         }
+        tee_end(&accumulated_content);
         Ok(accumulated_content)
     });
 
@@ -51,8 +87,8 @@ pub async fn handle_streamed_response(
         .map_err(|e| anyhow::anyhow!("iocraft render loop failed: {}", e))?;
 
     match stream_processor.await {
-        Ok(Ok(_content)) => {
-            Ok(())
+        Ok(Ok(content)) => {
+            Ok(content)
         }
         Ok(Err(e)) => {
             Err(e)
@@ -63,11 +99,41 @@ pub async fn handle_streamed_response(
     }
 }
 
+// The whole text of a response, with nothing shown or teed while it arrives; for callers that
+// check a reply before deciding whether to show it.
+pub async fn collect_streamed_response(
+    mut stream: Pin<Box<dyn Stream<Item = Result<ChatCompletionChunk>> + Send>>,
+) -> Result<String> {
+    let mut accumulated_content = String::new();
+    while let Some(chunk) = stream.next().await {
+        accumulated_content.extend(chunk?.choices.into_iter().filter_map(|choice| choice.delta.content));
+    }
+    Ok(accumulated_content)
+}
+
+// With the terminal renderer off (stream-json output, the editor bridge) each chunk goes to the
+// event bus as a content delta instead.
+async fn emit_streamed_response(
+    mut stream: Pin<Box<dyn Stream<Item = Result<ChatCompletionChunk>> + Send>>,
+) -> Result<String> {
+    let mut accumulated_content = String::new();
+    while let Some(chunk) = stream.next().await {
+        let content: String = chunk?.choices.into_iter().filter_map(|choice| choice.delta.content).collect();
+        if !content.is_empty() {
+            tee(&content);
+            accumulated_content.push_str(&content);
+            events::emit(UiEvent::ContentDelta { content });
+        }
+    }
+    tee_end(&accumulated_content);
+    Ok(accumulated_content)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use futures_util::stream;
-    use crate::api::models::{ChatCompletionChunk, ChoiceDelta, Choice};
+    use crate::api::models::{ChatCompletionChunk, ChunkChoice, Delta};
     use std::pin::Pin;
     use std::time::Duration;
 
@@ -75,14 +141,16 @@ mod tests {
     async fn test_handle_streamed_response_sends_data() {
         let chunk1 = ChatCompletionChunk {
             id: "1".to_string(), object: "chunk".to_string(), created: 0, model: "test".to_string(),
-            choices: vec![Choice { index: 0, delta: ChoiceDelta { content: Some("Hello ".to_string()) }, finish_reason: None }],
+            choices: vec![ChunkChoice { index: 0, delta: Delta { role: None, content: Some("Hello ".to_string()), reasoning: None, tool_calls: None }, finish_reason: None }],
+            usage: None,
         };
          let chunk2 = ChatCompletionChunk {
             id: "2".to_string(), object: "chunk".to_string(), created: 1, model: "test".to_string(),
-            choices: vec![Choice { index: 0, delta: ChoiceDelta { content: Some("World!".to_string()) }, finish_reason: None }],
+            choices: vec![ChunkChoice { index: 0, delta: Delta { role: None, content: Some("World!".to_string()), reasoning: None, tool_calls: None }, finish_reason: None }],
+            usage: None,
         };
         let s = stream::iter(vec![Ok(chunk1), Ok(chunk2)]);
-        let stream: Pin<Box<dyn Stream<Item = Result<ChatCompletionChunk>> + Send>> = Box::pin(s);
+        let mut stream: Pin<Box<dyn Stream<Item = Result<ChatCompletionChunk>> + Send>> = Box::pin(s);
 
         let (tx, mut rx) = mpsc::unbounded_channel::<Result<String, String>>();
 
@@ -96,10 +164,8 @@ mod tests {
                                 chunk_text.push_str(&content_text);
                             }
                         }
-                         if !chunk_text.is_empty() {
-                             if tx.send(Ok(chunk_text)).is_err() {
-                                return Err(anyhow::anyhow!("Send failed"));
-                             }
+                         if !chunk_text.is_empty() && tx.send(Ok(chunk_text)).is_err() {
+                            return Err(anyhow::anyhow!("Send failed"));
                          }
                     }
                     Err(e) => {
@@ -123,4 +189,23 @@ mod tests {
         assert!(processor_result.is_ok(), "Processor task timed out");
         assert!(processor_result.unwrap().unwrap().is_ok(), "Processor task failed");
     }
+
+    #[tokio::test]
+    async fn test_streamed_chunks_are_teed_to_file() {
+        let chunk = |content: &str| ChatCompletionChunk {
+            id: "1".to_string(), object: "chunk".to_string(), created: 0, model: "test".to_string(),
+            choices: vec![ChunkChoice { index: 0, delta: Delta { role: None, content: Some(content.to_string()), reasoning: None, tool_calls: None }, finish_reason: None }],
+            usage: None,
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.md");
+        tee_to(&path).unwrap();
+
+        let s = stream::iter(vec![Ok(chunk("# Title")), Ok(chunk("\nBody"))]);
+        let content = emit_streamed_response(Box::pin(s)).await.unwrap();
+        *TEE.lock().unwrap() = None;
+
+        assert_eq!(content, "# Title\nBody");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "# Title\nBody\n\n");
+    }
 }
\ No newline at end of file
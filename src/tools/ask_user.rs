@@ -0,0 +1,114 @@
+use crate::tools::{CliTool, ToolError};
+use crate::tui::{print_info, prompt_confirmation, prompt_select, prompt_text};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::io::IsTerminal;
+
+const OTHER_ANSWER: &str = "Something else (type an answer)";
+
+// Lets the agent stop and ask the user a question mid-run. Without a terminal to ask on
+// it fails, so the model falls back to its own judgement instead of hanging.
+#[derive(Debug)]
+pub struct AskUserTool;
+
+impl AskUserTool {
+    fn ask(question: &str, options: &[String]) -> Result<String> {
+        print_info(&format!("\nThe assistant has a question:\n{}", question));
+        if options.is_empty() {
+            return prompt_text("Your answer", "");
+        }
+        let mut items = options.to_vec();
+        items.push(OTHER_ANSWER.to_string());
+        let choice = prompt_select("Choose an answer", &items)?;
+        match options.get(choice) {
+            Some(option) => Ok(option.clone()),
+            None => prompt_text("Your answer", ""),
+        }
+    }
+}
+
+// Yes/no gate for tools whose action needs the user's explicit approval. Declining, or having
+// no terminal to ask on, fails the call with PermissionDenied.
+pub async fn confirm(prompt: String) -> Result<(), ToolError> {
+    if !std::io::stdin().is_terminal() {
+        return Err(ToolError::PermissionDenied {
+            resource: format!("{} (no interactive terminal to confirm on)", prompt),
+        });
+    }
+    let approved = tokio::task::spawn_blocking({
+        let prompt = prompt.clone();
+        move || prompt_confirmation(&prompt)
+    })
+    .await
+    .map_err(|e| ToolError::Other { message: format!("Prompt task failed: {}", e) })?
+    .map_err(|e| ToolError::Other { message: format!("Failed to read confirmation: {}", e) })?;
+    if !approved {
+        return Err(ToolError::PermissionDenied { resource: format!("{} (declined by user)", prompt) });
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl CliTool for AskUserTool {
+    fn name(&self) -> String {
+        "AskUserTool".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Asks the user a clarifying question and waits for the answer. Use it when the task is ambiguous and guessing wrong would be costly. Args: {\"question\": string, \"options\": [string] (optional multiple-choice answers)}".to_string()
+    }
+
+    fn parameters_schema(&self) -> Result<Value> {
+        Ok(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "question": { "type": "string" },
+                "options": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Suggested answers; the user can still type their own."
+                }
+            },
+            "required": ["question"]
+        }))
+    }
+
+    async fn execute(&self, args: Value) -> Result<Value, ToolError> {
+        let question = args
+            .get("question")
+            .and_then(Value::as_str)
+            .filter(|q| !q.trim().is_empty())
+            .ok_or_else(|| ToolError::InvalidArguments {
+                tool_name: self.name(),
+                details: "Missing or empty 'question' argument".to_string(),
+            })?
+            .to_string();
+        let options: Vec<String> = args
+            .get("options")
+            .and_then(Value::as_array)
+            .map(|options| options.iter().filter_map(Value::as_str).map(str::to_string).collect())
+            .unwrap_or_default();
+        if !std::io::stdin().is_terminal() {
+            return Err(ToolError::Other {
+                message: "No interactive terminal to ask the user on; proceed with your best judgement.".to_string(),
+            });
+        }
+        let answer = tokio::task::spawn_blocking(move || Self::ask(&question, &options))
+            .await
+            .map_err(|e| ToolError::Other { message: format!("Prompt task failed: {}", e) })?
+            .map_err(|e| ToolError::Other { message: format!("Failed to read the user's answer: {}", e) })?;
+        Ok(serde_json::json!({ "answer": answer }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_question_is_required() {
+        let err = AskUserTool.execute(serde_json::json!({ "question": "  " })).await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidArguments { .. }));
+    }
+}
@@ -0,0 +1,245 @@
+use async_trait::async_trait;
+use chromiumoxide::browser::{Browser, BrowserConfig};
+use chromiumoxide::cdp::browser_protocol::page::CaptureScreenshotFormat;
+use chromiumoxide::cdp::js_protocol::runtime::{ConsoleApiCalledType, EventConsoleApiCalled, EventExceptionThrown};
+use chromiumoxide::page::ScreenshotParams;
+use chromiumoxide::Page;
+use futures_util::StreamExt;
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::{rate_limit, CliTool, ToolError};
+
+const DEFAULT_SETTLE_MS: u64 = 500;
+const MAX_TEXT_CHARS: usize = 20_000;
+const DEFAULT_SCREENSHOT: &str = "opencode-screenshot.png";
+const SCREENSHOT_DIR: &str = "opencode-screenshots";
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum BrowserAction {
+    Text,
+    Screenshot,
+    Console,
+}
+
+#[derive(Debug, Deserialize)]
+struct BrowserInput {
+    url: String,
+    action: BrowserAction,
+    selector: Option<String>,
+    path: Option<String>,
+    wait_ms: Option<u64>,
+}
+
+// Drives a headless Chrome so the agent can check the frontend it is editing. Each call
+// launches a fresh browser, loads the page, and reports console errors and uncaught
+// exceptions alongside whatever the action asked for.
+#[derive(Debug)]
+pub struct BrowserTool;
+
+impl BrowserTool {
+    fn failed(e: impl std::fmt::Display) -> ToolError {
+        ToolError::Other { message: format!("Browser error: {}", e) }
+    }
+
+    // Screenshots only ever land in a directory under the system temp dir, so the model's `path`
+    // is a bare file name; anything with a directory part is rejected rather than written
+    // around PathPolicy, the tool policy's write paths and the snapshot store.
+    fn screenshot_path(&self, name: Option<&str>) -> Result<PathBuf, ToolError> {
+        let name = name.unwrap_or(DEFAULT_SCREENSHOT);
+        let mut components = Path::new(name).components();
+        if !matches!((components.next(), components.next()), (Some(Component::Normal(_)), None)) {
+            return Err(ToolError::InvalidArguments {
+                tool_name: self.name(),
+                details: format!("`path` must be a file name such as '{}', not '{}'", DEFAULT_SCREENSHOT, name),
+            });
+        }
+        let dir = std::env::temp_dir().join(SCREENSHOT_DIR);
+        std::fs::create_dir_all(&dir).map_err(Self::failed)?;
+        Ok(dir.join(name))
+    }
+
+    async fn run(&self, input: BrowserInput, screenshot: Option<PathBuf>) -> Result<Value, ToolError> {
+        let config = BrowserConfig::builder().build().map_err(Self::failed)?;
+        let (mut browser, mut handler) = Browser::launch(config).await.map_err(Self::failed)?;
+        let handler_task = tokio::spawn(async move { while handler.next().await.is_some() {} });
+
+        let result = self.visit(&browser, &input, screenshot).await;
+
+        if let Err(e) = browser.close().await {
+            tracing::warn!("Failed to close browser: {}", e);
+        }
+        handler_task.abort();
+        result
+    }
+
+    async fn visit(&self, browser: &Browser, input: &BrowserInput, screenshot: Option<PathBuf>) -> Result<Value, ToolError> {
+        // Listen before navigating so errors raised while the page loads are caught.
+        let page = browser.new_page("about:blank").await.map_err(Self::failed)?;
+        let console = Arc::new(Mutex::new(Vec::new()));
+        let listeners = Self::capture_console(&page, console.clone()).await?;
+
+        page.goto(input.url.as_str()).await.map_err(Self::failed)?;
+        page.wait_for_navigation().await.map_err(Self::failed)?;
+        tokio::time::sleep(Duration::from_millis(input.wait_ms.unwrap_or(DEFAULT_SETTLE_MS))).await;
+
+        let mut result = serde_json::json!({ "url": input.url });
+        match input.action {
+            BrowserAction::Text => {
+                let text = match &input.selector {
+                    Some(selector) => page
+                        .find_element(selector.as_str())
+                        .await
+                        .map_err(Self::failed)?
+                        .inner_text()
+                        .await
+                        .map_err(Self::failed)?
+                        .unwrap_or_default(),
+                    None => page
+                        .evaluate("document.body ? document.body.innerText : ''")
+                        .await
+                        .map_err(Self::failed)?
+                        .into_value()
+                        .map_err(Self::failed)?,
+                };
+                let truncated = text.chars().count() > MAX_TEXT_CHARS;
+                result["text"] = Value::String(text.chars().take(MAX_TEXT_CHARS).collect());
+                result["truncated"] = Value::Bool(truncated);
+            }
+            BrowserAction::Screenshot => {
+                let path = screenshot.ok_or_else(|| Self::failed("no screenshot path was resolved"))?;
+                let params = ScreenshotParams::builder().format(CaptureScreenshotFormat::Png).full_page(true).build();
+                page.save_screenshot(params, &path).await.map_err(Self::failed)?;
+                result["screenshot"] = Value::String(path.display().to_string());
+            }
+            BrowserAction::Console => {}
+        }
+
+        for listener in listeners {
+            listener.abort();
+        }
+        result["console_errors"] = serde_json::json!(*console.lock().unwrap());
+        Ok(result)
+    }
+
+    async fn capture_console(
+        page: &Page,
+        console: Arc<Mutex<Vec<String>>>,
+    ) -> Result<Vec<tokio::task::JoinHandle<()>>, ToolError> {
+        let mut calls = page.event_listener::<EventConsoleApiCalled>().await.map_err(Self::failed)?;
+        let mut exceptions = page.event_listener::<EventExceptionThrown>().await.map_err(Self::failed)?;
+        let calls_console = console.clone();
+        let calls_task = tokio::spawn(async move {
+            while let Some(event) = calls.next().await {
+                if !matches!(event.r#type, ConsoleApiCalledType::Error | ConsoleApiCalledType::Warning) {
+                    continue;
+                }
+                let message = event
+                    .args
+                    .iter()
+                    .map(|arg| match (&arg.value, &arg.description) {
+                        (Some(Value::String(s)), _) => s.clone(),
+                        (Some(value), _) => value.to_string(),
+                        (None, Some(description)) => description.clone(),
+                        (None, None) => String::new(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                calls_console.lock().unwrap().push(format!("console.{:?}: {}", event.r#type, message).to_lowercase());
+            }
+        });
+        let exceptions_task = tokio::spawn(async move {
+            while let Some(event) = exceptions.next().await {
+                let details = &event.exception_details;
+                let description = details
+                    .exception
+                    .as_ref()
+                    .and_then(|e| e.description.clone())
+                    .unwrap_or_else(|| details.text.clone());
+                console.lock().unwrap().push(format!("uncaught exception: {}", description));
+            }
+        });
+        Ok(vec![calls_task, exceptions_task])
+    }
+}
+
+#[async_trait]
+impl CliTool for BrowserTool {
+    fn name(&self) -> String {
+        "BrowserTool".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Opens a URL in headless Chrome to debug a web app. action \"text\" returns the rendered text (of `selector` if given), \"screenshot\" saves a PNG named `path` in a temp directory and returns where, \"console\" only reports console errors; every action also returns console errors and uncaught exceptions. Args: {\"url\": string, \"action\": \"text\" | \"screenshot\" | \"console\", \"selector\": string (optional), \"path\": string (optional), \"wait_ms\": number (optional)}".to_string()
+    }
+
+    fn parameters_schema(&self) -> anyhow::Result<Value> {
+        Ok(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "url": { "type": "string" },
+                "action": { "type": "string", "enum": ["text", "screenshot", "console"] },
+                "selector": {
+                    "type": "string",
+                    "description": "CSS selector to read text from (default: the whole page)."
+                },
+                "path": {
+                    "type": "string",
+                    "description": "File name for the screenshot, saved in a temp directory (default: opencode-screenshot.png)."
+                },
+                "wait_ms": {
+                    "type": "integer",
+                    "description": "How long to let scripts run after the page loads (default: 500)."
+                }
+            },
+            "required": ["url", "action"]
+        }))
+    }
+
+    fn network_target(&self, args: &Value) -> Option<String> {
+        args.get("url").and_then(|v| v.as_str()).and_then(rate_limit::domain_of)
+    }
+
+    async fn execute(&self, args: Value) -> Result<Value, ToolError> {
+        let input: BrowserInput = serde_json::from_value(args).map_err(|e| ToolError::InvalidArguments {
+            tool_name: self.name(),
+            details: format!("Failed to parse arguments: {}", e),
+        })?;
+        let screenshot = match input.action {
+            BrowserAction::Screenshot => Some(self.screenshot_path(input.path.as_deref())?),
+            _ => None,
+        };
+        self.run(input, screenshot).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_invalid_action_is_rejected_before_launch() {
+        let err = BrowserTool
+            .execute(serde_json::json!({ "url": "http://localhost:3000", "action": "click" }))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::InvalidArguments { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_screenshot_path_stays_in_the_temp_dir() {
+        for path in ["../src/main.rs", "/etc/cron.d/job", "src/shot.png"] {
+            let err = BrowserTool
+                .execute(serde_json::json!({ "url": "http://localhost:3000", "action": "screenshot", "path": path }))
+                .await
+                .unwrap_err();
+            assert!(matches!(err, ToolError::InvalidArguments { .. }), "{} was accepted", path);
+        }
+        let path = BrowserTool.screenshot_path(Some("home.png")).unwrap();
+        assert_eq!(path, std::env::temp_dir().join(SCREENSHOT_DIR).join("home.png"));
+    }
+}
@@ -0,0 +1,84 @@
+use crate::tools::ask_user::confirm;
+use crate::tools::session_env;
+use crate::tools::{CliTool, ToolError};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+
+const MAX_CLIPBOARD_CHARS: usize = 50_000;
+
+// Reads the system clipboard so "explain what I copied" works without pasting a long error into
+// the prompt. The clipboard may hold anything, so every read asks the user first.
+#[derive(Debug)]
+pub struct ClipboardReadTool;
+
+// Clipboard readers to try, in order, for the current platform.
+fn clipboard_commands() -> Vec<(&'static str, &'static [&'static str])> {
+    if cfg!(target_os = "macos") {
+        vec![("pbpaste", &[])]
+    } else if cfg!(target_os = "windows") {
+        vec![("powershell", &["-NoProfile", "-Command", "Get-Clipboard -Raw"])]
+    } else {
+        vec![
+            ("wl-paste", &["--no-newline"]),
+            ("xclip", &["-selection", "clipboard", "-out"]),
+            ("xsel", &["--clipboard", "--output"]),
+        ]
+    }
+}
+
+async fn read_clipboard() -> Result<String, ToolError> {
+    let mut failures = Vec::new();
+    for (program, args) in clipboard_commands() {
+        match session_env::command(program).args(args).kill_on_drop(true).output().await {
+            Ok(output) if output.status.success() => return Ok(String::from_utf8_lossy(&output.stdout).to_string()),
+            Ok(output) => failures.push(format!("{}: {}", program, String::from_utf8_lossy(&output.stderr).trim())),
+            Err(e) => failures.push(format!("{}: {}", program, e)),
+        }
+    }
+    Err(ToolError::Other { message: format!("Could not read the clipboard ({})", failures.join("; ")) })
+}
+
+fn truncate(text: &str, max_chars: usize) -> (String, bool) {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => (text[..end].to_string(), true),
+        None => (text.to_string(), false),
+    }
+}
+
+#[async_trait]
+impl CliTool for ClipboardReadTool {
+    fn name(&self) -> String {
+        "ClipboardReadTool".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Reads the text on the user's clipboard, after asking them for permission. Use it when the user refers to something they copied (\"explain my clipboard\", \"what does this error mean\"). Args: {}".to_string()
+    }
+
+    fn parameters_schema(&self) -> Result<Value> {
+        Ok(serde_json::json!({ "type": "object", "properties": {} }))
+    }
+
+    async fn execute(&self, _args: Value) -> Result<Value, ToolError> {
+        confirm("Allow the assistant to read your clipboard?".to_string()).await?;
+        let text = read_clipboard().await?;
+        let (content, truncated) = truncate(&text, MAX_CLIPBOARD_CHARS);
+        Ok(serde_json::json!({
+            "content": content,
+            "chars": text.chars().count(),
+            "truncated": truncated,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_counts_chars() {
+        assert_eq!(truncate("héllo", 10), ("héllo".to_string(), false));
+        assert_eq!(truncate("héllo", 2), ("hé".to_string(), true));
+    }
+}
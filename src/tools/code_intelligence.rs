@@ -13,7 +13,7 @@ pub struct ListCodeDefinitionsInput {
     pub path: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CodeDefinition {
     pub name: String,
     pub r#type: String, // Using r# to allow "type" as a field name
@@ -85,7 +85,7 @@ impl CliTool for ListCodeDefinitionsTool {
     }
 }
 
-fn parse_definitions(path: &Path, source_code: &str) -> Result<Vec<CodeDefinition>> {
+pub fn parse_definitions(path: &Path, source_code: &str) -> Result<Vec<CodeDefinition>> {
     let extension = path.extension().and_then(|ext| ext.to_str());
 
     // TODO: Support more languages
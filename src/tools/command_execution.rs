@@ -1,9 +1,9 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value; // Needed for CliTool trait
-use std::process::Command;
 use std::path::PathBuf;
 
+use super::devcontainer::DevcontainerTarget;
 use super::{CliTool, ToolError}; // Correct trait and error type
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,7 +20,15 @@ pub struct ExecuteCommandOutput {
 }
 
 #[derive(Debug)]
-pub struct ExecuteCommandTool;
+pub struct ExecuteCommandTool {
+    devcontainer: DevcontainerTarget,
+}
+
+impl ExecuteCommandTool {
+    pub fn new(devcontainer: DevcontainerTarget) -> Self {
+        ExecuteCommandTool { devcontainer }
+    }
+}
 
 #[async_trait]
 impl CliTool for ExecuteCommandTool {
@@ -51,6 +59,14 @@ impl CliTool for ExecuteCommandTool {
         }))
     }
 
+    fn examples(&self) -> Vec<Value> {
+        vec![serde_json::json!({ "command": "npm test -- --watch=false", "working_directory": "web" })]
+    }
+
+    fn spawns_commands(&self) -> bool {
+        true
+    }
+
     async fn execute(&self, args: Value) -> Result<Value, ToolError> {
         let input: ExecuteCommandInput = serde_json::from_value(args).map_err(|e| {
             ToolError::InvalidArguments {
@@ -59,15 +75,13 @@ impl CliTool for ExecuteCommandTool {
             }
         })?;
 
-        let (shell, shell_arg) = if cfg!(target_os = "windows") {
+        // Inside a devcontainer the command runs under Linux whatever the host is.
+        let (shell, shell_arg) = if cfg!(target_os = "windows") && self.devcontainer.container().is_none() {
             ("cmd", "/C")
         } else {
             ("sh", "-c")
         };
 
-        let mut command_builder = Command::new(shell);
-        command_builder.arg(shell_arg).arg(&input.command);
-
         let current_dir = match &input.working_directory {
             Some(dir) => PathBuf::from(dir),
             None => std::env::current_dir().map_err(|e| ToolError::Other {
@@ -75,11 +89,14 @@ impl CliTool for ExecuteCommandTool {
             })?,
         };
 
-        command_builder.current_dir(&current_dir);
-
-        let output = command_builder.output().map_err(|e| ToolError::Other {
-            message: format!("Failed to spawn command '{}': {}", input.command, e),
-        })?;
+        // Killed if the call is cancelled, e.g. when the user interrupts the run.
+        let output = self
+            .devcontainer
+            .output(shell, &[shell_arg.to_string(), input.command.clone()], Some(&current_dir))
+            .await
+            .map_err(|e| ToolError::Other {
+                message: format!("Failed to spawn command '{}': {}", input.command, e),
+            })?;
 
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
@@ -0,0 +1,91 @@
+use serde_json::Value;
+
+use crate::api::models::ToolDefinition;
+
+// Keywords that only explain a schema; validation and argument shapes do not depend on them.
+const PROSE_KEYWORDS: &[&str] = &["description", "examples", "default"];
+const SUBSCHEMA_KEYWORDS: &[&str] = &["items", "additionalProperties", "anyOf", "oneOf", "allOf"];
+
+// The first sentence of a description, without the `Args: {...}` summary or examples.
+fn short_description(description: &str) -> String {
+    let description = description.split(" Args:").next().unwrap_or_default();
+    let description = description.split(" Examples:").next().unwrap_or_default().trim();
+    match description.find(". ") {
+        Some(end) => description[..=end].to_string(),
+        None => description.to_string(),
+    }
+}
+
+fn strip_schema(schema: &mut Value) {
+    match schema {
+        Value::Object(map) => {
+            for keyword in PROSE_KEYWORDS {
+                map.remove(*keyword);
+            }
+            if let Some(Value::Object(properties)) = map.get_mut("properties") {
+                properties.values_mut().for_each(strip_schema);
+            }
+            for keyword in SUBSCHEMA_KEYWORDS {
+                if let Some(subschema) = map.get_mut(*keyword) {
+                    strip_schema(subschema);
+                }
+            }
+        }
+        Value::Array(schemas) => schemas.iter_mut().for_each(strip_schema),
+        _ => {}
+    }
+}
+
+// Tool definitions cut down for requests after the first of a run: one-sentence descriptions
+// and schemas without prose. The model has already seen the full versions earlier in the
+// conversation, so only the names and argument shapes need repeating.
+pub fn compact_tool_definitions(definitions: &[ToolDefinition]) -> Vec<ToolDefinition> {
+    definitions
+        .iter()
+        .cloned()
+        .map(|mut definition| {
+            definition.function.description = short_description(&definition.function.description);
+            strip_schema(&mut definition.function.parameters);
+            definition
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::models::FunctionDefinition;
+    use serde_json::json;
+
+    #[test]
+    fn test_compaction_keeps_names_and_shapes() {
+        let definition = ToolDefinition {
+            tool_type: "function".to_string(),
+            function: FunctionDefinition {
+                name: "ProcessTool".to_string(),
+                description: "Manages processes. Lists and starts them. Args: {\"action\": string} Examples: {}".to_string(),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {
+                        "action": { "type": "string", "enum": ["list"], "description": "What to do." },
+                        "description": { "type": "string", "default": "" }
+                    },
+                    "required": ["action"]
+                }),
+            },
+        };
+        let compact = &compact_tool_definitions(&[definition])[0];
+        assert_eq!(compact.function.description, "Manages processes.");
+        assert_eq!(
+            compact.function.parameters,
+            json!({
+                "type": "object",
+                "properties": {
+                    "action": { "type": "string", "enum": ["list"] },
+                    "description": { "type": "string" }
+                },
+                "required": ["action"]
+            })
+        );
+    }
+}
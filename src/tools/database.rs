@@ -0,0 +1,357 @@
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::any::{AnyConnectOptions, AnyRow};
+use sqlx::AnyConnection;
+use sqlx::{Column, ConnectOptions, Connection, Row};
+use std::str::FromStr;
+
+use super::{CliTool, ToolError};
+use crate::config::DatabaseConfig;
+
+const READ_ONLY_KEYWORDS: &[&str] = &["select", "with", "explain", "show", "describe", "desc", "pragma", "values"];
+const WRITE_KEYWORDS: &[&str] = &[
+    "insert", "update", "delete", "merge", "upsert", "replace", "create", "alter", "drop", "truncate", "grant",
+    "revoke", "attach", "detach", "vacuum", "reindex", "copy", "call", "do", "lock", "set",
+];
+const MAX_CELL_CHARS: usize = 200;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+enum OutputFormat {
+    #[default]
+    Json,
+    Markdown,
+}
+
+#[derive(Debug, Deserialize)]
+struct SqlQueryInput {
+    query: String,
+    #[serde(default)]
+    format: OutputFormat,
+    max_rows: Option<usize>,
+}
+
+// Runs SQL against the dev database from `[database]` for schema exploration and debugging.
+// Unless `allow_writes` is set, only single read statements are accepted, the connection is
+// made read-only by the database itself (so a function with side effects cannot write either),
+// and every query runs inside a transaction that is rolled back afterwards.
+#[derive(Debug)]
+pub struct SqlQueryTool {
+    url: Option<String>,
+    allow_writes: bool,
+    max_rows: usize,
+}
+
+impl SqlQueryTool {
+    pub fn new(config: &DatabaseConfig) -> Self {
+        SqlQueryTool {
+            url: config.url.clone().or_else(|| std::env::var("DATABASE_URL").ok()).filter(|u| !u.is_empty()),
+            allow_writes: config.allow_writes,
+            max_rows: config.max_rows,
+        }
+    }
+
+    fn failed(e: impl std::fmt::Display) -> ToolError {
+        ToolError::Other { message: format!("Database error: {}", e) }
+    }
+
+    async fn run(&self, input: SqlQueryInput) -> Result<Value, ToolError> {
+        let url = self.url.as_deref().ok_or_else(|| ToolError::Other {
+            message: "No database configured. Set [database] url in .OpenCode.toml or DATABASE_URL.".to_string(),
+        })?;
+        if !self.allow_writes {
+            check_read_only(&input.query).map_err(|details| ToolError::PermissionDenied {
+                resource: format!("{} (writes are disabled; set [database] allow_writes = true)", details),
+            })?;
+        }
+        let limit = input.max_rows.unwrap_or(self.max_rows).min(self.max_rows).max(1);
+
+        sqlx::any::install_default_drivers();
+        let options = AnyConnectOptions::from_str(url).map_err(Self::failed)?;
+        let mut conn = options.connect().await.map_err(Self::failed)?;
+        if !self.allow_writes {
+            enforce_read_only(&mut conn).await?;
+        }
+        let mut tx = conn.begin().await.map_err(Self::failed)?;
+
+        let mut columns: Vec<String> = Vec::new();
+        let mut rows: Vec<Vec<Value>> = Vec::new();
+        let mut truncated = false;
+        {
+            let mut stream = sqlx::query(&input.query).fetch(&mut *tx);
+            while let Some(row) = stream.try_next().await.map_err(Self::failed)? {
+                if columns.is_empty() {
+                    columns = row.columns().iter().map(|c| c.name().to_string()).collect();
+                }
+                if rows.len() == limit {
+                    truncated = true;
+                    break;
+                }
+                rows.push(row_values(&row));
+            }
+        }
+
+        if self.allow_writes {
+            tx.commit().await.map_err(Self::failed)?;
+        } else {
+            tx.rollback().await.map_err(Self::failed)?;
+        }
+
+        let mut result = serde_json::json!({
+            "columns": columns,
+            "row_count": rows.len(),
+            "truncated": truncated,
+        });
+        match input.format {
+            OutputFormat::Json => {
+                let objects: Vec<Value> = rows
+                    .into_iter()
+                    .map(|row| Value::Object(columns.iter().cloned().zip(row).collect()))
+                    .collect();
+                result["rows"] = Value::Array(objects);
+            }
+            OutputFormat::Markdown => {
+                result["table"] = Value::String(markdown_table(&columns, &rows));
+            }
+        }
+        Ok(result)
+    }
+}
+
+// Puts the session into read-only mode, so transactions it starts cannot write.
+async fn enforce_read_only(conn: &mut AnyConnection) -> Result<(), ToolError> {
+    let statement = match conn.backend_name() {
+        "PostgreSQL" => "SET SESSION CHARACTERISTICS AS TRANSACTION READ ONLY",
+        "MySQL" => "SET SESSION TRANSACTION READ ONLY",
+        "SQLite" => "PRAGMA query_only = ON",
+        other => {
+            return Err(ToolError::Other {
+                message: format!("Cannot open a read-only connection to {}; set [database] allow_writes to use it", other),
+            })
+        }
+    };
+    sqlx::query(statement).execute(&mut *conn).await.map_err(SqlQueryTool::failed)?;
+    Ok(())
+}
+
+// Accepts one statement that starts with a read keyword and mentions no write keyword outside
+// string literals and comments. Returns the reason on rejection.
+fn check_read_only(query: &str) -> Result<(), String> {
+    let code = strip_literals_and_comments(query);
+    let statements: Vec<&str> = code.split(';').filter(|s| !s.trim().is_empty()).collect();
+    if statements.len() != 1 {
+        return Err("only a single statement may be run".to_string());
+    }
+    let words: Vec<String> = statements[0]
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    match words.first() {
+        Some(first) if READ_ONLY_KEYWORDS.contains(&first.as_str()) => {}
+        Some(first) => return Err(format!("'{}' statements are not read-only", first.to_uppercase())),
+        None => return Err("empty statement".to_string()),
+    }
+    if let Some(word) = words.iter().find(|w| WRITE_KEYWORDS.contains(&w.as_str())) {
+        return Err(format!("statement contains '{}'", word.to_uppercase()));
+    }
+    Ok(())
+}
+
+fn strip_literals_and_comments(query: &str) -> String {
+    let mut out = String::with_capacity(query.len());
+    let mut chars = query.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                for next in chars.by_ref() {
+                    if next == c {
+                        break;
+                    }
+                }
+                out.push(' ');
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        break;
+                    }
+                }
+                out.push(' ');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for next in chars.by_ref() {
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+                out.push(' ');
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// The Any driver only exposes a handful of value kinds, so each column is decoded by trying
+// them in turn. Unsupported types are reported rather than failing the whole query.
+fn row_values(row: &AnyRow) -> Vec<Value> {
+    (0..row.columns().len())
+        .map(|i| {
+            if let Ok(v) = row.try_get::<Option<bool>, _>(i) {
+                return v.map(Value::Bool).unwrap_or(Value::Null);
+            }
+            if let Ok(v) = row.try_get::<Option<i16>, _>(i) {
+                return v.map(Value::from).unwrap_or(Value::Null);
+            }
+            if let Ok(v) = row.try_get::<Option<i32>, _>(i) {
+                return v.map(Value::from).unwrap_or(Value::Null);
+            }
+            if let Ok(v) = row.try_get::<Option<i64>, _>(i) {
+                return v.map(Value::from).unwrap_or(Value::Null);
+            }
+            if let Ok(v) = row.try_get::<Option<f32>, _>(i) {
+                return v.map(Value::from).unwrap_or(Value::Null);
+            }
+            if let Ok(v) = row.try_get::<Option<f64>, _>(i) {
+                return v.map(Value::from).unwrap_or(Value::Null);
+            }
+            if let Ok(v) = row.try_get::<Option<String>, _>(i) {
+                return v.map(Value::String).unwrap_or(Value::Null);
+            }
+            if let Ok(v) = row.try_get::<Option<Vec<u8>>, _>(i) {
+                return v.map(|b| Value::String(format!("<{} bytes>", b.len()))).unwrap_or(Value::Null);
+            }
+            Value::String(format!("<unsupported type {}>", row.columns()[i].type_info()))
+        })
+        .collect()
+}
+
+fn markdown_table(columns: &[String], rows: &[Vec<Value>]) -> String {
+    if columns.is_empty() {
+        return "(no rows)".to_string();
+    }
+    let cell = |value: &Value| {
+        let text = match value {
+            Value::Null => "NULL".to_string(),
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        let mut text: String = text.replace('|', "\\|").replace('\n', " ").chars().take(MAX_CELL_CHARS).collect();
+        if text.is_empty() {
+            text.push(' ');
+        }
+        text
+    };
+    let mut table = format!("| {} |\n|{}\n", columns.join(" | "), " --- |".repeat(columns.len()));
+    for row in rows {
+        table.push_str(&format!("| {} |\n", row.iter().map(cell).collect::<Vec<_>>().join(" | ")));
+    }
+    table
+}
+
+#[async_trait]
+impl CliTool for SqlQueryTool {
+    fn name(&self) -> String {
+        "SqlQueryTool".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Runs a SQL query against the project's configured dev database and returns the rows, for exploring the schema and debugging data. Only single read-only statements (SELECT, WITH, EXPLAIN, SHOW, PRAGMA) are allowed unless writes are enabled in config; results are capped at a maximum row count. Args: {\"query\": string, \"format\": \"json\" | \"markdown\" (optional), \"max_rows\": number (optional)}".to_string()
+    }
+
+    fn parameters_schema(&self) -> anyhow::Result<Value> {
+        Ok(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string", "description": "The SQL statement to run." },
+                "format": {
+                    "type": "string",
+                    "enum": ["json", "markdown"],
+                    "description": "Return rows as JSON objects (default) or as a markdown table."
+                },
+                "max_rows": {
+                    "type": "integer",
+                    "description": "Maximum rows to return; cannot exceed the configured limit."
+                }
+            },
+            "required": ["query"]
+        }))
+    }
+
+    async fn execute(&self, args: Value) -> Result<Value, ToolError> {
+        let input: SqlQueryInput = serde_json::from_value(args).map_err(|e| ToolError::InvalidArguments {
+            tool_name: self.name(),
+            details: format!("Failed to parse arguments: {}", e),
+        })?;
+        self.run(input).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sqlite_tool(dir: &tempfile::TempDir, allow_writes: bool) -> SqlQueryTool {
+        let path = dir.path().join("dev.db");
+        SqlQueryTool::new(&DatabaseConfig {
+            url: Some(format!("sqlite://{}?mode=rwc", path.display())),
+            allow_writes,
+            max_rows: 2,
+        })
+    }
+
+    #[test]
+    fn test_check_read_only() {
+        assert!(check_read_only("SELECT * FROM users WHERE name = 'drop table'").is_ok());
+        assert!(check_read_only("with t as (select 1) select * from t;").is_ok());
+        assert!(check_read_only("DELETE FROM users").is_err());
+        assert!(check_read_only("WITH gone AS (DELETE FROM users RETURNING *) SELECT * FROM gone").is_err());
+        assert!(check_read_only("SELECT 1; DROP TABLE users").is_err());
+        assert!(check_read_only("-- just a comment").is_err());
+    }
+
+    #[test]
+    fn test_markdown_table() {
+        let table = markdown_table(&["id".into(), "name".into()], &[vec![json!(1), json!("a|b")], vec![json!(2), Value::Null]]);
+        assert_eq!(table, "| id | name |\n| --- | --- |\n| 1 | a\\|b |\n| 2 | NULL |\n");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_writes_unless_allowed() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = sqlite_tool(&dir, false).execute(json!({ "query": "CREATE TABLE t (id INTEGER)" })).await.unwrap_err();
+        assert!(matches!(err, ToolError::PermissionDenied { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_query_limits_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        sqlite_tool(&dir, true)
+            .execute(json!({ "query": "CREATE TABLE t (id INTEGER, name TEXT)" }))
+            .await
+            .unwrap();
+        sqlite_tool(&dir, true)
+            .execute(json!({ "query": "INSERT INTO t VALUES (1, 'a'), (2, NULL), (3, 'c')" }))
+            .await
+            .unwrap();
+
+        let result = sqlite_tool(&dir, false).execute(json!({ "query": "SELECT id, name FROM t ORDER BY id" })).await.unwrap();
+        assert_eq!(result["columns"], json!(["id", "name"]));
+        assert_eq!(result["rows"], json!([{ "id": 1, "name": "a" }, { "id": 2, "name": null }]));
+        assert_eq!(result["truncated"], json!(true));
+
+        // The connection itself refuses writes, whatever gets past the keyword check.
+        sqlx::any::install_default_drivers();
+        let url = format!("sqlite://{}", dir.path().join("dev.db").display());
+        let mut conn = AnyConnectOptions::from_str(&url).unwrap().connect().await.unwrap();
+        enforce_read_only(&mut conn).await.unwrap();
+        assert!(sqlx::query("DELETE FROM t").execute(&mut conn).await.is_err());
+    }
+}
@@ -0,0 +1,282 @@
+use serde::Deserialize;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::process::Command;
+
+use crate::config::{DevcontainerConfig, DevcontainerMode};
+use crate::tools::session_env::SessionEnv;
+use crate::tui::{print_info, prompt_confirmation};
+
+const CONFIG_PATHS: &[&str] = &[".devcontainer/devcontainer.json", ".devcontainer.json"];
+// Set by VS Code and the devcontainer CLI on the containers they start.
+const LOCAL_FOLDER_LABEL: &str = "devcontainer.local_folder";
+// Runs the command in the background and records its pid in $OPENCODE_PID_FILE, so it can be
+// killed inside the container; the file is removed once the command exits.
+const CONTAINER_WRAPPER: &str = "\"$@\" & echo $! > \"$OPENCODE_PID_FILE\"; wait $!; status=$?; rm -f \"$OPENCODE_PID_FILE\"; exit $status";
+static NEXT_EXEC_ID: AtomicUsize = AtomicUsize::new(1);
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DevcontainerJson {
+    name: Option<String>,
+    workspace_folder: Option<String>,
+}
+
+// What `.devcontainer/devcontainer.json` says about the project's container.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DevcontainerSpec {
+    pub name: String,
+    pub project_root: PathBuf,
+    pub workspace_folder: String,
+}
+
+// devcontainer.json is JSON with comments and trailing commas.
+fn strip_jsonc(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let (mut i, mut in_string) = (0, false);
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            out.push(c);
+            if c == '\\' && i + 1 < chars.len() {
+                out.push(chars[i + 1]);
+                i += 1;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+            out.push(c);
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i += 2;
+            continue;
+        } else if c == ',' && chars[i + 1..].iter().find(|c| !c.is_whitespace()).is_some_and(|c| *c == '}' || *c == ']') {
+            // Trailing comma; the whitespace after it is kept.
+        } else {
+            out.push(c);
+        }
+        i += 1;
+    }
+    out
+}
+
+pub fn detect(project_root: &Path) -> Option<DevcontainerSpec> {
+    let path = CONFIG_PATHS.iter().map(|p| project_root.join(p)).find(|p| p.is_file())?;
+    let parsed = std::fs::read_to_string(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|text| serde_json::from_str::<DevcontainerJson>(&strip_jsonc(&text)).map_err(anyhow::Error::from));
+    let json = match parsed {
+        Ok(json) => json,
+        Err(e) => {
+            tracing::warn!("Ignoring unreadable {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    let dir_name = project_root.file_name().unwrap_or_default().to_string_lossy().to_string();
+    Some(DevcontainerSpec {
+        name: json.name.unwrap_or_else(|| dir_name.clone()),
+        project_root: project_root.to_path_buf(),
+        workspace_folder: json.workspace_folder.unwrap_or_else(|| format!("/workspaces/{}", dir_name)),
+    })
+}
+
+async fn running_container(spec: &DevcontainerSpec) -> Option<String> {
+    let filter = format!("label={}={}", LOCAL_FOLDER_LABEL, spec.project_root.display());
+    let output = tokio::process::Command::new("docker")
+        .args(["ps", "-q", "--filter", &filter])
+        .kill_on_drop(true)
+        .output()
+        .await
+        .ok()?;
+    let id = String::from_utf8_lossy(&output.stdout).lines().next()?.trim().to_string();
+    (output.status.success() && !id.is_empty()).then_some(id)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ActiveContainer {
+    id: String,
+    spec: DevcontainerSpec,
+}
+
+// Where ShellCommandTool and ExecuteCommandTool run their commands: on the host, or inside the
+// project's running devcontainer once the user has agreed to that, with the session's `/setenv`
+// overrides either way. Cloning shares the choice.
+#[derive(Debug, Clone, Default)]
+pub struct DevcontainerTarget {
+    active: Arc<Mutex<Option<ActiveContainer>>>,
+    env: SessionEnv,
+}
+
+impl DevcontainerTarget {
+    pub fn new(env: SessionEnv) -> Self {
+        DevcontainerTarget { env, ..Self::default() }
+    }
+
+    pub fn enable(&self, container_id: String, spec: DevcontainerSpec) {
+        *self.active.lock().unwrap() = Some(ActiveContainer { id: container_id, spec });
+    }
+
+    pub fn container(&self) -> Option<String> {
+        self.active.lock().unwrap().as_ref().map(|active| active.id.clone())
+    }
+
+    // Runs `program args` in `cwd` (the current directory when `None`) and collects its output.
+    // Dropping the future kills the process, inside the container too.
+    pub async fn output(&self, program: &str, args: &[String], cwd: Option<&Path>) -> std::io::Result<Output> {
+        let (mut command, process) = self.command(program, args, cwd);
+        let output = command.kill_on_drop(true).output().await;
+        if let Some(mut process) = process {
+            process.finished = true;
+        }
+        output
+    }
+
+    // `program args` run in `cwd`, via `docker exec` with the matching path inside the container
+    // when a devcontainer is active.
+    fn command(&self, program: &str, args: &[String], cwd: Option<&Path>) -> (Command, Option<ContainerProcess>) {
+        let Some(active) = self.active.lock().unwrap().clone() else {
+            let mut command = Command::new(program);
+            command.args(args);
+            self.env.apply(&mut command);
+            if let Some(cwd) = cwd {
+                command.current_dir(cwd);
+            }
+            return (command, None);
+        };
+        let host_dir = cwd.map(Path::to_path_buf).or_else(|| std::env::current_dir().ok()).unwrap_or_default();
+        let pid_file = format!("/tmp/opencode-exec-{}-{}.pid", std::process::id(), NEXT_EXEC_ID.fetch_add(1, Ordering::Relaxed));
+        let mut command = Command::new("docker");
+        command
+            .arg("exec")
+            .arg("-w")
+            .arg(container_path(&active.spec, &host_dir))
+            .args(self.env.vars().iter().flat_map(|(name, value)| ["-e".to_string(), format!("{}={}", name, value)]))
+            .args(["-e".to_string(), format!("OPENCODE_PID_FILE={}", pid_file)])
+            .arg(&active.id)
+            .args(["sh", "-c", CONTAINER_WRAPPER, "sh"])
+            .arg(program)
+            .args(args);
+        (command, Some(ContainerProcess { container: active.id, pid_file, finished: false }))
+    }
+}
+
+// A command started with `docker exec`. Killing the local client leaves the process running in
+// the container, so one dropped before it finished is killed there.
+struct ContainerProcess {
+    container: String,
+    pid_file: String,
+    finished: bool,
+}
+
+impl Drop for ContainerProcess {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        let kill = format!("kill -TERM \"$(cat '{}')\" 2>/dev/null", self.pid_file);
+        let spawned = std::process::Command::new("docker")
+            .args(["exec", &self.container, "sh", "-c", &kill])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        if let Err(e) = spawned {
+            tracing::warn!("Failed to stop the command in container {}: {}", self.container, e);
+        }
+    }
+}
+
+fn container_path(spec: &DevcontainerSpec, host_dir: &Path) -> String {
+    match host_dir.strip_prefix(&spec.project_root) {
+        Ok(relative) if !relative.as_os_str().is_empty() => {
+            format!("{}/{}", spec.workspace_folder.trim_end_matches('/'), relative.to_string_lossy().replace('\\', "/"))
+        }
+        _ => spec.workspace_folder.clone(),
+    }
+}
+
+// Looks for a devcontainer for `project_root` and, per `[devcontainer] mode`, switches command
+// tools into its running container, asking first in "ask" mode.
+pub async fn offer(config: &DevcontainerConfig, target: &DevcontainerTarget, project_root: &Path) {
+    if config.mode == DevcontainerMode::Never {
+        return;
+    }
+    let Some(spec) = detect(project_root) else { return };
+    let Some(id) = running_container(&spec).await else {
+        print_info(&format!("Found devcontainer '{}' but it is not running; commands run on the host.", spec.name));
+        return;
+    };
+    let accepted = match config.mode {
+        DevcontainerMode::Always => true,
+        _ if !std::io::stdin().is_terminal() => false,
+        _ => prompt_confirmation(&format!("Run shell commands inside devcontainer '{}'?", spec.name)).unwrap_or(false),
+    };
+    if accepted {
+        print_info(&format!("Shell commands will run in devcontainer '{}' at {}.", spec.name, spec.workspace_folder));
+        target.enable(id, spec);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_reads_jsonc_and_defaults_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("shop");
+        std::fs::create_dir_all(root.join(".devcontainer")).unwrap();
+        std::fs::write(
+            root.join(".devcontainer/devcontainer.json"),
+            "{\n  // the toolchain image\n  \"name\": \"Shop // dev\", /* inline */\n  \"image\": \"rust:1\",\n}\n",
+        )
+        .unwrap();
+
+        let spec = detect(&root).unwrap();
+        assert_eq!(spec.name, "Shop // dev");
+        assert_eq!(spec.workspace_folder, "/workspaces/shop");
+        assert_eq!(container_path(&spec, &root.join("crates/api")), "/workspaces/shop/crates/api");
+        assert_eq!(container_path(&spec, Path::new("/elsewhere")), "/workspaces/shop");
+        assert!(detect(dir.path()).is_none());
+    }
+
+    #[test]
+    fn test_command_uses_docker_exec_when_enabled() {
+        let env = SessionEnv::default();
+        env.set("RUST_LOG", "debug").unwrap();
+        let target = DevcontainerTarget::new(env);
+        let spec = DevcontainerSpec {
+            name: "dev".to_string(),
+            project_root: PathBuf::from("/repo"),
+            workspace_folder: "/workspaces/repo".to_string(),
+        };
+        let (host, process) = target.command("cargo", &["test".to_string()], Some(Path::new("/repo/src")));
+        assert_eq!(host.as_std().get_program(), "cargo");
+        assert!(process.is_none());
+
+        target.enable("abc123".to_string(), spec);
+        let (inside, process) = target.command("cargo", &["test".to_string()], Some(Path::new("/repo/src")));
+        let mut process = process.unwrap();
+        process.finished = true;
+        assert_eq!(inside.as_std().get_program(), "docker");
+        let args: Vec<_> = inside.as_std().get_args().map(|a| a.to_string_lossy().to_string()).collect();
+        let pid_env = format!("OPENCODE_PID_FILE={}", process.pid_file);
+        assert_eq!(
+            args,
+            vec!["exec", "-w", "/workspaces/repo/src", "-e", "RUST_LOG=debug", "-e", &pid_env, "abc123", "sh", "-c", CONTAINER_WRAPPER, "sh", "cargo", "test"]
+        );
+    }
+}
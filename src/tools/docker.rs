@@ -0,0 +1,227 @@
+use crate::tools::ask_user::confirm;
+use crate::tools::env_vars::mask_env_entry;
+use crate::tools::session_env;
+use crate::tools::{CliTool, ToolError};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+
+const RUNTIMES: &[&str] = &["docker", "podman"];
+const DEFAULT_LOG_LINES: u32 = 200;
+const MAX_LOG_LINES: u32 = 2000;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum DockerAction {
+    Ps,
+    Logs,
+    Inspect,
+    ComposeUp,
+    ComposeDown,
+}
+
+#[derive(Debug, Deserialize)]
+struct DockerInput {
+    action: DockerAction,
+    target: Option<String>,
+    #[serde(default)]
+    all: bool,
+    tail: Option<u32>,
+    file: Option<String>,
+    #[serde(default)]
+    services: Vec<String>,
+}
+
+// A safe subset of docker (or podman when docker is missing) for diagnosing containerized dev
+// environments. Read-only actions run directly; compose up/down change running state, so they
+// ask the user first and fail without a terminal to ask on.
+#[derive(Debug)]
+pub struct DockerTool;
+
+impl DockerTool {
+    fn invalid(&self, details: impl Into<String>) -> ToolError {
+        ToolError::InvalidArguments { tool_name: self.name(), details: details.into() }
+    }
+
+    async fn runtime() -> Result<&'static str, ToolError> {
+        for runtime in RUNTIMES {
+            let found = session_env::command(runtime)
+                .arg("--version")
+                .output()
+                .await
+                .map(|o| o.status.success())
+                .unwrap_or(false);
+            if found {
+                return Ok(runtime);
+            }
+        }
+        Err(ToolError::Other { message: "Neither docker nor podman was found on PATH".to_string() })
+    }
+
+    async fn run(runtime: &str, args: &[String]) -> Result<(String, String), ToolError> {
+        let command = format!("{} {}", runtime, args.join(" "));
+        let output = session_env::command(runtime)
+            .args(args)
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| ToolError::Other { message: format!("Failed to run {}: {}", command, e) })?;
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        if !output.status.success() {
+            return Err(ToolError::ExecutionFailed { command, stderr });
+        }
+        Ok((stdout, stderr))
+    }
+
+    fn compose_args(input: &DockerInput, subcommand: &[&str]) -> Vec<String> {
+        let mut args = vec!["compose".to_string()];
+        if let Some(file) = &input.file {
+            args.push("-f".to_string());
+            args.push(file.clone());
+        }
+        args.extend(subcommand.iter().map(|s| s.to_string()));
+        args.extend(input.services.iter().cloned());
+        args
+    }
+
+    // Rejects anything the runtime would parse as a flag rather than a name.
+    fn validate(&self, input: &DockerInput) -> Result<(), ToolError> {
+        let names = input.target.iter().chain(input.services.iter()).chain(input.file.iter());
+        for name in names {
+            if name.trim().is_empty() || name.starts_with('-') {
+                return Err(self.invalid(format!("Invalid container, service or file name '{}'", name)));
+            }
+        }
+        if matches!(input.action, DockerAction::Logs | DockerAction::Inspect) && input.target.is_none() {
+            return Err(self.invalid("'target' is required for logs and inspect"));
+        }
+        Ok(())
+    }
+}
+
+// `ps --format '{{json .}}'` prints one JSON object per line.
+fn parse_json_lines(stdout: &str) -> Vec<Value> {
+    stdout
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|_| Value::String(line.to_string())))
+        .collect()
+}
+
+#[async_trait]
+impl CliTool for DockerTool {
+    fn name(&self) -> String {
+        "DockerTool".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Inspects containers with docker (or podman): \"ps\" lists containers (`all` includes stopped ones), \"logs\" returns the last `tail` lines of a container's output, \"inspect\" returns a container or image's configuration, and \"compose_up\"/\"compose_down\" start or stop compose services after the user confirms. Args: {\"action\": \"ps\" | \"logs\" | \"inspect\" | \"compose_up\" | \"compose_down\", \"target\": string (container for logs/inspect), \"all\": bool (optional), \"tail\": number (optional), \"file\": string (optional compose file), \"services\": [string] (optional)}".to_string()
+    }
+
+    fn parameters_schema(&self) -> Result<Value> {
+        Ok(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": { "type": "string", "enum": ["ps", "logs", "inspect", "compose_up", "compose_down"] },
+                "target": { "type": "string", "description": "Container (or image, for inspect) name or id." },
+                "all": { "type": "boolean", "description": "Include stopped containers in ps." },
+                "tail": { "type": "integer", "description": "Number of log lines to return (default: 200)." },
+                "file": { "type": "string", "description": "Compose file to use instead of the default." },
+                "services": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Compose services to start or stop (default: all)."
+                }
+            },
+            "required": ["action"]
+        }))
+    }
+
+    fn spawns_commands(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<Value, ToolError> {
+        let input: DockerInput =
+            serde_json::from_value(args).map_err(|e| self.invalid(format!("Failed to parse arguments: {}", e)))?;
+        self.validate(&input)?;
+        let runtime = Self::runtime().await?;
+        let target = input.target.clone().unwrap_or_default();
+
+        match input.action {
+            DockerAction::Ps => {
+                let mut args = vec!["ps".to_string(), "--format".to_string(), "{{json .}}".to_string()];
+                if input.all {
+                    args.push("--all".to_string());
+                }
+                let (stdout, _) = Self::run(runtime, &args).await?;
+                Ok(serde_json::json!({ "runtime": runtime, "containers": parse_json_lines(&stdout) }))
+            }
+            DockerAction::Logs => {
+                let tail = input.tail.unwrap_or(DEFAULT_LOG_LINES).min(MAX_LOG_LINES);
+                let args = vec!["logs".to_string(), "--tail".to_string(), tail.to_string(), target.clone()];
+                // Containers commonly log to stderr, which `logs` passes through as-is.
+                let (stdout, stderr) = Self::run(runtime, &args).await?;
+                Ok(serde_json::json!({ "runtime": runtime, "container": target, "stdout": stdout, "stderr": stderr }))
+            }
+            DockerAction::Inspect => {
+                let (stdout, _) = Self::run(runtime, &["inspect".to_string(), target.clone()]).await?;
+                let mut details: Value = serde_json::from_str(&stdout).unwrap_or(Value::String(stdout));
+                mask_environment(&mut details);
+                Ok(serde_json::json!({ "runtime": runtime, "target": target, "details": details }))
+            }
+            DockerAction::ComposeUp | DockerAction::ComposeDown => {
+                let subcommand: &[&str] = if input.action == DockerAction::ComposeUp { &["up", "-d"] } else { &["down"] };
+                let args = Self::compose_args(&input, subcommand);
+                confirm(format!("Run `{} {}`?", runtime, args.join(" "))).await?;
+                let (stdout, stderr) = Self::run(runtime, &args).await?;
+                Ok(serde_json::json!({ "runtime": runtime, "command": args, "stdout": stdout, "stderr": stderr }))
+            }
+        }
+    }
+}
+
+// Containers' `Config.Env` often carries credentials, which are masked as EnvTool masks them.
+fn mask_environment(details: &mut Value) {
+    for object in details.as_array_mut().into_iter().flatten() {
+        let entries = object.pointer_mut("/Config/Env").and_then(Value::as_array_mut);
+        for entry in entries.into_iter().flatten() {
+            if let Some(text) = entry.as_str() {
+                *entry = Value::String(mask_env_entry(text));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_rejects_flag_like_names() {
+        let err = DockerTool.execute(json!({ "action": "logs", "target": "--help" })).await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidArguments { .. }));
+        let err = DockerTool.execute(json!({ "action": "inspect" })).await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidArguments { .. }));
+    }
+
+    #[test]
+    fn test_parse_json_lines_and_compose_args() {
+        let containers = parse_json_lines("{\"Names\":\"db\"}\n\n{\"Names\":\"web\"}\n");
+        assert_eq!(containers, vec![json!({ "Names": "db" }), json!({ "Names": "web" })]);
+
+        let input: DockerInput =
+            serde_json::from_value(json!({ "action": "compose_up", "file": "dev.yml", "services": ["db"] })).unwrap();
+        assert_eq!(DockerTool::compose_args(&input, &["up", "-d"]), vec!["compose", "-f", "dev.yml", "up", "-d", "db"]);
+
+        let mut details = json!([{ "Config": { "Env": ["PATH=/usr/bin", "POSTGRES_PASSWORD=hunter22", "DB=postgres://app:pw@db/app"] } }]);
+        mask_environment(&mut details);
+        assert_eq!(
+            details[0]["Config"]["Env"],
+            json!(["PATH=/usr/bin", "POSTGRES_PASSWORD=<masked, 8 chars>", "DB=postgres://app:****@db/app"])
+        );
+    }
+}
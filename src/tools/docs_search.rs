@@ -0,0 +1,369 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use super::rate_limit::{domain_of, NetworkLimiter};
+use super::url_fetch::UrlFetcher;
+use super::{CliTool, ToolError};
+use crate::config::NetworkConfig;
+
+const STD_DOCS_BASE: &str = "https://doc.rust-lang.org";
+const DOCS_RS_BASE: &str = "https://docs.rs";
+const LOCAL_DOC_DIR: &str = "target/doc";
+const STD_CRATES: &[&str] = &["std", "core", "alloc", "proc_macro", "test"];
+const ITEM_KINDS: &[&str] = &[
+    "struct", "enum", "trait", "fn", "macro", "type", "constant", "static", "union", "derive", "attr",
+];
+const MEMBER_KINDS: &[&str] = &[
+    "method", "tymethod", "variant", "structfield", "associatedconstant", "associatedtype",
+];
+const MAX_SUMMARY_CHARS: usize = 600;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocsSearchInput {
+    pub path: String,
+    #[serde(default)]
+    pub source: DocsSource,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocsSource {
+    // Prefer a local `cargo doc` build and fall back to the published docs.
+    #[default]
+    Auto,
+    Local,
+    Remote,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct DocsSearchOutput {
+    pub path: String,
+    pub kind: String,
+    pub signature: Option<String>,
+    pub summary: Option<String>,
+    pub location: String,
+}
+
+#[derive(Error, Debug)]
+pub enum DocsSearchError {
+    #[error("Expected a full item path such as 'std::vec::Vec::push', got '{0}'")]
+    InvalidPath(String),
+    #[error("No documentation found for '{0}'")]
+    NotFound(String),
+    #[error("Failed to fetch documentation: {0}")]
+    Fetch(#[from] super::url_fetch::UrlFetchError),
+}
+
+impl From<DocsSearchError> for ToolError {
+    fn from(error: DocsSearchError) -> Self {
+        match error {
+            DocsSearchError::Fetch(e) => e.into(),
+            other => ToolError::Other { message: other.to_string() },
+        }
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    decode_entities(&text).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Returns the inner HTML of the first element opened by `open_tag_prefix`, searching from `from`.
+fn inner_html<'a>(html: &'a str, from: usize, open_tag_prefix: &str, close_tag: &str) -> Option<&'a str> {
+    let open = from + html[from..].find(open_tag_prefix)?;
+    let start = open + html[open..].find('>')? + 1;
+    let end = start + html[start..].find(close_tag)?;
+    Some(&html[start..end])
+}
+
+fn first_paragraph(html: &str, from: usize) -> Option<String> {
+    let docblock = from + html[from..].find("class=\"docblock")?;
+    let paragraph = inner_html(html, docblock, "<p", "</p>")?;
+    let mut summary = strip_tags(paragraph);
+    if summary.chars().count() > MAX_SUMMARY_CHARS {
+        summary = summary.chars().take(MAX_SUMMARY_CHARS).collect::<String>() + "…";
+    }
+    (!summary.is_empty()).then_some(summary)
+}
+
+// Extracts the declaration and first doc paragraph from a rustdoc item page.
+pub fn parse_item_page(html: &str) -> (Option<String>, Option<String>) {
+    let Some(decl_pos) = html.find("item-decl") else {
+        return (None, first_paragraph(html, 0));
+    };
+    let signature = inner_html(html, decl_pos, "<pre", "</pre>")
+        .or_else(|| inner_html(html, decl_pos, "<code", "</code>"))
+        .map(strip_tags)
+        .filter(|s| !s.is_empty());
+    (signature, first_paragraph(html, decl_pos))
+}
+
+// Extracts the header and first doc paragraph for a member anchor such as `method.push`.
+pub fn parse_member(html: &str, anchor: &str) -> Option<(Option<String>, Option<String>)> {
+    let pos = html.find(&format!("id=\"{}\"", anchor))?;
+    let signature = inner_html(html, pos, "class=\"code-header\"", "</h4>").map(strip_tags);
+    // Only look for the docblock up to the next member so we don't borrow its summary.
+    let section_end = html[pos + 1..]
+        .find("<section id=")
+        .map(|i| pos + 1 + i)
+        .unwrap_or(html.len());
+    let summary = first_paragraph(&html[..section_end], pos);
+    Some((signature, summary))
+}
+
+#[derive(Debug)]
+enum DocsLocation {
+    Local(PathBuf),
+    Remote(String),
+}
+
+impl DocsLocation {
+    fn join(&self, relative: &str) -> String {
+        match self {
+            DocsLocation::Local(root) => root.join(relative).display().to_string(),
+            DocsLocation::Remote(base) => format!("{}/{}", base, relative),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DocsSearchTool {
+    fetcher: UrlFetcher,
+    limiter: NetworkLimiter,
+    local_doc_dir: PathBuf,
+}
+
+impl DocsSearchTool {
+    // A search can fetch several pages, so it takes a request token for each one from
+    // `limiter` rather than one for the whole call.
+    pub fn new(config: &NetworkConfig, limiter: NetworkLimiter) -> Self {
+        Self::with_local_doc_dir(config, limiter, PathBuf::from(LOCAL_DOC_DIR))
+    }
+
+    pub fn with_local_doc_dir(config: &NetworkConfig, limiter: NetworkLimiter, local_doc_dir: PathBuf) -> Self {
+        DocsSearchTool {
+            fetcher: UrlFetcher::new(config),
+            limiter,
+            local_doc_dir,
+        }
+    }
+
+    fn remote_base(crate_name: &str) -> String {
+        if STD_CRATES.contains(&crate_name) {
+            STD_DOCS_BASE.to_string()
+        } else {
+            format!("{}/{}/latest", DOCS_RS_BASE, crate_name.replace('_', "-"))
+        }
+    }
+
+    fn locations(&self, crate_name: &str, source: DocsSource) -> Vec<DocsLocation> {
+        let local = self.local_doc_dir.join(crate_name);
+        let mut locations = Vec::new();
+        if source != DocsSource::Remote && local.is_dir() {
+            locations.push(DocsLocation::Local(self.local_doc_dir.clone()));
+        }
+        if source != DocsSource::Local {
+            locations.push(DocsLocation::Remote(Self::remote_base(crate_name)));
+        }
+        locations
+    }
+
+    async fn load(&self, location: &DocsLocation, relative: &str) -> Result<Option<String>, DocsSearchError> {
+        match location {
+            DocsLocation::Local(root) => Ok(read_local(&root.join(relative))),
+            DocsLocation::Remote(base) => {
+                let url = format!("{}/{}", base, relative);
+                let _permit = self.limiter.acquire(&domain_of(&url).unwrap_or_default()).await;
+                let page = self.fetcher.fetch(&url).await?;
+                Ok((page.status == 200).then_some(page.content))
+            }
+        }
+    }
+
+    // Tries `kind.Name.html` under the module path, then `name/index.html` for modules.
+    async fn find_item(
+        &self,
+        location: &DocsLocation,
+        segments: &[&str],
+    ) -> Result<Option<(String, String, String)>, DocsSearchError> {
+        let (name, modules) = segments.split_last().expect("segments is non-empty");
+        let module_path = modules.join("/");
+        for kind in ITEM_KINDS {
+            let relative = format!("{}/{}.{}.html", module_path, kind, name);
+            if let Some(html) = self.load(location, &relative).await? {
+                return Ok(Some((kind.to_string(), relative, html)));
+            }
+        }
+        let relative = format!("{}/{}/index.html", module_path, name).trim_start_matches('/').to_string();
+        if let Some(html) = self.load(location, &relative).await? {
+            return Ok(Some(("mod".to_string(), relative, html)));
+        }
+        Ok(None)
+    }
+
+    pub async fn search(&self, path: &str, source: DocsSource) -> Result<DocsSearchOutput, DocsSearchError> {
+        let segments: Vec<&str> = path.trim().split("::").filter(|s| !s.is_empty()).collect();
+        if segments.len() < 2 {
+            return Err(DocsSearchError::InvalidPath(path.to_string()));
+        }
+
+        for location in self.locations(segments[0], source) {
+            if let Some((kind, relative, html)) = self.find_item(&location, &segments).await? {
+                let (signature, summary) = parse_item_page(&html);
+                return Ok(DocsSearchOutput {
+                    path: path.to_string(),
+                    kind,
+                    signature,
+                    summary,
+                    location: location.join(&relative),
+                });
+            }
+
+            // Fall back to treating the last segment as a member of its parent item.
+            if segments.len() < 3 {
+                continue;
+            }
+            let (member, parent) = segments.split_last().expect("segments is non-empty");
+            if let Some((_, relative, html)) = self.find_item(&location, parent).await? {
+                for kind in MEMBER_KINDS {
+                    let anchor = format!("{}.{}", kind, member);
+                    if let Some((signature, summary)) = parse_member(&html, &anchor) {
+                        return Ok(DocsSearchOutput {
+                            path: path.to_string(),
+                            kind: kind.to_string(),
+                            signature,
+                            summary,
+                            location: format!("{}#{}", location.join(&relative), anchor),
+                        });
+                    }
+                }
+            }
+        }
+        Err(DocsSearchError::NotFound(path.to_string()))
+    }
+}
+
+fn read_local(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok()
+}
+
+#[async_trait]
+impl CliTool for DocsSearchTool {
+    fn name(&self) -> String {
+        "DocsSearchTool".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Looks up a Rust item in the documentation (a local `cargo doc` build, docs.rs or the standard library docs) \
+         and returns its signature and doc summary. Use it to confirm API names before using them. \
+         Args: {\"path\": string (full path, e.g. \"std::vec::Vec::retain\"), \"source\": \"auto\" | \"local\" | \"remote\" (optional)}"
+            .to_string()
+    }
+
+    fn parameters_schema(&self) -> anyhow::Result<Value> {
+        Ok(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Fully qualified item path starting with the crate name, e.g. 'std::collections::HashMap::entry' or 'serde_json::from_str'."
+                },
+                "source": {
+                    "type": "string",
+                    "enum": ["auto", "local", "remote"],
+                    "description": "Where to look. 'auto' (default) prefers a local cargo doc build."
+                }
+            },
+            "required": ["path"]
+        }))
+    }
+
+    async fn execute(&self, args: Value) -> Result<Value, ToolError> {
+        let input: DocsSearchInput = serde_json::from_value(args).map_err(|e| ToolError::InvalidArguments {
+            tool_name: self.name(),
+            details: format!("Failed to parse arguments: {}", e),
+        })?;
+        let output = self.search(&input.path, input.source).await?;
+        serde_json::to_value(output).map_err(|e| ToolError::Other {
+            message: format!("Failed to serialize output: {}", e),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const VEC_PAGE: &str = r##"<html><body>
+<pre class="rust item-decl"><code>pub struct Vec&lt;T, A: <a href="#">Allocator</a> = <a>Global</a>&gt; { /* private fields */ }</code></pre>
+<details class="toggle top-doc"><div class="docblock"><p>A contiguous <strong>growable</strong> array type.</p><p>More text.</p></div></details>
+<section id="method.push" class="method"><h4 class="code-header">pub fn <a class="fn">push</a>(&amp;mut self, value: T)</h4></section>
+<div class="docblock"><p>Appends an element to the back of a collection.</p></div>
+<section id="method.pop" class="method"><h4 class="code-header">pub fn <a class="fn">pop</a>(&amp;mut self) -&gt; Option&lt;T&gt;</h4></section>
+<div class="docblock"><p>Removes the last element.</p></div>
+</body></html>"##;
+
+    #[test]
+    fn test_parse_item_page() {
+        let (signature, summary) = parse_item_page(VEC_PAGE);
+        assert_eq!(
+            signature.as_deref(),
+            Some("pub struct Vec<T, A: Allocator = Global> { /* private fields */ }")
+        );
+        assert_eq!(summary.as_deref(), Some("A contiguous growable array type."));
+    }
+
+    #[test]
+    fn test_parse_member() {
+        let (signature, summary) = parse_member(VEC_PAGE, "method.push").unwrap();
+        assert_eq!(signature.as_deref(), Some("pub fn push(&mut self, value: T)"));
+        assert_eq!(summary.as_deref(), Some("Appends an element to the back of a collection."));
+        assert!(parse_member(VEC_PAGE, "method.retain").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_search_local_docs() {
+        let dir = tempdir().unwrap();
+        let module = dir.path().join("mycrate").join("collections");
+        std::fs::create_dir_all(&module).unwrap();
+        std::fs::write(module.join("struct.Vec.html"), VEC_PAGE).unwrap();
+
+        let tool = DocsSearchTool::with_local_doc_dir(&NetworkConfig::default(), NetworkLimiter::default(), dir.path().to_path_buf());
+        let item = tool.search("mycrate::collections::Vec", DocsSource::Local).await.unwrap();
+        assert_eq!(item.kind, "struct");
+        assert!(item.location.ends_with("struct.Vec.html"));
+
+        let member = tool.search("mycrate::collections::Vec::pop", DocsSource::Local).await.unwrap();
+        assert_eq!(member.kind, "method");
+        assert_eq!(member.signature.as_deref(), Some("pub fn pop(&mut self) -> Option<T>"));
+        assert_eq!(member.summary.as_deref(), Some("Removes the last element."));
+
+        assert!(matches!(
+            tool.search("mycrate::collections::Missing", DocsSource::Local).await,
+            Err(DocsSearchError::NotFound(_))
+        ));
+        assert!(matches!(tool.search("Vec", DocsSource::Local).await, Err(DocsSearchError::InvalidPath(_))));
+    }
+}
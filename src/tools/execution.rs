@@ -1,8 +1,17 @@
+use crate::events::{self, UiEvent};
+use crate::hooks::{HookEvent, HookOutcome, HookRunner};
+use crate::config::{ToolPolicyConfig, UntrustedContentConfig};
+use crate::tools::ask_user::confirm;
+use crate::tools::injection::InjectionGuard;
+use crate::tools::policy::{PolicyDecision, ToolPolicy};
+use crate::tools::rate_limit::NetworkLimiter;
 use crate::tools::ToolError;
 use serde_json::Value;
 use anyhow::Result;
 
-#[derive(Debug)]
+// How calls the `[tool_policy]` marks "confirm" are handled: AllowAll runs them without
+// asking. Denials apply under either.
+#[derive(Debug, Clone, Copy)]
 pub enum SecurityPolicy {
     #[allow(dead_code)]
     AllowAll,
@@ -13,6 +22,10 @@ pub enum SecurityPolicy {
 pub struct ToolExecutionEngine<'a> {
     tool_registry: &'a crate::tools::registry::ToolRegistry,
     security_policy: SecurityPolicy,
+    network_limiter: NetworkLimiter,
+    hooks: HookRunner,
+    injection_guard: InjectionGuard,
+    tool_policy: ToolPolicy,
 }
 
 impl<'a> ToolExecutionEngine<'a> {
@@ -20,12 +33,122 @@ impl<'a> ToolExecutionEngine<'a> {
         ToolExecutionEngine {
             tool_registry,
             security_policy,
+            network_limiter: NetworkLimiter::default(),
+            hooks: HookRunner::default(),
+            injection_guard: InjectionGuard::default(),
+            tool_policy: ToolPolicy::default(),
         }
     }
 
+    pub fn with_network_limiter(mut self, network_limiter: NetworkLimiter) -> Self {
+        self.network_limiter = network_limiter;
+        self
+    }
+
+    pub fn with_hooks(mut self, hooks: HookRunner) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    pub fn with_untrusted_content(mut self, config: &UntrustedContentConfig) -> Self {
+        self.injection_guard = InjectionGuard::new(config);
+        self
+    }
+
+    pub fn with_tool_policy(mut self, config: &ToolPolicyConfig) -> Self {
+        self.tool_policy = ToolPolicy::new(config);
+        self
+    }
+
+    // Marks `output` as untrusted when `tool_name`'s results are, for tool output that reaches
+    // the model without a tool call (e.g. retrieval run before the request).
+    pub fn screen(&self, tool_name: &str, output: Value) -> Value {
+        self.injection_guard.screen(tool_name, output)
+    }
+
+    // Reports the call on the event bus so the terminal (or any other subscriber) can show
+    // progress without each command handler printing it.
     pub async fn execute_tool_call(&self, tool_name: &str, arguments: Value) -> Result<Value, ToolError> {
+        events::emit(UiEvent::ToolStarted { tool: tool_name.to_string(), arguments: arguments.clone() });
+        let result = self.execute_with_hooks(tool_name, arguments).await.map(|output| self.injection_guard.screen(tool_name, output));
+        let (success, output) = match &result {
+            Ok(output) => (true, output.clone()),
+            Err(e) => (false, Value::String(e.to_string())),
+        };
+        events::emit(UiEvent::ToolFinished { tool: tool_name.to_string(), success, output });
+        result
+    }
+
+    // Wraps the tool run in the configured pre_tool/post_tool hooks. pre_tool may rewrite the
+    // arguments and post_tool the result; either can veto.
+    async fn execute_with_hooks(&self, tool_name: &str, arguments: Value) -> Result<Value, ToolError> {
+        let hook_error = |e: anyhow::Error| ToolError::Other { message: format!("Hook failed: {}", e) };
+        let arguments = match self
+            .hooks
+            .run(HookEvent::PreTool, serde_json::json!({ "tool": tool_name, "arguments": arguments.clone() }))
+            .await
+            .map_err(hook_error)?
+        {
+            // A replacement payload without "arguments" leaves them as they were.
+            HookOutcome::Continue(payload) => payload.get("arguments").cloned().unwrap_or(arguments),
+            HookOutcome::Veto(reason) => {
+                return Err(ToolError::PermissionDenied {
+                    resource: format!("tool '{}' (blocked by pre_tool hook: {})", tool_name, reason),
+                })
+            }
+        };
+
+        self.tool_registry
+            .path_policy()
+            .check_arguments(tool_name, &arguments)
+            .map_err(|resource| ToolError::PermissionDenied { resource })?;
+
+        // A denial goes back to the model as the tool's error; at most one question is asked.
+        let decision = self.tool_policy.decide(tool_name, &arguments);
+        if let PolicyDecision::Deny(resource) = decision {
+            return Err(ToolError::PermissionDenied { resource });
+        }
+        if self.tool_registry.get_tool(tool_name).is_some_and(|tool| tool.spawns_commands()) {
+            if let Some(resource) = self.tool_policy.command_tool_denial(tool_name) {
+                return Err(ToolError::PermissionDenied { resource });
+            }
+        }
+        let policy_confirms = decision == PolicyDecision::Confirm && !matches!(self.security_policy, SecurityPolicy::AllowAll);
+        match self.injection_guard.confirmation_needed(tool_name) {
+            Some(sources) => {
+                confirm(format!("The plan may come from untrusted content ({}). Allow {} {}?", sources, tool_name, arguments)).await?
+            }
+            None if policy_confirms => confirm(format!("Allow {} {}?", tool_name, arguments)).await?,
+            None => {}
+        }
+
+        let result = self.run_tool(tool_name, arguments.clone()).await;
+        if !self.hooks.is_configured(HookEvent::PostTool) {
+            return result;
+        }
+        let payload = match &result {
+            Ok(output) => serde_json::json!({ "tool": tool_name, "arguments": arguments, "result": output }),
+            Err(e) => serde_json::json!({ "tool": tool_name, "arguments": arguments, "error": e.to_string() }),
+        };
+        match self.hooks.run(HookEvent::PostTool, payload).await.map_err(hook_error)? {
+            HookOutcome::Continue(payload) => match payload.get("result") {
+                Some(output) if result.is_ok() => Ok(output.clone()),
+                _ => result,
+            },
+            HookOutcome::Veto(reason) => Err(ToolError::PermissionDenied {
+                resource: format!("result of tool '{}' (rejected by post_tool hook: {})", tool_name, reason),
+            }),
+        }
+    }
+
+    async fn run_tool(&self, tool_name: &str, arguments: Value) -> Result<Value, ToolError> {
         tracing::info!("Attempting to execute tool '{}' with arguments: {:?}", tool_name, arguments);
         if let Some(tool) = self.tool_registry.get_tool(tool_name) {
+            // Held until the tool finishes so the download cap covers the whole transfer.
+            let _download_permit = match tool.network_target(&arguments) {
+                Some(domain) => Some(self.network_limiter.acquire(&domain).await),
+                None => None,
+            };
             match self.security_policy {
                 SecurityPolicy::AllowAll => {
                     tracing::debug!("Executing tool '{}' under AllowAll security policy.", tool_name);
@@ -45,4 +168,32 @@ impl<'a> ToolExecutionEngine<'a> {
             Err(ToolError::Other { message: format!("Tool '{}' not found", tool_name) })
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::events::EventBus;
+    use crate::tools::registry::ToolRegistry;
+
+    #[tokio::test]
+    async fn test_tool_execution_is_reported_on_event_bus() {
+        let config = Config::default();
+        let registry = ToolRegistry::new(&config);
+        let engine = ToolExecutionEngine::new(&registry, SecurityPolicy::AllowAll);
+        let mut events = EventBus::global().subscribe();
+
+        assert!(engine.execute_tool_call("MissingEventBusTool", serde_json::json!({})).await.is_err());
+
+        let mut seen = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            match event {
+                UiEvent::ToolStarted { tool, .. } if tool == "MissingEventBusTool" => seen.push("started"),
+                UiEvent::ToolFinished { tool, success: false, .. } if tool == "MissingEventBusTool" => seen.push("failed"),
+                _ => {}
+            }
+        }
+        assert_eq!(seen, vec!["started", "failed"]);
+    }
+}
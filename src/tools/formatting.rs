@@ -0,0 +1,149 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use similar::TextDiff;
+use std::fs;
+use std::path::Path;
+use tokio::process::Command;
+
+use crate::config::EditConfig;
+use crate::tools::session_env;
+
+const PRETTIER_EXTENSIONS: &[&str] = &[
+    "js", "jsx", "ts", "tsx", "mjs", "cjs", "json", "css", "scss", "html", "vue", "md", "yaml", "yml",
+];
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct FormatReport {
+    pub formatter: String,
+    pub changed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+}
+
+pub fn default_formatter_for(extension: &str) -> Option<&'static str> {
+    match extension {
+        "rs" => Some("rustfmt {file}"),
+        "py" | "pyi" => Some("black -q {file}"),
+        ext if PRETTIER_EXTENSIONS.contains(&ext) => Some("prettier --write {file}"),
+        _ => None,
+    }
+}
+
+pub fn formatter_command_for(path: &Path, edit_config: &EditConfig) -> Option<String> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    edit_config
+        .formatters
+        .get(&extension)
+        .cloned()
+        .or_else(|| default_formatter_for(&extension).map(String::from))
+}
+
+fn build_command(template: &str, path: &Path) -> Result<Command> {
+    let file = path.to_string_lossy();
+    let mut parts: Vec<String> = template.split_whitespace().map(String::from).collect();
+    if parts.is_empty() {
+        return Err(anyhow!("Formatter command is empty"));
+    }
+    if parts.iter().any(|p| p.contains("{file}")) {
+        for part in parts.iter_mut() {
+            *part = part.replace("{file}", &file);
+        }
+    } else {
+        parts.push(file.to_string());
+    }
+    let mut command = session_env::command(&parts[0]);
+    command.args(&parts[1..]).kill_on_drop(true);
+    Ok(command)
+}
+
+// Runs the configured or auto-detected formatter on a file that was just written.
+// Returns Ok(None) when formatting is disabled or no formatter applies to the file.
+pub async fn format_written_file(path: &Path, edit_config: &EditConfig) -> Result<Option<FormatReport>> {
+    if !edit_config.format_after_write {
+        return Ok(None);
+    }
+    let Some(template) = formatter_command_for(path, edit_config) else {
+        tracing::debug!("No formatter configured for {:?}", path);
+        return Ok(None);
+    };
+
+    let before = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {:?} before formatting", path))?;
+    tracing::info!("Formatting {:?} with '{}'", path, template);
+    let output = build_command(&template, path)?
+        .output()
+        .await
+        .with_context(|| format!("Failed to run formatter '{}'", template))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Formatter '{}' failed: {}",
+            template,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let after = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {:?} after formatting", path))?;
+
+    let changed = before != after;
+    let diff = changed.then(|| {
+        TextDiff::from_lines(&before, &after)
+            .unified_diff()
+            .context_radius(2)
+            .header("written", "formatted")
+            .to_string()
+    });
+    Ok(Some(FormatReport {
+        formatter: template,
+        changed,
+        diff,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    fn edit_config(formatters: &[(&str, &str)]) -> EditConfig {
+        EditConfig {
+            format_after_write: true,
+            formatters: formatters
+                .iter()
+                .map(|(ext, cmd)| (ext.to_string(), cmd.to_string()))
+                .collect::<HashMap<_, _>>(),
+            ..EditConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_formatter_detection_prefers_config() {
+        let config = edit_config(&[("rs", "cargo fmt --")]);
+        assert_eq!(formatter_command_for(Path::new("a.rs"), &config).as_deref(), Some("cargo fmt --"));
+        assert_eq!(formatter_command_for(Path::new("a.tsx"), &config).as_deref(), Some("prettier --write {file}"));
+        assert_eq!(formatter_command_for(Path::new("a.py"), &config).as_deref(), Some("black -q {file}"));
+        assert_eq!(formatter_command_for(Path::new("README"), &config), None);
+    }
+
+    #[tokio::test]
+    async fn test_format_written_file_reports_diff() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        fs::write(&path, "alpha\nbeta\n").unwrap();
+        let config = edit_config(&[("txt", "sed -i s/alpha/ALPHA/ {file}")]);
+
+        let report = format_written_file(&path, &config).await.unwrap().unwrap();
+        assert!(report.changed);
+        assert!(report.diff.unwrap().contains("+ALPHA"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "ALPHA\nbeta\n");
+    }
+
+    #[tokio::test]
+    async fn test_format_written_file_disabled() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("main.rs");
+        fs::write(&path, "fn main(){}").unwrap();
+        let config = EditConfig::default();
+        assert_eq!(format_written_file(&path, &config).await.unwrap(), None);
+    }
+}
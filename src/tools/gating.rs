@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use crate::api::models::ToolDefinition;
+use crate::config::ToolGatingConfig;
+
+// Where an `opencode run` is: exploring until the model records a plan and it is approved,
+// executing afterwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgentPhase {
+    Explore,
+    Execute,
+}
+
+pub const EXPLORE_INSTRUCTION: &str = "You are in the exploration phase: only read-only tools are \
+available. Investigate what you need, then reply with your plan as the JSON object described in the \
+instructions. Tools that change files or run commands become available once the plan is approved.";
+
+// The read-only built-in tools, the default "read" group. GitTool is not one: it can commit
+// and push; GitHistoryTool covers reading history.
+pub const READ_ONLY_TOOLS: &[&str] = &[
+    "FileReadTool",
+    "FileSearchTool",
+    "CodeSearchTool",
+    "ListFilesTool",
+    "list_code_definition_names",
+    "GitHistoryTool",
+    "web_search",
+    "UrlFetchTool",
+    "DocsSearchTool",
+    "PackageLookupTool",
+    "SecurityAuditTool",
+    "NotesTool",
+    "MemoryTool",
+    "TodoTool",
+    "AskUserTool",
+    "EnvTool",
+    "ClipboardReadTool",
+];
+
+// Tools that only read: no file, repository, memory or process changes and no questions to
+// the user. Safe to run in a dry run or right after untrusted content.
+pub const SIDE_EFFECT_FREE_TOOLS: &[&str] = &[
+    "FileReadTool",
+    "FileSearchTool",
+    "CodeSearchTool",
+    "ListFilesTool",
+    "list_code_definition_names",
+    "GitHistoryTool",
+    "web_search",
+    "UrlFetchTool",
+    "DocsSearchTool",
+    "PackageLookupTool",
+    "EnvTool",
+    "ClipboardReadTool",
+];
+
+// Decides which tools are sent to the model in each phase. A phase lists group names from
+// `[tool_gating.groups]`; an empty list exposes every tool.
+#[derive(Debug, Clone, Default)]
+pub struct ToolGate {
+    enabled: bool,
+    groups: HashMap<String, Vec<String>>,
+    explore: Vec<String>,
+    execute: Vec<String>,
+}
+
+impl ToolGate {
+    pub fn new(config: &ToolGatingConfig) -> Self {
+        let mut groups = config.groups.clone();
+        groups.entry("read".to_string()).or_insert_with(|| READ_ONLY_TOOLS.iter().map(|t| t.to_string()).collect());
+        ToolGate { enabled: config.enabled, groups, explore: config.explore.clone(), execute: config.execute.clone() }
+    }
+
+    // The phase a run starts in.
+    pub fn initial_phase(&self) -> AgentPhase {
+        if self.enabled { AgentPhase::Explore } else { AgentPhase::Execute }
+    }
+
+    pub fn allows(&self, phase: AgentPhase, tool: &str) -> bool {
+        let groups = match phase {
+            _ if !self.enabled => return true,
+            AgentPhase::Explore => &self.explore,
+            AgentPhase::Execute => &self.execute,
+        };
+        groups.is_empty()
+            || groups.iter().any(|group| match self.groups.get(group) {
+                Some(tools) => tools.iter().any(|t| t == tool),
+                None => {
+                    tracing::warn!("Unknown tool group '{}' in [tool_gating]", group);
+                    false
+                }
+            })
+    }
+
+    pub fn filter(&self, phase: AgentPhase, definitions: Vec<ToolDefinition>) -> Vec<ToolDefinition> {
+        definitions.into_iter().filter(|d| self.allows(phase, &d.function.name)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explore_phase_exposes_only_read_group() {
+        let mut config = ToolGatingConfig { enabled: true, ..ToolGatingConfig::default() };
+        config.groups.insert("build".to_string(), vec!["execute_command".to_string()]);
+        config.execute = vec!["read".to_string(), "build".to_string()];
+        let gate = ToolGate::new(&config);
+
+        assert_eq!(gate.initial_phase(), AgentPhase::Explore);
+        assert!(gate.allows(AgentPhase::Explore, "FileReadTool"));
+        assert!(!gate.allows(AgentPhase::Explore, "FileWriteTool"));
+        assert!(gate.allows(AgentPhase::Execute, "execute_command"));
+        assert!(!gate.allows(AgentPhase::Execute, "FileWriteTool"));
+        assert!(ToolGate::new(&ToolGatingConfig::default()).allows(AgentPhase::Explore, "FileWriteTool"));
+    }
+}
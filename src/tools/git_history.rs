@@ -0,0 +1,209 @@
+use crate::tools::session_env;
+use crate::tools::{CliTool, ToolError};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::Value;
+
+// Unit separator between `git log` fields, which commit subjects never contain.
+const FIELD_SEPARATOR: char = '\u{1f}';
+const LOG_FORMAT: &str = "--format=%H%x1f%an%x1f%ad%x1f%s";
+const DEFAULT_MAX_COMMITS: u64 = 20;
+// Patches and blames of large ranges are cut here so one call cannot flood the context.
+const MAX_OUTPUT_CHARS: usize = 20_000;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommitSummary {
+    pub hash: String,
+    pub author: String,
+    pub date: String,
+    pub subject: String,
+}
+
+// Answers questions about how code got the way it is: which commits added or removed some
+// text (`git log -S`), the history of a line range or function (`git log -L`), who last
+// changed each line (`git blame`), and what a commit changed (`git show`). Read-only.
+#[derive(Debug)]
+pub struct GitHistoryTool;
+
+// Commit lines printed with LOG_FORMAT; anything else (patch text) is skipped.
+pub fn parse_commits(output: &str) -> Vec<CommitSummary> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, FIELD_SEPARATOR);
+            let (hash, author, date, subject) = (fields.next()?, fields.next()?, fields.next()?, fields.next()?);
+            (hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit())).then(|| CommitSummary {
+                hash: hash.to_string(),
+                author: author.to_string(),
+                date: date.to_string(),
+                subject: subject.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn truncate(mut text: String) -> (String, bool) {
+    if text.len() <= MAX_OUTPUT_CHARS {
+        return (text, false);
+    }
+    let mut cut = MAX_OUTPUT_CHARS;
+    while !text.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    text.truncate(cut);
+    (text, true)
+}
+
+impl GitHistoryTool {
+    fn invalid(&self, details: impl Into<String>) -> ToolError {
+        ToolError::InvalidArguments { tool_name: self.name(), details: details.into() }
+    }
+
+    async fn git(&self, args: &[String]) -> Result<String, ToolError> {
+        let output = session_env::command("git")
+            .args(args)
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| ToolError::Other { message: format!("Failed to run git: {}", e) })?;
+        if !output.status.success() {
+            return Err(ToolError::ExecutionFailed {
+                command: format!("git {}", args.join(" ")),
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            });
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    // `start,end` from the line arguments, or `:function` when a function is named.
+    fn line_range(&self, args: &Value) -> Result<String, ToolError> {
+        if let Some(function) = args.get("function").and_then(Value::as_str) {
+            return Ok(format!(":{}", function));
+        }
+        let line = |key: &str| args.get(key).and_then(Value::as_u64).filter(|n| *n >= 1);
+        match (line("start_line"), line("end_line")) {
+            (Some(start), Some(end)) if start <= end => Ok(format!("{},{}", start, end)),
+            (Some(start), None) => Ok(format!("{},{}", start, start)),
+            _ => Err(self.invalid("Pass 'function', or 'start_line' (and optionally 'end_line', not before it)")),
+        }
+    }
+}
+
+#[async_trait]
+impl CliTool for GitHistoryTool {
+    fn name(&self) -> String {
+        "GitHistoryTool".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Consults git history instead of guessing why code is the way it is. Operations: \"search\" lists commits that added or removed text (git log -S, or -G with regex), \"log\" lists commits that changed a function or line range of a file (git log -L), \"blame\" shows who last changed each line of a range, \"show\" shows a commit's message and diff. Args: {\"operation\": \"search\" | \"log\" | \"blame\" | \"show\", \"text\": string (search), \"regex\": boolean (optional, search), \"path\": string (log, blame; optional for search and show), \"function\": string (optional, log), \"start_line\": number (log, blame), \"end_line\": number (optional), \"commit\": string (show), \"max_commits\": number (optional, default 20), \"patch\": boolean (optional, include diffs in log)}".to_string()
+    }
+
+    fn parameters_schema(&self) -> Result<Value> {
+        Ok(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "operation": { "type": "string", "enum": ["search", "log", "blame", "show"] },
+                "text": { "type": "string", "description": "Text whose addition or removal to look for (search)." },
+                "regex": { "type": "boolean", "description": "Treat 'text' as a regex matched against changed lines (git log -G)." },
+                "path": { "type": "string", "description": "File to inspect; limits search and show to it." },
+                "function": { "type": "string", "description": "Function whose history to follow (log)." },
+                "start_line": { "type": "integer", "description": "First line of the range, 1-based (log, blame)." },
+                "end_line": { "type": "integer", "description": "Last line of the range, inclusive (default: start_line)." },
+                "commit": { "type": "string", "description": "Commit to show (show)." },
+                "max_commits": { "type": "integer", "description": "Most commits to list (default: 20)." },
+                "patch": { "type": "boolean", "description": "Include the diffs of listed commits (log)." }
+            },
+            "required": ["operation"]
+        }))
+    }
+
+    fn examples(&self) -> Vec<Value> {
+        vec![
+            serde_json::json!({ "operation": "search", "text": "ensure_token_limit" }),
+            serde_json::json!({ "operation": "log", "path": "src/context/mod.rs", "function": "ensure_token_limit" }),
+        ]
+    }
+
+    async fn execute(&self, args: Value) -> Result<Value, ToolError> {
+        let operation = args
+            .get("operation")
+            .and_then(Value::as_str)
+            .ok_or_else(|| self.invalid("Missing or invalid 'operation' argument"))?;
+        let path = args.get("path").and_then(Value::as_str);
+        let max_commits = args.get("max_commits").and_then(Value::as_u64).unwrap_or(DEFAULT_MAX_COMMITS);
+        let mut git_args: Vec<String> = Vec::new();
+
+        match operation {
+            "search" => {
+                let text = args.get("text").and_then(Value::as_str).ok_or_else(|| self.invalid("'search' needs 'text'"))?;
+                let flag = if args.get("regex").and_then(Value::as_bool).unwrap_or(false) { "-G" } else { "-S" };
+                git_args.extend(["log".into(), format!("{}{}", flag, text), format!("--max-count={}", max_commits)]);
+                git_args.extend(["--date=short".into(), LOG_FORMAT.into()]);
+                if let Some(path) = path {
+                    git_args.extend(["--".into(), path.into()]);
+                }
+                let commits = parse_commits(&self.git(&git_args).await?);
+                Ok(serde_json::json!({ "count": commits.len(), "commits": commits }))
+            }
+            "log" => {
+                let path = path.ok_or_else(|| self.invalid("'log' needs 'path'"))?;
+                let range = self.line_range(&args)?;
+                let patch = args.get("patch").and_then(Value::as_bool).unwrap_or(false);
+                git_args.extend(["log".into(), format!("-L{}:{}", range, path), format!("--max-count={}", max_commits)]);
+                git_args.extend(["--date=short".into(), LOG_FORMAT.into()]);
+                let output = self.git(&git_args).await?;
+                let commits = parse_commits(&output);
+                if !patch {
+                    return Ok(serde_json::json!({ "count": commits.len(), "commits": commits }));
+                }
+                let (output, truncated) = truncate(output);
+                Ok(serde_json::json!({ "count": commits.len(), "commits": commits, "patches": output, "truncated": truncated }))
+            }
+            "blame" => {
+                let path = path.ok_or_else(|| self.invalid("'blame' needs 'path'"))?;
+                let range = self.line_range(&args)?;
+                git_args.extend(["blame".into(), "--date=short".into(), format!("-L{}", range), "--".into(), path.into()]);
+                let (output, truncated) = truncate(self.git(&git_args).await?);
+                Ok(serde_json::json!({ "blame": output, "truncated": truncated }))
+            }
+            "show" => {
+                let commit = args.get("commit").and_then(Value::as_str).ok_or_else(|| self.invalid("'show' needs 'commit'"))?;
+                if commit.starts_with('-') {
+                    return Err(self.invalid("'commit' must be a revision, not an option"));
+                }
+                git_args.extend(["show".into(), "--stat".into(), "--patch".into(), "--format=fuller".into(), commit.into()]);
+                if let Some(path) = path {
+                    git_args.extend(["--".into(), path.into()]);
+                }
+                let (output, truncated) = truncate(self.git(&git_args).await?);
+                Ok(serde_json::json!({ "show": output, "truncated": truncated }))
+            }
+            other => Err(self.invalid(format!("Unsupported operation '{}'; use search, log, blame or show", other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commits_skips_patch_lines() {
+        let hash = "0123456789abcdef0123456789abcdef01234567";
+        let output = format!(
+            "{h}\u{1f}Ana\u{1f}2024-05-01\u{1f}Evict oldest messages first\n\ndiff --git a/src/context/mod.rs b/src/context/mod.rs\n+    fn ensure_token_limit(&mut self) {{\n",
+            h = hash
+        );
+        assert_eq!(
+            parse_commits(&output),
+            vec![CommitSummary {
+                hash: hash.to_string(),
+                author: "Ana".to_string(),
+                date: "2024-05-01".to_string(),
+                subject: "Evict oldest messages first".to_string(),
+            }]
+        );
+    }
+}
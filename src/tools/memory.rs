@@ -0,0 +1,83 @@
+use crate::context::memory::ProjectMemory;
+use crate::tools::{CliTool, ToolError};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+
+// Lets the agent save facts worth knowing in future sessions of this project. Remembered facts
+// are already in every request, so there is no read action.
+#[derive(Debug)]
+pub struct MemoryTool {
+    memory: ProjectMemory,
+}
+
+impl MemoryTool {
+    pub fn new(memory: ProjectMemory) -> Self {
+        MemoryTool { memory }
+    }
+
+    fn failed(e: anyhow::Error) -> ToolError {
+        ToolError::Other { message: format!("{:#}", e) }
+    }
+}
+
+#[async_trait]
+impl CliTool for MemoryTool {
+    fn name(&self) -> String {
+        "MemoryTool".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Saves a durable fact about this project for future sessions (how to build or test it, conventions, ongoing migrations), or forgets one that is no longer true. Only save facts that will still matter next week; remembered facts are shown to you at the start of every request with their ids. Args: {\"action\": \"remember\" | \"forget\", \"fact\": string (for remember), \"id\": number (for forget)}".to_string()
+    }
+
+    fn parameters_schema(&self) -> Result<Value> {
+        Ok(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": { "type": "string", "enum": ["remember", "forget"] },
+                "fact": { "type": "string", "description": "One self-contained sentence." },
+                "id": { "type": "integer", "description": "Id of the fact to forget." }
+            },
+            "required": ["action"]
+        }))
+    }
+
+    async fn execute(&self, args: Value) -> Result<Value, ToolError> {
+        let invalid = |details: &str| ToolError::InvalidArguments { tool_name: self.name(), details: details.to_string() };
+        match args.get("action").and_then(Value::as_str) {
+            Some("remember") => {
+                let fact = args.get("fact").and_then(Value::as_str).ok_or_else(|| invalid("'fact' is required for remember"))?;
+                let id = self.memory.remember(fact).map_err(Self::failed)?;
+                Ok(serde_json::json!({ "status": "remembered", "id": id }))
+            }
+            Some("forget") => {
+                let id = args.get("id").and_then(Value::as_u64).ok_or_else(|| invalid("'id' is required for forget"))?;
+                let forgotten = self.memory.forget(id as usize).map_err(Self::failed)?;
+                Ok(serde_json::json!({ "status": "forgotten", "fact": forgotten.fact }))
+            }
+            _ => Err(invalid("'action' must be \"remember\" or \"forget\"")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_remember_and_forget() {
+        let dir = tempfile::tempdir().unwrap();
+        let memory = ProjectMemory::new(dir.path().join("memory.json"));
+        let tool = MemoryTool::new(memory.clone());
+
+        let saved = tool.execute(json!({ "action": "remember", "fact": "Run migrations with `just migrate`" })).await.unwrap();
+        assert_eq!(saved["id"], 1);
+        assert_eq!(memory.facts().unwrap().len(), 1);
+
+        let forgotten = tool.execute(json!({ "action": "forget", "id": 1 })).await.unwrap();
+        assert_eq!(forgotten["fact"], "Run migrations with `just migrate`");
+        assert!(matches!(tool.execute(json!({ "action": "forget" })).await, Err(ToolError::InvalidArguments { .. })));
+    }
+}
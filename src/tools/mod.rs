@@ -3,7 +3,40 @@ pub mod code_intelligence;
 pub mod command_execution;
 pub mod web_search;
 pub mod tool_result_format;
-use crate::config::UserToolConfig;
+pub mod formatting;
+pub mod write_checks;
+pub mod text_conventions;
+pub mod symlinks;
+pub mod snapshot;
+pub mod rate_limit;
+pub mod url_fetch;
+pub mod docs_search;
+pub mod package_lookup;
+pub mod security_audit;
+pub mod plugin;
+pub mod wasm_sandbox;
+pub mod path_policy;
+pub mod notes;
+pub mod memory;
+pub mod todo;
+pub mod ask_user;
+pub mod docker;
+pub mod devcontainer;
+pub mod gating;
+pub mod git_history;
+pub mod compact;
+pub mod process;
+pub mod env_vars;
+pub mod clipboard;
+pub mod run_snippet;
+pub mod session_env;
+pub mod injection;
+pub mod policy;
+#[cfg(feature = "browser")]
+pub mod browser;
+#[cfg(feature = "database")]
+pub mod database;
+use crate::config::{CheckFailureAction, Config, EditConfig, UserToolConfig};
 pub mod execution;
 use async_trait::async_trait;
 use anyhow::{Context, Result}; 
@@ -11,10 +44,12 @@ use rust_search::SearchBuilder;
 use thiserror::Error;
 use serde_json::Value;
 use tracing;
-use std::process::Command;
 use std::env;
 use std::path::{Path, PathBuf};
 use std::fs;
+use snapshot::SnapshotStore;
+use text_conventions::TextConventions;
+use symlinks::SymlinkMode;
 
 #[derive(Debug, Error)]
 pub enum ToolError {
@@ -43,17 +78,34 @@ pub enum ToolError {
 #[derive(Debug)]
 pub struct FileReadTool;
 
-#[derive(Debug)]
-pub struct FileWriteTool;
+#[derive(Debug, Default)]
+pub struct FileWriteTool {
+    edit_config: EditConfig,
+    snapshots: SnapshotStore,
+}
 
-#[derive(Debug)]
-pub struct ShellCommandTool;
+impl FileWriteTool {
+    pub fn new(config: &Config, snapshots: SnapshotStore) -> Self {
+        FileWriteTool {
+            edit_config: config.edit.clone(),
+            snapshots,
+        }
+    }
+}
 
 #[derive(Debug)]
-pub struct GitTool;
+pub struct ShellCommandTool {
+    devcontainer: devcontainer::DevcontainerTarget,
+}
+
+impl ShellCommandTool {
+    pub fn new(devcontainer: devcontainer::DevcontainerTarget) -> Self {
+        ShellCommandTool { devcontainer }
+    }
+}
 
 #[derive(Debug)]
-pub struct WebSearchTool;
+pub struct GitTool;
 
 #[derive(Debug)]
 pub struct CodeSearchTool;
@@ -64,8 +116,16 @@ pub struct FileSearchTool;
 #[derive(Debug)]
 pub struct CreateDirectoryTool;
 
-#[derive(Debug)]
-pub struct DeleteTool;
+#[derive(Debug, Default)]
+pub struct DeleteTool {
+    snapshots: SnapshotStore,
+}
+
+impl DeleteTool {
+    pub fn new(snapshots: SnapshotStore) -> Self {
+        DeleteTool { snapshots }
+    }
+}
 
 #[derive(Debug)]
 pub struct ListFilesTool;
@@ -77,6 +137,7 @@ pub struct UserDefinedTool {
     input_schema_val: Value, 
     compiled_schema: jsonschema::Validator, 
     command_template: String,
+    sandbox: Option<wasm_sandbox::WasmSandbox>,
 }
 
 impl UserDefinedTool {
@@ -96,10 +157,57 @@ impl UserDefinedTool {
             input_schema_val,
             compiled_schema,
             command_template: config.command_template.clone(),
+            sandbox: wasm_sandbox::WasmSandbox::from_config(config)?,
         })
     }
 }
 
+impl UserDefinedTool {
+    async fn execute_sandboxed(&self, sandbox: &wasm_sandbox::WasmSandbox, args: Value) -> Result<Value, ToolError> {
+        let invalid = |details: String| ToolError::InvalidArguments { tool_name: self.name(), details };
+        let mut argv = wasm_sandbox::split_template(&self.command_template).map_err(|e| invalid(e.to_string()))?;
+        if let Value::Object(map) = &args {
+            for (key, value) in map {
+                let value_str = match value {
+                    Value::String(s) => s.clone(),
+                    Value::Number(n) => n.to_string(),
+                    Value::Bool(b) => b.to_string(),
+                    _ => return Err(invalid(format!("Unsupported argument type for key '{}'", key))),
+                };
+                let placeholder = format!("{{{}}}", key);
+                for arg in argv.iter_mut() {
+                    *arg = arg.replace(&placeholder, &value_str);
+                }
+            }
+        }
+
+        tracing::info!("Executing sandboxed user tool '{}' with args: {:?}", self.name, argv);
+        let output = sandbox
+            .command(&argv)
+            .output()
+            .await
+            .map_err(|e| ToolError::Other {
+                message: format!(
+                    "Failed to start {} for sandboxed tool '{}': {}",
+                    wasm_sandbox::WASM_RUNTIME,
+                    self.name,
+                    e
+                ),
+            })?;
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        if output.status.success() {
+            Ok(Value::String(stdout))
+        } else {
+            tracing::error!("Sandboxed user tool '{}' failed. Stderr: {}", self.name, stderr);
+            Err(ToolError::ExecutionFailed {
+                command: argv.join(" "),
+                stderr,
+            })
+        }
+    }
+}
+
 #[async_trait]
 impl CliTool for UserDefinedTool {
     fn name(&self) -> String {
@@ -115,6 +223,10 @@ impl CliTool for UserDefinedTool {
         Ok(self.input_schema_val.clone())
     }
 
+    fn spawns_commands(&self) -> bool {
+        true
+    }
+
     async fn execute(&self, args: Value) -> Result<Value, ToolError> {
         
         let errors: Vec<String> = self.compiled_schema
@@ -130,7 +242,10 @@ impl CliTool for UserDefinedTool {
             });
         }
 
-        
+        if let Some(sandbox) = &self.sandbox {
+            return self.execute_sandboxed(sandbox, args).await;
+        }
+
         let mut command_string = self.command_template.clone();
         if let Value::Object(map) = args {
             for (key, value) in map {
@@ -162,10 +277,12 @@ impl CliTool for UserDefinedTool {
         
         
         tracing::info!("Executing user tool '{}' command: {}", self.name, command_string);
-        let output = Command::new("sh")
+        let output = session_env::command("sh")
             .arg("-c")
             .arg(&command_string) 
+            .kill_on_drop(true)
             .output()
+            .await
             .map_err(|e| ToolError::Other {
                 message: format!("Failed to execute command for tool '{}': {}", self.name, e),
             })?;
@@ -186,6 +303,10 @@ impl CliTool for UserDefinedTool {
     }
 }
 
+// Caps on CodeSearchTool output: characters per matched line, and bytes overall.
+const CODE_SEARCH_MAX_COLUMNS: usize = 300;
+const CODE_SEARCH_MAX_BYTES: usize = 200_000;
+
 #[async_trait]
 impl CliTool for CodeSearchTool {
     fn name(&self) -> String {
@@ -210,47 +331,30 @@ impl CliTool for CodeSearchTool {
             details: "Missing or invalid 'pattern' argument".to_string(),
         })?;
         let search_path = args.get("path").and_then(|v| v.as_str()).unwrap_or(".");
-        let output = std::process::Command::new("rg")
+        let output = session_env::command("rg")
+            .args(["--max-columns", &CODE_SEARCH_MAX_COLUMNS.to_string()])
+            .arg("--")
             .arg(pattern)
             .arg(search_path)
+            .kill_on_drop(true)
             .output()
+            .await
             .map_err(|e| ToolError::Other { message: format!("Failed to run ripgrep: {}", e) })?;
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let stdout = String::from_utf8_lossy(&output.stdout);
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
         let code = output.status.code().unwrap_or(-1);
         if !output.status.success() && !stdout.is_empty() {
             return Err(ToolError::ExecutionFailed { command: format!("rg {} {}", pattern, search_path), stderr });
         }
-        Ok(serde_json::json!({ "stdout": stdout, "exit_code": code }))
-    }
-}
-
-#[async_trait]
-impl CliTool for WebSearchTool {
-    fn name(&self) -> String {
-        "WebSearchTool".to_string()
-    }
-    fn description(&self) -> String {
-        "Fetches the contents of a URL. Args: {\"url\": string}".to_string()
-    }
-    fn parameters_schema(&self) -> Result<Value> {
-        Ok(serde_json::json!({
-            "type": "object",
-            "properties": {
-                "url": { "type": "string" }
-            },
-            "required": ["url"]
-        }))
-    }
-    async fn execute(&self, args: Value) -> Result<Value, ToolError> {
-        let url = args.get("url").and_then(|v| v.as_str()).ok_or_else(|| ToolError::InvalidArguments {
-            tool_name: self.name(),
-            details: "Missing or invalid 'url' argument".to_string(),
-        })?;
-        let resp = reqwest::get(url).await.map_err(|e| ToolError::NetworkError { source: anyhow::anyhow!(e) })?;
-        let status = resp.status().as_u16();
-        let content = resp.text().await.map_err(|e| ToolError::NetworkError { source: anyhow::anyhow!(e) })?;
-        Ok(serde_json::json!({ "status": status, "content": content }))
+        // A common pattern can match most of the tree; only whole lines up to the cap are kept.
+        let truncated = stdout.len() > CODE_SEARCH_MAX_BYTES;
+        let stdout = if truncated {
+            let cut = stdout.as_bytes()[..CODE_SEARCH_MAX_BYTES].iter().rposition(|b| *b == b'\n').unwrap_or(0);
+            format!("{}\n[output truncated]", &stdout[..cut])
+        } else {
+            stdout.into_owned()
+        };
+        Ok(serde_json::json!({ "stdout": stdout, "exit_code": code, "truncated": truncated }))
     }
 }
 
@@ -272,6 +376,9 @@ impl CliTool for GitTool {
             "required": ["operation"]
         }))
     }
+    fn examples(&self) -> Vec<Value> {
+        vec![serde_json::json!({ "operation": "status" })]
+    }
     async fn execute(&self, args: Value) -> Result<Value, ToolError> {
         let operation = args.get("operation").and_then(|v| v.as_str()).ok_or_else(|| ToolError::InvalidArguments {
             tool_name: self.name(),
@@ -279,9 +386,11 @@ impl CliTool for GitTool {
         })?;
         match operation {
             "status" => {
-                let output = std::process::Command::new("git")
+                let output = session_env::command("git")
                     .arg("status")
+                    .kill_on_drop(true)
                     .output()
+                    .await
                     .map_err(|e| ToolError::Other { message: format!("Failed to run git status: {}", e) })?;
                 let stdout = String::from_utf8_lossy(&output.stdout).to_string();
                 let stderr = String::from_utf8_lossy(&output.stderr).to_string();
@@ -317,6 +426,9 @@ impl CliTool for ShellCommandTool {
             "required": ["command"]
         }))
     }
+    fn examples(&self) -> Vec<Value> {
+        vec![serde_json::json!({ "command": "cargo", "args": ["test", "--", "parser"] })]
+    }
     async fn execute(&self, args: Value) -> Result<Value, ToolError> {
         let command = args.get("command").and_then(|v| v.as_str()).ok_or_else(|| ToolError::InvalidArguments {
             tool_name: self.name(),
@@ -325,10 +437,9 @@ impl CliTool for ShellCommandTool {
         let arg_list: Vec<String> = args.get("args")
             .and_then(|v| v.as_array())
             .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
-            .unwrap_or_else(Vec::new);
-        let output = std::process::Command::new(command)
-            .args(&arg_list)
-            .output()
+            .unwrap_or_default();
+        let output = self.devcontainer.output(command, &arg_list, None)
+            .await
             .map_err(|e| ToolError::Other { message: format!("Failed to execute command: {}", e) })?;
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
@@ -346,18 +457,39 @@ impl CliTool for FileWriteTool {
         "FileWriteTool".to_string()
     }
     fn description(&self) -> String {
-        "Writes content to a file. Args: {\"path\": string, \"content\": string}".to_string()
+        "Writes content to a file. An existing file keeps its line endings, byte order mark, trailing-newline convention and permissions unless overridden. A symlink is written through to its target unless symlink is \"link\", which replaces the link with a regular file. Args: {\"path\": string, \"content\": string, \"symlink\": \"target\" | \"link\" (optional), \"line_ending\": \"lf\" | \"crlf\" (optional), \"bom\": boolean (optional), \"trailing_newline\": boolean (optional)}".to_string()
     }
     fn parameters_schema(&self) -> Result<Value> {
         Ok(serde_json::json!({
             "type": "object",
             "properties": {
                 "path": { "type": "string" },
-                "content": { "type": "string" }
+                "content": { "type": "string" },
+                "symlink": {
+                    "type": "string",
+                    "enum": ["target", "link"],
+                    "description": "If path is a symlink: write the file it points to (\"target\", default) or replace the link itself with a regular file (\"link\")."
+                },
+                "line_ending": {
+                    "type": "string",
+                    "enum": ["lf", "crlf"],
+                    "description": "Line endings to write (default: those of the existing file)."
+                },
+                "bom": {
+                    "type": "boolean",
+                    "description": "Whether to start the file with a UTF-8 byte order mark (default: as the existing file)."
+                },
+                "trailing_newline": {
+                    "type": "boolean",
+                    "description": "Whether the file ends with a newline (default: as the existing file)."
+                }
             },
             "required": ["path", "content"]
         }))
     }
+    fn examples(&self) -> Vec<Value> {
+        vec![serde_json::json!({ "path": "src/lib.rs", "content": "pub mod parser;\n" })]
+    }
     async fn execute(&self, args: Value) -> Result<Value, ToolError> {
         let path = args.get("path").and_then(|v| v.as_str()).ok_or_else(|| ToolError::InvalidArguments {
             tool_name: self.name(),
@@ -367,14 +499,85 @@ impl CliTool for FileWriteTool {
             tool_name: self.name(),
             details: "Missing or invalid 'content' argument".to_string(),
         })?;
-        std::fs::write(path, content).map_err(|e| {
+        let mode = symlinks::symlink_mode(&args, SymlinkMode::Target)
+            .map_err(|details| ToolError::InvalidArguments { tool_name: self.name(), details })?;
+        let target = symlinks::resolve(Path::new(path), mode).map_err(|e| ToolError::Other {
+            message: format!("Failed to resolve symlink '{}': {}", path, e),
+        })?;
+        let previous = std::fs::read_to_string(&target).ok();
+        let mut conventions = TextConventions::detect(previous.as_deref().unwrap_or(content));
+        if let Some(line_ending) = args.get("line_ending").filter(|v| !v.is_null()) {
+            conventions.line_ending = serde_json::from_value(line_ending.clone()).map_err(|_| ToolError::InvalidArguments {
+                tool_name: self.name(),
+                details: "'line_ending' must be \"lf\" or \"crlf\"".to_string(),
+            })?;
+        }
+        if let Some(bom) = args.get("bom").and_then(|v| v.as_bool()) {
+            conventions.bom = bom;
+        }
+        if let Some(trailing_newline) = args.get("trailing_newline").and_then(|v| v.as_bool()) {
+            conventions.trailing_newline = trailing_newline;
+        }
+        let content = &conventions.apply(content);
+        if !self.edit_config.checks.is_empty() {
+            let failures = write_checks::run_checks(&self.edit_config.checks, &target, previous.as_deref(), content);
+            if !failures.is_empty() {
+                tracing::warn!("Write to '{}' rejected by {} check(s)", path, failures.len());
+                match self.edit_config.on_check_failure {
+                    CheckFailureAction::Block => {
+                        let summary: Vec<String> = failures.iter().map(|f| format!("{}: {}", f.check, f.message)).collect();
+                        return Err(ToolError::Other {
+                            message: format!("Write to '{}' blocked by pre-write checks: {}", path, summary.join("; ")),
+                        });
+                    }
+                    CheckFailureAction::Feedback => {
+                        return Ok(serde_json::json!({
+                            "status": "rejected",
+                            "path": path,
+                            "check_failures": failures,
+                            "instruction": "The file was NOT written. Fix the reported problems and call FileWriteTool again with corrected content."
+                        }));
+                    }
+                }
+            }
+        }
+        let write_error = |e: std::io::Error| {
             if e.kind() == std::io::ErrorKind::PermissionDenied {
                 ToolError::PermissionDenied { resource: path.to_string() }
             } else {
                 ToolError::Other { message: format!("Failed to write file: {}", e) }
             }
-        })?;
-        Ok(serde_json::json!({ "status": "success" }))
+        };
+        // Formatters may replace the file rather than rewrite it, so the mode is put back after.
+        let replaces_link = symlinks::is_symlink(&target);
+        let permissions = if replaces_link { None } else { fs::metadata(&target).ok().map(|m| m.permissions()) };
+        self.snapshots.record_before_change(&target);
+        if replaces_link {
+            fs::remove_file(&target).map_err(write_error)?;
+        }
+        std::fs::write(&target, content).map_err(write_error)?;
+        let mut result = serde_json::json!({ "status": "success" });
+        if target != Path::new(path) {
+            result["written_to"] = Value::String(target.display().to_string());
+        }
+        match formatting::format_written_file(&target, &self.edit_config).await {
+            Ok(Some(report)) => {
+                result["formatting"] = serde_json::to_value(report).unwrap_or(Value::Null);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                tracing::warn!("Formatting after write failed for '{}': {}", path, e);
+                result["formatting_error"] = Value::String(e.to_string());
+            }
+        }
+        if let Some(permissions) = permissions {
+            if fs::metadata(&target).is_ok_and(|m| m.permissions() != permissions) {
+                if let Err(e) = fs::set_permissions(&target, permissions) {
+                    tracing::warn!("Failed to restore permissions of '{}': {}", path, e);
+                }
+            }
+        }
+        Ok(result)
     }
 }
 
@@ -384,22 +587,47 @@ impl CliTool for FileReadTool {
         "FileReadTool".to_string()
     }
     fn description(&self) -> String {
-        "Reads a file from the file system. Args: {\"path\": string}".to_string()
+        "Reads a file from the file system. Pass start_line/end_line (1-based, inclusive) or a symbol name to read only that part of a large file. Args: {\"path\": string, \"start_line\": number (optional), \"end_line\": number (optional), \"symbol\": string (optional)}".to_string()
     }
     fn parameters_schema(&self) -> Result<Value> {
         Ok(serde_json::json!({
             "type": "object",
             "properties": {
-                "path": { "type": "string" }
+                "path": { "type": "string" },
+                "start_line": {
+                    "type": "integer",
+                    "description": "First line to read, 1-based (default: 1)."
+                },
+                "end_line": {
+                    "type": "integer",
+                    "description": "Last line to read, inclusive (default: end of file)."
+                },
+                "symbol": {
+                    "type": "string",
+                    "description": "Name of a function, struct, enum, trait or impl to read instead of a line range."
+                }
             },
             "required": ["path"]
         }))
     }
     async fn execute(&self, args: Value) -> Result<Value, ToolError> {
-        let path = args.get("path").and_then(|v| v.as_str()).ok_or_else(|| ToolError::InvalidArguments {
-            tool_name: self.name(),
-            details: "Missing or invalid 'path' argument".to_string(),
-        })?;
+        let invalid = |details: String| ToolError::InvalidArguments { tool_name: self.name(), details };
+        let path = args.get("path").and_then(|v| v.as_str()).ok_or_else(|| invalid("Missing or invalid 'path' argument".to_string()))?;
+        let line_arg = |key: &str| match args.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(v) => v
+                .as_u64()
+                .filter(|n| *n >= 1)
+                .map(|n| Some(n as usize))
+                .ok_or_else(|| invalid(format!("'{}' must be a positive integer", key))),
+        };
+        let start_line = line_arg("start_line")?;
+        let end_line = line_arg("end_line")?;
+        let symbol = args.get("symbol").and_then(|v| v.as_str());
+        if symbol.is_some() && (start_line.is_some() || end_line.is_some()) {
+            return Err(invalid("Pass either 'symbol' or 'start_line'/'end_line', not both".to_string()));
+        }
+
         let content = std::fs::read_to_string(path).map_err(|e| {
             if e.kind() == std::io::ErrorKind::NotFound {
                 ToolError::FileNotFound { path: path.to_string() }
@@ -409,7 +637,26 @@ impl CliTool for FileReadTool {
                 ToolError::Other { message: format!("Failed to read file: {}", e) }
             }
         })?;
-        Ok(serde_json::json!({ "content": content }))
+
+        let (start, end) = match (symbol, start_line, end_line) {
+            (None, None, None) => return Ok(serde_json::json!({ "content": content })),
+            (Some(symbol), _, _) => crate::parsing::find_symbol_lines(Path::new(path), &content, symbol)
+                .map_err(|e| invalid(e.to_string()))?
+                .ok_or_else(|| invalid(format!("Symbol '{}' not found in {}", symbol, path)))?,
+            (None, start, end) => (start.unwrap_or(1), end.unwrap_or(usize::MAX)),
+        };
+        let lines: Vec<&str> = content.lines().collect();
+        let total_lines = lines.len();
+        if start > total_lines || start > end {
+            return Err(invalid(format!("Line range {}-{} is outside the file ({} lines)", start, end, total_lines)));
+        }
+        let end = end.min(total_lines);
+        Ok(serde_json::json!({
+            "content": lines[start - 1..end].join("\n"),
+            "start_line": start,
+            "end_line": end,
+            "total_lines": total_lines,
+        }))
     }
 }
 
@@ -614,7 +861,7 @@ impl CliTool for DeleteTool {
     }
 
     fn description(&self) -> String {
-        "Deletes a file or directory. Args: {\"path\": string, \"recursive\": boolean (optional, default false)}".to_string()
+        "Deletes a file or directory. A symlink is removed itself, leaving what it points to, unless symlink is \"target\". Args: {\"path\": string, \"recursive\": boolean (optional, default false), \"symlink\": \"link\" | \"target\" (optional)}".to_string()
     }
 
     fn parameters_schema(&self) -> Result<Value> {
@@ -622,7 +869,8 @@ impl CliTool for DeleteTool {
             "type": "object",
             "properties": {
                 "path": { "type": "string", "description": "The path of the file or directory to delete." },
-                "recursive": { "type": "boolean", "description": "Whether to delete directories recursively (default: false). Required if path is a directory.", "default": false }
+                "recursive": { "type": "boolean", "description": "Whether to delete directories recursively (default: false). Required if path is a directory.", "default": false },
+                "symlink": { "type": "string", "enum": ["link", "target"], "description": "If path is a symlink: remove the link only (\"link\", default) or delete the file or directory it points to (\"target\")." }
             },
             "required": ["path"]
         }))
@@ -635,15 +883,23 @@ impl CliTool for DeleteTool {
         })?;
         let recursive = args.get("recursive").and_then(|v| v.as_bool()).unwrap_or(false);
 
-        let path = Path::new(path_str);
-
-        if !path.exists() {
-            return Err(ToolError::FileNotFound { path: path_str.to_string() });
-        }
-
-        let metadata = fs::metadata(path).map_err(|e| ToolError::Other {
-            message: format!("Failed to get metadata for '{}': {}", path_str, e),
+        let mode = symlinks::symlink_mode(&args, SymlinkMode::Link)
+            .map_err(|details| ToolError::InvalidArguments { tool_name: self.name(), details })?;
+        let target = symlinks::resolve(Path::new(path_str), mode).map_err(|e| ToolError::Other {
+            message: format!("Failed to resolve symlink '{}': {}", path_str, e),
         })?;
+        let path = target.as_path();
+
+        // Never follows a link: a symlink to a directory is removed like a file.
+        let metadata = match fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(ToolError::FileNotFound { path: path_str.to_string() });
+            }
+            Err(e) => {
+                return Err(ToolError::Other { message: format!("Failed to get metadata for '{}': {}", path_str, e) });
+            }
+        };
 
         if metadata.is_dir() {
             if recursive {
@@ -678,6 +934,9 @@ impl CliTool for DeleteTool {
                 }
             }
         } else {
+            if !metadata.file_type().is_symlink() {
+                self.snapshots.record_before_change(path);
+            }
             fs::remove_file(path).map_err(|e| {
                 tracing::error!("Failed to delete file '{}': {}", path_str, e);
                 if e.kind() == std::io::ErrorKind::PermissionDenied {
@@ -794,4 +1053,44 @@ pub trait CliTool: Send + Sync + std::fmt::Debug {
     
     
     async fn execute(&self, args: Value) -> Result<Value, ToolError>;
-}
\ No newline at end of file
+
+    // Domain this call will contact, used for rate limiting. None for local-only tools.
+    fn network_target(&self, _args: &Value) -> Option<String> {
+        None
+    }
+
+    // Whether the tool can start programs the model chooses, which a `shell_commands`
+    // allowlist cannot vet unless the tool is ShellCommandTool itself.
+    fn spawns_commands(&self) -> bool {
+        false
+    }
+
+    // Complete, valid argument objects shown to the model with the tool's description. Worth
+    // adding for tools whose arguments are easy to get subtly wrong.
+    fn examples(&self) -> Vec<Value> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_read_tool_reads_line_ranges_and_symbols() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lib.rs");
+        std::fs::write(&path, "fn one() {}\n\n/// Adds.\nfn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n").unwrap();
+        let path = path.to_str().unwrap();
+
+        let range = FileReadTool.execute(serde_json::json!({ "path": path, "start_line": 2, "end_line": 99 })).await.unwrap();
+        assert_eq!(range["content"], "\n/// Adds.\nfn add(a: i32, b: i32) -> i32 {\n    a + b\n}");
+        assert_eq!((range["start_line"].as_u64(), range["end_line"].as_u64()), (Some(2), Some(6)));
+
+        let symbol = FileReadTool.execute(serde_json::json!({ "path": path, "symbol": "add" })).await.unwrap();
+        assert_eq!(symbol["content"], "/// Adds.\nfn add(a: i32, b: i32) -> i32 {\n    a + b\n}");
+
+        assert!(FileReadTool.execute(serde_json::json!({ "path": path, "start_line": 7 })).await.is_err());
+        assert!(FileReadTool.execute(serde_json::json!({ "path": path, "symbol": "missing" })).await.is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use crate::tools::{CliTool, ToolError};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+const DEFAULT_TITLE: &str = "scratchpad";
+const PREVIEW_CHARS: usize = 80;
+
+// The agent's scratchpad for this session. Notes are kept out of the conversation; the
+// context manager only sends a one-line summary of each so the model knows to read them.
+// Cloning shares the same notes.
+#[derive(Debug, Clone, Default)]
+pub struct NotesStore {
+    notes: Arc<Mutex<BTreeMap<String, String>>>,
+}
+
+impl NotesStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write(&self, title: &str, content: &str) {
+        self.notes.lock().unwrap().insert(title.to_string(), content.to_string());
+    }
+
+    pub fn append(&self, title: &str, content: &str) {
+        let mut notes = self.notes.lock().unwrap();
+        let note = notes.entry(title.to_string()).or_default();
+        if !note.is_empty() && !note.ends_with('\n') {
+            note.push('\n');
+        }
+        note.push_str(content);
+    }
+
+    pub fn read(&self, title: &str) -> Option<String> {
+        self.notes.lock().unwrap().get(title).cloned()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.lock().unwrap().is_empty()
+    }
+
+    // None when there are no notes, so nothing is added to the request.
+    pub fn summary(&self) -> Option<String> {
+        let notes = self.notes.lock().unwrap();
+        if notes.is_empty() {
+            return None;
+        }
+        let mut summary = "Your scratchpad notes (read one in full with NotesTool):".to_string();
+        for (title, content) in notes.iter() {
+            let first_line = content.lines().next().unwrap_or_default();
+            let mut preview: String = first_line.chars().take(PREVIEW_CHARS).collect();
+            if preview.len() < first_line.len() {
+                preview.push('…');
+            }
+            summary.push_str(&format!("\n- {} ({} lines): {}", title, content.lines().count(), preview));
+        }
+        Some(summary)
+    }
+}
+
+#[derive(Debug)]
+pub struct NotesTool {
+    store: NotesStore,
+}
+
+impl NotesTool {
+    pub fn new(store: NotesStore) -> Self {
+        NotesTool { store }
+    }
+}
+
+#[async_trait]
+impl CliTool for NotesTool {
+    fn name(&self) -> String {
+        "NotesTool".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Keeps notes for the rest of this session outside the conversation, e.g. findings, decisions or the current plan. Args: {\"action\": \"write\" | \"append\" | \"read\", \"title\": string (optional), \"content\": string (for write/append)}".to_string()
+    }
+
+    fn parameters_schema(&self) -> Result<Value> {
+        Ok(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["write", "append", "read"],
+                    "description": "write replaces the note, append adds a line to it, read returns it."
+                },
+                "title": {
+                    "type": "string",
+                    "description": "Which note to use (default: scratchpad)."
+                },
+                "content": {
+                    "type": "string",
+                    "description": "Text to write or append."
+                }
+            },
+            "required": ["action"]
+        }))
+    }
+
+    async fn execute(&self, args: Value) -> Result<Value, ToolError> {
+        let invalid = |details: &str| ToolError::InvalidArguments { tool_name: self.name(), details: details.to_string() };
+        let action = args.get("action").and_then(Value::as_str).ok_or_else(|| invalid("Missing or invalid 'action' argument"))?;
+        let title = args.get("title").and_then(Value::as_str).unwrap_or(DEFAULT_TITLE);
+        let content = args.get("content").and_then(Value::as_str);
+        match action {
+            "write" | "append" => {
+                let content = content.ok_or_else(|| invalid("'content' is required to write or append"))?;
+                if action == "write" {
+                    self.store.write(title, content);
+                } else {
+                    self.store.append(title, content);
+                }
+                Ok(serde_json::json!({ "title": title, "status": "saved" }))
+            }
+            "read" => match self.store.read(title) {
+                Some(content) => Ok(serde_json::json!({ "title": title, "content": content })),
+                None => Err(invalid(&format!("No note titled '{}'", title))),
+            },
+            other => Err(invalid(&format!("Unknown action '{}', expected write, append or read", other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_notes_round_trip_and_summary() {
+        let store = NotesStore::new();
+        let tool = NotesTool::new(store.clone());
+        assert_eq!(store.summary(), None);
+
+        tool.execute(serde_json::json!({ "action": "write", "content": "1. read config" })).await.unwrap();
+        tool.execute(serde_json::json!({ "action": "append", "content": "2. fix parser" })).await.unwrap();
+        tool.execute(serde_json::json!({ "action": "write", "title": "bug", "content": "off by one in lexer" })).await.unwrap();
+
+        let read = tool.execute(serde_json::json!({ "action": "read" })).await.unwrap();
+        assert_eq!(read["content"], "1. read config\n2. fix parser");
+        assert_eq!(
+            store.summary().unwrap(),
+            "Your scratchpad notes (read one in full with NotesTool):\n- bug (1 lines): off by one in lexer\n- scratchpad (2 lines): 1. read config"
+        );
+        assert!(tool.execute(serde_json::json!({ "action": "read", "title": "missing" })).await.is_err());
+        assert!(tool.execute(serde_json::json!({ "action": "append" })).await.is_err());
+    }
+}
@@ -0,0 +1,420 @@
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use std::time::Duration;
+use thiserror::Error;
+
+use super::{CliTool, ToolError};
+use crate::config::NetworkConfig;
+
+const CRATES_IO_API: &str = "https://crates.io/api/v1";
+const NPM_REGISTRY: &str = "https://registry.npmjs.org";
+const PYPI_API: &str = "https://pypi.org/pypi";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Ecosystem {
+    Crates,
+    Npm,
+    Pypi,
+}
+
+impl Ecosystem {
+    // Picks the ecosystem from the manifest files present in `dir`, preferring Cargo.
+    pub fn detect(dir: &Path) -> Option<Self> {
+        if dir.join("Cargo.toml").exists() {
+            Some(Ecosystem::Crates)
+        } else if dir.join("package.json").exists() {
+            Some(Ecosystem::Npm)
+        } else if ["pyproject.toml", "requirements.txt", "setup.py"]
+            .iter()
+            .any(|f| dir.join(f).exists())
+        {
+            Some(Ecosystem::Pypi)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackageLookupInput {
+    pub name: String,
+    pub ecosystem: Option<Ecosystem>,
+    // Version or requirement currently in use, e.g. "1.0" or "^2.3.1".
+    pub current_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PackageInfo {
+    pub name: String,
+    pub ecosystem: Ecosystem,
+    pub latest_version: String,
+    pub description: Option<String>,
+    pub repository: Option<String>,
+    // Cargo features of the latest version; empty for other ecosystems.
+    pub features: Vec<String>,
+    // Minimum supported toolchain: `rust-version`, npm `engines.node` or `requires_python`.
+    pub min_toolchain: Option<String>,
+    pub outdated: Option<bool>,
+}
+
+#[derive(Error, Debug)]
+pub enum PackageLookupError {
+    #[error("Package '{0}' was not found in the registry")]
+    NotFound(String),
+    #[error("Could not detect the package ecosystem; pass \"ecosystem\" explicitly")]
+    UnknownEcosystem,
+    #[error("Network error: {0}")]
+    NetworkError(#[from] reqwest::Error),
+    #[error("Unexpected registry response: {0}")]
+    InvalidResponse(String),
+}
+
+impl From<PackageLookupError> for ToolError {
+    fn from(error: PackageLookupError) -> Self {
+        match error {
+            PackageLookupError::NetworkError(e) => ToolError::NetworkError { source: anyhow::anyhow!(e) },
+            other => ToolError::Other { message: other.to_string() },
+        }
+    }
+}
+
+fn version_numbers(version: &str) -> Vec<u64> {
+    let version = version.trim().trim_start_matches(|c: char| !c.is_ascii_digit());
+    let release = version.split(['-', '+', ' ', ',']).next().unwrap_or("");
+    release
+        .split('.')
+        .map_while(|part| part.parse::<u64>().ok())
+        .collect()
+}
+
+// Returns true when `latest` is newer than every version matched by the requirement's
+// base version, i.e. when bumping the requirement would pick up `latest`.
+pub fn is_outdated(current: &str, latest: &str) -> bool {
+    let current = version_numbers(current);
+    let latest = version_numbers(latest);
+    if current.is_empty() || latest.is_empty() {
+        return false;
+    }
+    let len = current.len().max(latest.len());
+    let pad = |v: &[u64]| (0..len).map(|i| v.get(i).copied().unwrap_or(0)).collect::<Vec<_>>();
+    pad(&latest) > pad(&current)
+}
+
+// Returns true when going from `current` to `latest` crosses a semver-incompatible boundary.
+pub fn is_major_bump(current: &str, latest: &str) -> bool {
+    let current = version_numbers(current);
+    let latest = version_numbers(latest);
+    // Cargo treats the first non-zero component as the compatibility boundary.
+    let boundary = |v: &[u64]| {
+        let idx = v.iter().position(|&n| n != 0).unwrap_or(v.len().saturating_sub(1));
+        v[..=idx.min(v.len().saturating_sub(1))].to_vec()
+    };
+    !current.is_empty() && !latest.is_empty() && boundary(&current) != boundary(&latest)
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageRegistry {
+    client: Client,
+    crates_io_api: String,
+    npm_registry: String,
+    pypi_api: String,
+}
+
+impl PackageRegistry {
+    pub fn new(config: &NetworkConfig) -> Self {
+        let client = Client::builder()
+            .user_agent(format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")))
+            .timeout(Duration::from_secs(config.fetch_timeout_seconds))
+            .build()
+            .unwrap_or_else(|e| {
+                tracing::error!("Failed to build registry client, using defaults: {}", e);
+                Client::new()
+            });
+        PackageRegistry {
+            client,
+            crates_io_api: CRATES_IO_API.to_string(),
+            npm_registry: NPM_REGISTRY.to_string(),
+            pypi_api: PYPI_API.to_string(),
+        }
+    }
+
+    // Points every ecosystem at `base_url`; used to test against a mock server.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.crates_io_api = base_url.to_string();
+        self.npm_registry = base_url.to_string();
+        self.pypi_api = base_url.to_string();
+        self
+    }
+
+    // `base` with `segments` appended, each percent-encoded, so a name like `@scope/pkg` or
+    // `../x` stays one path segment.
+    fn registry_url(base: &str, segments: &[&str]) -> Result<Url, PackageLookupError> {
+        let mut url = Url::parse(base).map_err(|e| PackageLookupError::InvalidResponse(format!("bad registry URL {}: {}", base, e)))?;
+        url.path_segments_mut()
+            .map_err(|_| PackageLookupError::InvalidResponse(format!("bad registry URL {}", base)))?
+            .pop_if_empty()
+            .extend(segments);
+        Ok(url)
+    }
+
+    async fn get_json(&self, url: Url, name: &str) -> Result<Value, PackageLookupError> {
+        let response = self.client.get(url).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(PackageLookupError::NotFound(name.to_string()));
+        }
+        Ok(response.error_for_status()?.json::<Value>().await?)
+    }
+
+    pub async fn lookup(&self, ecosystem: Ecosystem, name: &str) -> Result<PackageInfo, PackageLookupError> {
+        // URL joining would resolve these as dot segments rather than look them up.
+        if matches!(name, "" | "." | "..") {
+            return Err(PackageLookupError::NotFound(name.to_string()));
+        }
+        match ecosystem {
+            Ecosystem::Crates => self.lookup_crate(name).await,
+            Ecosystem::Npm => self.lookup_npm(name).await,
+            Ecosystem::Pypi => self.lookup_pypi(name).await,
+        }
+    }
+
+    async fn lookup_crate(&self, name: &str) -> Result<PackageInfo, PackageLookupError> {
+        let body = self.get_json(Self::registry_url(&self.crates_io_api, &["crates", name])?, name).await?;
+        let krate = &body["crate"];
+        let latest_version = krate["max_stable_version"]
+            .as_str()
+            .or_else(|| krate["newest_version"].as_str())
+            .ok_or_else(|| PackageLookupError::InvalidResponse("missing crate version".to_string()))?
+            .to_string();
+        let latest = body["versions"]
+            .as_array()
+            .and_then(|versions| versions.iter().find(|v| v["num"].as_str() == Some(latest_version.as_str())));
+        let mut features: Vec<String> = latest
+            .and_then(|v| v["features"].as_object())
+            .map(|f| f.keys().cloned().collect())
+            .unwrap_or_default();
+        features.sort();
+        Ok(PackageInfo {
+            name: name.to_string(),
+            ecosystem: Ecosystem::Crates,
+            latest_version,
+            description: krate["description"].as_str().map(|s| s.trim().to_string()),
+            repository: krate["repository"].as_str().map(String::from),
+            features,
+            min_toolchain: latest.and_then(|v| v["rust_version"].as_str()).map(String::from),
+            outdated: None,
+        })
+    }
+
+    async fn lookup_npm(&self, name: &str) -> Result<PackageInfo, PackageLookupError> {
+        let body = self.get_json(Self::registry_url(&self.npm_registry, &[name])?, name).await?;
+        let latest_version = body["dist-tags"]["latest"]
+            .as_str()
+            .ok_or_else(|| PackageLookupError::InvalidResponse("missing dist-tags.latest".to_string()))?
+            .to_string();
+        let repository = match &body["repository"] {
+            Value::String(url) => Some(url.clone()),
+            repo => repo["url"].as_str().map(String::from),
+        };
+        Ok(PackageInfo {
+            name: name.to_string(),
+            ecosystem: Ecosystem::Npm,
+            min_toolchain: body["versions"][&latest_version]["engines"]["node"].as_str().map(String::from),
+            latest_version,
+            description: body["description"].as_str().map(String::from),
+            repository,
+            features: Vec::new(),
+            outdated: None,
+        })
+    }
+
+    async fn lookup_pypi(&self, name: &str) -> Result<PackageInfo, PackageLookupError> {
+        let body = self.get_json(Self::registry_url(&self.pypi_api, &[name, "json"])?, name).await?;
+        let info = &body["info"];
+        let latest_version = info["version"]
+            .as_str()
+            .ok_or_else(|| PackageLookupError::InvalidResponse("missing info.version".to_string()))?
+            .to_string();
+        let repository = info["project_urls"]
+            .as_object()
+            .and_then(|urls| {
+                ["Source", "Repository", "Homepage"]
+                    .iter()
+                    .find_map(|key| urls.get(*key).and_then(|v| v.as_str()))
+            })
+            .or_else(|| info["home_page"].as_str())
+            .filter(|s| !s.is_empty())
+            .map(String::from);
+        Ok(PackageInfo {
+            name: name.to_string(),
+            ecosystem: Ecosystem::Pypi,
+            latest_version,
+            description: info["summary"].as_str().map(String::from),
+            repository,
+            features: Vec::new(),
+            min_toolchain: info["requires_python"].as_str().filter(|s| !s.is_empty()).map(String::from),
+            outdated: None,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct PackageLookupTool {
+    registry: PackageRegistry,
+}
+
+impl PackageLookupTool {
+    pub fn new(config: &NetworkConfig) -> Self {
+        PackageLookupTool {
+            registry: PackageRegistry::new(config),
+        }
+    }
+}
+
+#[async_trait]
+impl CliTool for PackageLookupTool {
+    fn name(&self) -> String {
+        "PackageLookupTool".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Looks up a package on crates.io, npm or PyPI and returns its latest version, description, repository, \
+         Cargo features and minimum toolchain version. Pass current_version to check whether it is outdated. \
+         The ecosystem is detected from the project's manifest files when omitted. \
+         Args: {\"name\": string, \"ecosystem\": \"crates\" | \"npm\" | \"pypi\" (optional), \"current_version\": string (optional)}"
+            .to_string()
+    }
+
+    fn parameters_schema(&self) -> anyhow::Result<Value> {
+        Ok(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": "The package name as published in the registry."
+                },
+                "ecosystem": {
+                    "type": "string",
+                    "enum": ["crates", "npm", "pypi"],
+                    "description": "The registry to query. Detected from Cargo.toml, package.json or pyproject.toml when omitted."
+                },
+                "current_version": {
+                    "type": "string",
+                    "description": "The version or requirement currently used, e.g. '1.0' or '^2.3.1'."
+                }
+            },
+            "required": ["name"]
+        }))
+    }
+
+    fn network_target(&self, args: &Value) -> Option<String> {
+        let ecosystem = args
+            .get("ecosystem")
+            .and_then(|v| serde_json::from_value::<Ecosystem>(v.clone()).ok())
+            .or_else(|| Ecosystem::detect(Path::new(".")))
+            .unwrap_or(Ecosystem::Crates);
+        Some(
+            match ecosystem {
+                Ecosystem::Crates => "crates.io",
+                Ecosystem::Npm => "registry.npmjs.org",
+                Ecosystem::Pypi => "pypi.org",
+            }
+            .to_string(),
+        )
+    }
+
+    async fn execute(&self, args: Value) -> Result<Value, ToolError> {
+        let input: PackageLookupInput = serde_json::from_value(args).map_err(|e| ToolError::InvalidArguments {
+            tool_name: self.name(),
+            details: format!("Failed to parse arguments: {}", e),
+        })?;
+        let ecosystem = input
+            .ecosystem
+            .or_else(|| Ecosystem::detect(Path::new(".")))
+            .ok_or(PackageLookupError::UnknownEcosystem)?;
+        let mut info = self.registry.lookup(ecosystem, &input.name).await?;
+        info.outdated = input
+            .current_version
+            .as_deref()
+            .map(|current| is_outdated(current, &info.latest_version));
+        serde_json::to_value(info).map_err(|e| ToolError::Other {
+            message: format!("Failed to serialize output: {}", e),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_comparisons() {
+        assert!(is_outdated("1.0", "1.0.1"));
+        assert!(is_outdated("^0.11.0", "0.12.0"));
+        assert!(!is_outdated("=2.3.1", "2.3.1"));
+        assert!(!is_outdated("3", "2.9.9"));
+        assert!(!is_outdated("*", "1.0.0"));
+
+        assert!(is_major_bump("1.4", "2.0.0"));
+        assert!(is_major_bump("0.11.0", "0.12.1"));
+        assert!(!is_major_bump("1.0.98", "1.2.0"));
+        assert!(!is_major_bump("0.3.30", "0.3.31"));
+    }
+
+    #[test]
+    fn test_detect_ecosystem() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(Ecosystem::detect(dir.path()), None);
+        std::fs::write(dir.path().join("package.json"), "{}").unwrap();
+        assert_eq!(Ecosystem::detect(dir.path()), Some(Ecosystem::Npm));
+        std::fs::write(dir.path().join("Cargo.toml"), "").unwrap();
+        assert_eq!(Ecosystem::detect(dir.path()), Some(Ecosystem::Crates));
+    }
+
+    #[tokio::test]
+    async fn test_lookup_crate() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/crates/serde")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::json!({
+                    "crate": {
+                        "max_stable_version": "1.0.219",
+                        "newest_version": "1.0.219",
+                        "description": "A serialization framework\n",
+                        "repository": "https://github.com/serde-rs/serde"
+                    },
+                    "versions": [
+                        {"num": "1.0.219", "rust_version": "1.31", "features": {"std": [], "derive": ["serde_derive"]}}
+                    ]
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let _missing = server.mock("GET", "/crates/nope").with_status(404).create_async().await;
+
+        let registry = PackageRegistry::new(&NetworkConfig::default()).with_base_url(&server.url());
+        let info = registry.lookup(Ecosystem::Crates, "serde").await.unwrap();
+        assert_eq!(info.latest_version, "1.0.219");
+        assert_eq!(info.features, vec!["derive".to_string(), "std".to_string()]);
+        assert_eq!(info.min_toolchain.as_deref(), Some("1.31"));
+        assert_eq!(info.description.as_deref(), Some("A serialization framework"));
+
+        assert!(matches!(
+            registry.lookup(Ecosystem::Crates, "nope").await,
+            Err(PackageLookupError::NotFound(_))
+        ));
+
+        let url = PackageRegistry::registry_url("https://registry.npmjs.org/", &["@types/node"]).unwrap();
+        assert_eq!(url.as_str(), "https://registry.npmjs.org/@types%2Fnode");
+        let url = PackageRegistry::registry_url("https://crates.io/api/v1", &["crates", "../../x?y"]).unwrap();
+        assert_eq!(url.as_str(), "https://crates.io/api/v1/crates/..%2F..%2Fx%3Fy");
+        assert!(matches!(registry.lookup(Ecosystem::Pypi, "..").await, Err(PackageLookupError::NotFound(_))));
+    }
+}
@@ -0,0 +1,230 @@
+use std::path::{Component, Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::config::PathRuleConfig;
+
+// Arguments that name a file or directory, across the built-in tools.
+const PATH_ARGUMENTS: &[&str] = &["path", "file", "working_directory"];
+
+#[derive(Debug, Clone)]
+struct PathRule {
+    segments: Vec<String>,
+    pattern: String,
+    allow: Vec<String>,
+    deny: Vec<String>,
+}
+
+impl PathRule {
+    fn forbids(&self, tool_name: &str) -> bool {
+        let denied = self.deny.iter().any(|t| t == "*" || t == tool_name);
+        let not_allowed = !self.allow.is_empty() && !self.allow.iter().any(|t| t == "*" || t == tool_name);
+        denied || not_allowed
+    }
+}
+
+// Per-directory tool restrictions from `[[path_rules]]`, checked against every path argument
+// of every tool call before it runs.
+#[derive(Debug, Clone, Default)]
+pub struct PathPolicy {
+    rules: Vec<PathRule>,
+    root: PathBuf,
+}
+
+impl PathPolicy {
+    pub fn new(rules: &[PathRuleConfig]) -> Self {
+        let root = std::env::current_dir().unwrap_or_default();
+        Self::with_root(rules, root)
+    }
+
+    pub fn with_root(rules: &[PathRuleConfig], root: PathBuf) -> Self {
+        let rules = rules
+            .iter()
+            .map(|rule| PathRule {
+                segments: pattern_segments(&rule.pattern),
+                pattern: rule.pattern.clone(),
+                allow: rule.allow.clone(),
+                deny: rule.deny.clone(),
+            })
+            .collect();
+        PathPolicy { rules, root }
+    }
+
+    // Returns the pattern of the first rule that forbids `tool_name` from touching `path`.
+    pub fn violation(&self, tool_name: &str, path: &str) -> Option<&str> {
+        let segments = relative_segments(&self.root, path)?;
+        self.rules
+            .iter()
+            .find(|rule| rule.forbids(tool_name) && segments_match(&rule.segments, &segments))
+            .map(|rule| rule.pattern.as_str())
+    }
+
+    // Checks each path argument of a tool call. A tool that any rule restricts may not reach
+    // outside the root, symlinks included, since no rule could be checked there.
+    pub fn check_arguments(&self, tool_name: &str, arguments: &Value) -> Result<(), String> {
+        let restricted = self.rules.iter().any(|rule| rule.forbids(tool_name));
+        for path in PATH_ARGUMENTS.iter().filter_map(|key| arguments.get(*key).and_then(Value::as_str)) {
+            if restricted && relative_segments(&self.root, path).is_none() {
+                return Err(format!("'{}' (tool '{}' is restricted by path_rules and may not leave the project)", path, tool_name));
+            }
+            if let Some(pattern) = self.violation(tool_name, path) {
+                return Err(format!("'{}' (tool '{}' is not allowed under '{}' by path_rules)", path, tool_name, pattern));
+            }
+        }
+        Ok(())
+    }
+
+    // Appended to the tool's description so the model knows up front where it may not go.
+    pub fn describe_for(&self, tool_name: &str) -> Option<String> {
+        let patterns: Vec<&str> = self
+            .rules
+            .iter()
+            .filter(|rule| rule.forbids(tool_name))
+            .map(|rule| rule.pattern.as_str())
+            .collect();
+        if patterns.is_empty() {
+            return None;
+        }
+        Some(format!("Not permitted on paths matching: {}.", patterns.join(", ")))
+    }
+
+}
+
+fn pattern_segments(pattern: &str) -> Vec<String> {
+    let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+    // A bare pattern such as `*.sql` applies at any depth, like in .gitignore.
+    let anchored = if pattern.contains('/') { pattern.to_string() } else { format!("**/{}", pattern) };
+    anchored.split('/').map(str::to_string).collect()
+}
+
+// `path` taken from `root` component by component, following symlinks as far as the path
+// exists, so `..` applies to where a link really points. A missing tail (a file about to be
+// created) is appended as written.
+fn resolve(root: &Path, path: &str) -> PathBuf {
+    let mut resolved = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => {
+                resolved.push(part);
+                if let Ok(real) = resolved.canonicalize() {
+                    resolved = real;
+                }
+            }
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::CurDir => {}
+            Component::RootDir | Component::Prefix(_) => resolved.push(component),
+        }
+    }
+    resolved
+}
+
+// `path` relative to `root` once resolved; None for paths that end up outside it.
+fn relative_segments(root: &Path, path: &str) -> Option<Vec<String>> {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let resolved = resolve(&root, path);
+    let relative = resolved.strip_prefix(&root).ok()?;
+    Some(relative.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect())
+}
+
+// Whether `path` falls under `pattern`, anchored the way `[[path_rules]]` patterns are; None
+// for paths outside `root`.
+pub fn pattern_matches(pattern: &str, root: &Path, path: &str) -> Option<bool> {
+    Some(segments_match(&pattern_segments(pattern), &relative_segments(root, path)?))
+}
+
+fn segments_match(pattern: &[String], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=path.len()).any(|skip| segments_match(rest, &path[skip..]))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((segment, path_rest)) => wildcard_match(first.as_bytes(), segment.as_bytes()) && segments_match(rest, path_rest),
+            None => false,
+        },
+    }
+}
+
+// `*` matches any run of characters within one path segment, `?` exactly one.
+fn wildcard_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|skip| wildcard_match(rest, &text[skip..])),
+        Some((b'?', rest)) => !text.is_empty() && wildcard_match(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && wildcard_match(rest, &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> PathPolicy {
+        PathPolicy::with_root(
+            &[
+                PathRuleConfig {
+                    pattern: "migrations/**".to_string(),
+                    allow: Vec::new(),
+                    deny: vec!["FileWriteTool".to_string(), "DeleteTool".to_string()],
+                },
+                PathRuleConfig {
+                    pattern: "*.lock".to_string(),
+                    allow: vec!["FileReadTool".to_string()],
+                    deny: Vec::new(),
+                },
+            ],
+            PathBuf::from("/work/project"),
+        )
+    }
+
+    #[test]
+    fn test_denies_listed_tools_under_matching_directory() {
+        let policy = policy();
+        assert_eq!(policy.violation("FileWriteTool", "migrations/001_init.sql"), Some("migrations/**"));
+        assert_eq!(policy.violation("DeleteTool", "./migrations/nested/002.sql"), Some("migrations/**"));
+        assert_eq!(policy.violation("FileWriteTool", "/work/project/migrations/003.sql"), Some("migrations/**"));
+        assert_eq!(policy.violation("FileWriteTool", "src/migrations.rs"), None);
+        assert_eq!(policy.violation("FileWriteTool", "src/../migrations/004.sql"), Some("migrations/**"));
+        assert_eq!(policy.violation("FileReadTool", "migrations/001_init.sql"), None);
+    }
+
+    #[test]
+    fn test_allow_list_applies_at_any_depth_for_bare_patterns() {
+        let policy = policy();
+        assert_eq!(policy.violation("FileWriteTool", "Cargo.lock"), Some("*.lock"));
+        assert_eq!(policy.violation("FileWriteTool", "web/package.lock"), Some("*.lock"));
+        assert_eq!(policy.violation("FileReadTool", "Cargo.lock"), None);
+    }
+
+    #[test]
+    fn test_describe_for_lists_forbidden_patterns() {
+        let policy = policy();
+        assert_eq!(
+            policy.describe_for("FileWriteTool").as_deref(),
+            Some("Not permitted on paths matching: migrations/**, *.lock.")
+        );
+        assert_eq!(policy.describe_for("FileReadTool"), None);
+        assert!(policy.check_arguments("FileWriteTool", &serde_json::json!({ "command": "ls" })).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_escapes_through_parents_and_symlinks_are_denied() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+        std::fs::create_dir_all(root.join("migrations")).unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::os::unix::fs::symlink(root.join("migrations"), root.join("src/linked")).unwrap();
+        let rules = [PathRuleConfig { pattern: "migrations/**".to_string(), allow: Vec::new(), deny: vec!["FileWriteTool".to_string()] }];
+        let policy = PathPolicy::with_root(&rules, root.clone());
+
+        assert_eq!(policy.violation("FileWriteTool", "src/linked/001.sql"), Some("migrations/**"));
+        let escape = serde_json::json!({ "path": "src/lib.rs", "working_directory": "../elsewhere" });
+        assert!(policy.check_arguments("FileWriteTool", &escape).unwrap_err().contains("may not leave the project"));
+        assert!(policy.check_arguments("FileReadTool", &escape).is_ok());
+        let nested = serde_json::json!({ "file": root.join("src/linked/002.sql").to_string_lossy() });
+        assert!(policy.check_arguments("FileWriteTool", &nested).unwrap_err().contains("under 'migrations/**'"));
+    }
+}
@@ -0,0 +1,303 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use super::session_env;
+use super::{CliTool, ToolError};
+use crate::config::global_config_dir;
+
+const PLUGIN_DIR: &str = "plugins";
+const PLUGIN_CALL_TIMEOUT_SECONDS: u64 = 120;
+const JSONRPC_VERSION: &str = "2.0";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PluginToolDefinition {
+    pub name: String,
+    pub description: String,
+    pub parameters: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PluginManifest {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    // Cached from `list_tools` at install time so startup doesn't spawn every plugin.
+    pub tools: Vec<PluginToolDefinition>,
+}
+
+#[derive(Error, Debug)]
+pub enum PluginError {
+    #[error("Failed to start plugin '{command}': {source}")]
+    Spawn { command: String, source: std::io::Error },
+    #[error("Plugin I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Plugin did not respond within {0}s")]
+    Timeout(u64),
+    #[error("Plugin exited without responding: {0}")]
+    NoResponse(String),
+    #[error("Plugin sent an invalid response: {0}")]
+    InvalidResponse(String),
+    #[error("Plugin returned error {code}: {message}")]
+    Remote { code: i64, message: String },
+    #[error("Plugin '{0}' is not installed")]
+    NotInstalled(String),
+    #[error("Could not determine the plugin directory")]
+    NoPluginDir,
+    #[error("Invalid plugin name '{0}': use letters, digits, '-', '_' and '.'")]
+    InvalidName(String),
+}
+
+impl From<PluginError> for ToolError {
+    fn from(error: PluginError) -> Self {
+        ToolError::Other { message: error.to_string() }
+    }
+}
+
+// Sends one JSON-RPC request to a freshly spawned plugin process and returns its result.
+// Plugins read newline-delimited requests on stdin and answer with one JSON line on stdout.
+pub async fn call_plugin(command: &str, args: &[String], method: &str, params: Value) -> Result<Value, PluginError> {
+    let mut child = session_env::command(command)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|source| PluginError::Spawn { command: command.to_string(), source })?;
+
+    let request = json!({ "jsonrpc": JSONRPC_VERSION, "id": 1, "method": method, "params": params });
+    let mut stdin = child.stdin.take().ok_or_else(|| PluginError::NoResponse("stdin unavailable".to_string()))?;
+    stdin.write_all(format!("{}\n", request).as_bytes()).await?;
+    drop(stdin);
+
+    let stdout = child.stdout.take().ok_or_else(|| PluginError::NoResponse("stdout unavailable".to_string()))?;
+    let mut lines = BufReader::new(stdout).lines();
+    let read_response = async {
+        while let Some(line) = lines.next_line().await? {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            return Ok(Some(line.to_string()));
+        }
+        Ok::<_, PluginError>(None)
+    };
+    let line = tokio::time::timeout(Duration::from_secs(PLUGIN_CALL_TIMEOUT_SECONDS), read_response)
+        .await
+        .map_err(|_| PluginError::Timeout(PLUGIN_CALL_TIMEOUT_SECONDS))??;
+    let Some(line) = line else {
+        let output = child.wait_with_output().await?;
+        return Err(PluginError::NoResponse(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    };
+
+    let response: Value = serde_json::from_str(&line).map_err(|e| PluginError::InvalidResponse(e.to_string()))?;
+    if let Some(error) = response.get("error") {
+        return Err(PluginError::Remote {
+            code: error["code"].as_i64().unwrap_or(-32000),
+            message: error["message"].as_str().unwrap_or("unknown error").to_string(),
+        });
+    }
+    response
+        .get("result")
+        .cloned()
+        .ok_or_else(|| PluginError::InvalidResponse("missing result".to_string()))
+}
+
+// Asks a plugin for the tools it provides.
+pub async fn describe_plugin(command: &str, args: &[String]) -> Result<Vec<PluginToolDefinition>, PluginError> {
+    let result = call_plugin(command, args, "list_tools", json!({})).await?;
+    serde_json::from_value(result).map_err(|e| PluginError::InvalidResponse(format!("list_tools: {}", e)))
+}
+
+#[derive(Debug, Clone)]
+pub struct PluginStore {
+    dir: PathBuf,
+}
+
+impl PluginStore {
+    pub fn new(dir: PathBuf) -> Self {
+        PluginStore { dir }
+    }
+
+    pub fn from_config_dir() -> Result<Self, PluginError> {
+        global_config_dir()
+            .map(|dir| PluginStore::new(dir.join(PLUGIN_DIR)))
+            .ok_or(PluginError::NoPluginDir)
+    }
+
+    fn manifest_path(&self, name: &str) -> Result<PathBuf, PluginError> {
+        validate_name(name)?;
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+
+    pub fn list(&self) -> Vec<PluginManifest> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut manifests: Vec<PluginManifest> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| match read_manifest(&path) {
+                Ok(manifest) => Some(manifest),
+                Err(e) => {
+                    tracing::error!("Skipping invalid plugin manifest {:?}: {}", path, e);
+                    None
+                }
+            })
+            .collect();
+        manifests.sort_by(|a, b| a.name.cmp(&b.name));
+        manifests
+    }
+
+    pub fn save(&self, manifest: &PluginManifest) -> Result<PathBuf, PluginError> {
+        let path = self.manifest_path(&manifest.name)?;
+        fs::create_dir_all(&self.dir)?;
+        let content = serde_json::to_string_pretty(manifest).map_err(|e| PluginError::InvalidResponse(e.to_string()))?;
+        fs::write(&path, content)?;
+        Ok(path)
+    }
+
+    pub fn remove(&self, name: &str) -> Result<(), PluginError> {
+        let path = self.manifest_path(name)?;
+        if !path.exists() {
+            return Err(PluginError::NotInstalled(name.to_string()));
+        }
+        fs::remove_file(path)?;
+        Ok(())
+    }
+}
+
+// A plugin's name becomes its manifest's file name, so it cannot climb out of the plugin
+// directory.
+pub fn validate_name(name: &str) -> Result<(), PluginError> {
+    let valid = name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if name.is_empty() || name.starts_with('.') || !valid {
+        return Err(PluginError::InvalidName(name.to_string()));
+    }
+    Ok(())
+}
+
+fn read_manifest(path: &Path) -> anyhow::Result<PluginManifest> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+#[derive(Debug)]
+pub struct PluginTool {
+    plugin: String,
+    command: String,
+    args: Vec<String>,
+    definition: PluginToolDefinition,
+}
+
+impl PluginTool {
+    pub fn from_manifest(manifest: &PluginManifest) -> Vec<PluginTool> {
+        manifest
+            .tools
+            .iter()
+            .map(|definition| PluginTool {
+                plugin: manifest.name.clone(),
+                command: manifest.command.clone(),
+                args: manifest.args.clone(),
+                definition: definition.clone(),
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl CliTool for PluginTool {
+    fn name(&self) -> String {
+        self.definition.name.clone()
+    }
+
+    fn description(&self) -> String {
+        format!("{} (provided by plugin '{}')", self.definition.description, self.plugin)
+    }
+
+    fn parameters_schema(&self) -> anyhow::Result<Value> {
+        Ok(self.definition.parameters.clone())
+    }
+
+    fn spawns_commands(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<Value, ToolError> {
+        let params = json!({ "name": self.definition.name, "arguments": args });
+        Ok(call_plugin(&self.command, &self.args, "call_tool", params).await?)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    const PLUGIN_SCRIPT: &str = r#"#!/bin/sh
+read request
+case "$request" in
+  *list_tools*) echo '{"jsonrpc":"2.0","id":1,"result":[{"name":"shout","description":"Upper-cases text","parameters":{"type":"object","properties":{"text":{"type":"string"}}}}]}' ;;
+  *'"text":"fail"'*) echo '{"jsonrpc":"2.0","id":1,"error":{"code":-32602,"message":"bad text"}}' ;;
+  *) echo '{"jsonrpc":"2.0","id":1,"result":{"text":"HELLO"}}' ;;
+esac
+"#;
+
+    fn write_plugin(dir: &Path) -> String {
+        let path = dir.join("shout-plugin");
+        fs::write(&path, PLUGIN_SCRIPT).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path.display().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_describe_and_call_plugin() {
+        let dir = tempfile::tempdir().unwrap();
+        let command = write_plugin(dir.path());
+
+        let tools = describe_plugin(&command, &[]).await.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "shout");
+
+        let manifest = PluginManifest {
+            name: "shout".to_string(),
+            command,
+            args: Vec::new(),
+            tools,
+        };
+        let tool = PluginTool::from_manifest(&manifest).remove(0);
+        assert_eq!(tool.execute(json!({"text": "hello"})).await.unwrap(), json!({"text": "HELLO"}));
+        assert!(tool.execute(json!({"text": "fail"})).await.unwrap_err().to_string().contains("bad text"));
+    }
+
+    #[test]
+    fn test_plugin_store_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PluginStore::new(dir.path().join("plugins"));
+        assert!(store.list().is_empty());
+
+        let manifest = PluginManifest {
+            name: "demo".to_string(),
+            command: "demo-plugin".to_string(),
+            args: vec!["--stdio".to_string()],
+            tools: Vec::new(),
+        };
+        store.save(&manifest).unwrap();
+        assert_eq!(store.list(), vec![manifest.clone()]);
+
+        store.remove("demo").unwrap();
+        assert!(store.list().is_empty());
+        assert!(matches!(store.remove("demo"), Err(PluginError::NotInstalled(_))));
+        let escaping = PluginManifest { name: "../../config".to_string(), ..manifest };
+        assert!(matches!(store.save(&escaping), Err(PluginError::InvalidName(_))));
+        assert!(matches!(store.remove("../plugins"), Err(PluginError::InvalidName(_))));
+    }
+}
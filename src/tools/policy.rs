@@ -0,0 +1,129 @@
+use serde_json::Value;
+use std::path::PathBuf;
+
+use crate::config::{ToolPermission, ToolPolicyConfig};
+use crate::tools::path_policy::pattern_matches;
+
+// Tools whose `path` argument must match `write_paths`, when it is set.
+pub const WRITE_PATH_TOOLS: &[&str] = &["FileWriteTool", "DeleteTool", "CreateDirectoryTool"];
+const SHELL_TOOL: &str = "ShellCommandTool";
+
+// What the policy says about one call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyDecision {
+    Allow,
+    Confirm,
+    // Why the call may not run, worded for the model.
+    Deny(String),
+}
+
+// `[tool_policy]`, checked against every tool call before it runs. Each rule that applies can
+// only tighten the outcome: an allowlist miss denies even a tool set to "allow".
+#[derive(Debug, Clone, Default)]
+pub struct ToolPolicy {
+    config: ToolPolicyConfig,
+    root: PathBuf,
+}
+
+impl ToolPolicy {
+    pub fn new(config: &ToolPolicyConfig) -> Self {
+        Self::with_root(config, std::env::current_dir().unwrap_or_default())
+    }
+
+    pub fn with_root(config: &ToolPolicyConfig, root: PathBuf) -> Self {
+        ToolPolicy { config: config.clone(), root }
+    }
+
+    pub fn permission(&self, tool_name: &str) -> ToolPermission {
+        self.config.tools.get(tool_name).copied().unwrap_or(self.config.default)
+    }
+
+    pub fn decide(&self, tool_name: &str, arguments: &Value) -> PolicyDecision {
+        let permission = self.permission(tool_name);
+        if permission == ToolPermission::Deny {
+            return PolicyDecision::Deny(format!("tool '{}' (denied by [tool_policy])", tool_name));
+        }
+        if let Some(denial) = self.allowlist_denial(tool_name, arguments) {
+            return PolicyDecision::Deny(denial);
+        }
+        match permission {
+            ToolPermission::Confirm => PolicyDecision::Confirm,
+            _ => PolicyDecision::Allow,
+        }
+    }
+
+    fn allowlist_denial(&self, tool_name: &str, arguments: &Value) -> Option<String> {
+        if WRITE_PATH_TOOLS.contains(&tool_name) && !self.config.write_paths.is_empty() {
+            let path = arguments.get("path").and_then(Value::as_str).unwrap_or_default();
+            let allowed = self.config.write_paths.iter().any(|pattern| pattern_matches(pattern, &self.root, path) == Some(true));
+            if !allowed {
+                return Some(format!(
+                    "'{}' (tool '{}' may only write under: {})",
+                    path,
+                    tool_name,
+                    self.config.write_paths.join(", ")
+                ));
+            }
+        }
+        if tool_name == SHELL_TOOL && !self.config.shell_commands.is_empty() {
+            let command = arguments.get("command").and_then(Value::as_str).unwrap_or_default();
+            // Commands run without a shell, so the program is the whole `command` argument.
+            if !self.config.shell_commands.iter().any(|allowed| allowed == command) {
+                return Some(format!(
+                    "command '{}' (ShellCommandTool may only run: {})",
+                    command,
+                    self.config.shell_commands.join(", ")
+                ));
+            }
+        }
+        None
+    }
+
+    // A tool that starts programs of its own choosing would get around `shell_commands`, so
+    // while that allowlist is set such tools are denied outright.
+    pub fn command_tool_denial(&self, tool_name: &str) -> Option<String> {
+        (tool_name != SHELL_TOOL && !self.config.shell_commands.is_empty()).then(|| {
+            format!(
+                "tool '{}' (only ShellCommandTool may run commands while [tool_policy] shell_commands is set)",
+                tool_name
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_policy_combines_permissions_and_allowlists() {
+        let config = ToolPolicyConfig {
+            default: ToolPermission::Allow,
+            tools: BTreeMap::from([
+                ("DockerTool".to_string(), ToolPermission::Deny),
+                ("ShellCommandTool".to_string(), ToolPermission::Confirm),
+            ]),
+            write_paths: vec!["src/**".to_string(), "*.md".to_string()],
+            shell_commands: vec!["cargo".to_string()],
+        };
+        let policy = ToolPolicy::with_root(&config, PathBuf::from("/work/app"));
+
+        assert_eq!(policy.decide("FileReadTool", &json!({ "path": "/etc/passwd" })), PolicyDecision::Allow);
+        assert!(matches!(policy.decide("DockerTool", &json!({})), PolicyDecision::Deny(reason) if reason.contains("[tool_policy]")));
+        assert_eq!(policy.decide("FileWriteTool", &json!({ "path": "src/lib.rs" })), PolicyDecision::Allow);
+        assert_eq!(policy.decide("DeleteTool", &json!({ "path": "/work/app/docs/README.md" })), PolicyDecision::Allow);
+        assert_eq!(
+            policy.decide("FileWriteTool", &json!({ "path": "src/../build.rs" })),
+            PolicyDecision::Deny("'src/../build.rs' (tool 'FileWriteTool' may only write under: src/**, *.md)".to_string())
+        );
+        assert!(matches!(policy.decide("DeleteTool", &json!({ "path": "/tmp/x" })), PolicyDecision::Deny(_)));
+
+        assert_eq!(policy.decide("ShellCommandTool", &json!({ "command": "cargo", "args": ["test"] })), PolicyDecision::Confirm);
+        assert!(matches!(policy.decide("ShellCommandTool", &json!({ "command": "/tmp/cargo" })), PolicyDecision::Deny(_)));
+        assert!(policy.command_tool_denial("ShellCommandTool").is_none());
+        assert!(policy.command_tool_denial("execute_command").is_some_and(|r| r.contains("shell_commands")));
+        assert!(ToolPolicy::default().command_tool_denial("ProcessTool").is_none());
+    }
+}
@@ -0,0 +1,335 @@
+use crate::tools::session_env::{self, SessionEnv};
+use crate::tools::{CliTool, ToolError};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, VecDeque};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+
+const MAX_OUTPUT_LINES: usize = 500;
+const DEFAULT_OUTPUT_LINES: usize = 100;
+const MAX_LISTED: usize = 50;
+
+type OutputBuffer = Arc<Mutex<VecDeque<String>>>;
+
+#[derive(Debug)]
+struct ManagedProcess {
+    command: String,
+    child: Child,
+    output: OutputBuffer,
+}
+
+// Background processes the agent started through ProcessTool, with the tail of their combined
+// stdout/stderr. Children are killed when the table is dropped, so dev servers don't outlive
+// the session. They start with the session's `/setenv` overrides. Cloning shares the same table.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessTable {
+    processes: Arc<Mutex<BTreeMap<u32, ManagedProcess>>>,
+    env: SessionEnv,
+}
+
+impl ProcessTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_env(env: SessionEnv) -> Self {
+        ProcessTable { env, ..Self::default() }
+    }
+
+    pub fn start(&self, command: &str, working_directory: Option<&str>) -> Result<u32, ToolError> {
+        let (shell, shell_arg) = if cfg!(target_os = "windows") { ("cmd", "/C") } else { ("sh", "-c") };
+        let mut builder = Command::new(shell);
+        builder
+            .arg(shell_arg)
+            .arg(command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        self.env.apply(&mut builder);
+        if let Some(dir) = working_directory {
+            builder.current_dir(dir);
+        }
+        let mut child = builder
+            .spawn()
+            .map_err(|e| ToolError::Other { message: format!("Failed to start '{}': {}", command, e) })?;
+        let pid = child.id().ok_or_else(|| ToolError::Other { message: format!("'{}' exited immediately", command) })?;
+
+        let output = OutputBuffer::default();
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(capture(stdout, output.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(capture(stderr, output.clone()));
+        }
+        self.processes
+            .lock()
+            .unwrap()
+            .insert(pid, ManagedProcess { command: command.to_string(), child, output });
+        Ok(pid)
+    }
+
+    // The last `lines` lines of output and whether the process is still running, or None if
+    // OpenCode did not start `pid`.
+    pub fn output(&self, pid: u32, lines: usize) -> Option<(Vec<String>, Option<i32>, bool)> {
+        let mut processes = self.processes.lock().unwrap();
+        let process = processes.get_mut(&pid)?;
+        let (running, exit_code) = match process.child.try_wait() {
+            Ok(Some(status)) => (false, status.code()),
+            _ => (true, None),
+        };
+        let output = process.output.lock().unwrap();
+        let tail = output.iter().skip(output.len().saturating_sub(lines)).cloned().collect();
+        Some((tail, exit_code, running))
+    }
+
+    pub fn commands(&self) -> Vec<(u32, String)> {
+        self.processes.lock().unwrap().iter().map(|(pid, p)| (*pid, p.command.clone())).collect()
+    }
+}
+
+async fn capture(stream: impl AsyncRead + Unpin, output: OutputBuffer) {
+    let mut lines = BufReader::new(stream).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let mut output = output.lock().unwrap();
+        if output.len() == MAX_OUTPUT_LINES {
+            output.pop_front();
+        }
+        output.push_back(line);
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum ProcessAction {
+    List,
+    Port,
+    Start,
+    Output,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProcessInput {
+    action: ProcessAction,
+    name: Option<String>,
+    port: Option<u16>,
+    command: Option<String>,
+    working_directory: Option<String>,
+    pid: Option<u32>,
+    lines: Option<usize>,
+}
+
+// Answers "what is running / what is listening on 8080" while debugging, and lets the agent
+// start long-running commands such as dev servers in the background and read their output.
+#[derive(Debug)]
+pub struct ProcessTool {
+    table: ProcessTable,
+}
+
+impl ProcessTool {
+    pub fn new(table: ProcessTable) -> Self {
+        ProcessTool { table }
+    }
+
+    fn missing(&self, field: &str, action: &str) -> ToolError {
+        ToolError::InvalidArguments { tool_name: self.name(), details: format!("'{}' is required for {}", field, action) }
+    }
+
+    async fn list(&self, name: Option<&str>) -> Result<Value, ToolError> {
+        let (program, args): (&str, &[&str]) = if cfg!(target_os = "windows") {
+            ("tasklist", &["/fo", "csv", "/nh"])
+        } else {
+            ("ps", &["-eo", "pid=,ppid=,etime=,args="])
+        };
+        let stdout = run(program, args).await?;
+        let needle = name.map(str::to_lowercase);
+        let started: BTreeMap<u32, String> = self.table.commands().into_iter().collect();
+        let matching: Vec<&str> = stdout
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter(|line| needle.as_ref().is_none_or(|n| line.to_lowercase().contains(n)))
+            .collect();
+        let processes: Vec<Value> = matching
+            .iter()
+            .take(MAX_LISTED)
+            .map(|line| {
+                let mut process = parse_process_line(line);
+                if let Some(pid) = process.get("pid").and_then(Value::as_u64) {
+                    process["started_by_opencode"] = Value::Bool(started.contains_key(&(pid as u32)));
+                }
+                process
+            })
+            .collect();
+        Ok(serde_json::json!({
+            "processes": processes,
+            "total_matches": matching.len(),
+            "truncated": matching.len() > MAX_LISTED,
+        }))
+    }
+
+    async fn port(&self, port: u16) -> Result<Value, ToolError> {
+        let listeners = match run("lsof", &["-nP", &format!("-iTCP:{}", port), "-sTCP:LISTEN"]).await {
+            Ok(stdout) => stdout.lines().skip(1).map(parse_lsof_line).collect(),
+            // lsof exits non-zero when nothing matches; fall back to ss where lsof is missing.
+            Err(ToolError::ExecutionFailed { .. }) => Vec::new(),
+            Err(_) => run("ss", &["-ltnpH", &format!("sport = :{}", port)])
+                .await?
+                .lines()
+                .map(|line| serde_json::json!({ "raw": line.trim() }))
+                .collect::<Vec<_>>(),
+        };
+        Ok(serde_json::json!({ "port": port, "in_use": !listeners.is_empty(), "listeners": listeners }))
+    }
+}
+
+async fn run(program: &str, args: &[&str]) -> Result<String, ToolError> {
+    let output = session_env::command(program)
+        .args(args)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| ToolError::Other { message: format!("Failed to run {}: {}", program, e) })?;
+    if !output.status.success() {
+        return Err(ToolError::ExecutionFailed {
+            command: format!("{} {}", program, args.join(" ")),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// `ps -eo pid=,ppid=,etime=,args=` lines; anything else (e.g. tasklist CSV) is returned raw.
+fn parse_process_line(line: &str) -> Value {
+    let mut fields = line.split_whitespace();
+    let parsed = (|| {
+        let pid: u32 = fields.next()?.parse().ok()?;
+        let ppid: u32 = fields.next()?.parse().ok()?;
+        let elapsed = fields.next()?.to_string();
+        let command = fields.collect::<Vec<_>>().join(" ");
+        Some(serde_json::json!({ "pid": pid, "ppid": ppid, "elapsed": elapsed, "command": command }))
+    })();
+    parsed.unwrap_or_else(|| serde_json::json!({ "raw": line.trim() }))
+}
+
+// COMMAND PID USER FD TYPE DEVICE SIZE/OFF NODE NAME
+fn parse_lsof_line(line: &str) -> Value {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    match (fields.first(), fields.get(1).and_then(|p| p.parse::<u32>().ok()), fields.get(2)) {
+        (Some(command), Some(pid), Some(user)) => serde_json::json!({
+            "command": command,
+            "pid": pid,
+            "user": user,
+            "address": fields.get(8).copied().unwrap_or_default(),
+        }),
+        _ => serde_json::json!({ "raw": line.trim() }),
+    }
+}
+
+#[async_trait]
+impl CliTool for ProcessTool {
+    fn name(&self) -> String {
+        "ProcessTool".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Inspects local processes: \"list\" shows processes whose command line contains `name`, \"port\" reports what is listening on a TCP `port`, \"start\" runs `command` in the background (e.g. a dev server) and returns its pid, and \"output\" returns the recent output of a process started with \"start\". Args: {\"action\": \"list\" | \"port\" | \"start\" | \"output\", \"name\": string (optional), \"port\": number, \"command\": string, \"working_directory\": string (optional), \"pid\": number, \"lines\": number (optional)}".to_string()
+    }
+
+    fn parameters_schema(&self) -> Result<Value> {
+        Ok(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": { "type": "string", "enum": ["list", "port", "start", "output"] },
+                "name": { "type": "string", "description": "Filter for list; matches anywhere in the command line." },
+                "port": { "type": "integer", "description": "TCP port to check." },
+                "command": { "type": "string", "description": "Shell command to start in the background." },
+                "working_directory": { "type": "string" },
+                "pid": { "type": "integer", "description": "Process started by this tool to read output from." },
+                "lines": { "type": "integer", "description": "How many recent output lines to return (default: 100)." }
+            },
+            "required": ["action"]
+        }))
+    }
+
+    fn examples(&self) -> Vec<Value> {
+        vec![
+            serde_json::json!({ "action": "start", "command": "npm run dev" }),
+            serde_json::json!({ "action": "output", "pid": 4242, "lines": 20 }),
+            serde_json::json!({ "action": "port", "port": 3000 }),
+        ]
+    }
+
+    fn spawns_commands(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<Value, ToolError> {
+        let input: ProcessInput = serde_json::from_value(args).map_err(|e| ToolError::InvalidArguments {
+            tool_name: self.name(),
+            details: format!("Failed to parse arguments: {}", e),
+        })?;
+        match input.action {
+            ProcessAction::List => self.list(input.name.as_deref()).await,
+            ProcessAction::Port => self.port(input.port.ok_or_else(|| self.missing("port", "port"))?).await,
+            ProcessAction::Start => {
+                let command = input.command.ok_or_else(|| self.missing("command", "start"))?;
+                let pid = self.table.start(&command, input.working_directory.as_deref())?;
+                Ok(serde_json::json!({ "pid": pid, "command": command, "status": "started" }))
+            }
+            ProcessAction::Output => {
+                let pid = input.pid.ok_or_else(|| self.missing("pid", "output"))?;
+                let lines = input.lines.unwrap_or(DEFAULT_OUTPUT_LINES).min(MAX_OUTPUT_LINES);
+                let (output, exit_code, running) = self.table.output(pid, lines).ok_or_else(|| ToolError::Other {
+                    message: format!("Process {} was not started by OpenCode, so its output was not captured", pid),
+                })?;
+                Ok(serde_json::json!({ "pid": pid, "running": running, "exit_code": exit_code, "output": output }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_lines() {
+        assert_eq!(
+            parse_process_line("  4242     1    01:02 node server.js --port 8080"),
+            json!({ "pid": 4242, "ppid": 1, "elapsed": "01:02", "command": "node server.js --port 8080" })
+        );
+        assert_eq!(
+            parse_lsof_line("node    4242 dev   23u  IPv4 0x1      0t0  TCP *:8080 (LISTEN)"),
+            json!({ "command": "node", "pid": 4242, "user": "dev", "address": "*:8080" })
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_start_and_read_output() {
+        let tool = ProcessTool::new(ProcessTable::new());
+        let started = tool.execute(json!({ "action": "start", "command": "echo ready; echo oops >&2" })).await.unwrap();
+        let pid = started["pid"].as_u64().unwrap();
+
+        let mut result = Value::Null;
+        for _ in 0..50 {
+            result = tool.execute(json!({ "action": "output", "pid": pid })).await.unwrap();
+            if result["running"] == json!(false) && result["output"].as_array().unwrap().len() == 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        let mut output: Vec<String> = serde_json::from_value(result["output"].clone()).unwrap();
+        output.sort();
+        assert_eq!(output, vec!["oops", "ready"]);
+        assert_eq!(result["exit_code"], json!(0));
+
+        assert!(tool.execute(json!({ "action": "output", "pid": 1 })).await.is_err());
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
+
+use crate::config::NetworkConfig;
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+// Token bucket per domain plus a global cap on concurrent downloads, shared by
+// every network-bound tool call that goes through the execution engine. Cloning shares
+// the buckets and download slots.
+#[derive(Debug, Clone)]
+pub struct NetworkLimiter {
+    buckets: Arc<Mutex<HashMap<String, TokenBucket>>>,
+    downloads: Arc<Semaphore>,
+    refill_per_second: f64,
+    burst: f64,
+}
+
+impl NetworkLimiter {
+    pub fn new(config: &NetworkConfig) -> Self {
+        NetworkLimiter {
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+            downloads: Arc::new(Semaphore::new(config.max_concurrent_downloads.max(1))),
+            refill_per_second: f64::from(config.requests_per_minute.max(1)) / 60.0,
+            burst: f64::from(config.burst.max(1)),
+        }
+    }
+
+    // Waits for a request token for `domain`, then for a download slot. The slot is
+    // released when the returned permit is dropped.
+    pub async fn acquire(&self, domain: &str) -> OwnedSemaphorePermit {
+        loop {
+            let wait = {
+                let mut buckets = self.buckets.lock().await;
+                let now = Instant::now();
+                let bucket = buckets.entry(domain.to_string()).or_insert(TokenBucket {
+                    tokens: self.burst,
+                    last_refill: now,
+                });
+                let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+                bucket.tokens = (bucket.tokens + elapsed * self.refill_per_second).min(self.burst);
+                bucket.last_refill = now;
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    None
+                } else {
+                    Some(Duration::from_secs_f64((1.0 - bucket.tokens) / self.refill_per_second))
+                }
+            };
+            match wait {
+                Some(delay) => {
+                    tracing::debug!("Rate limit reached for '{}', waiting {:?}", domain, delay);
+                    tokio::time::sleep(delay).await;
+                }
+                None => break,
+            }
+        }
+        self.downloads
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("download semaphore is never closed")
+    }
+}
+
+impl Default for NetworkLimiter {
+    fn default() -> Self {
+        Self::new(&NetworkConfig::default())
+    }
+}
+
+pub fn domain_of(url: &str) -> Option<String> {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(requests_per_minute: u32, burst: u32, max_concurrent_downloads: usize) -> NetworkLimiter {
+        NetworkLimiter::new(&NetworkConfig {
+            requests_per_minute,
+            burst,
+            max_concurrent_downloads,
+            ..NetworkConfig::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_bucket_throttles_per_domain() {
+        // 1200/min = one token every 50ms.
+        let limiter = limiter(1200, 1, 4);
+        let start = Instant::now();
+        drop(limiter.acquire("example.com").await);
+        drop(limiter.acquire("other.org").await);
+        assert!(start.elapsed() < Duration::from_millis(40), "different domains should not wait");
+
+        drop(limiter.acquire("example.com").await);
+        drop(limiter.acquire("example.com").await);
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[tokio::test]
+    async fn test_download_cap() {
+        let limiter = limiter(6000, 10, 1);
+        let permit = limiter.acquire("example.com").await;
+        let blocked = tokio::time::timeout(Duration::from_millis(50), limiter.acquire("other.org")).await;
+        assert!(blocked.is_err(), "second download should wait for the first");
+        drop(permit);
+        let next = tokio::time::timeout(Duration::from_millis(50), limiter.acquire("other.org")).await;
+        assert!(next.is_ok());
+    }
+
+    #[test]
+    fn test_domain_of() {
+        assert_eq!(domain_of("https://Docs.rs/serde/latest").as_deref(), Some("docs.rs"));
+        assert_eq!(domain_of("not a url"), None);
+    }
+}
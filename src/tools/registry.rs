@@ -1,16 +1,47 @@
 use std::collections::HashMap;
 use crate::config::Config; 
+use crate::context::memory::ProjectMemory;
 use crate::tools::CliTool;
 use anyhow::Result;
+use serde_json::Value;
 use crate::api::models::{ToolDefinition, FunctionDefinition};
+use crate::tools::ask_user::AskUserTool;
+use crate::tools::clipboard::ClipboardReadTool;
 use crate::tools::code_intelligence::ListCodeDefinitionsTool;
 use crate::tools::command_execution::ExecuteCommandTool;
-
+use crate::tools::devcontainer::DevcontainerTarget;
+use crate::tools::docker::DockerTool;
+
+use crate::tools::docs_search::DocsSearchTool;
+use crate::tools::env_vars::EnvTool;
+use crate::tools::git_history::GitHistoryTool;
+use crate::tools::memory::MemoryTool;
+use crate::tools::notes::{NotesStore, NotesTool};
+use crate::tools::todo::{TodoList, TodoTool};
+use crate::tools::package_lookup::PackageLookupTool;
+use crate::tools::process::{ProcessTable, ProcessTool};
+use crate::tools::path_policy::PathPolicy;
+use crate::tools::security_audit::SecurityAuditTool;
+use crate::tools::plugin::{PluginStore, PluginTool};
+use crate::tools::rate_limit::NetworkLimiter;
+use crate::tools::run_snippet::RunSnippetTool;
+use crate::tools::session_env::SessionEnv;
+use crate::tools::snapshot::SnapshotStore;
+use crate::tools::url_fetch::UrlFetchTool;
 use crate::tools::web_search::WebSearchTool;
 
 #[derive(Debug, Default)]
 pub struct ToolRegistry {
     tools: HashMap<String, Box<dyn CliTool>>,
+    snapshots: SnapshotStore,
+    notes: NotesStore,
+    memory: ProjectMemory,
+    todos: TodoList,
+    processes: ProcessTable,
+    path_policy: PathPolicy,
+    devcontainer: DevcontainerTarget,
+    session_env: SessionEnv,
+    network_limiter: NetworkLimiter,
 }
 
 impl ToolRegistry {
@@ -19,21 +50,49 @@ impl ToolRegistry {
     
     
     pub fn new(config: &Config) -> Self { 
-        let mut registry = Self::default();
+        let session_env = SessionEnv::global().clone();
+        let mut registry = Self {
+            processes: ProcessTable::with_env(session_env.clone()),
+            devcontainer: DevcontainerTarget::new(session_env.clone()),
+            session_env,
+            path_policy: PathPolicy::new(&config.path_rules),
+            network_limiter: NetworkLimiter::new(&config.network),
+            memory: std::env::current_dir().map(|dir| ProjectMemory::for_project(&dir)).unwrap_or_default(),
+            ..Self::default()
+        };
 
         registry.register(Box::new(crate::tools::FileReadTool));
-        registry.register(Box::new(crate::tools::FileWriteTool));
-        registry.register(Box::new(crate::tools::ShellCommandTool));
+        let snapshots = registry.snapshots.clone();
+        registry.register(Box::new(crate::tools::FileWriteTool::new(config, snapshots.clone())));
+        registry.register(Box::new(crate::tools::ShellCommandTool::new(registry.devcontainer.clone())));
         registry.register(Box::new(crate::tools::GitTool));
+        registry.register(Box::new(GitHistoryTool));
         registry.register(Box::new(WebSearchTool));
+        registry.register(Box::new(UrlFetchTool::new(&config.network)));
+        registry.register(Box::new(DocsSearchTool::new(&config.network, registry.network_limiter.clone())));
+        registry.register(Box::new(PackageLookupTool::new(&config.network)));
+        registry.register(Box::new(SecurityAuditTool));
         registry.register(Box::new(crate::tools::CodeSearchTool));
         registry.register(Box::new(crate::tools::FileSearchTool));
         registry.register(Box::new(crate::tools::CreateDirectoryTool));
-        registry.register(Box::new(crate::tools::DeleteTool));
+        registry.register(Box::new(crate::tools::DeleteTool::new(snapshots)));
         registry.register(Box::new(crate::tools::ListFilesTool));
 
         registry.register(Box::new(ListCodeDefinitionsTool));
-        registry.register(Box::new(ExecuteCommandTool));
+        registry.register(Box::new(ExecuteCommandTool::new(registry.devcontainer.clone())));
+        registry.register(Box::new(NotesTool::new(registry.notes.clone())));
+        registry.register(Box::new(MemoryTool::new(registry.memory.clone())));
+        registry.register(Box::new(TodoTool::new(registry.todos.clone())));
+        registry.register(Box::new(AskUserTool));
+        registry.register(Box::new(DockerTool));
+        registry.register(Box::new(ProcessTool::new(registry.processes.clone())));
+        registry.register(Box::new(EnvTool::new(&config.env)));
+        registry.register(Box::new(ClipboardReadTool));
+        registry.register(Box::new(RunSnippetTool));
+        #[cfg(feature = "browser")]
+        registry.register(Box::new(crate::tools::browser::BrowserTool));
+        #[cfg(feature = "database")]
+        registry.register(Box::new(crate::tools::database::SqlQueryTool::new(&config.database)));
 
         if let Some(user_tool_configs) = &config.usertools {
             for tool_config in user_tool_configs {
@@ -60,17 +119,80 @@ impl ToolRegistry {
         self.tools.insert(name, tool);
     }
 
+    pub fn retain_tools(&mut self, names: &[String]) {
+        self.tools.retain(|name, _| names.contains(name));
+    }
+
+    // Plugin tools never replace built-in or user-defined tools of the same name.
+    pub fn load_plugins(&mut self, store: &PluginStore) {
+        for manifest in store.list() {
+            for tool in PluginTool::from_manifest(&manifest) {
+                let name = tool.name();
+                if self.tools.contains_key(&name) {
+                    tracing::warn!("Plugin '{}' tool '{}' conflicts with an existing tool; skipping", manifest.name, name);
+                    continue;
+                }
+                self.register(Box::new(tool));
+            }
+        }
+    }
+
+    // Pre-change snapshots of every file the registered tools modified.
+    pub fn snapshots(&self) -> &SnapshotStore {
+        &self.snapshots
+    }
+
+    // The scratchpad NotesTool writes to, for the context manager to summarize.
+    pub fn notes(&self) -> &NotesStore {
+        &self.notes
+    }
+
+    // Facts MemoryTool saved for this project, for the context manager and `/memory`.
+    pub fn memory(&self) -> &ProjectMemory {
+        &self.memory
+    }
+
+    // The agent's plan as last recorded through TodoTool.
+    pub fn todos(&self) -> &TodoList {
+        &self.todos
+    }
+
+    // Overrides `/setenv` applies to every process a tool spawns.
+    pub fn session_env(&self) -> &SessionEnv {
+        &self.session_env
+    }
+
+    // Rate limits shared by the execution engine and tools that make several requests per call.
+    pub fn network_limiter(&self) -> &NetworkLimiter {
+        &self.network_limiter
+    }
+
+    // Whether ShellCommandTool and ExecuteCommandTool run inside the project's devcontainer.
+    pub fn devcontainer(&self) -> &DevcontainerTarget {
+        &self.devcontainer
+    }
+
     
     pub fn get_tool_definitions(&self) -> Result<Vec<ToolDefinition>> {
         self.tools
             .values()
             .map(|tool| {
                 let schema = tool.parameters_schema()?;
+                let name = tool.name();
+                let mut description = match self.path_policy.describe_for(&name) {
+                    Some(restriction) => format!("{} {}", tool.description(), restriction),
+                    None => tool.description(),
+                };
+                let examples = tool.examples();
+                if !examples.is_empty() {
+                    let examples: Vec<String> = examples.iter().map(Value::to_string).collect();
+                    description.push_str(&format!(" Examples: {}", examples.join(" ; ")));
+                }
                 Ok(ToolDefinition {
                     tool_type: "function".to_string(),
                     function: FunctionDefinition {
-                        name: tool.name(),
-                        description: tool.description(),
+                        name,
+                        description,
                         parameters: schema,
                     },
                 })
@@ -78,6 +200,10 @@ impl ToolRegistry {
             .collect()
     }
 
+    pub fn path_policy(&self) -> &PathPolicy {
+        &self.path_policy
+    }
+
     
     
     
@@ -93,7 +219,6 @@ pub mod tests {
     use serde_json::json;
     use async_trait::async_trait;
     use crate::tools::ToolError;
-    use serde_json::Value;
 
     #[derive(Debug)]
     pub struct DummyTool {
@@ -131,11 +256,14 @@ pub mod tests {
         }
     }
 
+    // Built-in tools registered by `ToolRegistry::new` with the default config.
+    const BUILTIN_TOOLS: usize = 26 + cfg!(feature = "browser") as usize + cfg!(feature = "database") as usize;
+
     #[test]
     fn test_tool_registry_new() {
         let config = Config::default(); 
         let registry = ToolRegistry::new(&config); 
-        assert_eq!(registry.tools.len(), 12);
+        assert_eq!(registry.tools.len(), BUILTIN_TOOLS);
     }
 
     #[test]
@@ -147,7 +275,7 @@ pub mod tests {
 
         registry.register(dummy_tool);
 
-        assert_eq!(registry.tools.len(), 13);
+        assert_eq!(registry.tools.len(), BUILTIN_TOOLS + 1);
         let retrieved_tool = registry.get_tool(&tool_name);
         assert!(retrieved_tool.is_some());
         assert_eq!(retrieved_tool.unwrap().name(), tool_name);
@@ -174,7 +302,17 @@ pub mod tests {
         assert!(schemas_result.is_ok());
         let schemas = schemas_result.unwrap();
 
-        assert_eq!(schemas.len(), 14);
+        assert_eq!(schemas.len(), BUILTIN_TOOLS + 2);
+    }
+
+    #[test]
+    fn test_tool_definitions_include_examples() {
+        let registry = ToolRegistry::new(&Config::default());
+        let definitions = registry.get_tool_definitions().unwrap();
+        let process = definitions.iter().find(|d| d.function.name == "ProcessTool").unwrap();
+        assert!(process.function.description.contains("Examples: {\"action\":\"start\",\"command\":\"npm run dev\"} ;"));
+        let delete = definitions.iter().find(|d| d.function.name == "DeleteTool").unwrap();
+        assert!(!delete.function.description.contains("Examples:"));
     }
 
     #[test]
@@ -183,7 +321,7 @@ pub mod tests {
         let registry = ToolRegistry::new(&config); 
         let schemas_result = registry.get_tool_definitions();
         assert!(schemas_result.is_ok());
-        assert_eq!(schemas_result.unwrap().len(), 12);
+        assert_eq!(schemas_result.unwrap().len(), BUILTIN_TOOLS);
     }
 
     
@@ -0,0 +1,187 @@
+use crate::tools::ask_user::confirm;
+use crate::tools::session_env;
+use crate::tools::{CliTool, ToolError};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+const DEFAULT_TIMEOUT_SECONDS: u64 = 10;
+const MAX_TIMEOUT_SECONDS: u64 = 60;
+const RUST_BUILD_TIMEOUT_SECONDS: u64 = 120;
+const MAX_OUTPUT_CHARS: usize = 20_000;
+// Environment passed through to snippets; everything else (API keys included) is dropped.
+const KEPT_ENV: &[&str] = &["PATH", "HOME", "CARGO_HOME", "RUSTUP_HOME", "RUSTUP_TOOLCHAIN", "SYSTEMROOT", "TEMP", "TMP"];
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum SnippetLanguage {
+    Python,
+    Rust,
+}
+
+#[derive(Debug, Deserialize)]
+struct RunSnippetInput {
+    language: SnippetLanguage,
+    code: String,
+    timeout_seconds: Option<u64>,
+}
+
+// Runs a short throwaway program so the agent can check a claim empirically instead of guessing.
+// Each snippet gets a fresh temp directory as its working directory, a scrubbed environment and
+// a hard timeout; the directory is removed afterwards. This is not a sandbox: the program runs
+// as the user and can reach their files and the network, so every run is confirmed first.
+#[derive(Debug)]
+pub struct RunSnippetTool;
+
+impl RunSnippetTool {
+    fn command(program: &str, dir: &Path) -> Command {
+        let mut command = session_env::command(program);
+        command
+            .current_dir(dir)
+            .env_clear()
+            .envs(KEPT_ENV.iter().filter_map(|key| std::env::var_os(key).map(|value| (key, value))))
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
+        command
+    }
+
+    async fn run(mut command: Command, stage: &str, timeout: Duration) -> Result<Value, ToolError> {
+        let output = match tokio::time::timeout(timeout, command.output()).await {
+            Ok(output) => output.map_err(|e| ToolError::Other { message: format!("Failed to start {}: {}", stage, e) })?,
+            Err(_) => {
+                return Ok(serde_json::json!({
+                    "stage": stage,
+                    "timed_out": true,
+                    "error": format!("{} did not finish within {}s and was killed", stage, timeout.as_secs()),
+                }))
+            }
+        };
+        Ok(serde_json::json!({
+            "stage": stage,
+            "timed_out": false,
+            "exit_code": output.status.code(),
+            "stdout": truncate(&String::from_utf8_lossy(&output.stdout)),
+            "stderr": truncate(&String::from_utf8_lossy(&output.stderr)),
+        }))
+    }
+
+    async fn run_python(dir: &Path, code: &str, timeout: Duration) -> Result<Value, ToolError> {
+        let script = dir.join("snippet.py");
+        tokio::fs::write(&script, code).await.map_err(write_failed)?;
+        let python = if cfg!(target_os = "windows") { "python" } else { "python3" };
+        let mut command = Self::command(python, dir);
+        command.arg("-I").arg(&script);
+        Self::run(command, "run", timeout).await
+    }
+
+    async fn run_input(input: RunSnippetInput) -> Result<Value, ToolError> {
+        let timeout = Duration::from_secs(input.timeout_seconds.unwrap_or(DEFAULT_TIMEOUT_SECONDS).clamp(1, MAX_TIMEOUT_SECONDS));
+        let dir = tempfile::Builder::new()
+            .prefix("opencode-snippet-")
+            .tempdir()
+            .map_err(|e| ToolError::Other { message: format!("Failed to create temp directory: {}", e) })?;
+        match input.language {
+            SnippetLanguage::Python => Self::run_python(dir.path(), &input.code, timeout).await,
+            SnippetLanguage::Rust => Self::run_rust(dir.path(), &input.code, timeout).await,
+        }
+    }
+
+    async fn run_rust(dir: &Path, code: &str, timeout: Duration) -> Result<Value, ToolError> {
+        tokio::fs::create_dir_all(dir.join("src")).await.map_err(write_failed)?;
+        let manifest = "[package]\nname = \"snippet\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\n";
+        tokio::fs::write(dir.join("Cargo.toml"), manifest).await.map_err(write_failed)?;
+        tokio::fs::write(dir.join("src/main.rs"), code).await.map_err(write_failed)?;
+
+        let mut build = Self::command("cargo", dir);
+        build.args(["build", "--quiet", "--offline"]);
+        let built = Self::run(build, "build", Duration::from_secs(RUST_BUILD_TIMEOUT_SECONDS)).await?;
+        if built["exit_code"] != 0 {
+            return Ok(built);
+        }
+        let binary = dir.join("target/debug").join(if cfg!(target_os = "windows") { "snippet.exe" } else { "snippet" });
+        Self::run(Self::command(&binary.to_string_lossy(), dir), "run", timeout).await
+    }
+}
+
+fn write_failed(e: std::io::Error) -> ToolError {
+    ToolError::Other { message: format!("Failed to write snippet: {}", e) }
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(MAX_OUTPUT_CHARS) {
+        Some((end, _)) => format!("{}\n[output truncated]", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[async_trait]
+impl CliTool for RunSnippetTool {
+    fn name(&self) -> String {
+        "RunSnippetTool".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Runs a short, self-contained Python script or Rust program (a complete main.rs, std only) from an empty temporary directory with a strict timeout and returns its stdout, stderr and exit code. The user confirms each run. Use it to verify behaviour empirically rather than guessing. It does not get the project's dependencies. Args: {\"language\": \"python\" | \"rust\", \"code\": string, \"timeout_seconds\": number (optional, max 60)}".to_string()
+    }
+
+    fn parameters_schema(&self) -> Result<Value> {
+        Ok(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "language": { "type": "string", "enum": ["python", "rust"] },
+                "code": { "type": "string", "description": "The whole script or main.rs." },
+                "timeout_seconds": {
+                    "type": "integer",
+                    "description": "How long the program may run (default: 10, max: 60). Rust compilation has its own limit."
+                }
+            },
+            "required": ["language", "code"]
+        }))
+    }
+
+    fn spawns_commands(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, args: Value) -> Result<Value, ToolError> {
+        let input: RunSnippetInput = serde_json::from_value(args).map_err(|e| ToolError::InvalidArguments {
+            tool_name: self.name(),
+            details: format!("Failed to parse arguments: {}", e),
+        })?;
+        confirm(format!("Run this {:?} snippet on your machine (it is not sandboxed)?\n{}", input.language, input.code)).await?;
+        Self::run_input(input).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_rejects_unknown_language() {
+        let err = RunSnippetTool.execute(json!({ "language": "cobol", "code": "" })).await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidArguments { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_python_snippet_runs_and_times_out() {
+        if std::process::Command::new("python3").arg("--version").output().is_err() {
+            return;
+        }
+        let input = |value| serde_json::from_value::<RunSnippetInput>(value).unwrap();
+        let result = RunSnippetTool::run_input(input(json!({ "language": "python", "code": "print(6 * 7)" }))).await.unwrap();
+        assert_eq!(result["stdout"], json!("42\n"));
+        assert_eq!(result["exit_code"], json!(0));
+
+        let result = RunSnippetTool::run_input(input(json!({ "language": "python", "code": "import time\ntime.sleep(5)", "timeout_seconds": 1 })))
+            .await
+            .unwrap();
+        assert_eq!(result["timed_out"], json!(true));
+    }
+}
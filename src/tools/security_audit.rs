@@ -0,0 +1,262 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use super::package_lookup::Ecosystem;
+use super::session_env;
+use super::{CliTool, ToolError};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SecurityAuditInput {
+    pub working_directory: Option<String>,
+    pub ecosystem: Option<Ecosystem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Advisory {
+    pub id: String,
+    pub package: String,
+    pub installed_version: Option<String>,
+    pub title: String,
+    pub severity: Option<String>,
+    pub url: Option<String>,
+    // Version requirements that contain the fix, e.g. `>=1.2.3`.
+    pub patched_versions: Vec<String>,
+    pub fix_available: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct SecurityAuditReport {
+    pub ecosystem: Ecosystem,
+    pub advisories: Vec<Advisory>,
+}
+
+#[derive(Error, Debug)]
+pub enum SecurityAuditError {
+    #[error("Could not detect a Cargo or npm project in {0}")]
+    UnknownEcosystem(String),
+    #[error("Auditing {0:?} projects is not supported")]
+    Unsupported(Ecosystem),
+    #[error("Failed to run `{command}`: {source}. Is it installed?")]
+    Spawn { command: String, source: std::io::Error },
+    #[error("`{command}` produced no parseable report: {stderr}")]
+    InvalidReport { command: String, stderr: String },
+}
+
+impl From<SecurityAuditError> for ToolError {
+    fn from(error: SecurityAuditError) -> Self {
+        match error {
+            SecurityAuditError::InvalidReport { command, stderr } => ToolError::ExecutionFailed { command, stderr },
+            other => ToolError::Other { message: other.to_string() },
+        }
+    }
+}
+
+// Parses the output of `cargo audit --json`.
+pub fn parse_cargo_audit(report: &Value) -> Option<Vec<Advisory>> {
+    let list = report.get("vulnerabilities")?.get("list")?.as_array()?;
+    Some(
+        list.iter()
+            .map(|entry| {
+                let advisory = &entry["advisory"];
+                let patched_versions: Vec<String> = entry["versions"]["patched"]
+                    .as_array()
+                    .map(|v| v.iter().filter_map(|p| p.as_str().map(String::from)).collect())
+                    .unwrap_or_default();
+                Advisory {
+                    id: advisory["id"].as_str().unwrap_or_default().to_string(),
+                    package: entry["package"]["name"]
+                        .as_str()
+                        .or_else(|| advisory["package"].as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                    installed_version: entry["package"]["version"].as_str().map(String::from),
+                    title: advisory["title"].as_str().unwrap_or_default().to_string(),
+                    severity: advisory["cvss"].as_str().map(String::from),
+                    url: advisory["url"].as_str().map(String::from),
+                    fix_available: !patched_versions.is_empty(),
+                    patched_versions,
+                }
+            })
+            .collect(),
+    )
+}
+
+// Parses the output of `npm audit --json` (npm 7 and later).
+pub fn parse_npm_audit(report: &Value) -> Option<Vec<Advisory>> {
+    let vulnerabilities = report.get("vulnerabilities")?.as_object()?;
+    let mut advisories = Vec::new();
+    for (name, vulnerability) in vulnerabilities {
+        let fix_available = match &vulnerability["fixAvailable"] {
+            Value::Bool(b) => *b,
+            Value::Object(_) => true,
+            _ => false,
+        };
+        let patched_versions = vulnerability["fixAvailable"]["version"]
+            .as_str()
+            .map(|v| vec![format!(">={}", v)])
+            .unwrap_or_default();
+        // Entries whose `via` only names other packages are transitive; report the direct advisories.
+        for via in vulnerability["via"].as_array().into_iter().flatten().filter(|v| v.is_object()) {
+            advisories.push(Advisory {
+                id: via["source"]
+                    .as_u64()
+                    .map(|s| s.to_string())
+                    .or_else(|| via["url"].as_str().and_then(|u| u.rsplit('/').next()).map(String::from))
+                    .unwrap_or_default(),
+                package: name.clone(),
+                installed_version: None,
+                title: via["title"].as_str().unwrap_or_default().to_string(),
+                severity: via["severity"]
+                    .as_str()
+                    .or_else(|| vulnerability["severity"].as_str())
+                    .map(String::from),
+                url: via["url"].as_str().map(String::from),
+                patched_versions: patched_versions.clone(),
+                fix_available,
+            });
+        }
+    }
+    Some(advisories)
+}
+
+pub async fn run_audit(dir: &Path, ecosystem: Option<Ecosystem>) -> Result<SecurityAuditReport, SecurityAuditError> {
+    let ecosystem = ecosystem
+        .or_else(|| Ecosystem::detect(dir))
+        .ok_or_else(|| SecurityAuditError::UnknownEcosystem(dir.display().to_string()))?;
+    let (program, args): (&str, &[&str]) = match ecosystem {
+        Ecosystem::Crates => ("cargo", &["audit", "--json"]),
+        Ecosystem::Npm => ("npm", &["audit", "--json"]),
+        Ecosystem::Pypi => return Err(SecurityAuditError::Unsupported(ecosystem)),
+    };
+    let command = format!("{} {}", program, args.join(" "));
+    tracing::info!("Running security audit: {}", command);
+    let output = session_env::command(program)
+        .args(args)
+        .current_dir(dir)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|source| SecurityAuditError::Spawn { command: command.clone(), source })?;
+
+    // Both tools exit non-zero when vulnerabilities are found, so rely on the JSON instead.
+    let invalid = || SecurityAuditError::InvalidReport {
+        command: command.clone(),
+        stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+    };
+    let report: Value = serde_json::from_slice(&output.stdout).map_err(|_| invalid())?;
+    let advisories = match ecosystem {
+        Ecosystem::Crates => parse_cargo_audit(&report),
+        _ => parse_npm_audit(&report),
+    }
+    .ok_or_else(invalid)?;
+    Ok(SecurityAuditReport { ecosystem, advisories })
+}
+
+#[derive(Debug)]
+pub struct SecurityAuditTool;
+
+#[async_trait]
+impl CliTool for SecurityAuditTool {
+    fn name(&self) -> String {
+        "SecurityAuditTool".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Runs `cargo audit` or `npm audit` for the project and returns the known security advisories affecting its \
+         dependencies, including the fixed versions when available. \
+         Args: {\"working_directory\": string (optional), \"ecosystem\": \"crates\" | \"npm\" (optional)}"
+            .to_string()
+    }
+
+    fn parameters_schema(&self) -> anyhow::Result<Value> {
+        Ok(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "working_directory": {
+                    "type": "string",
+                    "description": "The project directory to audit. Defaults to the current directory."
+                },
+                "ecosystem": {
+                    "type": "string",
+                    "enum": ["crates", "npm"],
+                    "description": "Which audit tool to run. Detected from the project's manifest when omitted."
+                }
+            }
+        }))
+    }
+
+    async fn execute(&self, args: Value) -> Result<Value, ToolError> {
+        let input: SecurityAuditInput = serde_json::from_value(args).map_err(|e| ToolError::InvalidArguments {
+            tool_name: self.name(),
+            details: format!("Failed to parse arguments: {}", e),
+        })?;
+        let dir = input.working_directory.map(PathBuf::from).unwrap_or_else(|| PathBuf::from("."));
+        let report = run_audit(&dir, input.ecosystem).await?;
+        serde_json::to_value(report).map_err(|e| ToolError::Other {
+            message: format!("Failed to serialize output: {}", e),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_cargo_audit() {
+        let report = json!({
+            "vulnerabilities": {
+                "found": true,
+                "count": 1,
+                "list": [{
+                    "advisory": {
+                        "id": "RUSTSEC-2023-0001",
+                        "package": "tokio",
+                        "title": "reject_remote_clients configuration corruption",
+                        "url": "https://github.com/tokio-rs/tokio/security/advisories/GHSA-7rrj-xr53-82p7",
+                        "cvss": "CVSS:3.1/AV:L/AC:H/PR:N/UI:N/S:U/C:L/I:L/A:L"
+                    },
+                    "versions": {"patched": [">=1.18.4, <1.19.0", ">=1.23.1"], "unaffected": []},
+                    "package": {"name": "tokio", "version": "1.18.0"}
+                }]
+            }
+        });
+        let advisories = parse_cargo_audit(&report).unwrap();
+        assert_eq!(advisories.len(), 1);
+        assert_eq!(advisories[0].id, "RUSTSEC-2023-0001");
+        assert_eq!(advisories[0].installed_version.as_deref(), Some("1.18.0"));
+        assert_eq!(advisories[0].patched_versions.len(), 2);
+        assert!(advisories[0].fix_available);
+
+        assert!(parse_cargo_audit(&json!({"error": "no lockfile"})).is_none());
+    }
+
+    #[test]
+    fn test_parse_npm_audit() {
+        let report = json!({
+            "vulnerabilities": {
+                "minimist": {
+                    "name": "minimist",
+                    "severity": "critical",
+                    "via": [{
+                        "source": 1085731,
+                        "title": "Prototype Pollution in minimist",
+                        "url": "https://github.com/advisories/GHSA-xvch-5gv4-984h",
+                        "severity": "critical"
+                    }],
+                    "fixAvailable": {"name": "minimist", "version": "1.2.8", "isSemVerMajor": false}
+                },
+                "mkdirp": {"name": "mkdirp", "severity": "critical", "via": ["minimist"], "fixAvailable": true}
+            }
+        });
+        let advisories = parse_npm_audit(&report).unwrap();
+        assert_eq!(advisories.len(), 1);
+        assert_eq!(advisories[0].package, "minimist");
+        assert_eq!(advisories[0].id, "1085731");
+        assert_eq!(advisories[0].patched_versions, vec![">=1.2.8".to_string()]);
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::process::Command;
+
+static SESSION_ENV: OnceLock<SessionEnv> = OnceLock::new();
+
+// Environment overrides set with `/setenv` for the processes tools spawn. They
+// only ever reach child processes, never OpenCode's own environment or the shell it was
+// started from. Cloning shares the same overrides.
+#[derive(Debug, Clone, Default)]
+pub struct SessionEnv {
+    vars: Arc<Mutex<BTreeMap<String, String>>>,
+}
+
+impl SessionEnv {
+    // The overrides of this session, which every tool process is started with.
+    pub fn global() -> &'static SessionEnv {
+        SESSION_ENV.get_or_init(SessionEnv::default)
+    }
+
+    // Sets `name` to `value` with `$NAME` and `${NAME}` references expanded against the
+    // overrides and then OpenCode's environment, so `PATH=./bin:$PATH` extends the path.
+    pub fn set(&self, name: &str, value: &str) -> Result<String, String> {
+        if name.is_empty() || name.contains('=') || name.chars().any(char::is_whitespace) {
+            return Err(format!("'{}' is not a valid variable name", name));
+        }
+        let expanded = self.expand(value);
+        self.vars.lock().unwrap().insert(name.to_string(), expanded.clone());
+        Ok(expanded)
+    }
+
+    pub fn unset(&self, name: &str) -> Option<String> {
+        self.vars.lock().unwrap().remove(name)
+    }
+
+    pub fn vars(&self) -> BTreeMap<String, String> {
+        self.vars.lock().unwrap().clone()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vars.lock().unwrap().is_empty()
+    }
+
+    pub fn apply(&self, command: &mut Command) {
+        command.envs(self.vars());
+    }
+
+    fn lookup(&self, name: &str) -> String {
+        let vars = self.vars.lock().unwrap();
+        vars.get(name).cloned().or_else(|| std::env::var(name).ok()).unwrap_or_default()
+    }
+
+    fn expand(&self, value: &str) -> String {
+        let mut out = String::new();
+        let mut rest = value;
+        while let Some(start) = rest.find('$') {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            let (name, consumed) = match after.strip_prefix('{').and_then(|braced| braced.find('}').map(|end| &braced[..end])) {
+                Some(name) => (name, name.len() + 2),
+                None => {
+                    let end = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+                    (&after[..end], end)
+                }
+            };
+            if name.is_empty() {
+                out.push('$');
+            } else {
+                out.push_str(&self.lookup(name));
+            }
+            rest = &after[consumed..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+// `program` with the session's `/setenv` overrides applied. Tools start their child processes
+// through this rather than `Command::new`, so an override reaches all of them.
+pub fn command(program: impl AsRef<OsStr>) -> Command {
+    let mut command = Command::new(program);
+    SessionEnv::global().apply(&mut command);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_expands_references_and_reaches_children() {
+        let env = SessionEnv::default();
+        env.set("OPENCODE_TEST_BIN", "/opt/tools").unwrap();
+        assert_eq!(env.set("OPENCODE_TEST_PATH", "${OPENCODE_TEST_BIN}/bin:$OPENCODE_TEST_UNSET:$").unwrap(), "/opt/tools/bin::$");
+        assert!(env.set("BAD NAME", "x").is_err());
+        assert!(std::env::var("OPENCODE_TEST_BIN").is_err());
+
+        let mut command = Command::new("sh");
+        env.apply(&mut command);
+        let vars: Vec<_> = command.as_std().get_envs().map(|(k, v)| (k.to_owned(), v.map(|v| v.to_owned()))).collect();
+        assert!(vars.contains(&("OPENCODE_TEST_BIN".into(), Some("/opt/tools".into()))));
+        assert_eq!(env.unset("OPENCODE_TEST_BIN").as_deref(), Some("/opt/tools"));
+        assert_eq!(env.vars().len(), 1);
+
+        SessionEnv::global().set("OPENCODE_TEST_GLOBAL", "on").unwrap();
+        let spawned = super::command("sh");
+        assert!(spawned.as_std().get_envs().any(|(k, v)| k == "OPENCODE_TEST_GLOBAL" && v == Some("on".as_ref())));
+        SessionEnv::global().unset("OPENCODE_TEST_GLOBAL");
+    }
+}
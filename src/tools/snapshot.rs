@@ -0,0 +1,307 @@
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileSnapshot {
+    pub path: PathBuf,
+    // None when the file did not exist before this invocation touched it.
+    pub original: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileStat {
+    pub path: PathBuf,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+// Records the pre-change content of every file the tools modify during one invocation.
+// Cloning shares the same underlying record.
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+    snapshots: Arc<Mutex<Vec<FileSnapshot>>>,
+    // The project root relative paths are resolved against.
+    root: PathBuf,
+}
+
+impl Default for SnapshotStore {
+    fn default() -> Self {
+        SnapshotStore::with_root(std::env::current_dir().unwrap_or_default())
+    }
+}
+
+impl SnapshotStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_root(root: PathBuf) -> Self {
+        SnapshotStore { snapshots: Arc::default(), root }
+    }
+
+    // An absolute, symlink-free path even for files that don't exist yet: the deepest existing
+    // ancestor is canonicalized and the rest appended, so every spelling of a path (relative,
+    // `./`, through a link) lands on one snapshot.
+    fn resolve(&self, path: &Path) -> PathBuf {
+        let absolute = self.root.join(path);
+        let mut existing = absolute.as_path();
+        let mut rest = Vec::new();
+        loop {
+            if let Ok(canonical) = fs::canonicalize(existing) {
+                return rest.iter().rev().fold(canonical, |resolved, part| resolved.join(part));
+            }
+            match (existing.parent(), existing.file_name()) {
+                (Some(parent), Some(name)) => {
+                    rest.push(name);
+                    existing = parent;
+                }
+                _ => return absolute,
+            }
+        }
+    }
+
+    // Only the first call per path is kept, so the snapshot is the state before the invocation.
+    pub fn record_before_change(&self, path: &Path) {
+        let path = self.resolve(path);
+        let mut snapshots = self.snapshots.lock().unwrap();
+        if snapshots.iter().any(|s| s.path == path) {
+            return;
+        }
+        let original = if path.is_file() {
+            Some(fs::read_to_string(&path).unwrap_or_default())
+        } else {
+            None
+        };
+        tracing::debug!("Recorded snapshot of {:?} (existed: {})", path, original.is_some());
+        snapshots.push(FileSnapshot { path, original });
+    }
+
+    pub fn snapshots(&self) -> Vec<FileSnapshot> {
+        self.snapshots.lock().unwrap().clone()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.lock().unwrap().is_empty()
+    }
+
+    pub fn clear(&self) {
+        self.snapshots.lock().unwrap().clear();
+    }
+
+    // Compares each snapshot with what is on disk now; unchanged files are omitted.
+    pub fn diffstat(&self) -> Vec<FileStat> {
+        let mut by_path: HashMap<PathBuf, FileStat> = HashMap::new();
+        for snapshot in self.snapshots() {
+            let current = fs::read_to_string(&snapshot.path).ok();
+            if current == snapshot.original {
+                continue;
+            }
+            let before = snapshot.original.as_deref().unwrap_or("");
+            let after = current.as_deref().unwrap_or("");
+            let (mut insertions, mut deletions) = (0, 0);
+            for change in TextDiff::from_lines(before, after).iter_all_changes() {
+                match change.tag() {
+                    ChangeTag::Insert => insertions += 1,
+                    ChangeTag::Delete => deletions += 1,
+                    ChangeTag::Equal => {}
+                }
+            }
+            by_path.insert(snapshot.path.clone(), FileStat { path: snapshot.path, insertions, deletions });
+        }
+        let mut stats: Vec<FileStat> = by_path.into_values().collect();
+        stats.sort_by(|a, b| a.path.cmp(&b.path));
+        stats
+    }
+}
+
+// Unified diffs of the changed files under `root`, with paths relative to it.
+fn diff_files(snapshots: &SnapshotStore, root: &Path) -> (Vec<FileStat>, String) {
+    let root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    let mut stats = Vec::new();
+    let mut diffs = String::new();
+    for stat in snapshots.diffstat() {
+        let Ok(relative) = stat.path.strip_prefix(&root) else { continue };
+        let name = relative.to_string_lossy().replace('\\', "/");
+        let original = snapshots.snapshots().into_iter().find(|s| s.path == stat.path).and_then(|s| s.original);
+        let current = fs::read_to_string(&stat.path).ok();
+        diffs.push_str(&format!("diff --git a/{0} b/{0}\n", name));
+        if original.is_none() {
+            diffs.push_str("new file mode 100644\n");
+        } else if current.is_none() {
+            diffs.push_str("deleted file mode 100644\n");
+        }
+        let old_name = if original.is_some() { format!("a/{}", name) } else { "/dev/null".to_string() };
+        let new_name = if current.is_some() { format!("b/{}", name) } else { "/dev/null".to_string() };
+        diffs.push_str(&format!("--- {}\n+++ {}\n", old_name, new_name));
+        let before = original.unwrap_or_default();
+        let after = current.unwrap_or_default();
+        diffs.push_str(&TextDiff::from_lines(&before, &after).unified_diff().context_radius(3).to_string());
+        stats.push(FileStat { path: PathBuf::from(&name), ..stat });
+    }
+    (stats, diffs)
+}
+
+// Everything the tools changed under `root` as one git-style diff.
+pub fn session_diff(snapshots: &SnapshotStore, root: &Path) -> String {
+    diff_files(snapshots, root).1
+}
+
+// The session's changes as a `git format-patch` style email, paths relative to `root`, for
+// `git am` or `opencode apply-patch` to replay elsewhere. Files outside `root` are left out;
+// `None` when nothing under it changed.
+pub fn format_patch(snapshots: &SnapshotStore, root: &Path, subject: &str) -> Option<String> {
+    let (stats, diffs) = diff_files(snapshots, root);
+    if stats.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "From 0000000000000000000000000000000000000000 Mon Sep 17 00:00:00 2001\n\
+         From: OpenCode <opencode@localhost>\n\
+         Subject: [PATCH] {}\n\n---\n{}\n\n{}",
+        subject,
+        format_diffstat(&stats, 40),
+        diffs
+    ))
+}
+
+pub fn display_path(path: &Path) -> String {
+    std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(|p| p.to_path_buf()))
+        .unwrap_or_else(|| path.to_path_buf())
+        .display()
+        .to_string()
+}
+
+// Renders stats the way `git diff --stat` does, scaled to at most `bar_width` markers.
+pub fn format_diffstat(stats: &[FileStat], bar_width: usize) -> String {
+    if stats.is_empty() {
+        return "No files changed.".to_string();
+    }
+    let names: Vec<String> = stats.iter().map(|s| display_path(&s.path)).collect();
+    let name_width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+    let max_changes = stats.iter().map(|s| s.insertions + s.deletions).max().unwrap_or(0);
+    let count_width = max_changes.to_string().len();
+
+    let mut out = String::new();
+    for (stat, name) in stats.iter().zip(&names) {
+        let total = stat.insertions + stat.deletions;
+        let (plus, minus) = if max_changes > bar_width {
+            let scale = |n: usize| if n == 0 { 0 } else { (n * bar_width / max_changes).max(1) };
+            (scale(stat.insertions), scale(stat.deletions))
+        } else {
+            (stat.insertions, stat.deletions)
+        };
+        out.push_str(&format!(
+            " {:<name_width$} | {:>count_width$} {}{}\n",
+            name,
+            total,
+            "+".repeat(plus),
+            "-".repeat(minus),
+        ));
+    }
+    let insertions: usize = stats.iter().map(|s| s.insertions).sum();
+    let deletions: usize = stats.iter().map(|s| s.deletions).sum();
+    out.push_str(&format!(
+        " {} file{} changed, {} insertion{}(+), {} deletion{}(-)",
+        stats.len(),
+        if stats.len() == 1 { "" } else { "s" },
+        insertions,
+        if insertions == 1 { "" } else { "s" },
+        deletions,
+        if deletions == 1 { "" } else { "s" },
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_diffstat_tracks_first_snapshot_only() {
+        let dir = tempdir().unwrap();
+        let edited = dir.path().join("edited.txt");
+        let created = dir.path().join("created.txt");
+        fs::write(&edited, "one\ntwo\nthree\n").unwrap();
+
+        let store = SnapshotStore::new();
+        store.record_before_change(&edited);
+        fs::write(&edited, "one\n2\nthree\n").unwrap();
+        store.record_before_change(&edited);
+        fs::write(&edited, "one\n2\nthree\nfour\n").unwrap();
+        store.record_before_change(&created);
+        fs::write(&created, "new\n").unwrap();
+
+        let stats = store.diffstat();
+        assert_eq!(stats.len(), 2);
+        let edited_stat = stats.iter().find(|s| s.path.ends_with("edited.txt")).unwrap();
+        assert_eq!((edited_stat.insertions, edited_stat.deletions), (2, 1));
+        let created_stat = stats.iter().find(|s| s.path.ends_with("created.txt")).unwrap();
+        assert_eq!((created_stat.insertions, created_stat.deletions), (1, 0));
+    }
+
+    #[test]
+    fn test_relative_and_new_paths_resolve_against_the_root() {
+        let dir = tempdir().unwrap();
+        let store = SnapshotStore::with_root(dir.path().to_path_buf());
+        store.record_before_change(Path::new("new/created.txt"));
+        store.record_before_change(&dir.path().join("new/./created.txt"));
+        let snapshots = store.snapshots();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].path, fs::canonicalize(dir.path()).unwrap().join("new/created.txt"));
+    }
+
+    #[test]
+    fn test_unchanged_files_are_omitted() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("same.txt");
+        fs::write(&path, "same\n").unwrap();
+        let store = SnapshotStore::new();
+        store.record_before_change(&path);
+        fs::write(&path, "same\n").unwrap();
+        assert!(store.diffstat().is_empty());
+    }
+
+    #[test]
+    fn test_format_patch_applies_with_git() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        std::process::Command::new("git").arg("init").arg("-q").current_dir(root).output().unwrap();
+        fs::write(root.join("edited.txt"), "one\ntwo\n").unwrap();
+        let store = SnapshotStore::new();
+        store.record_before_change(&root.join("edited.txt"));
+        fs::write(root.join("edited.txt"), "one\n2\n").unwrap();
+        store.record_before_change(&root.join("created.txt"));
+        fs::write(root.join("created.txt"), "new\n").unwrap();
+
+        let patch = format_patch(&store, root, "Session changes").unwrap();
+        assert!(patch.contains("Subject: [PATCH] Session changes\n"));
+        assert!(patch.contains("diff --git a/created.txt b/created.txt\nnew file mode 100644\n--- /dev/null\n+++ b/created.txt\n"));
+
+        fs::write(root.join("edited.txt"), "one\ntwo\n").unwrap();
+        fs::remove_file(root.join("created.txt")).unwrap();
+        fs::write(root.join("session.patch"), &patch).unwrap();
+        let applied = std::process::Command::new("git").args(["apply", "session.patch"]).current_dir(root).output().unwrap();
+        assert!(applied.status.success(), "{}", String::from_utf8_lossy(&applied.stderr));
+        assert_eq!(fs::read_to_string(root.join("edited.txt")).unwrap(), "one\n2\n");
+        assert_eq!(fs::read_to_string(root.join("created.txt")).unwrap(), "new\n");
+    }
+
+    #[test]
+    fn test_format_diffstat() {
+        let stats = vec![
+            FileStat { path: PathBuf::from("src/a.rs"), insertions: 3, deletions: 1 },
+            FileStat { path: PathBuf::from("b.rs"), insertions: 0, deletions: 2 },
+        ];
+        let out = format_diffstat(&stats, 40);
+        assert!(out.contains(" src/a.rs | 4 +++-\n"));
+        assert!(out.contains(" b.rs     | 2 --\n"));
+        assert!(out.ends_with(" 2 files changed, 3 insertions(+), 3 deletions(-)"));
+    }
+}
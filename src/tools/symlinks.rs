@@ -0,0 +1,76 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Longest symlink chain followed before giving up, as with the kernel's ELOOP limit.
+const MAX_LINK_HOPS: usize = 40;
+
+// Whether a file tool acts on a symlink itself or on the file it points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymlinkMode {
+    Link,
+    Target,
+}
+
+// The `symlink` argument of a file tool, `default` when absent.
+pub fn symlink_mode(args: &Value, default: SymlinkMode) -> Result<SymlinkMode, String> {
+    match args.get("symlink") {
+        None | Some(Value::Null) => Ok(default),
+        Some(value) => {
+            serde_json::from_value(value.clone()).map_err(|_| "'symlink' must be \"link\" or \"target\"".to_string())
+        }
+    }
+}
+
+pub fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).map(|m| m.file_type().is_symlink()).unwrap_or(false)
+}
+
+// The path an operation in `mode` acts on: `path` itself, or the end of its symlink chain.
+// Dangling links resolve to the missing file they name, so a write can create it.
+pub fn resolve(path: &Path, mode: SymlinkMode) -> io::Result<PathBuf> {
+    if mode == SymlinkMode::Link {
+        return Ok(path.to_path_buf());
+    }
+    let mut current = path.to_path_buf();
+    for _ in 0..MAX_LINK_HOPS {
+        if !is_symlink(&current) {
+            return Ok(current);
+        }
+        let link = fs::read_link(&current)?;
+        current = match current.parent() {
+            Some(parent) if link.is_relative() => parent.join(link),
+            _ => link,
+        };
+    }
+    Err(io::Error::other(format!("Too many levels of symbolic links: {}", path.display())))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_resolve_follows_relative_and_dangling_links() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("config.toml");
+        fs::write(&target, "a = 1\n").unwrap();
+        symlink("config.toml", dir.path().join("link.toml")).unwrap();
+        symlink("link.toml", dir.path().join("chain.toml")).unwrap();
+        symlink("missing.toml", dir.path().join("dangling.toml")).unwrap();
+
+        assert_eq!(resolve(&dir.path().join("chain.toml"), SymlinkMode::Target).unwrap(), target);
+        assert_eq!(resolve(&dir.path().join("chain.toml"), SymlinkMode::Link).unwrap(), dir.path().join("chain.toml"));
+        assert_eq!(
+            resolve(&dir.path().join("dangling.toml"), SymlinkMode::Target).unwrap(),
+            dir.path().join("missing.toml")
+        );
+        assert_eq!(symlink_mode(&serde_json::json!({ "symlink": "link" }), SymlinkMode::Target), Ok(SymlinkMode::Link));
+        assert!(symlink_mode(&serde_json::json!({ "symlink": "both" }), SymlinkMode::Target).is_err());
+    }
+}
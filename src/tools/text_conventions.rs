@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+const UTF8_BOM: &str = "\u{feff}";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+// How a text file is laid out on disk, beyond its lines. Models almost always emit LF text
+// without a BOM, so writes re-apply the conventions of the file being replaced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TextConventions {
+    pub line_ending: LineEnding,
+    pub bom: bool,
+    pub trailing_newline: bool,
+}
+
+impl TextConventions {
+    // The conventions of existing file content. Mixed line endings go with the majority.
+    pub fn detect(content: &str) -> Self {
+        let crlf = content.matches("\r\n").count();
+        let lf = content.matches('\n').count() - crlf;
+        TextConventions {
+            line_ending: if crlf > lf { LineEnding::Crlf } else { LineEnding::Lf },
+            bom: content.starts_with(UTF8_BOM),
+            trailing_newline: content.ends_with('\n'),
+        }
+    }
+
+    // `content` rewritten to follow these conventions.
+    pub fn apply(&self, content: &str) -> String {
+        let mut text = content.strip_prefix(UTF8_BOM).unwrap_or(content).replace("\r\n", "\n");
+        if self.trailing_newline && !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        } else if !self.trailing_newline && text.ends_with('\n') {
+            text.pop();
+        }
+        if self.line_ending == LineEnding::Crlf {
+            text = text.replace('\n', "\r\n");
+        }
+        if self.bom {
+            text.insert_str(0, UTF8_BOM);
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preserves_crlf_bom_and_missing_trailing_newline() {
+        let conventions = TextConventions::detect("\u{feff}[section]\r\nkey = 1\r\nother = 2");
+        assert_eq!(
+            conventions,
+            TextConventions { line_ending: LineEnding::Crlf, bom: true, trailing_newline: false }
+        );
+        assert_eq!(conventions.apply("[section]\nkey = 3\n"), "\u{feff}[section]\r\nkey = 3");
+
+        let unix = TextConventions::detect("fn main() {}\n");
+        assert_eq!(unix.apply("fn main() {\r\n}"), "fn main() {\n}\n");
+    }
+}
@@ -0,0 +1,166 @@
+use crate::events::{self, UiEvent};
+use crate::tools::{CliTool, ToolError};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoStatus {
+    Pending,
+    InProgress,
+    Done,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TodoItem {
+    pub content: String,
+    pub status: TodoStatus,
+}
+
+// The agent's current plan. Cloning shares the same list.
+#[derive(Debug, Clone, Default)]
+pub struct TodoList {
+    items: Arc<Mutex<Vec<TodoItem>>>,
+}
+
+impl TodoList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&self, items: Vec<TodoItem>) {
+        *self.items.lock().unwrap() = items;
+    }
+
+    pub fn items(&self) -> Vec<TodoItem> {
+        self.items.lock().unwrap().clone()
+    }
+
+    pub fn in_progress(&self) -> Option<String> {
+        self.items.lock().unwrap().iter().find(|i| i.status == TodoStatus::InProgress).map(|i| i.content.clone())
+    }
+
+    // One-line progress summary for prompts and spinners; None while there is no plan.
+    pub fn status_line(&self) -> Option<String> {
+        let items = self.items.lock().unwrap();
+        if items.is_empty() {
+            return None;
+        }
+        let done = items.iter().filter(|i| i.status == TodoStatus::Done).count();
+        let mut line = format!("Plan: {}/{} done", done, items.len());
+        if let Some(current) = items.iter().find(|i| i.status == TodoStatus::InProgress) {
+            line.push_str(&format!(" · now: {}", current.content));
+        }
+        Some(line)
+    }
+}
+
+pub fn format_todo_list(items: &[TodoItem]) -> String {
+    let done = items.iter().filter(|i| i.status == TodoStatus::Done).count();
+    let mut out = format!("Plan ({}/{} done):", done, items.len());
+    for item in items {
+        let marker = match item.status {
+            TodoStatus::Pending => "[ ]",
+            TodoStatus::InProgress => "[~]",
+            TodoStatus::Done => "[x]",
+        };
+        out.push_str(&format!("\n  {} {}", marker, item.content));
+    }
+    out
+}
+
+#[derive(Debug)]
+pub struct TodoTool {
+    list: TodoList,
+}
+
+impl TodoTool {
+    pub fn new(list: TodoList) -> Self {
+        TodoTool { list }
+    }
+}
+
+#[async_trait]
+impl CliTool for TodoTool {
+    fn name(&self) -> String {
+        "TodoTool".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Records your plan for the current task so the user can follow along. Send the whole list every time, marking one item in_progress while you work on it and items done as you finish them. Args: {\"todos\": [{\"content\": string, \"status\": \"pending\" | \"in_progress\" | \"done\"}]}".to_string()
+    }
+
+    fn parameters_schema(&self) -> Result<Value> {
+        Ok(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "todos": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "content": { "type": "string" },
+                            "status": { "type": "string", "enum": ["pending", "in_progress", "done"] }
+                        },
+                        "required": ["content", "status"]
+                    }
+                }
+            },
+            "required": ["todos"]
+        }))
+    }
+
+    fn examples(&self) -> Vec<Value> {
+        vec![serde_json::json!({ "todos": [
+            { "content": "Add the config option", "status": "done" },
+            { "content": "Use it in the parser", "status": "in_progress" },
+            { "content": "Add a test", "status": "pending" }
+        ] })]
+    }
+
+    async fn execute(&self, args: Value) -> Result<Value, ToolError> {
+        let todos = args.get("todos").cloned().unwrap_or(Value::Null);
+        let items: Vec<TodoItem> = serde_json::from_value(todos).map_err(|e| ToolError::InvalidArguments {
+            tool_name: self.name(),
+            details: format!("'todos' must be a list of {{content, status}} items: {}", e),
+        })?;
+        let done = items.iter().filter(|i| i.status == TodoStatus::Done).count();
+        let total = items.len();
+        self.list.set(items.clone());
+        events::emit(UiEvent::TodosUpdated { todos: items });
+        Ok(serde_json::json!({ "status": "updated", "done": done, "total": total }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_todo_list_updates_and_renders() {
+        let list = TodoList::new();
+        let tool = TodoTool::new(list.clone());
+        assert_eq!(list.status_line(), None);
+
+        let result = tool
+            .execute(serde_json::json!({ "todos": [
+                { "content": "Read the parser", "status": "done" },
+                { "content": "Fix the off-by-one", "status": "in_progress" },
+                { "content": "Add a test", "status": "pending" }
+            ]}))
+            .await
+            .unwrap();
+        assert_eq!(result["done"], 1);
+        assert_eq!(list.in_progress().as_deref(), Some("Fix the off-by-one"));
+        assert_eq!(list.status_line().unwrap(), "Plan: 1/3 done · now: Fix the off-by-one");
+        assert_eq!(
+            format_todo_list(&list.items()),
+            "Plan (1/3 done):\n  [x] Read the parser\n  [~] Fix the off-by-one\n  [ ] Add a test"
+        );
+
+        assert!(tool.execute(serde_json::json!({ "todos": [{ "content": "x", "status": "blocked" }] })).await.is_err());
+    }
+}
@@ -0,0 +1,408 @@
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE, LOCATION};
+use reqwest::{redirect, Client, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::Mutex;
+
+use super::{rate_limit, CliTool, ToolError};
+use crate::config::NetworkConfig;
+
+const ROBOTS_TIMEOUT_SECONDS: u64 = 5;
+const USER_AGENT_TOKEN: &str = "opencode";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UrlFetchInput {
+    pub url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct UrlFetchOutput {
+    pub status: u16,
+    pub final_url: String,
+    pub content_type: Option<String>,
+    pub title: Option<String>,
+    pub content: String,
+    pub bytes: usize,
+    pub truncated: bool,
+}
+
+#[derive(Error, Debug)]
+pub enum UrlFetchError {
+    #[error("Invalid URL '{0}'")]
+    InvalidUrl(String),
+    #[error("Only http and https URLs can be fetched, got '{0}'")]
+    UnsupportedScheme(String),
+    #[error("Fetching {0} is disallowed by the site's robots.txt")]
+    DisallowedByRobots(String),
+    #[error("Stopped after {0} redirects")]
+    TooManyRedirects(usize),
+    #[error("Network error: {0}")]
+    NetworkError(#[from] reqwest::Error),
+}
+
+impl From<UrlFetchError> for ToolError {
+    fn from(error: UrlFetchError) -> Self {
+        match error {
+            UrlFetchError::NetworkError(e) => ToolError::NetworkError { source: anyhow::anyhow!(e) },
+            UrlFetchError::DisallowedByRobots(url) => ToolError::PermissionDenied { resource: url },
+            other => ToolError::Other { message: other.to_string() },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct RobotsRules {
+    allow: Vec<String>,
+    disallow: Vec<String>,
+}
+
+impl RobotsRules {
+    // Longest matching rule wins; Allow wins ties, as in Google's robots.txt spec.
+    fn is_allowed(&self, path: &str) -> bool {
+        let longest = |rules: &[String]| {
+            rules
+                .iter()
+                .filter(|r| !r.is_empty() && path.starts_with(r.as_str()))
+                .map(|r| r.len())
+                .max()
+        };
+        match (longest(&self.allow), longest(&self.disallow)) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(a), Some(d)) => a >= d,
+        }
+    }
+}
+
+// Collects the rules from the group for our user agent, falling back to the `*` group.
+fn parse_robots(content: &str) -> RobotsRules {
+    let mut specific = RobotsRules::default();
+    let mut wildcard = RobotsRules::default();
+    let mut has_specific = false;
+    let mut current_agents: Vec<String> = Vec::new();
+    let mut in_rules = false;
+
+    for raw_line in content.lines() {
+        let line = raw_line.split('#').next().unwrap_or("").trim();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim().to_string();
+        match key.as_str() {
+            "user-agent" => {
+                if in_rules {
+                    current_agents.clear();
+                    in_rules = false;
+                }
+                current_agents.push(value.to_lowercase());
+            }
+            "allow" | "disallow" => {
+                in_rules = true;
+                for agent in &current_agents {
+                    let target = if agent == "*" {
+                        &mut wildcard
+                    } else if USER_AGENT_TOKEN.contains(agent.as_str()) || agent.contains(USER_AGENT_TOKEN) {
+                        has_specific = true;
+                        &mut specific
+                    } else {
+                        continue;
+                    };
+                    if key == "allow" {
+                        target.allow.push(value.clone());
+                    } else {
+                        target.disallow.push(value.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if has_specific {
+        specific
+    } else {
+        wildcard
+    }
+}
+
+pub fn extract_title(html: &str) -> Option<String> {
+    // ASCII lowercasing keeps byte offsets, so they can index `html`.
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title>")?;
+    let title = html[start..end].split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+// Page redirects are followed by hand so robots.txt is checked again at every hop; robots.txt
+// itself is fetched with a client that follows them.
+#[derive(Debug)]
+pub struct UrlFetcher {
+    client: Client,
+    robots_client: Client,
+    max_redirects: usize,
+    max_bytes: usize,
+    respect_robots_txt: bool,
+    robots_cache: Mutex<HashMap<String, RobotsRules>>,
+}
+
+impl UrlFetcher {
+    pub fn new(config: &NetworkConfig) -> Self {
+        let client = |policy: redirect::Policy| {
+            Client::builder()
+                .user_agent(format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")))
+                .timeout(Duration::from_secs(config.fetch_timeout_seconds))
+                .redirect(policy)
+                .build()
+                .unwrap_or_else(|e| {
+                    tracing::error!("Failed to build URL fetch client, using defaults: {}", e);
+                    Client::new()
+                })
+        };
+        UrlFetcher {
+            client: client(redirect::Policy::none()),
+            robots_client: client(redirect::Policy::limited(config.fetch_max_redirects)),
+            max_redirects: config.fetch_max_redirects,
+            max_bytes: config.fetch_max_bytes,
+            respect_robots_txt: config.respect_robots_txt,
+            robots_cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn robots_rules(&self, url: &Url) -> RobotsRules {
+        let origin = url.origin().ascii_serialization();
+        if let Some(rules) = self.robots_cache.lock().await.get(&origin) {
+            return rules.clone();
+        }
+        let robots_url = format!("{}/robots.txt", origin);
+        let rules = match self
+            .robots_client
+            .get(&robots_url)
+            .timeout(Duration::from_secs(ROBOTS_TIMEOUT_SECONDS))
+            .send()
+            .await
+        {
+            Ok(resp) if resp.status().is_success() => parse_robots(&resp.text().await.unwrap_or_default()),
+            Ok(resp) => {
+                tracing::debug!("No usable robots.txt at {} (status {})", robots_url, resp.status());
+                RobotsRules::default()
+            }
+            Err(e) => {
+                tracing::debug!("Failed to fetch {}: {}", robots_url, e);
+                RobotsRules::default()
+            }
+        };
+        self.robots_cache.lock().await.insert(origin, rules.clone());
+        rules
+    }
+
+    pub async fn fetch(&self, raw_url: &str) -> Result<UrlFetchOutput, UrlFetchError> {
+        let mut url = Url::parse(raw_url).map_err(|_| UrlFetchError::InvalidUrl(raw_url.to_string()))?;
+        let mut redirects = 0;
+        let response = loop {
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err(UrlFetchError::UnsupportedScheme(url.scheme().to_string()));
+            }
+            if self.respect_robots_txt && !self.robots_rules(&url).await.is_allowed(url.path()) {
+                return Err(UrlFetchError::DisallowedByRobots(url.to_string()));
+            }
+            let response = self.client.get(url.clone()).send().await?;
+            let location = response.headers().get(LOCATION).and_then(|v| v.to_str().ok());
+            match location {
+                Some(location) if response.status().is_redirection() => {
+                    if redirects == self.max_redirects {
+                        return Err(UrlFetchError::TooManyRedirects(redirects));
+                    }
+                    url = url.join(location).map_err(|_| UrlFetchError::InvalidUrl(location.to_string()))?;
+                    redirects += 1;
+                }
+                _ => break response,
+            }
+        };
+        let status = response.status().as_u16();
+        let final_url = response.url().to_string();
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        let declared_length = response
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<usize>().ok());
+
+        let mut body: Vec<u8> = Vec::new();
+        let mut truncated = declared_length.is_some_and(|len| len > self.max_bytes);
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            let remaining = self.max_bytes.saturating_sub(body.len());
+            if chunk.len() > remaining {
+                body.extend_from_slice(&chunk[..remaining]);
+                truncated = true;
+                break;
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        let content = String::from_utf8_lossy(&body).to_string();
+        let is_html = content_type.as_deref().is_some_and(|ct| ct.contains("html"));
+        let title = if is_html { extract_title(&content) } else { None };
+        Ok(UrlFetchOutput {
+            status,
+            final_url,
+            content_type,
+            title,
+            bytes: body.len(),
+            content,
+            truncated,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct UrlFetchTool {
+    fetcher: UrlFetcher,
+}
+
+impl UrlFetchTool {
+    pub fn new(config: &NetworkConfig) -> Self {
+        UrlFetchTool {
+            fetcher: UrlFetcher::new(config),
+        }
+    }
+}
+
+#[async_trait]
+impl CliTool for UrlFetchTool {
+    fn name(&self) -> String {
+        "UrlFetchTool".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Fetches the contents of an http(s) URL, honouring robots.txt and a size limit. \
+         Returns the final URL after redirects, content type, page title and (possibly truncated) content. \
+         Args: {\"url\": string}"
+            .to_string()
+    }
+
+    fn parameters_schema(&self) -> anyhow::Result<Value> {
+        Ok(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "description": "The absolute http or https URL to fetch."
+                }
+            },
+            "required": ["url"]
+        }))
+    }
+
+    fn network_target(&self, args: &Value) -> Option<String> {
+        args.get("url").and_then(|v| v.as_str()).and_then(rate_limit::domain_of)
+    }
+
+    async fn execute(&self, args: Value) -> Result<Value, ToolError> {
+        let input: UrlFetchInput = serde_json::from_value(args).map_err(|e| ToolError::InvalidArguments {
+            tool_name: self.name(),
+            details: format!("Failed to parse arguments: {}", e),
+        })?;
+        let output = self.fetcher.fetch(&input.url).await?;
+        serde_json::to_value(output).map_err(|e| ToolError::Other {
+            message: format!("Failed to serialize output: {}", e),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config(max_bytes: usize, respect_robots_txt: bool) -> NetworkConfig {
+        NetworkConfig {
+            fetch_max_bytes: max_bytes,
+            respect_robots_txt,
+            ..NetworkConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_robots_prefers_specific_group() {
+        let robots = "User-agent: *\nDisallow: /\n\nUser-agent: opencode\nDisallow: /private\nAllow: /private/docs\n";
+        let rules = parse_robots(robots);
+        assert!(rules.is_allowed("/public"));
+        assert!(!rules.is_allowed("/private/keys"));
+        assert!(rules.is_allowed("/private/docs/index.html"));
+
+        let wildcard_only = parse_robots("User-agent: *\nDisallow: /admin # staff only\n");
+        assert!(!wildcard_only.is_allowed("/admin/panel"));
+        assert!(wildcard_only.is_allowed("/"));
+    }
+
+    #[test]
+    fn test_extract_title() {
+        assert_eq!(extract_title("<html><TITLE>\n  Hello   World </TITLE></html>").as_deref(), Some("Hello World"));
+        assert_eq!(extract_title("<title lang=\"en\">Docs</title>").as_deref(), Some("Docs"));
+        assert_eq!(extract_title("<p>no title</p>"), None);
+        // 'İ' lowercases to three bytes, which used to shift the offsets.
+        assert_eq!(extract_title("<p>İİİ</p><title>Café</title>").as_deref(), Some("Café"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_follows_redirect_and_truncates() {
+        let mut server = mockito::Server::new_async().await;
+        let _robots = server.mock("GET", "/robots.txt").with_status(404).create_async().await;
+        let _redirect = server
+            .mock("GET", "/old")
+            .with_status(301)
+            .with_header("location", "/new")
+            .create_async()
+            .await;
+        let _page = server
+            .mock("GET", "/new")
+            .with_status(200)
+            .with_header("content-type", "text/html; charset=utf-8")
+            .with_body("<html><title>New Page</title><body>0123456789</body></html>")
+            .create_async()
+            .await;
+
+        let fetcher = UrlFetcher::new(&test_config(30, true));
+        let output = fetcher.fetch(&format!("{}/old", server.url())).await.unwrap();
+        assert_eq!(output.status, 200);
+        assert!(output.final_url.ends_with("/new"));
+        assert_eq!(output.title.as_deref(), Some("New Page"));
+        assert!(output.truncated);
+        assert_eq!(output.bytes, 30);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_respects_robots() {
+        let mut server = mockito::Server::new_async().await;
+        let _robots = server
+            .mock("GET", "/robots.txt")
+            .with_status(200)
+            .with_body("User-agent: *\nDisallow: /secret\n")
+            .create_async()
+            .await;
+        let _page = server.mock("GET", "/secret/page").with_status(200).with_body("hidden").create_async().await;
+
+        let url = format!("{}/secret/page", server.url());
+        let fetcher = UrlFetcher::new(&test_config(1024, true));
+        assert!(matches!(fetcher.fetch(&url).await, Err(UrlFetchError::DisallowedByRobots(_))));
+
+        let _redirect = server.mock("GET", "/open").with_status(302).with_header("location", "/secret/page").create_async().await;
+        let redirected = format!("{}/open", server.url());
+        assert!(matches!(fetcher.fetch(&redirected).await, Err(UrlFetchError::DisallowedByRobots(_))));
+
+        let ignoring = UrlFetcher::new(&test_config(1024, false));
+        assert_eq!(ignoring.fetch(&url).await.unwrap().content, "hidden");
+    }
+}
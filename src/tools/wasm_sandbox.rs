@@ -0,0 +1,126 @@
+use anyhow::{bail, Result};
+use std::path::PathBuf;
+use tokio::process::Command;
+
+use crate::config::UserToolConfig;
+
+// Sandboxed user tools run under the wasmtime CLI, which only exposes what it is granted.
+pub const WASM_RUNTIME: &str = "wasmtime";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WasmSandbox {
+    module: PathBuf,
+    allow_dirs: Vec<String>,
+    allow_network: bool,
+    allow_env: Vec<String>,
+}
+
+impl WasmSandbox {
+    pub fn from_config(config: &UserToolConfig) -> Result<Option<Self>> {
+        if !config.sandbox {
+            return Ok(None);
+        }
+        let Some(module) = &config.wasm_module else {
+            bail!("Tool '{}' has sandbox = true but no wasm_module", config.name);
+        };
+        Ok(Some(WasmSandbox {
+            module: PathBuf::from(module),
+            allow_dirs: config.allow_dirs.clone(),
+            allow_network: config.allow_network,
+            allow_env: config.allow_env.clone(),
+        }))
+    }
+
+    // Builds the runtime invocation. The module gets no filesystem, network or environment
+    // access beyond the configured grants.
+    pub fn command(&self, argv: &[String]) -> Command {
+        let mut command = Command::new(WASM_RUNTIME);
+        command.arg("run");
+        for dir in &self.allow_dirs {
+            command.arg("--dir").arg(dir);
+        }
+        if self.allow_network {
+            command.arg("-S").arg("inherit-network=y");
+        }
+        for key in &self.allow_env {
+            if let Ok(value) = std::env::var(key) {
+                command.arg("--env").arg(format!("{}={}", key, value));
+            }
+        }
+        command.arg(&self.module).arg("--").args(argv);
+        command.env_clear().kill_on_drop(true);
+        if let Some(path) = std::env::var_os("PATH") {
+            command.env("PATH", path);
+        }
+        command
+    }
+}
+
+// Splits a command template into arguments, honouring single and double quotes. Placeholders
+// are substituted per argument afterwards, so argument values can't inject extra arguments.
+pub fn split_template(template: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut quote: Option<char> = None;
+    for c in template.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_token = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_token {
+                    args.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        bail!("Unterminated quote in command template: {}", template);
+    }
+    if in_token {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_template() {
+        assert_eq!(
+            split_template(r#"count --path {path} --label "two words" ''"#).unwrap(),
+            vec!["count", "--path", "{path}", "--label", "two words", ""]
+        );
+        assert!(split_template("echo 'unterminated").is_err());
+    }
+
+    #[test]
+    fn test_command_only_passes_granted_capabilities() {
+        let config = UserToolConfig {
+            name: "wc".to_string(),
+            sandbox: true,
+            wasm_module: Some("tools/wc.wasm".to_string()),
+            allow_dirs: vec!["./src".to_string()],
+            ..UserToolConfig::default()
+        };
+        let sandbox = WasmSandbox::from_config(&config).unwrap().unwrap();
+        let command = sandbox.command(&["-l".to_string(), "src/main.rs; rm -rf /".to_string()]);
+        let args: Vec<String> = command.as_std().get_args().map(|a| a.to_string_lossy().to_string()).collect();
+        assert_eq!(args, vec!["run", "--dir", "./src", "tools/wc.wasm", "--", "-l", "src/main.rs; rm -rf /"]);
+
+        let missing_module = UserToolConfig { sandbox: true, ..UserToolConfig::default() };
+        assert!(WasmSandbox::from_config(&missing_module).is_err());
+        assert!(WasmSandbox::from_config(&UserToolConfig::default()).unwrap().is_none());
+    }
+}
@@ -7,6 +7,8 @@ use std::env; // Needed for reading environment variable
 
 use super::{CliTool, ToolError}; // Correct trait and error type
 
+const BRAVE_SEARCH_URL: &str = "https://api.search.brave.com/res/v1/web/search";
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WebSearchInput {
     pub query: String,
@@ -84,6 +86,10 @@ impl CliTool for WebSearchTool {
         }))
     }
 
+    fn network_target(&self, _args: &Value) -> Option<String> {
+        super::rate_limit::domain_of(BRAVE_SEARCH_URL)
+    }
+
     async fn execute(&self, args: Value) -> Result<Value, ToolError> {
         let input: WebSearchInput = serde_json::from_value(args).map_err(|e| {
             ToolError::InvalidArguments {
@@ -119,7 +125,7 @@ impl CliTool for WebSearchTool {
         }
 
         let response = client
-            .get("https://api.search.brave.com/res/v1/web/search")
+            .get(BRAVE_SEARCH_URL)
             .header("Accept", "application/json")
             .header("X-Subscription-Token", &api_key) // Pass reference
             .query(&[("q", &input.query), ("count", &num_results.to_string())])
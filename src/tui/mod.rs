@@ -1,62 +1,141 @@
 use anyhow::Context;
 use iocraft::prelude::*;
-use std::io::stdout;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::time::Duration;
-use dialoguer::Confirm;
-use similar::{ChangeTag, TextDiff};
+use dialoguer::{Confirm, Input, Select};
 use tokio::sync::mpsc;
+use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::{Arc, Mutex};
 
+use crate::events::{self, UiEvent};
+use crate::tools::todo::format_todo_list;
+
+pub mod pager;
+pub mod theme;
+pub mod wrap;
+
+use wrap::{wrap_for_output, wrap_text, width_for};
+
+// How much the terminal shows, from `-q`/`-v`. Warnings, errors and results always show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    // Only the final answer: no progress messages, tool activity or spinners.
+    Quiet,
+    Normal,
+    // Also tool arguments and output, and each request's model and token usage.
+    Verbose,
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+// Tool output beyond this is cut in verbose mode; the model still sees all of it.
+const VERBOSE_OUTPUT_CHARS: usize = 2000;
+
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+}
+
+pub fn verbosity() -> Verbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => Verbosity::Quiet,
+        1 => Verbosity::Normal,
+        _ => Verbosity::Verbose,
+    }
+}
+
+fn verbose_value(value: &serde_json::Value) -> String {
+    let text = value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string());
+    match text.char_indices().nth(VERBOSE_OUTPUT_CHARS) {
+        Some((cut, _)) => format!("{}... ({} more chars)", &text[..cut], text[cut..].chars().count()),
+        None => text,
+    }
+}
+
 pub fn print_info(message: &str) {
-    element! { Text(content: format!("{}\n", message)) }.print();
+    events::emit(UiEvent::Info { message: message.to_string() });
 }
 
 pub fn print_warning(message: &str) {
-    element! {
-        Text(color: Color::Yellow, content: format!("Warning: {}\n", message))
-    }
-    .print();
+    events::emit(UiEvent::Warning { message: message.to_string() });
 }
 
 pub fn print_error(message: &str) {
-    element! {
-        Text(color: Color::Red, content: format!("Error: {}\n", message))
+    events::emit(UiEvent::Error { message: message.to_string() });
+}
+
+// Detail for `-v`, such as request metadata; nothing at the other levels.
+pub fn print_verbose(message: &str) {
+    if verbosity() == Verbosity::Verbose {
+        print_info(message);
     }
-    .print();
 }
 
 pub fn print_result(content: &str) {
-    element! { Text(content: format!("{}\n", content)) }.print();
+    events::emit(UiEvent::Result { content: content.to_string() });
 }
 
-#[allow(dead_code)]
-pub fn print_diff(old_text: &str, new_text: &str) -> anyhow::Result<()> {
-    let diff = TextDiff::from_lines(old_text, new_text);
-    let mut stdout = stdout();
-
-    for change in diff.iter_all_changes() {
-        let (prefix, color) = match change.tag() {
-            ChangeTag::Delete => ("-", crossterm::style::Color::Red),
-            ChangeTag::Insert => ("+", crossterm::style::Color::Green),
-            ChangeTag::Equal => (" ", crossterm::style::Color::Reset),
-        };
-
-        crossterm::execute!(stdout, crossterm::style::SetForegroundColor(color))
-            .context("Failed to set foreground color")?;
-
-        for line in change.value().lines() {
-            crossterm::execute!(stdout, crossterm::style::Print(prefix), crossterm::style::Print(line), crossterm::style::Print("\n"))
-                .context("Failed to print diff line")?;
+// The terminal subscriber of the event bus.
+pub fn render_event(event: &UiEvent) {
+    let palette = theme::palette();
+    let color = |index: u8| Color::AnsiValue(index);
+    let verbose = verbosity() == Verbosity::Verbose;
+    match event {
+        UiEvent::Info { .. } | UiEvent::ToolStarted { .. } | UiEvent::TodosUpdated { .. } | UiEvent::ToolFinished { success: true, .. }
+            if verbosity() == Verbosity::Quiet => {}
+        UiEvent::Info { message } => element! { Text(content: format!("{}\n", wrap_for_output(message))) }.print(),
+        UiEvent::Warning { message } => element! {
+            Text(color: color(palette.warning), content: format!("{}\n", wrap_for_output(&format!("Warning: {}", message))))
         }
-
-        crossterm::execute!(stdout, crossterm::style::ResetColor).context("Failed to reset color")?;
+        .print(),
+        UiEvent::Error { message } => element! {
+            Text(color: color(palette.error), content: format!("{}\n", wrap_for_output(&format!("Error: {}", message))))
+        }
+        .print(),
+        // Results are payloads (diffs, code, messages to paste), so they are never re-wrapped.
+        UiEvent::Result { content } => {
+            let highlighted = theme::highlight_code_blocks(content);
+            // iocraft measures escape sequences as text, so highlighted output is printed as is.
+            if highlighted != *content {
+                println!("{}", highlighted);
+            } else {
+                element! { Text(content: format!("{}\n", content)) }.print()
+            }
+        }
+        UiEvent::ToolStarted { tool, arguments } => {
+            let arguments = if verbose { format!("  arguments: {}\n", verbose_value(arguments)) } else { String::new() };
+            element! { Text(color: color(palette.tool), content: format!("\nRunning tool: {}\n{}", tool, arguments)) }.print()
+        }
+        UiEvent::ToolFinished { tool, success: true, output } => {
+            let output = if verbose { format!("{}\n", verbose_value(output)) } else { String::new() };
+            element! { Text(color: color(palette.success), content: format!("  - {} finished\n{}", tool, output)) }.print()
+        }
+        UiEvent::ToolFinished { tool, success: false, output } => element! {
+            Text(color: color(palette.error), content: format!("  - {} failed: {}\n", tool, output.as_str().unwrap_or_default()))
+        }
+        .print(),
+        UiEvent::TodosUpdated { todos } => element! {
+            Text(color: color(palette.todo), content: format!("{}\n", format_todo_list(todos)))
+        }
+        .print(),
+        UiEvent::MessageStart { model } if verbose => element! { Text(content: format!("Model: {}\n", model)) }.print(),
+        UiEvent::Usage { prompt_tokens, completion_tokens, .. } if verbose => element! {
+            Text(content: format!("Tokens: {} prompt, {} completion\n", prompt_tokens, completion_tokens))
+        }
+        .print(),
+        // Commands print the final text themselves; these are for machine-readable consumers.
+        UiEvent::MessageStart { .. } | UiEvent::ContentDelta { .. } | UiEvent::Usage { .. } | UiEvent::Done { .. } => {}
     }
+}
 
-    Ok(())
+// Hunks of the change from `old_text` to `new_text`; diffs taller than the terminal open in
+// a pager so an approval prompt never scrolls out of view.
+pub fn print_diff(old_text: &str, new_text: &str) -> anyhow::Result<()> {
+    pager::page_diff(&pager::unified_diff(old_text, new_text))
 }
 
 pub fn start_spinner(message: &str) -> ProgressBar {
+    if verbosity() == Verbosity::Quiet {
+        return ProgressBar::hidden();
+    }
     let pb = ProgressBar::new_spinner();
     pb.enable_steady_tick(Duration::from_millis(120));
     pb.set_style(
@@ -79,7 +158,6 @@ pub fn start_spinner(message: &str) -> ProgressBar {
     pb
 }
 
-#[allow(dead_code)]
 pub fn prompt_confirmation(prompt_message: &str) -> anyhow::Result<bool> {
     Confirm::new()
         .with_prompt(prompt_message)
@@ -88,19 +166,43 @@ pub fn prompt_confirmation(prompt_message: &str) -> anyhow::Result<bool> {
         .context("Failed to get user confirmation")
 }
 
+pub type StreamReceiver = Arc<Mutex<Option<mpsc::UnboundedReceiver<Result<String, String>>>>>;
+
+pub fn prompt_text(prompt_message: &str, default: &str) -> anyhow::Result<String> {
+    Input::new()
+        .with_prompt(prompt_message)
+        .default(default.to_string())
+        .interact_text()
+        .context("Failed to read user input")
+}
+
+// Index of the chosen item.
+pub fn prompt_select(prompt_message: &str, items: &[String]) -> anyhow::Result<usize> {
+    Select::new()
+        .with_prompt(prompt_message)
+        .items(items)
+        .default(0)
+        .interact()
+        .context("Failed to read user selection")
+}
+
 #[derive(Props, Clone, Default)]
 pub struct StreamingOutputProps {
-    pub stream_rx: Arc<Mutex<Option<mpsc::UnboundedReceiver<Result<String, String>>>>>,
+    pub stream_rx: StreamReceiver,
 }
 
 #[component]
 pub fn StreamingOutput(mut hooks: Hooks, props: &StreamingOutputProps) -> impl Into<AnyElement<'static>> {
     let mut content = hooks.use_state(String::new);
     let mut error_message = hooks.use_state(|| None::<String>);
+    let mut finished = hooks.use_state(|| false);
+    let mut system = hooks.use_context_mut::<SystemContext>();
+    // Re-rendered, and so re-wrapped, whenever the terminal is resized.
+    let (columns, _) = hooks.use_terminal_size();
     let rx_ref = props.stream_rx.clone();
 
     hooks.use_future(async move {
-        let mut stream_rx = {
+        let stream_rx = {
             let mut guard = rx_ref.lock().unwrap();
             guard.take()
         };
@@ -119,11 +221,16 @@ pub fn StreamingOutput(mut hooks: Hooks, props: &StreamingOutputProps) -> impl I
                 }
             }
         }
+        finished.set(true);
     });
 
+    if finished.get() {
+        system.exit();
+    }
+
     element! {
         View() {
-            Text(content: content.to_string())
+            Text(content: wrap_text(&content.to_string(), width_for(columns as usize)))
         }
     }
 }
@@ -146,6 +253,18 @@ mod tests {
         assert!(result_all_change.is_ok(), "print_diff failed on all change: {:?}", result_all_change.err());
     }
 
+    #[test]
+    fn test_verbosity_flags_and_verbose_output() {
+        use clap::Parser;
+        let cli = crate::cli::commands::Cli::try_parse_from(["opencode", "ask", "hi", "-q"]).unwrap();
+        assert!(cli.quiet && !cli.verbose);
+        assert!(crate::cli::commands::Cli::try_parse_from(["opencode", "-q", "-v", "ask", "hi"]).is_err());
+
+        assert_eq!(verbose_value(&serde_json::json!("plain")), "plain");
+        let long = verbose_value(&serde_json::json!("x".repeat(VERBOSE_OUTPUT_CHARS + 5)));
+        assert!(long.ends_with("... (5 more chars)"));
+    }
+
     #[test]
     fn test_prompt_confirmation_compiles() {
         let _func: fn(&str) -> anyhow::Result<bool> = prompt_confirmation;
@@ -0,0 +1,274 @@
+use anyhow::{Context, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+use similar::TextDiff;
+use std::io::{stdout, IsTerminal, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::tui::theme;
+
+// `[output] tui`; when off, long diffs go to `$PAGER` instead of the built-in viewer.
+static TUI_ENABLED: AtomicBool = AtomicBool::new(true);
+// Lines of unchanged text kept around each hunk.
+const CONTEXT_LINES: usize = 3;
+const HELP: &str = "j/k scroll  space/b page  ]/[ hunk  / search  n/N match  q done";
+
+pub fn set_tui_enabled(enabled: bool) {
+    TUI_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+// `old_text` to `new_text` as a unified diff, hunk headers included.
+pub fn unified_diff(old_text: &str, new_text: &str) -> String {
+    TextDiff::from_lines(old_text, new_text).unified_diff().context_radius(CONTEXT_LINES).to_string()
+}
+
+// The `[ui] theme` palette index for a diff line; None for context lines.
+fn line_tone(line: &str) -> Option<u8> {
+    let palette = theme::palette();
+    match line.as_bytes().first() {
+        Some(b'+') if !line.starts_with("+++") => Some(palette.diff_add),
+        Some(b'-') if !line.starts_with("---") => Some(palette.diff_remove),
+        Some(b'@') => Some(palette.diff_hunk),
+        _ => None,
+    }
+}
+
+fn line_color(line: &str) -> Color {
+    line_tone(line).map_or(Color::Reset, Color::AnsiValue)
+}
+
+// Where the viewer is in a diff of `lines.len()` lines shown `height` at a time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PagerState {
+    pub top: usize,
+    pub height: usize,
+    pub search: Option<String>,
+    pub message: Option<String>,
+}
+
+impl PagerState {
+    pub fn new(height: usize) -> Self {
+        PagerState { top: 0, height: height.max(1), search: None, message: None }
+    }
+
+    fn last_top(&self, lines: &[String]) -> usize {
+        lines.len().saturating_sub(self.height)
+    }
+
+    fn scroll_to(&mut self, line: usize, lines: &[String]) {
+        self.top = line.min(self.last_top(lines));
+    }
+
+    pub fn scroll(&mut self, delta: isize, lines: &[String]) {
+        self.scroll_to(self.top.saturating_add_signed(delta), lines);
+    }
+
+    // Moves to the next (or previous) hunk header after (or before) the top line.
+    pub fn jump_hunk(&mut self, forward: bool, lines: &[String]) {
+        let mut hunks = lines.iter().enumerate().filter(|(_, l)| l.starts_with("@@")).map(|(i, _)| i);
+        let target = if forward { hunks.clone().find(|i| *i > self.top) } else { hunks.rfind(|i| *i < self.top) };
+        match target {
+            Some(line) => self.scroll_to(line, lines),
+            None => self.message = Some(if forward { "No later hunk" } else { "No earlier hunk" }.to_string()),
+        }
+    }
+
+    // Moves to the next (or previous) line containing the search text, wrapping around.
+    pub fn jump_match(&mut self, forward: bool, lines: &[String]) {
+        let Some(query) = self.search.clone().filter(|q| !q.is_empty()) else { return };
+        let n = lines.len();
+        let found = (1..=n)
+            .map(|step| if forward { (self.top + step) % n } else { (self.top + n - step % n) % n })
+            .find(|i| lines[*i].contains(&query));
+        match found {
+            Some(line) => self.scroll_to(line, lines),
+            None => self.message = Some(format!("Not found: {}", query)),
+        }
+    }
+
+    // Applies one key press; false once the viewer should close.
+    pub fn handle_key(&mut self, key: KeyEvent, lines: &[String]) -> bool {
+        self.message = None;
+        let page = self.height as isize;
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Char('j') | KeyCode::Down | KeyCode::Enter => self.scroll(1, lines),
+            KeyCode::Char('k') | KeyCode::Up => self.scroll(-1, lines),
+            KeyCode::Char(' ') | KeyCode::PageDown | KeyCode::Char('f') => self.scroll(page, lines),
+            KeyCode::Char('b') | KeyCode::PageUp => self.scroll(-page, lines),
+            KeyCode::Char('g') | KeyCode::Home => self.top = 0,
+            KeyCode::Char('G') | KeyCode::End => self.top = self.last_top(lines),
+            KeyCode::Char(']') => self.jump_hunk(true, lines),
+            KeyCode::Char('[') => self.jump_hunk(false, lines),
+            KeyCode::Char('n') => self.jump_match(true, lines),
+            KeyCode::Char('N') => self.jump_match(false, lines),
+            _ => {}
+        }
+        true
+    }
+}
+
+fn draw(lines: &[String], state: &PagerState, width: usize, prompt: Option<&str>) -> Result<()> {
+    let mut out = stdout();
+    queue!(out, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+    for (row, line) in lines.iter().skip(state.top).take(state.height).enumerate() {
+        let shown: String = line.chars().take(width).collect();
+        queue!(out, cursor::MoveTo(0, row as u16), SetForegroundColor(line_color(line)), Print(shown), ResetColor)?;
+    }
+    let bottom = (state.top + state.height).min(lines.len());
+    let status = match (prompt, &state.message) {
+        (Some(prompt), _) => format!("/{}", prompt),
+        (None, Some(message)) => message.clone(),
+        (None, None) => format!("lines {}-{} of {}  {}", state.top + 1, bottom, lines.len(), HELP),
+    };
+    let status: String = status.chars().take(width).collect();
+    queue!(out, cursor::MoveTo(0, state.height as u16), SetAttribute(Attribute::Reverse), Print(status), SetAttribute(Attribute::Reset))?;
+    out.flush().context("Failed to draw the diff viewer")
+}
+
+// Reads a search query typed on the status line; None when cancelled with Esc.
+fn read_query(lines: &[String], state: &PagerState, width: usize) -> Result<Option<String>> {
+    let mut query = String::new();
+    loop {
+        draw(lines, state, width, Some(&query))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Enter => return Ok(Some(query)),
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Backspace => {
+                    query.pop();
+                }
+                KeyCode::Char(c) => query.push(c),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn run_viewer(lines: &[String]) -> Result<()> {
+    let (columns, rows) = terminal::size().context("Failed to read the terminal size")?;
+    let mut state = PagerState::new(rows.saturating_sub(1) as usize);
+    loop {
+        draw(lines, &state, columns as usize, None)?;
+        let Event::Key(key) = event::read()? else { continue };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        if key.code == KeyCode::Char('/') {
+            if let Some(query) = read_query(lines, &state, columns as usize)? {
+                state.search = Some(query);
+                state.jump_match(true, lines);
+            }
+            continue;
+        }
+        if !state.handle_key(key, lines) {
+            return Ok(());
+        }
+    }
+}
+
+// The full-screen viewer on the alternate screen, which restores the terminal however it ends.
+fn view_in_tui(lines: &[String]) -> Result<()> {
+    terminal::enable_raw_mode().context("Failed to enable raw mode")?;
+    execute!(stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+    let result = run_viewer(lines);
+    let _ = execute!(stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+    result
+}
+
+fn colored(lines: &[String]) -> String {
+    lines
+        .iter()
+        .map(|line| match line_tone(line) {
+            Some(index) => format!("\x1b[38;5;{}m{}\x1b[0m\n", index, line),
+            None => format!("{}\n", line),
+        })
+        .collect()
+}
+
+// `$PAGER`, or `less -R` so the colors survive.
+fn view_in_pager(lines: &[String]) -> Result<()> {
+    let pager = std::env::var("PAGER").ok().filter(|p| !p.trim().is_empty()).unwrap_or_else(|| "less -R".to_string());
+    let (shell, shell_arg) = if cfg!(target_os = "windows") { ("cmd", "/C") } else { ("sh", "-c") };
+    let mut child = Command::new(shell)
+        .arg(shell_arg)
+        .arg(&pager)
+        .stdin(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to start pager '{}'", pager))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager closing early (the user quit) is not an error.
+        let _ = stdin.write_all(colored(lines).as_bytes());
+    }
+    child.wait().with_context(|| format!("Pager '{}' failed", pager))?;
+    Ok(())
+}
+
+fn print_lines(lines: &[String]) -> Result<()> {
+    let mut out = stdout();
+    for line in lines {
+        execute!(out, SetForegroundColor(line_color(line)), Print(line), Print("\n"), ResetColor).context("Failed to print diff line")?;
+    }
+    Ok(())
+}
+
+// Shows a unified diff: printed when it fits the terminal or output is not one, otherwise in
+// the built-in viewer, or `$PAGER` when `[output] tui = false`.
+pub fn page_diff(diff: &str) -> Result<()> {
+    let lines: Vec<String> = diff.lines().map(str::to_string).collect();
+    let rows = (stdout().is_terminal() && std::io::stdin().is_terminal())
+        .then(|| terminal::size().ok())
+        .flatten()
+        .map(|(_, rows)| rows as usize);
+    match rows {
+        Some(rows) if lines.len() >= rows => {
+            let shown = if TUI_ENABLED.load(Ordering::Relaxed) { view_in_tui(&lines) } else { view_in_pager(&lines) };
+            shown.or_else(|e| {
+                tracing::warn!("Diff viewer unavailable, printing instead: {:#}", e);
+                print_lines(&lines)
+            })
+        }
+        _ => print_lines(&lines),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_pager_navigates_hunks_and_search() {
+        let old: String = (0..100).map(|i| format!("line {}\n", i)).collect();
+        let new = old.replace("line 10\n", "line ten\n").replace("line 80\n", "line eighty\n");
+        let lines: Vec<String> = unified_diff(&old, &new).lines().map(str::to_string).collect();
+        let hunks: Vec<usize> = lines.iter().enumerate().filter(|(_, l)| l.starts_with("@@")).map(|(i, _)| i).collect();
+        assert_eq!(hunks.len(), 2);
+
+        let mut state = PagerState::new(4);
+        state.handle_key(key(KeyCode::Char(']')), &lines);
+        assert_eq!(state.top, hunks[1]);
+        state.handle_key(key(KeyCode::Char(']')), &lines);
+        assert_eq!(state.message.as_deref(), Some("No later hunk"));
+        state.handle_key(key(KeyCode::Char('[')), &lines);
+        assert_eq!(state.top, hunks[0]);
+
+        state.search = Some("eighty".to_string());
+        state.handle_key(key(KeyCode::Char('n')), &lines);
+        assert_eq!(lines[state.top], "+line eighty");
+        state.handle_key(key(KeyCode::Char('G')), &lines);
+        assert_eq!(state.top, lines.len() - 4);
+        assert!(!state.handle_key(key(KeyCode::Char('q')), &lines));
+    }
+}
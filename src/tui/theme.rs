@@ -0,0 +1,185 @@
+use std::io::{IsTerminal, Write};
+use std::sync::{Mutex, OnceLock};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+
+use crate::config::{ThemeMode, UiConfig};
+
+const DARK_SYNTAX_THEME: &str = "base16-ocean.dark";
+const LIGHT_SYNTAX_THEME: &str = "base16-ocean.light";
+// How long to wait for the terminal to answer the background color query.
+#[cfg(unix)]
+const QUERY_TIMEOUT_MS: i32 = 100;
+
+// Colors for each kind of output, as 256-color palette indexes so both crossterm versions in
+// the tree (ours and iocraft's) render them the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub warning: u8,
+    pub error: u8,
+    pub tool: u8,
+    pub success: u8,
+    pub todo: u8,
+    pub diff_add: u8,
+    pub diff_remove: u8,
+    pub diff_hunk: u8,
+}
+
+// The bright colors OpenCode always used; they wash out on a light background.
+pub const DARK: Palette =
+    Palette { warning: 11, error: 9, tool: 14, success: 10, todo: 13, diff_add: 10, diff_remove: 9, diff_hunk: 14 };
+pub const LIGHT: Palette = Palette { warning: 3, error: 1, tool: 6, success: 2, todo: 5, diff_add: 2, diff_remove: 1, diff_hunk: 4 };
+
+#[derive(Debug)]
+struct ResolvedTheme {
+    palette: Palette,
+    syntax_theme: String,
+}
+
+// `[ui]`, set once at startup; the theme is resolved on first use so commands that print no
+// colors never query the terminal.
+static CONFIG: Mutex<Option<UiConfig>> = Mutex::new(None);
+static RESOLVED: OnceLock<ResolvedTheme> = OnceLock::new();
+static SYNTAXES: OnceLock<(SyntaxSet, ThemeSet)> = OnceLock::new();
+
+pub fn set_ui_config(config: &UiConfig) {
+    *CONFIG.lock().unwrap() = Some(config.clone());
+}
+
+// Whether an `rgb:RRRR/GGGG/BBBB` color (the xterm reply format, 1 to 4 hex digits per
+// channel) is light, by perceived luminance.
+pub fn is_light_rgb(reply: &str) -> Option<bool> {
+    let spec = &reply[reply.find("rgb:")? + 4..];
+    let channels: Vec<f64> = spec
+        .split('/')
+        .take(3)
+        .map(|c| {
+            let hex: String = c.chars().take_while(char::is_ascii_hexdigit).collect();
+            let max = 16f64.powi(hex.len() as i32) - 1.0;
+            u32::from_str_radix(&hex, 16).ok().filter(|_| !hex.is_empty()).map(|v| v as f64 / max)
+        })
+        .collect::<Option<_>>()?;
+    let [r, g, b] = channels[..] else { return None };
+    Some(0.299 * r + 0.587 * g + 0.114 * b > 0.5)
+}
+
+// `COLORFGBG` ("15;0") as set by rxvt, Konsole and others: the last field is the background.
+fn is_light_colorfgbg(value: &str) -> Option<bool> {
+    let background: u8 = value.rsplit(';').next()?.parse().ok()?;
+    Some(matches!(background, 7 | 9..=15))
+}
+
+// Asks the terminal for its background color (OSC 11) and reads the reply without blocking
+// past a short timeout; terminals that do not answer are treated as unknown.
+#[cfg(unix)]
+fn query_background() -> Option<bool> {
+    use std::os::fd::AsRawFd;
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return None;
+    }
+    crossterm::terminal::enable_raw_mode().ok()?;
+    let mut reply = Vec::new();
+    let mut stdout = std::io::stdout();
+    if stdout.write_all(b"\x1b]11;?\x1b\\").and_then(|_| stdout.flush()).is_ok() {
+        let fd = std::io::stdin().as_raw_fd();
+        let mut poll = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+        while reply.len() < 64 && !reply.ends_with(b"\x07") && !reply.ends_with(b"\x1b\\") {
+            // SAFETY: `poll` and `byte` outlive the calls, which write at most one struct/byte.
+            if unsafe { libc::poll(&mut poll, 1, QUERY_TIMEOUT_MS) } <= 0 {
+                break;
+            }
+            let mut byte = 0u8;
+            if unsafe { libc::read(fd, (&mut byte as *mut u8).cast(), 1) } != 1 {
+                break;
+            }
+            reply.push(byte);
+        }
+    }
+    let _ = crossterm::terminal::disable_raw_mode();
+    is_light_rgb(&String::from_utf8_lossy(&reply))
+}
+
+#[cfg(not(unix))]
+fn query_background() -> Option<bool> {
+    None
+}
+
+fn resolve(config: &UiConfig) -> ResolvedTheme {
+    let light = match config.theme {
+        ThemeMode::Light => true,
+        ThemeMode::Dark => false,
+        ThemeMode::Auto => query_background()
+            .or_else(|| std::env::var("COLORFGBG").ok().and_then(|v| is_light_colorfgbg(&v)))
+            .unwrap_or(false),
+    };
+    let default_syntax = if light { LIGHT_SYNTAX_THEME } else { DARK_SYNTAX_THEME };
+    ResolvedTheme {
+        palette: if light { LIGHT } else { DARK },
+        syntax_theme: config.syntax_theme.clone().unwrap_or_else(|| default_syntax.to_string()),
+    }
+}
+
+fn resolved() -> &'static ResolvedTheme {
+    RESOLVED.get_or_init(|| resolve(&CONFIG.lock().unwrap().clone().unwrap_or_default()))
+}
+
+pub fn palette() -> Palette {
+    resolved().palette
+}
+
+fn syntax_theme(themes: &ThemeSet) -> &Theme {
+    let name = &resolved().syntax_theme;
+    themes.themes.get(name).unwrap_or_else(|| {
+        tracing::warn!("Unknown syntax theme '{}'; using {}", name, DARK_SYNTAX_THEME);
+        &themes.themes[DARK_SYNTAX_THEME]
+    })
+}
+
+// `text` with the code inside its fenced blocks highlighted for the terminal. Fences with no
+// known language, and output that is not a terminal, are left as they are.
+pub fn highlight_code_blocks(text: &str) -> String {
+    if !std::io::stdout().is_terminal() || !text.contains("```") {
+        return text.to_string();
+    }
+    let (syntaxes, themes) = SYNTAXES.get_or_init(|| (SyntaxSet::load_defaults_newlines(), ThemeSet::load_defaults()));
+    let theme = syntax_theme(themes);
+    let mut out = String::with_capacity(text.len());
+    let mut highlighter: Option<HighlightLines> = None;
+    for line in LinesWithEndings::from(text) {
+        if let Some(tag) = line.trim_start().strip_prefix("```") {
+            highlighter = match highlighter {
+                Some(_) => None,
+                None => syntaxes.find_syntax_by_token(tag.trim()).map(|syntax| HighlightLines::new(syntax, theme)),
+            };
+            out.push_str(line);
+            continue;
+        }
+        match highlighter.as_mut().and_then(|h| h.highlight_line(line, syntaxes).ok()) {
+            Some(ranges) => out.push_str(&format!("{}\x1b[0m", as_24_bit_terminal_escaped(&ranges, false))),
+            None => out.push_str(line),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_background_detection() {
+        assert_eq!(is_light_rgb("\x1b]11;rgb:ffff/ffff/ffff\x07"), Some(true));
+        assert_eq!(is_light_rgb("\x1b]11;rgb:1c1c/1c1c/2020\x1b\\"), Some(false));
+        assert_eq!(is_light_rgb("rgb:fd/f6/e3"), Some(true));
+        assert_eq!(is_light_rgb(""), None);
+        assert_eq!(is_light_colorfgbg("0;15"), Some(true));
+        assert_eq!(is_light_colorfgbg("15;default;0"), Some(false));
+
+        let light = resolve(&UiConfig { theme: ThemeMode::Light, syntax_theme: None });
+        assert_eq!((light.palette, light.syntax_theme.as_str()), (LIGHT, LIGHT_SYNTAX_THEME));
+        let custom = resolve(&UiConfig { theme: ThemeMode::Dark, syntax_theme: Some("Solarized (dark)".to_string()) });
+        assert_eq!((custom.palette, custom.syntax_theme.as_str()), (DARK, "Solarized (dark)"));
+    }
+}
@@ -0,0 +1,273 @@
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// `[output] max_width`; 0 means no cap beyond the terminal width.
+static MAX_WIDTH: AtomicUsize = AtomicUsize::new(0);
+
+pub fn set_max_width(max_width: Option<usize>) {
+    MAX_WIDTH.store(max_width.unwrap_or(0), Ordering::Relaxed);
+}
+
+// `columns` capped by the configured maximum.
+pub fn width_for(columns: usize) -> usize {
+    match MAX_WIDTH.load(Ordering::Relaxed) {
+        0 => columns,
+        cap => columns.min(cap),
+    }
+}
+
+// The width text is wrapped to: the terminal's, capped by `max_width`. Output that is not a
+// terminal is never wrapped, so piped text reaches scripts exactly as written.
+pub fn output_width() -> Option<usize> {
+    std::io::stdout()
+        .is_terminal()
+        .then(|| crossterm::terminal::size().ok())
+        .flatten()
+        .map(|(columns, _)| width_for(columns as usize))
+}
+
+// Prose `text` wrapped to the current output width, unchanged when there is none. Results
+// (diffs, code, commit messages, JSON) must not go through this.
+pub fn wrap_for_output(text: &str) -> String {
+    match output_width() {
+        Some(width) => wrap_text(text, width),
+        None => text.to_string(),
+    }
+}
+
+fn is_table_row(line: &str) -> bool {
+    line.trim_start().starts_with('|')
+}
+
+fn table_cells(row: &str) -> Vec<String> {
+    let row = row.trim();
+    let row = row.strip_prefix('|').unwrap_or(row);
+    let row = row.strip_suffix('|').unwrap_or(row);
+    row.split('|').map(|cell| cell.trim().to_string()).collect()
+}
+
+// The leading whitespace and list marker of `line`, as the indent of its continuation lines.
+fn hanging_indent(line: &str) -> (usize, usize) {
+    let indent = line.len() - line.trim_start().len();
+    let rest = &line[indent..];
+    let digits = rest.chars().take_while(char::is_ascii_digit).count();
+    let marker = if rest.starts_with("- ") || rest.starts_with("* ") || rest.starts_with("+ ") {
+        2
+    } else if digits > 0 && rest[digits..].starts_with(". ") {
+        digits + 2
+    } else {
+        0
+    };
+    (indent, indent + marker)
+}
+
+// Wraps one line of prose at word boundaries; words longer than a line are split.
+fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    if line.chars().count() <= width {
+        return vec![line.to_string()];
+    }
+    let (indent, continuation) = hanging_indent(line);
+    let mut lines = Vec::new();
+    let mut current = line[..indent].to_string();
+    let mut current_len = indent;
+    let mut line_indent = indent;
+    for word in line[indent..].split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        loop {
+            let space = usize::from(current_len > line_indent);
+            if current_len + space + word.len() <= width {
+                if space == 1 {
+                    current.push(' ');
+                }
+                current.extend(word.iter());
+                current_len += space + word.len();
+                break;
+            }
+            if current_len > line_indent {
+                lines.push(std::mem::replace(&mut current, " ".repeat(continuation)));
+                current_len = continuation;
+                line_indent = continuation;
+                continue;
+            }
+            // The word alone is wider than a line.
+            let fits = width.saturating_sub(current_len).max(1);
+            current.extend(word.drain(..fits.min(word.len())));
+            lines.push(std::mem::replace(&mut current, " ".repeat(continuation)));
+            current_len = continuation;
+            line_indent = continuation;
+            if word.is_empty() {
+                break;
+            }
+        }
+    }
+    if current_len > line_indent {
+        lines.push(current);
+    }
+    lines
+}
+
+// A markdown table too wide for `width` becomes one `header: cell` block per row; tables that
+// fit are kept as they are.
+fn wrap_table(rows: &[&str], width: usize) -> Vec<String> {
+    if rows.iter().all(|row| row.chars().count() <= width) {
+        return rows.iter().map(|row| row.to_string()).collect();
+    }
+    let headers = table_cells(rows[0]);
+    let is_separator = |row: &str| table_cells(row).iter().all(|c| c.chars().all(|ch| matches!(ch, '-' | ':')));
+    let mut lines = Vec::new();
+    for row in rows[1..].iter().filter(|row| !is_separator(row)) {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        for (i, cell) in table_cells(row).iter().enumerate() {
+            let header = headers.get(i).map(String::as_str).unwrap_or("");
+            let field = if header.is_empty() { cell.clone() } else { format!("{}: {}", header, cell) };
+            lines.extend(wrap_line(&format!("- {}", field), width));
+        }
+    }
+    lines
+}
+
+// Wraps markdown-ish text to `width` columns. Code blocks are left alone so they stay
+// copyable, and tables that don't fit are laid out row by row.
+pub fn wrap_text(text: &str, width: usize) -> String {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let mut wrapped = Vec::new();
+    let mut in_code = false;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            wrapped.push(line.to_string());
+        } else if in_code {
+            wrapped.push(line.to_string());
+        } else if is_table_row(line) {
+            let end = i + lines[i..].iter().take_while(|l| is_table_row(l)).count();
+            wrapped.extend(wrap_table(&lines[i..end], width));
+            i = end;
+            continue;
+        } else {
+            wrapped.extend(wrap_line(line, width));
+        }
+        i += 1;
+    }
+    wrapped.join("\n")
+}
+
+// Wraps text printed as it streams in. Each word is held back until it ends, so it can move
+// to the next line whole; code blocks and table rows pass through unchanged.
+#[derive(Debug, Default)]
+pub struct StreamWrapper {
+    width: Option<usize>,
+    line: String,
+    word: String,
+    column: usize,
+    indent: usize,
+    pending_space: bool,
+    passthrough: bool,
+    in_code: bool,
+}
+
+impl StreamWrapper {
+    pub fn new(width: Option<usize>) -> Self {
+        StreamWrapper { width, ..Default::default() }
+    }
+
+    fn flush_word(&mut self, out: &mut String) {
+        let Some(width) = self.width else { return };
+        if self.word.is_empty() {
+            return;
+        }
+        let len = self.word.chars().count();
+        let space = usize::from(self.pending_space);
+        if self.column > self.indent && self.column + space + len > width {
+            out.push('\n');
+            out.push_str(&" ".repeat(self.indent));
+            self.column = self.indent;
+        } else if self.pending_space {
+            out.push(' ');
+            self.column += 1;
+        }
+        out.push_str(&self.word);
+        self.column += len;
+        self.word.clear();
+        self.pending_space = false;
+    }
+
+    // The text to print for `chunk`.
+    pub fn push(&mut self, chunk: &str) -> String {
+        let Some(width) = self.width else {
+            return chunk.to_string();
+        };
+        let mut out = String::new();
+        for c in chunk.chars() {
+            if c == '\n' {
+                self.flush_word(&mut out);
+                out.push('\n');
+                if self.line.trim_start().starts_with("```") {
+                    self.in_code = !self.in_code;
+                }
+                self.line.clear();
+                (self.column, self.indent, self.pending_space, self.passthrough) = (0, 0, false, false);
+                continue;
+            }
+            let at_line_start = self.line.trim().is_empty();
+            self.line.push(c);
+            if self.passthrough || (at_line_start && (self.in_code || c == '|')) {
+                self.passthrough = true;
+                out.push(c);
+            } else if c.is_whitespace() {
+                if at_line_start {
+                    out.push(c);
+                    self.column += 1;
+                    self.indent = self.column;
+                } else {
+                    self.flush_word(&mut out);
+                    self.pending_space = self.column > self.indent;
+                }
+            } else {
+                self.word.push(c);
+                if self.word.chars().count() >= width.saturating_sub(self.indent).max(1) {
+                    self.flush_word(&mut out);
+                }
+            }
+        }
+        out
+    }
+
+    // Whatever is still held back once the stream ends.
+    pub fn finish(&mut self) -> String {
+        let mut out = String::new();
+        self.flush_word(&mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_text_keeps_words_code_and_fits_tables() {
+        let text = "- a list item that is long enough to wrap\n```\nlet code_is_never_wrapped = true;\n```\n| Name | Description |\n|------|-------------|\n| wrap | Word wrapping |";
+        assert_eq!(
+            wrap_text(text, 20),
+            "- a list item that\n  is long enough to\n  wrap\n```\nlet code_is_never_wrapped = true;\n```\n- Name: wrap\n- Description: Word\n  wrapping"
+        );
+        assert_eq!(wrap_text("abcdefghij", 4), "abcd\nefgh\nij");
+        assert_eq!(wrap_text("| a | b |\n|---|---|", 40), "| a | b |\n|---|---|");
+    }
+
+    #[test]
+    fn test_stream_wrapper_wraps_across_chunks() {
+        let mut wrapper = StreamWrapper::new(Some(12));
+        let mut out = String::new();
+        for chunk in ["The quick br", "own fox jumps", " over\n| a | b long row |\n  the dog"] {
+            out.push_str(&wrapper.push(chunk));
+        }
+        out.push_str(&wrapper.finish());
+        assert_eq!(out, "The quick\nbrown fox\njumps over\n| a | b long row |\n  the dog");
+        assert_eq!(StreamWrapper::new(None).push("unchanged text"), "unchanged text");
+    }
+}
@@ -0,0 +1,49 @@
+use opencode::api::models::{Message, Role};
+use opencode::config::{Config, EvictionStrategyKind};
+use opencode::context::ContextManager;
+
+const FACT: &str = "Remember: the database migration tool is sqlx-cli, pinned to 0.7.";
+
+fn message(role: Role, content: String) -> Message {
+    Message { role, content: Some(content), tool_calls: None, tool_call_id: None }
+}
+
+// Replays the same overflowing session under a strategy and reports whether the early fact the
+// final question depends on survived, and how many messages are sent with the question.
+fn run_session(strategy: EvictionStrategyKind) -> (bool, usize) {
+    let mut config = Config::default();
+    config.context.eviction = strategy;
+    config.context.preserve_turns = 2;
+    let mut manager = ContextManager::new(config).unwrap();
+
+    manager.add_message(message(Role::User, FACT.to_string())).unwrap();
+    manager.add_message(message(Role::Assistant, "Noted.".to_string())).unwrap();
+    for step in 0..12 {
+        manager.add_message(message(Role::User, format!("Run database migration step {}.", step))).unwrap();
+        let noise = format!("{} heartbeat ok latency {}ms\n", step, step * 7).repeat(80);
+        manager.add_message(message(Role::Tool, noise)).unwrap();
+    }
+    manager.add_message(message(Role::User, "Which database migration tool do we use?".to_string())).unwrap();
+
+    let messages = manager.construct_api_messages().unwrap();
+    let kept_fact = messages.iter().any(|m| m.content.as_deref() == Some(FACT));
+    (kept_fact, messages.len())
+}
+
+#[test]
+fn test_eviction_strategies_compared_on_long_session() {
+    let strategies = [
+        EvictionStrategyKind::OldestFirst,
+        EvictionStrategyKind::LargestFirst,
+        EvictionStrategyKind::Relevance,
+        EvictionStrategyKind::PreserveRecent,
+    ];
+    let results: Vec<(bool, usize)> = strategies.iter().map(|s| run_session(*s)).collect();
+
+    assert!(!results[0].0, "oldest-first drops the fact once the window fills");
+    for (strategy, (kept_fact, _)) in strategies.iter().zip(&results).skip(1) {
+        assert!(kept_fact, "{:?} should keep the fact: {:?}", strategy, results);
+    }
+    // Dropping bulky tool output instead of short turns leaves more of the conversation.
+    assert!(results[1].1 > results[0].1, "{:?}", results);
+}